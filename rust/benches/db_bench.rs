@@ -126,6 +126,7 @@ fn bench_task_crud(c: &mut Criterion) {
                         repository_id: None,
                         environment_id: None,
                         params: None,
+                        priority: None,
                     };
                     let _: Task = store.create_task(task).await.unwrap();
                 }
@@ -163,6 +164,7 @@ fn bench_task_crud(c: &mut Criterion) {
                         repository_id: None,
                         environment_id: None,
                         params: None,
+                        priority: None,
                     };
                     let _: Task = store.create_task(task).await.unwrap();
                 }
@@ -250,6 +250,30 @@ pub async fn change_password(
     Ok(())
 }
 
+/// Заголовок `Authorization` с JWT администратора — для тестов API
+#[cfg(test)]
+pub(crate) fn test_admin_bearer() -> String {
+    let store: Arc<dyn Store + Send + Sync> = Arc::new(crate::db::mock::MockStore::new());
+    let user = User {
+        id: 1,
+        created: chrono::Utc::now(),
+        username: "admin".to_string(),
+        name: "Admin".to_string(),
+        email: "admin@example.com".to_string(),
+        password: String::new(),
+        admin: true,
+        external: false,
+        alert: false,
+        pro: false,
+        totp: None,
+        email_otp: None,
+    };
+    let token = LocalAuthService::new(StoreWrapper::new(store))
+        .generate_token(&user)
+        .unwrap();
+    format!("Bearer {}", token.token)
+}
+
 // ============================================================================
 // Тесты
 // ============================================================================
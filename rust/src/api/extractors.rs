@@ -7,14 +7,25 @@
 
 use axum::{
     Json,
-    extract::FromRequestParts,
-    http::{StatusCode, request::Parts},
+    extract::{ConnectInfo, FromRequestParts},
+    http::{Method, StatusCode, request::Parts},
 };
+use chrono::Utc;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use crate::api::auth_local::LocalAuthService;
 use crate::api::middleware::ErrorResponse;
+use crate::api::middleware::project_permissions::token_route_allowed;
 use crate::api::state::AppState;
+use crate::api::store_wrapper::StoreWrapper;
+use crate::api::token_blacklist::TokenBlacklist;
+use crate::db::store::{TokenManager, UserManager};
+use crate::models::{APIToken, TokenScope};
+use crate::services::project_access::Caller;
+
+/// Как часто обновлять время последнего использования API-токена
+const TOKEN_TOUCH_INTERVAL_SECS: i64 = 60;
 
 /// Извлекает токен из заголовка Authorization (только Bearer)
 pub fn extract_token_from_header(auth_header: Option<&str>) -> Option<&str> {
@@ -38,6 +49,22 @@ fn extract_token_from_cookie(cookie_header: Option<&str>) -> Option<String> {
     None
 }
 
+/// IP клиента: первый адрес из X-Forwarded-For, иначе адрес соединения
+fn client_ip(parts: &Parts) -> Option<String> {
+    parts
+        .headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        })
+}
+
 /// Извлекает токен из запроса: сначала Authorization, затем Cookie (для Vue)
 pub fn extract_token_from_parts(parts: &Parts) -> Option<String> {
     let auth_header = parts
@@ -76,6 +103,125 @@ pub struct AuthUser {
     pub jti: String,
     /// Token expiry (Unix seconds) — for blacklist TTL
     pub exp: usize,
+    /// API-токен, если запрос аутентифицирован им, а не JWT
+    pub api_token: Option<Arc<APIToken>>,
+}
+
+impl AuthUser {
+    /// Субъект проверки прав в проекте
+    pub fn caller(&self) -> Caller {
+        Caller {
+            user_id: self.user_id,
+            admin: self.admin,
+            token: self.api_token.clone(),
+        }
+    }
+}
+
+/// Отказ в аутентификации: HTTP статус и тело ошибки
+pub type AuthRejection = (StatusCode, Json<ErrorResponse>);
+
+fn auth_failed() -> AuthRejection {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorResponse::new("Неверный токен".to_string()).with_code("AUTH_FAILED")),
+    )
+}
+
+/// Аутентификация персональным API-токеном или токеном сервисного аккаунта
+///
+/// Права администратора сохраняются только у токена с областью `admin`.
+async fn authenticate_api_token(
+    store: &StoreWrapper,
+    value: &str,
+    ip: Option<String>,
+) -> Result<AuthUser, AuthRejection> {
+    let token = store
+        .get_api_token_by_value(value)
+        .await
+        .map_err(|_| auth_failed())?;
+    let now = Utc::now();
+    if !token.is_active(now) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Срок действия токена истёк").with_code("TOKEN_EXPIRED")),
+        ));
+    }
+    let user = store
+        .get_user(token.user_id)
+        .await
+        .map_err(|_| auth_failed())?;
+
+    let stale = token
+        .last_used_at
+        .is_none_or(|t| (now - t).num_seconds() >= TOKEN_TOUCH_INTERVAL_SECS);
+    if stale || token.last_used_ip != ip {
+        if let Err(e) = store.touch_api_token(token.id, ip.as_deref()).await {
+            tracing::warn!("Failed to record API token {} usage: {}", token.id, e);
+        }
+    }
+
+    Ok(AuthUser {
+        user_id: user.id,
+        username: user.username,
+        email: user.email,
+        admin: user.admin && token.has_scope(TokenScope::Admin),
+        jti: format!("api-token:{}", token.id),
+        exp: token
+            .expires_at
+            .map_or(0, |t| t.timestamp().max(0) as usize),
+        api_token: Some(Arc::new(token)),
+    })
+}
+
+/// Аутентифицирует токен: JWT сессии (если не отозван при выходе), иначе
+/// API-токен. Используется HTTP API и gRPC.
+pub async fn authenticate_token(
+    store: &StoreWrapper,
+    blacklist: &TokenBlacklist,
+    token: &str,
+    ip: Option<String>,
+) -> Result<AuthUser, AuthRejection> {
+    let claims = match LocalAuthService::new(store.clone()).verify_token(token) {
+        Ok(claims) => claims,
+        Err(_) => return authenticate_api_token(store, token, ip).await,
+    };
+
+    // Check blacklist — token may have been revoked on logout
+    if blacklist.is_revoked(&claims.jti) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Токен отозван").with_code("TOKEN_REVOKED")),
+        ));
+    }
+
+    Ok(AuthUser {
+        user_id: claims.sub,
+        username: claims.username,
+        email: claims.email,
+        admin: claims.admin,
+        jti: claims.jti,
+        exp: claims.exp,
+        api_token: None,
+    })
+}
+
+/// Проверяет, что области API-токена допускают операцию `method path`
+/// (для JWT ограничений нет)
+pub fn check_token_route(
+    user: &AuthUser,
+    method: &Method,
+    path: &str,
+) -> Result<(), AuthRejection> {
+    let Some(token) = &user.api_token else {
+        return Ok(());
+    };
+    token_route_allowed(token, method, path).map_err(|message| {
+        (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new(message).with_code("TOKEN_SCOPE")),
+        )
+    })
 }
 
 impl FromRequestParts<Arc<AppState>> for AuthUser {
@@ -85,39 +231,28 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
         parts: &mut Parts,
         state: &Arc<AppState>,
     ) -> Result<Self, Self::Rejection> {
+        // Уже аутентифицирован middleware выше по цепочке
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(user.clone());
+        }
+
         // Токен из Authorization или Cookie (Vue upstream использует cookie)
         let token = extract_token_from_parts(parts).ok_or((
             StatusCode::UNAUTHORIZED,
             Json(ErrorResponse::new("Требуется аутентификация").with_code("AUTH_REQUIRED")),
         ))?;
 
-        // Получаем LocalAuthService из состояния
-        let auth_service = LocalAuthService::new(state.store.clone());
-
-        // Проверяем токен
-        let claims = auth_service.verify_token(&token).map_err(|_| {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse::new("Неверный токен".to_string()).with_code("AUTH_FAILED")),
-            )
-        })?;
-
-        // Check blacklist — token may have been revoked on logout
-        if state.token_blacklist.is_revoked(&claims.jti) {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse::new("Токен отозван").with_code("TOKEN_REVOKED")),
-            ));
-        }
-
-        Ok(AuthUser {
-            user_id: claims.sub,
-            username: claims.username,
-            email: claims.email,
-            admin: claims.admin,
-            jti: claims.jti,
-            exp: claims.exp,
-        })
+        // Проверяем токен: JWT сессии, иначе API-токен
+        let user = authenticate_token(
+            &state.store,
+            &state.token_blacklist,
+            &token,
+            client_ip(parts),
+        )
+        .await?;
+        check_token_route(&user, &parts.method, parts.uri.path())?;
+        parts.extensions.insert(user.clone());
+        Ok(user)
     }
 }
 
@@ -232,6 +367,7 @@ mod tests {
             admin: true,
             jti: "test-jti".to_string(),
             exp: 9999999999,
+            api_token: None,
         };
 
         assert_eq!(user.user_id, 1);
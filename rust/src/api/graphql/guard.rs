@@ -0,0 +1,76 @@
+//! Проверка прав в GraphQL resolvers
+//!
+//! Обработчики `/graphql` и `/graphql/ws` кладут [`AuthUser`] в данные
+//! запроса, resolvers проверяют доступ к проекту по тем же ролям, что и REST.
+//! Отказ возвращается GraphQL-ошибкой с расширениями `code` и `permission`.
+
+use std::sync::Arc;
+
+use async_graphql::{Context, Error, ErrorExtensions, Result};
+
+use crate::api::extractors::AuthUser;
+use crate::api::state::AppState;
+use crate::models::Permission;
+use crate::services::project_access::{self, AccessDenied, Caller};
+
+/// AppState схемы
+pub fn state<'a>(ctx: &Context<'a>) -> Result<&'a Arc<AppState>> {
+    ctx.data::<Arc<AppState>>()
+}
+
+/// Аутентифицированный пользователь запроса
+pub fn caller(ctx: &Context<'_>) -> Result<Caller> {
+    let user = ctx.data::<AuthUser>().map_err(|_| {
+        Error::new("Authentication required").extend_with(|_, e| e.set("code", "AUTH_REQUIRED"))
+    })?;
+    Ok(user.caller())
+}
+
+fn denied(denied: AccessDenied) -> Error {
+    Error::new(denied.to_string()).extend_with(|_, e| {
+        e.set("code", "PERMISSION_DENIED");
+        if let Some(permission) = denied.permission() {
+            e.set("permission", permission.as_str());
+        }
+    })
+}
+
+/// Требует глобального администратора
+pub fn require_admin(ctx: &Context<'_>) -> Result<()> {
+    if caller(ctx)?.admin {
+        Ok(())
+    } else {
+        Err(Error::new("Administrator privileges required")
+            .extend_with(|_, e| e.set("code", "PERMISSION_DENIED")))
+    }
+}
+
+/// Требует права на изменения вне проектов (API-токену — области
+/// `manage_resources` или `admin`)
+pub fn require_global_write(ctx: &Context<'_>) -> Result<()> {
+    if caller(ctx)?.allows_global_writes() {
+        Ok(())
+    } else {
+        Err(Error::new("API token scope does not allow this operation")
+            .extend_with(|_, e| e.set("code", "PERMISSION_DENIED")))
+    }
+}
+
+/// Требует разрешения в проекте; без `required` достаточно быть участником
+pub async fn require(
+    ctx: &Context<'_>,
+    project_id: i32,
+    template_id: Option<i32>,
+    required: Option<Permission>,
+) -> Result<()> {
+    let caller = caller(ctx)?;
+    project_access::check_permission(
+        state(ctx)?.store.store(),
+        &caller,
+        project_id,
+        template_id,
+        required,
+    )
+    .await?
+    .map_err(denied)
+}
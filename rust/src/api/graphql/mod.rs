@@ -6,7 +6,11 @@
 //! - `GET  /graphql`    — GraphiQL playground
 //! - `POST /graphql`    — HTTP запросы (query / mutation)
 //! - `GET  /graphql/ws` — WebSocket подписки (Subscription)
+//!
+//! Запросы и подписки требуют аутентификации; права в проекте проверяются
+//! в resolvers (см. [`guard`]).
 
+pub mod guard;
 pub mod mutation;
 pub mod query;
 pub mod schema;
@@ -18,12 +22,13 @@ use std::sync::Arc;
 use async_graphql::http::{ALL_WEBSOCKET_PROTOCOLS, GraphiQLSource};
 use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
 use axum::{
-    Router,
-    extract::{State, WebSocketUpgrade},
+    Extension, Router,
+    extract::WebSocketUpgrade,
     response::{Html, IntoResponse},
     routing::get,
 };
 
+use crate::api::extractors::AuthUser;
 use crate::api::state::AppState;
 
 /// Создаёт маршруты GraphQL, получая AppState для инъекции в схему.
@@ -33,27 +38,32 @@ pub fn graphql_routes(app_state: Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route("/graphql", get(graphql_playground).post(graphql_handler))
         .route("/graphql/ws", get(graphql_ws_handler))
-        .with_state(schema)
+        .layer(Extension(schema))
 }
 
 /// Обработчик HTTP GraphQL запросов (query + mutation)
 pub async fn graphql_handler(
-    State(schema): State<schema::Schema>,
+    Extension(schema): Extension<schema::Schema>,
+    user: AuthUser,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    schema.execute(req.into_inner().data(user)).await.into()
 }
 
 /// WebSocket обработчик для GraphQL Subscription
 pub async fn graphql_ws_handler(
-    State(schema): State<schema::Schema>,
+    Extension(schema): Extension<schema::Schema>,
+    user: AuthUser,
     protocol: GraphQLProtocol,
     upgrade: WebSocketUpgrade,
 ) -> impl IntoResponse {
     upgrade
         .protocols(ALL_WEBSOCKET_PROTOCOLS)
         .on_upgrade(move |stream| async move {
+            let mut data = async_graphql::Data::default();
+            data.insert(user);
             GraphQLWebSocket::new(stream, schema, protocol)
+                .with_data(data)
                 .serve()
                 .await;
         })
@@ -1,13 +1,16 @@
 //! GraphQL Mutation корень — CRUD операции
 
-use crate::api::state::AppState;
 use crate::db::store::{ProjectStore, TaskManager, TemplateManager, UserManager};
 use crate::models::template::{TemplateApp, TemplateType};
-use crate::models::{Project as DbProject, Task as DbTask, Template as DbTemplate, User as DbUser};
+use crate::models::{
+    Permission, Project as DbProject, ProjectUser, ProjectUserRole, Task as DbTask,
+    Template as DbTemplate, User as DbUser,
+};
 use crate::services::task_logger::TaskStatus;
 use async_graphql::{Context, InputObject, Object, Result};
 use chrono::Utc;
 
+use super::guard;
 use super::types::{Project, Task, Template, User};
 
 /// Input для создания пользователя
@@ -57,7 +60,8 @@ pub struct MutationRoot;
 impl MutationRoot {
     /// Создать пользователя
     async fn create_user(&self, ctx: &Context<'_>, input: CreateUserInput) -> Result<User> {
-        let state = ctx.data::<AppState>()?;
+        guard::require_admin(ctx)?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         let admin = input.admin.unwrap_or(false);
@@ -94,7 +98,9 @@ impl MutationRoot {
         ctx: &Context<'_>,
         input: CreateProjectInput,
     ) -> Result<Project> {
-        let state = ctx.data::<AppState>()?;
+        guard::require_global_write(ctx)?;
+        let caller = guard::caller(ctx)?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         let new_project = DbProject {
@@ -109,6 +115,13 @@ impl MutationRoot {
         };
 
         let created = store.create_project(new_project).await?;
+        store
+            .create_project_user(ProjectUser::new(
+                created.id,
+                caller.user_id,
+                ProjectUserRole::Owner,
+            ))
+            .await?;
 
         Ok(Project {
             id: created.id,
@@ -122,7 +135,14 @@ impl MutationRoot {
         ctx: &Context<'_>,
         input: CreateTemplateInput,
     ) -> Result<Template> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(
+            ctx,
+            input.project_id,
+            None,
+            Some(Permission::UpdateResources),
+        )
+        .await?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         let new_template = DbTemplate {
@@ -158,6 +178,9 @@ impl MutationRoot {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
 
         let created = store.create_template(new_template).await?;
@@ -172,7 +195,14 @@ impl MutationRoot {
 
     /// Запустить задачу
     async fn create_task(&self, ctx: &Context<'_>, input: CreateTaskInput) -> Result<Task> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(
+            ctx,
+            input.project_id,
+            Some(input.template_id),
+            Some(Permission::RunTasks),
+        )
+        .await?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         let new_task = DbTask {
@@ -200,6 +230,7 @@ impl MutationRoot {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         let created = store.create_task(new_task).await?;
@@ -222,7 +253,8 @@ impl MutationRoot {
         name: String,
         playbook: String,
     ) -> Result<Template> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, Some(id), Some(Permission::UpdateResources)).await?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         let template = store
@@ -248,7 +280,7 @@ impl MutationRoot {
 
     /// Удалить шаблон
     async fn delete_template(&self, ctx: &Context<'_>, id: i32) -> Result<bool> {
-        let state = ctx.data::<AppState>()?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         // Получаем project_id из шаблона
@@ -259,13 +291,14 @@ impl MutationRoot {
             .ok_or_else(|| async_graphql::Error::new("Template not found"))?;
         let project_id = template.project_id;
 
+        guard::require(ctx, project_id, Some(id), Some(Permission::UpdateResources)).await?;
         store.delete_template(project_id, id).await?;
         Ok(true)
     }
 
     /// Удалить задачу
     async fn delete_task(&self, ctx: &Context<'_>, id: i32) -> Result<bool> {
-        let state = ctx.data::<AppState>()?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         // Получаем project_id из задачи
@@ -276,6 +309,7 @@ impl MutationRoot {
             .ok_or_else(|| async_graphql::Error::new("Task not found"))?;
         let project_id = task.task.project_id;
 
+        guard::require(ctx, project_id, None, Some(Permission::UpdateResources)).await?;
         store.delete_task(project_id, id).await?;
         Ok(true)
     }
@@ -283,7 +317,7 @@ impl MutationRoot {
     /// Остановить задачу (перевести в статус stopped)
     async fn stop_task(&self, ctx: &Context<'_>, project_id: i32, task_id: i32) -> Result<bool> {
         use crate::db::store::TaskManager;
-        let state = ctx.data::<AppState>()?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         let mut task = store
@@ -291,6 +325,13 @@ impl MutationRoot {
             .await
             .map_err(|_| async_graphql::Error::new("Task not found"))?;
 
+        guard::require(
+            ctx,
+            project_id,
+            Some(task.template_id),
+            Some(Permission::RunTasks),
+        )
+        .await?;
         if matches!(
             task.status,
             crate::services::task_logger::TaskStatus::Running
@@ -316,7 +357,14 @@ impl MutationRoot {
         debug: Option<bool>,
         dry_run: Option<bool>,
     ) -> Result<Task> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(
+            ctx,
+            project_id,
+            Some(template_id),
+            Some(Permission::RunTasks),
+        )
+        .await?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
         let new_task = DbTask {
@@ -345,6 +393,7 @@ impl MutationRoot {
             environment_id: None,
             params: debug
                 .map(|d| serde_json::json!({"debug": d, "dry_run": dry_run.unwrap_or(false)})),
+            priority: None,
         };
 
         let created = store
@@ -370,14 +419,21 @@ impl MutationRoot {
     async fn approve_plan(
         &self,
         ctx: &Context<'_>,
+        project_id: i32,
         plan_id: i32,
         reviewed_by: i32,
         comment: Option<String>,
     ) -> Result<bool> {
         use crate::db::store::PlanApprovalManager;
-        let state = ctx.data::<AppState>()?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
+        guard::require(ctx, project_id, None, Some(Permission::RunTasks)).await?;
+        let pending = store.list_pending_plans(project_id).await?;
+        if !pending.iter().any(|p| p.id == plan_id as i64) {
+            return Err(async_graphql::Error::new("Plan not found"));
+        }
+
         store
             .approve_plan(plan_id as i64, reviewed_by, comment)
             .await
@@ -390,14 +446,21 @@ impl MutationRoot {
     async fn reject_plan(
         &self,
         ctx: &Context<'_>,
+        project_id: i32,
         plan_id: i32,
         reviewed_by: i32,
         reason: Option<String>,
     ) -> Result<bool> {
         use crate::db::store::PlanApprovalManager;
-        let state = ctx.data::<AppState>()?;
+        let state = guard::state(ctx)?;
         let store = &state.store;
 
+        guard::require(ctx, project_id, None, Some(Permission::RunTasks)).await?;
+        let pending = store.list_pending_plans(project_id).await?;
+        if !pending.iter().any(|p| p.id == plan_id as i64) {
+            return Err(async_graphql::Error::new("Plan not found"));
+        }
+
         store
             .reject_plan(plan_id as i64, reviewed_by, reason)
             .await
@@ -1,12 +1,13 @@
 //! GraphQL Query корень — полный набор запросов
 
-use crate::api::state::AppState;
 use crate::db::store::{
     EnvironmentManager, EventManager, InventoryManager, ProjectStore, RepositoryManager,
     RunnerManager, ScheduleManager, TaskManager, TemplateManager, UserManager,
 };
+use crate::models::Permission;
 use async_graphql::{Context, Object, Result};
 
+use super::guard;
 use super::types::{
     AuditEvent, Environment, Inventory, KubernetesClusterInfo, KubernetesNamespace, KubernetesNode,
     Project, Repository, Runner, Schedule, Task, Template, User,
@@ -26,7 +27,8 @@ impl QueryRoot {
 
     /// Получить всех пользователей
     async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require_admin(ctx)?;
+        let state = guard::state(ctx)?;
         let users = state.store.get_users(Default::default()).await?;
         Ok(users
             .into_iter()
@@ -42,7 +44,10 @@ impl QueryRoot {
 
     /// Получить пользователя по ID
     async fn user(&self, ctx: &Context<'_>, id: i32) -> Result<User> {
-        let state = ctx.data::<AppState>()?;
+        if guard::caller(ctx)?.user_id != id {
+            guard::require_admin(ctx)?;
+        }
+        let state = guard::state(ctx)?;
         let u = state.store.get_user(id).await?;
         Ok(User {
             id: u.id,
@@ -53,12 +58,17 @@ impl QueryRoot {
         })
     }
 
-    /// Получить все проекты
+    /// Получить проекты пользователя (администратору — все)
     async fn projects(&self, ctx: &Context<'_>) -> Result<Vec<Project>> {
-        let state = ctx.data::<AppState>()?;
-        let projects = state.store.get_projects(None).await?;
+        let caller = guard::caller(ctx)?;
+        let state = guard::state(ctx)?;
+        let projects = state
+            .store
+            .get_projects((!caller.admin).then_some(caller.user_id))
+            .await?;
         Ok(projects
             .into_iter()
+            .filter(|p| caller.allows_project(p.id))
             .map(|p| Project {
                 id: p.id,
                 name: p.name,
@@ -68,7 +78,8 @@ impl QueryRoot {
 
     /// Получить проект по ID
     async fn project(&self, ctx: &Context<'_>, id: i32) -> Result<Project> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, id, None, None).await?;
+        let state = guard::state(ctx)?;
         let p = state.store.get_project(id).await?;
         Ok(Project {
             id: p.id,
@@ -78,7 +89,8 @@ impl QueryRoot {
 
     /// Получить шаблоны проекта
     async fn templates(&self, ctx: &Context<'_>, project_id: i32) -> Result<Vec<Template>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, None, None).await?;
+        let state = guard::state(ctx)?;
         let templates = state.store.get_templates(project_id).await?;
         Ok(templates
             .into_iter()
@@ -93,7 +105,8 @@ impl QueryRoot {
 
     /// Получить задачи проекта
     async fn tasks(&self, ctx: &Context<'_>, project_id: i32) -> Result<Vec<Task>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, None, None).await?;
+        let state = guard::state(ctx)?;
         let tasks = state.store.get_tasks(project_id, None).await?;
         Ok(tasks
             .into_iter()
@@ -109,7 +122,8 @@ impl QueryRoot {
 
     /// Получить одну задачу по ID
     async fn task(&self, ctx: &Context<'_>, project_id: i32, task_id: i32) -> Result<Task> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, None, None).await?;
+        let state = guard::state(ctx)?;
         let t = state.store.get_task(project_id, task_id).await?;
         Ok(Task {
             id: t.id,
@@ -124,7 +138,8 @@ impl QueryRoot {
 
     /// Получить инвентари проекта
     async fn inventories(&self, ctx: &Context<'_>, project_id: i32) -> Result<Vec<Inventory>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, None, None).await?;
+        let state = guard::state(ctx)?;
         let items = state.store.get_inventories(project_id).await?;
         Ok(items
             .into_iter()
@@ -139,7 +154,8 @@ impl QueryRoot {
 
     /// Получить репозитории проекта
     async fn repositories(&self, ctx: &Context<'_>, project_id: i32) -> Result<Vec<Repository>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, None, None).await?;
+        let state = guard::state(ctx)?;
         let items = state.store.get_repositories(project_id).await?;
         Ok(items
             .into_iter()
@@ -155,7 +171,8 @@ impl QueryRoot {
 
     /// Получить окружения проекта
     async fn environments(&self, ctx: &Context<'_>, project_id: i32) -> Result<Vec<Environment>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, None, None).await?;
+        let state = guard::state(ctx)?;
         let items = state.store.get_environments(project_id).await?;
         Ok(items
             .into_iter()
@@ -169,7 +186,8 @@ impl QueryRoot {
 
     /// Получить расписания проекта
     async fn schedules(&self, ctx: &Context<'_>, project_id: i32) -> Result<Vec<Schedule>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require(ctx, project_id, None, None).await?;
+        let state = guard::state(ctx)?;
         let items = state.store.get_schedules(project_id).await?;
         Ok(items
             .into_iter()
@@ -186,7 +204,11 @@ impl QueryRoot {
 
     /// Получить раннеры (опционально — для конкретного проекта)
     async fn runners(&self, ctx: &Context<'_>, project_id: Option<i32>) -> Result<Vec<Runner>> {
-        let state = ctx.data::<AppState>()?;
+        match project_id {
+            Some(project_id) => guard::require(ctx, project_id, None, None).await?,
+            None => guard::require_admin(ctx)?,
+        }
+        let state = guard::state(ctx)?;
         let items = state.store.get_runners(project_id).await?;
         Ok(items
             .into_iter()
@@ -208,7 +230,13 @@ impl QueryRoot {
         project_id: Option<i32>,
         limit: Option<usize>,
     ) -> Result<Vec<AuditEvent>> {
-        let state = ctx.data::<AppState>()?;
+        match project_id {
+            Some(project_id) => {
+                guard::require(ctx, project_id, None, Some(Permission::ViewAuditLog)).await?
+            }
+            None => guard::require_admin(ctx)?,
+        }
+        let state = guard::state(ctx)?;
         let limit = limit.unwrap_or(100).min(1000);
         let items = state.store.get_events(project_id, limit).await?;
         Ok(items
@@ -230,7 +258,8 @@ impl QueryRoot {
     ///
     /// Возвращает пустой список если Kubernetes не сконфигурирован.
     async fn kubernetes_namespaces(&self, ctx: &Context<'_>) -> Result<Vec<KubernetesNamespace>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require_admin(ctx)?;
+        let state = guard::state(ctx)?;
 
         let client = match state.kubernetes_client() {
             Ok(c) => c,
@@ -267,7 +296,8 @@ impl QueryRoot {
     ///
     /// Возвращает пустой список если Kubernetes не сконфигурирован.
     async fn kubernetes_nodes(&self, ctx: &Context<'_>) -> Result<Vec<KubernetesNode>> {
-        let state = ctx.data::<AppState>()?;
+        guard::require_admin(ctx)?;
+        let state = guard::state(ctx)?;
 
         let client = match state.kubernetes_client() {
             Ok(c) => c,
@@ -313,7 +343,8 @@ impl QueryRoot {
     ///
     /// Возвращает ошибку если Kubernetes не сконфигурирован.
     async fn kubernetes_cluster_info(&self, ctx: &Context<'_>) -> Result<KubernetesClusterInfo> {
-        let state = ctx.data::<AppState>()?;
+        guard::require_admin(ctx)?;
+        let state = guard::state(ctx)?;
 
         let client = state
             .kubernetes_client()
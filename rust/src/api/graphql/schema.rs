@@ -15,7 +15,7 @@ pub type Schema = AsyncSchema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 /// Создаёт GraphQL схему с инъецированным AppState.
 ///
-/// AppState доступен в resolvers через `ctx.data::<AppState>()`.
+/// AppState доступен в resolvers через `ctx.data::<Arc<AppState>>()`.
 pub fn create_schema(state: Arc<AppState>) -> Schema {
     AsyncSchema::build(QueryRoot, MutationRoot, SubscriptionRoot)
         .data(state)
@@ -1,9 +1,12 @@
 //! GraphQL Subscription корень — real-time события задач
 //!
 //! ## Подписки
-//! - `taskCreated` — новая задача создана в любом проекте
+//! - `taskCreated(projectId?)` — новая задача создана (фильтр по project_id опционален)
 //! - `taskStatus(projectId?)` — изменение статуса задачи (фильтр по project_id опционален)
-//! - `taskOutput(taskId)` — строки лога выполняющейся задачи
+//! - `taskOutput(taskId, projectId?)` — строки лога выполняющейся задачи
+//!
+//! Без `projectId` подписки доступны только администратору, с ним — участникам
+//! проекта.
 //!
 //! ## Публикация из других модулей
 //! ```rust,ignore
@@ -15,10 +18,13 @@
 
 use async_graphql::{Context, Result, Subscription};
 use async_stream::stream;
-use futures_util::Stream;
+use futures_util::{Stream, StreamExt};
 use once_cell::sync::Lazy;
 use tokio::sync::broadcast;
 
+use crate::db::store::TaskManager;
+
+use super::guard;
 use super::types::{Task, TaskOutputLine, TaskStatusEvent};
 
 // ── Broadcast channels ────────────────────────────────────────────────────────
@@ -57,16 +63,25 @@ pub struct SubscriptionRoot;
 
 #[Subscription]
 impl SubscriptionRoot {
-    /// Подписка на создание задач в любом проекте.
+    /// Подписка на создание задач.
+    ///
+    /// Если `project_id` передан — фильтрует только задачи данного проекта.
     ///
     /// ```graphql
     /// subscription {
-    ///   taskCreated { id projectId status }
+    ///   taskCreated(projectId: 1) { id projectId status }
     /// }
     /// ```
-    async fn task_created(&self, _ctx: &Context<'_>) -> Result<impl Stream<Item = Task>> {
+    async fn task_created(
+        &self,
+        ctx: &Context<'_>,
+        project_id: Option<i32>,
+    ) -> Result<impl Stream<Item = Task>> {
+        require_scope(ctx, project_id).await?;
         let rx = TASK_CREATED_TX.subscribe();
-        Ok(recv_broadcast(rx))
+        Ok(recv_broadcast(rx).filter(move |task| {
+            std::future::ready(project_id.is_none() || project_id == Some(task.project_id))
+        }))
     }
 
     /// Подписка на изменения статуса задач.
@@ -80,9 +95,10 @@ impl SubscriptionRoot {
     /// ```
     async fn task_status(
         &self,
-        _ctx: &Context<'_>,
+        ctx: &Context<'_>,
         project_id: Option<i32>,
     ) -> Result<impl Stream<Item = TaskStatusEvent>> {
+        require_scope(ctx, project_id).await?;
         let rx = TASK_STATUS_TX.subscribe();
         Ok(stream! {
             let mut rx = rx;
@@ -107,14 +123,23 @@ impl SubscriptionRoot {
     ///
     /// ```graphql
     /// subscription {
-    ///   taskOutput(taskId: 42) { taskId line timestamp level }
+    ///   taskOutput(taskId: 42, projectId: 1) { taskId line timestamp level }
     /// }
     /// ```
     async fn task_output(
         &self,
-        _ctx: &Context<'_>,
+        ctx: &Context<'_>,
         task_id: i32,
+        project_id: Option<i32>,
     ) -> Result<impl Stream<Item = TaskOutputLine>> {
+        require_scope(ctx, project_id).await?;
+        if let Some(project_id) = project_id {
+            // Задача должна принадлежать проекту, к которому есть доступ
+            guard::state(ctx)?
+                .store
+                .get_task(project_id, task_id)
+                .await?;
+        }
         let rx = TASK_OUTPUT_TX.subscribe();
         Ok(stream! {
             let mut rx = rx;
@@ -135,6 +160,14 @@ impl SubscriptionRoot {
 
 // ── Helper ────────────────────────────────────────────────────────────────────
 
+/// Подписка на проект — участникам, на все проекты — администратору
+async fn require_scope(ctx: &Context<'_>, project_id: Option<i32>) -> Result<()> {
+    match project_id {
+        Some(project_id) => guard::require(ctx, project_id, None, None).await,
+        None => guard::require_admin(ctx),
+    }
+}
+
 /// Превращает broadcast receiver в Stream, пропуская lagged ошибки
 fn recv_broadcast<T: Clone + Send + 'static>(
     mut rx: broadcast::Receiver<T>,
@@ -2,7 +2,7 @@
 //!
 //! Обработчики HTTP запросов для управления audit log
 
-use crate::api::extractors::AdminUser;
+use crate::api::extractors::{AdminUser, AuthUser};
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
 use crate::db::store::{AuditLogManager, ProjectStore};
@@ -168,10 +168,12 @@ pub async fn get_audit_log(
 }
 
 /// GET /api/project/:id/audit-log - Получение audit log проекта
+///
+/// Право `view_audit_log` проверяет middleware прав проекта.
 #[axum::debug_handler]
 pub async fn get_project_audit_logs(
     State(state): State<Arc<AppState>>,
-    _admin: AdminUser,
+    _user: AuthUser,
     Path(project_id): Path<i64>,
     Query(params): Query<AuditLogQueryParams>,
 ) -> std::result::Result<Json<Vec<AuditLog>>, (StatusCode, Json<ErrorResponse>)> {
@@ -104,6 +104,9 @@ pub async fn health_full(State(state): State<Arc<AppState>>) -> impl IntoRespons
     let running_tasks: usize = state.store.get_running_tasks_count().await.unwrap_or(0);
     let waiting_tasks: usize = state.store.get_waiting_tasks_count().await.unwrap_or(0);
 
+    // Лидер кластера, на котором работают планировщик, автобэкап и очистка задач
+    let leader = crate::services::leader_election::status().await;
+
     let all_healthy = db_ready;
 
     let status = if all_healthy {
@@ -119,6 +122,7 @@ pub async fn health_full(State(state): State<Arc<AppState>>) -> impl IntoRespons
             "version": env!("CARGO_PKG_VERSION"),
             "ha_mode": state.config.ha.enable,
             "node_id": state.config.ha.node_id,
+            "leader": leader,
             "checks": {
                 "database": {
                     "ready": db_ready,
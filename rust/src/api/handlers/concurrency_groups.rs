@@ -0,0 +1,96 @@
+//! Concurrency Group Handlers
+//!
+//! Именованные группы параллелизма проекта: шаблоны и окружения деплоя
+//! входят в группу по имени, здесь настраивается лимит группы.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::api::extractors::AuthUser;
+use crate::api::state::AppState;
+use crate::db::store::ConcurrencyGroupManager;
+use crate::models::ConcurrencyGroupUpsert;
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn internal_error(e: crate::error::Error) -> ApiError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"error": e.to_string()})),
+    )
+}
+
+/// Payload для изменения лимита группы
+#[derive(Debug, Deserialize)]
+pub struct ConcurrencyGroupLimit {
+    pub max_parallel: i32,
+}
+
+/// GET /api/project/{project_id}/concurrency-groups
+pub async fn list_concurrency_groups(
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i32>,
+    _auth: AuthUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let groups = state
+        .store
+        .get_concurrency_groups(project_id)
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(json!(groups)))
+}
+
+/// PUT /api/project/{project_id}/concurrency-groups/{name}
+pub async fn put_concurrency_group(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, name)): Path<(i32, String)>,
+    _auth: AuthUser,
+    Json(payload): Json<ConcurrencyGroupLimit>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Group name is required"})),
+        ));
+    }
+    if payload.max_parallel < 1 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "max_parallel must be at least 1"})),
+        ));
+    }
+    let group = state
+        .store
+        .upsert_concurrency_group(
+            project_id,
+            ConcurrencyGroupUpsert {
+                name,
+                max_parallel: payload.max_parallel,
+            },
+        )
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(json!(group)))
+}
+
+/// DELETE /api/project/{project_id}/concurrency-groups/{name}
+///
+/// Шаблоны и окружения остаются в группе; без настройки она работает как мьютекс.
+pub async fn delete_concurrency_group(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, name)): Path<(i32, String)>,
+    _auth: AuthUser,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .delete_concurrency_group(project_id, &name)
+        .await
+        .map_err(internal_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -142,6 +142,7 @@ mod tests {
             url: Some("https://prod.example.com".to_string()),
             tier: "production".to_string(),
             template_id: Some(5),
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&create).unwrap();
         assert!(json.contains("\"name\":\"production\""));
@@ -155,6 +156,7 @@ mod tests {
             url: None,
             tier: String::new(),
             template_id: None,
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&create).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
@@ -180,6 +182,7 @@ mod tests {
             tier: None,
             status: None,
             template_id: None,
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&update).unwrap();
         assert!(json.contains("\"name\":\"renamed-env\""));
@@ -195,6 +198,7 @@ mod tests {
             tier: Some("staging".to_string()),
             status: Some("active".to_string()),
             template_id: Some(3),
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&update).unwrap();
         let parsed: DeploymentEnvironmentUpdate = serde_json::from_str(&json).unwrap();
@@ -249,6 +253,7 @@ mod tests {
             last_deployed_by: Some(5),
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&env).unwrap();
         assert!(json.contains("\"name\":\"prod\""));
@@ -295,6 +300,7 @@ mod tests {
             url: Some("https://clone.example.com".to_string()),
             tier: "review".to_string(),
             template_id: Some(7),
+            concurrency_group: None,
         };
         let cloned = create.clone();
         assert_eq!(cloned.name, create.name);
@@ -309,6 +315,7 @@ mod tests {
             tier: None,
             status: Some("stopped".to_string()),
             template_id: None,
+            concurrency_group: None,
         };
         let cloned = update.clone();
         assert_eq!(cloned.name, update.name);
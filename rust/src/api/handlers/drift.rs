@@ -3,7 +3,10 @@
 use crate::api::extractors::AuthUser;
 use crate::api::state::AppState;
 use crate::db::store::DriftManager;
+use crate::error::Error;
 use crate::models::drift::{DriftConfigCreate, DriftConfigWithStatus};
+use crate::services::drift;
+use crate::services::scheduler::SchedulePool;
 use axum::{
     Json,
     extract::{Path, State},
@@ -14,6 +17,17 @@ use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
+/// Validates a drift cron schedule; an empty string clears it (manual only)
+fn normalize_schedule(schedule: Option<String>) -> Result<Option<String>, String> {
+    match schedule.map(|s| s.trim().to_string()) {
+        Some(s) if s.is_empty() => Ok(None),
+        Some(s) => SchedulePool::validate_cron_for_storage(&s)
+            .map(|_| Some(s))
+            .map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
 pub async fn list_drift_configs(
     State(state): State<Arc<AppState>>,
     _auth: AuthUser,
@@ -44,9 +58,13 @@ pub async fn create_drift_config(
     State(state): State<Arc<AppState>>,
     _auth: AuthUser,
     Path(project_id): Path<i32>,
-    Json(body): Json<DriftConfigCreate>,
+    Json(mut body): Json<DriftConfigCreate>,
 ) -> impl IntoResponse {
     let store = state.store.store();
+    body.schedule = match normalize_schedule(body.schedule) {
+        Ok(schedule) => schedule,
+        Err(msg) => return (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))),
+    };
     match store.create_drift_config(project_id, body).await {
         Ok(c) => (StatusCode::CREATED, Json(json!(c))),
         Err(e) => (
@@ -69,6 +87,24 @@ pub async fn update_drift_config(
     Json(body): Json<DriftToggle>,
 ) -> impl IntoResponse {
     let store = state.store.store();
+    if let Some(schedule) = body.schedule {
+        let schedule = match normalize_schedule(Some(schedule)) {
+            Ok(schedule) => schedule,
+            Err(msg) => {
+                return (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response();
+            }
+        };
+        if let Err(e) = store
+            .update_drift_config_schedule(id, project_id, schedule)
+            .await
+        {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    }
     match store
         .update_drift_config_enabled(id, project_id, body.enabled)
         .await
@@ -258,7 +294,8 @@ mod tests {
     }
 }
 
-/// Trigger manual drift check — create a task with --check flag and record result
+/// Trigger manual drift check — enqueue a check-mode task; the result is
+/// classified in the background once the task finishes
 pub async fn trigger_drift_check(
     State(state): State<Arc<AppState>>,
     _auth: AuthUser,
@@ -266,7 +303,6 @@ pub async fn trigger_drift_check(
 ) -> impl IntoResponse {
     let store = state.store.store();
 
-    // Get the drift config
     let config = match store.get_drift_config(id, project_id).await {
         Ok(c) => c,
         Err(e) => {
@@ -274,38 +310,20 @@ pub async fn trigger_drift_check(
         }
     };
 
-    // Create a task with --check argument (dry run)
-    let task_body = json!({
-        "template_id": config.template_id,
-        "message": "Drift check (auto)",
-        "arguments": "--check --diff",
-        "dry_run": true
-    });
-
-    // Post task to the project tasks endpoint via store
-    // We record the drift result with "pending" status and the task_id
-    let result = store
-        .create_drift_result(
-            project_id,
-            id,
-            config.template_id,
-            "pending",
-            Some("Drift check triggered manually".to_string()),
-            None,
-        )
-        .await;
-
-    match result {
+    match drift::start_drift_check(state.store.as_arc(), &config).await {
         Ok(r) => (
-            StatusCode::OK,
+            StatusCode::ACCEPTED,
             Json(json!({
-                "message": "Drift check triggered",
+                "message": "Drift check started",
                 "drift_result_id": r.id,
                 "template_id": config.template_id,
-                "hint": "Create a task manually with --check --diff arguments to complete the check"
+                "task_id": r.task_id,
             })),
         )
             .into_response(),
+        Err(Error::Validation(msg)) => {
+            (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response()
+        }
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"error": e.to_string()})),
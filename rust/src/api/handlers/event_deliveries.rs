@@ -0,0 +1,104 @@
+//! Event Delivery Handlers
+//!
+//! Доставки событий задач политикам уведомлений и webhook проекта:
+//! просмотр состояния, истории попыток и повторная отправка.
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::api::extractors::AuthUser;
+use crate::api::state::AppState;
+use crate::db::store::EventOutboxManager;
+use crate::error::Error;
+use crate::models::{DeliveryStatus, EventDelivery};
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn store_error(e: Error) -> ApiError {
+    let status = match e {
+        Error::NotFound(_) => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(json!({"error": e.to_string()})))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveryQuery {
+    /// `pending`, `delivered` или `failed`
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/project/{project_id}/events/deliveries
+pub async fn list_event_deliveries(
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i32>,
+    Query(query): Query<DeliveryQuery>,
+    _auth: AuthUser,
+) -> Result<Json<Vec<EventDelivery>>, ApiError> {
+    let status = query
+        .status
+        .as_deref()
+        .map(str::parse::<DeliveryStatus>)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": e}))))?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let deliveries = state
+        .store
+        .get_event_deliveries(project_id, status, limit)
+        .await
+        .map_err(store_error)?;
+    Ok(Json(deliveries))
+}
+
+/// GET /api/project/{project_id}/events/deliveries/{id}
+///
+/// Доставка вместе с событием и журналом попыток.
+pub async fn get_event_delivery(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, id)): Path<(i32, i64)>,
+    _auth: AuthUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let delivery = state
+        .store
+        .get_event_delivery(project_id, id)
+        .await
+        .map_err(store_error)?;
+    let event = state
+        .store
+        .get_outbox_event(delivery.event_id)
+        .await
+        .map_err(store_error)?;
+    let attempts = state
+        .store
+        .get_event_delivery_logs(id)
+        .await
+        .map_err(store_error)?;
+    Ok(Json(json!({
+        "delivery": delivery,
+        "event": event,
+        "attempts": attempts,
+    })))
+}
+
+/// POST /api/project/{project_id}/events/deliveries/{id}/redeliver
+///
+/// Ставит доставку в очередь заново с полным числом попыток.
+pub async fn redeliver_event_delivery(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, id)): Path<(i32, i64)>,
+    _auth: AuthUser,
+) -> Result<Json<EventDelivery>, ApiError> {
+    let delivery = state
+        .store
+        .redeliver_event_delivery(project_id, id)
+        .await
+        .map_err(store_error)?;
+    Ok(Json(delivery))
+}
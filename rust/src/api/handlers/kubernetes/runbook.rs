@@ -315,6 +315,7 @@ pub async fn execute_runbook(
             "kubernetes": payload.kubernetes_context,
             "task_params": payload.task_params,
         })),
+        priority: None,
     };
 
     // Сохраняем задачу
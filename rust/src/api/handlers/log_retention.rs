@@ -0,0 +1,75 @@
+//! Log Retention Handlers
+//!
+//! Политика хранения задач проекта: удаление задач старше N дней и/или
+//! вне последних N запусков каждого шаблона (вместе с выводом и архивом).
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::api::extractors::AuthUser;
+use crate::api::state::AppState;
+use crate::db::store::TaskLogArchiveManager;
+use crate::models::LogRetentionPolicy;
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn internal_error(e: crate::error::Error) -> ApiError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"error": e.to_string()})),
+    )
+}
+
+/// GET /api/project/{project_id}/log-retention
+///
+/// Без настроенной политики возвращается политика без ограничений.
+pub async fn get_log_retention(
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i32>,
+    _auth: AuthUser,
+) -> Result<Json<LogRetentionPolicy>, ApiError> {
+    let policy = state
+        .store
+        .get_log_retention_policy(project_id)
+        .await
+        .map_err(internal_error)?
+        .unwrap_or(LogRetentionPolicy {
+            project_id,
+            ..Default::default()
+        });
+    Ok(Json(policy))
+}
+
+/// PUT /api/project/{project_id}/log-retention
+///
+/// Пустые поля снимают соответствующее ограничение.
+pub async fn put_log_retention(
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i32>,
+    _auth: AuthUser,
+    Json(mut payload): Json<LogRetentionPolicy>,
+) -> Result<Json<LogRetentionPolicy>, ApiError> {
+    for (name, value) in [
+        ("max_age_days", payload.max_age_days),
+        ("keep_last_runs", payload.keep_last_runs),
+    ] {
+        if value.is_some_and(|v| v < 1) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("{name} must be at least 1")})),
+            ));
+        }
+    }
+    payload.project_id = project_id;
+    state
+        .store
+        .set_log_retention_policy(payload.clone())
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(payload))
+}
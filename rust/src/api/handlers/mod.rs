@@ -7,14 +7,17 @@ pub mod ai;
 pub mod analytics;
 pub mod audit_log;
 pub mod auth;
+pub mod concurrency_groups;
 pub mod cost_estimate;
 pub mod credential_type;
 pub mod deployment_environment;
 pub mod drift;
 pub mod environment;
+pub mod event_deliveries;
 pub mod inventory;
 pub mod kubernetes;
 pub mod ldap_groups;
+pub mod log_retention;
 pub mod mailer;
 pub mod metrics;
 pub mod notification;
@@ -24,8 +27,10 @@ pub mod organizations;
 pub mod plan_approval;
 pub mod playbook;
 pub mod playbook_runs;
+pub mod plugins;
 pub mod projects;
 pub mod repository;
+pub mod service_accounts;
 pub mod snapshot;
 pub mod state_backend;
 pub mod task_structured_output;
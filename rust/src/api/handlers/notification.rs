@@ -6,6 +6,9 @@ use crate::db::store::NotificationPolicyManager;
 use crate::models::notification::{
     NotificationPolicy, NotificationPolicyCreate, NotificationPolicyUpdate,
 };
+use crate::plugins::{
+    NotificationLevel, notify_plugin_channel, plugin_target, validate_plugin_channel,
+};
 use axum::{
     Json,
     extract::{Path, State},
@@ -13,6 +16,34 @@ use axum::{
 };
 use std::sync::Arc;
 
+/// Проверяет поля политики; каналу `plugin:<id>` webhook URL не нужен
+async fn validate_policy(
+    name: &str,
+    channel_type: &str,
+    webhook_url: &str,
+    trigger: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let bad_request =
+        |message: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message)));
+    if name.trim().is_empty() {
+        return Err(bad_request("Policy name is required".to_string()));
+    }
+    let plugin_channel = validate_plugin_channel(channel_type)
+        .await
+        .map_err(|e| bad_request(e.to_string()))?;
+    if !plugin_channel && webhook_url.trim().is_empty() {
+        return Err(bad_request("Webhook URL is required".to_string()));
+    }
+    let valid_triggers = ["on_failure", "on_success", "on_start", "always"];
+    if !valid_triggers.contains(&trigger) {
+        return Err(bad_request(format!(
+            "Invalid trigger '{}'. Must be one of: on_failure, on_success, on_start, always",
+            trigger
+        )));
+    }
+    Ok(())
+}
+
 /// GET /api/project/{project_id}/notifications
 pub async fn list_notification_policies(
     State(state): State<Arc<AppState>>,
@@ -37,28 +68,13 @@ pub async fn create_notification_policy(
     Path(project_id): Path<i32>,
     Json(payload): Json<NotificationPolicyCreate>,
 ) -> Result<(StatusCode, Json<NotificationPolicy>), (StatusCode, Json<ErrorResponse>)> {
-    if payload.name.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("Policy name is required".to_string())),
-        ));
-    }
-    if payload.webhook_url.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("Webhook URL is required".to_string())),
-        ));
-    }
-    let valid_triggers = ["on_failure", "on_success", "on_start", "always"];
-    if !valid_triggers.contains(&payload.trigger.as_str()) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new(format!(
-                "Invalid trigger '{}'. Must be one of: on_failure, on_success, on_start, always",
-                payload.trigger
-            ))),
-        ));
-    }
+    validate_policy(
+        &payload.name,
+        &payload.channel_type,
+        &payload.webhook_url,
+        &payload.trigger,
+    )
+    .await?;
     let policy = state
         .store
         .create_notification_policy(project_id, payload)
@@ -96,28 +112,13 @@ pub async fn update_notification_policy(
     Path((project_id, id)): Path<(i32, i32)>,
     Json(payload): Json<NotificationPolicyUpdate>,
 ) -> Result<Json<NotificationPolicy>, (StatusCode, Json<ErrorResponse>)> {
-    if payload.name.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("Policy name is required".to_string())),
-        ));
-    }
-    if payload.webhook_url.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("Webhook URL is required".to_string())),
-        ));
-    }
-    let valid_triggers = ["on_failure", "on_success", "on_start", "always"];
-    if !valid_triggers.contains(&payload.trigger.as_str()) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new(format!(
-                "Invalid trigger '{}'. Must be one of: on_failure, on_success, on_start, always",
-                payload.trigger
-            ))),
-        ));
-    }
+    validate_policy(
+        &payload.name,
+        &payload.channel_type,
+        &payload.webhook_url,
+        &payload.trigger,
+    )
+    .await?;
     let policy = state
         .store
         .update_notification_policy(id, project_id, payload)
@@ -172,6 +173,26 @@ pub async fn test_notification_policy(
         "project_id": policy.project_id,
     });
 
+    if plugin_target(&policy.channel_type).is_some() {
+        notify_plugin_channel(
+            &policy,
+            "Test notification",
+            NotificationLevel::Info,
+            test_payload,
+        )
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse::new(format!(
+                    "Failed to send test notification: {}",
+                    e
+                ))),
+            )
+        })?;
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
     let client = reqwest::Client::new();
     client
         .post(&policy.webhook_url)
@@ -207,12 +228,20 @@ mod tests {
         create_app(store).await
     }
 
+    /// Запрос от имени администратора: маршруты проекта требуют аутентификации
+    fn authorized() -> axum::http::request::Builder {
+        Request::builder().header(
+            axum::http::header::AUTHORIZATION,
+            crate::api::auth_local::test_admin_bearer(),
+        )
+    }
+
     #[tokio::test]
     async fn test_list_notifications_returns_empty() {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .uri("/api/project/1/notifications")
                     .body(Body::empty())
                     .unwrap(),
@@ -229,7 +258,7 @@ mod tests {
             json!({"name": "", "webhook_url": "http://example.com", "trigger": "on_failure"});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/notifications")
                     .header("Content-Type", "application/json")
@@ -251,7 +280,7 @@ mod tests {
         let body = json!({"name": "Test", "webhook_url": "", "trigger": "on_failure"});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/notifications")
                     .header("Content-Type", "application/json")
@@ -274,7 +303,7 @@ mod tests {
             json!({"name": "Test", "webhook_url": "http://example.com", "trigger": "invalid"});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/notifications")
                     .header("Content-Type", "application/json")
@@ -298,7 +327,7 @@ mod tests {
                 json!({"name": "Test", "webhook_url": "http://example.com", "trigger": trigger});
             let resp = app
                 .oneshot(
-                    Request::builder()
+                    authorized()
                         .method("POST")
                         .uri("/api/project/1/notifications")
                         .header("Content-Type", "application/json")
@@ -317,7 +346,7 @@ mod tests {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .uri("/api/project/1/notifications/999")
                     .body(Body::empty())
                     .unwrap(),
@@ -334,7 +363,7 @@ mod tests {
             json!({"name": "", "webhook_url": "http://example.com", "trigger": "on_failure"});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("PUT")
                     .uri("/api/project/1/notifications/1")
                     .header("Content-Type", "application/json")
@@ -357,7 +386,7 @@ mod tests {
             json!({"name": "Test", "webhook_url": "http://example.com", "trigger": "invalid"});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("PUT")
                     .uri("/api/project/1/notifications/1")
                     .header("Content-Type", "application/json")
@@ -377,7 +406,7 @@ mod tests {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("DELETE")
                     .uri("/api/project/1/notifications/1")
                     .body(Body::empty())
@@ -394,7 +423,7 @@ mod tests {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/notifications/999/test")
                     .body(Body::empty())
@@ -404,4 +433,24 @@ mod tests {
             .unwrap();
         assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_validate_policy_plugin_channel() {
+        // Обычный канал требует webhook URL
+        assert!(
+            validate_policy("Test", "slack", "", "on_failure")
+                .await
+                .is_err()
+        );
+        assert!(
+            validate_policy("Test", "slack", "http://example.com", "always")
+                .await
+                .is_ok()
+        );
+        // Плагин-канал недоступен без подсистемы плагинов
+        let (status, _) = validate_policy("Test", "plugin:jira", "", "on_failure")
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
 }
@@ -134,18 +134,12 @@ pub async fn approve_plan(
             .into_response();
     }
 
-    // Set task back to WaitingRun so it can be re-queued
+    // Задача возвращается в очередь: исполнитель заберёт её из `waiting`
     let _ = store
-        .update_task_status(project_id, task_id, TaskStatus::Running)
+        .update_task_status(project_id, task_id, TaskStatus::Waiting)
         .await;
 
-    // Trigger task execution
-    if let Ok(task) = store.get_task(project_id, task_id).await {
-        let store_arc = state.store.as_arc();
-        tokio::spawn(crate::services::task_execution::execute_task(
-            store_arc, task,
-        ));
-    }
+    crate::services::task_execution::wake_dispatcher();
 
     StatusCode::OK.into_response()
 }
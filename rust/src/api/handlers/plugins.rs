@@ -0,0 +1,112 @@
+//! Handlers для управления плагинами (только администраторы)
+
+use crate::api::extractors::AuthUser;
+use crate::api::middleware::ErrorResponse;
+use crate::api::state::AppState;
+use crate::plugins::{PluginInfo, PluginManager, PluginStatus, persist_disabled, plugin_manager};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+fn require_admin(admin: bool) -> Result<Arc<RwLock<PluginManager>>, ApiError> {
+    if !admin {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new("Only admins can manage plugins")),
+        ));
+    }
+    plugin_manager().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                "Plugin subsystem is not enabled (SEMAPHORE_PLUGINS_DIR)",
+            )),
+        )
+    })
+}
+
+fn internal_error(e: crate::error::Error) -> ApiError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(e.to_string())),
+    )
+}
+
+async fn plugin_info(manager: &PluginManager, id: &str) -> Result<PluginInfo, ApiError> {
+    manager
+        .list_plugins()
+        .await
+        .into_iter()
+        .find(|info| info.id == id)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new(format!("Plugin {} not found", id))),
+            )
+        })
+}
+
+/// GET /api/admin/plugins
+pub async fn list_plugins(
+    AuthUser { admin, .. }: AuthUser,
+) -> Result<Json<Vec<PluginInfo>>, ApiError> {
+    let manager = require_admin(admin)?;
+    let plugins = manager.read().await.list_plugins().await;
+    Ok(Json(plugins))
+}
+
+/// POST /api/admin/plugins/{id}/enable
+pub async fn enable_plugin(
+    State(state): State<Arc<AppState>>,
+    AuthUser { admin, .. }: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PluginInfo>, ApiError> {
+    let manager = require_admin(admin)?;
+    let mut manager = manager.write().await;
+    plugin_info(&manager, &id).await?;
+    manager.enable_plugin(&id).map_err(internal_error)?;
+
+    // Плагин, отключённый при старте, ещё не инициализирован
+    if let Some(plugin) = manager.get_plugin(&id) {
+        let mut plugin = plugin.write().await;
+        if !matches!(plugin.status(), PluginStatus::Loaded) {
+            plugin.load().await.map_err(internal_error)?;
+        }
+    }
+    persist_disabled(&manager, state.store.store())
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(plugin_info(&manager, &id).await?))
+}
+
+/// POST /api/admin/plugins/{id}/disable
+pub async fn disable_plugin(
+    State(state): State<Arc<AppState>>,
+    AuthUser { admin, .. }: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PluginInfo>, ApiError> {
+    let manager = require_admin(admin)?;
+    let mut manager = manager.write().await;
+    plugin_info(&manager, &id).await?;
+    manager.disable_plugin(&id).map_err(internal_error)?;
+    persist_disabled(&manager, state.store.store())
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(plugin_info(&manager, &id).await?))
+}
+
+/// POST /api/admin/plugins/reload
+pub async fn reload_plugins(
+    AuthUser { admin, .. }: AuthUser,
+) -> Result<Json<Vec<PluginInfo>>, ApiError> {
+    let manager = require_admin(admin)?;
+    let mut manager = manager.write().await;
+    manager.reload().await.map_err(internal_error)?;
+    Ok(Json(manager.list_plugins().await))
+}
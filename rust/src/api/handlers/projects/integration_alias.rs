@@ -4,7 +4,8 @@
 
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
-use crate::error::{Error, Result};
+use crate::db::store::IntegrationManager;
+use crate::error::Error;
 use crate::models::IntegrationAlias;
 use axum::{
     Json,
@@ -21,40 +22,89 @@ pub struct PublicAlias {
     pub url: String,
 }
 
-/// Получает псевдонимы интеграций
+/// Публичный URL входящего вызова для псевдонима
+fn public_alias(web_host: &str, alias: &IntegrationAlias) -> PublicAlias {
+    PublicAlias {
+        id: alias.id,
+        url: format!(
+            "{}/api/integrations/{}",
+            web_host.trim_end_matches('/'),
+            alias.alias
+        ),
+    }
+}
+
+fn internal_error(e: Error) -> (StatusCode, Json<ErrorResponse>) {
+    match e {
+        Error::NotFound(msg) => (StatusCode::NOT_FOUND, Json(ErrorResponse::new(msg))),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(e.to_string())),
+        ),
+    }
+}
+
+/// Получает псевдонимы интеграции
 pub async fn get_integration_aliases(
     State(state): State<Arc<AppState>>,
-    Path(project_id): Path<i32>,
+    Path((project_id, integration_id)): Path<(i32, i32)>,
 ) -> std::result::Result<Json<Vec<PublicAlias>>, (StatusCode, Json<ErrorResponse>)> {
-    // В реальной реализации нужно получить псевдонимы из БД
-    // let aliases = state.store.get_integration_aliases(project_id, None).await?;
+    let aliases = state
+        .store
+        .get_integration_aliases(project_id, Some(integration_id))
+        .await
+        .map_err(internal_error)?;
 
-    Ok(Json(vec![]))
+    Ok(Json(
+        aliases
+            .iter()
+            .map(|a| public_alias(&state.config.web_host, a))
+            .collect(),
+    ))
 }
 
 /// Создаёт псевдоним интеграции
 pub async fn add_integration_alias(
     State(state): State<Arc<AppState>>,
-    Path(project_id): Path<i32>,
+    Path((project_id, integration_id)): Path<(i32, i32)>,
 ) -> std::result::Result<(StatusCode, Json<PublicAlias>), (StatusCode, Json<ErrorResponse>)> {
-    // Генерация случайного алиаса
-    let alias_value = rand::random::<u128>().to_string();
+    // Проверяем, что интеграция принадлежит проекту
+    state
+        .store
+        .get_integration(project_id, integration_id)
+        .await
+        .map_err(internal_error)?;
 
-    let alias = PublicAlias {
+    // Генерация случайного алиаса
+    let alias = IntegrationAlias {
         id: 0,
-        url: format!("https://example.com/integrations/{}", alias_value),
+        integration_id,
+        project_id,
+        alias: format!("{:032x}", rand::random::<u128>()),
     };
 
-    Ok((StatusCode::CREATED, Json(alias)))
+    let created = state
+        .store
+        .create_integration_alias(alias)
+        .await
+        .map_err(internal_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(public_alias(&state.config.web_host, &created)),
+    ))
 }
 
 /// Удаляет псевдоним интеграции
 pub async fn delete_integration_alias(
     State(state): State<Arc<AppState>>,
-    Path((project_id, alias_id)): Path<(i32, i32)>,
+    Path((project_id, _integration_id, alias_id)): Path<(i32, i32, i32)>,
 ) -> std::result::Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    // В реальной реализации нужно удалить псевдоним из БД
-    // state.store.delete_integration_alias(project_id, alias_id).await?;
+    state
+        .store
+        .delete_integration_alias(project_id, alias_id)
+        .await
+        .map_err(internal_error)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -68,8 +118,15 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_integration_aliases_handler() {
-        // Тест для проверки обработчиков псевдонимов
-        assert!(true);
+    fn test_public_alias_url() {
+        let alias = IntegrationAlias {
+            id: 5,
+            integration_id: 1,
+            project_id: 1,
+            alias: "abc".to_string(),
+        };
+        let public = public_alias("https://semaphore.local/", &alias);
+        assert_eq!(public.id, 5);
+        assert_eq!(public.url, "https://semaphore.local/api/integrations/abc");
     }
 }
@@ -8,6 +8,7 @@ use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
 use crate::db::store::{ProjectStore, TaskManager, UserManager};
 use crate::models::{Project, ProjectUser, ProjectUserRole};
+use crate::plugins::HookType;
 use crate::services::backup::BackupFormat;
 use axum::{
     Json,
@@ -20,14 +21,17 @@ use std::sync::Arc;
 /// Получает проекты пользователя
 pub async fn get_projects(
     State(state): State<Arc<AppState>>,
-    _auth_user: AuthUser,
+    auth_user: AuthUser,
 ) -> std::result::Result<Json<Vec<Project>>, (StatusCode, Json<ErrorResponse>)> {
-    let projects = state.store.get_projects(None).await.map_err(|e| {
+    let mut projects = state.store.get_projects(None).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new(e.to_string())),
         )
     })?;
+    // API-токен, ограниченный проектами, видит только их
+    let caller = auth_user.caller();
+    projects.retain(|p| caller.allows_project(p.id));
 
     Ok(Json(projects))
 }
@@ -100,6 +104,7 @@ pub async fn add_project(
                 Json(ErrorResponse::new(e.to_string())),
             )
         })?;
+    crate::plugins::emit_project(HookType::ProjectAfterCreate, &created, Some(user_id));
 
     Ok((StatusCode::CREATED, Json(created)))
 }
@@ -179,12 +184,17 @@ pub async fn update_project(
         project.default_secret_storage_id = Some(default_secret_storage_id);
     }
 
-    state.store.update_project(project).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse::new(e.to_string())),
-        )
-    })?;
+    state
+        .store
+        .update_project(project.clone())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+        })?;
+    crate::plugins::emit_project(HookType::ProjectAfterUpdate, &project, None);
 
     Ok(StatusCode::OK)
 }
@@ -227,12 +237,16 @@ pub async fn delete_project(
     State(state): State<Arc<AppState>>,
     Path(project_id): Path<i32>,
 ) -> std::result::Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let project = state.store.get_project(project_id).await.ok();
     state.store.delete_project(project_id).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new(e.to_string())),
         )
     })?;
+    if let Some(project) = project {
+        crate::plugins::emit_project(HookType::ProjectAfterDelete, &project, None);
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -4,9 +4,9 @@
 
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
-use crate::db::store::ProjectRoleManager;
+use crate::db::store::{ProjectAccessManager, ProjectRoleManager, TemplateManager};
 use crate::error::Error;
-use crate::models::Role;
+use crate::models::{Role, TemplateRolePerm};
 use axum::{
     Json,
     extract::{Path, State},
@@ -15,44 +15,6 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-/// Встроенные роли проекта (всегда доступны)
-fn builtin_roles(project_id: i32) -> Vec<Role> {
-    vec![
-        Role {
-            id: -1,
-            project_id,
-            slug: "owner".to_string(),
-            name: "Owner".to_string(),
-            description: Some("Full project control".to_string()),
-            permissions: Some(0x7FFFFFFF),
-        },
-        Role {
-            id: -2,
-            project_id,
-            slug: "manager".to_string(),
-            name: "Manager".to_string(),
-            description: Some("Manage project resources".to_string()),
-            permissions: Some(0x0FFFFFFF),
-        },
-        Role {
-            id: -3,
-            project_id,
-            slug: "task_runner".to_string(),
-            name: "Task Runner".to_string(),
-            description: Some("Run tasks".to_string()),
-            permissions: Some(0x00000001),
-        },
-        Role {
-            id: -4,
-            project_id,
-            slug: "guest".to_string(),
-            name: "Guest".to_string(),
-            description: Some("View only".to_string()),
-            permissions: Some(0),
-        },
-    ]
-}
-
 /// Получить все роли проекта (включая built-in)
 ///
 /// GET /api/project/{project_id}/roles/all
@@ -60,7 +22,7 @@ pub async fn get_all_roles(
     State(state): State<Arc<AppState>>,
     Path(project_id): Path<i32>,
 ) -> Result<Json<Vec<Role>>, (StatusCode, Json<ErrorResponse>)> {
-    let mut roles = builtin_roles(project_id);
+    let mut roles = Role::builtin(project_id);
     let custom = state
         .store
         .get_project_roles(project_id)
@@ -212,10 +174,108 @@ pub async fn delete_role(
     Ok(StatusCode::NO_CONTENT)
 }
 
+fn internal_error(e: Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(e.to_string())),
+    )
+}
+
+/// Переопределения ролей шаблона
+///
+/// GET /api/project/{project_id}/templates/{template_id}/roles
+pub async fn get_template_role_perms(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, template_id)): Path<(i32, i32)>,
+) -> Result<Json<Vec<TemplateRolePerm>>, (StatusCode, Json<ErrorResponse>)> {
+    let perms = state
+        .store
+        .get_template_role_perms(project_id, template_id)
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(perms))
+}
+
+/// Добавить переопределение роли на шаблоне: участники с ролью `role_slug`
+/// получают на шаблоне разрешения роли `role_id`
+///
+/// POST /api/project/{project_id}/templates/{template_id}/roles
+pub async fn create_template_role_perm(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, template_id)): Path<(i32, i32)>,
+    Json(payload): Json<TemplateRolePermPayload>,
+) -> Result<(StatusCode, Json<TemplateRolePerm>), (StatusCode, Json<ErrorResponse>)> {
+    state
+        .store
+        .get_template(project_id, template_id)
+        .await
+        .map_err(|e| match e {
+            Error::NotFound(_) => (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new(e.to_string())),
+            ),
+            _ => internal_error(e),
+        })?;
+
+    let mut roles = Role::builtin(project_id);
+    roles.extend(
+        state
+            .store
+            .get_project_roles(project_id)
+            .await
+            .map_err(internal_error)?,
+    );
+    let bad_request =
+        |message: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(message)));
+    if !roles.iter().any(|r| r.slug == payload.role_slug) {
+        return Err(bad_request(format!("Unknown role: {}", payload.role_slug)));
+    }
+    if !roles.iter().any(|r| r.id == payload.role_id) {
+        return Err(bad_request(format!("Role {} not found", payload.role_id)));
+    }
+
+    let perm = state
+        .store
+        .create_template_role_perm(TemplateRolePerm {
+            id: 0,
+            project_id,
+            template_id,
+            role_id: payload.role_id,
+            role_slug: payload.role_slug,
+        })
+        .await
+        .map_err(internal_error)?;
+    Ok((StatusCode::CREATED, Json(perm)))
+}
+
+/// Удалить переопределение роли на шаблоне
+///
+/// DELETE /api/project/{project_id}/templates/{template_id}/roles/{id}
+pub async fn delete_template_role_perm(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, template_id, id)): Path<(i32, i32, i32)>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .store
+        .delete_template_role_perm(project_id, template_id, id)
+        .await
+        .map_err(internal_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 // ============================================================================
 // Types
 // ============================================================================
 
+/// Payload переопределения роли на шаблоне
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateRolePermPayload {
+    /// Роль участников, к которым применяется переопределение
+    pub role_slug: String,
+    /// Роль, чьи разрешения они получают на шаблоне
+    pub role_id: i32,
+}
+
 /// Payload для создания роли
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RoleCreatePayload {
@@ -7,11 +7,18 @@ use crate::api::state::AppState;
 use crate::db::store::ScheduleManager;
 use crate::error::{Error, Result};
 use crate::models::Schedule;
+use crate::models::schedule::{
+    MISFIRE_RUN_ALL, MISFIRE_RUN_ONCE, MISFIRE_SKIP, SCHEDULE_TARGET_DRIFT,
+    SCHEDULE_TARGET_TEMPLATE, SCHEDULE_TARGET_WORKFLOW,
+};
+use crate::services::schedule_calendar::{ScheduleCalendar, parse_timezone};
+use crate::services::schedule_commit::validate_commit_paths;
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -29,6 +36,53 @@ fn schedule_cron_must_parse(schedule: &Schedule) -> Option<String> {
     }
 }
 
+/// Наибольшая допустимая случайная задержка запуска, секунды
+const MAX_JITTER_SECS: i32 = 3600;
+
+/// Проверяет цель, часовой пояс, политику пропусков, задержку, периоды запрета
+/// и настройки запуска по коммиту
+fn schedule_settings_must_be_valid(schedule: &Schedule) -> Option<String> {
+    match schedule.target_type.as_str() {
+        SCHEDULE_TARGET_TEMPLATE => {}
+        SCHEDULE_TARGET_WORKFLOW | SCHEDULE_TARGET_DRIFT => {
+            if schedule.target_id.is_none() {
+                return Some(format!(
+                    "target_id is required for target type '{}'",
+                    schedule.target_type
+                ));
+            }
+        }
+        other => return Some(format!("Unknown schedule target type '{}'", other)),
+    }
+    if let Err(e) = parse_timezone(schedule.timezone.as_deref()) {
+        return Some(e.to_string());
+    }
+    if ![MISFIRE_SKIP, MISFIRE_RUN_ONCE, MISFIRE_RUN_ALL]
+        .contains(&schedule.misfire_policy.as_str())
+    {
+        return Some(format!(
+            "Unknown misfire policy '{}'",
+            schedule.misfire_policy
+        ));
+    }
+    if !(0..=MAX_JITTER_SECS).contains(&schedule.jitter_secs) {
+        return Some(format!(
+            "jitter_secs must be between 0 and {}",
+            MAX_JITTER_SECS
+        ));
+    }
+    if schedule.blackout_windows.iter().any(|w| w.start >= w.end) {
+        return Some("Blackout window start must be before its end".to_string());
+    }
+    if schedule.is_commit_triggered() && schedule.target_type != SCHEDULE_TARGET_TEMPLATE {
+        return Some("Commit-triggered schedules can only run templates".to_string());
+    }
+    if let Err(e) = validate_commit_paths(&schedule.commit_paths) {
+        return Some(e.to_string());
+    }
+    None
+}
+
 /// Получает расписания проекта
 pub async fn get_project_schedules(
     State(state): State<Arc<AppState>>,
@@ -76,7 +130,9 @@ pub async fn add_schedule(
     let mut schedule = payload;
     schedule.project_id = project_id;
 
-    if let Some(err) = schedule_cron_must_parse(&schedule) {
+    if let Some(err) =
+        schedule_cron_must_parse(&schedule).or_else(|| schedule_settings_must_be_valid(&schedule))
+    {
         return Err((StatusCode::BAD_REQUEST, Json(ErrorResponse::new(err))));
     }
 
@@ -100,7 +156,9 @@ pub async fn update_schedule(
     schedule.id = schedule_id;
     schedule.project_id = project_id;
 
-    if let Some(err) = schedule_cron_must_parse(&schedule) {
+    if let Some(err) =
+        schedule_cron_must_parse(&schedule).or_else(|| schedule_settings_must_be_valid(&schedule))
+    {
         return Err((StatusCode::BAD_REQUEST, Json(ErrorResponse::new(err))));
     }
 
@@ -172,6 +230,43 @@ pub async fn toggle_schedule_active(
     Ok(StatusCode::OK)
 }
 
+/// Ближайшие времена срабатывания расписания (UTC), без попавших в периоды запрета
+///
+/// GET /api/project/{project_id}/schedules/{id}/next?count=N
+pub async fn get_schedule_next_runs(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, schedule_id)): Path<(i32, i32)>,
+    Query(query): Query<NextRunsQuery>,
+) -> std::result::Result<Json<Vec<DateTime<Utc>>>, (StatusCode, Json<ErrorResponse>)> {
+    let schedule = state
+        .store
+        .get_schedule(project_id, schedule_id)
+        .await
+        .map_err(|e| match e {
+            Error::NotFound(_) => (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Schedule not found".to_string())),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            ),
+        })?;
+
+    if schedule.cron.trim().is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+    let calendar = ScheduleCalendar::for_schedule(&schedule).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(e.to_string())),
+        )
+    })?;
+    let count = query.count.clamp(1, MAX_NEXT_RUNS);
+
+    Ok(Json(calendar.upcoming(Utc::now(), count)))
+}
+
 /// Валидирует cron-выражение
 ///
 /// POST /api/projects/{project_id}/schedules/validate
@@ -194,6 +289,20 @@ pub async fn validate_schedule_cron_format(
 // Types
 // ============================================================================
 
+/// Наибольшее число срабатываний в предпросмотре
+const MAX_NEXT_RUNS: usize = 100;
+
+fn default_next_runs() -> usize {
+    5
+}
+
+/// Query-параметры предпросмотра срабатываний
+#[derive(Debug, Deserialize)]
+pub struct NextRunsQuery {
+    #[serde(default = "default_next_runs")]
+    pub count: usize,
+}
+
 /// Payload для валидации cron
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidateCronPayload {
@@ -230,6 +339,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         }
     }
 
@@ -415,4 +533,52 @@ mod tests {
         assert_eq!(s.project_id, 1);
         assert!(s.active);
     }
+
+    #[test]
+    fn settings_validation_accepts_defaults() {
+        let s = sample_schedule("0 0 * * * *", None);
+        assert!(schedule_settings_must_be_valid(&s).is_none());
+    }
+
+    #[test]
+    fn settings_validation_requires_target_id_for_workflow() {
+        let mut s = sample_schedule("0 0 * * * *", None);
+        s.target_type = SCHEDULE_TARGET_WORKFLOW.to_string();
+        assert!(schedule_settings_must_be_valid(&s).is_some());
+        s.target_id = Some(3);
+        assert!(schedule_settings_must_be_valid(&s).is_none());
+        s.target_type = "pipeline".to_string();
+        assert!(schedule_settings_must_be_valid(&s).is_some());
+    }
+
+    #[test]
+    fn settings_validation_rejects_bad_timezone_policy_and_jitter() {
+        let mut s = sample_schedule("0 0 * * * *", None);
+        s.timezone = Some("Europe/Atlantis".to_string());
+        assert!(schedule_settings_must_be_valid(&s).is_some());
+
+        let mut s = sample_schedule("0 0 * * * *", None);
+        s.misfire_policy = "sometimes".to_string();
+        assert!(schedule_settings_must_be_valid(&s).is_some());
+
+        let mut s = sample_schedule("0 0 * * * *", None);
+        s.jitter_secs = -1;
+        assert!(schedule_settings_must_be_valid(&s).is_some());
+    }
+
+    #[test]
+    fn settings_validation_checks_commit_trigger() {
+        let mut s = sample_schedule("0 */5 * * * *", None);
+        s.repository_id = Some(2);
+        s.commit_paths = vec!["roles/**".to_string()];
+        assert!(schedule_settings_must_be_valid(&s).is_none());
+
+        s.commit_paths = vec!["roles/[".to_string()];
+        assert!(schedule_settings_must_be_valid(&s).is_some());
+
+        s.commit_paths.clear();
+        s.target_type = SCHEDULE_TARGET_DRIFT.to_string();
+        s.target_id = Some(1);
+        assert!(schedule_settings_must_be_valid(&s).is_some());
+    }
 }
@@ -7,7 +7,9 @@ use crate::api::state::AppState;
 use crate::db::store::{RetrieveQueryParams, TaskManager, UserManager};
 use crate::error::{Error, Result};
 use crate::models::{Task, TaskOutput, TaskWithTpl};
+use crate::services::log_archive;
 use crate::services::task_logger::TaskStatus;
+use crate::services::task_pool::{self, QueueEntry, QueuePriorityPolicy};
 use axum::{
     Json,
     extract::{Path, Query, State},
@@ -106,6 +108,7 @@ pub async fn add_task(
         repository_id: payload.repository_id,
         environment_id: payload.environment_id,
         params: None,
+        priority: payload.priority,
     };
 
     let created = state.store.create_task(task).await.map_err(|e| {
@@ -174,8 +177,39 @@ pub async fn stop_task(
     State(state): State<Arc<AppState>>,
     Path((project_id, task_id)): Path<(i32, i32)>,
 ) -> std::result::Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    // В реальной реализации нужно остановить задачу
-    // state.store.stop_task(project_id, task_id).await?;
+    let task = state
+        .store
+        .get_task(project_id, task_id)
+        .await
+        .map_err(|e| match e {
+            Error::NotFound(_) => (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("Task not found".to_string())),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            ),
+        })?;
+
+    // Запущенная задача переводится в Stopping — исполнитель сам остановит
+    // процесс/контейнер и выставит Stopped; ожидающая останавливается сразу
+    let status = match task.status {
+        TaskStatus::Running | TaskStatus::Starting => TaskStatus::Stopping,
+        status if status.is_finished() => return Ok(StatusCode::OK),
+        _ => TaskStatus::Stopped,
+    };
+
+    state
+        .store
+        .update_task_status(project_id, task_id, status)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+        })?;
 
     Ok(StatusCode::OK)
 }
@@ -277,13 +311,15 @@ pub async fn get_task_output(
     State(state): State<Arc<AppState>>,
     Path((project_id, task_id)): Path<(i32, i32)>,
 ) -> std::result::Result<Json<Vec<TaskOutput>>, (StatusCode, Json<ErrorResponse>)> {
-    // Получаем вывод задачи
-    let outputs = state.store.get_task_outputs(task_id).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse::new(e.to_string())),
-        )
-    })?;
+    // Получаем вывод задачи (из БД или архива логов)
+    let outputs = log_archive::load_task_outputs(state.store.store(), task_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+        })?;
 
     Ok(Json(outputs))
 }
@@ -295,12 +331,14 @@ pub async fn get_task_raw_output(
     State(state): State<Arc<AppState>>,
     Path((project_id, task_id)): Path<(i32, i32)>,
 ) -> std::result::Result<String, (StatusCode, Json<ErrorResponse>)> {
-    let outputs = state.store.get_task_outputs(task_id).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse::new(e.to_string())),
-        )
-    })?;
+    let outputs = log_archive::load_task_outputs(state.store.store(), task_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+        })?;
 
     // Объединяем все строки вывода в plain text
     let raw = outputs
@@ -327,6 +365,161 @@ pub async fn get_task_stages(
     Ok(Json(serde_json::json!([])))
 }
 
+/// Задача в цепочке хуков
+#[derive(Debug, Serialize)]
+pub struct TaskHookEntry {
+    /// Тип хука: `pre`, `post` или `fail`
+    pub hook_type: String,
+    pub task: Task,
+}
+
+/// Цепочка хуков задачи
+#[derive(Debug, Serialize)]
+pub struct TaskHookChain {
+    /// Родительская задача, если задача сама является хуком
+    pub parent: Option<TaskHookEntry>,
+    /// Хуки задачи в порядке запуска
+    pub hooks: Vec<TaskHookEntry>,
+}
+
+/// Возвращает цепочку pre/post/fail хуков задачи
+///
+/// GET /api/projects/{project_id}/tasks/{id}/hooks
+pub async fn get_task_hooks(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, task_id)): Path<(i32, i32)>,
+) -> std::result::Result<Json<TaskHookChain>, (StatusCode, Json<ErrorResponse>)> {
+    let internal = |e: Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(e.to_string())),
+        )
+    };
+
+    let links = state
+        .store
+        .get_task_hook_links(project_id, task_id)
+        .await
+        .map_err(internal)?;
+
+    let mut chain = TaskHookChain {
+        parent: None,
+        hooks: Vec::new(),
+    };
+    for link in links {
+        if link.task_id == task_id {
+            let parent = state
+                .store
+                .get_task(project_id, link.parent_task_id)
+                .await
+                .map_err(internal)?;
+            chain.parent = Some(TaskHookEntry {
+                hook_type: link.hook_type,
+                task: parent,
+            });
+        } else {
+            let hook = state
+                .store
+                .get_task(project_id, link.task_id)
+                .await
+                .map_err(internal)?;
+            chain.hooks.push(TaskHookEntry {
+                hook_type: link.hook_type,
+                task: hook,
+            });
+        }
+    }
+
+    Ok(Json(chain))
+}
+
+fn queue_error(e: Error) -> (StatusCode, Json<ErrorResponse>) {
+    (e.to_status_code(), Json(ErrorResponse::new(e.to_string())))
+}
+
+/// Возвращает очередь ожидающих задач проекта в порядке запуска
+///
+/// GET /api/project/{project_id}/tasks/queue
+pub async fn get_task_queue(
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i32>,
+) -> std::result::Result<Json<Vec<QueueEntry>>, (StatusCode, Json<ErrorResponse>)> {
+    let queue = task_pool::load_queue(
+        state.store.store(),
+        project_id,
+        &QueuePriorityPolicy::default(),
+    )
+    .await
+    .map_err(queue_error)?;
+    Ok(Json(queue))
+}
+
+/// Payload для смены приоритета задачи
+#[derive(Debug, Deserialize)]
+pub struct TaskPriorityPayload {
+    /// `null` — вернуть приоритет шаблона
+    pub priority: Option<i32>,
+}
+
+/// Меняет приоритет ожидающей задачи
+///
+/// POST /api/project/{project_id}/tasks/{task_id}/priority
+pub async fn set_task_priority(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, task_id)): Path<(i32, i32)>,
+    Json(payload): Json<TaskPriorityPayload>,
+) -> std::result::Result<Json<Task>, (StatusCode, Json<ErrorResponse>)> {
+    let task =
+        task_pool::set_task_priority(state.store.store(), project_id, task_id, payload.priority)
+            .await
+            .map_err(queue_error)?;
+    Ok(Json(task))
+}
+
+/// Поднимает ожидающую задачу в начало очереди
+///
+/// POST /api/project/{project_id}/tasks/{task_id}/bump
+pub async fn bump_task(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, task_id)): Path<(i32, i32)>,
+) -> std::result::Result<Json<Task>, (StatusCode, Json<ErrorResponse>)> {
+    let task = task_pool::bump_task(
+        state.store.store(),
+        project_id,
+        task_id,
+        &QueuePriorityPolicy::default(),
+    )
+    .await
+    .map_err(queue_error)?;
+    Ok(Json(task))
+}
+
+/// Payload для перестановки очереди
+#[derive(Debug, Deserialize)]
+pub struct ReorderQueuePayload {
+    /// Задачи в желаемом порядке запуска
+    pub task_ids: Vec<i32>,
+}
+
+/// Переставляет задачи в начало очереди в указанном порядке
+///
+/// POST /api/project/{project_id}/tasks/queue/reorder
+pub async fn reorder_task_queue(
+    State(state): State<Arc<AppState>>,
+    Path(project_id): Path<i32>,
+    Json(payload): Json<ReorderQueuePayload>,
+) -> std::result::Result<Json<Vec<QueueEntry>>, (StatusCode, Json<ErrorResponse>)> {
+    let queue = task_pool::reorder_queue(
+        state.store.store(),
+        project_id,
+        &payload.task_ids,
+        &QueuePriorityPolicy::default(),
+    )
+    .await
+    .map_err(queue_error)?;
+    Ok(Json(queue))
+}
+
 /// Возвращает все активные задачи по всем проектам
 ///
 /// GET /api/tasks
@@ -361,6 +554,7 @@ pub struct CreateTaskPayload {
     pub inventory_id: Option<i32>,
     pub repository_id: Option<i32>,
     pub environment_id: Option<i32>,
+    pub priority: Option<i32>,
 }
 
 // ============================================================================
@@ -4,9 +4,11 @@
 
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
-use crate::db::store::{ProjectStore, RetrieveQueryParams, UserManager};
-use crate::error::{Error, Result};
-use crate::models::{ProjectUser, ProjectUserRole, User};
+use crate::db::store::{
+    ProjectAccessManager, ProjectRoleManager, ProjectStore, RetrieveQueryParams, UserManager,
+};
+use crate::error::Error;
+use crate::models::{ProjectUser, ProjectUserRole, Role};
 use axum::{
     Json,
     extract::{Path, State},
@@ -24,6 +26,36 @@ pub struct ProjectUserResponse {
     pub role: String,
 }
 
+fn internal_error(e: Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(e.to_string())),
+    )
+}
+
+/// Проверяет, что роль встроенная или кастомная роль проекта
+async fn validate_role(
+    state: &AppState,
+    project_id: i32,
+    role: &str,
+) -> std::result::Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if Role::builtin(project_id).iter().any(|r| r.slug == role) {
+        return Ok(());
+    }
+    let custom = state
+        .store
+        .get_project_roles(project_id)
+        .await
+        .map_err(internal_error)?;
+    if custom.iter().any(|r| r.slug == role) {
+        return Ok(());
+    }
+    Err((
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new(format!("Unknown role: {role}"))),
+    ))
+}
+
 /// Получает пользователей проекта
 pub async fn get_users(
     State(state): State<Arc<AppState>>,
@@ -33,22 +65,27 @@ pub async fn get_users(
         .store
         .get_project_users(project_id, RetrieveQueryParams::default())
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(e.to_string())),
-            )
-        })?;
-
-    let result: Vec<ProjectUserResponse> = users
-        .into_iter()
-        .map(|user| ProjectUserResponse {
+        .map_err(internal_error)?;
+
+    let mut result = Vec::with_capacity(users.len());
+    for user in users {
+        // Кастомные роли не представимы в ProjectUserRole — берём slug из БД
+        let role = match user.role {
+            ProjectUserRole::None => state
+                .store
+                .get_project_user_role(project_id, user.user_id)
+                .await
+                .map_err(internal_error)?
+                .unwrap_or_else(|| user.role.to_string()),
+            role => role.to_string(),
+        };
+        result.push(ProjectUserResponse {
             id: user.id,
             username: user.username,
             name: user.name,
-            role: user.role.to_string(),
-        })
-        .collect();
+            role,
+        });
+    }
 
     Ok(Json(result))
 }
@@ -60,24 +97,39 @@ pub async fn add_user(
     Json(payload): Json<AddUserPayload>,
 ) -> std::result::Result<(StatusCode, Json<ProjectUserResponse>), (StatusCode, Json<ErrorResponse>)>
 {
-    let project_user = ProjectUser {
-        id: 0,
-        project_id,
-        user_id: payload.user_id,
-        role: payload.role.clone(),
-        created: chrono::Utc::now(),
-        username: String::new(),
-        name: String::new(),
-    };
+    validate_role(&state, project_id, &payload.role).await?;
+    let user = state
+        .store
+        .get_user(payload.user_id)
+        .await
+        .map_err(|e| match e {
+            Error::NotFound(_) => (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new(e.to_string())),
+            ),
+            _ => internal_error(e),
+        })?;
 
-    // В реальной реализации нужно сохранить в БД
-    // state.store.create_project_user(project_user).await?;
+    state
+        .store
+        .create_project_user(ProjectUser::new(
+            project_id,
+            payload.user_id,
+            ProjectUserRole::None,
+        ))
+        .await
+        .map_err(internal_error)?;
+    state
+        .store
+        .set_project_user_role(project_id, payload.user_id, &payload.role)
+        .await
+        .map_err(internal_error)?;
 
     let response = ProjectUserResponse {
-        id: payload.user_id,
-        username: String::new(),
-        name: String::new(),
-        role: project_user.role.to_string(),
+        id: user.id,
+        username: user.username,
+        name: user.name,
+        role: payload.role,
     };
 
     Ok((StatusCode::CREATED, Json(response)))
@@ -89,10 +141,26 @@ pub async fn update_user_role(
     Path((project_id, user_id)): Path<(i32, i32)>,
     Json(payload): Json<UpdateUserRolePayload>,
 ) -> std::result::Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    // В реальной реализации нужно обновить в БД
-    // state.store.update_project_user_role(project_id, user_id, payload.role).await?;
+    validate_role(&state, project_id, &payload.role).await?;
+    let current = state
+        .store
+        .get_project_user_role(project_id, user_id)
+        .await
+        .map_err(internal_error)?;
+    if current.is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("User is not a member of this project")),
+        ));
+    }
 
-    Ok(StatusCode::OK)
+    state
+        .store
+        .set_project_user_role(project_id, user_id, &payload.role)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// Удаляет пользователя из проекта
@@ -100,8 +168,11 @@ pub async fn delete_user(
     State(state): State<Arc<AppState>>,
     Path((project_id, user_id)): Path<(i32, i32)>,
 ) -> std::result::Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    // В реальной реализации нужно удалить из БД
-    // state.store.delete_project_user(project_id, user_id).await?;
+    state
+        .store
+        .delete_project_user(project_id, user_id)
+        .await
+        .map_err(internal_error)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -110,13 +181,15 @@ pub async fn delete_user(
 #[derive(Debug, Deserialize)]
 pub struct AddUserPayload {
     pub user_id: i32,
-    pub role: ProjectUserRole,
+    /// Slug встроенной или кастомной роли
+    pub role: String,
 }
 
 /// Payload для обновления роли
 #[derive(Debug, Deserialize)]
 pub struct UpdateUserRolePayload {
-    pub role: ProjectUserRole,
+    /// Slug встроенной или кастомной роли
+    pub role: String,
 }
 
 // ============================================================================
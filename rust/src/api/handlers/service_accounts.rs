@@ -0,0 +1,264 @@
+//! Service Accounts Handlers
+//!
+//! Сервисные аккаунты и их API-токены (только для администраторов)
+
+use crate::api::extractors::AdminUser;
+use crate::api::middleware::ErrorResponse;
+use crate::api::state::AppState;
+use crate::api::user::issue_api_token;
+use crate::db::store::{ServiceAccountManager, TokenManager, UserManager};
+use crate::error::Error;
+use crate::models::{APIToken, APITokenCreate, ServiceAccount, ServiceAccountCreate, User};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use base64::Engine;
+use rand::RngCore;
+use std::sync::Arc;
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+fn store_error(e: Error) -> ApiError {
+    let (status, resp) = ErrorResponse::from_crate_error(&e);
+    (status, Json(resp))
+}
+
+/// Получить сервисные аккаунты
+///
+/// GET /api/service-accounts
+pub async fn get_service_accounts(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
+) -> Result<Json<Vec<ServiceAccount>>, ApiError> {
+    let accounts = state
+        .store
+        .get_service_accounts()
+        .await
+        .map_err(store_error)?;
+    Ok(Json(accounts))
+}
+
+/// Создать сервисный аккаунт
+///
+/// POST /api/service-accounts
+/// Создаёт внешнего пользователя со случайным паролем: войти под ним нельзя,
+/// работать от его имени можно только через выпущенные API-токены.
+pub async fn create_service_account(
+    State(state): State<Arc<AppState>>,
+    admin: AdminUser,
+    Json(payload): Json<ServiceAccountCreate>,
+) -> Result<(StatusCode, Json<ServiceAccount>), ApiError> {
+    let username = payload.username.trim().to_string();
+    if username.is_empty() {
+        let err = ErrorResponse::new("Имя пользователя обязательно").with_code("VALIDATION_ERROR");
+        return Err((StatusCode::BAD_REQUEST, Json(err)));
+    }
+
+    let mut password_bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut password_bytes);
+    let password = base64::engine::general_purpose::STANDARD.encode(password_bytes);
+
+    let user = state
+        .store
+        .create_user(
+            User {
+                id: 0,
+                created: chrono::Utc::now(),
+                name: payload.name.unwrap_or_else(|| username.clone()),
+                username,
+                email: String::new(),
+                password: String::new(),
+                admin: false,
+                external: true,
+                alert: false,
+                pro: false,
+                totp: None,
+                email_otp: None,
+            },
+            &password,
+        )
+        .await
+        .map_err(store_error)?;
+
+    let account = state
+        .store
+        .create_service_account(ServiceAccount {
+            id: 0,
+            user_id: user.id,
+            description: payload.description,
+            created_by: Some(admin.into_inner().user_id),
+            created: chrono::Utc::now(),
+            username: user.username.clone(),
+            name: user.name.clone(),
+        })
+        .await;
+
+    match account {
+        Ok(account) => Ok((StatusCode::CREATED, Json(account))),
+        Err(e) => {
+            // Не оставляем пользователя без аккаунта
+            if let Err(cleanup) = state.store.delete_user(user.id).await {
+                tracing::warn!("Failed to remove user {}: {}", user.id, cleanup);
+            }
+            Err(store_error(e))
+        }
+    }
+}
+
+/// Удалить сервисный аккаунт вместе с пользователем и токенами
+///
+/// DELETE /api/service-accounts/:id
+pub async fn delete_service_account(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .get_service_account(id)
+        .await
+        .map_err(store_error)?;
+    state
+        .store
+        .delete_service_account(id)
+        .await
+        .map_err(store_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Получить токены сервисного аккаунта
+///
+/// GET /api/service-accounts/:id/tokens
+pub async fn get_service_account_tokens(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<APIToken>>, ApiError> {
+    let account = state
+        .store
+        .get_service_account(id)
+        .await
+        .map_err(store_error)?;
+    let tokens = state
+        .store
+        .get_api_tokens(account.user_id)
+        .await
+        .map_err(store_error)?;
+    Ok(Json(tokens))
+}
+
+/// Выпустить токен сервисного аккаунта
+///
+/// POST /api/service-accounts/:id/tokens
+pub async fn create_service_account_token(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<APITokenCreate>,
+) -> Result<(StatusCode, Json<APIToken>), ApiError> {
+    let account = state
+        .store
+        .get_service_account(id)
+        .await
+        .map_err(store_error)?;
+    let token = issue_api_token(&state, account.user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+/// Отозвать токен сервисного аккаунта
+///
+/// DELETE /api/service-accounts/:id/tokens/:token_id
+pub async fn delete_service_account_token(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
+    Path((id, token_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiError> {
+    let account = state
+        .store
+        .get_service_account(id)
+        .await
+        .map_err(store_error)?;
+    state
+        .store
+        .delete_api_token(account.user_id, token_id)
+        .await
+        .map_err(store_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::create_app;
+    use crate::db::mock::MockStore;
+    use axum::body::Body;
+    use axum::http::{Method, Request, header};
+    use tower::ServiceExt;
+
+    fn request(method: Method, uri: &str, body: serde_json::Value) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(
+                header::AUTHORIZATION,
+                crate::api::auth_local::test_admin_bearer(),
+            )
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_service_account_token_lifecycle() {
+        let store = Arc::new(MockStore::new());
+        let app = create_app(store.clone()).await;
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                Method::POST,
+                "/api/service-accounts",
+                serde_json::json!({"username": "svc-ci", "description": "CI"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let account = store.get_service_accounts().await.unwrap().remove(0);
+        let user = store.get_user(account.user_id).await.unwrap();
+        assert!(user.external);
+        assert!(!user.admin);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                Method::POST,
+                &format!("/api/service-accounts/{}/tokens", account.id),
+                serde_json::json!({"scopes": ["run_tasks"], "expires_in_days": 30}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let tokens = store.get_api_tokens(account.user_id).await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert!(tokens[0].expires_at.is_some());
+
+        let response = app
+            .oneshot(request(
+                Method::DELETE,
+                &format!("/api/service-accounts/{}", account.id),
+                serde_json::Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(
+            store
+                .get_api_tokens(account.user_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
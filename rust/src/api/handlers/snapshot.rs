@@ -122,6 +122,7 @@ pub async fn rollback_snapshot(
         environment_id: snap.environment_id,
         params: None,
         environment: None,
+        priority: None,
     };
 
     match store.create_task(task).await {
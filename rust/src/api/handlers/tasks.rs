@@ -4,15 +4,11 @@
 
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
-use crate::db::store::{
-    EnvironmentManager, InventoryManager, ProjectStore, RepositoryManager, TaskManager,
-    TemplateManager,
-};
-use crate::db_lib::AccessKeyInstallerImpl;
+use crate::db::store::{ProjectStore, TaskManager};
 use crate::error::Error;
-use crate::models::{Environment, Inventory, Repository, Task, TaskOutput, TaskWithTpl};
-use crate::services::local_job::LocalJob;
-use crate::services::task_logger::{BasicLogger, LogListener, TaskLogger, TaskStatus};
+use crate::models::{Task, TaskWithTpl};
+use crate::services::task_execution;
+use crate::services::task_logger::TaskStatus;
 use axum::{
     Json,
     extract::{Path, State},
@@ -20,7 +16,7 @@ use axum::{
 };
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 /// Получить все активные задачи всех проектов
 ///
@@ -117,6 +113,7 @@ pub async fn create_task(
         repository_id: payload.repository_id,
         environment_id: payload.environment_id,
         params: None,
+        priority: payload.priority,
     };
 
     let created: Result<Task, Error> = state.store.create_task(task).await;
@@ -127,13 +124,10 @@ pub async fn create_task(
             Json(ErrorResponse::new(e.to_string())),
         )
     })?;
+    crate::plugins::emit_task(crate::plugins::HookType::TaskAfterCreate, &created);
 
-    // Запускаем выполнение задачи в фоне
-    let task_state = state.clone();
-    let task_to_run = created.clone();
-    tokio::spawn(async move {
-        execute_task_background(task_state, task_to_run).await;
-    });
+    // Задачу забирает пул задач: слоты групп, хуки, гейт плана и таймаут
+    task_execution::wake_dispatcher();
 
     Ok((StatusCode::CREATED, Json(created)))
 }
@@ -201,237 +195,6 @@ pub async fn delete_task(
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Выполняет задачу в фоновом потоке
-async fn execute_task_background(state: Arc<AppState>, mut task: Task) {
-    println!(
-        "[task_runner] Starting task {} (template {})",
-        task.id, task.template_id
-    );
-    let store = &state.store;
-
-    match store
-        .update_task_status(task.project_id, task.id, TaskStatus::Running)
-        .await
-    {
-        Ok(()) => println!("[task_runner] task {} status → Running", task.id),
-        Err(e) => println!("[task_runner] task {} failed to set Running: {e}", task.id),
-    }
-
-    let template = match store.get_template(task.project_id, task.template_id).await {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!(
-                "[task_runner] task {}: failed to get template: {e}",
-                task.id
-            );
-            let _ = store
-                .update_task_status(task.project_id, task.id, TaskStatus::Error)
-                .await;
-            return;
-        }
-    };
-
-    let inventory_id = task.inventory_id.or(template.inventory_id);
-    let inventory = match inventory_id {
-        Some(id) => store
-            .get_inventory(task.project_id, id)
-            .await
-            .unwrap_or_default(),
-        None => Inventory::default(),
-    };
-
-    let repository_id = task.repository_id.or(template.repository_id);
-    let repository = match repository_id {
-        Some(id) => store
-            .get_repository(task.project_id, id)
-            .await
-            .unwrap_or_default(),
-        None => Repository::default(),
-    };
-
-    let environment_id = task.environment_id.or(template.environment_id);
-    let environment = match environment_id {
-        Some(id) => store
-            .get_environment(task.project_id, id)
-            .await
-            .unwrap_or_default(),
-        None => Environment::default(),
-    };
-
-    let log_buffer: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-    let buf_clone = log_buffer.clone();
-    let logger = Arc::new(BasicLogger::new());
-    logger.add_log_listener(Box::new(move |_time, msg| {
-        let _ = buf_clone.lock().map(|mut v| v.push(msg));
-    }));
-
-    let work_dir =
-        std::env::temp_dir().join(format!("semaphore_task_{}_{}", task.project_id, task.id));
-    let tmp_dir = work_dir.join("tmp");
-
-    if let Err(e) = tokio::fs::create_dir_all(&tmp_dir).await {
-        eprintln!(
-            "[task_runner] task {}: failed to create workdir: {e}",
-            task.id
-        );
-        let _ = store
-            .update_task_status(task.project_id, task.id, TaskStatus::Error)
-            .await;
-        return;
-    }
-
-    let key_installer = AccessKeyInstallerImpl::new();
-    let mut job = LocalJob::new(
-        task.clone(),
-        template,
-        inventory,
-        repository,
-        environment,
-        logger,
-        key_installer,
-        work_dir,
-        tmp_dir,
-    );
-
-    job.store =
-        Some(Arc::new(state.store.clone()) as Arc<dyn crate::db::store::Store + Send + Sync>);
-    let result = job.run("runner", None, "default").await;
-    job.cleanup();
-
-    let log_lines: Vec<String> = log_buffer.lock().map(|v| v.clone()).unwrap_or_default();
-    for line in log_lines {
-        let output = TaskOutput {
-            id: 0,
-            task_id: task.id,
-            project_id: task.project_id,
-            time: Utc::now(),
-            output: line,
-            stage_id: None,
-        };
-        let _ = store.create_task_output(output).await;
-    }
-
-    task.end = Some(Utc::now());
-    match result {
-        Ok(()) => {
-            let _ = store
-                .update_task_status(task.project_id, task.id, TaskStatus::Success)
-                .await;
-            println!("[task_runner] task {} completed successfully", task.id);
-        }
-        Err(e) => {
-            eprintln!("[task_runner] task {} failed: {e}", task.id);
-            let _ = store
-                .update_task_status(task.project_id, task.id, TaskStatus::Error)
-                .await;
-        }
-    }
-}
-
-/// Выполняет задачу с переданными параметрами и возвращает результат
-/// Используется workflow executor для выполнения узлов DAG
-pub async fn execute_task_background_with_template(
-    state: Arc<AppState>,
-    task: Task,
-    template: crate::models::template::Template,
-    inventory: Inventory,
-    repository: Repository,
-    environment: Environment,
-) -> TaskStatus {
-    use crate::db_lib::AccessKeyInstallerImpl;
-    use crate::services::local_job::LocalJob;
-    use crate::services::task_logger::BasicLogger;
-    use std::sync::Mutex;
-
-    println!(
-        "[workflow_task] Starting task {} (template {})",
-        task.id, task.template_id
-    );
-    let store = &state.store;
-
-    match store
-        .update_task_status(task.project_id, task.id, TaskStatus::Running)
-        .await
-    {
-        Ok(()) => println!("[workflow_task] task {} status → Running", task.id),
-        Err(e) => println!(
-            "[workflow_task] task {} failed to set Running: {e}",
-            task.id
-        ),
-    }
-
-    let log_buffer: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-    let buf_clone = log_buffer.clone();
-    let logger = Arc::new(BasicLogger::new());
-    logger.add_log_listener(Box::new(move |_time, msg| {
-        let _ = buf_clone.lock().map(|mut v| v.push(msg));
-    }));
-
-    let work_dir =
-        std::env::temp_dir().join(format!("semaphore_task_{}_{}", task.project_id, task.id));
-    let tmp_dir = work_dir.join("tmp");
-
-    if let Err(e) = tokio::fs::create_dir_all(&tmp_dir).await {
-        eprintln!(
-            "[workflow_task] task {}: failed to create workdir: {e}",
-            task.id
-        );
-        let _ = store
-            .update_task_status(task.project_id, task.id, TaskStatus::Error)
-            .await;
-        return TaskStatus::Error;
-    }
-
-    let key_installer = AccessKeyInstallerImpl::new();
-    let mut job = LocalJob::new(
-        task.clone(),
-        template,
-        inventory,
-        repository,
-        environment,
-        logger,
-        key_installer,
-        work_dir,
-        tmp_dir,
-    );
-
-    job.store =
-        Some(Arc::new(state.store.clone()) as Arc<dyn crate::db::store::Store + Send + Sync>);
-    let result = job.run("runner", None, "default").await;
-    job.cleanup();
-
-    // Сохранить логи
-    let log_lines: Vec<String> = log_buffer.lock().map(|v| v.clone()).unwrap_or_default();
-    for line in log_lines {
-        let output = crate::models::task::TaskOutput {
-            id: 0,
-            task_id: task.id,
-            project_id: task.project_id,
-            time: Utc::now(),
-            output: line,
-            stage_id: None,
-        };
-        let _ = store.create_task_output(output).await;
-    }
-
-    match result {
-        Ok(()) => {
-            let _ = store
-                .update_task_status(task.project_id, task.id, TaskStatus::Success)
-                .await;
-            println!("[workflow_task] task {} completed successfully", task.id);
-            TaskStatus::Success
-        }
-        Err(e) => {
-            eprintln!("[workflow_task] task {} failed: {e}", task.id);
-            let _ = store
-                .update_task_status(task.project_id, task.id, TaskStatus::Error)
-                .await;
-            TaskStatus::Error
-        }
-    }
-}
-
 // ============================================================================
 // Types
 // ============================================================================
@@ -458,6 +221,9 @@ pub struct TaskCreatePayload {
     pub repository_id: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub environment_id: Option<i32>,
+    /// Приоритет в очереди (по умолчанию — приоритет шаблона)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
 }
 
 // ============================================================================
@@ -513,6 +279,7 @@ mod tests {
             inventory_id: None,
             repository_id: None,
             environment_id: None,
+            priority: None,
         };
         let json = serde_json::to_string(&payload).unwrap();
         assert!(!json.contains("playbook"));
@@ -534,6 +301,7 @@ mod tests {
             inventory_id: Some(2),
             repository_id: Some(1),
             environment_id: Some(4),
+            priority: None,
         };
         let json = serde_json::to_string(&payload).unwrap();
         assert!(json.contains("\"template_id\":5"));
@@ -558,6 +326,7 @@ mod tests {
             inventory_id: None,
             repository_id: Some(3),
             environment_id: Some(5),
+            priority: None,
         };
         let json = serde_json::to_string(&original).unwrap();
         let restored: TaskCreatePayload = serde_json::from_str(&json).unwrap();
@@ -634,6 +403,7 @@ mod tests {
             inventory_id: None,
             repository_id: None,
             environment_id: None,
+            priority: None,
         };
         let debug_str = format!("{:?}", payload);
         assert!(debug_str.contains("TaskCreatePayload"));
@@ -662,6 +432,7 @@ mod tests {
             inventory_id: Some(-3),
             repository_id: None,
             environment_id: None,
+            priority: None,
         };
         let json = serde_json::to_string(&payload).unwrap();
         let restored: TaskCreatePayload = serde_json::from_str(&json).unwrap();
@@ -710,6 +481,7 @@ mod tests {
             inventory_id: None,
             repository_id: None,
             environment_id: None,
+            priority: None,
         };
         let json = serde_json::to_string(&payload).unwrap();
         let restored: TaskCreatePayload = serde_json::from_str(&json).unwrap();
@@ -766,6 +538,7 @@ mod tests {
             inventory_id: Some(3),
             repository_id: Some(4),
             environment_id: Some(5),
+            priority: None,
         };
         let json = serde_json::to_string(&payload).unwrap();
         assert!(json.contains("playbook"));
@@ -778,4 +551,102 @@ mod tests {
         assert!(json.contains("repository_id"));
         assert!(json.contains("environment_id"));
     }
+
+    #[tokio::test]
+    async fn test_create_task_runs_template_hooks_through_job_pool() {
+        use crate::db::mock::MockStore;
+        use crate::db::store::{Store, TemplateManager};
+        use crate::models::Template;
+        use crate::services::runners::JobPool;
+
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(MockStore::new());
+        let tpl = Template {
+            id: 1,
+            project_id: 1,
+            pre_template_id: Some(200),
+            fail_template_id: Some(300),
+            ..Default::default()
+        };
+        store.create_template(tpl).await.unwrap();
+        let state = Arc::new(AppState::new(
+            store.clone(),
+            crate::config::Config::default(),
+            None,
+        ));
+        let payload: TaskCreatePayload = serde_json::from_str(r#"{"template_id": 1}"#).unwrap();
+
+        let (status, Json(created)) = create_task(State(state), Path(1), Json(payload))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let pool = Arc::new(JobPool::new(store.clone()));
+        let runner = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run().await }
+        });
+        let mut links = Vec::new();
+        for _ in 0..100 {
+            let task = store.get_task(1, created.id).await.unwrap();
+            links = store.get_task_hook_links(1, created.id).await.unwrap();
+            if task.status.is_finished() && links.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        pool.shutdown().await;
+        runner.abort();
+
+        let task = store.get_task(1, created.id).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Error);
+        assert!(task.message.unwrap().contains("Pre-hook"));
+        let types: Vec<&str> = links.iter().map(|l| l.hook_type.as_str()).collect();
+        assert_eq!(
+            types,
+            vec![task_execution::HOOK_PRE, task_execution::HOOK_FAIL]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_task_is_claimed_by_this_node() {
+        use crate::db::mock::MockStore;
+        use crate::db::store::{ClusterNodeManager, Store, TemplateManager};
+        use crate::models::Template;
+        use crate::services::task_execution::{Dispatch, dispatch_task};
+
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                id: 1,
+                project_id: 1,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let state = Arc::new(AppState::new(
+            store.clone(),
+            crate::config::Config::default(),
+            None,
+        ));
+        let payload: TaskCreatePayload = serde_json::from_str(r#"{"template_id": 1}"#).unwrap();
+        let (_, Json(created)) = create_task(State(state), Path(1), Json(payload))
+            .await
+            .unwrap();
+
+        // Однопоточный рантайм теста: задача забрана, но ещё не выполняется
+        let Dispatch::Started(handle) = dispatch_task(store.clone(), created.clone()).await else {
+            panic!("task was not dispatched");
+        };
+        let task = store.get_task(1, created.id).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Running);
+        assert!(task.start.is_some());
+
+        // Задача закреплена за узлом: без его отметок она считается потерянной,
+        // хотя началась только что
+        handle.abort();
+        assert_eq!(
+            store.fail_orphaned_tasks(60, "lost").await.unwrap(),
+            vec![created.id]
+        );
+    }
 }
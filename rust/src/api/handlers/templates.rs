@@ -2,13 +2,18 @@
 //!
 //! Обработчики запросов для управления шаблонами
 
+use crate::api::extractors::AuthUser;
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
 use crate::db::store::{ProjectStore, TaskManager, TemplateManager};
 use crate::error::Error;
-use crate::models::Template;
 use crate::models::template::{TemplateApp, TemplateType};
+use crate::models::{Permission, Template};
+use crate::services::project_access;
+use crate::services::promotion::{BuildVersion, deploy_build, list_build_versions};
+use crate::services::task_execution::validate_template_hooks;
 use crate::services::task_logger::TaskStatus;
+use crate::services::template_inheritance::{EffectiveTemplate, resolve_template};
 use axum::{
     Json,
     extract::{Path, State},
@@ -86,14 +91,26 @@ pub async fn create_template(
         post_template_id: payload.post_template_id,
         fail_template_id: payload.fail_template_id,
         deploy_environment_id: payload.deploy_environment_id,
+        priority: payload.priority,
+        concurrency_group: payload.concurrency_group,
+        timeout_secs: payload.timeout_secs.filter(|t| *t > 0),
     };
 
+    validate_template_hooks(state.store.store(), &template)
+        .await
+        .map_err(service_error)?;
+
     let created = state.store.create_template(template).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new(e.to_string())),
         )
     })?;
+    crate::plugins::emit_template(
+        crate::plugins::HookType::TemplateAfterCreate,
+        &created,
+        None,
+    );
 
     Ok((StatusCode::CREATED, Json(created)))
 }
@@ -123,6 +140,90 @@ pub async fn get_template(
     Ok(Json(template))
 }
 
+/// Эффективная конфигурация шаблона с учётом наследования
+///
+/// GET /api/projects/:project_id/templates/:template_id/effective
+pub async fn get_effective_template(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, template_id)): Path<(i32, i32)>,
+) -> Result<Json<EffectiveTemplate>, (StatusCode, Json<ErrorResponse>)> {
+    let store = state.store.as_arc();
+    let effective = resolve_template(store.as_ref(), project_id, template_id)
+        .await
+        .map_err(|e| {
+            let status = match e {
+                Error::NotFound(_) => StatusCode::NOT_FOUND,
+                Error::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(ErrorResponse::new(e.to_string())))
+        })?;
+
+    Ok(Json(effective))
+}
+
+/// Успешные сборки, доступные для развёртывания deploy-шаблоном
+///
+/// GET /api/projects/:project_id/templates/:template_id/builds
+pub async fn get_template_builds(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, template_id)): Path<(i32, i32)>,
+) -> Result<Json<Vec<BuildVersion>>, (StatusCode, Json<ErrorResponse>)> {
+    let store = state.store.as_arc();
+    let builds = async {
+        let deploy = store.get_template(project_id, template_id).await?;
+        list_build_versions(store.as_ref(), &deploy).await
+    }
+    .await
+    .map_err(service_error)?;
+
+    Ok(Json(builds))
+}
+
+/// Развернуть версию сборки deploy-шаблоном
+///
+/// POST /api/projects/:project_id/templates/:template_id/deploy
+pub async fn deploy_template_build(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((project_id, template_id)): Path<(i32, i32)>,
+    Json(payload): Json<DeployBuildPayload>,
+) -> Result<(StatusCode, Json<crate::models::Task>), (StatusCode, Json<ErrorResponse>)> {
+    let store = state.store.as_arc();
+    let task = async {
+        project_access::require_permission(
+            store.as_ref(),
+            &auth.caller(),
+            project_id,
+            Some(template_id),
+            Some(Permission::RunTasks),
+        )
+        .await?;
+        let deploy = store.get_template(project_id, template_id).await?;
+        deploy_build(
+            store.clone(),
+            &deploy,
+            payload.build_task_id,
+            Some(auth.user_id),
+        )
+        .await
+    }
+    .await
+    .map_err(service_error)?;
+
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+fn service_error(e: Error) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match e {
+        Error::NotFound(_) => StatusCode::NOT_FOUND,
+        Error::Validation(_) => StatusCode::BAD_REQUEST,
+        Error::Forbidden(_) => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(ErrorResponse::new(e.to_string())))
+}
+
 /// Обновить шаблон
 ///
 /// PUT /api/projects/:project_id/templates/:template_id
@@ -218,6 +319,15 @@ pub async fn update_template(
     if let Some(v) = payload.vaults {
         template.vaults = Some(v);
     }
+    if let Some(v) = payload.priority {
+        template.priority = v;
+    }
+    if let Some(v) = payload.concurrency_group {
+        template.concurrency_group = Some(v).filter(|g| !g.is_empty());
+    }
+    if let Some(v) = payload.timeout_secs {
+        template.timeout_secs = Some(v).filter(|t| *t > 0);
+    }
 
     state.store.update_template(template).await.map_err(|e| {
         (
@@ -351,6 +461,13 @@ pub struct TemplateCreatePayload {
     pub fail_template_id: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deploy_environment_id: Option<i32>,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency_group: Option<String>,
+    /// Таймаут задач шаблона в секундах
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<i32>,
 }
 
 /// Payload для обновления шаблона
@@ -402,6 +519,20 @@ pub struct TemplateUpdatePayload {
     pub survey_vars: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vaults: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// Пустая строка убирает шаблон из группы
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency_group: Option<String>,
+    /// Таймаут задач в секундах; `0` возвращает глобальное значение
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<i32>,
+}
+
+/// Payload для развёртывания сборки
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployBuildPayload {
+    pub build_task_id: i32,
 }
 
 // ============================================================================
@@ -551,6 +682,9 @@ mod tests {
             post_template_id: Some(8),
             fail_template_id: Some(9),
             deploy_environment_id: Some(11),
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let json = serde_json::to_string(&original).unwrap();
         let restored: TemplateCreatePayload = serde_json::from_str(&json).unwrap();
@@ -594,6 +728,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let json = serde_json::to_string(&payload).unwrap();
         assert!(!json.contains("inventory_id"));
@@ -628,6 +765,9 @@ mod tests {
             task_params: Some(serde_json::json!({"key": "val"})),
             survey_vars: None,
             vaults: None,
+            priority: None,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let json = serde_json::to_string(&original).unwrap();
         let restored: TemplateUpdatePayload = serde_json::from_str(&json).unwrap();
@@ -725,6 +865,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let debug_str = format!("{:?}", payload);
         assert!(debug_str.contains("TemplateCreatePayload"));
@@ -757,6 +900,9 @@ mod tests {
             task_params: None,
             survey_vars: None,
             vaults: None,
+            priority: None,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let debug_str = format!("{:?}", payload);
         assert!(debug_str.contains("TemplateUpdatePayload"));
@@ -812,4 +958,76 @@ mod tests {
         let p2: TemplateUpdatePayload = serde_json::from_str(json).unwrap();
         assert_eq!(p1.name, p2.name);
     }
+
+    #[tokio::test]
+    async fn test_deploy_template_build_requires_run_tasks_and_uses_caller() {
+        use crate::db::mock::MockStore;
+        use crate::db::store::{ProjectAccessManager, Store};
+        use crate::models::Task;
+
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(MockStore::new());
+        for tpl in [
+            Template {
+                id: 1,
+                project_id: 1,
+                r#type: TemplateType::Build,
+                ..Default::default()
+            },
+            Template {
+                id: 2,
+                project_id: 1,
+                r#type: TemplateType::Deploy,
+                build_template_id: Some(1),
+                ..Default::default()
+            },
+        ] {
+            store.create_template(tpl).await.unwrap();
+        }
+        store
+            .create_task(Task {
+                id: 10,
+                project_id: 1,
+                template_id: 1,
+                status: TaskStatus::Success,
+                version: Some("1.2.0".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store.set_project_user_role(1, 5, "guest").await.unwrap();
+        store
+            .set_project_user_role(1, 6, "task_runner")
+            .await
+            .unwrap();
+        let state = Arc::new(AppState::new(
+            store.clone(),
+            crate::config::Config::default(),
+            None,
+        ));
+        let user = |user_id| AuthUser {
+            user_id,
+            username: format!("user{user_id}"),
+            email: String::new(),
+            admin: false,
+            jti: String::new(),
+            exp: 0,
+            api_token: None,
+        };
+        let deploy = |user_id| {
+            deploy_template_build(
+                State(state.clone()),
+                user(user_id),
+                Path((1, 2)),
+                Json(serde_json::from_str(r#"{"build_task_id": 10, "user_id": 1}"#).unwrap()),
+            )
+        };
+
+        let (status, _) = deploy(5).await.unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, Json(task)) = deploy(6).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(task.template_id, 2);
+        assert_eq!(task.user_id, Some(6));
+    }
 }
@@ -373,6 +373,8 @@ mod tests {
         let log = WebhookLog {
             id: 100,
             webhook_id: 5,
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "task_failed".to_string(),
             status_code: Some(500),
             success: false,
@@ -1,11 +1,13 @@
 //! Handlers для Workflow DAG API
 
+use crate::api::extractors::AuthUser;
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
 use crate::db::store::WorkflowManager;
 use crate::models::workflow::{
-    WorkflowCreate, WorkflowEdgeCreate, WorkflowFull, WorkflowNodeCreate, WorkflowNodeUpdate,
-    WorkflowUpdate,
+    NODE_TYPE_APPROVAL, NODE_TYPE_TEMPLATE, WorkflowApprovalDecision, WorkflowCreate,
+    WorkflowEdgeCreate, WorkflowFull, WorkflowNodeCreate, WorkflowNodeInput, WorkflowNodeUpdate,
+    WorkflowRun, WorkflowRunCreate, WorkflowRunDetails, WorkflowUpdate,
 };
 use axum::{
     Json,
@@ -14,6 +16,71 @@ use axum::{
 };
 use std::sync::Arc;
 
+/// Проверяет входные переменные узла: имена и ключи заданы, имена не повторяются
+fn validate_node_inputs(
+    inputs: &[WorkflowNodeInput],
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let mut names = std::collections::HashSet::new();
+    for input in inputs {
+        let message = if input.name.trim().is_empty() || input.key.trim().is_empty() {
+            "Input name and key are required".to_string()
+        } else if !names.insert(input.name.as_str()) {
+            format!("Duplicate input '{}'", input.name)
+        } else {
+            continue;
+        };
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(message).with_code("VALIDATION_ERROR")),
+        ));
+    }
+    Ok(())
+}
+
+/// Проверяет тип узла: "template" или "approval"
+fn validate_node_type(node_type: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if [NODE_TYPE_TEMPLATE, NODE_TYPE_APPROVAL].contains(&node_type) {
+        return Ok(());
+    }
+    Err((
+        StatusCode::BAD_REQUEST,
+        Json(
+            ErrorResponse::new(format!(
+                "Invalid node_type '{}'. Must be one of: template, approval",
+                node_type
+            ))
+            .with_code("VALIDATION_ERROR"),
+        ),
+    ))
+}
+
+/// Запуск workflow `id` в проекте; 404, если запуск относится к другому workflow
+async fn load_run(
+    state: &AppState,
+    project_id: i32,
+    id: i32,
+    run_id: i32,
+) -> Result<WorkflowRun, (StatusCode, Json<ErrorResponse>)> {
+    match state.store.get_workflow_run(run_id, project_id).await {
+        Ok(run) if run.workflow_id == id => Ok(run),
+        Ok(_) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Workflow run not found".to_string())),
+        )),
+        Err(e) => {
+            let (status, resp) = ErrorResponse::from_crate_error(&e);
+            Err((status, Json(resp)))
+        }
+    }
+}
+
+fn conflict(message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::CONFLICT,
+        Json(ErrorResponse::new(message.to_string()).with_code("CONFLICT")),
+    )
+}
+
 /// GET /api/project/{project_id}/workflows
 pub async fn get_workflows(
     State(state): State<Arc<AppState>>,
@@ -141,6 +208,8 @@ pub async fn add_workflow_node(
     (StatusCode, Json<crate::models::workflow::WorkflowNode>),
     (StatusCode, Json<ErrorResponse>),
 > {
+    validate_node_inputs(&payload.inputs)?;
+    validate_node_type(&payload.node_type)?;
     // Verify workflow belongs to project
     state
         .store
@@ -171,6 +240,8 @@ pub async fn update_workflow_node(
     Path((project_id, id, node_id)): Path<(i32, i32, i32)>,
     Json(payload): Json<WorkflowNodeUpdate>,
 ) -> Result<Json<crate::models::workflow::WorkflowNode>, (StatusCode, Json<ErrorResponse>)> {
+    validate_node_inputs(&payload.inputs)?;
+    validate_node_type(&payload.node_type)?;
     // Verify workflow belongs to project
     state
         .store
@@ -298,9 +369,12 @@ pub async fn delete_workflow_edge(
 
 /// POST /api/project/{project_id}/workflows/{id}/run
 /// Запускает workflow DAG execution
+///
+/// Тело запроса необязательно: `{"params": {...}}` — входные параметры запуска.
 pub async fn run_workflow(
     State(state): State<Arc<AppState>>,
     Path((project_id, id)): Path<(i32, i32)>,
+    payload: Option<Json<WorkflowRunCreate>>,
 ) -> Result<
     (StatusCode, Json<crate::models::workflow::WorkflowRun>),
     (StatusCode, Json<ErrorResponse>),
@@ -318,14 +392,14 @@ pub async fn run_workflow(
         })?;
 
     // Запустить workflow executor
-    let run = crate::services::workflow_executor::run_workflow(state.clone(), id, project_id)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(e.to_string())),
-            )
-        })?;
+    let params = payload.map(|Json(p)| p.params).unwrap_or_default();
+    let run =
+        crate::services::workflow_executor::run_workflow(state.clone(), id, project_id, params)
+            .await
+            .map_err(|e| {
+                let (status, resp) = ErrorResponse::from_crate_error(&e);
+                (status, Json(resp))
+            })?;
 
     Ok((StatusCode::ACCEPTED, Json(run)))
 }
@@ -359,6 +433,105 @@ pub async fn get_workflow_runs(
     Ok(Json(runs))
 }
 
+/// GET /api/project/{project_id}/workflows/{id}/runs/{run_id}
+/// Запуск вместе с состояниями узлов
+pub async fn get_workflow_run(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, id, run_id)): Path<(i32, i32, i32)>,
+) -> Result<Json<WorkflowRunDetails>, (StatusCode, Json<ErrorResponse>)> {
+    let run = load_run(&state, project_id, id, run_id).await?;
+    let nodes = state
+        .store
+        .get_workflow_run_nodes(run_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+        })?;
+    Ok(Json(WorkflowRunDetails { run, nodes }))
+}
+
+/// POST /api/project/{project_id}/workflows/{id}/runs/{run_id}/cancel
+/// Отменяет запуск и останавливает задачи выполняющихся узлов
+pub async fn cancel_workflow_run(
+    State(state): State<Arc<AppState>>,
+    Path((project_id, id, run_id)): Path<(i32, i32, i32)>,
+) -> Result<Json<WorkflowRun>, (StatusCode, Json<ErrorResponse>)> {
+    let run = load_run(&state, project_id, id, run_id).await?;
+    if matches!(run.status.as_str(), "success" | "failed" | "cancelled") {
+        return Err(conflict("Workflow run is already finished"));
+    }
+    crate::services::workflow_executor::cancel_workflow_run(&state, &run)
+        .await
+        .map_err(|e| {
+            let (status, resp) = ErrorResponse::from_crate_error(&e);
+            (status, Json(resp))
+        })?;
+    let run = load_run(&state, project_id, id, run_id).await?;
+    Ok(Json(run))
+}
+
+/// Одобрение или отклонение узла подтверждения
+async fn decide_approval(
+    state: &AppState,
+    auth: &AuthUser,
+    (project_id, id, run_id, node_id): (i32, i32, i32, i32),
+    approved: bool,
+    decision: WorkflowApprovalDecision,
+) -> Result<Json<WorkflowRunDetails>, (StatusCode, Json<ErrorResponse>)> {
+    let run = load_run(state, project_id, id, run_id).await?;
+    let decided = state
+        .store
+        .decide_workflow_approval(run_id, node_id, approved, auth.user_id, decision.comment)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+        })?;
+    if !decided {
+        return Err(conflict("Node is not waiting for approval"));
+    }
+    let nodes = state
+        .store
+        .get_workflow_run_nodes(run_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+        })?;
+    Ok(Json(WorkflowRunDetails { run, nodes }))
+}
+
+/// POST /api/project/{project_id}/workflows/{id}/runs/{run_id}/nodes/{node_id}/approve
+///
+/// Тело необязательно: `{"comment": "..."}`.
+pub async fn approve_workflow_node(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(ids): Path<(i32, i32, i32, i32)>,
+    payload: Option<Json<WorkflowApprovalDecision>>,
+) -> Result<Json<WorkflowRunDetails>, (StatusCode, Json<ErrorResponse>)> {
+    let decision = payload.map(|Json(d)| d).unwrap_or_default();
+    decide_approval(&state, &auth, ids, true, decision).await
+}
+
+/// POST /api/project/{project_id}/workflows/{id}/runs/{run_id}/nodes/{node_id}/reject
+pub async fn reject_workflow_node(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(ids): Path<(i32, i32, i32, i32)>,
+    payload: Option<Json<WorkflowApprovalDecision>>,
+) -> Result<Json<WorkflowRunDetails>, (StatusCode, Json<ErrorResponse>)> {
+    let decision = payload.map(|Json(d)| d).unwrap_or_default();
+    decide_approval(&state, &auth, ids, false, decision).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -376,12 +549,20 @@ mod tests {
         create_app(store).await
     }
 
+    /// Запрос от имени администратора: маршруты проекта требуют аутентификации
+    fn authorized() -> axum::http::request::Builder {
+        Request::builder().header(
+            axum::http::header::AUTHORIZATION,
+            crate::api::auth_local::test_admin_bearer(),
+        )
+    }
+
     #[tokio::test]
     async fn test_get_workflows_returns_empty_list() {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .uri("/api/project/1/workflows")
                     .body(Body::empty())
                     .unwrap(),
@@ -397,7 +578,7 @@ mod tests {
         let body = json!({"name": "", "description": "test"});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/workflows")
                     .header("Content-Type", "application/json")
@@ -415,7 +596,7 @@ mod tests {
         let body = json!({"name": "test-workflow", "description": "A test workflow"});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/workflows")
                     .header("Content-Type", "application/json")
@@ -432,7 +613,7 @@ mod tests {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .uri("/api/project/1/workflows/999")
                     .body(Body::empty())
                     .unwrap(),
@@ -448,7 +629,7 @@ mod tests {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("DELETE")
                     .uri("/api/project/1/workflows/1")
                     .body(Body::empty())
@@ -465,7 +646,7 @@ mod tests {
         let body = json!({"name": ""});
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("PUT")
                     .uri("/api/project/1/workflows/1")
                     .header("Content-Type", "application/json")
@@ -487,7 +668,7 @@ mod tests {
         });
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/workflows/1/edges")
                     .header("Content-Type", "application/json")
@@ -521,7 +702,7 @@ mod tests {
             });
             let resp = app
                 .oneshot(
-                    Request::builder()
+                    authorized()
                         .method("POST")
                         .uri("/api/project/1/workflows/1/edges")
                         .header("Content-Type", "application/json")
@@ -545,7 +726,7 @@ mod tests {
         });
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("POST")
                     .uri("/api/project/1/workflows/999/nodes")
                     .header("Content-Type", "application/json")
@@ -566,7 +747,7 @@ mod tests {
         let app = create_test_app().await;
         let resp = app
             .oneshot(
-                Request::builder()
+                authorized()
                     .method("DELETE")
                     .uri("/api/project/1/workflows/1/edges/1")
                     .body(Body::empty())
@@ -577,4 +758,44 @@ mod tests {
         // MockStore may return 404 for non-existent edge, or 204 if it's forgiving
         assert!(resp.status() == StatusCode::NO_CONTENT || resp.status() == StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_add_workflow_node_invalid_type() {
+        let app = create_test_app().await;
+        let body = json!({
+            "template_id": 1,
+            "name": "Gate",
+            "pos_x": 0.0,
+            "pos_y": 0.0,
+            "node_type": "manual"
+        });
+        let resp = app
+            .oneshot(
+                authorized()
+                    .method("POST")
+                    .uri("/api/project/1/workflows/1/nodes")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_workflow_run_not_found() {
+        let app = create_test_app().await;
+        let resp = app
+            .oneshot(
+                authorized()
+                    .method("POST")
+                    .uri("/api/project/1/workflows/1/runs/5/cancel")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
 }
@@ -1,51 +1,89 @@
 //! API - Integration Handler
 //!
-//! Обработчики для интеграций (общие, не project-specific)
+//! Публичные приёмники входящих вызовов интеграций (`POST /api/integrations/{alias}`)
+//! и вебхуков GitHub/GitLab/Gitea (`POST /api/integrations/{alias}/{forge}`).
+//! Аутентификация выполняется самой интеграцией, а не сессией пользователя.
 
 use crate::api::middleware::ErrorResponse;
 use crate::api::state::AppState;
-use crate::db::store::RetrieveQueryParams;
-use crate::error::{Error, Result};
-use crate::models::Integration;
+use crate::error::Error;
+use crate::services::forge_webhook::{Forge, trigger_forge_webhook};
+use crate::services::integration::{IntegrationRequest, trigger_integration};
 use axum::{
     Json,
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
 };
 use std::sync::Arc;
 
-/// Получает все интеграции (глобальные)
-pub async fn get_integrations(
+/// Принимает входящий вызов интеграции по псевдониму
+///
+/// - `202 Accepted` с созданной задачей — матчеры совпали, задача поставлена в очередь;
+/// - `204 No Content` — матчеры не совпали;
+/// - `401` — не пройдена аутентификация интеграции;
+/// - `404` — псевдоним или интеграция не найдены.
+pub async fn receive_integration(
     State(state): State<Arc<AppState>>,
-    Query(_params): Query<RetrieveQueryParams>,
-) -> std::result::Result<Json<Vec<Integration>>, (StatusCode, Json<ErrorResponse>)> {
-    // В реальной реализации нужно получить глобальные интеграции
-    Ok(Json(vec![]))
-}
+    Path(alias): Path<String>,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+) -> std::result::Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let request = IntegrationRequest::from_http(method.as_str(), &headers, body.to_vec());
 
-/// Получает интеграцию по ID
-pub async fn get_integration(
-    State(state): State<Arc<AppState>>,
-    Path(integration_id): Path<i32>,
-) -> std::result::Result<Json<Integration>, (StatusCode, Json<ErrorResponse>)> {
-    // В реальной реализации нужно получить интеграцию из БД
-    Err((
-        StatusCode::NOT_FOUND,
-        Json(ErrorResponse::new("Integration not found".to_string())),
-    ))
+    match trigger_integration(state.store.as_arc(), &alias, &request).await {
+        Ok(Some(task)) => Ok((StatusCode::ACCEPTED, Json(task)).into_response()),
+        Ok(None) => Ok(StatusCode::NO_CONTENT.into_response()),
+        Err(Error::NotFound(_)) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Integration not found".to_string())),
+        )),
+        Err(Error::Unauthorized(msg)) => {
+            Err((StatusCode::UNAUTHORIZED, Json(ErrorResponse::new(msg))))
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(e.to_string())),
+        )),
+    }
 }
 
-// ============================================================================
-// Tests
-// ============================================================================
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Принимает push/PR вебхук GitHub, GitLab или Gitea по псевдониму интеграции
+///
+/// - `202 Accepted` со списком созданных задач;
+/// - `204 No Content` — событие не совпало ни с одним шаблоном;
+/// - `401` — неверная подпись вебхука;
+/// - `404` — неизвестный форж, псевдоним или интеграция.
+pub async fn receive_forge_webhook(
+    State(state): State<Arc<AppState>>,
+    Path((alias, forge)): Path<(String, String)>,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+) -> std::result::Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let Some(forge) = Forge::parse(&forge) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!("Unknown forge: {}", forge))),
+        ));
+    };
+    let request = IntegrationRequest::from_http(method.as_str(), &headers, body.to_vec());
 
-    #[test]
-    fn test_integration_handler() {
-        // Тест для проверки обработчиков интеграций
-        assert!(true);
+    match trigger_forge_webhook(state.store.as_arc(), &alias, forge, &request).await {
+        Ok(tasks) if tasks.is_empty() => Ok(StatusCode::NO_CONTENT.into_response()),
+        Ok(tasks) => Ok((StatusCode::ACCEPTED, Json(tasks)).into_response()),
+        Err(Error::NotFound(_)) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("Integration not found".to_string())),
+        )),
+        Err(Error::Unauthorized(msg)) => {
+            Err((StatusCode::UNAUTHORIZED, Json(ErrorResponse::new(msg))))
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(e.to_string())),
+        )),
     }
 }
@@ -4,6 +4,7 @@ use super::protocol::{McpRequest, McpResponse};
 use super::tools;
 use crate::api::extractors::AuthUser;
 use crate::api::state::AppState;
+use crate::services::project_access::Caller;
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
@@ -15,14 +16,14 @@ const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // ── POST /mcp ─────────────────────────────────────────────────────────────────
 
-/// Main MCP JSON-RPC 2.0 endpoint. Requires Bearer JWT auth.
+/// Main MCP JSON-RPC 2.0 endpoint. Requires Bearer JWT or API token auth.
 pub async fn mcp_endpoint(
     State(state): State<Arc<AppState>>,
-    _auth: AuthUser, // enforce authentication
+    auth: AuthUser,
     Json(req): Json<McpRequest>,
 ) -> impl IntoResponse {
     let id = req.id.clone();
-    let resp = handle(req, &state).await;
+    let resp = handle(req, &state, &auth.caller()).await;
     // Notification responses (Value::Null) → 204 No Content
     if resp == Value::Null {
         (StatusCode::NO_CONTENT, Json(Value::Null))
@@ -31,7 +32,7 @@ pub async fn mcp_endpoint(
     }
 }
 
-async fn handle(req: McpRequest, state: &Arc<AppState>) -> Value {
+async fn handle(req: McpRequest, state: &Arc<AppState>, caller: &Caller) -> Value {
     let id = req.id.clone();
     match req.method.as_str() {
         "initialize" => serde_json::to_value(McpResponse::ok(
@@ -60,7 +61,7 @@ async fn handle(req: McpRequest, state: &Arc<AppState>) -> Value {
                 .cloned()
                 .unwrap_or_else(|| Value::Object(Default::default()));
 
-            let result = tools::dispatch(&tool_name, &args, state).await;
+            let result = tools::dispatch(&tool_name, &args, state, caller).await;
             let content: Vec<Value> = result
                 .content
                 .iter()
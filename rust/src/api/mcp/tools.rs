@@ -3,7 +3,11 @@
 use super::protocol::{ToolContent, prop_bool, prop_int, prop_int_opt, prop_str, prop_str_opt};
 use crate::api::state::AppState;
 use crate::models::repository::RepositoryType;
-use crate::models::{Environment, Project, Repository, Schedule, Task};
+use crate::models::schedule::{MISFIRE_SKIP, SCHEDULE_TARGET_TEMPLATE};
+use crate::models::{
+    Environment, Permission, Project, ProjectUser, ProjectUserRole, Repository, Schedule, Task,
+};
+use crate::services::project_access::{self, Caller};
 use crate::services::task_logger::TaskStatus;
 use serde_json::{Value, json};
 use std::sync::Arc;
@@ -135,9 +139,10 @@ pub fn all_definitions() -> Vec<Value> {
             json!({
                 "type":"object",
                 "properties":{
+                    "project_id":prop_int("Project ID"),
                     "task_id":prop_int("Task ID"),
                     "last_n":prop_int_opt("Return only the last N lines (default: all)")
-                },"required":["task_id"]
+                },"required":["project_id","task_id"]
             }),
         ),
         tool(
@@ -392,17 +397,96 @@ fn tool(name: &str, description: &str, input_schema: Value) -> Value {
 
 // ── Dispatcher ───────────────────────────────────────────────────────────────
 
-pub async fn dispatch(name: &str, args: &Value, state: &Arc<AppState>) -> ToolResult {
-    match dispatch_inner(name, args, state).await {
+/// Who may call a tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolAccess {
+    /// Any authenticated user
+    User,
+    /// Global administrators only
+    Admin,
+    /// Members of `project_id`, optionally holding a permission
+    /// (resolved with template-level role overrides when `template_id` is set)
+    Project {
+        project_id: i32,
+        template_id: Option<i32>,
+        permission: Option<Permission>,
+    },
+}
+
+/// Access rule for a tool call; mirrors the permissions of the REST routes
+fn tool_access(name: &str, args: &Value) -> ToolAccess {
+    let i32_arg = |key: &str| args.get(key).and_then(Value::as_i64).map(|v| v as i32);
+    let project = |template_id, permission| ToolAccess::Project {
+        project_id: i32_arg("project_id").unwrap_or(0),
+        template_id,
+        permission,
+    };
+
+    match name {
+        "list_projects" | "create_project" | "get_mcp_settings" => ToolAccess::User,
+        "list_runners" | "toggle_runner" | "get_system_info" => ToolAccess::Admin,
+        "delete_project" => project(None, Some(Permission::ManageProject)),
+        "run_template" => project(i32_arg("template_id"), Some(Permission::RunTasks)),
+        "stop_task" => project(None, Some(Permission::RunTasks)),
+        "create_schedule" | "toggle_schedule" | "delete_schedule" | "create_repository"
+        | "delete_repository" | "create_environment" | "delete_environment"
+        | "ai_create_template" => project(None, Some(Permission::UpdateResources)),
+        "get_template" | "get_template_last_outputs" => project(i32_arg("template_id"), None),
+        _ => project(None, None),
+    }
+}
+
+pub async fn dispatch(
+    name: &str,
+    args: &Value,
+    state: &Arc<AppState>,
+    caller: &Caller,
+) -> ToolResult {
+    match authorize(name, args, state, caller).await {
+        Ok(Ok(())) => {}
+        Ok(Err(message)) => return ToolResult::error(message),
+        Err(e) => return ToolResult::error(format!("Tool error: {e}")),
+    }
+    match dispatch_inner(name, args, state, caller).await {
         Ok(r) => r,
         Err(e) => ToolResult::error(format!("Tool error: {e}")),
     }
 }
 
+async fn authorize(
+    name: &str,
+    args: &Value,
+    state: &Arc<AppState>,
+    caller: &Caller,
+) -> crate::error::Result<Result<(), String>> {
+    Ok(match tool_access(name, args) {
+        ToolAccess::User if name == "create_project" && !caller.allows_global_writes() => {
+            Err("Permission denied: API token scope does not allow this operation".into())
+        }
+        ToolAccess::User => Ok(()),
+        ToolAccess::Admin if caller.admin => Ok(()),
+        ToolAccess::Admin => Err("Permission denied: administrator privileges required".into()),
+        ToolAccess::Project {
+            project_id,
+            template_id,
+            permission,
+        } => project_access::check_permission(
+            state.store.store(),
+            caller,
+            project_id,
+            template_id,
+            permission,
+        )
+        .await?
+        .map_err(|denied| format!("Permission denied: {denied}")),
+    })
+}
+
 async fn dispatch_inner(
     name: &str,
     args: &Value,
     state: &Arc<AppState>,
+    caller: &Caller,
 ) -> anyhow::Result<ToolResult> {
     let store = state.store.store();
 
@@ -411,7 +495,12 @@ async fn dispatch_inner(
     match name {
         // ── Projects ─────────────────────────────────────────────────────────
         "list_projects" => {
-            let projects = store.get_projects(None).await?;
+            let projects = store
+                .get_projects((!caller.admin).then_some(caller.user_id))
+                .await?
+                .into_iter()
+                .filter(|p| caller.allows_project(p.id))
+                .collect::<Vec<_>>();
             Ok(ToolResult::ok(&json!(projects)))
         }
         "get_project" => {
@@ -433,6 +522,13 @@ async fn dispatch_inner(
                 default_secret_storage_id: None,
             };
             let created = store.create_project(project).await?;
+            store
+                .create_project_user(ProjectUser::new(
+                    created.id,
+                    caller.user_id,
+                    ProjectUserRole::Owner,
+                ))
+                .await?;
             Ok(ToolResult::ok(&json!(created)))
         }
         "delete_project" => {
@@ -485,6 +581,7 @@ async fn dispatch_inner(
                 repository_id: None,
                 environment_id: None,
                 params: None,
+                priority: None,
             };
             let created = store.create_task(task).await?;
             Ok(ToolResult::ok(&json!(created)))
@@ -509,7 +606,10 @@ async fn dispatch_inner(
             Ok(ToolResult::ok(&json!(task)))
         }
         "get_task_output" => {
+            let pid = i32_arg("project_id");
             let tid = i32_arg("task_id");
+            // The task must belong to the project the caller was authorized for
+            store.get_task(pid, tid).await?;
             let last_n = args
                 .get("last_n")
                 .and_then(Value::as_u64)
@@ -615,6 +715,15 @@ async fn dispatch_inner(
                 created: None,
                 run_at: None,
                 delete_after_run: false,
+                target_type: SCHEDULE_TARGET_TEMPLATE.to_string(),
+                target_id: None,
+                timezone: None,
+                misfire_policy: MISFIRE_SKIP.to_string(),
+                jitter_secs: 0,
+                blackout_windows: Vec::new(),
+                last_fired: None,
+                git_branch: None,
+                commit_paths: Vec::new(),
             };
             let created = store.create_schedule(schedule).await?;
             Ok(ToolResult::ok(&json!(created)))
@@ -965,6 +1074,39 @@ fn generate_template_suggestion(description: &str) -> serde_json::Value {
 mod tests {
     use super::*;
 
+    // ── Access rules ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_tool_access() {
+        let args = json!({"project_id": 3, "template_id": 7});
+        assert_eq!(tool_access("list_projects", &args), ToolAccess::User);
+        assert_eq!(tool_access("toggle_runner", &args), ToolAccess::Admin);
+        assert_eq!(
+            tool_access("run_template", &args),
+            ToolAccess::Project {
+                project_id: 3,
+                template_id: Some(7),
+                permission: Some(Permission::RunTasks),
+            }
+        );
+        assert_eq!(
+            tool_access("delete_repository", &args),
+            ToolAccess::Project {
+                project_id: 3,
+                template_id: None,
+                permission: Some(Permission::UpdateResources),
+            }
+        );
+        assert_eq!(
+            tool_access("list_tasks", &args),
+            ToolAccess::Project {
+                project_id: 3,
+                template_id: None,
+                permission: None,
+            }
+        );
+    }
+
     // ── ToolResult ───────────────────────────────────────────────────────
 
     #[test]
@@ -5,15 +5,18 @@
 //! - Security headers (CSP, HSTS, X-Frame-Options)
 //! - CORS (Cross-Origin Resource Sharing)
 //! - Кэширование
+//! - Права в проекте (роли участников)
 
 pub mod cache;
 pub mod correlation_id;
+pub mod project_permissions;
 pub mod rate_limiter;
 pub mod security_headers;
 pub mod trace_id;
 
 pub use cache::CacheMiddleware;
 pub use correlation_id::{CorrelationId, correlation_id_middleware};
+pub use project_permissions::project_permissions;
 pub use rate_limiter::*;
 pub use security_headers::*;
 pub use trace_id::{TraceId, trace_id_middleware};
@@ -0,0 +1,271 @@
+//! Проверка прав в проекте для REST API
+//!
+//! Для маршрутов `/api/project/{project_id}/...` (и алиасов `/api/projects/...`)
+//! определяет требуемое разрешение по методу и ресурсу, разрешает роль
+//! вызывающего в проекте (встроенную или кастомную, с переопределениями на
+//! шаблоне) и отвечает 403 с именем недостающего разрешения.
+//!
+//! Чтение доступно любому участнику проекта.
+//!
+//! Запросы с API-токеном вне проектов ограничивает [`token_route_allowed`].
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{FromRequestParts, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::api::extractors::AuthUser;
+use crate::api::middleware::ErrorResponse;
+use crate::api::state::AppState;
+use crate::models::{APIToken, Permission};
+use crate::services::project_access;
+
+/// Маршруты управления учётными данными, закрытые для API-токенов
+const CREDENTIAL_ROUTES: [&str; 2] = ["/api/user/tokens", "/api/service-accounts"];
+
+/// Наибольшее тело запроса на запуск задачи, из которого читается `template_id`
+const MAX_TASK_BODY: usize = 1024 * 1024;
+
+/// ID проекта и сегменты пути после него
+fn project_scope(path: &str) -> Option<(i32, Vec<&str>)> {
+    let mut segments = path.trim_matches('/').split('/');
+    if segments.next() != Some("api") || !matches!(segments.next(), Some("project" | "projects")) {
+        return None;
+    }
+    let project_id = segments.next()?.parse().ok()?;
+    Some((project_id, segments.filter(|s| !s.is_empty()).collect()))
+}
+
+fn is_read(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Разрешение, нужное для операции; `None` — достаточно быть участником
+pub fn required_permission(method: &Method, rest: &[&str]) -> Option<Permission> {
+    let resource = rest.first().copied().unwrap_or_default();
+    let action = rest.get(2).copied();
+
+    if is_read(method) {
+        return match resource {
+            "audit-log" => Some(Permission::ViewAuditLog),
+            // Резервная копия содержит ключи и секреты проекта
+            "backup" => Some(Permission::ManageProject),
+            _ => None,
+        };
+    }
+
+    Some(match resource {
+        // Выход из проекта
+        "me" => return None,
+        "" | "log-retention" => Permission::ManageProject,
+        "users" | "invites" => Permission::ManageUsers,
+        "roles" => Permission::ManageRoles,
+        "integrations" | "webhooks" | "notifications" | "events" => Permission::ManageIntegrations,
+        "secret_storages" => Permission::ManageSecretStorages,
+        "tasks" if *method != Method::DELETE => Permission::RunTasks,
+        "templates" if action == Some("roles") => Permission::ManageRoles,
+        "templates" if matches!(action, Some("deploy" | "stop_all_tasks")) => Permission::RunTasks,
+        "playbooks" | "workflows" if action == Some("run") => Permission::RunTasks,
+        // Отмена запусков workflow и решения по узлам подтверждения
+        "workflows" if action == Some("runs") => Permission::RunTasks,
+        "drift" if action == Some("check") => Permission::RunTasks,
+        // Одобрение и отклонение Terraform-планов
+        "terraform" => Permission::RunTasks,
+        _ => Permission::UpdateResources,
+    })
+}
+
+/// Может ли API-токен обратиться к маршруту
+///
+/// Операции в проекте проверяет [`project_permissions`] по ролям и областям
+/// токена; GraphQL и MCP проверяют права в resolvers и tools. Прочие изменения
+/// требуют области `manage_resources` или `admin`, а токены и сервисные
+/// аккаунты API-токеном не управляются вовсе.
+pub fn token_route_allowed(
+    token: &APIToken,
+    method: &Method,
+    path: &str,
+) -> Result<(), &'static str> {
+    if CREDENTIAL_ROUTES.iter().any(|r| path.starts_with(r)) || path.ends_with("/password") {
+        return Err("API tokens cannot manage credentials");
+    }
+    if is_read(method)
+        || project_scope(path).is_some()
+        || matches!(path, "/graphql" | "/mcp")
+        || token.allows_global_writes()
+    {
+        Ok(())
+    } else {
+        Err("API token scope does not allow this operation")
+    }
+}
+
+fn forbidden(denied: project_access::AccessDenied) -> Response {
+    let mut error = ErrorResponse::new(denied.to_string()).with_code("PERMISSION_DENIED");
+    if let Some(permission) = denied.permission() {
+        error = error.with_details(json!({ "permission": permission }));
+    }
+    (StatusCode::FORBIDDEN, Json(error)).into_response()
+}
+
+fn internal_error(message: impl ToString) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(message.to_string())),
+    )
+        .into_response()
+}
+
+/// Middleware проверки прав в проекте
+pub async fn project_permissions(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some((project_id, rest)) = project_scope(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    // Приглашение принимает ещё не участник
+    if rest.starts_with(&["invites", "accept"]) {
+        return next.run(request).await;
+    }
+    let required = required_permission(request.method(), &rest);
+    let mut template_id = match rest.as_slice() {
+        ["templates", id, ..] => id.parse().ok(),
+        _ => None,
+    };
+    let creates_task = *request.method() == Method::POST && rest == ["tasks"];
+
+    let (mut parts, body) = request.into_parts();
+    let user = match AuthUser::from_request_parts(&mut parts, &state).await {
+        Ok(user) => user,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    // Запуск задачи: права зависят от шаблона из тела запроса
+    let body = if creates_task {
+        let bytes = match axum::body::to_bytes(body, MAX_TASK_BODY).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    Json(ErrorResponse::new(e.to_string())),
+                )
+                    .into_response();
+            }
+        };
+        template_id = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .ok()
+            .and_then(|v| v["template_id"].as_i64())
+            .map(|id| id as i32);
+        Body::from(bytes)
+    } else {
+        body
+    };
+
+    match project_access::check_permission(
+        state.store.store(),
+        &user.caller(),
+        project_id,
+        template_id,
+        required,
+    )
+    .await
+    {
+        Ok(Ok(())) => next.run(Request::from_parts(parts, body)).await,
+        Ok(Err(denied)) => forbidden(denied),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn required(method: Method, path: &str) -> Option<Permission> {
+        let (_, rest) = project_scope(path).unwrap();
+        required_permission(&method, &rest)
+    }
+
+    #[test]
+    fn test_project_scope() {
+        assert_eq!(
+            project_scope("/api/project/3/templates/7"),
+            Some((3, vec!["templates", "7"]))
+        );
+        assert_eq!(project_scope("/api/projects/12"), Some((12, vec![])));
+        assert_eq!(project_scope("/api/projects/restore"), None);
+        assert_eq!(project_scope("/api/tasks"), None);
+    }
+
+    #[test]
+    fn test_required_permission() {
+        assert_eq!(required(Method::GET, "/api/project/1/keys"), None);
+        assert_eq!(
+            required(Method::GET, "/api/project/1/audit-log"),
+            Some(Permission::ViewAuditLog)
+        );
+        assert_eq!(
+            required(Method::POST, "/api/project/1/tasks"),
+            Some(Permission::RunTasks)
+        );
+        assert_eq!(
+            required(Method::DELETE, "/api/project/1/tasks/4"),
+            Some(Permission::UpdateResources)
+        );
+        assert_eq!(
+            required(Method::POST, "/api/project/1/templates/2/deploy"),
+            Some(Permission::RunTasks)
+        );
+        assert_eq!(
+            required(
+                Method::POST,
+                "/api/project/1/workflows/2/runs/3/nodes/4/approve"
+            ),
+            Some(Permission::RunTasks)
+        );
+        assert_eq!(
+            required(Method::PUT, "/api/project/1/templates/2"),
+            Some(Permission::UpdateResources)
+        );
+        assert_eq!(
+            required(Method::PUT, "/api/projects/1/users/9"),
+            Some(Permission::ManageUsers)
+        );
+        assert_eq!(
+            required(Method::DELETE, "/api/project/1"),
+            Some(Permission::ManageProject)
+        );
+        assert_eq!(
+            required(Method::POST, "/api/projects/1/secret_storages/2/sync"),
+            Some(Permission::ManageSecretStorages)
+        );
+        assert_eq!(required(Method::DELETE, "/api/project/1/me"), None);
+    }
+
+    #[test]
+    fn test_token_route_allowed() {
+        let read_only = APIToken {
+            scopes: vec![crate::models::TokenScope::ReadOnly],
+            ..Default::default()
+        };
+        let allowed = |token: &APIToken, method, path| token_route_allowed(token, &method, path);
+        assert!(allowed(&read_only, Method::GET, "/api/projects").is_ok());
+        assert!(allowed(&read_only, Method::POST, "/api/project/1/tasks").is_ok());
+        assert!(allowed(&read_only, Method::POST, "/graphql").is_ok());
+        assert!(allowed(&read_only, Method::POST, "/api/projects").is_err());
+        assert!(allowed(&read_only, Method::GET, "/api/user/tokens").is_err());
+
+        let legacy = APIToken::default();
+        assert!(allowed(&legacy, Method::POST, "/api/projects").is_ok());
+        assert!(allowed(&legacy, Method::POST, "/api/user/tokens").is_err());
+        assert!(allowed(&legacy, Method::POST, "/api/users/2/password").is_err());
+        assert!(allowed(&legacy, Method::DELETE, "/api/service-accounts/1").is_err());
+    }
+}
@@ -66,6 +66,15 @@ pub use middleware::{rate_limiter, security_headers};
 
 /// Создаёт приложение Axum
 pub async fn create_app(store: Arc<dyn crate::db::Store + Send + Sync>) -> Router {
+    create_app_with_token_blacklist(store, token_blacklist::TokenBlacklist::new()).await
+}
+
+/// Создаёт приложение Axum с общим списком отозванных JWT (его же
+/// использует gRPC сервер)
+pub async fn create_app_with_token_blacklist(
+    store: Arc<dyn crate::db::Store + Send + Sync>,
+    token_blacklist: token_blacklist::TokenBlacklist,
+) -> Router {
     let config = crate::config::Config::default();
 
     // Инициализация Redis cache для HA режима
@@ -123,14 +132,16 @@ pub async fn create_app(store: Arc<dyn crate::db::Store + Send + Sync>) -> Route
         Arc::new(WebSocketManager::new())
     };
 
-    let state = Arc::new(AppState::with_ws_and_task_queue(
+    let mut state = AppState::with_ws_and_task_queue(
         store,
         config,
         cache,
         task_queue,
         ws_manager,
         ws_redis_url,
-    ));
+    );
+    state.token_blacklist = token_blacklist;
+    let state = Arc::new(state);
 
     // Start JWT blacklist pruner — cleans up expired entries every 5 minutes
     token_blacklist::spawn_pruner(
@@ -164,12 +175,17 @@ pub async fn create_app(store: Arc<dyn crate::db::Store + Send + Sync>) -> Route
         .merge(graphql::graphql_routes(Arc::clone(&state)))
         // Auth с отдельным строгим rate limiter
         .merge(auth_routes)
-        // Остальные API с мягким rate limiting (100 req/min per IP)
+        // Остальные API с мягким rate limiting (100 req/min per IP) и проверкой прав в проекте
         .merge(
-            routes::api_routes().layer(axum_middleware::from_fn_with_state(
-                Arc::clone(&state),
-                middleware::rate_limiter::app_api_rate_limit,
-            )),
+            routes::api_routes()
+                .layer(axum_middleware::from_fn_with_state(
+                    Arc::clone(&state),
+                    middleware::project_permissions,
+                ))
+                .layer(axum_middleware::from_fn_with_state(
+                    Arc::clone(&state),
+                    middleware::rate_limiter::app_api_rate_limit,
+                )),
         )
         // Static files с fallback
         .merge(routes::static_routes())
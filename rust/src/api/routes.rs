@@ -845,6 +845,10 @@ pub fn api_routes() -> Router<Arc<AppState>> {
             "/api/internal/tasks/{task_id}/log",
             post(runners::runner_submit_log),
         )
+        .route(
+            "/api/internal/tasks/{task_id}/status",
+            post(runners::runner_submit_status),
+        )
         // Кэш (Cache) - admin only
         .route("/api/cache", delete(cache::clear_cache))
         // Кэш проекта (B-BE-24)
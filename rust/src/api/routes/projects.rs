@@ -3,7 +3,7 @@
 //! Проекты, статистика, организации, брендинг, deployment environments
 
 use crate::api::handlers;
-use crate::api::handlers::projects::project;
+use crate::api::handlers::projects::{project, roles};
 use crate::api::state::AppState;
 use axum::{
     Router,
@@ -42,6 +42,34 @@ pub fn project_routes() -> Router<Arc<AppState>> {
             "/api/projects/{project_id}/stats",
             get(project::get_project_stats),
         )
+        // Роли проекта и их переопределения на шаблонах
+        .route(
+            "/api/project/{project_id}/roles",
+            get(roles::get_roles).post(roles::create_role),
+        )
+        .route(
+            "/api/project/{project_id}/roles/all",
+            get(roles::get_all_roles),
+        )
+        .route(
+            "/api/project/{project_id}/roles/{role_id}",
+            get(roles::get_role)
+                .put(roles::update_role)
+                .delete(roles::delete_role),
+        )
+        .route(
+            "/api/project/{project_id}/templates/{template_id}/roles",
+            get(roles::get_template_role_perms).post(roles::create_template_role_perm),
+        )
+        .route(
+            "/api/project/{project_id}/templates/{template_id}/roles/{id}",
+            delete(roles::delete_template_role_perm),
+        )
+        // Аудит проекта
+        .route(
+            "/api/project/{project_id}/audit-log",
+            get(handlers::audit_log::get_project_audit_logs),
+        )
         // Organizations (Multi-Tenancy, v4.0)
         .route(
             "/api/organizations",
@@ -111,6 +139,54 @@ pub fn project_routes() -> Router<Arc<AppState>> {
             "/api/project/{project_id}/deploy-environments/{id}/history",
             get(handlers::deployment_environment::get_deploy_history),
         )
+        // Concurrency Groups — взаимоисключение задач
+        .route(
+            "/api/project/{project_id}/concurrency-groups",
+            get(handlers::concurrency_groups::list_concurrency_groups),
+        )
+        .route(
+            "/api/project/{project_id}/concurrency-groups/{name}",
+            put(handlers::concurrency_groups::put_concurrency_group)
+                .delete(handlers::concurrency_groups::delete_concurrency_group),
+        )
+        // Политика хранения задач и их логов
+        .route(
+            "/api/project/{project_id}/log-retention",
+            get(handlers::log_retention::get_log_retention)
+                .put(handlers::log_retention::put_log_retention),
+        )
+        // Webhook проекта
+        .route(
+            "/api/project/{project_id}/webhooks",
+            get(handlers::webhooks::get_project_webhooks).post(handlers::webhooks::create_webhook),
+        )
+        .route(
+            "/api/project/{project_id}/webhooks/{id}",
+            get(handlers::webhooks::get_webhook)
+                .put(handlers::webhooks::update_webhook)
+                .delete(handlers::webhooks::delete_webhook),
+        )
+        .route(
+            "/api/project/{project_id}/webhooks/{id}/test",
+            post(handlers::webhooks::test_webhook),
+        )
+        .route(
+            "/api/project/{project_id}/webhooks/{id}/logs",
+            get(handlers::webhooks::get_webhook_logs),
+        )
+        // Доставки событий задач политикам уведомлений и webhook
+        .route(
+            "/api/project/{project_id}/events/deliveries",
+            get(handlers::event_deliveries::list_event_deliveries),
+        )
+        .route(
+            "/api/project/{project_id}/events/deliveries/{id}",
+            get(handlers::event_deliveries::get_event_delivery),
+        )
+        .route(
+            "/api/project/{project_id}/events/deliveries/{id}/redeliver",
+            post(handlers::event_deliveries::redeliver_event_delivery),
+        )
 }
 
 #[cfg(test)]
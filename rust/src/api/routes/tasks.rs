@@ -1,12 +1,12 @@
 //! Маршруты задач и сервисов проекта
 //!
 //! Tasks, Schedules, Integrations, Secret Storages, Project Users,
-//! Analytics, Views, Notifications, Drift, Credentials, Backup/Restore
+//! Analytics, Views, Notifications, Drift, Credentials, Plugins, Backup/Restore
 
 use crate::api::handlers;
 use crate::api::handlers::projects::{
-    integration as project_integration, invites, notifications, schedules, secret_storages, tasks,
-    users as project_users, views,
+    integration as project_integration, integration_alias as project_integration_alias, invites,
+    notifications, schedules, secret_storages, tasks, users as project_users, views,
 };
 use crate::api::state::AppState;
 use axum::{
@@ -52,6 +52,39 @@ pub fn task_routes() -> Router<Arc<AppState>> {
             "/api/project/{project_id}/tasks/last",
             get(tasks::get_last_tasks),
         )
+        // Очередь задач: приоритеты и перестановка
+        .route(
+            "/api/project/{project_id}/tasks/queue",
+            get(tasks::get_task_queue),
+        )
+        .route(
+            "/api/project/{project_id}/tasks/queue/reorder",
+            post(tasks::reorder_task_queue),
+        )
+        .route(
+            "/api/project/{project_id}/tasks/{id}/priority",
+            post(tasks::set_task_priority),
+        )
+        .route(
+            "/api/project/{project_id}/tasks/{id}/bump",
+            post(tasks::bump_task),
+        )
+        .route(
+            "/api/projects/{project_id}/tasks/queue",
+            get(tasks::get_task_queue),
+        )
+        .route(
+            "/api/projects/{project_id}/tasks/queue/reorder",
+            post(tasks::reorder_task_queue),
+        )
+        .route(
+            "/api/projects/{project_id}/tasks/{id}/priority",
+            post(tasks::set_task_priority),
+        )
+        .route(
+            "/api/projects/{project_id}/tasks/{id}/bump",
+            post(tasks::bump_task),
+        )
         // Задачи - дополнительные endpoints
         .route(
             "/api/projects/{project_id}/tasks/{id}/stop",
@@ -69,6 +102,10 @@ pub fn task_routes() -> Router<Arc<AppState>> {
             "/api/projects/{project_id}/tasks/{id}/output",
             get(tasks::get_task_output),
         )
+        .route(
+            "/api/projects/{project_id}/tasks/{id}/hooks",
+            get(tasks::get_task_hooks),
+        )
         .route(
             "/api/project/{project_id}/tasks/{id}/stop",
             post(tasks::stop_task),
@@ -126,6 +163,10 @@ pub fn task_routes() -> Router<Arc<AppState>> {
             "/api/projects/{project_id}/schedules/validate",
             post(schedules::validate_schedule_cron_format),
         )
+        .route(
+            "/api/projects/{project_id}/schedules/{id}/next",
+            get(schedules::get_schedule_next_runs),
+        )
         .route(
             "/api/project/{project_id}/schedules",
             get(schedules::get_project_schedules),
@@ -150,6 +191,10 @@ pub fn task_routes() -> Router<Arc<AppState>> {
             "/api/project/{project_id}/schedules/validate",
             post(schedules::validate_schedule_cron_format),
         )
+        .route(
+            "/api/project/{project_id}/schedules/{id}/next",
+            get(schedules::get_schedule_next_runs),
+        )
         // Analytics
         .route(
             "/api/project/{project_id}/analytics",
@@ -242,27 +287,45 @@ pub fn task_routes() -> Router<Arc<AppState>> {
             "/api/project/{project_id}/integrations/{id}",
             delete(project_integration::delete_integration),
         )
-        // Integration Aliases - заглушки
-        // .route(
-        //     "/api/project/{project_id}/integrations/aliases",
-        //     get(project_integration::get_integration_aliases),
-        // )
-        // .route(
-        //     "/api/project/{project_id}/integrations/aliases",
-        //     post(project_integration::add_integration_alias),
-        // )
-        // .route(
-        //     "/api/project/{project_id}/integrations/aliases/{id}",
-        //     get(project_integration::get_integration_alias),
-        // )
-        // .route(
-        //     "/api/project/{project_id}/integrations/aliases/{id}",
-        //     put(project_integration::update_integration_alias),
-        // )
-        // .route(
-        //     "/api/project/{project_id}/integrations/aliases/{id}",
-        //     delete(project_integration::delete_integration_alias),
-        // )
+        // Integration Matchers / Extract Values / Aliases
+        .route(
+            "/api/projects/{project_id}/integrations/{id}/matchers",
+            get(project_integration::get_integration_matchers)
+                .post(project_integration::add_integration_matcher),
+        )
+        .route(
+            "/api/projects/{project_id}/integrations/{id}/matchers/{matcher_id}",
+            put(project_integration::update_integration_matcher)
+                .delete(project_integration::delete_integration_matcher),
+        )
+        .route(
+            "/api/projects/{project_id}/integrations/{id}/values",
+            get(project_integration::get_integration_extract_values)
+                .post(project_integration::add_integration_extract_value),
+        )
+        .route(
+            "/api/projects/{project_id}/integrations/{id}/values/{value_id}",
+            put(project_integration::update_integration_extract_value)
+                .delete(project_integration::delete_integration_extract_value),
+        )
+        .route(
+            "/api/projects/{project_id}/integrations/{id}/aliases",
+            get(project_integration_alias::get_integration_aliases)
+                .post(project_integration_alias::add_integration_alias),
+        )
+        .route(
+            "/api/projects/{project_id}/integrations/{id}/aliases/{alias_id}",
+            delete(project_integration_alias::delete_integration_alias),
+        )
+        // Входящие вызовы интеграций (публичный, аутентификация самой интеграцией)
+        .route(
+            "/api/integrations/{alias}",
+            post(crate::api::integration::receive_integration),
+        )
+        .route(
+            "/api/integrations/{alias}/{forge}",
+            post(crate::api::integration::receive_forge_webhook),
+        )
         // Secret Storages
         .route(
             "/api/projects/{project_id}/secret_storages",
@@ -396,6 +459,62 @@ pub fn task_routes() -> Router<Arc<AppState>> {
             "/api/project/{project_id}/notifications/{id}/test",
             post(handlers::notification::test_notification_policy),
         )
+        // GitOps Drift Detection
+        .route(
+            "/api/project/{project_id}/drift",
+            get(handlers::drift::list_drift_configs),
+        )
+        .route(
+            "/api/project/{project_id}/drift",
+            post(handlers::drift::create_drift_config),
+        )
+        .route(
+            "/api/project/{project_id}/drift/{id}",
+            put(handlers::drift::update_drift_config),
+        )
+        .route(
+            "/api/project/{project_id}/drift/{id}",
+            delete(handlers::drift::delete_drift_config),
+        )
+        .route(
+            "/api/project/{project_id}/drift/{id}/check",
+            post(handlers::drift::trigger_drift_check),
+        )
+        .route(
+            "/api/project/{project_id}/drift/{id}/results",
+            get(handlers::drift::get_drift_results),
+        )
+        // Plan Approval — ревью сохранённых Terraform-планов
+        .route(
+            "/api/project/{project_id}/terraform/plans",
+            get(handlers::plan_approval::list_pending_plans),
+        )
+        .route(
+            "/api/project/{project_id}/terraform/plans/{plan_id}/approve",
+            post(handlers::plan_approval::approve_plan),
+        )
+        .route(
+            "/api/project/{project_id}/terraform/plans/{plan_id}/reject",
+            post(handlers::plan_approval::reject_plan),
+        )
+        .route(
+            "/api/project/{project_id}/tasks/{id}/plan",
+            get(handlers::plan_approval::get_task_plan),
+        )
+        // Plugins (admin)
+        .route("/api/admin/plugins", get(handlers::plugins::list_plugins))
+        .route(
+            "/api/admin/plugins/reload",
+            post(handlers::plugins::reload_plugins),
+        )
+        .route(
+            "/api/admin/plugins/{id}/enable",
+            post(handlers::plugins::enable_plugin),
+        )
+        .route(
+            "/api/admin/plugins/{id}/disable",
+            post(handlers::plugins::disable_plugin),
+        )
         // Backup & Restore - заглушки, т.к. handlers::backup не существует
         // .route(
         //     "/api/project/{project_id}/backup",
@@ -418,6 +537,10 @@ pub fn task_routes() -> Router<Arc<AppState>> {
             "/api/internal/tasks/{task_id}/log",
             post(crate::api::runners::runner_submit_log),
         )
+        .route(
+            "/api/internal/tasks/{task_id}/status",
+            post(crate::api::runners::runner_submit_status),
+        )
 }
 
 #[cfg(test)]
@@ -34,6 +34,18 @@ pub fn template_routes() -> Router<Arc<AppState>> {
             "/api/projects/{project_id}/templates/{id}",
             delete(handlers::delete_template),
         )
+        .route(
+            "/api/projects/{project_id}/templates/{id}/effective",
+            get(handlers::get_effective_template),
+        )
+        .route(
+            "/api/projects/{project_id}/templates/{id}/builds",
+            get(handlers::get_template_builds),
+        )
+        .route(
+            "/api/projects/{project_id}/templates/{id}/deploy",
+            post(handlers::deploy_template_build),
+        )
         .route(
             "/api/project/{project_id}/templates",
             get(handlers::get_templates),
@@ -54,6 +66,18 @@ pub fn template_routes() -> Router<Arc<AppState>> {
             "/api/project/{project_id}/templates/{id}",
             delete(handlers::delete_template),
         )
+        .route(
+            "/api/project/{project_id}/templates/{id}/effective",
+            get(handlers::get_effective_template),
+        )
+        .route(
+            "/api/project/{project_id}/templates/{id}/builds",
+            get(handlers::get_template_builds),
+        )
+        .route(
+            "/api/project/{project_id}/templates/{id}/deploy",
+            post(handlers::deploy_template_build),
+        )
         .route(
             "/api/project/{project_id}/templates/{id}/stop_all_tasks",
             post(handlers::stop_all_template_tasks),
@@ -103,6 +127,26 @@ pub fn template_routes() -> Router<Arc<AppState>> {
             "/api/project/{project_id}/workflows/{id}/run",
             post(handlers::workflow::run_workflow),
         )
+        .route(
+            "/api/project/{project_id}/workflows/{id}/runs",
+            get(handlers::workflow::get_workflow_runs),
+        )
+        .route(
+            "/api/project/{project_id}/workflows/{id}/runs/{run_id}",
+            get(handlers::workflow::get_workflow_run),
+        )
+        .route(
+            "/api/project/{project_id}/workflows/{id}/runs/{run_id}/cancel",
+            post(handlers::workflow::cancel_workflow_run),
+        )
+        .route(
+            "/api/project/{project_id}/workflows/{id}/runs/{run_id}/nodes/{node_id}/approve",
+            post(handlers::workflow::approve_workflow_node),
+        )
+        .route(
+            "/api/project/{project_id}/workflows/{id}/runs/{run_id}/nodes/{node_id}/reject",
+            post(handlers::workflow::reject_workflow_node),
+        )
     // .route(
     //     "/api/project/{project_id}/workflows/{id}/dry-run",
     //     post(handlers::workflow::dry_run_workflow),
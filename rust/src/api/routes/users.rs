@@ -1,9 +1,10 @@
 //! Маршруты пользователей
 //!
-//! Пользователи, текущий пользователь
+//! Пользователи, текущий пользователь, API-токены и сервисные аккаунты
 
 use crate::api::handlers;
 use crate::api::state::AppState;
+use crate::api::user;
 use axum::{
     Router,
     routing::{delete, get, post, put},
@@ -15,6 +16,12 @@ pub fn user_routes() -> Router<Arc<AppState>> {
     Router::new()
         // Текущий пользователь
         .route("/api/user", get(handlers::get_current_user))
+        // API-токены текущего пользователя
+        .route(
+            "/api/user/tokens",
+            get(user::get_api_tokens).post(user::create_api_token),
+        )
+        .route("/api/user/tokens/{id}", delete(user::delete_api_token))
         // Пользователи
         .route("/api/users", get(handlers::get_users))
         .route("/api/users", post(handlers::create_user))
@@ -25,6 +32,25 @@ pub fn user_routes() -> Router<Arc<AppState>> {
             "/api/users/{id}/password",
             post(handlers::update_user_password),
         )
+        // Сервисные аккаунты
+        .route(
+            "/api/service-accounts",
+            get(handlers::service_accounts::get_service_accounts)
+                .post(handlers::service_accounts::create_service_account),
+        )
+        .route(
+            "/api/service-accounts/{id}",
+            delete(handlers::service_accounts::delete_service_account),
+        )
+        .route(
+            "/api/service-accounts/{id}/tokens",
+            get(handlers::service_accounts::get_service_account_tokens)
+                .post(handlers::service_accounts::create_service_account_token),
+        )
+        .route(
+            "/api/service-accounts/{id}/tokens/{token_id}",
+            delete(handlers::service_accounts::delete_service_account_token),
+        )
 }
 
 #[cfg(test)]
@@ -380,6 +380,18 @@ impl TaskManager for StoreWrapper {
         self.inner.as_ref().update_task(task).await
     }
 
+    async fn set_waiting_task_priority(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        priority: Option<i32>,
+    ) -> Result<bool> {
+        self.inner
+            .as_ref()
+            .set_waiting_task_priority(project_id, task_id, priority)
+            .await
+    }
+
     async fn delete_task(&self, project_id: i32, task_id: i32) -> Result<()> {
         self.inner.as_ref().delete_task(project_id, task_id).await
     }
@@ -415,6 +427,13 @@ impl TaskManager for StoreWrapper {
             .await
     }
 
+    async fn get_queued_tasks(&self, project_id: Option<i32>, limit: i32) -> Result<Vec<Task>> {
+        self.inner
+            .as_ref()
+            .get_queued_tasks(project_id, limit)
+            .await
+    }
+
     async fn get_running_tasks_count(&self) -> Result<usize> {
         self.inner.as_ref().get_running_tasks_count().await
     }
@@ -422,6 +441,29 @@ impl TaskManager for StoreWrapper {
     async fn get_waiting_tasks_count(&self) -> Result<usize> {
         self.inner.as_ref().get_waiting_tasks_count().await
     }
+
+    async fn link_task_hook(&self, project_id: i32, hook: TaskHook) -> Result<()> {
+        self.inner.as_ref().link_task_hook(project_id, hook).await
+    }
+
+    async fn create_hook_task(
+        &self,
+        task: Task,
+        parent_task_id: i32,
+        hook_type: &str,
+    ) -> Result<Task> {
+        self.inner
+            .as_ref()
+            .create_hook_task(task, parent_task_id, hook_type)
+            .await
+    }
+
+    async fn get_task_hook_links(&self, project_id: i32, task_id: i32) -> Result<Vec<TaskHook>> {
+        self.inner
+            .as_ref()
+            .get_task_hook_links(project_id, task_id)
+            .await
+    }
 }
 
 #[async_trait]
@@ -479,6 +521,10 @@ impl ScheduleManager for StoreWrapper {
             .set_schedule_commit_hash(project_id, schedule_id, hash)
             .await
     }
+
+    async fn set_schedule_last_fired(&self, schedule_id: i32, fired: DateTime<Utc>) -> Result<()> {
+        self.inner.set_schedule_last_fired(schedule_id, fired).await
+    }
 }
 
 #[async_trait]
@@ -537,6 +583,33 @@ impl TokenManager for StoreWrapper {
             .delete_api_token(user_id, token_id)
             .await
     }
+
+    async fn get_api_token_by_value(&self, token: &str) -> Result<APIToken> {
+        self.inner.as_ref().get_api_token_by_value(token).await
+    }
+
+    async fn touch_api_token(&self, token_id: i32, ip: Option<&str>) -> Result<()> {
+        self.inner.as_ref().touch_api_token(token_id, ip).await
+    }
+}
+
+#[async_trait]
+impl ServiceAccountManager for StoreWrapper {
+    async fn get_service_accounts(&self) -> Result<Vec<ServiceAccount>> {
+        self.inner.as_ref().get_service_accounts().await
+    }
+
+    async fn get_service_account(&self, id: i32) -> Result<ServiceAccount> {
+        self.inner.as_ref().get_service_account(id).await
+    }
+
+    async fn create_service_account(&self, account: ServiceAccount) -> Result<ServiceAccount> {
+        self.inner.as_ref().create_service_account(account).await
+    }
+
+    async fn delete_service_account(&self, id: i32) -> Result<()> {
+        self.inner.as_ref().delete_service_account(id).await
+    }
 }
 
 #[async_trait]
@@ -587,6 +660,21 @@ impl RunnerManager for StoreWrapper {
     async fn touch_runner(&self, runner_id: i32) -> Result<()> {
         self.inner.as_ref().touch_runner(runner_id).await
     }
+
+    async fn claim_task(&self, task_id: i32, runner_id: i32) -> Result<bool> {
+        self.inner.as_ref().claim_task(task_id, runner_id).await
+    }
+
+    async fn get_runner_task_ids(&self, runner_id: i32) -> Result<Vec<i32>> {
+        self.inner.as_ref().get_runner_task_ids(runner_id).await
+    }
+
+    async fn requeue_stale_runner_tasks(&self, timeout_secs: i64) -> Result<Vec<Task>> {
+        self.inner
+            .as_ref()
+            .requeue_stale_runner_tasks(timeout_secs)
+            .await
+    }
 }
 
 #[async_trait]
@@ -646,6 +734,35 @@ impl IntegrationManager for StoreWrapper {
             .delete_integration(project_id, integration_id)
             .await
     }
+
+    async fn get_integration_aliases(
+        &self,
+        project_id: i32,
+        integration_id: Option<i32>,
+    ) -> Result<Vec<IntegrationAlias>> {
+        self.inner
+            .as_ref()
+            .get_integration_aliases(project_id, integration_id)
+            .await
+    }
+
+    async fn get_integration_alias_by_alias(&self, alias: &str) -> Result<IntegrationAlias> {
+        self.inner
+            .as_ref()
+            .get_integration_alias_by_alias(alias)
+            .await
+    }
+
+    async fn create_integration_alias(&self, alias: IntegrationAlias) -> Result<IntegrationAlias> {
+        self.inner.as_ref().create_integration_alias(alias).await
+    }
+
+    async fn delete_integration_alias(&self, project_id: i32, alias_id: i32) -> Result<()> {
+        self.inner
+            .as_ref()
+            .delete_integration_alias(project_id, alias_id)
+            .await
+    }
 }
 
 #[async_trait]
@@ -1150,6 +1267,46 @@ impl ProjectRoleManager for StoreWrapper {
     }
 }
 
+#[async_trait]
+impl ProjectAccessManager for StoreWrapper {
+    async fn get_project_user_role(&self, project_id: i32, user_id: i32) -> Result<Option<String>> {
+        self.inner
+            .as_ref()
+            .get_project_user_role(project_id, user_id)
+            .await
+    }
+    async fn set_project_user_role(&self, project_id: i32, user_id: i32, role: &str) -> Result<()> {
+        self.inner
+            .as_ref()
+            .set_project_user_role(project_id, user_id, role)
+            .await
+    }
+    async fn get_template_role_perms(
+        &self,
+        project_id: i32,
+        template_id: i32,
+    ) -> Result<Vec<TemplateRolePerm>> {
+        self.inner
+            .as_ref()
+            .get_template_role_perms(project_id, template_id)
+            .await
+    }
+    async fn create_template_role_perm(&self, perm: TemplateRolePerm) -> Result<TemplateRolePerm> {
+        self.inner.as_ref().create_template_role_perm(perm).await
+    }
+    async fn delete_template_role_perm(
+        &self,
+        project_id: i32,
+        template_id: i32,
+        id: i32,
+    ) -> Result<()> {
+        self.inner
+            .as_ref()
+            .delete_template_role_perm(project_id, template_id, id)
+            .await
+    }
+}
+
 #[async_trait]
 impl crate::db::store::WorkflowManager for StoreWrapper {
     async fn get_workflows(&self, project_id: i32) -> crate::error::Result<Vec<Workflow>> {
@@ -1251,10 +1408,11 @@ impl crate::db::store::WorkflowManager for StoreWrapper {
         &self,
         workflow_id: i32,
         project_id: i32,
+        params: serde_json::Map<String, serde_json::Value>,
     ) -> crate::error::Result<WorkflowRun> {
         self.inner
             .as_ref()
-            .create_workflow_run(workflow_id, project_id)
+            .create_workflow_run(workflow_id, project_id, params)
             .await
     }
     async fn update_workflow_run_status(
@@ -1268,6 +1426,41 @@ impl crate::db::store::WorkflowManager for StoreWrapper {
             .update_workflow_run_status(id, status, message)
             .await
     }
+    async fn get_workflow_run(
+        &self,
+        id: i32,
+        project_id: i32,
+    ) -> crate::error::Result<WorkflowRun> {
+        self.inner.as_ref().get_workflow_run(id, project_id).await
+    }
+    async fn get_active_workflow_runs(&self) -> crate::error::Result<Vec<WorkflowRun>> {
+        self.inner.as_ref().get_active_workflow_runs().await
+    }
+    async fn get_workflow_run_nodes(
+        &self,
+        run_id: i32,
+    ) -> crate::error::Result<Vec<crate::models::workflow::WorkflowRunNode>> {
+        self.inner.as_ref().get_workflow_run_nodes(run_id).await
+    }
+    async fn save_workflow_run_node(
+        &self,
+        node: crate::models::workflow::WorkflowRunNode,
+    ) -> crate::error::Result<()> {
+        self.inner.as_ref().save_workflow_run_node(node).await
+    }
+    async fn decide_workflow_approval(
+        &self,
+        run_id: i32,
+        node_id: i32,
+        approved: bool,
+        user_id: i32,
+        comment: Option<String>,
+    ) -> crate::error::Result<bool> {
+        self.inner
+            .as_ref()
+            .decide_workflow_approval(run_id, node_id, approved, user_id, comment)
+            .await
+    }
 }
 
 #[async_trait]
@@ -1416,6 +1609,11 @@ impl crate::db::store::DriftManager for StoreWrapper {
     ) -> crate::error::Result<Vec<crate::models::drift::DriftConfig>> {
         self.inner.as_ref().get_drift_configs(project_id).await
     }
+    async fn get_enabled_drift_configs(
+        &self,
+    ) -> crate::error::Result<Vec<crate::models::drift::DriftConfig>> {
+        self.inner.as_ref().get_enabled_drift_configs().await
+    }
     async fn get_drift_config(
         &self,
         id: i32,
@@ -1444,6 +1642,17 @@ impl crate::db::store::DriftManager for StoreWrapper {
             .update_drift_config_enabled(id, project_id, enabled)
             .await
     }
+    async fn update_drift_config_schedule(
+        &self,
+        id: i32,
+        project_id: i32,
+        schedule: Option<String>,
+    ) -> crate::error::Result<()> {
+        self.inner
+            .as_ref()
+            .update_drift_config_schedule(id, project_id, schedule)
+            .await
+    }
     async fn delete_drift_config(&self, id: i32, project_id: i32) -> crate::error::Result<()> {
         self.inner
             .as_ref()
@@ -1481,6 +1690,17 @@ impl crate::db::store::DriftManager for StoreWrapper {
             )
             .await
     }
+    async fn update_drift_result(
+        &self,
+        id: i32,
+        status: &str,
+        summary: Option<String>,
+    ) -> crate::error::Result<()> {
+        self.inner
+            .as_ref()
+            .update_drift_result(id, status, summary)
+            .await
+    }
     async fn get_latest_drift_results(
         &self,
         project_id: i32,
@@ -1490,6 +1710,11 @@ impl crate::db::store::DriftManager for StoreWrapper {
             .get_latest_drift_results(project_id)
             .await
     }
+    async fn get_pending_drift_results(
+        &self,
+    ) -> crate::error::Result<Vec<crate::models::drift::DriftResult>> {
+        self.inner.as_ref().get_pending_drift_results().await
+    }
 }
 
 #[async_trait]
@@ -1909,6 +2134,235 @@ impl crate::db::store::DeploymentEnvironmentManager for StoreWrapper {
     }
 }
 
+#[async_trait]
+impl crate::db::store::ClusterNodeManager for StoreWrapper {
+    async fn touch_cluster_node(&self, node_id: &str, hostname: &str) -> Result<()> {
+        self.inner
+            .as_ref()
+            .touch_cluster_node(node_id, hostname)
+            .await
+    }
+    async fn set_task_node(&self, task_id: i32, node_id: &str) -> Result<()> {
+        self.inner.as_ref().set_task_node(task_id, node_id).await
+    }
+    async fn claim_task_for_node(&self, task_id: i32, node_id: &str) -> Result<bool> {
+        self.inner
+            .as_ref()
+            .claim_task_for_node(task_id, node_id)
+            .await
+    }
+    async fn fail_orphaned_tasks(&self, node_timeout_secs: i64, message: &str) -> Result<Vec<i32>> {
+        self.inner
+            .as_ref()
+            .fail_orphaned_tasks(node_timeout_secs, message)
+            .await
+    }
+    async fn set_workflow_run_node(&self, run_id: i32, node_id: &str) -> Result<()> {
+        self.inner
+            .as_ref()
+            .set_workflow_run_node(run_id, node_id)
+            .await
+    }
+    async fn claim_orphaned_workflow_runs(
+        &self,
+        node_timeout_secs: i64,
+        node_id: &str,
+    ) -> Result<Vec<WorkflowRun>> {
+        self.inner
+            .as_ref()
+            .claim_orphaned_workflow_runs(node_timeout_secs, node_id)
+            .await
+    }
+    async fn try_acquire_leader_lock(&self, lease: &str, node_id: &str) -> Result<bool> {
+        self.inner
+            .as_ref()
+            .try_acquire_leader_lock(lease, node_id)
+            .await
+    }
+    async fn release_leader_lock(&self, lease: &str, node_id: &str) -> Result<()> {
+        self.inner
+            .as_ref()
+            .release_leader_lock(lease, node_id)
+            .await
+    }
+    async fn get_cluster_leader(
+        &self,
+        lease: &str,
+    ) -> Result<Option<crate::models::ClusterLeader>> {
+        self.inner.as_ref().get_cluster_leader(lease).await
+    }
+}
+
+#[async_trait]
+impl crate::db::store::EventOutboxManager for StoreWrapper {
+    async fn create_outbox_event(&self, event: OutboxEvent) -> Result<OutboxEvent> {
+        self.inner.as_ref().create_outbox_event(event).await
+    }
+    async fn get_outbox_event(&self, id: i64) -> Result<OutboxEvent> {
+        self.inner.as_ref().get_outbox_event(id).await
+    }
+    async fn get_undispatched_events(&self, limit: i64) -> Result<Vec<OutboxEvent>> {
+        self.inner.as_ref().get_undispatched_events(limit).await
+    }
+    async fn dispatch_outbox_event(
+        &self,
+        event_id: i64,
+        deliveries: Vec<NewEventDelivery>,
+    ) -> Result<()> {
+        self.inner
+            .as_ref()
+            .dispatch_outbox_event(event_id, deliveries)
+            .await
+    }
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> Result<Vec<EventDelivery>> {
+        self.inner
+            .as_ref()
+            .claim_due_deliveries(limit, lease_secs)
+            .await
+    }
+    async fn finish_event_delivery(
+        &self,
+        id: i64,
+        status: DeliveryStatus,
+        next_attempt: Option<DateTime<Utc>>,
+        error: Option<String>,
+    ) -> Result<()> {
+        self.inner
+            .as_ref()
+            .finish_event_delivery(id, status, next_attempt, error)
+            .await
+    }
+    async fn get_event_deliveries(
+        &self,
+        project_id: i32,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> Result<Vec<EventDelivery>> {
+        self.inner
+            .as_ref()
+            .get_event_deliveries(project_id, status, limit)
+            .await
+    }
+    async fn get_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery> {
+        self.inner.as_ref().get_event_delivery(project_id, id).await
+    }
+    async fn redeliver_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery> {
+        self.inner
+            .as_ref()
+            .redeliver_event_delivery(project_id, id)
+            .await
+    }
+    async fn get_event_delivery_logs(&self, delivery_id: i64) -> Result<Vec<WebhookLog>> {
+        self.inner
+            .as_ref()
+            .get_event_delivery_logs(delivery_id)
+            .await
+    }
+}
+
+#[async_trait]
+impl crate::db::store::TaskLogArchiveManager for StoreWrapper {
+    async fn get_tasks_to_archive(
+        &self,
+        finished_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<(i32, i32)>> {
+        self.inner
+            .as_ref()
+            .get_tasks_to_archive(finished_before, limit)
+            .await
+    }
+    async fn get_task_log_archive(
+        &self,
+        task_id: i32,
+    ) -> Result<Option<crate::models::TaskLogArchive>> {
+        self.inner.as_ref().get_task_log_archive(task_id).await
+    }
+    async fn complete_task_log_archive(
+        &self,
+        archive: crate::models::TaskLogArchive,
+        last_output_id: i32,
+    ) -> Result<()> {
+        self.inner
+            .as_ref()
+            .complete_task_log_archive(archive, last_output_id)
+            .await
+    }
+    async fn get_log_retention_policies(&self) -> Result<Vec<crate::models::LogRetentionPolicy>> {
+        self.inner.as_ref().get_log_retention_policies().await
+    }
+    async fn get_log_retention_policy(
+        &self,
+        project_id: i32,
+    ) -> Result<Option<crate::models::LogRetentionPolicy>> {
+        self.inner
+            .as_ref()
+            .get_log_retention_policy(project_id)
+            .await
+    }
+    async fn set_log_retention_policy(
+        &self,
+        policy: crate::models::LogRetentionPolicy,
+    ) -> Result<()> {
+        self.inner.as_ref().set_log_retention_policy(policy).await
+    }
+    async fn get_expired_task_ids(
+        &self,
+        policy: &crate::models::LogRetentionPolicy,
+    ) -> Result<Vec<i32>> {
+        self.inner.as_ref().get_expired_task_ids(policy).await
+    }
+    async fn purge_tasks(&self, project_id: i32, task_ids: &[i32]) -> Result<()> {
+        self.inner.as_ref().purge_tasks(project_id, task_ids).await
+    }
+}
+
+#[async_trait]
+impl crate::db::store::ConcurrencyGroupManager for StoreWrapper {
+    async fn get_concurrency_groups(
+        &self,
+        project_id: i32,
+    ) -> Result<Vec<crate::models::ConcurrencyGroup>> {
+        self.inner.as_ref().get_concurrency_groups(project_id).await
+    }
+    async fn upsert_concurrency_group(
+        &self,
+        project_id: i32,
+        payload: crate::models::ConcurrencyGroupUpsert,
+    ) -> Result<crate::models::ConcurrencyGroup> {
+        self.inner
+            .as_ref()
+            .upsert_concurrency_group(project_id, payload)
+            .await
+    }
+    async fn delete_concurrency_group(&self, project_id: i32, name: &str) -> Result<()> {
+        self.inner
+            .as_ref()
+            .delete_concurrency_group(project_id, name)
+            .await
+    }
+    async fn claim_task_with_slots(
+        &self,
+        task_id: i32,
+        claimer: crate::models::TaskClaimer,
+        slots: &[crate::models::SlotRequest],
+    ) -> Result<crate::models::SlotClaim> {
+        self.inner
+            .as_ref()
+            .claim_task_with_slots(task_id, claimer, slots)
+            .await
+    }
+    async fn get_concurrency_slot_holders(
+        &self,
+    ) -> Result<Vec<crate::models::ConcurrencySlotHolder>> {
+        self.inner.as_ref().get_concurrency_slot_holders().await
+    }
+}
+
 #[async_trait]
 impl crate::db::store::StructuredOutputManager for StoreWrapper {
     async fn get_task_structured_outputs(
@@ -2243,6 +2697,14 @@ mod tests {
         async fn update_task(&self, _task: Task) -> Result<()> {
             Ok(())
         }
+        async fn set_waiting_task_priority(
+            &self,
+            _project_id: i32,
+            _task_id: i32,
+            _priority: Option<i32>,
+        ) -> Result<bool> {
+            Ok(false)
+        }
         async fn delete_task(&self, _project_id: i32, _task_id: i32) -> Result<()> {
             Ok(())
         }
@@ -2269,12 +2731,37 @@ mod tests {
         ) -> Result<Vec<TaskWithTpl>> {
             Ok(vec![])
         }
+        async fn get_queued_tasks(
+            &self,
+            _project_id: Option<i32>,
+            _limit: i32,
+        ) -> Result<Vec<Task>> {
+            Ok(vec![])
+        }
         async fn get_running_tasks_count(&self) -> Result<usize> {
             Ok(0)
         }
         async fn get_waiting_tasks_count(&self) -> Result<usize> {
             Ok(0)
         }
+        async fn link_task_hook(&self, _project_id: i32, _hook: TaskHook) -> Result<()> {
+            Ok(())
+        }
+        async fn create_hook_task(
+            &self,
+            _task: Task,
+            _parent_task_id: i32,
+            _hook_type: &str,
+        ) -> Result<Task> {
+            Err(crate::error::Error::Validation("Cannot create task".into()))
+        }
+        async fn get_task_hook_links(
+            &self,
+            _project_id: i32,
+            _task_id: i32,
+        ) -> Result<Vec<TaskHook>> {
+            Ok(vec![])
+        }
     }
 
     #[async_trait]
@@ -2315,6 +2802,14 @@ mod tests {
         ) -> Result<()> {
             Ok(())
         }
+
+        async fn set_schedule_last_fired(
+            &self,
+            _schedule_id: i32,
+            _fired: chrono::DateTime<chrono::Utc>,
+        ) -> Result<()> {
+            Ok(())
+        }
     }
 
     #[async_trait]
@@ -2357,6 +2852,30 @@ mod tests {
         async fn delete_api_token(&self, _user_id: i32, _token_id: i32) -> Result<()> {
             Ok(())
         }
+        async fn get_api_token_by_value(&self, _token: &str) -> Result<APIToken> {
+            Err(crate::error::Error::NotFound("Token not found".into()))
+        }
+        async fn touch_api_token(&self, _token_id: i32, _ip: Option<&str>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ServiceAccountManager for MockStore {
+        async fn get_service_accounts(&self) -> Result<Vec<ServiceAccount>> {
+            Ok(vec![])
+        }
+        async fn get_service_account(&self, _id: i32) -> Result<ServiceAccount> {
+            Err(crate::error::Error::NotFound(
+                "Service account not found".into(),
+            ))
+        }
+        async fn create_service_account(&self, account: ServiceAccount) -> Result<ServiceAccount> {
+            Ok(account)
+        }
+        async fn delete_service_account(&self, _id: i32) -> Result<()> {
+            Ok(())
+        }
     }
 
     #[async_trait]
@@ -2402,6 +2921,15 @@ mod tests {
         async fn touch_runner(&self, _runner_id: i32) -> Result<()> {
             Ok(())
         }
+        async fn claim_task(&self, _task_id: i32, _runner_id: i32) -> Result<bool> {
+            Ok(false)
+        }
+        async fn get_runner_task_ids(&self, _runner_id: i32) -> Result<Vec<i32>> {
+            Ok(vec![])
+        }
+        async fn requeue_stale_runner_tasks(&self, _timeout_secs: i64) -> Result<Vec<Task>> {
+            Ok(vec![])
+        }
     }
 
     #[async_trait]
@@ -2455,6 +2983,29 @@ mod tests {
         async fn delete_integration(&self, _project_id: i32, _integration_id: i32) -> Result<()> {
             Ok(())
         }
+        async fn get_integration_aliases(
+            &self,
+            _project_id: i32,
+            _integration_id: Option<i32>,
+        ) -> Result<Vec<IntegrationAlias>> {
+            Ok(vec![])
+        }
+        async fn get_integration_alias_by_alias(&self, _alias: &str) -> Result<IntegrationAlias> {
+            Err(crate::error::Error::NotFound(
+                "Integration alias not found".into(),
+            ))
+        }
+        async fn create_integration_alias(
+            &self,
+            _alias: IntegrationAlias,
+        ) -> Result<IntegrationAlias> {
+            Err(crate::error::Error::Validation(
+                "Cannot create integration alias".into(),
+            ))
+        }
+        async fn delete_integration_alias(&self, _project_id: i32, _alias_id: i32) -> Result<()> {
+            Ok(())
+        }
     }
 
     #[async_trait]
@@ -2858,6 +3409,46 @@ mod tests {
         }
     }
 
+    #[async_trait]
+    impl ProjectAccessManager for MockStore {
+        async fn get_project_user_role(
+            &self,
+            _project_id: i32,
+            _user_id: i32,
+        ) -> Result<Option<String>> {
+            Ok(None)
+        }
+        async fn set_project_user_role(
+            &self,
+            _project_id: i32,
+            _user_id: i32,
+            _role: &str,
+        ) -> Result<()> {
+            Ok(())
+        }
+        async fn get_template_role_perms(
+            &self,
+            _project_id: i32,
+            _template_id: i32,
+        ) -> Result<Vec<crate::models::TemplateRolePerm>> {
+            Ok(vec![])
+        }
+        async fn create_template_role_perm(
+            &self,
+            perm: crate::models::TemplateRolePerm,
+        ) -> Result<crate::models::TemplateRolePerm> {
+            Ok(perm)
+        }
+        async fn delete_template_role_perm(
+            &self,
+            _project_id: i32,
+            _template_id: i32,
+            _id: i32,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
     #[async_trait]
     impl crate::db::store::WorkflowManager for MockStore {
         async fn get_workflows(&self, _project_id: i32) -> Result<Vec<Workflow>> {
@@ -2933,6 +3524,7 @@ mod tests {
             &self,
             _workflow_id: i32,
             _project_id: i32,
+            _params: serde_json::Map<String, serde_json::Value>,
         ) -> Result<WorkflowRun> {
             Err(crate::error::Error::Validation("Cannot create run".into()))
         }
@@ -2944,6 +3536,36 @@ mod tests {
         ) -> Result<()> {
             Ok(())
         }
+        async fn get_workflow_run(&self, _id: i32, _project_id: i32) -> Result<WorkflowRun> {
+            Err(crate::error::Error::NotFound(
+                "Workflow run not found".into(),
+            ))
+        }
+        async fn get_active_workflow_runs(&self) -> Result<Vec<WorkflowRun>> {
+            Ok(vec![])
+        }
+        async fn get_workflow_run_nodes(
+            &self,
+            _run_id: i32,
+        ) -> Result<Vec<crate::models::workflow::WorkflowRunNode>> {
+            Ok(vec![])
+        }
+        async fn save_workflow_run_node(
+            &self,
+            _node: crate::models::workflow::WorkflowRunNode,
+        ) -> Result<()> {
+            Ok(())
+        }
+        async fn decide_workflow_approval(
+            &self,
+            _run_id: i32,
+            _node_id: i32,
+            _approved: bool,
+            _user_id: i32,
+            _comment: Option<String>,
+        ) -> Result<bool> {
+            Ok(false)
+        }
     }
 
     #[async_trait]
@@ -3061,6 +3683,11 @@ mod tests {
         ) -> Result<Vec<crate::models::drift::DriftConfig>> {
             Ok(vec![])
         }
+        async fn get_enabled_drift_configs(
+            &self,
+        ) -> Result<Vec<crate::models::drift::DriftConfig>> {
+            Ok(vec![])
+        }
         async fn get_drift_config(
             &self,
             _id: i32,
@@ -3087,6 +3714,14 @@ mod tests {
         ) -> Result<()> {
             Ok(())
         }
+        async fn update_drift_config_schedule(
+            &self,
+            _id: i32,
+            _project_id: i32,
+            _schedule: Option<String>,
+        ) -> Result<()> {
+            Ok(())
+        }
         async fn delete_drift_config(&self, _id: i32, _project_id: i32) -> Result<()> {
             Ok(())
         }
@@ -3110,12 +3745,25 @@ mod tests {
                 "Cannot create drift result".into(),
             ))
         }
+        async fn update_drift_result(
+            &self,
+            _id: i32,
+            _status: &str,
+            _summary: Option<String>,
+        ) -> Result<()> {
+            Ok(())
+        }
         async fn get_latest_drift_results(
             &self,
             _project_id: i32,
         ) -> Result<Vec<crate::models::drift::DriftResult>> {
             Ok(vec![])
         }
+        async fn get_pending_drift_results(
+            &self,
+        ) -> Result<Vec<crate::models::drift::DriftResult>> {
+            Ok(vec![])
+        }
     }
 
     #[async_trait]
@@ -3461,6 +4109,190 @@ mod tests {
         }
     }
 
+    #[async_trait]
+    impl crate::db::store::ClusterNodeManager for MockStore {
+        async fn touch_cluster_node(&self, _node_id: &str, _hostname: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn set_task_node(&self, _task_id: i32, _node_id: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn claim_task_for_node(&self, _task_id: i32, _node_id: &str) -> Result<bool> {
+            Ok(true)
+        }
+        async fn fail_orphaned_tasks(
+            &self,
+            _node_timeout_secs: i64,
+            _message: &str,
+        ) -> Result<Vec<i32>> {
+            Ok(vec![])
+        }
+        async fn set_workflow_run_node(&self, _run_id: i32, _node_id: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn claim_orphaned_workflow_runs(
+            &self,
+            _node_timeout_secs: i64,
+            _node_id: &str,
+        ) -> Result<Vec<WorkflowRun>> {
+            Ok(vec![])
+        }
+        async fn try_acquire_leader_lock(&self, _lease: &str, _node_id: &str) -> Result<bool> {
+            Ok(true)
+        }
+        async fn release_leader_lock(&self, _lease: &str, _node_id: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn get_cluster_leader(
+            &self,
+            _lease: &str,
+        ) -> Result<Option<crate::models::ClusterLeader>> {
+            Ok(None)
+        }
+    }
+
+    #[async_trait]
+    impl crate::db::store::EventOutboxManager for MockStore {
+        async fn create_outbox_event(&self, event: OutboxEvent) -> Result<OutboxEvent> {
+            Ok(event)
+        }
+        async fn get_outbox_event(&self, _id: i64) -> Result<OutboxEvent> {
+            Err(crate::error::Error::NotFound("Event not found".into()))
+        }
+        async fn get_undispatched_events(&self, _limit: i64) -> Result<Vec<OutboxEvent>> {
+            Ok(vec![])
+        }
+        async fn dispatch_outbox_event(
+            &self,
+            _event_id: i64,
+            _deliveries: Vec<NewEventDelivery>,
+        ) -> Result<()> {
+            Ok(())
+        }
+        async fn claim_due_deliveries(
+            &self,
+            _limit: i64,
+            _lease_secs: i64,
+        ) -> Result<Vec<EventDelivery>> {
+            Ok(vec![])
+        }
+        async fn finish_event_delivery(
+            &self,
+            _id: i64,
+            _status: DeliveryStatus,
+            _next_attempt: Option<DateTime<Utc>>,
+            _error: Option<String>,
+        ) -> Result<()> {
+            Ok(())
+        }
+        async fn get_event_deliveries(
+            &self,
+            _project_id: i32,
+            _status: Option<DeliveryStatus>,
+            _limit: i64,
+        ) -> Result<Vec<EventDelivery>> {
+            Ok(vec![])
+        }
+        async fn get_event_delivery(&self, _project_id: i32, _id: i64) -> Result<EventDelivery> {
+            Err(crate::error::Error::NotFound("Delivery not found".into()))
+        }
+        async fn redeliver_event_delivery(
+            &self,
+            _project_id: i32,
+            _id: i64,
+        ) -> Result<EventDelivery> {
+            Err(crate::error::Error::NotFound("Delivery not found".into()))
+        }
+        async fn get_event_delivery_logs(&self, _delivery_id: i64) -> Result<Vec<WebhookLog>> {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl crate::db::store::TaskLogArchiveManager for MockStore {
+        async fn get_tasks_to_archive(
+            &self,
+            _finished_before: chrono::DateTime<chrono::Utc>,
+            _limit: i64,
+        ) -> Result<Vec<(i32, i32)>> {
+            Ok(vec![])
+        }
+        async fn get_task_log_archive(
+            &self,
+            _task_id: i32,
+        ) -> Result<Option<crate::models::TaskLogArchive>> {
+            Ok(None)
+        }
+        async fn complete_task_log_archive(
+            &self,
+            _archive: crate::models::TaskLogArchive,
+            _last_output_id: i32,
+        ) -> Result<()> {
+            Ok(())
+        }
+        async fn get_log_retention_policies(
+            &self,
+        ) -> Result<Vec<crate::models::LogRetentionPolicy>> {
+            Ok(vec![])
+        }
+        async fn get_log_retention_policy(
+            &self,
+            _project_id: i32,
+        ) -> Result<Option<crate::models::LogRetentionPolicy>> {
+            Ok(None)
+        }
+        async fn set_log_retention_policy(
+            &self,
+            _policy: crate::models::LogRetentionPolicy,
+        ) -> Result<()> {
+            Ok(())
+        }
+        async fn get_expired_task_ids(
+            &self,
+            _policy: &crate::models::LogRetentionPolicy,
+        ) -> Result<Vec<i32>> {
+            Ok(vec![])
+        }
+        async fn purge_tasks(&self, _project_id: i32, _task_ids: &[i32]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl crate::db::store::ConcurrencyGroupManager for MockStore {
+        async fn get_concurrency_groups(
+            &self,
+            _project_id: i32,
+        ) -> Result<Vec<crate::models::ConcurrencyGroup>> {
+            Ok(vec![])
+        }
+        async fn upsert_concurrency_group(
+            &self,
+            _project_id: i32,
+            _payload: crate::models::ConcurrencyGroupUpsert,
+        ) -> Result<crate::models::ConcurrencyGroup> {
+            Err(crate::error::Error::Validation(
+                "Cannot create concurrency group".into(),
+            ))
+        }
+        async fn delete_concurrency_group(&self, _project_id: i32, _name: &str) -> Result<()> {
+            Ok(())
+        }
+        async fn claim_task_with_slots(
+            &self,
+            _task_id: i32,
+            _claimer: crate::models::TaskClaimer,
+            _slots: &[crate::models::SlotRequest],
+        ) -> Result<crate::models::SlotClaim> {
+            Ok(crate::models::SlotClaim::Claimed)
+        }
+        async fn get_concurrency_slot_holders(
+            &self,
+        ) -> Result<Vec<crate::models::ConcurrencySlotHolder>> {
+            Ok(vec![])
+        }
+    }
+
     #[async_trait]
     impl crate::db::store::StructuredOutputManager for MockStore {
         async fn get_task_structured_outputs(
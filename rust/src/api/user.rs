@@ -15,9 +15,9 @@ use std::sync::Arc;
 
 use crate::api::extractors::AuthUser;
 use crate::api::state::AppState;
-use crate::db::store::{TokenManager, UserManager};
+use crate::db::store::{ProjectStore, TemplateManager, TokenManager, UserManager};
 use crate::error::{Error, Result};
-use crate::models::{APIToken, User};
+use crate::models::{APIToken, APITokenCreate, User};
 
 // ============================================================================
 // Свободные функции для использования в routes
@@ -45,28 +45,97 @@ pub async fn get_api_tokens(
     Ok(Json(tokens))
 }
 
-/// Создаёт новый API токен
-pub async fn create_api_token(
-    State(state): State<Arc<AppState>>,
-    auth_user: AuthUser,
-) -> std::result::Result<
-    (StatusCode, Json<APIToken>),
-    (StatusCode, Json<crate::api::middleware::ErrorResponse>),
-> {
+type ApiError = (StatusCode, Json<crate::api::middleware::ErrorResponse>);
+
+fn bad_request(message: impl Into<String>) -> ApiError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(crate::api::middleware::ErrorResponse::new(message).with_code("INVALID_TOKEN_PARAMS")),
+    )
+}
+
+/// Проверяет параметры и выпускает API-токен владельцу `user_id`
+///
+/// Используется и для личных токенов, и для токенов сервисных аккаунтов.
+pub(crate) async fn issue_api_token(
+    state: &AppState,
+    user_id: i32,
+    payload: APITokenCreate,
+) -> std::result::Result<APIToken, ApiError> {
+    let now = Utc::now();
+    let expires_at = match (payload.expires_at, payload.expires_in_days) {
+        (Some(_), Some(_)) => {
+            return Err(bad_request("Укажите либо expires_at, либо expires_in_days"));
+        }
+        (Some(at), None) if at <= now => {
+            return Err(bad_request("Срок действия токена уже истёк"));
+        }
+        (Some(at), None) => Some(at),
+        (None, Some(days)) if days <= 0 => {
+            return Err(bad_request("expires_in_days должен быть положительным"));
+        }
+        (None, Some(days)) => Some(now + chrono::Duration::days(days)),
+        (None, None) => None,
+    };
+
+    if !payload.template_ids.is_empty() && payload.project_ids.is_empty() {
+        return Err(bad_request(
+            "Ограничение по шаблонам требует ограничения по проектам",
+        ));
+    }
+    for &project_id in &payload.project_ids {
+        state
+            .store
+            .get_project(project_id)
+            .await
+            .map_err(|_| bad_request(format!("Проект {project_id} не найден")))?;
+    }
+    for &template_id in &payload.template_ids {
+        let mut found = false;
+        for &project_id in &payload.project_ids {
+            if state
+                .store
+                .get_template(project_id, template_id)
+                .await
+                .is_ok()
+            {
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            return Err(bad_request(format!(
+                "Шаблон {template_id} не найден в указанных проектах"
+            )));
+        }
+    }
+
     // Генерируем случайный токен
     let mut token_bytes = vec![0u8; 32];
     rand::rng().fill_bytes(&mut token_bytes);
     let token_str = base64::engine::general_purpose::STANDARD.encode(&token_bytes);
 
-    let token = state
+    let mut scopes = payload.scopes;
+    scopes.dedup();
+
+    state
         .store
         .create_api_token(APIToken {
             id: 0, // Будет установлен БД
-            user_id: auth_user.user_id,
-            name: format!("Token {}", Utc::now().format("%Y-%m-%d %H:%M")),
+            user_id,
+            name: payload
+                .name
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| format!("Token {}", now.format("%Y-%m-%d %H:%M"))),
             token: token_str.to_lowercase(),
-            created: Utc::now(),
+            created: now,
             expired: false,
+            expires_at,
+            scopes,
+            project_ids: payload.project_ids,
+            template_ids: payload.template_ids,
+            last_used_at: None,
+            last_used_ip: None,
         })
         .await
         .map_err(|e| {
@@ -74,8 +143,20 @@ pub async fn create_api_token(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(crate::api::middleware::ErrorResponse::new(e.to_string())),
             )
-        })?;
+        })
+}
 
+/// Создаёт новый API токен
+///
+/// Тело запроса необязательно: без него выпускается бессрочный токен со
+/// всеми правами владельца.
+pub async fn create_api_token(
+    State(state): State<Arc<AppState>>,
+    auth_user: AuthUser,
+    payload: Option<Json<APITokenCreate>>,
+) -> std::result::Result<(StatusCode, Json<APIToken>), ApiError> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let token = issue_api_token(&state, auth_user.user_id, payload).await?;
     Ok((StatusCode::CREATED, Json(token)))
 }
 
@@ -158,6 +239,7 @@ impl UserController {
                 token: token_str.to_lowercase(),
                 created: Utc::now(),
                 expired: false,
+                ..Default::default()
             })
             .await?;
 
@@ -1,15 +1,33 @@
 //! CLI - Runner Command
 //!
 //! Velum Runner Agent — запускается на удалённой машине, поллит сервер на задачи,
-//! выполняет ansible/terraform/bash локально и стримит логи обратно.
+//! выполняет их тем же `LocalJob`, что и сервер (checkout репозитория, ключи,
+//! окружение, параметры шаблона), стримит логи и статус обратно и останавливает
+//! задачу по запросу пользователя.
 //!
 //! Использование:
 //! ```text
 //! velum runner --token `<TOKEN>` --server-url http://velum-server:3000
 //! ```
 
+use crate::api::runners::{RUNNER_KEY_HEADER, RUNNER_TOKEN_HEADER};
 use crate::cli::CliResult;
+use crate::models::RunnerCapabilities;
+use crate::services::runner_job::RunnerJob;
+use crate::services::task_logger::{BasicLogger, TaskLogger, TaskStatus};
+use crate::utils::encryption::SealingKeyPair;
 use clap::Args;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, mpsc, watch};
+
+type AgentResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Интервал heartbeat — с ним же раннер узнаёт о запросах остановки
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Интервал отправки накопленных логов
+const LOG_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
 
 /// Команда runner
 #[derive(Debug, Args)]
@@ -63,37 +81,62 @@ impl RunnerCommand {
         Ok(())
     }
 
-    async fn run_agent(
-        &self,
-        token: String,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+    async fn run_agent(&self, token: String) -> AgentResult<()> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
             .build()?;
+        let mut client = RunnerClient {
+            http,
+            server_url: self.server_url.trim_end_matches('/').to_string(),
+            token,
+            runner_id: 0,
+        };
 
         // Регистрация / переподключение
-        let runner_id = self.register(&client, &token).await?;
+        client.runner_id = self.register(&client).await?;
         println!(
-            "✅ Runner registered (id={runner_id}). Polling for tasks every {}s...",
-            self.poll_interval
+            "✅ Runner registered (id={}). Polling for tasks every {}s...",
+            client.runner_id, self.poll_interval
         );
 
-        let mut heartbeat_tick = tokio::time::interval(std::time::Duration::from_secs(30));
-        let poll_delay = std::time::Duration::from_secs(self.poll_interval);
+        let slots = Arc::new(Semaphore::new(self.max_parallel.max(1) as usize));
+        let (stop_tx, stop_rx) = watch::channel(Vec::<i32>::new());
+        let mut heartbeat_tick = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut poll_tick = tokio::time::interval(Duration::from_secs(self.poll_interval.max(1)));
 
         loop {
             tokio::select! {
                 _ = heartbeat_tick.tick() => {
-                    let _ = self.heartbeat(&client, runner_id).await;
+                    match client.heartbeat().await {
+                        Ok(stop) => {
+                            stop_tx.send_if_modified(|current| {
+                                let changed = *current != stop;
+                                *current = stop;
+                                changed
+                            });
+                        }
+                        Err(e) => eprintln!("Heartbeat error: {e}"),
+                    }
                 }
-                _ = tokio::time::sleep(poll_delay) => {
-                    match self.poll_task(&client, runner_id).await {
+                _ = poll_tick.tick() => {
+                    // Свободного слота нет — задачу не запрашиваем
+                    let Ok(permit) = slots.clone().try_acquire_owned() else {
+                        continue;
+                    };
+                    let keys = SealingKeyPair::generate();
+                    match client.poll_task(&keys).await {
                         Ok(Some(assignment)) => {
                             println!("▶  Task #{} (project={}, template={})",
                                 assignment.task_id, assignment.project_id, assignment.template_id);
-                            if let Err(e) = self.execute_task(&client, &assignment).await {
-                                eprintln!("   Task #{} failed: {e}", assignment.task_id);
-                            }
+                            let client = client.clone();
+                            let stop_rx = stop_rx.clone();
+                            tokio::spawn(async move {
+                                let task_id = assignment.task_id;
+                                if let Err(e) = execute_task(&client, assignment, keys, stop_rx).await {
+                                    eprintln!("   Task #{task_id} failed: {e}");
+                                }
+                                drop(permit);
+                            });
                         }
                         Ok(None) => {} // нет задач — продолжаем поллить
                         Err(e) => eprintln!("Poll error: {e}"),
@@ -104,20 +147,18 @@ impl RunnerCommand {
     }
 
     /// Регистрирует раннер на сервере, возвращает runner_id
-    async fn register(
-        &self,
-        client: &reqwest::Client,
-        token: &str,
-    ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+    async fn register(&self, client: &RunnerClient) -> AgentResult<i32> {
         let payload = serde_json::json!({
-            "token": token,
+            "token": client.token,
             "name": self.name.as_deref().unwrap_or("velum-runner"),
             "max_parallel_tasks": self.max_parallel,
             "tag": self.tag,
+            "capabilities": detect_capabilities(self.max_parallel, self.tag.as_deref()),
         });
 
         let resp = client
-            .post(format!("{}/api/internal/runners", self.server_url))
+            .http
+            .post(format!("{}/api/internal/runners", client.server_url))
             .json(&payload)
             .send()
             .await?;
@@ -134,35 +175,46 @@ impl RunnerCommand {
             .ok_or("Missing 'id' in registration response")? as i32;
         Ok(id)
     }
+}
 
-    /// Heartbeat — обновляет last_active на сервере
-    async fn heartbeat(
-        &self,
-        client: &reqwest::Client,
-        runner_id: i32,
-    ) -> Result<(), reqwest::Error> {
-        let _ = client
-            .post(format!(
-                "{}/api/internal/runners/{runner_id}",
-                self.server_url
-            ))
+/// HTTP-клиент внутреннего API раннеров
+#[derive(Clone)]
+struct RunnerClient {
+    http: reqwest::Client,
+    server_url: String,
+    token: String,
+    runner_id: i32,
+}
+
+impl RunnerClient {
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .post(format!("{}{path}", self.server_url))
+            .header(RUNNER_TOKEN_HEADER, &self.token)
+    }
+
+    /// Heartbeat — обновляет last_active на сервере, возвращает задачи для остановки
+    async fn heartbeat(&self) -> AgentResult<Vec<i32>> {
+        let resp = self
+            .post(&format!("/api/internal/runners/{}", self.runner_id))
             .json(&serde_json::json!({}))
             .send()
-            .await?;
-        Ok(())
+            .await?
+            .error_for_status()?;
+        let body: HeartbeatResponse = resp.json().await?;
+        Ok(body.stop)
     }
 
     /// Запрашивает задачу — 200 = есть задача, 204 = нет задач
-    async fn poll_task(
-        &self,
-        client: &reqwest::Client,
-        runner_id: i32,
-    ) -> Result<Option<TaskAssignment>, Box<dyn std::error::Error + Send + Sync>> {
-        let resp = client
+    async fn poll_task(&self, keys: &SealingKeyPair) -> AgentResult<Option<TaskAssignment>> {
+        let resp = self
+            .http
             .get(format!(
-                "{}/api/internal/runners/{runner_id}/task",
-                self.server_url
+                "{}/api/internal/runners/{}/task",
+                self.server_url, self.runner_id
             ))
+            .header(RUNNER_TOKEN_HEADER, &self.token)
+            .header(RUNNER_KEY_HEADER, keys.public_key())
             .send()
             .await?;
 
@@ -170,161 +222,222 @@ impl RunnerCommand {
             return Ok(None);
         }
         if !resp.status().is_success() {
-            return Err(format!("Poll failed: {}", resp.status()).into());
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("Poll failed ({status}): {body}").into());
         }
 
         let assignment: TaskAssignment = resp.json().await?;
         Ok(Some(assignment))
     }
 
-    /// Выполняет задачу локально и стримит логи на сервер
-    async fn execute_task(
+    /// Отправляет буфер логов на сервер
+    async fn flush_logs(
         &self,
-        client: &reqwest::Client,
-        assignment: &TaskAssignment,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        use tokio::io::{AsyncBufReadExt, BufReader};
-        use tokio::process::Command;
-
-        // Строим команду на основе типа задачи
-        let (program, args) = self.build_command(assignment);
-
-        println!("   Executing: {program} {}", args.join(" "));
-
-        let mut child = Command::new(&program)
-            .args(&args)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()?;
-
-        let stdout = child.stdout.take().expect("stdout piped");
-        let stderr = child.stderr.take().expect("stderr piped");
+        project_id: i32,
+        task_id: i32,
+        lines: &[LogLine],
+    ) -> AgentResult<()> {
+        let payload = serde_json::json!({ "project_id": project_id, "output": lines });
+        self.post(&format!("/api/internal/tasks/{task_id}/log"))
+            .json(&payload)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
 
-        let task_id = assignment.task_id;
-        let server_url = self.server_url.clone();
-        let client_clone = client.clone();
+    /// Сообщает серверу статус задачи
+    async fn report_status(&self, report: &StatusReport) -> AgentResult<()> {
+        self.post(&format!("/api/internal/tasks/{}/status", report.task_id))
+            .json(report)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
 
-        // Читаем stdout/stderr в отдельных задачах и буферизуем логи
-        let mut lines_stdout = BufReader::new(stdout).lines();
-        let mut lines_stderr = BufReader::new(stderr).lines();
+/// Выполняет задание через `LocalJob`, стримит логи и отчитывается о статусе
+async fn execute_task(
+    client: &RunnerClient,
+    assignment: TaskAssignment,
+    keys: SealingKeyPair,
+    mut stop_rx: watch::Receiver<Vec<i32>>,
+) -> AgentResult<()> {
+    let task_id = assignment.task_id;
+    let project_id = assignment.project_id;
+    let mut report = StatusReport::new(task_id, project_id, TaskStatus::Running);
+
+    let (log_tx, log_rx) = mpsc::unbounded_channel();
+    let logger = Arc::new(BasicLogger::new());
+    logger.add_log_listener(Box::new(move |_time, msg| {
+        let _ = log_tx.send(LogLine::now(msg, task_id));
+    }));
+    let forwarder = tokio::spawn(forward_logs(client.clone(), project_id, task_id, log_rx));
+
+    let work_dir = std::env::temp_dir().join(format!("velum_runner_task_{project_id}_{task_id}"));
+    let prepared = match tokio::fs::create_dir_all(work_dir.join("tmp")).await {
+        Ok(()) => assignment
+            .job
+            .into_local_job(&keys, logger.clone(), work_dir)
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(format!("Failed to create work dir: {e}")),
+    };
+    let mut job = match prepared {
+        Ok(job) => job,
+        Err(e) => {
+            logger.log(&e);
+            drop(logger);
+            let _ = forwarder.await;
+            report.status = TaskStatus::Error;
+            report.message = Some(e);
+            return client.report_status(&report).await;
+        }
+    };
 
-        let mut log_buffer: Vec<LogLine> = Vec::new();
-        let flush_interval = std::time::Duration::from_secs(2);
-        let mut last_flush = std::time::Instant::now();
+    client.report_status(&report).await?;
 
-        loop {
-            tokio::select! {
-                line = lines_stdout.next_line() => {
-                    match line? {
-                        Some(l) => {
-                            println!("   | {l}");
-                            log_buffer.push(LogLine::now(l, task_id));
-                        }
-                        None => break,
-                    }
-                }
-                line = lines_stderr.next_line() => {
-                    if let Ok(Some(l)) = line {
-                        eprintln!("   ! {l}");
-                        log_buffer.push(LogLine::now(format!("[stderr] {l}"), task_id));
-                    }
-                }
-            }
+    let incoming_version = job.incoming_version.clone();
+    let result = tokio::select! {
+        result = job.run("runner", incoming_version.as_deref(), "default") => Some(result),
+        _ = wait_for_stop(&mut stop_rx, task_id) => None,
+    };
 
-            if last_flush.elapsed() >= flush_interval && !log_buffer.is_empty() {
-                let _ = Self::flush_logs(&client_clone, &server_url, task_id, &log_buffer).await;
-                log_buffer.clear();
-                last_flush = std::time::Instant::now();
-            }
+    report.status = match result {
+        Some(Ok(())) => TaskStatus::Success,
+        Some(Err(e)) => {
+            report.message = Some(e.to_string());
+            TaskStatus::Error
         }
+        None => {
+            // Процесс приложения уже завершён отменой future (kill_on_drop)
+            job.kill();
+            job.log("Task stopped by user");
+            TaskStatus::Stopped
+        }
+    };
+    println!("   Task #{task_id} finished: {}", report.status);
 
-        // Ждём завершения процесса
-        let exit = child.wait().await?;
-        let final_line = if exit.success() {
-            format!("Task #{task_id} completed successfully")
-        } else {
-            format!("Task #{task_id} failed with exit code {:?}", exit.code())
-        };
-
-        println!("   {final_line}");
-        log_buffer.push(LogLine::now(final_line, task_id));
+    if let Some((hash, message)) = logger.commit() {
+        report.commit_hash = Some(hash);
+        report.commit_message = Some(message);
+    }
+    job.cleanup();
+    drop(job);
+    drop(logger);
 
-        // Финальный flush
-        if !log_buffer.is_empty() {
-            let _ = Self::flush_logs(client, &self.server_url, task_id, &log_buffer).await;
-        }
+    // Логи отправляются до итогового статуса: после него задача уже не за раннером
+    let _ = forwarder.await;
+    client.report_status(&report).await
+}
 
-        Ok(())
+/// Ждёт, пока сервер не попросит остановить задачу
+async fn wait_for_stop(stop_rx: &mut watch::Receiver<Vec<i32>>, task_id: i32) {
+    if stop_rx
+        .wait_for(|stop| stop.contains(&task_id))
+        .await
+        .is_err()
+    {
+        // Агент завершается — остановка больше не придёт
+        std::future::pending::<()>().await;
     }
+}
 
-    /// Строит команду для запуска задачи
-    fn build_command(&self, assignment: &TaskAssignment) -> (String, Vec<String>) {
-        // Ansible playbook
-        if let Some(ref playbook) = assignment.playbook {
-            let mut args = vec![playbook.clone()];
-            if assignment.dry_run {
-                args.push("--check".to_string());
-            }
-            if assignment.debug {
-                args.push("-vvv".to_string());
-            }
-            if let Some(ref extra) = assignment.arguments {
-                args.push("--extra-vars".to_string());
-                args.push(extra.clone());
+/// Пересылает логи задачи на сервер пачками, пока логгер не будет закрыт
+async fn forward_logs(
+    client: RunnerClient,
+    project_id: i32,
+    task_id: i32,
+    mut rx: mpsc::UnboundedReceiver<LogLine>,
+) {
+    let mut buffer: Vec<LogLine> = Vec::new();
+    let mut tick = tokio::time::interval(LOG_FLUSH_INTERVAL);
+    loop {
+        tokio::select! {
+            line = rx.recv() => match line {
+                Some(line) => buffer.push(line),
+                None => break,
+            },
+            _ = tick.tick() => {
+                if !buffer.is_empty() {
+                    if let Err(e) = client.flush_logs(project_id, task_id, &buffer).await {
+                        eprintln!("   Task #{task_id}: failed to send logs: {e}");
+                    }
+                    buffer.clear();
+                }
             }
-            return ("ansible-playbook".to_string(), args);
         }
-
-        // Terraform
-        if let Some(ref env) = assignment.environment {
-            if env.contains("terraform") {
-                let cmd = if assignment.dry_run { "plan" } else { "apply" };
-                return (
-                    "terraform".to_string(),
-                    vec![cmd.to_string(), "-auto-approve".to_string()],
-                );
-            }
+    }
+    if !buffer.is_empty() {
+        if let Err(e) = client.flush_logs(project_id, task_id, &buffer).await {
+            eprintln!("   Task #{task_id}: failed to send logs: {e}");
         }
-
-        // Bash fallback
-        (
-            "bash".to_string(),
-            vec![
-                "-c".to_string(),
-                "echo 'No playbook configured'".to_string(),
-            ],
-        )
     }
+}
 
-    /// Отправляет буфер логов на сервер
-    async fn flush_logs(
-        client: &reqwest::Client,
-        server_url: &str,
-        task_id: i32,
-        lines: &[LogLine],
-    ) -> Result<(), reqwest::Error> {
-        let payload = serde_json::json!({ "output": lines });
-        let _ = client
-            .post(format!("{server_url}/api/internal/tasks/{task_id}/log"))
-            .json(&payload)
-            .send()
-            .await?;
-        Ok(())
+/// Определяет возможности раннера по исполняемым файлам в `PATH`
+fn detect_capabilities(max_parallel: u32, tag: Option<&str>) -> RunnerCapabilities {
+    RunnerCapabilities {
+        ansible: in_path("ansible-playbook"),
+        terraform: in_path("terraform") || in_path("tofu"),
+        bash: in_path("bash"),
+        powershell: in_path("pwsh"),
+        kubernetes: in_path("kubectl"),
+        max_parallel_tasks: max_parallel as i32,
+        tags: tag.map(|t| vec![t.to_string()]).unwrap_or_default(),
     }
 }
 
+/// Проверяет наличие исполняемого файла в `PATH`
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths)
+            .any(|dir| dir.join(program).is_file() || dir.join(format!("{program}.exe")).is_file())
+    })
+}
+
 /// Задача, полученная от сервера
 #[derive(Debug, serde::Deserialize)]
 struct TaskAssignment {
     task_id: i32,
     project_id: i32,
     template_id: i32,
-    playbook: Option<String>,
-    environment: Option<String>,
-    arguments: Option<String>,
-    git_branch: Option<String>,
-    debug: bool,
-    dry_run: bool,
+    job: RunnerJob,
+}
+
+/// Ответ сервера на heartbeat
+#[derive(Debug, Default, serde::Deserialize)]
+struct HeartbeatResponse {
+    #[serde(default)]
+    stop: Vec<i32>,
+}
+
+/// Статус задачи для отправки на сервер
+#[derive(Debug, serde::Serialize)]
+struct StatusReport {
+    task_id: i32,
+    project_id: i32,
+    status: TaskStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_message: Option<String>,
+}
+
+impl StatusReport {
+    fn new(task_id: i32, project_id: i32, status: TaskStatus) -> Self {
+        Self {
+            task_id,
+            project_id,
+            status,
+            message: None,
+            commit_hash: None,
+            commit_message: None,
+        }
+    }
 }
 
 /// Строка лога для отправки на сервер
@@ -375,53 +488,36 @@ mod tests {
     }
 
     #[test]
-    fn test_build_command_ansible() {
-        let cmd = RunnerCommand {
-            token: None,
-            server_url: "http://localhost:3000".to_string(),
-            name: None,
-            poll_interval: 5,
-            max_parallel: 1,
-            tag: None,
-        };
-        let assignment = TaskAssignment {
-            task_id: 1,
-            project_id: 1,
-            template_id: 1,
-            playbook: Some("site.yml".to_string()),
-            environment: None,
-            arguments: None,
-            git_branch: None,
-            debug: false,
-            dry_run: false,
-        };
-        let (prog, args) = cmd.build_command(&assignment);
-        assert_eq!(prog, "ansible-playbook");
-        assert_eq!(args[0], "site.yml");
+    fn test_status_report_serialization() {
+        let mut report = StatusReport::new(7, 1, TaskStatus::Stopped);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "stopped");
+        assert!(json.get("message").is_none());
+
+        report.commit_hash = Some("abc123".to_string());
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["commit_hash"], "abc123");
     }
 
     #[test]
-    fn test_build_command_dry_run() {
-        let cmd = RunnerCommand {
-            token: None,
-            server_url: "http://localhost:3000".to_string(),
-            name: None,
-            poll_interval: 5,
-            max_parallel: 1,
-            tag: None,
-        };
-        let assignment = TaskAssignment {
-            task_id: 1,
-            project_id: 1,
-            template_id: 1,
-            playbook: Some("deploy.yml".to_string()),
-            environment: None,
-            arguments: None,
-            git_branch: None,
-            debug: false,
-            dry_run: true,
-        };
-        let (_, args) = cmd.build_command(&assignment);
-        assert!(args.contains(&"--check".to_string()));
+    fn test_detect_capabilities_uses_tag() {
+        let caps = detect_capabilities(3, Some("gpu"));
+        assert_eq!(caps.max_parallel_tasks, 3);
+        assert_eq!(caps.tags, vec!["gpu".to_string()]);
+        assert!(!in_path("velum-definitely-missing-binary"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_stop_resolves_for_task() {
+        let (tx, mut rx) = watch::channel(Vec::new());
+        let waiter = tokio::spawn(async move { wait_for_stop(&mut rx, 5).await });
+        tx.send(vec![4]).unwrap();
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        tx.send(vec![4, 5]).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
     }
 }
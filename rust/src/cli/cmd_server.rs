@@ -40,8 +40,27 @@ impl ServerCommand {
             // Сид admin-пользователя при первом запуске
             Self::seed_admin_if_empty(store.as_ref()).await;
 
+            // Выбор лидера: планировщик, автобэкап и очистка потерянных задач
+            // работают только на одном узле кластера
+            let election = crate::services::leader_election::init(
+                crate::services::leader_election::LeaderElection::connect(
+                    store.clone(),
+                    crate::services::leader_election::config_from_env(&config.ha)?,
+                )
+                .await?,
+            );
+            election.renew().await;
+            crate::services::leader_election::spawn_renewal(election.clone());
+            let leadership = election.leadership();
+            println!(
+                "Leader election: {} (leader: {})",
+                election.backend(),
+                leadership.is_leader()
+            );
+
             // Запускаем планировщик задач
-            let scheduler = crate::services::scheduler::SchedulePool::new(store.clone());
+            let scheduler = crate::services::scheduler::SchedulePool::new(store.clone())
+                .with_leadership(leadership.clone());
             if let Err(e) = scheduler.start().await {
                 eprintln!("Warning: scheduler failed to start: {e}");
             } else {
@@ -60,9 +79,75 @@ impl ServerCommand {
             });
             println!("Job pool started");
 
+            // Возвращаем в очередь задачи раннеров, переставших слать heartbeat
+            crate::services::remote_runners::spawn_orphan_reaper(
+                store.clone(),
+                crate::services::remote_runners::RemoteRunnersConfig::default(),
+                leadership.clone(),
+            );
+
+            // Отмечаем узел живым и завершаем задачи исчезнувших узлов и раннеров
+            let reaper_config = crate::services::task_reaper::TaskReaperConfig::default();
+            crate::services::task_reaper::spawn_node_heartbeat(
+                store.clone(),
+                reaper_config.clone(),
+            );
+            crate::services::task_reaper::spawn_task_reaper(
+                store.clone(),
+                reaper_config,
+                leadership.clone(),
+            );
+
+            // Продолжаем запуски workflow, чей узел исчез
+            crate::services::workflow_executor::spawn_resume(
+                Arc::new(api::state::AppState::new(
+                    store.clone(),
+                    config.as_ref().clone(),
+                    None,
+                )),
+                leadership.clone(),
+            );
+
+            // Архивируем логи завершённых задач и применяем политики хранения
+            let log_archive = match crate::services::log_archive::config_from_env() {
+                Ok(Some(archive_config)) => {
+                    let archive = crate::services::log_archive::init(
+                        crate::services::log_archive::LogArchive::from_config(archive_config),
+                    );
+                    println!("Task log archive enabled ({})", archive.backend());
+                    Some(archive)
+                }
+                Ok(None) => None,
+                Err(e) => {
+                    eprintln!("Warning: task log archive disabled: {e}");
+                    None
+                }
+            };
+            crate::services::log_archive::spawn_log_maintenance(
+                store.clone(),
+                log_archive,
+                crate::services::log_archive::maintenance_interval(),
+                leadership.clone(),
+            );
+
+            // Диспетчер событий задач: политики уведомлений и webhook проектов
+            crate::services::event_outbox::spawn_event_dispatcher(
+                store.clone(),
+                crate::services::event_outbox::dispatch_interval(),
+            );
+
             // Запускаем Telegram Bot (если токен задан в конфиге/env)
             crate::services::telegram_bot::start_bot_if_configured(&config);
 
+            // Загружаем плагины (если задан SEMAPHORE_PLUGINS_DIR)
+            if let Some(plugins_config) = crate::plugins::config_from_env() {
+                let manager = crate::plugins::init_plugins(plugins_config, store.as_ref()).await;
+                println!(
+                    "Plugins loaded: {}",
+                    manager.read().await.list_plugins().await.len()
+                );
+            }
+
             // Запускаем сервис автобэкапа (если включён через env)
             let backup_enabled = std::env::var("SEMAPHORE_AUTO_BACKUP_ENABLED")
                 .map(|v| v == "true" || v == "1")
@@ -85,13 +170,38 @@ impl ServerCommand {
                 let backup_svc = crate::services::auto_backup::AutoBackupService::new(
                     backup_config,
                     store.clone(),
-                );
+                )
+                .with_leadership(leadership.clone());
                 backup_svc.start().await;
                 println!("Auto backup service started");
             }
 
+            // Отозванные при выходе JWT общие для HTTP API и gRPC
+            let token_blacklist = api::token_blacklist::TokenBlacklist::new();
+
+            // Запускаем gRPC сервер (если включён через env)
+            let grpc_enabled = std::env::var("SEMAPHORE_GRPC_ENABLED")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false);
+            if grpc_enabled {
+                let address = std::env::var("SEMAPHORE_GRPC_ADDRESS")
+                    .unwrap_or_else(|_| "0.0.0.0:50051".to_string())
+                    .parse()
+                    .map_err(|e| {
+                        crate::error::Error::Config(format!("Invalid SEMAPHORE_GRPC_ADDRESS: {e}"))
+                    })?;
+                let grpc = crate::grpc::GrpcServer::with_address(address, store.clone())
+                    .with_token_blacklist(token_blacklist.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = grpc.serve().await {
+                        eprintln!("Warning: gRPC server error: {e}");
+                    }
+                });
+                println!("gRPC server started at {address}");
+            }
+
             // Создаём приложение
-            let app = api::create_app(store.clone()).await;
+            let app = api::create_app_with_token_blacklist(store.clone(), token_blacklist).await;
 
             // Запускаем сервер с graceful shutdown
             let listener = tokio::net::TcpListener::bind(format!("{}:{}", self.host, self.port))
@@ -135,6 +245,10 @@ impl ServerCommand {
                         eprintln!("Warning: scheduler stop error: {}", e);
                     }
 
+                    // Отпускаем лидерство, чтобы другой узел подхватил фоновые службы сразу
+                    println!("Releasing cluster leadership...");
+                    election.release().await;
+
                     println!("Waiting for running jobs to finish (max 30s)...");
                     job_pool.shutdown().await;
 
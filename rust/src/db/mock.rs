@@ -19,10 +19,40 @@ pub struct MockStore {
     inventories: RwLock<HashMap<i32, Inventory>>,
     repositories: RwLock<HashMap<i32, Repository>>,
     environments: RwLock<HashMap<i32, Environment>>,
+    access_keys: RwLock<HashMap<i32, AccessKey>>,
     playbook_runs: RwLock<HashMap<i32, PlaybookRun>>,
     organizations: RwLock<HashMap<i32, Organization>>,
     organization_users: RwLock<HashMap<i32, OrganizationUser>>,
     terraform_plans: RwLock<HashMap<(i32, i32), TerraformPlan>>,
+    task_hooks: RwLock<Vec<TaskHook>>,
+    task_outputs: RwLock<Vec<TaskOutput>>,
+    /// Именованные outputs задач: задача → ключ → значение
+    structured_outputs: RwLock<HashMap<i32, HashMap<String, serde_json::Value>>>,
+    drift_configs: RwLock<Vec<crate::models::drift::DriftConfig>>,
+    drift_results: RwLock<Vec<crate::models::drift::DriftResult>>,
+    runners: RwLock<HashMap<i32, Runner>>,
+    runner_claims: RwLock<HashMap<i32, i32>>,
+    concurrency_groups: RwLock<Vec<crate::models::ConcurrencyGroup>>,
+    /// Занятые слоты групп: (ключ группы, задача)
+    concurrency_slots: RwLock<Vec<(String, i32)>>,
+    cluster_nodes: RwLock<HashMap<String, chrono::DateTime<Utc>>>,
+    task_nodes: RwLock<HashMap<i32, String>>,
+    /// Аренды лидерства: аренда → узел-держатель
+    leader_locks: RwLock<HashMap<String, String>>,
+    task_log_archives: RwLock<HashMap<i32, TaskLogArchive>>,
+    log_retention: RwLock<HashMap<i32, LogRetentionPolicy>>,
+    webhooks: RwLock<HashMap<i64, Webhook>>,
+    webhook_logs: RwLock<Vec<WebhookLog>>,
+    notification_policies: RwLock<HashMap<i32, NotificationPolicy>>,
+    /// События outbox с признаком раскладки на доставки
+    outbox_events: RwLock<Vec<(OutboxEvent, bool)>>,
+    event_deliveries: RwLock<Vec<EventDelivery>>,
+    /// Роли участников проекта: (project_id, user_id) → slug роли
+    project_user_roles: RwLock<HashMap<(i32, i32), String>>,
+    project_roles: RwLock<Vec<Role>>,
+    template_role_perms: RwLock<Vec<TemplateRolePerm>>,
+    api_tokens: RwLock<Vec<APIToken>>,
+    service_accounts: RwLock<Vec<ServiceAccount>>,
 }
 
 impl Default for MockStore {
@@ -41,10 +71,35 @@ impl MockStore {
             inventories: RwLock::new(HashMap::new()),
             repositories: RwLock::new(HashMap::new()),
             environments: RwLock::new(HashMap::new()),
+            access_keys: RwLock::new(HashMap::new()),
             playbook_runs: RwLock::new(HashMap::new()),
             organizations: RwLock::new(HashMap::new()),
             organization_users: RwLock::new(HashMap::new()),
             terraform_plans: RwLock::new(HashMap::new()),
+            task_hooks: RwLock::new(Vec::new()),
+            task_outputs: RwLock::new(Vec::new()),
+            structured_outputs: RwLock::new(HashMap::new()),
+            drift_configs: RwLock::new(Vec::new()),
+            drift_results: RwLock::new(Vec::new()),
+            runners: RwLock::new(HashMap::new()),
+            runner_claims: RwLock::new(HashMap::new()),
+            concurrency_groups: RwLock::new(Vec::new()),
+            concurrency_slots: RwLock::new(Vec::new()),
+            cluster_nodes: RwLock::new(HashMap::new()),
+            task_nodes: RwLock::new(HashMap::new()),
+            leader_locks: RwLock::new(HashMap::new()),
+            task_log_archives: RwLock::new(HashMap::new()),
+            log_retention: RwLock::new(HashMap::new()),
+            webhooks: RwLock::new(HashMap::new()),
+            webhook_logs: RwLock::new(Vec::new()),
+            notification_policies: RwLock::new(HashMap::new()),
+            outbox_events: RwLock::new(Vec::new()),
+            event_deliveries: RwLock::new(Vec::new()),
+            project_user_roles: RwLock::new(HashMap::new()),
+            project_roles: RwLock::new(Vec::new()),
+            template_role_perms: RwLock::new(Vec::new()),
+            api_tokens: RwLock::new(Vec::new()),
+            service_accounts: RwLock::new(Vec::new()),
         }
     }
 
@@ -266,10 +321,18 @@ impl ProjectStore for MockStore {
         self.projects.write().unwrap().remove(&project_id);
         Ok(())
     }
-    async fn create_project_user(&self, _project_user: crate::models::ProjectUser) -> Result<()> {
+    async fn create_project_user(&self, project_user: crate::models::ProjectUser) -> Result<()> {
+        self.project_user_roles.write().unwrap().insert(
+            (project_user.project_id, project_user.user_id),
+            project_user.role.to_string(),
+        );
         Ok(())
     }
-    async fn delete_project_user(&self, _project_id: i32, _user_id: i32) -> Result<()> {
+    async fn delete_project_user(&self, project_id: i32, user_id: i32) -> Result<()> {
+        self.project_user_roles
+            .write()
+            .unwrap()
+            .remove(&(project_id, user_id));
         Ok(())
     }
 }
@@ -434,19 +497,32 @@ impl EnvironmentManager for MockStore {
 #[async_trait]
 impl AccessKeyManager for MockStore {
     async fn get_access_keys(&self, _project_id: i32) -> Result<Vec<AccessKey>> {
-        Ok(vec![])
+        Ok(self.access_keys.read().unwrap().values().cloned().collect())
     }
     async fn get_access_key(&self, _project_id: i32, key_id: i32) -> Result<AccessKey> {
-        Err(Error::NotFound(format!("AccessKey {} not found", key_id)))
+        self.access_keys
+            .read()
+            .unwrap()
+            .get(&key_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("AccessKey {} not found", key_id)))
     }
-    async fn create_access_key(&self, key: AccessKey) -> Result<AccessKey> {
+    async fn create_access_key(&self, mut key: AccessKey) -> Result<AccessKey> {
+        if key.id == 0 {
+            key.id = (self.access_keys.read().unwrap().len() as i32) + 1;
+        }
+        self.access_keys
+            .write()
+            .unwrap()
+            .insert(key.id, key.clone());
         Ok(key)
     }
     async fn update_access_key(&self, key: AccessKey) -> Result<()> {
-        let _ = key;
+        self.access_keys.write().unwrap().insert(key.id, key);
         Ok(())
     }
-    async fn delete_access_key(&self, _project_id: i32, _key_id: i32) -> Result<()> {
+    async fn delete_access_key(&self, _project_id: i32, key_id: i32) -> Result<()> {
+        self.access_keys.write().unwrap().remove(&key_id);
         Ok(())
     }
 }
@@ -491,14 +567,41 @@ impl TaskManager for MockStore {
         self.tasks.write().unwrap().insert(task.id, task.clone());
         Ok(())
     }
+    async fn set_waiting_task_priority(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        priority: Option<i32>,
+    ) -> Result<bool> {
+        let mut tasks = self.tasks.write().unwrap();
+        match tasks.get_mut(&task_id) {
+            Some(t) if t.project_id == project_id && t.status == TaskStatus::Waiting => {
+                t.priority = priority;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
     async fn delete_task(&self, _project_id: i32, task_id: i32) -> Result<()> {
         self.tasks.write().unwrap().remove(&task_id);
         Ok(())
     }
-    async fn get_task_outputs(&self, _task_id: i32) -> Result<Vec<TaskOutput>> {
-        Ok(vec![])
+    async fn get_task_outputs(&self, task_id: i32) -> Result<Vec<TaskOutput>> {
+        Ok(self
+            .task_outputs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|o| o.task_id == task_id)
+            .cloned()
+            .collect())
     }
-    async fn create_task_output(&self, output: TaskOutput) -> Result<TaskOutput> {
+    async fn create_task_output(&self, mut output: TaskOutput) -> Result<TaskOutput> {
+        let mut outputs = self.task_outputs.write().unwrap();
+        if output.id == 0 {
+            output.id = outputs.iter().map(|o| o.id).max().unwrap_or(0) + 1;
+        }
+        outputs.push(output.clone());
         Ok(output)
     }
     async fn update_task_status(
@@ -532,6 +635,39 @@ impl TaskManager for MockStore {
             .collect())
     }
 
+    async fn get_queued_tasks(&self, project_id: Option<i32>, limit: i32) -> Result<Vec<Task>> {
+        let templates = self.templates.read().unwrap();
+        let hooks = self.task_hooks.read().unwrap();
+        let mut queued: Vec<(i32, Task)> = self
+            .tasks
+            .read()
+            .unwrap()
+            .values()
+            .filter(|t| t.status == TaskStatus::Waiting)
+            .filter(|t| project_id.is_none_or(|id| id == t.project_id))
+            .filter(|t| !hooks.iter().any(|h| h.task_id == t.id))
+            .map(|t| {
+                let priority = t.priority.unwrap_or_else(|| {
+                    templates
+                        .get(&t.template_id)
+                        .map(|tpl| tpl.priority)
+                        .unwrap_or(0)
+                });
+                (priority, t.clone())
+            })
+            .collect();
+        queued.sort_by(|(pa, a), (pb, b)| {
+            pb.cmp(pa)
+                .then(a.created.cmp(&b.created))
+                .then(a.id.cmp(&b.id))
+        });
+        Ok(queued
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|(_, t)| t)
+            .collect())
+    }
+
     async fn get_running_tasks_count(&self) -> Result<usize> {
         Ok(0)
     }
@@ -539,6 +675,36 @@ impl TaskManager for MockStore {
     async fn get_waiting_tasks_count(&self) -> Result<usize> {
         Ok(0)
     }
+    async fn link_task_hook(&self, _project_id: i32, hook: TaskHook) -> Result<()> {
+        let mut hooks = self.task_hooks.write().unwrap();
+        hooks.retain(|h| h.task_id != hook.task_id);
+        hooks.push(hook);
+        Ok(())
+    }
+    async fn create_hook_task(
+        &self,
+        task: Task,
+        parent_task_id: i32,
+        hook_type: &str,
+    ) -> Result<Task> {
+        let task = self.create_task(task).await?;
+        self.task_hooks.write().unwrap().push(TaskHook {
+            task_id: task.id,
+            parent_task_id,
+            hook_type: hook_type.to_string(),
+        });
+        Ok(task)
+    }
+    async fn get_task_hook_links(&self, _project_id: i32, task_id: i32) -> Result<Vec<TaskHook>> {
+        Ok(self
+            .task_hooks
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|h| h.task_id == task_id || h.parent_task_id == task_id)
+            .cloned()
+            .collect())
+    }
 }
 
 #[async_trait]
@@ -581,6 +747,14 @@ impl ScheduleManager for MockStore {
     ) -> Result<()> {
         Ok(())
     }
+
+    async fn set_schedule_last_fired(
+        &self,
+        _schedule_id: i32,
+        _fired: chrono::DateTime<Utc>,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -604,19 +778,105 @@ impl SessionManager for MockStore {
 
 #[async_trait]
 impl TokenManager for MockStore {
-    async fn get_api_tokens(&self, _user_id: i32) -> Result<Vec<APIToken>> {
-        Ok(vec![])
+    async fn get_api_tokens(&self, user_id: i32) -> Result<Vec<APIToken>> {
+        Ok(self
+            .api_tokens
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|t| t.user_id == user_id)
+            .cloned()
+            .collect())
     }
-    async fn create_api_token(&self, token: APIToken) -> Result<APIToken> {
+    async fn create_api_token(&self, mut token: APIToken) -> Result<APIToken> {
+        let mut tokens = self.api_tokens.write().unwrap();
+        token.id = tokens.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        tokens.push(token.clone());
         Ok(token)
     }
     async fn get_api_token(&self, token_id: i32) -> Result<APIToken> {
-        Err(Error::NotFound(format!("Token {} not found", token_id)))
+        self.api_tokens
+            .read()
+            .unwrap()
+            .iter()
+            .find(|t| t.id == token_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("Token {} not found", token_id)))
+    }
+    async fn expire_api_token(&self, user_id: i32, token_id: i32) -> Result<()> {
+        self.api_tokens
+            .write()
+            .unwrap()
+            .iter_mut()
+            .filter(|t| t.id == token_id && t.user_id == user_id)
+            .for_each(|t| t.expired = true);
+        Ok(())
+    }
+    async fn delete_api_token(&self, user_id: i32, token_id: i32) -> Result<()> {
+        self.api_tokens
+            .write()
+            .unwrap()
+            .retain(|t| !(t.id == token_id && t.user_id == user_id));
+        Ok(())
+    }
+    async fn get_api_token_by_value(&self, token: &str) -> Result<APIToken> {
+        self.api_tokens
+            .read()
+            .unwrap()
+            .iter()
+            .find(|t| t.token == token)
+            .cloned()
+            .ok_or_else(|| Error::NotFound("Token not found".to_string()))
     }
-    async fn expire_api_token(&self, _user_id: i32, _token_id: i32) -> Result<()> {
+    async fn touch_api_token(&self, token_id: i32, ip: Option<&str>) -> Result<()> {
+        if let Some(token) = self
+            .api_tokens
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|t| t.id == token_id)
+        {
+            token.last_used_at = Some(Utc::now());
+            token.last_used_ip = ip.map(str::to_string);
+        }
         Ok(())
     }
-    async fn delete_api_token(&self, _user_id: i32, _token_id: i32) -> Result<()> {
+}
+
+#[async_trait]
+impl ServiceAccountManager for MockStore {
+    async fn get_service_accounts(&self) -> Result<Vec<ServiceAccount>> {
+        Ok(self.service_accounts.read().unwrap().clone())
+    }
+    async fn get_service_account(&self, id: i32) -> Result<ServiceAccount> {
+        self.service_accounts
+            .read()
+            .unwrap()
+            .iter()
+            .find(|a| a.id == id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("Service account {} not found", id)))
+    }
+    async fn create_service_account(&self, mut account: ServiceAccount) -> Result<ServiceAccount> {
+        let mut accounts = self.service_accounts.write().unwrap();
+        account.id = accounts.iter().map(|a| a.id).max().unwrap_or(0) + 1;
+        accounts.push(account.clone());
+        Ok(account)
+    }
+    async fn delete_service_account(&self, id: i32) -> Result<()> {
+        let removed = {
+            let mut accounts = self.service_accounts.write().unwrap();
+            let user_id = accounts.iter().find(|a| a.id == id).map(|a| a.user_id);
+            accounts.retain(|a| a.id != id);
+            user_id
+        };
+        if let Some(user_id) = removed {
+            self.users.write().unwrap().remove(&user_id);
+            self.api_tokens
+                .write()
+                .unwrap()
+                .retain(|t| t.user_id != user_id);
+        }
         Ok(())
     }
 }
@@ -633,38 +893,147 @@ impl EventManager for MockStore {
 
 #[async_trait]
 impl RunnerManager for MockStore {
-    async fn get_runners(&self, _project_id: Option<i32>) -> Result<Vec<Runner>> {
-        Ok(vec![])
+    async fn get_runners(&self, project_id: Option<i32>) -> Result<Vec<Runner>> {
+        Ok(self
+            .runners
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| project_id.is_none() || r.project_id == project_id)
+            .cloned()
+            .collect())
     }
     async fn get_runner(&self, runner_id: i32) -> Result<Runner> {
-        Err(Error::NotFound(format!("Runner {} not found", runner_id)))
+        self.runners
+            .read()
+            .unwrap()
+            .get(&runner_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("Runner {} not found", runner_id)))
     }
-    async fn create_runner(&self, runner: Runner) -> Result<Runner> {
+    async fn create_runner(&self, mut runner: Runner) -> Result<Runner> {
+        if runner.id == 0 {
+            runner.id = (self.runners.read().unwrap().len() as i32) + 1;
+        }
+        self.runners
+            .write()
+            .unwrap()
+            .insert(runner.id, runner.clone());
         Ok(runner)
     }
     async fn update_runner(&self, runner: Runner) -> Result<()> {
-        let _ = runner;
+        self.runners.write().unwrap().insert(runner.id, runner);
         Ok(())
     }
-    async fn delete_runner(&self, _runner_id: i32) -> Result<()> {
+    async fn delete_runner(&self, runner_id: i32) -> Result<()> {
+        self.runners.write().unwrap().remove(&runner_id);
         Ok(())
     }
 
     async fn get_runners_count(&self) -> Result<usize> {
-        Ok(0)
+        Ok(self.runners.read().unwrap().len())
     }
 
     async fn get_active_runners_count(&self) -> Result<usize> {
-        Ok(0)
+        Ok(self
+            .runners
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.active)
+            .count())
     }
 
-    async fn find_runner_by_token(&self, _token: &str) -> Result<Runner> {
-        Err(Error::NotFound("Runner not found".to_string()))
+    async fn find_runner_by_token(&self, token: &str) -> Result<Runner> {
+        self.runners
+            .read()
+            .unwrap()
+            .values()
+            .find(|r| !token.is_empty() && r.token == token)
+            .cloned()
+            .ok_or_else(|| Error::NotFound("Runner not found".to_string()))
     }
 
-    async fn touch_runner(&self, _runner_id: i32) -> Result<()> {
+    async fn touch_runner(&self, runner_id: i32) -> Result<()> {
+        if let Some(runner) = self.runners.write().unwrap().get_mut(&runner_id) {
+            runner.last_active = Some(chrono::Utc::now());
+        }
         Ok(())
     }
+
+    async fn claim_task(&self, task_id: i32, runner_id: i32) -> Result<bool> {
+        let mut tasks = self.tasks.write().unwrap();
+        match tasks.get_mut(&task_id) {
+            Some(task) if task.status == TaskStatus::Waiting => {
+                task.status = TaskStatus::Running;
+                task.start = Some(Utc::now());
+                self.runner_claims
+                    .write()
+                    .unwrap()
+                    .insert(task_id, runner_id);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn get_runner_task_ids(&self, runner_id: i32) -> Result<Vec<i32>> {
+        let tasks = self.tasks.read().unwrap();
+        let mut ids: Vec<i32> = self
+            .runner_claims
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(task_id, owner)| {
+                **owner == runner_id
+                    && tasks.get(task_id).is_some_and(|t| {
+                        matches!(
+                            t.status,
+                            TaskStatus::Starting | TaskStatus::Running | TaskStatus::Stopping
+                        )
+                    })
+            })
+            .map(|(task_id, _)| *task_id)
+            .collect();
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    async fn requeue_stale_runner_tasks(&self, timeout_secs: i64) -> Result<Vec<Task>> {
+        let cutoff = Utc::now() - chrono::Duration::seconds(timeout_secs);
+        let runners = self.runners.read().unwrap();
+        let mut tasks = self.tasks.write().unwrap();
+        let mut claims = self.runner_claims.write().unwrap();
+        let mut requeued = Vec::new();
+        claims.retain(|task_id, runner_id| {
+            // Задачи удалённых и отключённых раннеров завершает fail_orphaned_tasks
+            let alive = runners
+                .get(runner_id)
+                .is_none_or(|r| !r.active || r.last_active.is_some_and(|t| t >= cutoff));
+            let Some(task) = tasks.get_mut(task_id) else {
+                return false;
+            };
+            if alive
+                || !matches!(
+                    task.status,
+                    TaskStatus::Starting | TaskStatus::Running | TaskStatus::Stopping
+                )
+            {
+                return true;
+            }
+            if task.status == TaskStatus::Stopping {
+                task.status = TaskStatus::Stopped;
+                task.end = Some(Utc::now());
+            } else {
+                task.status = TaskStatus::Waiting;
+                task.start = None;
+            }
+            requeued.push(task.clone());
+            false
+        });
+        requeued.sort_unstable_by_key(|t| t.id);
+        Ok(requeued)
+    }
 }
 
 #[async_trait]
@@ -716,6 +1085,25 @@ impl IntegrationManager for MockStore {
     async fn delete_integration(&self, _project_id: i32, _integration_id: i32) -> Result<()> {
         Ok(())
     }
+    async fn get_integration_aliases(
+        &self,
+        _project_id: i32,
+        _integration_id: Option<i32>,
+    ) -> Result<Vec<IntegrationAlias>> {
+        Ok(vec![])
+    }
+    async fn get_integration_alias_by_alias(&self, alias: &str) -> Result<IntegrationAlias> {
+        Err(Error::NotFound(format!(
+            "Integration alias {} not found",
+            alias
+        )))
+    }
+    async fn create_integration_alias(&self, alias: IntegrationAlias) -> Result<IntegrationAlias> {
+        Ok(alias)
+    }
+    async fn delete_integration_alias(&self, _project_id: i32, _alias_id: i32) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -979,16 +1367,95 @@ impl IntegrationExtractValueManager for MockStore {
 
 #[async_trait]
 impl ProjectRoleManager for MockStore {
-    async fn get_project_roles(&self, _project_id: i32) -> Result<Vec<crate::models::Role>> {
-        Ok(vec![])
+    async fn get_project_roles(&self, project_id: i32) -> Result<Vec<crate::models::Role>> {
+        Ok(self
+            .project_roles
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.project_id == project_id)
+            .cloned()
+            .collect())
     }
-    async fn create_project_role(&self, role: crate::models::Role) -> Result<crate::models::Role> {
+    async fn create_project_role(
+        &self,
+        mut role: crate::models::Role,
+    ) -> Result<crate::models::Role> {
+        let mut roles = self.project_roles.write().unwrap();
+        role.id = roles.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+        roles.push(role.clone());
         Ok(role)
     }
-    async fn update_project_role(&self, _role: crate::models::Role) -> Result<()> {
+    async fn update_project_role(&self, role: crate::models::Role) -> Result<()> {
+        if let Some(existing) = self
+            .project_roles
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|r| r.id == role.id && r.project_id == role.project_id)
+        {
+            *existing = role;
+        }
+        Ok(())
+    }
+    async fn delete_project_role(&self, project_id: i32, role_id: i32) -> Result<()> {
+        self.project_roles
+            .write()
+            .unwrap()
+            .retain(|r| !(r.id == role_id && r.project_id == project_id));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ProjectAccessManager for MockStore {
+    async fn get_project_user_role(&self, project_id: i32, user_id: i32) -> Result<Option<String>> {
+        Ok(self
+            .project_user_roles
+            .read()
+            .unwrap()
+            .get(&(project_id, user_id))
+            .cloned())
+    }
+    async fn set_project_user_role(&self, project_id: i32, user_id: i32, role: &str) -> Result<()> {
+        self.project_user_roles
+            .write()
+            .unwrap()
+            .insert((project_id, user_id), role.to_string());
         Ok(())
     }
-    async fn delete_project_role(&self, _project_id: i32, _role_id: i32) -> Result<()> {
+    async fn get_template_role_perms(
+        &self,
+        project_id: i32,
+        template_id: i32,
+    ) -> Result<Vec<TemplateRolePerm>> {
+        Ok(self
+            .template_role_perms
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|p| p.project_id == project_id && p.template_id == template_id)
+            .cloned()
+            .collect())
+    }
+    async fn create_template_role_perm(
+        &self,
+        mut perm: TemplateRolePerm,
+    ) -> Result<TemplateRolePerm> {
+        let mut perms = self.template_role_perms.write().unwrap();
+        perm.id = perms.iter().map(|p| p.id).max().unwrap_or(0) + 1;
+        perms.push(perm.clone());
+        Ok(perm)
+    }
+    async fn delete_template_role_perm(
+        &self,
+        project_id: i32,
+        template_id: i32,
+        id: i32,
+    ) -> Result<()> {
+        self.template_role_perms.write().unwrap().retain(|p| {
+            !(p.id == id && p.project_id == project_id && p.template_id == template_id)
+        });
         Ok(())
     }
 }
@@ -1189,58 +1656,167 @@ impl Store for MockStore {}
 impl crate::db::store::DriftManager for MockStore {
     async fn get_drift_configs(
         &self,
-        _project_id: i32,
+        project_id: i32,
     ) -> Result<Vec<crate::models::drift::DriftConfig>> {
-        Ok(Vec::new())
+        Ok(self
+            .drift_configs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|c| c.project_id == project_id)
+            .cloned()
+            .collect())
+    }
+    async fn get_enabled_drift_configs(&self) -> Result<Vec<crate::models::drift::DriftConfig>> {
+        Ok(self
+            .drift_configs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|c| c.enabled)
+            .cloned()
+            .collect())
     }
     async fn get_drift_config(
         &self,
-        _id: i32,
-        _project_id: i32,
+        id: i32,
+        project_id: i32,
     ) -> Result<crate::models::drift::DriftConfig> {
-        Err(Error::NotFound("DriftConfig not found".to_string()))
+        self.drift_configs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|c| c.id == id && c.project_id == project_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound("DriftConfig not found".to_string()))
     }
     async fn create_drift_config(
         &self,
-        _project_id: i32,
-        _payload: crate::models::drift::DriftConfigCreate,
+        project_id: i32,
+        payload: crate::models::drift::DriftConfigCreate,
     ) -> Result<crate::models::drift::DriftConfig> {
-        Err(Error::Other("not implemented".to_string()))
+        let mut configs = self.drift_configs.write().unwrap();
+        let config = crate::models::drift::DriftConfig {
+            id: configs.len() as i32 + 1,
+            project_id,
+            template_id: payload.template_id,
+            enabled: payload.enabled.unwrap_or(true),
+            schedule: payload.schedule,
+            created: Utc::now(),
+        };
+        configs.push(config.clone());
+        Ok(config)
     }
     async fn update_drift_config_enabled(
         &self,
-        _id: i32,
-        _project_id: i32,
-        _enabled: bool,
+        id: i32,
+        project_id: i32,
+        enabled: bool,
     ) -> Result<()> {
+        let mut configs = self.drift_configs.write().unwrap();
+        if let Some(c) = configs
+            .iter_mut()
+            .find(|c| c.id == id && c.project_id == project_id)
+        {
+            c.enabled = enabled;
+        }
+        Ok(())
+    }
+    async fn update_drift_config_schedule(
+        &self,
+        id: i32,
+        project_id: i32,
+        schedule: Option<String>,
+    ) -> Result<()> {
+        let mut configs = self.drift_configs.write().unwrap();
+        if let Some(c) = configs
+            .iter_mut()
+            .find(|c| c.id == id && c.project_id == project_id)
+        {
+            c.schedule = schedule;
+        }
         Ok(())
     }
-    async fn delete_drift_config(&self, _id: i32, _project_id: i32) -> Result<()> {
+    async fn delete_drift_config(&self, id: i32, project_id: i32) -> Result<()> {
+        self.drift_configs
+            .write()
+            .unwrap()
+            .retain(|c| !(c.id == id && c.project_id == project_id));
         Ok(())
     }
     async fn get_drift_results(
         &self,
-        _drift_config_id: i32,
-        _limit: i64,
+        drift_config_id: i32,
+        limit: i64,
     ) -> Result<Vec<crate::models::drift::DriftResult>> {
-        Ok(Vec::new())
+        Ok(self
+            .drift_results
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|r| r.drift_config_id == drift_config_id)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
     }
     async fn create_drift_result(
         &self,
-        _project_id: i32,
-        _drift_config_id: i32,
-        _template_id: i32,
-        _status: &str,
-        _summary: Option<String>,
-        _task_id: Option<i32>,
+        project_id: i32,
+        drift_config_id: i32,
+        template_id: i32,
+        status: &str,
+        summary: Option<String>,
+        task_id: Option<i32>,
     ) -> Result<crate::models::drift::DriftResult> {
-        Err(Error::Other("not implemented".to_string()))
+        let mut results = self.drift_results.write().unwrap();
+        let result = crate::models::drift::DriftResult {
+            id: results.len() as i32 + 1,
+            drift_config_id,
+            project_id,
+            template_id,
+            status: status.to_string(),
+            summary,
+            task_id,
+            checked_at: Utc::now(),
+        };
+        results.push(result.clone());
+        Ok(result)
     }
-    async fn get_latest_drift_results(
+    async fn update_drift_result(
         &self,
-        _project_id: i32,
-    ) -> Result<Vec<crate::models::drift::DriftResult>> {
-        Ok(Vec::new())
+        id: i32,
+        status: &str,
+        summary: Option<String>,
+    ) -> Result<()> {
+        let mut results = self.drift_results.write().unwrap();
+        if let Some(r) = results.iter_mut().find(|r| r.id == id) {
+            r.status = status.to_string();
+            r.summary = summary;
+            r.checked_at = Utc::now();
+        }
+        Ok(())
+    }
+    async fn get_latest_drift_results(
+        &self,
+        project_id: i32,
+    ) -> Result<Vec<crate::models::drift::DriftResult>> {
+        let results = self.drift_results.read().unwrap();
+        let mut latest: HashMap<i32, crate::models::drift::DriftResult> = HashMap::new();
+        for r in results.iter().filter(|r| r.project_id == project_id) {
+            latest.insert(r.drift_config_id, r.clone());
+        }
+        Ok(latest.into_values().collect())
+    }
+    async fn get_pending_drift_results(&self) -> Result<Vec<crate::models::drift::DriftResult>> {
+        Ok(self
+            .drift_results
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.status == "pending")
+            .cloned()
+            .collect())
     }
 }
 
@@ -1270,54 +1846,92 @@ impl crate::db::store::LdapGroupMappingManager for MockStore {
 
 #[async_trait]
 impl WebhookManager for MockStore {
-    async fn get_webhook(&self, _webhook_id: i64) -> Result<crate::models::webhook::Webhook> {
-        Err(Error::NotFound("Webhook not found".to_string()))
+    async fn get_webhook(&self, webhook_id: i64) -> Result<Webhook> {
+        self.webhooks
+            .read()
+            .unwrap()
+            .get(&webhook_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound("Webhook not found".to_string()))
     }
 
-    async fn get_webhooks_by_project(
-        &self,
-        _project_id: i64,
-    ) -> Result<Vec<crate::models::webhook::Webhook>> {
-        Ok(Vec::new())
+    async fn get_webhooks_by_project(&self, project_id: i64) -> Result<Vec<Webhook>> {
+        let mut webhooks: Vec<Webhook> = self
+            .webhooks
+            .read()
+            .unwrap()
+            .values()
+            .filter(|w| w.project_id == Some(project_id))
+            .cloned()
+            .collect();
+        webhooks.sort_by_key(|w| w.id);
+        Ok(webhooks)
     }
 
-    async fn create_webhook(
-        &self,
-        _webhook: crate::models::webhook::Webhook,
-    ) -> Result<crate::models::webhook::Webhook> {
-        Err(Error::Database(sqlx::Error::Protocol(
-            "Not implemented in mock".to_string(),
-        )))
+    async fn create_webhook(&self, mut webhook: Webhook) -> Result<Webhook> {
+        let mut webhooks = self.webhooks.write().unwrap();
+        webhook.id = webhooks.keys().max().copied().unwrap_or(0) + 1;
+        webhooks.insert(webhook.id, webhook.clone());
+        Ok(webhook)
     }
 
-    async fn update_webhook(
-        &self,
-        _webhook_id: i64,
-        _webhook: crate::models::webhook::UpdateWebhook,
-    ) -> Result<crate::models::webhook::Webhook> {
-        Err(Error::Database(sqlx::Error::Protocol(
-            "Not implemented in mock".to_string(),
-        )))
+    async fn update_webhook(&self, webhook_id: i64, update: UpdateWebhook) -> Result<Webhook> {
+        let mut webhooks = self.webhooks.write().unwrap();
+        let webhook = webhooks
+            .get_mut(&webhook_id)
+            .ok_or_else(|| Error::NotFound("Webhook not found".to_string()))?;
+        if let Some(name) = update.name {
+            webhook.name = name;
+        }
+        if let Some(r#type) = update.r#type {
+            webhook.r#type = r#type;
+        }
+        if let Some(url) = update.url {
+            webhook.url = url;
+        }
+        if update.secret.is_some() {
+            webhook.secret = update.secret;
+        }
+        if update.headers.is_some() {
+            webhook.headers = update.headers;
+        }
+        if let Some(active) = update.active {
+            webhook.active = active;
+        }
+        if let Some(events) = update.events {
+            webhook.events = serde_json::json!(events);
+        }
+        if let Some(retry_count) = update.retry_count {
+            webhook.retry_count = retry_count;
+        }
+        if let Some(timeout_secs) = update.timeout_secs {
+            webhook.timeout_secs = timeout_secs;
+        }
+        webhook.updated = Utc::now();
+        Ok(webhook.clone())
     }
 
-    async fn delete_webhook(&self, _webhook_id: i64) -> Result<()> {
+    async fn delete_webhook(&self, webhook_id: i64) -> Result<()> {
+        self.webhooks.write().unwrap().remove(&webhook_id);
         Ok(())
     }
 
-    async fn get_webhook_logs(
-        &self,
-        _webhook_id: i64,
-    ) -> Result<Vec<crate::models::webhook::WebhookLog>> {
-        Ok(Vec::new())
+    async fn get_webhook_logs(&self, webhook_id: i64) -> Result<Vec<WebhookLog>> {
+        Ok(self
+            .webhook_logs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|l| l.webhook_id == webhook_id)
+            .cloned()
+            .collect())
     }
 
-    async fn create_webhook_log(
-        &self,
-        _log: crate::models::webhook::WebhookLog,
-    ) -> Result<crate::models::webhook::WebhookLog> {
-        Err(Error::Database(sqlx::Error::Protocol(
-            "Not implemented in mock".to_string(),
-        )))
+    async fn create_webhook_log(&self, mut log: WebhookLog) -> Result<WebhookLog> {
+        let mut logs = self.webhook_logs.write().unwrap();
+        log.id = logs.len() as i64 + 1;
+        logs.push(log.clone());
+        Ok(log)
     }
 }
 
@@ -1602,6 +2216,8 @@ impl crate::db::store::WorkflowManager for MockStore {
             pos_x: payload.pos_x,
             pos_y: payload.pos_y,
             wave: payload.wave,
+            inputs: payload.inputs,
+            node_type: payload.node_type,
         })
     }
     async fn update_workflow_node(
@@ -1619,6 +2235,8 @@ impl crate::db::store::WorkflowManager for MockStore {
             pos_x: payload.pos_x,
             pos_y: payload.pos_y,
             wave: payload.wave,
+            inputs: payload.inputs,
+            node_type: payload.node_type,
         })
     }
     async fn delete_workflow_node(&self, _id: i32, _workflow_id: i32) -> Result<()> {
@@ -1654,10 +2272,21 @@ impl crate::db::store::WorkflowManager for MockStore {
     ) -> Result<Vec<crate::models::workflow::WorkflowRun>> {
         Ok(Vec::new())
     }
+    async fn get_workflow_run(
+        &self,
+        _id: i32,
+        _project_id: i32,
+    ) -> Result<crate::models::workflow::WorkflowRun> {
+        Err(Error::NotFound("Workflow run not found".to_string()))
+    }
+    async fn get_active_workflow_runs(&self) -> Result<Vec<crate::models::workflow::WorkflowRun>> {
+        Ok(Vec::new())
+    }
     async fn create_workflow_run(
         &self,
         _workflow_id: i32,
         _project_id: i32,
+        _params: serde_json::Map<String, serde_json::Value>,
     ) -> Result<crate::models::workflow::WorkflowRun> {
         Err(Error::Other("not implemented".to_string()))
     }
@@ -1669,48 +2298,121 @@ impl crate::db::store::WorkflowManager for MockStore {
     ) -> Result<()> {
         Ok(())
     }
+    async fn get_workflow_run_nodes(
+        &self,
+        _run_id: i32,
+    ) -> Result<Vec<crate::models::workflow::WorkflowRunNode>> {
+        Ok(Vec::new())
+    }
+    async fn save_workflow_run_node(
+        &self,
+        _node: crate::models::workflow::WorkflowRunNode,
+    ) -> Result<()> {
+        Ok(())
+    }
+    async fn decide_workflow_approval(
+        &self,
+        _run_id: i32,
+        _node_id: i32,
+        _approved: bool,
+        _user_id: i32,
+        _comment: Option<String>,
+    ) -> Result<bool> {
+        Ok(false)
+    }
 }
 
 #[async_trait]
 impl crate::db::store::NotificationPolicyManager for MockStore {
-    async fn get_notification_policies(
-        &self,
-        _project_id: i32,
-    ) -> Result<Vec<crate::models::notification::NotificationPolicy>> {
-        Ok(Vec::new())
+    async fn get_notification_policies(&self, project_id: i32) -> Result<Vec<NotificationPolicy>> {
+        let mut policies: Vec<NotificationPolicy> = self
+            .notification_policies
+            .read()
+            .unwrap()
+            .values()
+            .filter(|p| p.project_id == project_id)
+            .cloned()
+            .collect();
+        policies.sort_by_key(|p| p.id);
+        Ok(policies)
     }
     async fn get_notification_policy(
         &self,
-        _id: i32,
-        _project_id: i32,
-    ) -> Result<crate::models::notification::NotificationPolicy> {
-        Err(Error::NotFound("NotificationPolicy not found".to_string()))
+        id: i32,
+        project_id: i32,
+    ) -> Result<NotificationPolicy> {
+        self.notification_policies
+            .read()
+            .unwrap()
+            .get(&id)
+            .filter(|p| p.project_id == project_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound("NotificationPolicy not found".to_string()))
     }
     async fn create_notification_policy(
         &self,
-        _project_id: i32,
-        _payload: crate::models::notification::NotificationPolicyCreate,
-    ) -> Result<crate::models::notification::NotificationPolicy> {
-        Err(Error::Other("not implemented".to_string()))
+        project_id: i32,
+        payload: NotificationPolicyCreate,
+    ) -> Result<NotificationPolicy> {
+        let mut policies = self.notification_policies.write().unwrap();
+        let policy = NotificationPolicy {
+            id: policies.keys().max().copied().unwrap_or(0) + 1,
+            project_id,
+            name: payload.name,
+            channel_type: payload.channel_type,
+            webhook_url: payload.webhook_url,
+            trigger: payload.trigger,
+            template_id: payload.template_id,
+            enabled: payload.enabled.unwrap_or(true),
+            created: Utc::now(),
+        };
+        policies.insert(policy.id, policy.clone());
+        Ok(policy)
     }
     async fn update_notification_policy(
         &self,
-        _id: i32,
-        _project_id: i32,
-        _payload: crate::models::notification::NotificationPolicyUpdate,
-    ) -> Result<crate::models::notification::NotificationPolicy> {
-        Err(Error::Other("not implemented".to_string()))
-    }
-    async fn delete_notification_policy(&self, _id: i32, _project_id: i32) -> Result<()> {
+        id: i32,
+        project_id: i32,
+        payload: NotificationPolicyUpdate,
+    ) -> Result<NotificationPolicy> {
+        let mut policies = self.notification_policies.write().unwrap();
+        let policy = policies
+            .get_mut(&id)
+            .filter(|p| p.project_id == project_id)
+            .ok_or_else(|| Error::NotFound("NotificationPolicy not found".to_string()))?;
+        policy.name = payload.name;
+        policy.channel_type = payload.channel_type;
+        policy.webhook_url = payload.webhook_url;
+        policy.trigger = payload.trigger;
+        policy.template_id = payload.template_id;
+        policy.enabled = payload.enabled;
+        Ok(policy.clone())
+    }
+    async fn delete_notification_policy(&self, id: i32, project_id: i32) -> Result<()> {
+        self.notification_policies
+            .write()
+            .unwrap()
+            .retain(|_, p| !(p.id == id && p.project_id == project_id));
         Ok(())
     }
     async fn get_matching_policies(
         &self,
-        _project_id: i32,
-        _trigger: &str,
-        _template_id: Option<i32>,
-    ) -> Result<Vec<crate::models::notification::NotificationPolicy>> {
-        Ok(Vec::new())
+        project_id: i32,
+        trigger: &str,
+        template_id: Option<i32>,
+    ) -> Result<Vec<NotificationPolicy>> {
+        let mut policies: Vec<NotificationPolicy> = self
+            .notification_policies
+            .read()
+            .unwrap()
+            .values()
+            .filter(|p| p.project_id == project_id && p.enabled)
+            .filter(|p| p.trigger == trigger || p.trigger == "always")
+            .filter(|p| p.template_id.is_none() || p.template_id == template_id)
+            .cloned()
+            .collect();
+        policies.sort_by_key(|p| p.id);
+        Ok(policies)
     }
 }
 
@@ -1999,6 +2701,517 @@ impl crate::db::store::DeploymentEnvironmentManager for MockStore {
     }
 }
 
+#[async_trait::async_trait]
+impl crate::db::store::ClusterNodeManager for MockStore {
+    async fn touch_cluster_node(&self, node_id: &str, _hostname: &str) -> Result<()> {
+        self.cluster_nodes
+            .write()
+            .unwrap()
+            .insert(node_id.to_string(), Utc::now());
+        Ok(())
+    }
+    async fn set_task_node(&self, task_id: i32, node_id: &str) -> Result<()> {
+        self.task_nodes
+            .write()
+            .unwrap()
+            .insert(task_id, node_id.to_string());
+        Ok(())
+    }
+    async fn claim_task_for_node(&self, task_id: i32, node_id: &str) -> Result<bool> {
+        let mut tasks = self.tasks.write().unwrap();
+        match tasks.get_mut(&task_id) {
+            Some(task) if task.status == TaskStatus::Waiting => {
+                task.status = TaskStatus::Running;
+                task.start = Some(Utc::now());
+                self.task_nodes
+                    .write()
+                    .unwrap()
+                    .insert(task_id, node_id.to_string());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+    async fn fail_orphaned_tasks(&self, node_timeout_secs: i64, message: &str) -> Result<Vec<i32>> {
+        let cutoff = Utc::now() - chrono::Duration::seconds(node_timeout_secs);
+        let nodes = self.cluster_nodes.read().unwrap();
+        let task_nodes = self.task_nodes.read().unwrap();
+        let runners = self.runners.read().unwrap();
+        let claims = self.runner_claims.read().unwrap();
+        let mut tasks = self.tasks.write().unwrap();
+        let mut failed = Vec::new();
+        for task in tasks.values_mut() {
+            if !matches!(
+                task.status,
+                TaskStatus::Starting | TaskStatus::Running | TaskStatus::Stopping
+            ) {
+                continue;
+            }
+            let orphaned = match (claims.get(&task.id), task_nodes.get(&task.id)) {
+                (Some(runner_id), _) => !runners.get(runner_id).is_some_and(|r| r.active),
+                (None, Some(node_id)) => nodes.get(node_id).is_none_or(|t| *t < cutoff),
+                (None, None) => task.start.is_some_and(|t| t < cutoff),
+            };
+            if orphaned {
+                task.status = TaskStatus::Error;
+                task.end = Some(Utc::now());
+                task.message = Some(message.to_string());
+                failed.push(task.id);
+            }
+        }
+        failed.sort_unstable();
+        Ok(failed)
+    }
+    async fn set_workflow_run_node(&self, _run_id: i32, _node_id: &str) -> Result<()> {
+        Ok(())
+    }
+    async fn claim_orphaned_workflow_runs(
+        &self,
+        _node_timeout_secs: i64,
+        _node_id: &str,
+    ) -> Result<Vec<crate::models::workflow::WorkflowRun>> {
+        Ok(Vec::new())
+    }
+    async fn try_acquire_leader_lock(&self, lease: &str, node_id: &str) -> Result<bool> {
+        let mut locks = self.leader_locks.write().unwrap();
+        let holder = locks
+            .entry(lease.to_string())
+            .or_insert_with(|| node_id.to_string());
+        Ok(holder == node_id)
+    }
+    async fn release_leader_lock(&self, lease: &str, node_id: &str) -> Result<()> {
+        let mut locks = self.leader_locks.write().unwrap();
+        if locks.get(lease).is_some_and(|holder| holder == node_id) {
+            locks.remove(lease);
+        }
+        Ok(())
+    }
+    async fn get_cluster_leader(&self, lease: &str) -> Result<Option<ClusterLeader>> {
+        let nodes = self.cluster_nodes.read().unwrap();
+        Ok(self
+            .leader_locks
+            .read()
+            .unwrap()
+            .get(lease)
+            .map(|node_id| ClusterLeader {
+                node_id: node_id.clone(),
+                hostname: None,
+                last_seen: nodes.get(node_id).copied(),
+            }))
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::db::store::EventOutboxManager for MockStore {
+    async fn create_outbox_event(&self, mut event: OutboxEvent) -> Result<OutboxEvent> {
+        let mut events = self.outbox_events.write().unwrap();
+        event.id = events.len() as i64 + 1;
+        event.created = Utc::now();
+        events.push((event.clone(), false));
+        Ok(event)
+    }
+    async fn get_outbox_event(&self, id: i64) -> Result<OutboxEvent> {
+        self.outbox_events
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(e, _)| e.id == id)
+            .map(|(e, _)| e.clone())
+            .ok_or_else(|| Error::NotFound(format!("Event {id} not found")))
+    }
+    async fn get_undispatched_events(&self, limit: i64) -> Result<Vec<OutboxEvent>> {
+        Ok(self
+            .outbox_events
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, dispatched)| !dispatched)
+            .take(limit.max(0) as usize)
+            .map(|(e, _)| e.clone())
+            .collect())
+    }
+    async fn dispatch_outbox_event(
+        &self,
+        event_id: i64,
+        deliveries: Vec<NewEventDelivery>,
+    ) -> Result<()> {
+        let mut events = self.outbox_events.write().unwrap();
+        let Some((event, dispatched)) = events
+            .iter_mut()
+            .find(|(e, dispatched)| e.id == event_id && !*dispatched)
+        else {
+            return Ok(());
+        };
+        *dispatched = true;
+        let mut all = self.event_deliveries.write().unwrap();
+        let now = Utc::now();
+        for delivery in deliveries {
+            let id = all.len() as i64 + 1;
+            all.push(EventDelivery {
+                id,
+                event_id,
+                project_id: event.project_id,
+                target: delivery.target,
+                target_id: delivery.target_id,
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                max_attempts: delivery.max_attempts,
+                next_attempt: now,
+                last_error: None,
+                created: now,
+                delivered: None,
+            });
+        }
+        Ok(())
+    }
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> Result<Vec<EventDelivery>> {
+        let now = Utc::now();
+        let mut claimed = Vec::new();
+        for delivery in self.event_deliveries.write().unwrap().iter_mut() {
+            if claimed.len() as i64 >= limit {
+                break;
+            }
+            if delivery.status == DeliveryStatus::Pending && delivery.next_attempt <= now {
+                delivery.attempts += 1;
+                delivery.next_attempt = now + chrono::Duration::seconds(lease_secs);
+                claimed.push(delivery.clone());
+            }
+        }
+        Ok(claimed)
+    }
+    async fn finish_event_delivery(
+        &self,
+        id: i64,
+        status: DeliveryStatus,
+        next_attempt: Option<chrono::DateTime<Utc>>,
+        error: Option<String>,
+    ) -> Result<()> {
+        if let Some(delivery) = self
+            .event_deliveries
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|d| d.id == id)
+        {
+            delivery.status = status;
+            if let Some(next_attempt) = next_attempt {
+                delivery.next_attempt = next_attempt;
+            }
+            delivery.last_error = error;
+            if status == DeliveryStatus::Delivered {
+                delivery.delivered = Some(Utc::now());
+            }
+        }
+        Ok(())
+    }
+    async fn get_event_deliveries(
+        &self,
+        project_id: i32,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> Result<Vec<EventDelivery>> {
+        Ok(self
+            .event_deliveries
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|d| d.project_id == project_id)
+            .filter(|d| status.is_none_or(|s| d.status == s))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+    async fn get_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery> {
+        self.event_deliveries
+            .read()
+            .unwrap()
+            .iter()
+            .find(|d| d.id == id && d.project_id == project_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("Delivery {id} not found")))
+    }
+    async fn redeliver_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery> {
+        let mut deliveries = self.event_deliveries.write().unwrap();
+        let delivery = deliveries
+            .iter_mut()
+            .find(|d| d.id == id && d.project_id == project_id)
+            .ok_or_else(|| Error::NotFound(format!("Delivery {id} not found")))?;
+        delivery.status = DeliveryStatus::Pending;
+        delivery.attempts = 0;
+        delivery.next_attempt = Utc::now();
+        delivery.last_error = None;
+        Ok(delivery.clone())
+    }
+    async fn get_event_delivery_logs(&self, delivery_id: i64) -> Result<Vec<WebhookLog>> {
+        Ok(self
+            .webhook_logs
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|l| l.delivery_id == Some(delivery_id))
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::db::store::TaskLogArchiveManager for MockStore {
+    async fn get_tasks_to_archive(
+        &self,
+        finished_before: chrono::DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<(i32, i32)>> {
+        let archives = self.task_log_archives.read().unwrap();
+        let outputs = self.task_outputs.read().unwrap();
+        let mut found: Vec<(i32, i32)> = self
+            .tasks
+            .read()
+            .unwrap()
+            .values()
+            .filter(|t| t.status.is_finished())
+            .filter(|t| t.end.unwrap_or(t.created) < finished_before)
+            .filter(|t| !archives.contains_key(&t.id))
+            .filter(|t| outputs.iter().any(|o| o.task_id == t.id))
+            .map(|t| (t.project_id, t.id))
+            .collect();
+        found.sort_by_key(|(_, id)| *id);
+        found.truncate(limit.max(0) as usize);
+        Ok(found)
+    }
+    async fn get_task_log_archive(&self, task_id: i32) -> Result<Option<TaskLogArchive>> {
+        Ok(self
+            .task_log_archives
+            .read()
+            .unwrap()
+            .get(&task_id)
+            .cloned())
+    }
+    async fn complete_task_log_archive(
+        &self,
+        archive: TaskLogArchive,
+        last_output_id: i32,
+    ) -> Result<()> {
+        let task_id = archive.task_id;
+        let mut archives = self.task_log_archives.write().unwrap();
+        // Как первичный ключ task_log_archive в SQL
+        if archives.contains_key(&task_id) {
+            return Err(Error::Validation(format!(
+                "Log of task {task_id} is already archived"
+            )));
+        }
+        archives.insert(task_id, archive);
+        drop(archives);
+        self.task_outputs
+            .write()
+            .unwrap()
+            .retain(|o| !(o.task_id == task_id && o.id <= last_output_id));
+        Ok(())
+    }
+    async fn get_log_retention_policies(&self) -> Result<Vec<LogRetentionPolicy>> {
+        let mut policies: Vec<_> = self
+            .log_retention
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        policies.sort_by_key(|p| p.project_id);
+        Ok(policies)
+    }
+    async fn get_log_retention_policy(
+        &self,
+        project_id: i32,
+    ) -> Result<Option<LogRetentionPolicy>> {
+        Ok(self.log_retention.read().unwrap().get(&project_id).cloned())
+    }
+    async fn set_log_retention_policy(&self, policy: LogRetentionPolicy) -> Result<()> {
+        let mut policies = self.log_retention.write().unwrap();
+        if policy.is_unlimited() {
+            policies.remove(&policy.project_id);
+        } else {
+            policies.insert(policy.project_id, policy);
+        }
+        Ok(())
+    }
+    async fn get_expired_task_ids(&self, policy: &LogRetentionPolicy) -> Result<Vec<i32>> {
+        let tasks = self.tasks.read().unwrap();
+        let mut project_tasks: Vec<&Task> = tasks
+            .values()
+            .filter(|t| t.project_id == policy.project_id)
+            .collect();
+        project_tasks.sort_by(|a, b| b.created.cmp(&a.created).then(b.id.cmp(&a.id)));
+
+        let cutoff = policy
+            .max_age_days
+            .map(|days| Utc::now() - chrono::Duration::days(days as i64));
+        let mut runs: HashMap<i32, i32> = HashMap::new();
+        let mut expired = Vec::new();
+        for task in project_tasks {
+            let run = runs.entry(task.template_id).or_insert(0);
+            *run += 1;
+            let too_old = cutoff.is_some_and(|c| task.end.unwrap_or(task.created) < c);
+            let too_many = policy.keep_last_runs.is_some_and(|keep| *run > keep);
+            if task.status.is_finished() && (too_old || too_many) {
+                expired.push(task.id);
+            }
+        }
+        expired.sort_unstable();
+        Ok(expired)
+    }
+    async fn purge_tasks(&self, project_id: i32, task_ids: &[i32]) -> Result<()> {
+        self.tasks
+            .write()
+            .unwrap()
+            .retain(|id, t| !(t.project_id == project_id && task_ids.contains(id)));
+        self.task_outputs
+            .write()
+            .unwrap()
+            .retain(|o| !task_ids.contains(&o.task_id));
+        self.task_log_archives
+            .write()
+            .unwrap()
+            .retain(|id, _| !task_ids.contains(id));
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::db::store::ConcurrencyGroupManager for MockStore {
+    async fn get_concurrency_groups(
+        &self,
+        project_id: i32,
+    ) -> Result<Vec<crate::models::ConcurrencyGroup>> {
+        Ok(self
+            .concurrency_groups
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|g| g.project_id == project_id)
+            .cloned()
+            .collect())
+    }
+    async fn upsert_concurrency_group(
+        &self,
+        project_id: i32,
+        payload: crate::models::ConcurrencyGroupUpsert,
+    ) -> Result<crate::models::ConcurrencyGroup> {
+        let mut groups = self.concurrency_groups.write().unwrap();
+        if let Some(group) = groups
+            .iter_mut()
+            .find(|g| g.project_id == project_id && g.name == payload.name)
+        {
+            group.max_parallel = payload.max_parallel;
+            return Ok(group.clone());
+        }
+        let group = crate::models::ConcurrencyGroup {
+            id: groups.len() as i32 + 1,
+            project_id,
+            name: payload.name,
+            max_parallel: payload.max_parallel,
+            created: Utc::now(),
+        };
+        groups.push(group.clone());
+        Ok(group)
+    }
+    async fn delete_concurrency_group(&self, project_id: i32, name: &str) -> Result<()> {
+        self.concurrency_groups
+            .write()
+            .unwrap()
+            .retain(|g| !(g.project_id == project_id && g.name == name));
+        Ok(())
+    }
+    async fn claim_task_with_slots(
+        &self,
+        task_id: i32,
+        claimer: crate::models::TaskClaimer,
+        slots: &[crate::models::SlotRequest],
+    ) -> Result<crate::models::SlotClaim> {
+        use crate::models::{SlotClaim, TaskClaimer};
+
+        let mut tasks = self.tasks.write().unwrap();
+        let mut held = self.concurrency_slots.write().unwrap();
+        held.retain(|(_, id)| {
+            tasks.get(id).is_some_and(|t| {
+                matches!(
+                    t.status,
+                    TaskStatus::Starting | TaskStatus::Running | TaskStatus::Stopping
+                )
+            })
+        });
+        for slot in slots {
+            let holders: Vec<i32> = held
+                .iter()
+                .filter(|(key, id)| *key == slot.group_key && *id != task_id)
+                .map(|(_, id)| *id)
+                .collect();
+            if holders.len() >= slot.limit.max(1) as usize {
+                return Ok(SlotClaim::Blocked {
+                    group_key: slot.group_key.clone(),
+                    task_id: holders[0],
+                });
+            }
+        }
+
+        let is_hook = self
+            .task_hooks
+            .read()
+            .unwrap()
+            .iter()
+            .any(|h| h.task_id == task_id);
+        let Some(task) = tasks
+            .get_mut(&task_id)
+            .filter(|t| t.status == TaskStatus::Waiting)
+            .filter(|_| !(is_hook && matches!(claimer, TaskClaimer::Runner(_))))
+        else {
+            return Ok(SlotClaim::NotWaiting);
+        };
+        task.status = TaskStatus::Running;
+        task.start = Some(Utc::now());
+        match claimer {
+            TaskClaimer::Node(node_id) => {
+                self.task_nodes.write().unwrap().insert(task_id, node_id);
+            }
+            TaskClaimer::Runner(runner_id) => {
+                self.runner_claims
+                    .write()
+                    .unwrap()
+                    .insert(task_id, runner_id);
+            }
+        }
+        held.retain(|(_, id)| *id != task_id);
+        held.extend(slots.iter().map(|s| (s.group_key.clone(), task_id)));
+        Ok(SlotClaim::Claimed)
+    }
+    async fn get_concurrency_slot_holders(
+        &self,
+    ) -> Result<Vec<crate::models::ConcurrencySlotHolder>> {
+        let tasks = self.tasks.read().unwrap();
+        Ok(self
+            .concurrency_slots
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, id)| {
+                tasks.get(id).is_some_and(|t| {
+                    matches!(
+                        t.status,
+                        TaskStatus::Starting | TaskStatus::Running | TaskStatus::Stopping
+                    )
+                })
+            })
+            .map(|(key, id)| crate::models::ConcurrencySlotHolder {
+                group_key: key.clone(),
+                task_id: *id,
+            })
+            .collect())
+    }
+}
+
 #[async_trait::async_trait]
 impl crate::db::store::StructuredOutputManager for MockStore {
     async fn get_task_structured_outputs(
@@ -2015,7 +3228,13 @@ impl crate::db::store::StructuredOutputManager for MockStore {
     ) -> Result<crate::models::TaskOutputsMap> {
         Ok(crate::models::TaskOutputsMap {
             task_id,
-            outputs: Default::default(),
+            outputs: self
+                .structured_outputs
+                .read()
+                .unwrap()
+                .get(&task_id)
+                .cloned()
+                .unwrap_or_default(),
         })
     }
     async fn create_task_structured_output(
@@ -2028,10 +3247,16 @@ impl crate::db::store::StructuredOutputManager for MockStore {
     }
     async fn create_task_structured_outputs_batch(
         &self,
-        _task_id: i32,
+        task_id: i32,
         _project_id: i32,
-        _outputs: Vec<crate::models::TaskStructuredOutputCreate>,
+        outputs: Vec<crate::models::TaskStructuredOutputCreate>,
     ) -> Result<()> {
+        self.structured_outputs
+            .write()
+            .unwrap()
+            .entry(task_id)
+            .or_default()
+            .extend(outputs.into_iter().map(|o| (o.key, o.value)));
         Ok(())
     }
     async fn delete_task_structured_outputs(&self, _task_id: i32, _project_id: i32) -> Result<()> {
@@ -2119,6 +3344,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         }
     }
 
@@ -2265,6 +3493,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         store.seed_terraform_plan(plan);
         assert_eq!(store.terraform_plans.read().unwrap().len(), 1);
@@ -2362,6 +3592,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let plan2 = TerraformPlan {
             id: 2,
@@ -2377,6 +3609,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         store.seed_terraform_plan(plan1);
         store.seed_terraform_plan(plan2);
@@ -3135,6 +4369,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let created = store.create_plan(plan.clone()).await.unwrap();
         assert_eq!(created.id, 100);
@@ -3386,6 +4622,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         store.seed_template(tpl2);
         assert_eq!(store.templates.read().unwrap().len(), 1);
@@ -0,0 +1,237 @@
+//! ClusterNodeManager — узлы кластера, владение задачами и лидерство
+
+use crate::db::sql::SqlStore;
+use crate::db::store::ClusterNodeManager;
+use crate::error::{Error, Result};
+use crate::models::ClusterLeader;
+use crate::models::workflow::WorkflowRun;
+use async_trait::async_trait;
+use sqlx::{Connection, PgConnection, Row};
+
+/// Префикс `application_name` соединения, держащего блокировку лидерства
+const LEADER_APPLICATION_PREFIX: &str = "velum-leader:";
+
+/// Пространство ключей advisory lock лидерства («velu»)
+const LEADER_LOCK_CLASS: i32 = 0x7665_6c75;
+
+/// TCP keepalive соединения лидерства (простой, интервал, число проб):
+/// PostgreSQL закрывает сессию исчезнувшего узла и снимает блокировку
+/// примерно через 11 секунд, а не через системные два часа
+const LEADER_KEEPALIVES: (&str, &str, &str) = ("5", "2", "3");
+
+/// Предельное время запроса на соединении лидерства
+const LEADER_STATEMENT_TIMEOUT: &str = "5s";
+
+/// Выделенное соединение для advisory lock лидерства
+pub(crate) struct LeaderConnection {
+    conn: PgConnection,
+    /// Блокировка захвачена этим соединением
+    held: bool,
+}
+
+/// Ключ advisory lock аренды (FNV-1a, неотрицательный)
+fn leader_lock_key(lease: &str) -> i32 {
+    let hash = lease.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    (hash & 0x7fff_ffff) as i32
+}
+
+impl SqlStore {
+    /// Открывает соединение вне пула; по `application_name` виден держатель блокировки
+    ///
+    /// Keepalive и `statement_timeout` не дают оборванной сессии удерживать
+    /// блокировку, а продлению — зависнуть на полуоткрытом сокете.
+    async fn open_leader_connection(&self, node_id: &str) -> Result<LeaderConnection> {
+        let mut conn = self
+            .get_postgres_pool()?
+            .acquire()
+            .await
+            .map_err(Error::Database)?
+            .detach();
+        let (idle, interval, count) = LEADER_KEEPALIVES;
+        sqlx::query(
+            "SELECT set_config('application_name', $1, false), \
+             set_config('tcp_keepalives_idle', $2, false), \
+             set_config('tcp_keepalives_interval', $3, false), \
+             set_config('tcp_keepalives_count', $4, false), \
+             set_config('statement_timeout', $5, false)",
+        )
+        .bind(format!("{LEADER_APPLICATION_PREFIX}{node_id}"))
+        .bind(idle)
+        .bind(interval)
+        .bind(count)
+        .bind(LEADER_STATEMENT_TIMEOUT)
+        .execute(&mut conn)
+        .await
+        .map_err(Error::Database)?;
+        Ok(LeaderConnection { conn, held: false })
+    }
+}
+
+#[async_trait]
+impl ClusterNodeManager for SqlStore {
+    async fn touch_cluster_node(&self, node_id: &str, hostname: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO cluster_node (id, hostname, started, last_seen) \
+             VALUES ($1, $2, NOW(), NOW()) \
+             ON CONFLICT (id) DO UPDATE SET hostname = EXCLUDED.hostname, last_seen = NOW()",
+        )
+        .bind(node_id)
+        .bind(hostname)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn set_task_node(&self, task_id: i32, node_id: &str) -> Result<()> {
+        sqlx::query("UPDATE task SET node_id = $2 WHERE id = $1")
+            .bind(task_id)
+            .bind(node_id)
+            .execute(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn claim_task_for_node(&self, task_id: i32, node_id: &str) -> Result<bool> {
+        let claimed: Option<i32> = sqlx::query_scalar(
+            "UPDATE task SET status = 'running', node_id = $2, start_time = NOW() \
+             WHERE id = $1 AND status = 'waiting' RETURNING id",
+        )
+        .bind(task_id)
+        .bind(node_id)
+        .fetch_optional(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(claimed.is_some())
+    }
+
+    async fn fail_orphaned_tasks(&self, node_timeout_secs: i64, message: &str) -> Result<Vec<i32>> {
+        // Задачи сервера без node_id (запущенные до появления учёта узлов)
+        // считаются потерянными, если начались раньше таймаута узла
+        sqlx::query_scalar(
+            "UPDATE task SET status = 'error', end_time = NOW(), message = $2 \
+             WHERE status IN ('starting', 'running', 'stopping') AND ( \
+                 (runner_id IS NULL AND node_id IS NOT NULL AND NOT EXISTS ( \
+                     SELECT 1 FROM cluster_node n WHERE n.id = task.node_id \
+                     AND n.last_seen >= NOW() - make_interval(secs => $1))) \
+                 OR (runner_id IS NULL AND node_id IS NULL \
+                     AND start_time < NOW() - make_interval(secs => $1)) \
+                 OR (runner_id IS NOT NULL AND NOT EXISTS ( \
+                     SELECT 1 FROM runner r WHERE r.id = task.runner_id AND r.active))) \
+             RETURNING id",
+        )
+        .bind(node_timeout_secs as f64)
+        .bind(message)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)
+    }
+
+    async fn set_workflow_run_node(&self, run_id: i32, node_id: &str) -> Result<()> {
+        sqlx::query("UPDATE workflow_run SET node_id = $2 WHERE id = $1")
+            .bind(run_id)
+            .bind(node_id)
+            .execute(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn claim_orphaned_workflow_runs(
+        &self,
+        node_timeout_secs: i64,
+        node_id: &str,
+    ) -> Result<Vec<WorkflowRun>> {
+        // Запуски без node_id (созданные до учёта узлов) считаются потерянными,
+        // если созданы раньше таймаута узла
+        sqlx::query_as::<_, WorkflowRun>(
+            "UPDATE workflow_run SET node_id = $2 \
+             WHERE status IN ('pending', 'running', 'waiting_approval') AND ( \
+                 (node_id IS NOT NULL AND NOT EXISTS ( \
+                     SELECT 1 FROM cluster_node n WHERE n.id = workflow_run.node_id \
+                     AND n.last_seen >= NOW() - make_interval(secs => $1))) \
+                 OR (node_id IS NULL AND created < NOW() - make_interval(secs => $1))) \
+             RETURNING *",
+        )
+        .bind(node_timeout_secs as f64)
+        .bind(node_id)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)
+    }
+
+    async fn try_acquire_leader_lock(&self, lease: &str, node_id: &str) -> Result<bool> {
+        let mut locks = self.leader_locks.lock().await;
+        let mut leader = match locks.remove(lease) {
+            Some(leader) => leader,
+            None => self.open_leader_connection(node_id).await?,
+        };
+        // Держатель только проверяет, что соединение живо: блокировка живёт вместе с ним
+        let result = if leader.held {
+            sqlx::query("SELECT 1")
+                .execute(&mut leader.conn)
+                .await
+                .map(|_| true)
+        } else {
+            sqlx::query_scalar("SELECT pg_try_advisory_lock($1, $2)")
+                .bind(LEADER_LOCK_CLASS)
+                .bind(leader_lock_key(lease))
+                .fetch_one(&mut leader.conn)
+                .await
+        };
+        // Разорванное соединение отбрасывается; следующая попытка откроет новое
+        let held = result.map_err(Error::Database)?;
+        leader.held = held;
+        locks.insert(lease.to_string(), leader);
+        Ok(held)
+    }
+
+    async fn release_leader_lock(&self, lease: &str, _node_id: &str) -> Result<()> {
+        let leader = self.leader_locks.lock().await.remove(lease);
+        if let Some(leader) = leader {
+            // Закрытие соединения снимает advisory lock
+            leader.conn.close().await.map_err(Error::Database)?;
+        }
+        Ok(())
+    }
+
+    async fn get_cluster_leader(&self, lease: &str) -> Result<Option<ClusterLeader>> {
+        let row = sqlx::query(
+            "SELECT split_part(a.application_name, ':', 2) AS node_id, n.hostname, n.last_seen \
+             FROM pg_locks l \
+             JOIN pg_stat_activity a ON a.pid = l.pid \
+             LEFT JOIN cluster_node n ON n.id = split_part(a.application_name, ':', 2) \
+             WHERE l.locktype = 'advisory' AND l.granted AND l.objsubid = 2 \
+               AND l.classid::bigint = $1 AND l.objid::bigint = $2 \
+               AND a.application_name LIKE $3 \
+             LIMIT 1",
+        )
+        .bind(LEADER_LOCK_CLASS as i64)
+        .bind(leader_lock_key(lease) as i64)
+        .bind(format!("{LEADER_APPLICATION_PREFIX}%"))
+        .fetch_optional(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(row.map(|row| ClusterLeader {
+            node_id: row.get("node_id"),
+            hostname: row.get("hostname"),
+            last_seen: row.get("last_seen"),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leader_lock_key_is_stable_and_non_negative() {
+        let key = leader_lock_key("background-services");
+        assert_eq!(key, leader_lock_key("background-services"));
+        assert_ne!(key, leader_lock_key("other"));
+        assert!(key >= 0 && leader_lock_key("") >= 0);
+    }
+}
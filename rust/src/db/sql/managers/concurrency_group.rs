@@ -0,0 +1,177 @@
+//! ConcurrencyGroupManager — группы параллелизма задач
+
+use crate::db::sql::SqlStore;
+use crate::db::store::ConcurrencyGroupManager;
+use crate::error::{Error, Result};
+use crate::models::{
+    ConcurrencyGroup, ConcurrencyGroupUpsert, ConcurrencySlotHolder, SlotClaim, SlotRequest,
+    TaskClaimer,
+};
+use async_trait::async_trait;
+use sqlx::Row;
+
+/// Статусы задач, занимающих слоты групп
+const ACTIVE_STATUSES: &str = "('starting', 'running', 'stopping')";
+
+#[async_trait]
+impl ConcurrencyGroupManager for SqlStore {
+    async fn get_concurrency_groups(&self, project_id: i32) -> Result<Vec<ConcurrencyGroup>> {
+        let rows =
+            sqlx::query("SELECT * FROM concurrency_group WHERE project_id = $1 ORDER BY name")
+                .bind(project_id)
+                .fetch_all(self.get_postgres_pool()?)
+                .await
+                .map_err(Error::Database)?;
+
+        Ok(rows.iter().map(row_to_group).collect())
+    }
+
+    async fn upsert_concurrency_group(
+        &self,
+        project_id: i32,
+        payload: ConcurrencyGroupUpsert,
+    ) -> Result<ConcurrencyGroup> {
+        let row = sqlx::query(
+            "INSERT INTO concurrency_group (project_id, name, max_parallel, created) \
+             VALUES ($1, $2, $3, NOW()) \
+             ON CONFLICT (project_id, name) DO UPDATE SET max_parallel = EXCLUDED.max_parallel \
+             RETURNING *",
+        )
+        .bind(project_id)
+        .bind(&payload.name)
+        .bind(payload.max_parallel)
+        .fetch_one(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(row_to_group(&row))
+    }
+
+    async fn delete_concurrency_group(&self, project_id: i32, name: &str) -> Result<()> {
+        sqlx::query("DELETE FROM concurrency_group WHERE project_id = $1 AND name = $2")
+            .bind(project_id)
+            .bind(name)
+            .execute(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn claim_task_with_slots(
+        &self,
+        task_id: i32,
+        claimer: TaskClaimer,
+        slots: &[SlotRequest],
+    ) -> Result<SlotClaim> {
+        let mut tx = self
+            .get_postgres_pool()?
+            .begin()
+            .await
+            .map_err(Error::Database)?;
+
+        // Группы блокируются в одном порядке, чтобы встречные захваты не взаимоблокировались
+        let mut slots: Vec<&SlotRequest> = slots.iter().collect();
+        slots.sort_by(|a, b| a.group_key.cmp(&b.group_key));
+        for slot in &slots {
+            sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+                .bind(&slot.group_key)
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        for slot in &slots {
+            let holders: Vec<i32> = sqlx::query_scalar(&format!(
+                "SELECT s.task_id FROM concurrency_slot s JOIN task t ON t.id = s.task_id \
+                 WHERE s.group_key = $1 AND s.task_id <> $2 AND t.status IN {ACTIVE_STATUSES} \
+                 ORDER BY s.acquired, s.task_id"
+            ))
+            .bind(&slot.group_key)
+            .bind(task_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+            if holders.len() >= slot.limit.max(1) as usize {
+                return Ok(SlotClaim::Blocked {
+                    group_key: slot.group_key.clone(),
+                    task_id: holders[0],
+                });
+            }
+        }
+
+        let claimed: Option<i32> = match &claimer {
+            TaskClaimer::Node(node_id) => sqlx::query_scalar(
+                "UPDATE task SET status = 'running', node_id = $2, start_time = NOW() \
+                 WHERE id = $1 AND status = 'waiting' RETURNING id",
+            )
+            .bind(task_id)
+            .bind(node_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Error::Database)?,
+            TaskClaimer::Runner(runner_id) => sqlx::query_scalar(
+                "UPDATE task SET status = 'running', runner_id = $2, start_time = NOW() \
+                 WHERE id = $1 AND status = 'waiting' AND parent_task_id IS NULL RETURNING id",
+            )
+            .bind(task_id)
+            .bind(runner_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Error::Database)?,
+        };
+        if claimed.is_none() {
+            return Ok(SlotClaim::NotWaiting);
+        }
+
+        // Слоты завершившихся и возвращённых в очередь задач больше не нужны
+        let keys: Vec<String> = slots.iter().map(|s| s.group_key.clone()).collect();
+        sqlx::query(&format!(
+            "DELETE FROM concurrency_slot s USING task t WHERE s.task_id = t.id \
+             AND (s.task_id = $1 OR (s.group_key = ANY($2) AND t.status NOT IN {ACTIVE_STATUSES}))"
+        ))
+        .bind(task_id)
+        .bind(&keys)
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        for key in &keys {
+            sqlx::query("INSERT INTO concurrency_slot (group_key, task_id) VALUES ($1, $2)")
+                .bind(key)
+                .bind(task_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+        Ok(SlotClaim::Claimed)
+    }
+
+    async fn get_concurrency_slot_holders(&self) -> Result<Vec<ConcurrencySlotHolder>> {
+        let rows = sqlx::query(&format!(
+            "SELECT s.group_key, s.task_id FROM concurrency_slot s JOIN task t ON t.id = s.task_id \
+             WHERE t.status IN {ACTIVE_STATUSES} ORDER BY s.acquired, s.task_id"
+        ))
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ConcurrencySlotHolder {
+                group_key: row.get("group_key"),
+                task_id: row.get("task_id"),
+            })
+            .collect())
+    }
+}
+
+fn row_to_group(row: &sqlx::postgres::PgRow) -> ConcurrencyGroup {
+    ConcurrencyGroup {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        name: row.get("name"),
+        max_parallel: row.get("max_parallel"),
+        created: row.get("created"),
+    }
+}
@@ -55,14 +55,15 @@ impl DeploymentEnvironmentManager for SqlStore {
     ) -> Result<DeploymentEnvironment> {
         let row = sqlx::query(
             "INSERT INTO deployment_environment \
-             (project_id, name, url, tier, status, template_id, created, updated) \
-             VALUES ($1, $2, $3, $4, 'unknown', $5, NOW(), NOW()) RETURNING *",
+             (project_id, name, url, tier, status, template_id, concurrency_group, created, updated) \
+             VALUES ($1, $2, $3, $4, 'unknown', $5, $6, NOW(), NOW()) RETURNING *",
         )
         .bind(project_id)
         .bind(&payload.name)
         .bind(&payload.url)
         .bind(&payload.tier)
         .bind(payload.template_id)
+        .bind(&payload.concurrency_group)
         .fetch_one(self.get_postgres_pool()?)
         .await
         .map_err(Error::Database)?;
@@ -83,6 +84,7 @@ impl DeploymentEnvironmentManager for SqlStore {
              tier        = COALESCE($5, tier), \
              status      = COALESCE($6, status), \
              template_id = COALESCE($7, template_id), \
+             concurrency_group = COALESCE($8, concurrency_group), \
              updated     = NOW() \
              WHERE id = $1 AND project_id = $2 RETURNING *",
         )
@@ -93,6 +95,7 @@ impl DeploymentEnvironmentManager for SqlStore {
         .bind(&payload.tier)
         .bind(&payload.status)
         .bind(payload.template_id)
+        .bind(&payload.concurrency_group)
         .fetch_one(self.get_postgres_pool()?)
         .await
         .map_err(|e| match e {
@@ -205,6 +208,7 @@ fn row_to_env(row: &sqlx::postgres::PgRow) -> DeploymentEnvironment {
         last_task_id: row.try_get("last_task_id").ok().flatten(),
         last_deploy_version: row.try_get("last_deploy_version").ok().flatten(),
         last_deployed_by: row.try_get("last_deployed_by").ok().flatten(),
+        concurrency_group: row.try_get("concurrency_group").ok().flatten(),
         created: row.get("created"),
         updated: row.get("updated"),
     }
@@ -234,6 +238,7 @@ mod tests {
             last_deployed_by: Some(1),
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         assert_eq!(env.name, "Production");
         assert_eq!(env.tier, "production");
@@ -246,6 +251,7 @@ mod tests {
             url: Some("https://staging.example.com".to_string()),
             tier: "staging".to_string(),
             template_id: Some(3),
+            concurrency_group: None,
         };
         assert_eq!(create.name, "Staging");
         assert!(create.url.is_some());
@@ -259,6 +265,7 @@ mod tests {
             tier: Some("production".to_string()),
             status: Some("active".to_string()),
             template_id: Some(5),
+            concurrency_group: None,
         };
         assert!(update.name.is_some());
         assert!(update.status.is_some());
@@ -314,6 +321,7 @@ mod tests {
                 last_deployed_by: None,
                 created: Utc::now(),
                 updated: Utc::now(),
+                concurrency_group: None,
             };
             assert_eq!(env.status, status);
         }
@@ -336,6 +344,7 @@ mod tests {
                 last_deployed_by: None,
                 created: Utc::now(),
                 updated: Utc::now(),
+                concurrency_group: None,
             };
             assert_eq!(env.tier, tier);
         }
@@ -356,6 +365,7 @@ mod tests {
             last_deployed_by: None,
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         let cloned = env.clone();
         assert_eq!(cloned.id, env.id);
@@ -395,6 +405,7 @@ mod tests {
             url: None,
             tier: "development".to_string(),
             template_id: None,
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&create).unwrap();
         assert!(json.contains("\"name\":\"Test Env\""));
@@ -19,6 +19,16 @@ impl DriftManager for SqlStore {
         Ok(rows)
     }
 
+    async fn get_enabled_drift_configs(&self) -> Result<Vec<DriftConfig>> {
+        let rows = sqlx::query_as::<_, DriftConfig>(
+            "SELECT * FROM drift_config WHERE enabled = TRUE ORDER BY id",
+        )
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(rows)
+    }
+
     async fn get_drift_config(&self, id: i32, project_id: i32) -> Result<DriftConfig> {
         let row = sqlx::query_as::<_, DriftConfig>(
             "SELECT * FROM drift_config WHERE id = $1 AND project_id = $2",
@@ -67,6 +77,22 @@ impl DriftManager for SqlStore {
         Ok(())
     }
 
+    async fn update_drift_config_schedule(
+        &self,
+        id: i32,
+        project_id: i32,
+        schedule: Option<String>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE drift_config SET schedule = $1 WHERE id = $2 AND project_id = $3")
+            .bind(&schedule)
+            .bind(id)
+            .bind(project_id)
+            .execute(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        Ok(())
+    }
+
     async fn delete_drift_config(&self, id: i32, project_id: i32) -> Result<()> {
         sqlx::query("DELETE FROM drift_config WHERE id = $1 AND project_id = $2")
             .bind(id)
@@ -118,6 +144,24 @@ impl DriftManager for SqlStore {
         Ok(row)
     }
 
+    async fn update_drift_result(
+        &self,
+        id: i32,
+        status: &str,
+        summary: Option<String>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE drift_result SET status = $1, summary = $2, checked_at = NOW() WHERE id = $3",
+        )
+        .bind(status)
+        .bind(&summary)
+        .bind(id)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(())
+    }
+
     async fn get_latest_drift_results(&self, project_id: i32) -> Result<Vec<DriftResult>> {
         let rows = sqlx::query_as::<_, DriftResult>(
             "SELECT DISTINCT ON (drift_config_id) *
@@ -131,6 +175,16 @@ impl DriftManager for SqlStore {
         .map_err(Error::Database)?;
         Ok(rows)
     }
+
+    async fn get_pending_drift_results(&self) -> Result<Vec<DriftResult>> {
+        let rows = sqlx::query_as::<_, DriftResult>(
+            "SELECT * FROM drift_result WHERE status = 'pending' ORDER BY id",
+        )
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(rows)
+    }
 }
 
 #[cfg(test)]
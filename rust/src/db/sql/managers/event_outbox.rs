@@ -0,0 +1,229 @@
+//! EventOutboxManager — outbox событий и доставки
+
+use crate::db::sql::SqlStore;
+use crate::db::store::EventOutboxManager;
+use crate::error::{Error, Result};
+use crate::models::{
+    DeliveryStatus, EventDelivery, NewEventDelivery, OutboxEvent, WebhookLog,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use sqlx::postgres::PgRow;
+
+fn row_to_event(row: &PgRow) -> OutboxEvent {
+    OutboxEvent {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        event_type: row.get("event_type"),
+        task_id: row.get("task_id"),
+        template_id: row.get("template_id"),
+        payload: row.get("payload"),
+        created: row.get("created"),
+    }
+}
+
+fn row_to_delivery(row: &PgRow) -> Result<EventDelivery> {
+    let target: String = row.get("target");
+    let status: String = row.get("status");
+    Ok(EventDelivery {
+        id: row.get("id"),
+        event_id: row.get("event_id"),
+        project_id: row.get("project_id"),
+        target: target.parse().map_err(Error::Other)?,
+        target_id: row.get("target_id"),
+        status: status.parse().map_err(Error::Other)?,
+        attempts: row.get("attempts"),
+        max_attempts: row.get("max_attempts"),
+        next_attempt: row.get("next_attempt"),
+        last_error: row.get("last_error"),
+        created: row.get("created"),
+        delivered: row.get("delivered"),
+    })
+}
+
+#[async_trait]
+impl EventOutboxManager for SqlStore {
+    async fn create_outbox_event(&self, mut event: OutboxEvent) -> Result<OutboxEvent> {
+        let row = sqlx::query(
+            "INSERT INTO event_outbox (project_id, event_type, task_id, template_id, payload) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING id, created",
+        )
+        .bind(event.project_id)
+        .bind(&event.event_type)
+        .bind(event.task_id)
+        .bind(event.template_id)
+        .bind(&event.payload)
+        .fetch_one(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+
+        event.id = row.get("id");
+        event.created = row.get("created");
+        Ok(event)
+    }
+
+    async fn get_outbox_event(&self, id: i64) -> Result<OutboxEvent> {
+        let row = sqlx::query("SELECT * FROM event_outbox WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?
+            .ok_or_else(|| Error::NotFound(format!("Event {id} not found")))?;
+        Ok(row_to_event(&row))
+    }
+
+    async fn get_undispatched_events(&self, limit: i64) -> Result<Vec<OutboxEvent>> {
+        let rows =
+            sqlx::query("SELECT * FROM event_outbox WHERE NOT dispatched ORDER BY id LIMIT $1")
+                .bind(limit)
+                .fetch_all(self.get_postgres_pool()?)
+                .await
+                .map_err(Error::Database)?;
+        Ok(rows.iter().map(row_to_event).collect())
+    }
+
+    async fn dispatch_outbox_event(
+        &self,
+        event_id: i64,
+        deliveries: Vec<NewEventDelivery>,
+    ) -> Result<()> {
+        let mut tx = self
+            .get_postgres_pool()?
+            .begin()
+            .await
+            .map_err(Error::Database)?;
+
+        // Отметка и вставка в одной транзакции: событие раскладывается ровно один раз
+        let project_id: Option<i32> = sqlx::query_scalar(
+            "UPDATE event_outbox SET dispatched = TRUE \
+             WHERE id = $1 AND NOT dispatched RETURNING project_id",
+        )
+        .bind(event_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        let Some(project_id) = project_id else {
+            return Ok(());
+        };
+
+        for delivery in deliveries {
+            sqlx::query(
+                "INSERT INTO event_delivery (event_id, project_id, target, target_id, max_attempts) \
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(event_id)
+            .bind(project_id)
+            .bind(delivery.target.as_str())
+            .bind(delivery.target_id)
+            .bind(delivery.max_attempts)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+        }
+
+        tx.commit().await.map_err(Error::Database)
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> Result<Vec<EventDelivery>> {
+        let rows = sqlx::query(
+            "UPDATE event_delivery SET attempts = attempts + 1, \
+                 next_attempt = NOW() + make_interval(secs => $2) \
+             WHERE id IN ( \
+                 SELECT id FROM event_delivery \
+                 WHERE status = 'pending' AND next_attempt <= NOW() \
+                 ORDER BY next_attempt, id LIMIT $1 \
+                 FOR UPDATE SKIP LOCKED \
+             ) RETURNING *",
+        )
+        .bind(limit)
+        .bind(lease_secs as f64)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+
+        let mut deliveries = rows
+            .iter()
+            .map(row_to_delivery)
+            .collect::<Result<Vec<_>>>()?;
+        deliveries.sort_by_key(|d| d.id);
+        Ok(deliveries)
+    }
+
+    async fn finish_event_delivery(
+        &self,
+        id: i64,
+        status: DeliveryStatus,
+        next_attempt: Option<DateTime<Utc>>,
+        error: Option<String>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE event_delivery SET status = $2, \
+                 next_attempt = COALESCE($3, next_attempt), last_error = $4, \
+                 delivered = CASE WHEN $2 = 'delivered' THEN NOW() ELSE delivered END \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(next_attempt)
+        .bind(error)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn get_event_deliveries(
+        &self,
+        project_id: i32,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> Result<Vec<EventDelivery>> {
+        let rows = sqlx::query(
+            "SELECT * FROM event_delivery \
+             WHERE project_id = $1 AND ($2::TEXT IS NULL OR status = $2) \
+             ORDER BY id DESC LIMIT $3",
+        )
+        .bind(project_id)
+        .bind(status.map(|s| s.as_str()))
+        .bind(limit)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        rows.iter().map(row_to_delivery).collect()
+    }
+
+    async fn get_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery> {
+        let row = sqlx::query("SELECT * FROM event_delivery WHERE id = $1 AND project_id = $2")
+            .bind(id)
+            .bind(project_id)
+            .fetch_optional(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?
+            .ok_or_else(|| Error::NotFound(format!("Delivery {id} not found")))?;
+        row_to_delivery(&row)
+    }
+
+    async fn redeliver_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery> {
+        let row = sqlx::query(
+            "UPDATE event_delivery SET status = 'pending', attempts = 0, \
+                 next_attempt = NOW(), last_error = NULL \
+             WHERE id = $1 AND project_id = $2 RETURNING *",
+        )
+        .bind(id)
+        .bind(project_id)
+        .fetch_optional(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?
+        .ok_or_else(|| Error::NotFound(format!("Delivery {id} not found")))?;
+        row_to_delivery(&row)
+    }
+
+    async fn get_event_delivery_logs(&self, delivery_id: i64) -> Result<Vec<WebhookLog>> {
+        self.db.get_delivery_webhook_logs(delivery_id).await
+    }
+}
@@ -3,7 +3,7 @@
 use crate::db::sql::SqlStore;
 use crate::db::store::*;
 use crate::error::{Error, Result};
-use crate::models::Integration;
+use crate::models::{Integration, IntegrationAlias};
 use async_trait::async_trait;
 use sqlx::Row;
 
@@ -103,6 +103,65 @@ impl IntegrationManager for SqlStore {
             .map_err(Error::Database)?;
         Ok(())
     }
+
+    async fn get_integration_aliases(
+        &self,
+        project_id: i32,
+        integration_id: Option<i32>,
+    ) -> Result<Vec<IntegrationAlias>> {
+        let pool = self.get_postgres_pool()?;
+        let aliases = sqlx::query_as::<_, IntegrationAlias>(
+            "SELECT id, integration_id, project_id, alias FROM integration_alias WHERE project_id = $1 AND ($2::INTEGER IS NULL OR integration_id = $2) ORDER BY id",
+        )
+        .bind(project_id)
+        .bind(integration_id)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Database)?;
+        Ok(aliases)
+    }
+
+    async fn get_integration_alias_by_alias(&self, alias: &str) -> Result<IntegrationAlias> {
+        let pool = self.get_postgres_pool()?;
+        sqlx::query_as::<_, IntegrationAlias>(
+            "SELECT id, integration_id, project_id, alias FROM integration_alias WHERE alias = $1",
+        )
+        .bind(alias)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Database)?
+        .ok_or_else(|| Error::NotFound("Псевдоним интеграции не найден".to_string()))
+    }
+
+    async fn create_integration_alias(
+        &self,
+        mut alias: IntegrationAlias,
+    ) -> Result<IntegrationAlias> {
+        let pool = self.get_postgres_pool()?;
+        let id: i32 = sqlx::query_scalar(
+            "INSERT INTO integration_alias (integration_id, project_id, alias) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(alias.integration_id)
+        .bind(alias.project_id)
+        .bind(&alias.alias)
+        .fetch_one(pool)
+        .await
+        .map_err(Error::Database)?;
+
+        alias.id = id;
+        Ok(alias)
+    }
+
+    async fn delete_integration_alias(&self, project_id: i32, alias_id: i32) -> Result<()> {
+        let pool = self.get_postgres_pool()?;
+        sqlx::query("DELETE FROM integration_alias WHERE id = $1 AND project_id = $2")
+            .bind(alias_id)
+            .bind(project_id)
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
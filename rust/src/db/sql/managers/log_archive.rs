@@ -0,0 +1,211 @@
+//! TaskLogArchiveManager — архив логов задач и политики хранения
+
+use crate::db::sql::SqlStore;
+use crate::db::store::TaskLogArchiveManager;
+use crate::error::{Error, Result};
+use crate::models::{LogRetentionPolicy, TaskLogArchive};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// Статусы завершённых задач (`TaskStatus::is_finished`)
+const FINISHED_STATUSES: &str = "('success', 'error', 'stopped', 'not_executed')";
+
+#[async_trait]
+impl TaskLogArchiveManager for SqlStore {
+    async fn get_tasks_to_archive(
+        &self,
+        finished_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<(i32, i32)>> {
+        let rows = sqlx::query(&format!(
+            "SELECT t.project_id, t.id FROM task t \
+             WHERE t.status IN {FINISHED_STATUSES} \
+             AND COALESCE(t.end_time, t.created) < $1 \
+             AND NOT EXISTS (SELECT 1 FROM task_log_archive a WHERE a.task_id = t.id) \
+             AND EXISTS (SELECT 1 FROM task_output o WHERE o.task_id = t.id) \
+             ORDER BY t.id LIMIT $2"
+        ))
+        .bind(finished_before)
+        .bind(limit)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("project_id"), row.get("id")))
+            .collect())
+    }
+
+    async fn get_task_log_archive(&self, task_id: i32) -> Result<Option<TaskLogArchive>> {
+        let row = sqlx::query("SELECT * FROM task_log_archive WHERE task_id = $1")
+            .bind(task_id)
+            .fetch_optional(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(row.map(|row| TaskLogArchive {
+            task_id: row.get("task_id"),
+            project_id: row.get("project_id"),
+            backend: row.get("backend"),
+            location: row.get("location"),
+            chunks: row.get("chunks"),
+            lines: row.get("lines"),
+            bytes: row.get("bytes"),
+            archived: row.get("archived"),
+        }))
+    }
+
+    async fn complete_task_log_archive(
+        &self,
+        archive: TaskLogArchive,
+        last_output_id: i32,
+    ) -> Result<()> {
+        let mut tx = self
+            .get_postgres_pool()?
+            .begin()
+            .await
+            .map_err(Error::Database)?;
+
+        sqlx::query(
+            "INSERT INTO task_log_archive \
+             (task_id, project_id, backend, location, chunks, lines, bytes, archived) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(archive.task_id)
+        .bind(archive.project_id)
+        .bind(&archive.backend)
+        .bind(&archive.location)
+        .bind(archive.chunks)
+        .bind(archive.lines)
+        .bind(archive.bytes)
+        .bind(archive.archived)
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query("DELETE FROM task_output WHERE task_id = $1 AND id <= $2")
+            .bind(archive.task_id)
+            .bind(last_output_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)
+    }
+
+    async fn get_log_retention_policies(&self) -> Result<Vec<LogRetentionPolicy>> {
+        let rows = sqlx::query("SELECT * FROM project_log_retention ORDER BY project_id")
+            .fetch_all(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(rows.iter().map(row_to_policy).collect())
+    }
+
+    async fn get_log_retention_policy(
+        &self,
+        project_id: i32,
+    ) -> Result<Option<LogRetentionPolicy>> {
+        let row = sqlx::query("SELECT * FROM project_log_retention WHERE project_id = $1")
+            .bind(project_id)
+            .fetch_optional(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(row.as_ref().map(row_to_policy))
+    }
+
+    async fn set_log_retention_policy(&self, policy: LogRetentionPolicy) -> Result<()> {
+        let pool = self.get_postgres_pool()?;
+        if policy.is_unlimited() {
+            sqlx::query("DELETE FROM project_log_retention WHERE project_id = $1")
+                .bind(policy.project_id)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+            return Ok(());
+        }
+
+        sqlx::query(
+            "INSERT INTO project_log_retention (project_id, max_age_days, keep_last_runs) \
+             VALUES ($1, $2, $3) \
+             ON CONFLICT (project_id) DO UPDATE SET \
+                 max_age_days = EXCLUDED.max_age_days, \
+                 keep_last_runs = EXCLUDED.keep_last_runs",
+        )
+        .bind(policy.project_id)
+        .bind(policy.max_age_days)
+        .bind(policy.keep_last_runs)
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn get_expired_task_ids(&self, policy: &LogRetentionPolicy) -> Result<Vec<i32>> {
+        // Запуски нумеруются в пределах шаблона от новых к старым
+        sqlx::query_scalar(&format!(
+            "SELECT id FROM ( \
+                 SELECT id, status, COALESCE(end_time, created) AS finished, \
+                     ROW_NUMBER() OVER (PARTITION BY template_id ORDER BY created DESC, id DESC) AS run \
+                 FROM task WHERE project_id = $1 \
+             ) ranked \
+             WHERE status IN {FINISHED_STATUSES} \
+             AND (($2::INTEGER IS NOT NULL AND finished < NOW() - make_interval(days => $2)) \
+                 OR ($3::INTEGER IS NOT NULL AND run > $3)) \
+             ORDER BY id"
+        ))
+        .bind(policy.project_id)
+        .bind(policy.max_age_days)
+        .bind(policy.keep_last_runs)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)
+    }
+
+    async fn purge_tasks(&self, project_id: i32, task_ids: &[i32]) -> Result<()> {
+        if task_ids.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self
+            .get_postgres_pool()?
+            .begin()
+            .await
+            .map_err(Error::Database)?;
+
+        // Стадии хранятся не во всех установках
+        let has_stages: bool = sqlx::query_scalar("SELECT to_regclass('task_stage') IS NOT NULL")
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+        let mut tables = vec!["task_output", "task_log_archive"];
+        if has_stages {
+            tables.push("task_stage");
+        }
+        for table in tables {
+            sqlx::query(&format!("DELETE FROM {table} WHERE task_id = ANY($1)"))
+                .bind(task_ids)
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+        }
+        sqlx::query("DELETE FROM task WHERE project_id = $1 AND id = ANY($2)")
+            .bind(project_id)
+            .bind(task_ids)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)
+    }
+}
+
+fn row_to_policy(row: &sqlx::postgres::PgRow) -> LogRetentionPolicy {
+    LogRetentionPolicy {
+        project_id: row.get("project_id"),
+        max_age_days: row.get("max_age_days"),
+        keep_last_runs: row.get("keep_last_runs"),
+    }
+}
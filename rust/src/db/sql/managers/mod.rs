@@ -35,6 +35,8 @@
 //! - `WebhookManager` - управление webhook
 
 pub mod access_key;
+pub mod cluster_node;
+pub mod concurrency_group;
 pub mod connection;
 pub mod cost_estimate;
 pub mod credential_type;
@@ -42,11 +44,13 @@ pub mod deployment_environment;
 pub mod drift;
 pub mod environment;
 pub mod event;
+pub mod event_outbox;
 pub mod hook;
 pub mod integration;
 pub mod integration_matcher;
 pub mod inventory;
 pub mod ldap_group;
+pub mod log_archive;
 pub mod migration;
 pub mod notification;
 pub mod options;
@@ -55,10 +59,12 @@ pub mod plan_approval;
 pub mod playbook;
 pub mod playbook_run;
 pub mod project;
+pub mod project_access;
 pub mod project_invite;
 pub mod repository;
 pub mod runner;
 pub mod schedule;
+pub mod service_account;
 pub mod session;
 pub mod snapshot;
 pub mod state_backend;
@@ -14,11 +14,13 @@ impl PlanApprovalManager for SqlStore {
         let row = sqlx::query(
             "INSERT INTO terraform_plan
                (task_id, project_id, plan_output, plan_json,
-                resources_added, resources_changed, resources_removed, status)
-             VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+                resources_added, resources_changed, resources_removed, status,
+                plan_file, state_serial)
+             VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
              RETURNING id, task_id, project_id, plan_output, plan_json,
                        resources_added, resources_changed, resources_removed,
-                       status, created_at, reviewed_at, reviewed_by, review_comment",
+                       status, created_at, reviewed_at, reviewed_by, review_comment,
+                       plan_file, state_serial",
         )
         .bind(plan.task_id)
         .bind(plan.project_id)
@@ -28,6 +30,8 @@ impl PlanApprovalManager for SqlStore {
         .bind(plan.resources_changed)
         .bind(plan.resources_removed)
         .bind(&plan.status)
+        .bind(&plan.plan_file)
+        .bind(plan.state_serial)
         .fetch_one(pool)
         .await
         .map_err(Error::Database)?;
@@ -44,7 +48,8 @@ impl PlanApprovalManager for SqlStore {
         let row = sqlx::query(
             "SELECT id, task_id, project_id, plan_output, plan_json,
                     resources_added, resources_changed, resources_removed,
-                    status, created_at, reviewed_at, reviewed_by, review_comment
+                    status, created_at, reviewed_at, reviewed_by, review_comment,
+                    plan_file, state_serial
              FROM terraform_plan
              WHERE project_id = $1 AND task_id = $2
              ORDER BY id DESC LIMIT 1",
@@ -164,6 +169,8 @@ fn row_to_plan(row: sqlx::postgres::PgRow) -> TerraformPlan {
         reviewed_at: row.try_get("reviewed_at").ok().flatten(),
         reviewed_by: row.try_get("reviewed_by").ok().flatten(),
         review_comment: row.try_get("review_comment").ok().flatten(),
+        plan_file: row.try_get("plan_file").ok().flatten(),
+        state_serial: row.try_get("state_serial").ok().flatten(),
     }
 }
 
@@ -187,6 +194,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         assert_eq!(plan.resources_added, 1);
         assert_eq!(plan.status, "pending");
@@ -208,6 +217,8 @@ mod tests {
             reviewed_at: Some(chrono::Utc::now()),
             reviewed_by: Some(5),
             review_comment: Some("Looks good".to_string()),
+            plan_file: None,
+            state_serial: None,
         };
         assert_eq!(plan.status, "approved");
         assert!(plan.review_comment.is_some());
@@ -231,6 +242,8 @@ mod tests {
                 reviewed_at: None,
                 reviewed_by: None,
                 review_comment: None,
+                plan_file: None,
+                state_serial: None,
             };
             assert_eq!(plan.status, status);
         }
@@ -252,6 +265,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let json = serde_json::to_string(&plan).unwrap();
         assert!(json.contains("\"id\":42"));
@@ -274,6 +289,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         assert_eq!(plan.resources_added, 5);
         assert_eq!(plan.resources_changed, 3);
@@ -296,6 +313,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: Some("review comment".to_string()),
+            plan_file: None,
+            state_serial: None,
         };
         let cloned = plan.clone();
         assert_eq!(cloned.task_id, plan.task_id);
@@ -371,6 +390,8 @@ mod tests {
             reviewed_at: Some(chrono::Utc::now()),
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         assert!(plan.plan_json.is_none());
         assert!(plan.reviewed_at.is_some());
@@ -393,6 +414,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         assert!(!plan.plan_output.is_empty());
     }
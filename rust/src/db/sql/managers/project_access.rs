@@ -0,0 +1,67 @@
+//! ProjectAccessManager — роли участников проекта и переопределения шаблонов
+
+use crate::db::sql::SqlStore;
+use crate::db::store::ProjectAccessManager;
+use crate::error::{Error, Result};
+use crate::models::TemplateRolePerm;
+use async_trait::async_trait;
+
+#[async_trait]
+impl ProjectAccessManager for SqlStore {
+    async fn get_project_user_role(&self, project_id: i32, user_id: i32) -> Result<Option<String>> {
+        sqlx::query_scalar(
+            "SELECT role FROM project_user WHERE project_id = $1 AND user_id = $2 \
+             ORDER BY id LIMIT 1",
+        )
+        .bind(project_id)
+        .bind(user_id)
+        .fetch_optional(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)
+    }
+
+    async fn set_project_user_role(&self, project_id: i32, user_id: i32, role: &str) -> Result<()> {
+        let pool = self.get_postgres_pool()?;
+        let updated =
+            sqlx::query("UPDATE project_user SET role = $3 WHERE project_id = $1 AND user_id = $2")
+                .bind(project_id)
+                .bind(user_id)
+                .bind(role)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        if updated.rows_affected() == 0 {
+            sqlx::query("INSERT INTO project_user (project_id, user_id, role) VALUES ($1, $2, $3)")
+                .bind(project_id)
+                .bind(user_id)
+                .bind(role)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+        Ok(())
+    }
+
+    async fn get_template_role_perms(
+        &self,
+        project_id: i32,
+        template_id: i32,
+    ) -> Result<Vec<TemplateRolePerm>> {
+        self.db.get_template_roles(project_id, template_id).await
+    }
+
+    async fn create_template_role_perm(&self, perm: TemplateRolePerm) -> Result<TemplateRolePerm> {
+        self.db.create_template_role(perm).await
+    }
+
+    async fn delete_template_role_perm(
+        &self,
+        project_id: i32,
+        template_id: i32,
+        id: i32,
+    ) -> Result<()> {
+        self.db
+            .delete_template_role(project_id, template_id, id)
+            .await
+    }
+}
@@ -1,9 +1,10 @@
 //! RunnerManager - управление раннерами
 
+use super::task::row_to_task;
 use crate::db::sql::SqlStore;
 use crate::db::store::*;
 use crate::error::{Error, Result};
-use crate::models::Runner;
+use crate::models::{Runner, Task};
 use async_trait::async_trait;
 use sqlx::Row;
 
@@ -21,9 +22,21 @@ fn row_to_runner(row: sqlx::postgres::PgRow) -> Runner {
         cleaning_requested: None,
         touched: row.try_get("last_active").ok().flatten(),
         created: row.try_get("created").ok().flatten(),
+        capabilities: row
+            .try_get::<Option<String>, _>("capabilities")
+            .ok()
+            .flatten()
+            .and_then(|c| serde_json::from_str(&c).ok()),
     }
 }
 
+fn capabilities_json(runner: &Runner) -> Option<String> {
+    runner
+        .capabilities
+        .as_ref()
+        .and_then(|c| serde_json::to_string(c).ok())
+}
+
 #[async_trait]
 impl RunnerManager for SqlStore {
     async fn get_runners(&self, project_id: Option<i32>) -> Result<Vec<Runner>> {
@@ -59,7 +72,7 @@ impl RunnerManager for SqlStore {
     async fn create_runner(&self, mut runner: Runner) -> Result<Runner> {
         let pool = self.get_postgres_pool()?;
         let id: i32 = sqlx::query_scalar(
-            "INSERT INTO runner (project_id, token, name, active, webhook, max_parallel_tasks, tag) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id"
+            "INSERT INTO runner (project_id, token, name, active, webhook, max_parallel_tasks, tag, capabilities) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id"
         )
         .bind(runner.project_id)
         .bind(&runner.token)
@@ -68,6 +81,7 @@ impl RunnerManager for SqlStore {
         .bind(&runner.webhook)
         .bind(runner.max_parallel_tasks)
         .bind(&runner.tag)
+        .bind(capabilities_json(&runner))
         .fetch_one(pool)
         .await
         .map_err(Error::Database)?;
@@ -79,13 +93,14 @@ impl RunnerManager for SqlStore {
     async fn update_runner(&self, runner: Runner) -> Result<()> {
         let pool = self.get_postgres_pool()?;
         sqlx::query(
-            "UPDATE runner SET name = $1, active = $2, webhook = $3, max_parallel_tasks = $4, tag = $5 WHERE id = $6"
+            "UPDATE runner SET name = $1, active = $2, webhook = $3, max_parallel_tasks = $4, tag = $5, capabilities = COALESCE($6, capabilities) WHERE id = $7"
         )
         .bind(&runner.name)
         .bind(runner.active)
         .bind(&runner.webhook)
         .bind(runner.max_parallel_tasks)
         .bind(&runner.tag)
+        .bind(capabilities_json(&runner))
         .bind(runner.id)
         .execute(pool)
         .await
@@ -141,6 +156,54 @@ impl RunnerManager for SqlStore {
             .map_err(Error::Database)?;
         Ok(())
     }
+
+    async fn claim_task(&self, task_id: i32, runner_id: i32) -> Result<bool> {
+        let pool = self.get_postgres_pool()?;
+        let claimed: Option<i32> = sqlx::query_scalar(
+            "UPDATE task SET status = 'running', runner_id = $2, start_time = NOW() \
+             WHERE id = $1 AND status = 'waiting' RETURNING id",
+        )
+        .bind(task_id)
+        .bind(runner_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::Database)?;
+        Ok(claimed.is_some())
+    }
+
+    async fn get_runner_task_ids(&self, runner_id: i32) -> Result<Vec<i32>> {
+        let pool = self.get_postgres_pool()?;
+        sqlx::query_scalar(
+            "SELECT id FROM task WHERE runner_id = $1 \
+             AND status IN ('starting', 'running', 'stopping') ORDER BY id",
+        )
+        .bind(runner_id)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    async fn requeue_stale_runner_tasks(&self, timeout_secs: i64) -> Result<Vec<Task>> {
+        let pool = self.get_postgres_pool()?;
+        // Остановку уже некому выполнить — задача считается остановленной
+        let rows = sqlx::query(
+            "UPDATE task SET \
+                 status = CASE WHEN status = 'stopping' THEN 'stopped' ELSE 'waiting' END, \
+                 runner_id = CASE WHEN status = 'stopping' THEN runner_id END, \
+                 start_time = CASE WHEN status = 'stopping' THEN start_time END, \
+                 end_time = CASE WHEN status = 'stopping' THEN NOW() ELSE end_time END \
+             WHERE runner_id IS NOT NULL AND status IN ('starting', 'running', 'stopping') \
+             AND EXISTS (SELECT 1 FROM runner r WHERE r.id = task.runner_id AND r.active \
+                 AND (r.last_active IS NULL \
+                     OR r.last_active < NOW() - make_interval(secs => $1))) \
+             RETURNING *",
+        )
+        .bind(timeout_secs as f64)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Database)?;
+        Ok(rows.iter().map(row_to_task).collect())
+    }
 }
 
 #[cfg(test)]
@@ -163,6 +226,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(json.contains("\"name\":\"Build Runner\""));
@@ -185,6 +249,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(!json.contains("\"webhook\":"));
@@ -207,6 +272,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert!(runner.active);
     }
@@ -226,6 +292,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(json.contains("\"active\":false"));
@@ -246,6 +313,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let cloned = runner.clone();
         assert_eq!(cloned.name, runner.name);
@@ -278,6 +346,7 @@ mod tests {
             cleaning_requested: Some(now),
             touched: Some(now),
             created: Some(now),
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(json.contains("\"webhook\":\"https://hooks.example.com\""));
@@ -300,6 +369,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert!(global.project_id.is_none());
 
@@ -316,6 +386,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert_eq!(project.project_id, Some(5));
     }
@@ -335,6 +406,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert!(!runner.token.is_empty());
     }
@@ -354,6 +426,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(json.contains("\"tag\":\"windows\""));
@@ -374,6 +447,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert_eq!(runner.max_parallel_tasks, Some(0));
     }
@@ -393,6 +467,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert!(!runner.name.is_empty());
     }
@@ -3,18 +3,22 @@
 //! Реализация трейта ScheduleManager для SqlStore
 
 use crate::db::sql::SqlStore;
+use crate::db::sql::schedule::schedule_from_row;
 use crate::db::store::*;
 use crate::error::{Error, Result};
 use crate::models::{Schedule, ScheduleWithTpl};
 use async_trait::async_trait;
-use sqlx::Row;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
 
 #[async_trait]
 impl ScheduleManager for SqlStore {
     async fn get_schedules(&self, project_id: i32) -> Result<Vec<Schedule>> {
         let rows = sqlx::query(
             "SELECT id, project_id, template_id, cron, cron_format, name, active, \
-             last_commit_hash, repository_id, created::text AS created, run_at, delete_after_run \
+             last_commit_hash, repository_id, created::text AS created, run_at, delete_after_run, \
+             target_type, target_id, timezone, misfire_policy, jitter_secs, blackout_windows, \
+             last_fired, git_branch, commit_paths \
              FROM schedule WHERE project_id = $1 ORDER BY name",
         )
         .bind(project_id)
@@ -22,13 +26,15 @@ impl ScheduleManager for SqlStore {
         .await
         .map_err(Error::Database)?;
 
-        Ok(rows.into_iter().map(row_to_schedule).collect())
+        Ok(rows.iter().map(schedule_from_row).collect())
     }
 
     async fn get_schedule(&self, _project_id: i32, schedule_id: i32) -> Result<Schedule> {
         let row = sqlx::query(
             "SELECT id, project_id, template_id, cron, cron_format, name, active, \
-             last_commit_hash, repository_id, created::text AS created, run_at, delete_after_run \
+             last_commit_hash, repository_id, created::text AS created, run_at, delete_after_run, \
+             target_type, target_id, timezone, misfire_policy, jitter_secs, blackout_windows, \
+             last_fired, git_branch, commit_paths \
              FROM schedule WHERE id = $1",
         )
         .bind(schedule_id)
@@ -39,13 +45,16 @@ impl ScheduleManager for SqlStore {
             _ => Error::Database(e),
         })?;
 
-        Ok(row_to_schedule(row))
+        Ok(schedule_from_row(&row))
     }
 
     async fn create_schedule(&self, mut schedule: Schedule) -> Result<Schedule> {
         let id: i32 = sqlx::query_scalar(
-            "INSERT INTO schedule (project_id, template_id, cron, cron_format, name, active, run_at, delete_after_run) \
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+            "INSERT INTO schedule (project_id, template_id, cron, cron_format, name, active, run_at, delete_after_run, \
+             target_type, target_id, timezone, misfire_policy, jitter_secs, blackout_windows, \
+             repository_id, git_branch, commit_paths) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17) \
+             RETURNING id",
         )
         .bind(schedule.project_id)
         .bind(schedule.template_id)
@@ -55,6 +64,15 @@ impl ScheduleManager for SqlStore {
         .bind(schedule.active)
         .bind(&schedule.run_at)
         .bind(schedule.delete_after_run)
+        .bind(&schedule.target_type)
+        .bind(schedule.target_id)
+        .bind(&schedule.timezone)
+        .bind(&schedule.misfire_policy)
+        .bind(schedule.jitter_secs)
+        .bind(Json(&schedule.blackout_windows))
+        .bind(schedule.repository_id)
+        .bind(&schedule.git_branch)
+        .bind(Json(&schedule.commit_paths))
         .fetch_one(self.get_postgres_pool()?)
         .await
         .map_err(Error::Database)?;
@@ -66,7 +84,9 @@ impl ScheduleManager for SqlStore {
     async fn update_schedule(&self, schedule: Schedule) -> Result<()> {
         sqlx::query(
             "UPDATE schedule SET cron = $1, cron_format = $2, name = $3, active = $4, \
-             run_at = $5, delete_after_run = $6 WHERE id = $7",
+             run_at = $5, delete_after_run = $6, target_type = $7, target_id = $8, \
+             timezone = $9, misfire_policy = $10, jitter_secs = $11, blackout_windows = $12, \
+             repository_id = $13, git_branch = $14, commit_paths = $15 WHERE id = $16",
         )
         .bind(&schedule.cron)
         .bind(&schedule.cron_format)
@@ -74,6 +94,15 @@ impl ScheduleManager for SqlStore {
         .bind(schedule.active)
         .bind(&schedule.run_at)
         .bind(schedule.delete_after_run)
+        .bind(&schedule.target_type)
+        .bind(schedule.target_id)
+        .bind(&schedule.timezone)
+        .bind(&schedule.misfire_policy)
+        .bind(schedule.jitter_secs)
+        .bind(Json(&schedule.blackout_windows))
+        .bind(schedule.repository_id)
+        .bind(&schedule.git_branch)
+        .bind(Json(&schedule.commit_paths))
         .bind(schedule.id)
         .execute(self.get_postgres_pool()?)
         .await
@@ -120,25 +149,18 @@ impl ScheduleManager for SqlStore {
         Ok(())
     }
 
-    async fn get_all_schedules(&self) -> Result<Vec<Schedule>> {
-        self.db.get_all_schedules().await
+    async fn set_schedule_last_fired(&self, schedule_id: i32, fired: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE schedule SET last_fired = $1 WHERE id = $2")
+            .bind(fired)
+            .bind(schedule_id)
+            .execute(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        Ok(())
     }
-}
 
-fn row_to_schedule(row: sqlx::postgres::PgRow) -> Schedule {
-    Schedule {
-        id: row.get("id"),
-        project_id: row.get("project_id"),
-        template_id: row.get("template_id"),
-        cron: row.get("cron"),
-        cron_format: row.try_get("cron_format").ok().flatten(),
-        name: row.get("name"),
-        active: row.get("active"),
-        last_commit_hash: row.try_get("last_commit_hash").ok().flatten(),
-        repository_id: row.try_get("repository_id").ok(),
-        created: row.try_get("created").ok().flatten(),
-        run_at: row.try_get("run_at").ok().flatten(),
-        delete_after_run: row.get("delete_after_run"),
+    async fn get_all_schedules(&self) -> Result<Vec<Schedule>> {
+        self.db.get_all_schedules().await
     }
 }
 
@@ -165,6 +187,15 @@ mod tests {
             created: Some("2024-01-01T00:00:00Z".to_string()),
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"name\":\"Hourly Deploy\""));
@@ -187,6 +218,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(!json.contains("\"run_at\":"));
@@ -209,6 +249,15 @@ mod tests {
             created: None,
             run_at: Some("2024-06-15T10:00:00Z".to_string()),
             delete_after_run: true,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"run_at\":\"2024-06-15T10:00:00Z\""));
@@ -230,6 +279,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let with_tpl = ScheduleWithTpl {
             schedule,
@@ -255,6 +313,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let with_tpl = ScheduleWithTpl {
             schedule,
@@ -279,6 +346,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let cloned = schedule.clone();
         assert_eq!(cloned.id, schedule.id);
@@ -301,6 +377,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert!(schedule.cron.is_empty());
         assert!(schedule.name.is_empty());
@@ -323,6 +408,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert!(active_schedule.active);
 
@@ -339,6 +433,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert!(!inactive.active);
     }
@@ -358,6 +461,15 @@ mod tests {
             created: None,
             run_at: Some("2025-01-01T00:00:00Z".to_string()),
             delete_after_run: true,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert!(schedule.delete_after_run);
     }
@@ -379,6 +491,15 @@ mod tests {
                 created: None,
                 run_at: None,
                 delete_after_run: false,
+                target_type: "template".to_string(),
+                target_id: None,
+                timezone: None,
+                misfire_policy: "skip".to_string(),
+                jitter_secs: 0,
+                blackout_windows: Vec::new(),
+                last_fired: None,
+                git_branch: None,
+                commit_paths: Vec::new(),
             };
             let json = serde_json::to_string(&schedule).unwrap();
             assert!(json.contains(&format!("\"cron_format\":\"{}\"", fmt)));
@@ -400,6 +521,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"repository_id\":42"));
@@ -421,6 +551,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert!(!schedule.name.is_empty());
     }
@@ -0,0 +1,65 @@
+//! ServiceAccountManager — сервисные аккаунты
+
+use crate::db::sql::SqlStore;
+use crate::db::store::ServiceAccountManager;
+use crate::error::{Error, Result};
+use crate::models::ServiceAccount;
+use async_trait::async_trait;
+use sqlx::FromRow;
+
+const SELECT_ACCOUNTS: &str = "SELECT sa.*, u.username, u.name FROM service_account sa \
+     JOIN \"user\" u ON u.id = sa.user_id";
+
+#[async_trait]
+impl ServiceAccountManager for SqlStore {
+    async fn get_service_accounts(&self) -> Result<Vec<ServiceAccount>> {
+        let rows = sqlx::query(&format!("{SELECT_ACCOUNTS} ORDER BY sa.id"))
+            .fetch_all(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        rows.iter()
+            .map(|row| ServiceAccount::from_row(row).map_err(Error::Database))
+            .collect()
+    }
+
+    async fn get_service_account(&self, id: i32) -> Result<ServiceAccount> {
+        let row = sqlx::query(&format!("{SELECT_ACCOUNTS} WHERE sa.id = $1"))
+            .bind(id)
+            .fetch_one(self.get_postgres_pool()?)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => {
+                    Error::NotFound("Сервисный аккаунт не найден".to_string())
+                }
+                _ => Error::Database(e),
+            })?;
+        ServiceAccount::from_row(&row).map_err(Error::Database)
+    }
+
+    async fn create_service_account(&self, account: ServiceAccount) -> Result<ServiceAccount> {
+        let id: i32 = sqlx::query_scalar(
+            "INSERT INTO service_account (user_id, description, created_by, created) \
+             VALUES ($1, $2, $3, $4) RETURNING id",
+        )
+        .bind(account.user_id)
+        .bind(&account.description)
+        .bind(account.created_by)
+        .bind(account.created)
+        .fetch_one(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        self.get_service_account(id).await
+    }
+
+    async fn delete_service_account(&self, id: i32) -> Result<()> {
+        // Пользователь удаляется вместе с аккаунтом, токенами и членством в проектах
+        sqlx::query(
+            "DELETE FROM \"user\" WHERE id = (SELECT user_id FROM service_account WHERE id = $1)",
+        )
+        .bind(id)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@
 use crate::db::sql::SqlStore;
 use crate::db::store::*;
 use crate::error::{Error, Result};
-use crate::models::{Task, TaskOutput, TaskWithTpl};
+use crate::models::{Task, TaskHook, TaskOutput, TaskWithTpl};
 use crate::services::task_logger::TaskStatus;
 use async_trait::async_trait;
 use sqlx::Row;
@@ -57,7 +57,8 @@ impl TaskManager for SqlStore {
                     inventory_id: row.try_get("inventory_id").ok(),
                     repository_id: row.try_get("repository_id").ok(),
                     environment_id: row.try_get("environment_id").ok(),
-                    params: None,
+                    params: row_params(&row),
+                    priority: row.try_get("priority").ok().flatten(),
                 },
                 tpl_playbook: row.get("tpl_playbook"),
                 tpl_type: row.try_get("tpl_type").ok(),
@@ -78,67 +79,15 @@ impl TaskManager for SqlStore {
                 sqlx::Error::RowNotFound => Error::NotFound("Задача не найдена".to_string()),
                 _ => Error::Database(e),
             })?;
-        Ok(Task {
-            id: row.get("id"),
-            template_id: row.get("template_id"),
-            project_id: row.get("project_id"),
-            status: row.get("status"),
-            playbook: row.try_get("playbook").ok().flatten(),
-            environment: row.try_get("environment").ok().flatten(),
-            secret: None,
-            arguments: row.try_get("arguments").ok().flatten(),
-            git_branch: row.try_get("git_branch").ok().flatten(),
-            user_id: row.try_get("user_id").ok(),
-            integration_id: row.try_get("integration_id").ok(),
-            schedule_id: row.try_get("schedule_id").ok(),
-            created: row.get("created"),
-            start: row.try_get("start_time").ok(),
-            end: row.try_get("end_time").ok(),
-            message: row.try_get("message").ok().flatten(),
-            commit_hash: row.try_get("commit_hash").ok().flatten(),
-            commit_message: row.try_get("commit_message").ok().flatten(),
-            build_task_id: row.try_get("build_task_id").ok(),
-            version: row.try_get("version").ok().flatten(),
-            inventory_id: row.try_get("inventory_id").ok(),
-            repository_id: row.try_get("repository_id").ok(),
-            environment_id: row.try_get("environment_id").ok(),
-            params: None,
-        })
+        Ok(row_to_task(&row))
     }
 
-    async fn create_task(&self, mut task: Task) -> Result<Task> {
-        let query = "INSERT INTO task (template_id, project_id, status, playbook, environment, arguments, git_branch, user_id, integration_id, schedule_id, created, start_time, end_time, message, commit_hash, commit_message, build_task_id, version, inventory_id, repository_id, environment_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21) RETURNING id";
-        let id: i32 = sqlx::query_scalar(query)
-            .bind(task.template_id)
-            .bind(task.project_id)
-            .bind(task.status.to_string())
-            .bind(&task.playbook)
-            .bind(&task.environment)
-            .bind(&task.arguments)
-            .bind(&task.git_branch)
-            .bind(task.user_id)
-            .bind(task.integration_id)
-            .bind(task.schedule_id)
-            .bind(task.created)
-            .bind(task.start)
-            .bind(task.end)
-            .bind(&task.message)
-            .bind(&task.commit_hash)
-            .bind(&task.commit_message)
-            .bind(task.build_task_id)
-            .bind(&task.version)
-            .bind(task.inventory_id)
-            .bind(task.repository_id)
-            .bind(task.environment_id)
-            .fetch_one(self.get_postgres_pool()?)
-            .await
-            .map_err(Error::Database)?;
-        task.id = id;
-        Ok(task)
+    async fn create_task(&self, task: Task) -> Result<Task> {
+        insert_task(self, task, None).await
     }
 
     async fn update_task(&self, task: Task) -> Result<()> {
-        let query = "UPDATE task SET status = $1, playbook = $2, environment = $3, arguments = $4, git_branch = $5, user_id = $6, integration_id = $7, schedule_id = $8, start_time = $9, end_time = $10, message = $11, commit_hash = $12, commit_message = $13, build_task_id = $14, version = $15, inventory_id = $16, repository_id = $17, environment_id = $18 WHERE id = $19";
+        let query = "UPDATE task SET status = $1, playbook = $2, environment = $3, arguments = $4, git_branch = $5, user_id = $6, integration_id = $7, schedule_id = $8, start_time = $9, end_time = $10, message = $11, commit_hash = $12, commit_message = $13, build_task_id = $14, version = $15, inventory_id = $16, repository_id = $17, environment_id = $18, params = $19, priority = $20 WHERE id = $21";
         sqlx::query(query)
             .bind(task.status.to_string())
             .bind(&task.playbook)
@@ -158,6 +107,8 @@ impl TaskManager for SqlStore {
             .bind(task.inventory_id)
             .bind(task.repository_id)
             .bind(task.environment_id)
+            .bind(task.params.as_ref().map(|p| p.to_string()))
+            .bind(task.priority)
             .bind(task.id)
             .execute(self.get_postgres_pool()?)
             .await
@@ -165,6 +116,23 @@ impl TaskManager for SqlStore {
         Ok(())
     }
 
+    async fn set_waiting_task_priority(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        priority: Option<i32>,
+    ) -> Result<bool> {
+        let query = "UPDATE task SET priority = $1 WHERE id = $2 AND project_id = $3 AND status = 'waiting'";
+        let result = sqlx::query(query)
+            .bind(priority)
+            .bind(task_id)
+            .bind(project_id)
+            .execute(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        Ok(result.rows_affected() > 0)
+    }
+
     async fn delete_task(&self, _project_id: i32, task_id: i32) -> Result<()> {
         let query = "DELETE FROM task WHERE id = $1";
         sqlx::query(query)
@@ -233,6 +201,22 @@ impl TaskManager for SqlStore {
         self.db.get_global_tasks(status_filter, limit).await
     }
 
+    async fn get_queued_tasks(&self, project_id: Option<i32>, limit: i32) -> Result<Vec<Task>> {
+        let rows = sqlx::query(
+            "SELECT t.* FROM task t LEFT JOIN template tpl ON tpl.id = t.template_id \
+             WHERE t.status = 'waiting' AND t.parent_task_id IS NULL \
+             AND ($1::INTEGER IS NULL OR t.project_id = $1) \
+             ORDER BY COALESCE(t.priority, tpl.priority, 0) DESC, t.created ASC, t.id ASC \
+             LIMIT $2",
+        )
+        .bind(project_id)
+        .bind(limit as i64)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(rows.iter().map(row_to_task).collect())
+    }
+
     async fn get_running_tasks_count(&self) -> Result<usize> {
         let pool = self.get_postgres_pool()?;
         let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM task WHERE status = 'Running'")
@@ -251,6 +235,117 @@ impl TaskManager for SqlStore {
                 .map_err(Error::Database)?;
         Ok(count as usize)
     }
+
+    async fn link_task_hook(&self, project_id: i32, hook: TaskHook) -> Result<()> {
+        sqlx::query(
+            "UPDATE task SET parent_task_id = $1, hook_type = $2 WHERE id = $3 AND project_id = $4",
+        )
+        .bind(hook.parent_task_id)
+        .bind(&hook.hook_type)
+        .bind(hook.task_id)
+        .bind(project_id)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn create_hook_task(
+        &self,
+        task: Task,
+        parent_task_id: i32,
+        hook_type: &str,
+    ) -> Result<Task> {
+        insert_task(self, task, Some((parent_task_id, hook_type))).await
+    }
+
+    async fn get_task_hook_links(&self, project_id: i32, task_id: i32) -> Result<Vec<TaskHook>> {
+        sqlx::query_as::<_, TaskHook>(
+            "SELECT id AS task_id, parent_task_id, hook_type FROM task \
+             WHERE project_id = $1 AND parent_task_id IS NOT NULL AND hook_type IS NOT NULL \
+             AND (id = $2 OR parent_task_id = $2) ORDER BY id",
+        )
+        .bind(project_id)
+        .bind(task_id)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)
+    }
+}
+
+/// Вставляет задачу; задача-хук сразу получает связь с родительской задачей
+async fn insert_task(store: &SqlStore, mut task: Task, hook: Option<(i32, &str)>) -> Result<Task> {
+    let query = "INSERT INTO task (template_id, project_id, status, playbook, environment, arguments, git_branch, user_id, integration_id, schedule_id, created, start_time, end_time, message, commit_hash, commit_message, build_task_id, version, inventory_id, repository_id, environment_id, params, priority, parent_task_id, hook_type) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25) RETURNING id";
+    let id: i32 = sqlx::query_scalar(query)
+        .bind(task.template_id)
+        .bind(task.project_id)
+        .bind(task.status.to_string())
+        .bind(&task.playbook)
+        .bind(&task.environment)
+        .bind(&task.arguments)
+        .bind(&task.git_branch)
+        .bind(task.user_id)
+        .bind(task.integration_id)
+        .bind(task.schedule_id)
+        .bind(task.created)
+        .bind(task.start)
+        .bind(task.end)
+        .bind(&task.message)
+        .bind(&task.commit_hash)
+        .bind(&task.commit_message)
+        .bind(task.build_task_id)
+        .bind(&task.version)
+        .bind(task.inventory_id)
+        .bind(task.repository_id)
+        .bind(task.environment_id)
+        .bind(task.params.as_ref().map(|p| p.to_string()))
+        .bind(task.priority)
+        .bind(hook.map(|(parent_task_id, _)| parent_task_id))
+        .bind(hook.map(|(_, hook_type)| hook_type))
+        .fetch_one(store.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+    task.id = id;
+    Ok(task)
+}
+
+/// Конвертирует строку таблицы `task` в Task
+pub(super) fn row_to_task(row: &sqlx::postgres::PgRow) -> Task {
+    Task {
+        id: row.get("id"),
+        template_id: row.get("template_id"),
+        project_id: row.get("project_id"),
+        status: row.get("status"),
+        playbook: row.try_get("playbook").ok().flatten(),
+        environment: row.try_get("environment").ok().flatten(),
+        secret: None,
+        arguments: row.try_get("arguments").ok().flatten(),
+        git_branch: row.try_get("git_branch").ok().flatten(),
+        user_id: row.try_get("user_id").ok(),
+        integration_id: row.try_get("integration_id").ok(),
+        schedule_id: row.try_get("schedule_id").ok(),
+        created: row.get("created"),
+        start: row.try_get("start_time").ok(),
+        end: row.try_get("end_time").ok(),
+        message: row.try_get("message").ok().flatten(),
+        commit_hash: row.try_get("commit_hash").ok().flatten(),
+        commit_message: row.try_get("commit_message").ok().flatten(),
+        build_task_id: row.try_get("build_task_id").ok(),
+        version: row.try_get("version").ok().flatten(),
+        inventory_id: row.try_get("inventory_id").ok(),
+        repository_id: row.try_get("repository_id").ok(),
+        environment_id: row.try_get("environment_id").ok(),
+        params: row_params(row),
+        priority: row.try_get("priority").ok().flatten(),
+    }
+}
+
+/// Параметры задачи хранятся в колонке `params` как JSON-текст
+fn row_params(row: &sqlx::postgres::PgRow) -> Option<serde_json::Value> {
+    row.try_get::<Option<String>, _>("params")
+        .ok()
+        .flatten()
+        .and_then(|p| serde_json::from_str(&p).ok())
 }
 
 #[cfg(test)]
@@ -311,6 +406,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
         assert_eq!(task.template_id, 10);
         assert_eq!(task.project_id, 5);
@@ -345,6 +441,7 @@ mod tests {
                 repository_id: None,
                 environment_id: None,
                 params: None,
+                priority: None,
             },
             tpl_playbook: Some("site.yml".to_string()),
             tpl_type: Some(TemplateType::Task),
@@ -412,6 +509,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
         let json = serde_json::to_string(&task).unwrap();
         assert!(!json.contains("sensitive_data"));
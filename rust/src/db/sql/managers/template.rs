@@ -66,6 +66,9 @@ impl TemplateManager for SqlStore {
                 post_template_id: row.try_get("post_template_id").ok().flatten(),
                 fail_template_id: row.try_get("fail_template_id").ok().flatten(),
                 deploy_environment_id: row.try_get("deploy_environment_id").ok().flatten(),
+                priority: row.try_get("priority").ok().unwrap_or(0),
+                concurrency_group: row.try_get("concurrency_group").ok().flatten(),
+                timeout_secs: row.try_get("timeout_secs").ok().flatten(),
             })
             .collect())
     }
@@ -121,6 +124,9 @@ impl TemplateManager for SqlStore {
             post_template_id: row.try_get("post_template_id").ok().flatten(),
             fail_template_id: row.try_get("fail_template_id").ok().flatten(),
             deploy_environment_id: row.try_get("deploy_environment_id").ok().flatten(),
+            priority: row.try_get("priority").ok().unwrap_or(0),
+            concurrency_group: row.try_get("concurrency_group").ok().flatten(),
+            timeout_secs: row.try_get("timeout_secs").ok().flatten(),
         })
     }
 
@@ -130,8 +136,9 @@ impl TemplateManager for SqlStore {
              type, app, git_branch, created, arguments, vault_key_id, view_id, build_template_id, \
              autorun, allow_override_args_vars, allow_override_branch_in_task, allow_inventory_in_task, \
              allow_parallel_tasks, suppress_success_alerts, require_approval, task_params, survey_vars, vaults, \
-             parent_template_id, execution_image, pre_template_id, post_template_id, fail_template_id, deploy_environment_id) \
-            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20,$21,$22,$23,$24,$25,$26,$27,$28,$29,$30,$31) \
+             parent_template_id, execution_image, pre_template_id, post_template_id, fail_template_id, deploy_environment_id, priority, \
+             concurrency_group, timeout_secs) \
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20,$21,$22,$23,$24,$25,$26,$27,$28,$29,$30,$31,$32,$33,$34) \
             RETURNING id";
         let id: i32 = sqlx::query_scalar(query)
             .bind(template.project_id)
@@ -165,6 +172,9 @@ impl TemplateManager for SqlStore {
             .bind(template.post_template_id)
             .bind(template.fail_template_id)
             .bind(template.deploy_environment_id)
+            .bind(template.priority)
+            .bind(&template.concurrency_group)
+            .bind(template.timeout_secs)
             .fetch_one(self.get_postgres_pool()?)
             .await
             .map_err(Error::Database)?;
@@ -182,8 +192,9 @@ impl TemplateManager for SqlStore {
             allow_inventory_in_task = $17, allow_parallel_tasks = $18, suppress_success_alerts = $19, \
             require_approval = $20, task_params = $21, survey_vars = $22, vaults = $23, \
             parent_template_id = $24, execution_image = $25, pre_template_id = $26, \
-            post_template_id = $27, fail_template_id = $28, deploy_environment_id = $29 \
-            WHERE id = $30 AND project_id = $31";
+            post_template_id = $27, fail_template_id = $28, deploy_environment_id = $29, \
+            priority = $30, concurrency_group = $31, timeout_secs = $32 \
+            WHERE id = $33 AND project_id = $34";
         sqlx::query(query)
             .bind(&template.name)
             .bind(&template.playbook)
@@ -214,6 +225,9 @@ impl TemplateManager for SqlStore {
             .bind(template.post_template_id)
             .bind(template.fail_template_id)
             .bind(template.deploy_environment_id)
+            .bind(template.priority)
+            .bind(&template.concurrency_group)
+            .bind(template.timeout_secs)
             .bind(template.id)
             .bind(template.project_id)
             .execute(self.get_postgres_pool()?)
@@ -280,6 +294,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         assert_eq!(tpl.project_id, 10);
         assert_eq!(tpl.name, "Deploy App");
@@ -346,6 +363,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         assert!(tpl.autorun);
         assert!(tpl.require_approval);
@@ -387,6 +407,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let json = serde_json::to_string(&tpl).unwrap();
         assert!(json.contains("\"name\":\"Minimal\""));
@@ -427,6 +450,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let cloned = tpl.clone();
         assert_eq!(cloned.id, tpl.id);
@@ -505,6 +531,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         assert!(tpl.survey_vars.is_some());
     }
@@ -21,6 +21,15 @@ mod tests {
             created: Some(Utc::now().to_rfc3339()),
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         }
     }
 
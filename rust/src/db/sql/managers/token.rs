@@ -6,7 +6,15 @@ use crate::error::{Error, Result};
 use crate::models::APIToken;
 use async_trait::async_trait;
 use chrono::Utc;
-use sqlx::Row;
+use sqlx::FromRow;
+use sqlx::types::Json;
+
+fn token_not_found(e: sqlx::Error) -> Error {
+    match e {
+        sqlx::Error::RowNotFound => Error::NotFound("Токен не найден".to_string()),
+        _ => Error::Database(e),
+    }
+}
 
 #[async_trait]
 impl TokenManager for SqlStore {
@@ -17,27 +25,25 @@ impl TokenManager for SqlStore {
             .fetch_all(self.get_postgres_pool()?)
             .await
             .map_err(Error::Database)?;
-        Ok(rows
-            .into_iter()
-            .map(|row| APIToken {
-                id: row.get("id"),
-                user_id: row.get("user_id"),
-                name: row.get("name"),
-                token: row.get("token"),
-                created: row.get("created"),
-                expired: row.get("expired"),
-            })
-            .collect())
+        rows.iter()
+            .map(|row| APIToken::from_row(row).map_err(Error::Database))
+            .collect()
     }
 
     async fn create_api_token(&self, mut token: APIToken) -> Result<APIToken> {
-        let query = "INSERT INTO api_token (user_id, name, token, created, expired) VALUES ($1, $2, $3, $4, $5) RETURNING id";
+        let query = "INSERT INTO api_token \
+             (user_id, name, token, created, expired, expires_at, scopes, project_ids, template_ids) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id";
         let id: i32 = sqlx::query_scalar(query)
             .bind(token.user_id)
             .bind(&token.name)
             .bind(&token.token)
             .bind(token.created)
             .bind(token.expired)
+            .bind(token.expires_at)
+            .bind(Json(&token.scopes))
+            .bind(Json(&token.project_ids))
+            .bind(Json(&token.template_ids))
             .fetch_one(self.get_postgres_pool()?)
             .await
             .map_err(Error::Database)?;
@@ -51,34 +57,48 @@ impl TokenManager for SqlStore {
             .bind(token_id)
             .fetch_one(self.get_postgres_pool()?)
             .await
-            .map_err(|e| match e {
-                sqlx::Error::RowNotFound => Error::NotFound("Токен не найден".to_string()),
-                _ => Error::Database(e),
-            })?;
-        Ok(APIToken {
-            id: row.get("id"),
-            user_id: row.get("user_id"),
-            name: row.get("name"),
-            token: row.get("token"),
-            created: row.get("created"),
-            expired: row.get("expired"),
-        })
+            .map_err(token_not_found)?;
+        APIToken::from_row(&row).map_err(Error::Database)
+    }
+
+    async fn expire_api_token(&self, user_id: i32, token_id: i32) -> Result<()> {
+        let query = "UPDATE api_token SET expired = TRUE WHERE id = $1 AND user_id = $2";
+        sqlx::query(query)
+            .bind(token_id)
+            .bind(user_id)
+            .execute(self.get_postgres_pool()?)
+            .await
+            .map_err(Error::Database)?;
+        Ok(())
     }
 
-    async fn expire_api_token(&self, _user_id: i32, token_id: i32) -> Result<()> {
-        let query = "UPDATE api_token SET expired = TRUE WHERE id = $1";
+    async fn delete_api_token(&self, user_id: i32, token_id: i32) -> Result<()> {
+        let query = "DELETE FROM api_token WHERE id = $1 AND user_id = $2";
         sqlx::query(query)
             .bind(token_id)
+            .bind(user_id)
             .execute(self.get_postgres_pool()?)
             .await
             .map_err(Error::Database)?;
         Ok(())
     }
 
-    async fn delete_api_token(&self, _user_id: i32, token_id: i32) -> Result<()> {
-        let query = "DELETE FROM api_token WHERE id = $1";
+    async fn get_api_token_by_value(&self, token: &str) -> Result<APIToken> {
+        let query = "SELECT * FROM api_token WHERE token = $1";
+        let row = sqlx::query(query)
+            .bind(token)
+            .fetch_one(self.get_postgres_pool()?)
+            .await
+            .map_err(token_not_found)?;
+        APIToken::from_row(&row).map_err(Error::Database)
+    }
+
+    async fn touch_api_token(&self, token_id: i32, ip: Option<&str>) -> Result<()> {
+        let query = "UPDATE api_token SET last_used_at = $2, last_used_ip = $3 WHERE id = $1";
         sqlx::query(query)
             .bind(token_id)
+            .bind(Utc::now())
+            .bind(ip)
             .execute(self.get_postgres_pool()?)
             .await
             .map_err(Error::Database)?;
@@ -100,6 +120,7 @@ mod tests {
             token: "secret_token_value".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("\"name\":\"My Token\""));
@@ -125,6 +146,7 @@ mod tests {
             token: "val".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let cloned = token.clone();
         assert_eq!(cloned.name, token.name);
@@ -140,6 +162,7 @@ mod tests {
             token: "expired".to_string(),
             created: Utc::now() - chrono::Duration::days(30),
             expired: true,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("\"expired\":true"));
@@ -154,6 +177,7 @@ mod tests {
             token: "active_token".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         assert!(!token.expired);
     }
@@ -176,6 +200,7 @@ mod tests {
             token: "tok".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         assert!(token.name.is_empty());
     }
@@ -189,6 +214,7 @@ mod tests {
             token: "t1".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let token2 = APIToken {
             id: 2,
@@ -197,6 +223,7 @@ mod tests {
             token: "t2".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         assert_ne!(token1.user_id, token2.user_id);
         assert_ne!(token1.name, token2.name);
@@ -211,6 +238,7 @@ mod tests {
             token: "token_value".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         let deserialized: APIToken = serde_json::from_str(&json).unwrap();
@@ -226,6 +254,7 @@ mod tests {
             token: "значение".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("Токен"));
@@ -243,6 +272,7 @@ mod tests {
             token: String::new(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         assert_eq!(token.id, 0);
         assert_eq!(token.user_id, 0);
@@ -259,6 +289,7 @@ mod tests {
             token: "max_token".to_string(),
             created: Utc::now(),
             expired: true,
+            ..Default::default()
         };
         assert_eq!(token.id, i32::MAX);
         assert_eq!(token.user_id, i32::MAX);
@@ -273,6 +304,7 @@ mod tests {
             token: "debug_val".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let debug_str = format!("{:?}", token);
         assert!(debug_str.contains("Debug Test"));
@@ -288,6 +320,7 @@ mod tests {
             token: "same".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let token2 = token1.clone();
         // APIToken may not implement PartialEq, so test field-by-field
@@ -320,6 +353,7 @@ mod tests {
             token: "past".to_string(),
             created: past_time,
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("created"));
@@ -335,6 +369,7 @@ mod tests {
             token: "t1".to_string(),
             created: now,
             expired: false,
+            ..Default::default()
         };
         let token2 = APIToken {
             id: 2,
@@ -343,6 +378,7 @@ mod tests {
             token: "t2".to_string(),
             created: now,
             expired: false,
+            ..Default::default()
         };
 
         assert_eq!(token1.user_id, token2.user_id);
@@ -359,6 +395,7 @@ mod tests {
             token: "roundtrip_value".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&original).unwrap();
@@ -161,6 +161,8 @@ mod tests {
         let log = WebhookLog {
             id: 1,
             webhook_id: 10,
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "task_completed".to_string(),
             status_code: Some(200),
             success: true,
@@ -206,6 +208,8 @@ mod tests {
         let log = WebhookLog {
             id: 1,
             webhook_id: 1,
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "test".to_string(),
             status_code: None,
             success: false,
@@ -5,9 +5,10 @@ use crate::db::store::WorkflowManager;
 use crate::error::{Error, Result};
 use crate::models::workflow::{
     Workflow, WorkflowCreate, WorkflowEdge, WorkflowEdgeCreate, WorkflowNode, WorkflowNodeCreate,
-    WorkflowNodeUpdate, WorkflowRun, WorkflowUpdate,
+    WorkflowNodeUpdate, WorkflowRun, WorkflowRunNode, WorkflowUpdate,
 };
 use async_trait::async_trait;
+use sqlx::types::Json;
 
 #[async_trait]
 impl WorkflowManager for SqlStore {
@@ -103,14 +104,18 @@ impl WorkflowManager for SqlStore {
         payload: WorkflowNodeCreate,
     ) -> Result<WorkflowNode> {
         let row = sqlx::query_as::<_, WorkflowNode>(
-            "INSERT INTO workflow_node (workflow_id, template_id, name, pos_x, pos_y)
-                 VALUES ($1, $2, $3, $4, $5) RETURNING *",
+            "INSERT INTO workflow_node
+                 (workflow_id, template_id, name, pos_x, pos_y, wave, inputs, node_type)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *",
         )
         .bind(workflow_id)
         .bind(payload.template_id)
         .bind(&payload.name)
         .bind(payload.pos_x)
         .bind(payload.pos_y)
+        .bind(payload.wave)
+        .bind(Json(&payload.inputs))
+        .bind(&payload.node_type)
         .fetch_one(self.get_postgres_pool()?)
         .await
         .map_err(Error::Database)?;
@@ -124,12 +129,16 @@ impl WorkflowManager for SqlStore {
         payload: WorkflowNodeUpdate,
     ) -> Result<WorkflowNode> {
         let row = sqlx::query_as::<_, WorkflowNode>(
-            "UPDATE workflow_node SET name = $1, pos_x = $2, pos_y = $3
-                 WHERE id = $4 AND workflow_id = $5 RETURNING *",
+            "UPDATE workflow_node SET name = $1, pos_x = $2, pos_y = $3, wave = $4, inputs = $5,
+                 node_type = $6
+                 WHERE id = $7 AND workflow_id = $8 RETURNING *",
         )
         .bind(&payload.name)
         .bind(payload.pos_x)
         .bind(payload.pos_y)
+        .bind(payload.wave)
+        .bind(Json(&payload.inputs))
+        .bind(&payload.node_type)
         .bind(id)
         .bind(workflow_id)
         .fetch_one(self.get_postgres_pool()?)
@@ -212,13 +221,42 @@ impl WorkflowManager for SqlStore {
         Ok(rows)
     }
 
-    async fn create_workflow_run(&self, workflow_id: i32, project_id: i32) -> Result<WorkflowRun> {
+    async fn get_workflow_run(&self, id: i32, project_id: i32) -> Result<WorkflowRun> {
         let row = sqlx::query_as::<_, WorkflowRun>(
-            "INSERT INTO workflow_run (workflow_id, project_id, status, created)
-                 VALUES ($1, $2, 'pending', NOW()) RETURNING *",
+            "SELECT * FROM workflow_run WHERE id = $1 AND project_id = $2",
+        )
+        .bind(id)
+        .bind(project_id)
+        .fetch_one(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(row)
+    }
+
+    async fn get_active_workflow_runs(&self) -> Result<Vec<WorkflowRun>> {
+        let rows = sqlx::query_as::<_, WorkflowRun>(
+            "SELECT * FROM workflow_run
+                 WHERE status IN ('pending', 'running', 'waiting_approval') ORDER BY id",
+        )
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(rows)
+    }
+
+    async fn create_workflow_run(
+        &self,
+        workflow_id: i32,
+        project_id: i32,
+        params: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<WorkflowRun> {
+        let row = sqlx::query_as::<_, WorkflowRun>(
+            "INSERT INTO workflow_run (workflow_id, project_id, status, created, params)
+                 VALUES ($1, $2, 'pending', NOW(), $3) RETURNING *",
         )
         .bind(workflow_id)
         .bind(project_id)
+        .bind(Json(&params))
         .fetch_one(self.get_postgres_pool()?)
         .await
         .map_err(Error::Database)?;
@@ -231,15 +269,79 @@ impl WorkflowManager for SqlStore {
         status: &str,
         message: Option<String>,
     ) -> Result<()> {
-        sqlx::query("UPDATE workflow_run SET status = $1, message = $2 WHERE id = $3")
-            .bind(status)
-            .bind(&message)
-            .bind(id)
-            .execute(self.get_postgres_pool()?)
-            .await
-            .map_err(Error::Database)?;
+        // Завершённый запуск не меняет статус: отмена не перезаписывается итогом executor'а
+        sqlx::query(
+            "UPDATE workflow_run SET status = $1, message = $2,
+                 started = CASE WHEN $1 = 'running' THEN COALESCE(started, NOW()) ELSE started END,
+                 finished = CASE WHEN $1 IN ('success', 'failed', 'cancelled')
+                     THEN NOW() ELSE finished END
+                 WHERE id = $3 AND status NOT IN ('success', 'failed', 'cancelled')",
+        )
+        .bind(status)
+        .bind(&message)
+        .bind(id)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(())
+    }
+
+    async fn get_workflow_run_nodes(&self, run_id: i32) -> Result<Vec<WorkflowRunNode>> {
+        let rows = sqlx::query_as::<_, WorkflowRunNode>(
+            "SELECT * FROM workflow_run_node WHERE run_id = $1 ORDER BY node_id",
+        )
+        .bind(run_id)
+        .fetch_all(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(rows)
+    }
+
+    async fn save_workflow_run_node(&self, node: WorkflowRunNode) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO workflow_run_node
+                 (run_id, node_id, status, task_id, decided_by, message, updated)
+                 VALUES ($1, $2, $3, $4, $5, $6, NOW())
+                 ON CONFLICT (run_id, node_id) DO UPDATE SET
+                     status = EXCLUDED.status, task_id = EXCLUDED.task_id,
+                     decided_by = EXCLUDED.decided_by, message = EXCLUDED.message,
+                     updated = NOW()",
+        )
+        .bind(node.run_id)
+        .bind(node.node_id)
+        .bind(&node.status)
+        .bind(node.task_id)
+        .bind(node.decided_by)
+        .bind(&node.message)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
         Ok(())
     }
+
+    async fn decide_workflow_approval(
+        &self,
+        run_id: i32,
+        node_id: i32,
+        approved: bool,
+        user_id: i32,
+        comment: Option<String>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE workflow_run_node SET status = $1, decided_by = $2, message = $3,
+                 updated = NOW()
+                 WHERE run_id = $4 AND node_id = $5 AND status = 'waiting_approval'",
+        )
+        .bind(if approved { "approved" } else { "rejected" })
+        .bind(user_id)
+        .bind(&comment)
+        .bind(run_id)
+        .bind(node_id)
+        .execute(self.get_postgres_pool()?)
+        .await
+        .map_err(Error::Database)?;
+        Ok(result.rows_affected() > 0)
+    }
 }
 
 #[cfg(test)]
@@ -295,6 +397,8 @@ mod tests {
             pos_x: 100.0,
             pos_y: 200.0,
             wave: 0,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         assert_eq!(node.template_id, 10);
         assert_eq!(node.wave, 0);
@@ -308,6 +412,8 @@ mod tests {
             pos_x: 0.0,
             pos_y: 0.0,
             wave: 1,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         assert_eq!(create.wave, 1);
     }
@@ -319,6 +425,8 @@ mod tests {
             pos_x: 150.0,
             pos_y: 250.0,
             wave: 2,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         assert_eq!(update.name, "Renamed");
     }
@@ -372,6 +480,7 @@ mod tests {
             created: Utc::now(),
             started: None,
             finished: None,
+            params: Default::default(),
         };
         assert_eq!(run.workflow_id, 5);
         assert_eq!(run.status, "running");
@@ -62,6 +62,8 @@ use std::collections::HashMap;
 /// SQL-хранилище данных (PostgreSQL)
 pub struct SqlStore {
     db: SqlDb,
+    /// Выделенные соединения с advisory lock лидерства: аренда → соединение
+    leader_locks: tokio::sync::Mutex<HashMap<String, managers::cluster_node::LeaderConnection>>,
 }
 
 impl SqlStore {
@@ -69,7 +71,10 @@ impl SqlStore {
     pub async fn new(database_url: &str) -> Result<Self> {
         let db = init::create_database_connection(database_url).await?;
 
-        let store = Self { db };
+        let store = Self {
+            db,
+            leader_locks: tokio::sync::Mutex::new(HashMap::new()),
+        };
         store.ensure_schema().await?;
         Ok(store)
     }
@@ -352,6 +357,16 @@ impl SqlStore {
         .await
         .map_err(Error::Database)?;
 
+        // Связь задач-хуков (pre/post/fail) с родительской задачей
+        for column in ["parent_task_id INTEGER", "hook_type TEXT"] {
+            sqlx::query(&format!(
+                "ALTER TABLE task ADD COLUMN IF NOT EXISTS {column}"
+            ))
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        }
+
         // schedule — расписания (cron)
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS schedule (
@@ -401,6 +416,20 @@ impl SqlStore {
         .await
         .map_err(Error::Database)?;
 
+        // template_role — роль, которую получают на шаблоне участники с ролью `role_slug`
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS template_role (
+                id SERIAL PRIMARY KEY,
+                project_id INTEGER NOT NULL REFERENCES project(id) ON DELETE CASCADE,
+                template_id INTEGER NOT NULL REFERENCES template(id) ON DELETE CASCADE,
+                role_id INTEGER NOT NULL,
+                role_slug VARCHAR(255) NOT NULL
+            )",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+
         // playbook — хранимые YAML плейбуки
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS playbook (
@@ -498,6 +527,29 @@ impl SqlStore {
         .await
         .map_err(Error::Database)?;
 
+        // Области, ограничения и срок действия API-токенов; сервисные аккаунты
+        for ddl in [
+            "ALTER TABLE api_token ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ",
+            "ALTER TABLE api_token ADD COLUMN IF NOT EXISTS scopes JSONB NOT NULL DEFAULT '[]'",
+            "ALTER TABLE api_token ADD COLUMN IF NOT EXISTS project_ids JSONB NOT NULL DEFAULT '[]'",
+            "ALTER TABLE api_token ADD COLUMN IF NOT EXISTS template_ids JSONB NOT NULL DEFAULT '[]'",
+            "ALTER TABLE api_token ADD COLUMN IF NOT EXISTS last_used_at TIMESTAMPTZ",
+            "ALTER TABLE api_token ADD COLUMN IF NOT EXISTS last_used_ip TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_api_token_token ON api_token(token)",
+            "CREATE TABLE IF NOT EXISTS service_account (
+                id SERIAL PRIMARY KEY,
+                user_id INTEGER NOT NULL UNIQUE REFERENCES \"user\"(id) ON DELETE CASCADE,
+                description TEXT,
+                created_by INTEGER REFERENCES \"user\"(id) ON DELETE SET NULL,
+                created TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        ] {
+            sqlx::query(ddl)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
         // playbook_run — история запусков плейбуков
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS playbook_run (
@@ -544,6 +596,47 @@ impl SqlStore {
         .await
         .map_err(Error::Database)?;
 
+        // integration_matcher — матчеры входящих вызовов (используется IntegrationMatcherManager)
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS integration_matcher (
+                id SERIAL PRIMARY KEY,
+                integration_id INTEGER NOT NULL REFERENCES integration(id) ON DELETE CASCADE,
+                project_id INTEGER NOT NULL,
+                name TEXT NOT NULL DEFAULT '',
+                body_data_type TEXT NOT NULL DEFAULT 'json',
+                key TEXT,
+                matcher_type TEXT NOT NULL DEFAULT 'equals',
+                matcher_value TEXT NOT NULL DEFAULT '',
+                method TEXT NOT NULL DEFAULT ''
+            )",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+
+        // integration_extract_value — колонки, которые читает IntegrationExtractValueManager
+        for column in [
+            "project_id INTEGER NOT NULL DEFAULT 0",
+            "body_data_type TEXT NOT NULL DEFAULT 'json'",
+            "value_name TEXT NOT NULL DEFAULT ''",
+            "value_type TEXT NOT NULL DEFAULT 'environment'",
+        ] {
+            sqlx::query(&format!(
+                "ALTER TABLE integration_extract_value ADD COLUMN IF NOT EXISTS {column}"
+            ))
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        }
+        for column in ["key", "variable"] {
+            sqlx::query(&format!(
+                "ALTER TABLE integration_extract_value ALTER COLUMN {column} DROP NOT NULL"
+            ))
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        }
+
         // workflow — DAG пайплайны из шаблонов
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS workflow (
@@ -811,6 +904,16 @@ impl SqlStore {
             .await
             .map_err(Error::Database)?;
 
+        // terraform_plan: сохранённый бинарный план и serial состояния для apply
+        sqlx::query(
+            "ALTER TABLE terraform_plan
+                ADD COLUMN IF NOT EXISTS plan_file BYTEA,
+                ADD COLUMN IF NOT EXISTS state_serial BIGINT",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+
         // organization — Multi-Tenancy организации (v4.0)
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS organization (
@@ -907,6 +1010,49 @@ impl SqlStore {
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_deploy_record_env ON deployment_record(deploy_environment_id)")
             .execute(pool).await.map_err(Error::Database)?;
 
+        // ── Concurrency Groups: взаимоисключение задач ─────────────────────
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS concurrency_group (
+                id           SERIAL PRIMARY KEY,
+                project_id   INTEGER NOT NULL REFERENCES project(id) ON DELETE CASCADE,
+                name         TEXT NOT NULL,
+                max_parallel INTEGER NOT NULL DEFAULT 1,
+                created      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                UNIQUE (project_id, name)
+            )",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+        sqlx::query("ALTER TABLE template ADD COLUMN IF NOT EXISTS concurrency_group TEXT")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        sqlx::query(
+            "ALTER TABLE deployment_environment ADD COLUMN IF NOT EXISTS concurrency_group TEXT",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+        // Занятые слоты групп — общие для всех узлов кластера и раннеров
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS concurrency_slot (
+                group_key TEXT NOT NULL,
+                task_id   INTEGER NOT NULL REFERENCES task(id) ON DELETE CASCADE,
+                acquired  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (group_key, task_id)
+            )",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_concurrency_slot_task ON concurrency_slot(task_id)",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+
         // ── FI-PUL-1: Task Structured Outputs ──────────────────────────────
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS task_structured_output (
@@ -959,6 +1105,158 @@ impl SqlStore {
             .await
             .map_err(Error::Database)?;
 
+        // ── Приоритет очереди задач ─────────────────────────────────────────
+        sqlx::query(
+            "ALTER TABLE template ADD COLUMN IF NOT EXISTS priority INTEGER NOT NULL DEFAULT 0",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+        sqlx::query("ALTER TABLE task ADD COLUMN IF NOT EXISTS priority INTEGER")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_task_waiting ON task(created) WHERE status = 'waiting'",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+
+        // ── Remote Runners: возможности раннера и захват задач ─────────────
+        sqlx::query("ALTER TABLE runner ADD COLUMN IF NOT EXISTS capabilities TEXT")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        sqlx::query("ALTER TABLE task ADD COLUMN IF NOT EXISTS runner_id INTEGER")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_task_runner ON task(runner_id)")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+
+        // ── Таймауты задач и узлы кластера ──────────────────────────────────
+        sqlx::query("ALTER TABLE template ADD COLUMN IF NOT EXISTS timeout_secs INTEGER")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        sqlx::query("ALTER TABLE task ADD COLUMN IF NOT EXISTS node_id TEXT")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS cluster_node (
+                id        TEXT PRIMARY KEY,
+                hostname  TEXT NOT NULL DEFAULT '',
+                started   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                last_seen TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+        )
+        .execute(pool)
+        .await
+        .map_err(Error::Database)?;
+        // Узел, выполняющий executor запуска workflow
+        sqlx::query("ALTER TABLE workflow_run ADD COLUMN IF NOT EXISTS node_id TEXT")
+            .execute(pool)
+            .await
+            .map_err(Error::Database)?;
+
+        // ── Архив логов задач и политики хранения ───────────────────────────
+        for ddl in [
+            "CREATE TABLE IF NOT EXISTS task_log_archive (
+                task_id    INTEGER PRIMARY KEY,
+                project_id INTEGER NOT NULL,
+                backend    TEXT NOT NULL,
+                location   TEXT NOT NULL,
+                chunks     INTEGER NOT NULL DEFAULT 0,
+                lines      INTEGER NOT NULL DEFAULT 0,
+                bytes      BIGINT NOT NULL DEFAULT 0,
+                archived   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+            "CREATE TABLE IF NOT EXISTS project_log_retention (
+                project_id     INTEGER PRIMARY KEY REFERENCES project(id) ON DELETE CASCADE,
+                max_age_days   INTEGER,
+                keep_last_runs INTEGER
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_task_output_task ON task_output(task_id)",
+        ] {
+            sqlx::query(ddl)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        // ── Outbox событий и доставки webhook / политикам уведомлений ───────
+        for ddl in [
+            "CREATE TABLE IF NOT EXISTS webhook (
+                id BIGSERIAL PRIMARY KEY,
+                project_id BIGINT REFERENCES project(id) ON DELETE CASCADE,
+                name VARCHAR(255) NOT NULL,
+                type VARCHAR(50) NOT NULL,
+                url VARCHAR(2048) NOT NULL,
+                secret VARCHAR(255),
+                headers JSONB,
+                active BOOLEAN DEFAULT TRUE,
+                events JSONB NOT NULL DEFAULT '[]',
+                retry_count INTEGER DEFAULT 3,
+                timeout_secs BIGINT DEFAULT 30,
+                created_at TIMESTAMPTZ DEFAULT NOW(),
+                updated_at TIMESTAMPTZ DEFAULT NOW()
+            )",
+            "CREATE TABLE IF NOT EXISTS webhook_log (
+                id BIGSERIAL PRIMARY KEY,
+                webhook_id BIGINT REFERENCES webhook(id) ON DELETE CASCADE,
+                event_type VARCHAR(100) NOT NULL,
+                status_code INTEGER,
+                success BOOLEAN DEFAULT FALSE,
+                error TEXT,
+                attempts INTEGER DEFAULT 0,
+                payload JSONB,
+                response JSONB,
+                created_at TIMESTAMPTZ DEFAULT NOW()
+            )",
+            "ALTER TABLE webhook_log ADD COLUMN IF NOT EXISTS notification_policy_id INTEGER",
+            "ALTER TABLE webhook_log ADD COLUMN IF NOT EXISTS delivery_id BIGINT",
+            "CREATE TABLE IF NOT EXISTS event_outbox (
+                id          BIGSERIAL PRIMARY KEY,
+                project_id  INTEGER NOT NULL,
+                event_type  TEXT NOT NULL,
+                task_id     INTEGER,
+                template_id INTEGER,
+                payload     JSONB NOT NULL DEFAULT '{}',
+                dispatched  BOOLEAN NOT NULL DEFAULT FALSE,
+                created     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_event_outbox_pending \
+             ON event_outbox(id) WHERE NOT dispatched",
+            "CREATE TABLE IF NOT EXISTS event_delivery (
+                id           BIGSERIAL PRIMARY KEY,
+                event_id     BIGINT NOT NULL REFERENCES event_outbox(id) ON DELETE CASCADE,
+                project_id   INTEGER NOT NULL,
+                target       TEXT NOT NULL,
+                target_id    BIGINT NOT NULL,
+                status       TEXT NOT NULL DEFAULT 'pending',
+                attempts     INTEGER NOT NULL DEFAULT 0,
+                max_attempts INTEGER NOT NULL DEFAULT 1,
+                next_attempt TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                last_error   TEXT,
+                created      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                delivered    TIMESTAMPTZ
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_event_delivery_due \
+             ON event_delivery(next_attempt) WHERE status = 'pending'",
+            "CREATE INDEX IF NOT EXISTS idx_event_delivery_project \
+             ON event_delivery(project_id, id)",
+            "CREATE INDEX IF NOT EXISTS idx_webhook_log_delivery ON webhook_log(delivery_id)",
+        ] {
+            sqlx::query(ddl)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
         // ── FI-ARGO-1: Sync Waves ───────────────────────────────────────────
         sqlx::query(
             "ALTER TABLE workflow_node ADD COLUMN IF NOT EXISTS wave INTEGER NOT NULL DEFAULT 0",
@@ -967,6 +1265,69 @@ impl SqlStore {
         .await
         .map_err(Error::Database)?;
 
+        // Передача данных между узлами workflow
+        for ddl in [
+            "ALTER TABLE workflow_node ADD COLUMN IF NOT EXISTS inputs JSONB NOT NULL DEFAULT '[]'",
+            "ALTER TABLE workflow_run ADD COLUMN IF NOT EXISTS params JSONB NOT NULL DEFAULT '{}'",
+        ] {
+            sqlx::query(ddl)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        // Узлы подтверждения и состояние узлов запуска (отмена, возобновление)
+        for ddl in [
+            "ALTER TABLE workflow_node ADD COLUMN IF NOT EXISTS node_type TEXT NOT NULL \
+             DEFAULT 'template'",
+            "CREATE TABLE IF NOT EXISTS workflow_run_node (
+                run_id INTEGER NOT NULL REFERENCES workflow_run(id) ON DELETE CASCADE,
+                node_id INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                task_id INTEGER,
+                decided_by INTEGER,
+                message TEXT,
+                updated TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (run_id, node_id)
+            )",
+        ] {
+            sqlx::query(ddl)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        // Цели расписаний, часовые пояса, пропуски запусков и периоды запрета
+        for ddl in [
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS target_type TEXT NOT NULL \
+             DEFAULT 'template'",
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS target_id INTEGER",
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS timezone TEXT",
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS misfire_policy TEXT NOT NULL \
+             DEFAULT 'skip'",
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS jitter_secs INTEGER NOT NULL DEFAULT 0",
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS blackout_windows JSONB NOT NULL \
+             DEFAULT '[]'",
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS last_fired TIMESTAMPTZ",
+        ] {
+            sqlx::query(ddl)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
+        // Запуск расписаний по новому коммиту в репозитории
+        for ddl in [
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS git_branch TEXT",
+            "ALTER TABLE schedule ADD COLUMN IF NOT EXISTS commit_paths JSONB NOT NULL \
+             DEFAULT '[]'",
+        ] {
+            sqlx::query(ddl)
+                .execute(pool)
+                .await
+                .map_err(Error::Database)?;
+        }
+
         tracing::info!("Схема БД инициализирована");
         Ok(())
     }
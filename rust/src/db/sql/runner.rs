@@ -183,6 +183,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: Some(chrono::Utc::now()),
+            capabilities: None,
         }
     }
 
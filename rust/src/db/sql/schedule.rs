@@ -4,9 +4,57 @@
 
 use crate::db::sql::types::SqlDb;
 use crate::error::{Error, Result};
+use crate::models::schedule::{BlackoutWindow, MISFIRE_SKIP, SCHEDULE_TARGET_TEMPLATE};
 use crate::models::{Schedule, ScheduleWithTpl};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sqlx::Row;
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+
+/// Собирает расписание из строки таблицы `schedule`
+///
+/// Колонки, добавленные миграциями позже, читаются с безопасными значениями по умолчанию.
+pub(crate) fn schedule_from_row(row: &PgRow) -> Schedule {
+    Schedule {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        template_id: row.get("template_id"),
+        cron: row.get("cron"),
+        cron_format: row.try_get("cron_format").ok().flatten(),
+        name: row.get("name"),
+        active: row.get("active"),
+        last_commit_hash: row.try_get("last_commit_hash").ok().flatten(),
+        repository_id: row.try_get("repository_id").ok().flatten(),
+        created: row.try_get("created").ok().flatten(),
+        run_at: row.try_get("run_at").ok().flatten(),
+        delete_after_run: row
+            .try_get::<bool, _>("delete_after_run")
+            .ok()
+            .unwrap_or(false),
+        target_type: row
+            .try_get::<String, _>("target_type")
+            .unwrap_or_else(|_| SCHEDULE_TARGET_TEMPLATE.to_string()),
+        target_id: row.try_get("target_id").ok().flatten(),
+        timezone: row.try_get("timezone").ok().flatten(),
+        misfire_policy: row
+            .try_get::<String, _>("misfire_policy")
+            .unwrap_or_else(|_| MISFIRE_SKIP.to_string()),
+        jitter_secs: row.try_get::<i32, _>("jitter_secs").unwrap_or(0),
+        blackout_windows: row
+            .try_get::<Json<Vec<BlackoutWindow>>, _>("blackout_windows")
+            .map(|windows| windows.0)
+            .unwrap_or_default(),
+        last_fired: row
+            .try_get::<Option<DateTime<Utc>>, _>("last_fired")
+            .ok()
+            .flatten(),
+        git_branch: row.try_get("git_branch").ok().flatten(),
+        commit_paths: row
+            .try_get::<Json<Vec<String>>, _>("commit_paths")
+            .map(|paths| paths.0)
+            .unwrap_or_default(),
+    }
+}
 
 impl SqlDb {
     fn pg_pool_schedule(&self) -> Result<&sqlx::PgPool> {
@@ -24,23 +72,7 @@ impl SqlDb {
 
         Ok(rows
             .into_iter()
-            .map(|row| Schedule {
-                id: row.get("id"),
-                project_id: row.get("project_id"),
-                template_id: row.get("template_id"),
-                cron: row.get("cron"),
-                cron_format: row.try_get("cron_format").ok().flatten(),
-                name: row.get("name"),
-                active: row.get("active"),
-                last_commit_hash: row.try_get("last_commit_hash").ok().flatten(),
-                repository_id: row.try_get("repository_id").ok(),
-                created: row.try_get("created").ok(),
-                run_at: row.try_get("run_at").ok().flatten(),
-                delete_after_run: row
-                    .try_get::<bool, _>("delete_after_run")
-                    .ok()
-                    .unwrap_or(false),
-            })
+            .map(|row| schedule_from_row(&row))
             .collect())
     }
 
@@ -53,23 +85,7 @@ impl SqlDb {
 
         Ok(rows
             .into_iter()
-            .map(|row| Schedule {
-                id: row.get("id"),
-                project_id: row.get("project_id"),
-                template_id: row.get("template_id"),
-                cron: row.get("cron"),
-                cron_format: row.try_get("cron_format").ok().flatten(),
-                name: row.get("name"),
-                active: row.get("active"),
-                last_commit_hash: row.try_get("last_commit_hash").ok().flatten(),
-                repository_id: row.try_get("repository_id").ok(),
-                created: row.try_get("created").ok(),
-                run_at: row.try_get("run_at").ok().flatten(),
-                delete_after_run: row
-                    .try_get::<bool, _>("delete_after_run")
-                    .ok()
-                    .unwrap_or(false),
-            })
+            .map(|row| schedule_from_row(&row))
             .collect())
     }
 
@@ -85,31 +101,18 @@ impl SqlDb {
                 _ => Error::Database(e),
             })?;
 
-        Ok(Schedule {
-            id: row.get("id"),
-            project_id: row.get("project_id"),
-            template_id: row.get("template_id"),
-            cron: row.get("cron"),
-            cron_format: row.try_get("cron_format").ok().flatten(),
-            name: row.get("name"),
-            active: row.get("active"),
-            last_commit_hash: row.try_get("last_commit_hash").ok().flatten(),
-            repository_id: row.try_get("repository_id").ok(),
-            created: row.try_get("created").ok(),
-            run_at: row.try_get("run_at").ok().flatten(),
-            delete_after_run: row
-                .try_get::<bool, _>("delete_after_run")
-                .ok()
-                .unwrap_or(false),
-        })
+        Ok(schedule_from_row(&row))
     }
 
     /// Создаёт расписание
     pub async fn create_schedule(&self, mut schedule: Schedule) -> Result<Schedule> {
         let id: i32 = sqlx::query_scalar(
             "INSERT INTO schedule (project_id, template_id, cron, cron_format, name, active, \
-             created, run_at, delete_after_run) \
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+             created, run_at, delete_after_run, target_type, target_id, timezone, \
+             misfire_policy, jitter_secs, blackout_windows, repository_id, git_branch, \
+             commit_paths) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, \
+             $17, $18) RETURNING id",
         )
         .bind(schedule.project_id)
         .bind(schedule.template_id)
@@ -120,6 +123,15 @@ impl SqlDb {
         .bind(&schedule.created)
         .bind(&schedule.run_at)
         .bind(schedule.delete_after_run)
+        .bind(&schedule.target_type)
+        .bind(schedule.target_id)
+        .bind(&schedule.timezone)
+        .bind(&schedule.misfire_policy)
+        .bind(schedule.jitter_secs)
+        .bind(Json(&schedule.blackout_windows))
+        .bind(schedule.repository_id)
+        .bind(&schedule.git_branch)
+        .bind(Json(&schedule.commit_paths))
         .fetch_one(self.pg_pool_schedule()?)
         .await
         .map_err(Error::Database)?;
@@ -132,7 +144,10 @@ impl SqlDb {
     pub async fn update_schedule(&self, schedule: Schedule) -> Result<()> {
         sqlx::query(
             "UPDATE schedule SET cron = $1, cron_format = $2, name = $3, active = $4, \
-             run_at = $5, delete_after_run = $6 WHERE id = $7 AND project_id = $8",
+             run_at = $5, delete_after_run = $6, target_type = $7, target_id = $8, \
+             timezone = $9, misfire_policy = $10, jitter_secs = $11, blackout_windows = $12, \
+             repository_id = $13, git_branch = $14, commit_paths = $15 \
+             WHERE id = $16 AND project_id = $17",
         )
         .bind(&schedule.cron)
         .bind(&schedule.cron_format)
@@ -140,6 +155,15 @@ impl SqlDb {
         .bind(schedule.active)
         .bind(&schedule.run_at)
         .bind(schedule.delete_after_run)
+        .bind(&schedule.target_type)
+        .bind(schedule.target_id)
+        .bind(&schedule.timezone)
+        .bind(&schedule.misfire_policy)
+        .bind(schedule.jitter_secs)
+        .bind(Json(&schedule.blackout_windows))
+        .bind(schedule.repository_id)
+        .bind(&schedule.git_branch)
+        .bind(Json(&schedule.commit_paths))
         .bind(schedule.id)
         .bind(schedule.project_id)
         .execute(self.pg_pool_schedule()?)
@@ -179,6 +203,15 @@ mod tests {
             created: Some("2024-01-01T00:00:00Z".to_string()),
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert_eq!(schedule.id, 1);
         assert_eq!(schedule.cron, "0 * * * *");
@@ -201,6 +234,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let cloned = schedule.clone();
         assert_eq!(cloned.id, schedule.id);
@@ -223,6 +265,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"name\":\"Daily Build\""));
@@ -254,6 +305,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let with_tpl = ScheduleWithTpl {
             schedule,
@@ -278,6 +338,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let with_tpl = ScheduleWithTpl {
             schedule,
@@ -303,6 +372,15 @@ mod tests {
             created: None,
             run_at: Some("2024-12-31T23:59:59Z".to_string()),
             delete_after_run: true,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"run_at\":\"2024-12-31T23:59:59Z\""));
@@ -324,6 +402,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert_eq!(schedule.id, 0);
         assert_eq!(schedule.template_id, 0);
@@ -354,6 +441,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         // run_at uses skip_serializing_if
@@ -375,6 +471,15 @@ mod tests {
             created: Some("2024-06-01T00:00:00Z".to_string()),
             run_at: Some("2024-06-03T12:00:00Z".to_string()),
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert_eq!(schedule.id, 100);
         assert_eq!(schedule.template_id, 200);
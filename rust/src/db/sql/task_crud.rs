@@ -48,6 +48,7 @@ impl SqlDb {
             repository_id: row.get("repository_id"),
             environment_id: row.get("environment_id"),
             params,
+            priority: row.try_get("priority").ok().flatten(),
         })
     }
 
@@ -327,6 +328,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
         assert_eq!(task.id, 1);
         assert_eq!(task.template_id, 10);
@@ -30,7 +30,9 @@ struct WebhookRow {
 #[derive(Debug, Clone, FromRow)]
 struct WebhookLogRow {
     id: i64,
-    webhook_id: i64,
+    webhook_id: Option<i64>,
+    notification_policy_id: Option<i32>,
+    delivery_id: Option<i64>,
     event_type: String,
     status_code: Option<i32>,
     success: bool,
@@ -188,13 +190,32 @@ impl SqlDb {
             .collect())
     }
 
+    /// Попытки доставки outbox-события
+    pub async fn get_delivery_webhook_logs(&self, delivery_id: i64) -> Result<Vec<WebhookLog>> {
+        let rows = sqlx::query_as::<_, WebhookLogRow>(
+            "SELECT * FROM webhook_log WHERE delivery_id = $1 ORDER BY id",
+        )
+        .bind(delivery_id)
+        .fetch_all(
+            self.get_postgres_pool()
+                .ok_or(Error::Other("PostgreSQL pool not found".to_string()))?,
+        )
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| self.row_to_webhook_log(r))
+            .collect())
+    }
+
     /// Создаёт лог webhook
     pub async fn create_webhook_log(&self, mut log: WebhookLog) -> Result<WebhookLog> {
         let now = Utc::now();
 
         let id = sqlx::query_scalar::<_, i64>(
-                    "INSERT INTO webhook_log (webhook_id, event_type, status_code, success, error, attempts, payload, response, created_at)
-                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id"
+                    "INSERT INTO webhook_log (webhook_id, event_type, status_code, success, error, attempts, payload, response, created_at, notification_policy_id, delivery_id)
+                     VALUES (NULLIF($1, 0), $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING id"
                 )
                 .bind(log.webhook_id)
                 .bind(&log.event_type)
@@ -205,6 +226,8 @@ impl SqlDb {
                 .bind(&log.payload)
                 .bind(&log.response)
                 .bind(now)
+                .bind(log.notification_policy_id)
+                .bind(log.delivery_id)
                 .fetch_one(self.get_postgres_pool().ok_or(Error::Other("PostgreSQL pool not found".to_string()))?)
                 .await
                 .map_err(Error::Database)?;
@@ -237,7 +260,9 @@ impl SqlDb {
     fn row_to_webhook_log(&self, row: WebhookLogRow) -> WebhookLog {
         WebhookLog {
             id: row.id,
-            webhook_id: row.webhook_id,
+            webhook_id: row.webhook_id.unwrap_or(0),
+            notification_policy_id: row.notification_policy_id,
+            delivery_id: row.delivery_id,
             event_type: row.event_type,
             status_code: row.status_code,
             success: row.success,
@@ -302,7 +327,9 @@ mod tests {
     fn test_webhook_log_row_struct_fields() {
         let row = WebhookLogRow {
             id: 1,
-            webhook_id: 10,
+            webhook_id: Some(10),
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "task_completed".to_string(),
             status_code: Some(200),
             success: true,
@@ -313,7 +340,7 @@ mod tests {
             created_at: Utc::now(),
         };
         assert_eq!(row.id, 1);
-        assert_eq!(row.webhook_id, 10);
+        assert_eq!(row.webhook_id, Some(10));
         assert!(row.success);
     }
 
@@ -375,7 +402,9 @@ mod tests {
     fn test_webhook_log_row_clone() {
         let row = WebhookLogRow {
             id: 1,
-            webhook_id: 5,
+            webhook_id: Some(5),
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "task_failed".to_string(),
             status_code: Some(500),
             success: false,
@@ -447,6 +476,8 @@ mod tests {
         let log = WebhookLog {
             id: 1,
             webhook_id: 10,
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "task_completed".to_string(),
             status_code: Some(200),
             success: true,
@@ -24,7 +24,7 @@ use crate::models::playbook_run_history::{
 use crate::models::snapshot::{TaskSnapshot, TaskSnapshotCreate};
 use crate::models::workflow::{
     Workflow, WorkflowCreate, WorkflowEdge, WorkflowEdgeCreate, WorkflowNode, WorkflowNodeCreate,
-    WorkflowNodeUpdate, WorkflowRun, WorkflowUpdate,
+    WorkflowNodeUpdate, WorkflowRun, WorkflowRunNode, WorkflowUpdate,
 };
 use crate::models::*;
 use crate::services::task_logger::TaskStatus;
@@ -197,9 +197,24 @@ pub trait TaskManager: Send + Sync {
         status_filter: Option<Vec<String>>,
         limit: Option<i32>,
     ) -> Result<Vec<TaskWithTpl>>;
+    /// Очередь ожидающих задач в порядке запуска: `priority DESC, created ASC`
+    ///
+    /// Задача без собственного приоритета получает приоритет шаблона. Задачи-хуки
+    /// в очередь не входят: их забирает узел родительской задачи.
+    async fn get_queued_tasks(&self, project_id: Option<i32>, limit: i32) -> Result<Vec<Task>>;
     async fn get_task(&self, project_id: i32, task_id: i32) -> Result<Task>;
     async fn create_task(&self, task: Task) -> Result<Task>;
     async fn update_task(&self, task: Task) -> Result<()>;
+    /// Меняет приоритет задачи, только пока она ждёт в очереди
+    ///
+    /// Возвращает `false`, если задача не найдена в проекте или уже не `waiting`
+    /// (например, её успел забрать пул или раннер).
+    async fn set_waiting_task_priority(
+        &self,
+        project_id: i32,
+        task_id: i32,
+        priority: Option<i32>,
+    ) -> Result<bool>;
     async fn delete_task(&self, project_id: i32, task_id: i32) -> Result<()>;
     async fn get_task_outputs(&self, task_id: i32) -> Result<Vec<TaskOutput>>;
     async fn create_task_output(&self, output: TaskOutput) -> Result<TaskOutput>;
@@ -211,6 +226,19 @@ pub trait TaskManager: Send + Sync {
     ) -> Result<()>;
     async fn get_running_tasks_count(&self) -> Result<usize>;
     async fn get_waiting_tasks_count(&self) -> Result<usize>;
+    /// Записывает связь задачи-хука с родительской задачей
+    async fn link_task_hook(&self, project_id: i32, hook: TaskHook) -> Result<()>;
+    /// Создаёт задачу-хук вместе со связью с родительской задачей
+    ///
+    /// Такая задача с самого начала не видна очереди и раннерам.
+    async fn create_hook_task(
+        &self,
+        task: Task,
+        parent_task_id: i32,
+        hook_type: &str,
+    ) -> Result<Task>;
+    /// Связи хуков, в которых задача является хуком или родителем
+    async fn get_task_hook_links(&self, project_id: i32, task_id: i32) -> Result<Vec<TaskHook>>;
 }
 
 /// Менеджер расписаний
@@ -234,6 +262,9 @@ pub trait ScheduleManager: Send + Sync {
         schedule_id: i32,
         hash: &str,
     ) -> Result<()>;
+
+    /// Запоминает последнее обработанное время срабатывания расписания
+    async fn set_schedule_last_fired(&self, schedule_id: i32, fired: DateTime<Utc>) -> Result<()>;
 }
 
 /// Менеджер сессий
@@ -254,6 +285,21 @@ pub trait TokenManager: Send + Sync {
     async fn get_api_token(&self, token_id: i32) -> Result<APIToken>;
     async fn expire_api_token(&self, user_id: i32, token_id: i32) -> Result<()>;
     async fn delete_api_token(&self, user_id: i32, token_id: i32) -> Result<()>;
+    /// Находит токен по его значению (для аутентификации)
+    async fn get_api_token_by_value(&self, token: &str) -> Result<APIToken>;
+    /// Запоминает время и IP последнего использования токена
+    async fn touch_api_token(&self, token_id: i32, ip: Option<&str>) -> Result<()>;
+}
+
+/// Менеджер сервисных аккаунтов
+#[async_trait]
+pub trait ServiceAccountManager: Send + Sync {
+    async fn get_service_accounts(&self) -> Result<Vec<ServiceAccount>>;
+    async fn get_service_account(&self, id: i32) -> Result<ServiceAccount>;
+    /// Регистрирует уже созданного пользователя как сервисный аккаунт
+    async fn create_service_account(&self, account: ServiceAccount) -> Result<ServiceAccount>;
+    /// Удаляет аккаунт вместе с его пользователем и токенами
+    async fn delete_service_account(&self, id: i32) -> Result<()>;
 }
 
 /// Менеджер событий
@@ -277,6 +323,18 @@ pub trait RunnerManager: Send + Sync {
     async fn find_runner_by_token(&self, token: &str) -> Result<Runner>;
     /// Обновить время последней активности раннера
     async fn touch_runner(&self, runner_id: i32) -> Result<()>;
+    /// Атомарно закрепить ожидающую задачу за раннером.
+    ///
+    /// Возвращает `false`, если задачу уже забрал кто-то другой.
+    async fn claim_task(&self, task_id: i32, runner_id: i32) -> Result<bool>;
+    /// ID незавершённых задач, закреплённых за раннером
+    async fn get_runner_task_ids(&self, runner_id: i32) -> Result<Vec<i32>>;
+    /// Вернуть в очередь задачи активных раннеров без heartbeat дольше `timeout_secs`.
+    ///
+    /// Останавливаемые задачи таких раннеров сразу переводятся в `stopped`.
+    /// Задачи удалённых и отключённых раннеров завершаются
+    /// [`ClusterNodeManager::fail_orphaned_tasks`]. Возвращает затронутые задачи.
+    async fn requeue_stale_runner_tasks(&self, timeout_secs: i64) -> Result<Vec<Task>>;
 }
 
 /// Менеджер представлений
@@ -298,6 +356,16 @@ pub trait IntegrationManager: Send + Sync {
     async fn create_integration(&self, integration: Integration) -> Result<Integration>;
     async fn update_integration(&self, integration: Integration) -> Result<()>;
     async fn delete_integration(&self, project_id: i32, integration_id: i32) -> Result<()>;
+    /// Псевдонимы интеграций проекта (все или только указанной интеграции)
+    async fn get_integration_aliases(
+        &self,
+        project_id: i32,
+        integration_id: Option<i32>,
+    ) -> Result<Vec<IntegrationAlias>>;
+    /// Находит псевдоним по публичному значению (для входящих вызовов)
+    async fn get_integration_alias_by_alias(&self, alias: &str) -> Result<IntegrationAlias>;
+    async fn create_integration_alias(&self, alias: IntegrationAlias) -> Result<IntegrationAlias>;
+    async fn delete_integration_alias(&self, project_id: i32, alias_id: i32) -> Result<()>;
 }
 
 /// Менеджер приглашений проекта
@@ -562,6 +630,29 @@ pub trait ProjectRoleManager: Send + Sync {
     async fn delete_project_role(&self, project_id: i32, role_id: i32) -> Result<()>;
 }
 
+/// Доступ к проекту: роли участников и переопределения ролей на шаблонах
+#[async_trait]
+pub trait ProjectAccessManager: Send + Sync {
+    /// Slug роли участника проекта (встроенной или кастомной);
+    /// `None`, если пользователь не участник
+    async fn get_project_user_role(&self, project_id: i32, user_id: i32) -> Result<Option<String>>;
+    /// Назначает участнику роль, добавляя его в проект при необходимости
+    async fn set_project_user_role(&self, project_id: i32, user_id: i32, role: &str) -> Result<()>;
+    /// Переопределения ролей шаблона (`template_role`)
+    async fn get_template_role_perms(
+        &self,
+        project_id: i32,
+        template_id: i32,
+    ) -> Result<Vec<TemplateRolePerm>>;
+    async fn create_template_role_perm(&self, perm: TemplateRolePerm) -> Result<TemplateRolePerm>;
+    async fn delete_template_role_perm(
+        &self,
+        project_id: i32,
+        template_id: i32,
+        id: i32,
+    ) -> Result<()>;
+}
+
 /// Менеджер Workflow (DAG)
 #[async_trait]
 pub trait WorkflowManager: Send + Sync {
@@ -600,13 +691,36 @@ pub trait WorkflowManager: Send + Sync {
         workflow_id: i32,
         project_id: i32,
     ) -> Result<Vec<WorkflowRun>>;
-    async fn create_workflow_run(&self, workflow_id: i32, project_id: i32) -> Result<WorkflowRun>;
+    async fn get_workflow_run(&self, id: i32, project_id: i32) -> Result<WorkflowRun>;
+    /// Незавершённые запуски всех проектов
+    async fn get_active_workflow_runs(&self) -> Result<Vec<WorkflowRun>>;
+    async fn create_workflow_run(
+        &self,
+        workflow_id: i32,
+        project_id: i32,
+        params: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<WorkflowRun>;
+    /// Меняет статус незавершённого запуска; статусы success/failed/cancelled финальны
     async fn update_workflow_run_status(
         &self,
         id: i32,
         status: &str,
         message: Option<String>,
     ) -> Result<()>;
+    async fn get_workflow_run_nodes(&self, run_id: i32) -> Result<Vec<WorkflowRunNode>>;
+    /// Сохраняет (upsert) состояние узла в запуске
+    async fn save_workflow_run_node(&self, node: WorkflowRunNode) -> Result<()>;
+    /// Одобряет или отклоняет ожидающий узел подтверждения
+    ///
+    /// Возвращает `false`, если узел не ожидает решения.
+    async fn decide_workflow_approval(
+        &self,
+        run_id: i32,
+        node_id: i32,
+        approved: bool,
+        user_id: i32,
+        comment: Option<String>,
+    ) -> Result<bool>;
 }
 
 /// Менеджер политик уведомлений
@@ -702,6 +816,12 @@ pub trait Store:
     + OrganizationManager
     + DeploymentEnvironmentManager
     + StructuredOutputManager
+    + ConcurrencyGroupManager
+    + ClusterNodeManager
+    + TaskLogArchiveManager
+    + EventOutboxManager
+    + ProjectAccessManager
+    + ServiceAccountManager
 {
 }
 
@@ -709,6 +829,8 @@ pub trait Store:
 #[async_trait]
 pub trait DriftManager: Send + Sync {
     async fn get_drift_configs(&self, project_id: i32) -> Result<Vec<DriftConfig>>;
+    /// Включённые конфигурации всех проектов (для планировщика)
+    async fn get_enabled_drift_configs(&self) -> Result<Vec<DriftConfig>>;
     async fn get_drift_config(&self, id: i32, project_id: i32) -> Result<DriftConfig>;
     async fn create_drift_config(
         &self,
@@ -721,6 +843,12 @@ pub trait DriftManager: Send + Sync {
         project_id: i32,
         enabled: bool,
     ) -> Result<()>;
+    async fn update_drift_config_schedule(
+        &self,
+        id: i32,
+        project_id: i32,
+        schedule: Option<String>,
+    ) -> Result<()>;
     async fn delete_drift_config(&self, id: i32, project_id: i32) -> Result<()>;
     async fn get_drift_results(&self, drift_config_id: i32, limit: i64)
     -> Result<Vec<DriftResult>>;
@@ -733,7 +861,15 @@ pub trait DriftManager: Send + Sync {
         summary: Option<String>,
         task_id: Option<i32>,
     ) -> Result<DriftResult>;
+    async fn update_drift_result(
+        &self,
+        id: i32,
+        status: &str,
+        summary: Option<String>,
+    ) -> Result<()>;
     async fn get_latest_drift_results(&self, project_id: i32) -> Result<Vec<DriftResult>>;
+    /// Незавершённые (`pending`) результаты проверок всех проектов
+    async fn get_pending_drift_results(&self) -> Result<Vec<DriftResult>>;
 }
 
 /// Менеджер LDAP Group → Team маппингов
@@ -967,6 +1103,141 @@ pub trait StructuredOutputManager: Send + Sync {
     ) -> Result<crate::models::TaskOutputsMap>;
 }
 
+/// Менеджер групп параллелизма задач
+#[async_trait]
+pub trait ConcurrencyGroupManager: Send + Sync {
+    async fn get_concurrency_groups(&self, project_id: i32) -> Result<Vec<ConcurrencyGroup>>;
+    /// Создаёт группу или обновляет лимит существующей (по имени)
+    async fn upsert_concurrency_group(
+        &self,
+        project_id: i32,
+        payload: ConcurrencyGroupUpsert,
+    ) -> Result<ConcurrencyGroup>;
+    async fn delete_concurrency_group(&self, project_id: i32, name: &str) -> Result<()>;
+    /// Атомарно занимает слоты задачи во всех её группах и забирает задачу
+    /// (`waiting` → `running`)
+    ///
+    /// Слоты задач, которые уже не выполняются, считаются свободными.
+    async fn claim_task_with_slots(
+        &self,
+        task_id: i32,
+        claimer: TaskClaimer,
+        slots: &[SlotRequest],
+    ) -> Result<SlotClaim>;
+    /// Слоты групп, занятые выполняющимися задачами
+    async fn get_concurrency_slot_holders(&self) -> Result<Vec<ConcurrencySlotHolder>>;
+}
+
+/// Менеджер узлов кластера и владения выполняемыми задачами
+#[async_trait]
+pub trait ClusterNodeManager: Send + Sync {
+    /// Отметить узел живым (запись создаётся при первом вызове)
+    async fn touch_cluster_node(&self, node_id: &str, hostname: &str) -> Result<()>;
+    /// Закрепить выполняемую задачу за узлом сервера
+    async fn set_task_node(&self, task_id: i32, node_id: &str) -> Result<()>;
+    /// Атомарно забрать ожидающую задачу на выполнение узлом сервера
+    ///
+    /// Переводит задачу из `waiting` в `running` и закрепляет за узлом.
+    /// Возвращает `false`, если задачу уже забрал другой обработчик.
+    async fn claim_task_for_node(&self, task_id: i32, node_id: &str) -> Result<bool>;
+    /// Завершить с ошибкой незавершённые задачи, чей узел не отмечался дольше
+    /// `node_timeout_secs`, либо чей раннер удалён или отключён.
+    ///
+    /// Возвращает ID затронутых задач.
+    async fn fail_orphaned_tasks(&self, node_timeout_secs: i64, message: &str) -> Result<Vec<i32>>;
+    /// Закрепить запуск workflow за узлом, выполняющим его executor
+    async fn set_workflow_run_node(&self, run_id: i32, node_id: &str) -> Result<()>;
+    /// Атомарно забрать на узел `node_id` незавершённые запуски workflow, чей
+    /// узел не отмечался дольше `node_timeout_secs`
+    ///
+    /// Возвращает забранные запуски.
+    async fn claim_orphaned_workflow_runs(
+        &self,
+        node_timeout_secs: i64,
+        node_id: &str,
+    ) -> Result<Vec<WorkflowRun>>;
+    /// Захватить или подтвердить лидерство узла в аренде `lease`.
+    ///
+    /// Возвращает `true`, пока аренда принадлежит узлу. В PostgreSQL это
+    /// advisory lock на выделенном соединении: он освобождается сам при
+    /// падении процесса или обрыве соединения.
+    async fn try_acquire_leader_lock(&self, lease: &str, node_id: &str) -> Result<bool>;
+    /// Отпустить аренду лидерства, если она принадлежит узлу
+    async fn release_leader_lock(&self, lease: &str, node_id: &str) -> Result<()>;
+    /// Текущий держатель аренды лидерства
+    async fn get_cluster_leader(&self, lease: &str) -> Result<Option<ClusterLeader>>;
+}
+
+/// Менеджер архива логов задач и политик хранения
+#[async_trait]
+pub trait TaskLogArchiveManager: Send + Sync {
+    /// Задачи, завершённые до `finished_before`, с неархивированным выводом:
+    /// пары `(project_id, task_id)`
+    async fn get_tasks_to_archive(
+        &self,
+        finished_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<(i32, i32)>>;
+    async fn get_task_log_archive(&self, task_id: i32) -> Result<Option<TaskLogArchive>>;
+    /// Сохраняет запись архива и удаляет из `task_output` строки задачи
+    /// с ID не больше `last_output_id`
+    async fn complete_task_log_archive(
+        &self,
+        archive: TaskLogArchive,
+        last_output_id: i32,
+    ) -> Result<()>;
+    async fn get_log_retention_policies(&self) -> Result<Vec<LogRetentionPolicy>>;
+    async fn get_log_retention_policy(&self, project_id: i32)
+    -> Result<Option<LogRetentionPolicy>>;
+    /// Сохраняет политику проекта; политика без ограничений удаляется
+    async fn set_log_retention_policy(&self, policy: LogRetentionPolicy) -> Result<()>;
+    /// ID завершённых задач проекта, выходящих за политику хранения
+    async fn get_expired_task_ids(&self, policy: &LogRetentionPolicy) -> Result<Vec<i32>>;
+    /// Удаляет задачи вместе с выводом, стадиями и записями архива
+    async fn purge_tasks(&self, project_id: i32, task_ids: &[i32]) -> Result<()>;
+}
+
+/// Менеджер outbox событий и их доставок
+#[async_trait]
+pub trait EventOutboxManager: Send + Sync {
+    /// Записывает событие в outbox
+    async fn create_outbox_event(&self, event: OutboxEvent) -> Result<OutboxEvent>;
+    async fn get_outbox_event(&self, id: i64) -> Result<OutboxEvent>;
+    /// События, ещё не разложенные на доставки, в порядке записи
+    async fn get_undispatched_events(&self, limit: i64) -> Result<Vec<OutboxEvent>>;
+    /// Создаёт доставки события и отмечает его разложенным; повторная
+    /// раскладка уже разложенного события ничего не делает
+    async fn dispatch_outbox_event(
+        &self,
+        event_id: i64,
+        deliveries: Vec<NewEventDelivery>,
+    ) -> Result<()>;
+    /// Берёт в работу ожидающие доставки, чьё время пришло: увеличивает
+    /// счётчик попыток и сдвигает `next_attempt` на `lease_secs`, чтобы
+    /// доставку не взял другой узел, а после падения узла она повторилась
+    async fn claim_due_deliveries(&self, limit: i64, lease_secs: i64)
+    -> Result<Vec<EventDelivery>>;
+    /// Сохраняет результат попытки; для `Pending` — время следующей попытки
+    async fn finish_event_delivery(
+        &self,
+        id: i64,
+        status: DeliveryStatus,
+        next_attempt: Option<DateTime<Utc>>,
+        error: Option<String>,
+    ) -> Result<()>;
+    async fn get_event_deliveries(
+        &self,
+        project_id: i32,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> Result<Vec<EventDelivery>>;
+    async fn get_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery>;
+    /// Ставит доставку в очередь заново со сброшенным счётчиком попыток
+    async fn redeliver_event_delivery(&self, project_id: i32, id: i64) -> Result<EventDelivery>;
+    /// Попытки доставки из `webhook_log`
+    async fn get_event_delivery_logs(&self, delivery_id: i64) -> Result<Vec<WebhookLog>>;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1492,6 +1763,8 @@ mod tests {
             pos_x: 100.0,
             pos_y: 200.0,
             wave: 0,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         assert_eq!(node.workflow_id, 10);
         assert_eq!(node.template_id, 20);
@@ -1525,6 +1798,7 @@ mod tests {
             created: Utc::now(),
             started: Some(Utc::now()),
             finished: None,
+            params: Default::default(),
         };
         assert_eq!(run.workflow_id, 10);
         assert_eq!(run.project_id, 20);
@@ -94,6 +94,10 @@ impl AnsiblePlaybook {
     ) -> TokioCommand {
         let mut cmd = TokioCommand::new(command);
         cmd.args(&args);
+        // Отмена future выполнения (stop задачи) должна завершать процесс
+        cmd.kill_on_drop(true);
+        // Своя группа процессов — при таймауте завершается вместе с потомками
+        crate::utils::process::isolate_process_group(&mut cmd);
         cmd.current_dir(self.get_playbook_dir());
 
         // Добавляем переменные окружения
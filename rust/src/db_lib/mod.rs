@@ -34,4 +34,4 @@ pub use git_client_factory::{
 pub use go_git_client::GoGitClient;
 pub use local_app::{AccessKeyInstaller, LocalApp, LocalAppInstallingArgs, LocalAppRunningArgs};
 pub use shell_app::ShellApp;
-pub use terraform_app::TerraformApp;
+pub use terraform_app::{PlanReview, PlanStage, SavedPlan, TerraformApp};
@@ -56,6 +56,7 @@ impl ShellApp {
 
         cmd.env("HOME", get_home_dir(&self.repository, self.template.id));
         cmd.env("PWD", self.get_full_path());
+        crate::utils::process::isolate_std_process_group(&mut cmd);
 
         cmd
     }
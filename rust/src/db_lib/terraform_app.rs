@@ -12,6 +12,75 @@ use tracing::{debug, error, info, warn};
 use crate::error::{Error, Result};
 use crate::models::{Inventory, Repository, Template, TerraformTaskParams};
 use crate::services::task_logger::{TaskLogger, TaskLoggerArc, TaskStatus};
+use crate::utils::process::ProcessGroups;
+
+/// Этап задачи с подтверждением плана (`require_approval`)
+#[derive(Debug, Clone)]
+pub enum PlanStage {
+    /// Построить план `plan -out` и вернуть его на ревью без apply
+    Review,
+    /// Применить ранее сохранённый и одобренный план
+    Apply(SavedPlan),
+}
+
+/// Бинарный план и serial состояния, на котором он построен
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedPlan {
+    pub plan: Vec<u8>,
+    pub state_serial: Option<i64>,
+}
+
+/// Результат построения плана для ревью
+#[derive(Debug, Clone, Default)]
+pub struct PlanReview {
+    pub has_changes: bool,
+    pub saved: SavedPlan,
+    /// Вывод `terraform show -json` для сохранённого плана
+    pub plan_json: Option<String>,
+    pub resources_added: i32,
+    pub resources_changed: i32,
+    pub resources_removed: i32,
+}
+
+/// Подсчитывает добавляемые, изменяемые и удаляемые ресурсы по `show -json`
+///
+/// Замена ресурса (`delete` + `create`) считается как добавление и удаление,
+/// как в сводке `Plan: N to add, N to change, N to destroy`.
+pub fn count_plan_changes(plan_json: &serde_json::Value) -> (i32, i32, i32) {
+    let (mut added, mut changed, mut removed) = (0, 0, 0);
+    let changes = plan_json
+        .get("resource_changes")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten();
+    for change in changes {
+        let actions: Vec<&str> = change
+            .pointer("/change/actions")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        let creates = actions.contains(&"create");
+        let deletes = actions.contains(&"delete");
+        if creates {
+            added += 1;
+        }
+        if deletes {
+            removed += 1;
+        }
+        if !creates && !deletes && actions.contains(&"update") {
+            changed += 1;
+        }
+    }
+    (added, changed, removed)
+}
+
+/// Извлекает serial из вывода `terraform state pull` (`None`, если состояния нет)
+pub fn state_serial(state: &[u8]) -> Option<i64> {
+    serde_json::from_slice::<serde_json::Value>(state)
+        .ok()?
+        .get("serial")?
+        .as_i64()
+}
 
 /// TerraformApp представляет приложение для выполнения Terraform команд
 pub struct TerraformApp {
@@ -31,6 +100,8 @@ pub struct TerraformApp {
     pub backend_filename: Option<String>,
     /// Рабочая директория
     pub work_dir: PathBuf,
+    /// Группы запущенных процессов (для завершения по таймауту)
+    pub process_groups: ProcessGroups,
 }
 
 impl TerraformApp {
@@ -52,6 +123,7 @@ impl TerraformApp {
             plan_has_no_changes: false,
             backend_filename: None,
             work_dir,
+            process_groups: ProcessGroups::default(),
         }
     }
 
@@ -69,6 +141,10 @@ impl TerraformApp {
     ) -> TokioCommand {
         let mut cmd = TokioCommand::new(command);
         cmd.args(&args);
+        // Отмена future выполнения (stop задачи) должна завершать процесс
+        cmd.kill_on_drop(true);
+        // Своя группа процессов — при таймауте завершается вместе с потомками
+        crate::utils::process::isolate_process_group(&mut cmd);
         cmd.current_dir(self.get_full_path());
 
         // Добавляем переменные окружения
@@ -101,10 +177,35 @@ impl TerraformApp {
     }
 
     /// Выполняет команду
-    async fn run_cmd(&self, command: &str, args: Vec<String>) -> Result<()> {
-        let mut cmd = self.make_cmd(command, args, vec![]);
+    async fn run_cmd(
+        &self,
+        command: &str,
+        args: Vec<String>,
+        environment_vars: Vec<String>,
+    ) -> Result<()> {
+        let status = self.run_cmd_status(command, args, environment_vars).await?;
+
+        if !status.success() {
+            return Err(Error::Other(format!(
+                "Command failed with status: {}",
+                status
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Выполняет команду, записывая вывод в лог, и возвращает код завершения
+    async fn run_cmd_status(
+        &self,
+        command: &str,
+        args: Vec<String>,
+        environment_vars: Vec<String>,
+    ) -> Result<std::process::ExitStatus> {
+        let mut cmd = self.make_cmd(command, args, environment_vars);
 
         let mut child = cmd.spawn()?;
+        self.process_groups.register(child.id().unwrap_or(0));
 
         // Читаем вывод
         if let Some(ref mut stdout) = child.stdout {
@@ -121,16 +222,39 @@ impl TerraformApp {
             }
         }
 
-        let status = child.wait().await?;
+        Ok(child.wait().await?)
+    }
 
-        if !status.success() {
+    /// Выполняет команду и возвращает её stdout без записи в лог
+    async fn cmd_output(
+        &self,
+        args: Vec<String>,
+        environment_vars: Vec<String>,
+    ) -> Result<Vec<u8>> {
+        let output = self
+            .make_cmd(&self.name, args, environment_vars)
+            .output()
+            .await?;
+        if !output.status.success() {
             return Err(Error::Other(format!(
-                "Command failed with status: {}",
-                status
+                "{} failed with status {}: {}",
+                self.name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
             )));
         }
+        Ok(output.stdout)
+    }
 
-        Ok(())
+    /// Текущий serial состояния (`terraform state pull`)
+    pub async fn current_state_serial(&self, environment_vars: Vec<String>) -> Result<Option<i64>> {
+        let state = self
+            .cmd_output(
+                vec!["state".to_string(), "pull".to_string()],
+                environment_vars,
+            )
+            .await?;
+        Ok(state_serial(&state))
     }
 
     /// Инициализирует Terraform
@@ -161,7 +285,7 @@ impl TerraformApp {
         // Дополнительные аргументы
         args.extend(extra_args);
 
-        self.run_cmd(&self.name, args).await?;
+        self.run_cmd(&self.name, args, environment_vars).await?;
 
         Ok(())
     }
@@ -205,7 +329,7 @@ impl TerraformApp {
                 "select".to_string(),
                 workspace.to_string(),
             ];
-            self.run_cmd(&self.name, args).await?;
+            self.run_cmd(&self.name, args, environment_vars).await?;
         } else {
             // Создаём новый
             let args = vec![
@@ -213,7 +337,7 @@ impl TerraformApp {
                 "new".to_string(),
                 workspace.to_string(),
             ];
-            self.run_cmd(&self.name, args).await?;
+            self.run_cmd(&self.name, args, environment_vars).await?;
         }
 
         Ok(())
@@ -226,7 +350,7 @@ impl TerraformApp {
         args: Vec<String>,
         environment_vars: Vec<String>,
         inputs: HashMap<String, String>,
-        cb: Option<Box<dyn Fn(&Child) + Send>>,
+        _cb: Option<Box<dyn Fn(&Child) + Send>>,
     ) -> Result<bool> {
         self.logger.log("Running Terraform plan...");
 
@@ -234,6 +358,7 @@ impl TerraformApp {
             "plan".to_string(),
             "-input=false".to_string(),
             "-no-color".to_string(),
+            "-detailed-exitcode".to_string(),
         ];
 
         // Plan file
@@ -243,20 +368,16 @@ impl TerraformApp {
         // Дополнительные аргументы
         plan_args.extend(args);
 
-        let mut cmd = self.make_cmd(&self.name, plan_args, environment_vars);
-
-        let mut child = cmd.spawn()?;
+        let status = self
+            .run_cmd_status(&self.name, plan_args, environment_vars)
+            .await?;
 
-        // Callback для процесса
-        if let Some(callback) = cb {
-            callback(&child);
+        // -detailed-exitcode: 0 — изменений нет, 2 — есть изменения, иначе ошибка
+        match status.code() {
+            Some(0) => Ok(false),
+            Some(2) => Ok(true),
+            _ => Err(Error::Other(format!("Plan failed with status: {}", status))),
         }
-
-        let status = child.wait().await?;
-
-        // Проверяем есть ли изменения
-        // Возвращаем true если команда выполнилась успешно
-        Ok(status.success())
     }
 
     /// Выполняет apply
@@ -284,7 +405,8 @@ impl TerraformApp {
 
         apply_args.extend(args);
 
-        self.run_cmd(&self.name, apply_args).await?;
+        self.run_cmd(&self.name, apply_args, environment_vars)
+            .await?;
 
         Ok(())
     }
@@ -301,7 +423,8 @@ impl TerraformApp {
 
         destroy_args.extend(args);
 
-        self.run_cmd(&self.name, destroy_args).await?;
+        self.run_cmd(&self.name, destroy_args, environment_vars)
+            .await?;
 
         Ok(())
     }
@@ -313,7 +436,7 @@ impl TerraformApp {
     }
 
     /// Устанавливает зависимости
-    pub async fn install_requirements(&self) -> Result<()> {
+    pub async fn install_requirements(&self, environment_vars: Vec<String>) -> Result<()> {
         self.logger.log("Installing Terraform requirements...");
 
         // Инициализация
@@ -325,30 +448,118 @@ impl TerraformApp {
             reconfigure: false,
         };
 
-        self.init(vec![], &params, vec![]).await?;
+        self.init(environment_vars, &params, vec![]).await?;
 
         Ok(())
     }
 
+    /// Строит план для ревью: `plan -out`, `show -json` и serial состояния
+    pub async fn plan_for_review(
+        &self,
+        args: crate::db_lib::LocalAppRunningArgs,
+    ) -> Result<PlanReview> {
+        let cli_args = args.cli_args.get("default").cloned().unwrap_or_default();
+        let env = args.environment_vars;
+        self.install_requirements(env.clone()).await?;
+        let state_serial = self.current_state_serial(env.clone()).await?;
+        let has_changes = self
+            .plan(cli_args, env.clone(), HashMap::new(), None)
+            .await?;
+
+        let plan_file = self.work_dir.join("tfplan");
+        let plan = tokio::fs::read(&plan_file).await?;
+        let plan_json = self
+            .cmd_output(
+                vec![
+                    "show".to_string(),
+                    "-json".to_string(),
+                    plan_file.display().to_string(),
+                ],
+                env,
+            )
+            .await?;
+        let plan_json = String::from_utf8_lossy(&plan_json).into_owned();
+        let (resources_added, resources_changed, resources_removed) =
+            serde_json::from_str(&plan_json)
+                .map(|v| count_plan_changes(&v))
+                .unwrap_or_default();
+
+        self.logger.log(if has_changes {
+            "Plan saved, waiting for approval"
+        } else {
+            "No changes"
+        });
+
+        Ok(PlanReview {
+            has_changes,
+            saved: SavedPlan { plan, state_serial },
+            plan_json: Some(plan_json),
+            resources_added,
+            resources_changed,
+            resources_removed,
+        })
+    }
+
+    /// Применяет ровно сохранённый план
+    ///
+    /// Отклоняет план, если serial состояния изменился после его построения.
+    pub async fn apply_saved_plan(
+        &self,
+        args: crate::db_lib::LocalAppRunningArgs,
+        saved: &SavedPlan,
+    ) -> Result<()> {
+        let env = args.environment_vars;
+        self.install_requirements(env.clone()).await?;
+        let current = self.current_state_serial(env.clone()).await?;
+        if current != saved.state_serial {
+            let serial = |s: Option<i64>| s.map_or("none".to_string(), |s| s.to_string());
+            return Err(Error::Validation(format!(
+                "State changed since the plan was created (serial {} -> {}), plan rejected",
+                serial(saved.state_serial),
+                serial(current)
+            )));
+        }
+
+        tokio::fs::write(self.work_dir.join("tfplan"), &saved.plan).await?;
+        self.logger.log("Applying approved plan...");
+        self.apply(vec![], env, HashMap::new(), None).await
+    }
+
     /// Запускает задачу
     pub async fn run(&self, args: crate::db_lib::LocalAppRunningArgs) -> Result<()> {
-        // Параметры извлекаются из Template через playbook или args
-        let params = TerraformTaskParams::default();
+        // Параметры задачи передаются LocalJob через task_params
+        let params = args
+            .task_params
+            .downcast_ref::<TerraformTaskParams>()
+            .cloned()
+            .unwrap_or_default();
+
+        // Аргументы шаблона и задачи идут в plan и destroy
+        let cli_args = args.cli_args.get("default").cloned().unwrap_or_default();
 
         // Инициализация
-        self.install_requirements().await?;
+        let env = args.environment_vars;
+        self.install_requirements(env.clone()).await?;
 
         // Workspace выбирается из параметров если указан
         // Workspace поддержка зависит от конфигурации Terraform
 
         // Plan
-        let has_changes = self.plan(vec![], vec![], HashMap::new(), None).await?;
+        let has_changes = self
+            .plan(cli_args.clone(), env.clone(), HashMap::new(), None)
+            .await?;
 
         // Apply или Destroy
-        if params.destroy {
-            self.destroy(vec![], vec![]).await?;
+        if params.plan {
+            self.logger.log(if has_changes {
+                "Plan has changes, apply skipped (plan only)"
+            } else {
+                "No changes (plan only)"
+            });
+        } else if params.destroy {
+            self.destroy(cli_args, env).await?;
         } else if has_changes {
-            self.apply(vec![], vec![], HashMap::new(), None).await?;
+            self.apply(vec![], env, HashMap::new(), None).await?;
         } else {
             self.logger.log("No changes to apply");
         }
@@ -399,6 +610,109 @@ mod tests {
         assert!(path.ends_with("repository"));
     }
 
+    #[test]
+    fn test_count_plan_changes() {
+        let plan = serde_json::json!({
+            "resource_changes": [
+                {"change": {"actions": ["create"]}},
+                {"change": {"actions": ["update"]}},
+                {"change": {"actions": ["delete"]}},
+                {"change": {"actions": ["delete", "create"]}},
+                {"change": {"actions": ["no-op"]}},
+                {"change": {"actions": ["read"]}}
+            ]
+        });
+        assert_eq!(count_plan_changes(&plan), (2, 1, 2));
+        assert_eq!(count_plan_changes(&serde_json::json!({})), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_state_serial() {
+        assert_eq!(state_serial(br#"{"version":4,"serial":7}"#), Some(7));
+        assert_eq!(state_serial(b""), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_apply_passes_environment_vars() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let work_dir = std::env::temp_dir().join(format!("tf_apply_env_{}", std::process::id()));
+        std::fs::create_dir_all(work_dir.join("repository")).unwrap();
+        let bin = work_dir.join("fake-terraform");
+        let out = work_dir.join("env.out");
+        std::fs::write(
+            &bin,
+            format!("#!/bin/sh\necho \"$TF_VAR_region\" > {}\n", out.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let app = TerraformApp::new(
+            Arc::new(BasicLogger::new()),
+            Template::default(),
+            Repository::default(),
+            Inventory::default(),
+            bin.display().to_string(),
+            work_dir.clone(),
+        );
+        app.apply(
+            vec![],
+            vec!["TF_VAR_region=eu-west-1".to_string()],
+            HashMap::new(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let written = std::fs::read_to_string(&out).unwrap();
+        assert_eq!(written.trim(), "eu-west-1");
+        drop(app);
+        assert!(!work_dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_passes_cli_args_to_plan_and_destroy() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let work_dir = std::env::temp_dir().join(format!("tf_run_args_{}", std::process::id()));
+        std::fs::create_dir_all(work_dir.join("repository")).unwrap();
+        let bin = work_dir.join("fake-terraform");
+        let out = work_dir.join("args.out");
+        std::fs::write(
+            &bin,
+            format!("#!/bin/sh\necho \"$*\" >> {}\n", out.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let app = TerraformApp::new(
+            Arc::new(BasicLogger::new()),
+            Template::default(),
+            Repository::default(),
+            Inventory::default(),
+            bin.display().to_string(),
+            work_dir.clone(),
+        );
+        let args = crate::db_lib::LocalAppRunningArgs {
+            cli_args: HashMap::from([("default".to_string(), vec!["-var=region=eu".to_string()])]),
+            task_params: Box::new(TerraformTaskParams {
+                destroy: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        app.run(args).await.unwrap();
+
+        let written = std::fs::read_to_string(&out).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert!(lines[0].starts_with("init"));
+        assert!(lines[1].starts_with("plan") && lines[1].ends_with("-var=region=eu"));
+        assert!(lines[2].starts_with("destroy") && lines[2].ends_with("-var=region=eu"));
+        drop(app);
+    }
+
     #[test]
     fn test_get_environment_vars() {
         let app = create_test_terraform_app();
@@ -5,12 +5,17 @@
 //! - Управления проектами (ProjectService)
 //! - Управления раннерами (RunnerService)
 //!
-//! Примечание: Для полной компиляции требуется protoc.
-//! В этой версии предоставлены только базовые структуры.
+//! Код сообщений и сервисов предварительно сгенерирован из
+//! `proto/semaphore.proto` в `generated/semaphore.rs`.
 
 pub mod server;
 pub mod services;
 
-// Типы и сервисы будут определены в services.rs
+/// Сгенерированные типы `semaphore.*`
+#[allow(clippy::all)]
+pub mod proto {
+    include!("generated/semaphore.rs");
+}
+
 pub use server::GrpcServer;
-pub use services::{ProjectServiceImpl, RunnerServiceImpl, TaskServiceImpl};
+pub use services::{GrpcServerConfig, ProjectServiceImpl, RunnerServiceImpl, TaskServiceImpl};
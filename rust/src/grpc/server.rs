@@ -1,42 +1,123 @@
 //! gRPC Server - Сервер gRPC для внутреннего взаимодействия
 //!
-//! Примечание: Полная реализация требует protoc для генерации кода.
+//! Поднимает `TaskService`, `ProjectService` и `RunnerService` поверх `Store`.
+//! Код сообщений и сервисов предварительно сгенерирован из `proto/semaphore.proto`
+//! (см. `grpc::proto`), поэтому protoc для сборки не нужен.
 
-use crate::error::Result;
-use crate::grpc::services::GrpcServerConfig;
+use std::future::Future;
 use std::net::SocketAddr;
-use tracing::info;
+use std::sync::Arc;
+
+use tonic::transport::Server;
+use tonic::transport::server::Router;
+use tracing::{info, warn};
+
+use crate::api::token_blacklist::TokenBlacklist;
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::grpc::proto::project_service_server::ProjectServiceServer;
+use crate::grpc::proto::runner_service_server::RunnerServiceServer;
+use crate::grpc::proto::task_service_server::TaskServiceServer;
+use crate::grpc::services::{
+    GrpcServerConfig, ProjectServiceImpl, RunnerServiceImpl, TaskServiceImpl,
+};
+use crate::services::cache_service::CacheService;
 
 /// gRPC сервер Velum
 pub struct GrpcServer {
     config: GrpcServerConfig,
+    store: Arc<dyn Store + Send + Sync>,
+    cache: Option<Arc<CacheService>>,
+    token_blacklist: TokenBlacklist,
 }
 
 impl GrpcServer {
-    pub fn new(config: GrpcServerConfig) -> Self {
-        Self { config }
+    pub fn new(config: GrpcServerConfig, store: Arc<dyn Store + Send + Sync>) -> Self {
+        Self {
+            config,
+            store,
+            cache: None,
+            token_blacklist: TokenBlacklist::new(),
+        }
     }
 
-    pub fn with_defaults() -> Self {
-        Self::new(GrpcServerConfig::default())
+    pub fn with_defaults(store: Arc<dyn Store + Send + Sync>) -> Self {
+        Self::new(GrpcServerConfig::default(), store)
     }
 
-    pub fn with_address(address: SocketAddr) -> Self {
-        Self::new(GrpcServerConfig {
-            address,
-            ..Default::default()
-        })
+    pub fn with_address(address: SocketAddr, store: Arc<dyn Store + Send + Sync>) -> Self {
+        Self::new(
+            GrpcServerConfig {
+                address,
+                ..Default::default()
+            },
+            store,
+        )
+    }
+
+    /// Подключает кэш, который сбрасывает `InvalidateProjectCache`
+    pub fn with_cache(mut self, cache: Arc<CacheService>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Подключает список отозванных JWT HTTP API, чтобы токен после выхода
+    /// не работал и через gRPC
+    pub fn with_token_blacklist(mut self, token_blacklist: TokenBlacklist) -> Self {
+        self.token_blacklist = token_blacklist;
+        self
+    }
+
+    /// Собирает роутер со всеми сервисами
+    pub fn router(&self) -> Router {
+        let max = self.config.max_message_size;
+        if self.config.enable_reflection {
+            warn!("gRPC reflection is not available: proto descriptors are not bundled");
+        }
+
+        Server::builder()
+            .add_service(
+                TaskServiceServer::new(
+                    TaskServiceImpl::new(self.store.clone())
+                        .with_token_blacklist(self.token_blacklist.clone()),
+                )
+                .max_decoding_message_size(max)
+                .max_encoding_message_size(max),
+            )
+            .add_service(
+                ProjectServiceServer::new(
+                    ProjectServiceImpl::new(self.store.clone(), self.cache.clone())
+                        .with_token_blacklist(self.token_blacklist.clone()),
+                )
+                .max_decoding_message_size(max)
+                .max_encoding_message_size(max),
+            )
+            .add_service(
+                RunnerServiceServer::new(RunnerServiceImpl::new(self.store.clone()))
+                    .max_decoding_message_size(max)
+                    .max_encoding_message_size(max),
+            )
     }
 
     /// Запускает gRPC сервер
     pub async fn serve(self) -> Result<()> {
-        info!("gRPC server stub running on {}", self.config.address);
-        info!("Full implementation requires protoc");
+        info!("gRPC server listening on {}", self.config.address);
+        self.router()
+            .serve(self.config.address)
+            .await
+            .map_err(|e| Error::Other(format!("gRPC server error: {e}")))
+    }
 
-        // Заглушка - просто ждём
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(3600)).await;
-        }
+    /// Запускает gRPC сервер до срабатывания `signal`
+    pub async fn serve_with_shutdown<F>(self, signal: F) -> Result<()>
+    where
+        F: Future<Output = ()> + Send,
+    {
+        info!("gRPC server listening on {}", self.config.address);
+        self.router()
+            .serve_with_shutdown(self.config.address, signal)
+            .await
+            .map_err(|e| Error::Other(format!("gRPC server error: {e}")))
     }
 
     pub fn address(&self) -> SocketAddr {
@@ -47,11 +128,23 @@ impl GrpcServer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::auth_local::LocalAuthService;
+    use crate::api::store_wrapper::StoreWrapper;
+    use crate::db::mock::MockStore;
+    use crate::db::store::TaskManager;
+    use crate::grpc::proto;
+    use crate::grpc::proto::task_service_client::TaskServiceClient;
+    use crate::models::{Task, User};
+    use crate::services::task_logger::TaskStatus;
+
+    fn store() -> Arc<dyn Store + Send + Sync> {
+        Arc::new(MockStore::new())
+    }
 
     #[test]
     fn test_grpc_server_new() {
         let config = GrpcServerConfig::default();
-        let server = GrpcServer::new(config);
+        let server = GrpcServer::new(config, store());
         assert_eq!(
             server.config.address,
             "0.0.0.0:50051".parse::<SocketAddr>().unwrap()
@@ -60,7 +153,7 @@ mod tests {
 
     #[test]
     fn test_grpc_server_with_defaults() {
-        let server = GrpcServer::with_defaults();
+        let server = GrpcServer::with_defaults(store());
         assert_eq!(
             server.address(),
             "0.0.0.0:50051".parse::<SocketAddr>().unwrap()
@@ -70,7 +163,7 @@ mod tests {
     #[test]
     fn test_grpc_server_with_custom_address() {
         let addr: SocketAddr = "127.0.0.1:9090".parse().unwrap();
-        let server = GrpcServer::with_address(addr);
+        let server = GrpcServer::with_address(addr, store());
         assert_eq!(server.address(), addr);
     }
 
@@ -122,7 +215,60 @@ mod tests {
     #[test]
     fn test_grpc_server_address_method() {
         let addr: SocketAddr = "[::1]:5555".parse().unwrap();
-        let server = GrpcServer::with_address(addr);
+        let server = GrpcServer::with_address(addr, store());
         assert_eq!(server.address(), addr);
     }
+
+    #[tokio::test]
+    async fn test_grpc_server_serves_task_status_over_tcp() {
+        let store = store();
+        store
+            .create_task(Task {
+                id: 1,
+                project_id: 1,
+                template_id: 1,
+                status: TaskStatus::Success,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let admin = User {
+            id: 1,
+            created: chrono::Utc::now(),
+            username: "admin".to_string(),
+            name: "Admin".to_string(),
+            email: "admin@example.com".to_string(),
+            password: String::new(),
+            admin: true,
+            external: false,
+            alert: false,
+            pro: false,
+            totp: None,
+            email_otp: None,
+        };
+        let token = LocalAuthService::new(StoreWrapper::new(store.clone()))
+            .generate_token(&admin)
+            .unwrap()
+            .token;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = GrpcServer::with_address(addr, store).router();
+        let server = tokio::spawn(
+            router.serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let mut client = TaskServiceClient::connect(format!("http://{addr}"))
+            .await
+            .unwrap();
+        let mut request = tonic::Request::new(proto::GetTaskStatusRequest { task_id: 1 });
+        request
+            .metadata_mut()
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+        let info = client.get_task_status(request).await.unwrap().into_inner();
+        assert_eq!(info.id, 1);
+        assert_eq!(info.status, proto::TaskStatus::Success as i32);
+
+        server.abort();
+    }
 }
@@ -1,16 +1,52 @@
-//! gRPC Services - Заглушки для gRPC сервисов
+//! gRPC Services - реализации сервисов из `proto/semaphore.proto`
 //!
-//! Примечание: Полная реализация требует protoc для генерации кода из .proto файлов.
+//! Сервисы работают поверх `Store`:
+//! - `TaskService` и `ProjectService` аутентифицируют пользователя токеном из
+//!   метаданных `authorization: Bearer <token>` так же, как HTTP API: JWT
+//!   сессии (отозванный при выходе отклоняется) или API-токен с его областями;
+//!   доступ к проекту проверяется по ролям (администраторы видят все проекты),
+//!   запуск и остановка задач требуют разрешения `RunTasks`;
+//! - `RunnerService` аутентифицирует раннер его токеном: в `RegisterRunner` —
+//!   полем `runner_token`, в остальных вызовах — метаданными `authorization`.
 
-use crate::error::Result;
+// `tonic::Status` — штатный тип ошибки всех gRPC-обработчиков
+#![allow(clippy::result_large_err)]
+
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use tonic::transport::Server;
-use tracing::info;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::Method;
+use chrono::Utc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+use crate::api::extractors::{self, AuthRejection, AuthUser};
+use crate::api::store_wrapper::StoreWrapper;
+use crate::api::token_blacklist::TokenBlacklist;
+use crate::db::store::Store;
+use crate::error::Error;
+use crate::grpc::proto;
+use crate::grpc::proto::project_service_server::ProjectService;
+use crate::grpc::proto::runner_service_server::RunnerService;
+use crate::grpc::proto::task_service_server::TaskService;
+use crate::models::{Permission, Runner, Task, TaskOutput};
+use crate::services::cache_service::CacheService;
+use crate::services::project_access;
+use crate::services::remote_runners;
+use crate::services::task_logger::TaskStatus;
+
+/// Интервал опроса задачи в `StreamTaskStatus`
+const STREAM_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Конфигурация gRPC сервера
 #[derive(Debug, Clone)]
 pub struct GrpcServerConfig {
     pub address: SocketAddr,
+    /// Зарезервировано: дескрипторы proto не входят в сборку, reflection не публикуется
     pub enable_reflection: bool,
     pub max_message_size: usize,
 }
@@ -25,85 +61,827 @@ impl Default for GrpcServerConfig {
     }
 }
 
-/// gRPC сервер Velum
-pub struct GrpcServer {
-    config: GrpcServerConfig,
+type SharedStore = Arc<dyn Store + Send + Sync>;
+
+// ============================================================================
+// Аутентификация и преобразования
+// ============================================================================
+
+/// Извлекает Bearer-токен из метаданных `authorization`
+fn bearer_token<T>(request: &Request<T>) -> Result<String, Status> {
+    request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| Status::unauthenticated("Missing bearer token"))
 }
 
-impl GrpcServer {
-    pub fn new(config: GrpcServerConfig) -> Self {
-        Self { config }
+/// Отказ HTTP API в терминах gRPC
+fn status_from_rejection((code, body): AuthRejection) -> Status {
+    if code == axum::http::StatusCode::FORBIDDEN {
+        Status::permission_denied(body.0.error)
+    } else {
+        Status::unauthenticated(body.0.error)
     }
+}
+
+/// Аутентифицирует пользователя тем же путём, что и HTTP API
+async fn authenticate<T>(
+    store: &SharedStore,
+    blacklist: &TokenBlacklist,
+    request: &Request<T>,
+) -> Result<AuthUser, Status> {
+    let token = bearer_token(request)?;
+    let ip = request.remote_addr().map(|addr| addr.ip().to_string());
+    extractors::authenticate_token(&StoreWrapper::new(store.clone()), blacklist, &token, ip)
+        .await
+        .map_err(status_from_rejection)
+}
 
-    pub fn with_defaults() -> Self {
-        Self::new(GrpcServerConfig::default())
+/// Проверяет области API-токена для изменения в проекте (как у
+/// `POST /api/project/{id}/...` в HTTP API)
+fn check_project_write(user: &AuthUser, project_id: i32) -> Result<(), Status> {
+    extractors::check_token_route(user, &Method::POST, &format!("/api/project/{project_id}"))
+        .map_err(status_from_rejection)
+}
+
+/// Проверяет, что пользователь имеет доступ к проекту
+async fn authorize_project(
+    store: &SharedStore,
+    user: &AuthUser,
+    project_id: i32,
+) -> Result<(), Status> {
+    project_access::require_permission(store.as_ref(), &user.caller(), project_id, None, None)
+        .await
+        .map_err(status_from_error)
+}
+
+/// Проверяет разрешение пользователя в проекте по ролям (как в HTTP API)
+async fn require_permission(
+    store: &SharedStore,
+    user: &AuthUser,
+    project_id: i32,
+    template_id: i32,
+    permission: Permission,
+) -> Result<(), Status> {
+    check_project_write(user, project_id)?;
+    project_access::require_permission(
+        store.as_ref(),
+        &user.caller(),
+        project_id,
+        Some(template_id),
+        Some(permission),
+    )
+    .await
+    .map_err(status_from_error)
+}
+
+/// Находит раннер по токену из метаданных и сверяет его ID
+async fn authenticate_runner<T>(
+    store: &SharedStore,
+    request: &Request<T>,
+    runner_id: &str,
+) -> Result<Runner, Status> {
+    let token = bearer_token(request)?;
+    let runner = store
+        .find_runner_by_token(&token)
+        .await
+        .map_err(|_| Status::unauthenticated("Invalid runner token"))?;
+    if runner.id.to_string() != runner_id {
+        return Err(Status::permission_denied(
+            "Runner token does not match runner_id",
+        ));
     }
+    if !runner.active {
+        return Err(Status::permission_denied("Runner is disabled"));
+    }
+    Ok(runner)
+}
 
-    pub fn with_address(address: SocketAddr) -> Self {
-        Self::new(GrpcServerConfig {
-            address,
-            ..Default::default()
-        })
+fn status_from_error(e: Error) -> Status {
+    match e {
+        Error::NotFound(msg) => Status::not_found(msg),
+        Error::Validation(msg) => Status::invalid_argument(msg),
+        Error::Unauthorized(msg) | Error::Auth(msg) => Status::unauthenticated(msg),
+        Error::Forbidden(msg) => Status::permission_denied(msg),
+        other => Status::internal(other.to_string()),
     }
+}
 
-    /// Запускает gRPC сервер (заглушка)
-    pub async fn serve(self) -> Result<()> {
-        info!("gRPC server configured on {}", self.config.address);
-        info!("Note: Full gRPC implementation requires protoc");
-        info!("To enable, install protoc and recompile with tonic-build");
+/// Преобразует ID из proto (int64) в ID хранилища
+fn to_id(value: i64, field: &str) -> Result<i32, Status> {
+    i32::try_from(value).map_err(|_| Status::invalid_argument(format!("{field} is out of range")))
+}
 
-        // В полной реализации здесь будет запуск сервера
-        // Пока просто блокируем задачу
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(3600)).await;
-        }
+/// Загружает задачу по ID (`get_task` ищет по ID, project_id не учитывается)
+async fn find_task(store: &SharedStore, task_id: i64) -> Result<Task, Status> {
+    store
+        .get_task(0, to_id(task_id, "task_id")?)
+        .await
+        .map_err(status_from_error)
+}
+
+/// Статус задачи в терминах proto
+pub fn proto_status(status: TaskStatus) -> proto::TaskStatus {
+    match status {
+        TaskStatus::Waiting
+        | TaskStatus::Starting
+        | TaskStatus::WaitingConfirmation
+        | TaskStatus::Confirmed => proto::TaskStatus::Pending,
+        TaskStatus::Running | TaskStatus::Stopping => proto::TaskStatus::Running,
+        TaskStatus::Success => proto::TaskStatus::Success,
+        TaskStatus::Error | TaskStatus::Rejected => proto::TaskStatus::Failed,
+        TaskStatus::Stopped | TaskStatus::NotExecuted => proto::TaskStatus::Stopped,
     }
+}
+
+/// Информация о задаче для ответа
+pub fn task_info(task: &Task, output: String) -> proto::TaskInfo {
+    proto::TaskInfo {
+        id: task.id.into(),
+        template_id: task.template_id.into(),
+        project_id: task.project_id.into(),
+        status: proto_status(task.status) as i32,
+        output,
+        exit_code: match task.status {
+            TaskStatus::Error => 1,
+            _ => 0,
+        },
+        commit_hash: task.commit_hash.clone(),
+        created_at: task.created.timestamp(),
+        started_at: task.start.map(|t| t.timestamp()),
+        ended_at: task.end.map(|t| t.timestamp()),
+    }
+}
+
+fn join_output(lines: &[TaskOutput]) -> String {
+    lines
+        .iter()
+        .map(|l| l.output.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    pub fn address(&self) -> SocketAddr {
-        self.config.address
+/// Окно `offset`/`limit` (`limit <= 0` — без ограничения)
+fn paginate<T>(items: Vec<T>, offset: i32, limit: i32) -> Vec<T> {
+    let items = items.into_iter().skip(offset.max(0) as usize);
+    if limit > 0 {
+        items.take(limit as usize).collect()
+    } else {
+        items.collect()
     }
 }
 
-/// Заглушки сервисов
-pub struct TaskServiceImpl;
+// ============================================================================
+// TaskService
+// ============================================================================
+
+/// Реализация `semaphore.TaskService`
+pub struct TaskServiceImpl {
+    store: SharedStore,
+    token_blacklist: TokenBlacklist,
+}
+
 impl TaskServiceImpl {
-    pub fn new() -> Self {
-        Self
+    pub fn new(store: SharedStore) -> Self {
+        Self {
+            store,
+            token_blacklist: TokenBlacklist::new(),
+        }
+    }
+
+    /// Общий с HTTP API список отозванных JWT
+    pub fn with_token_blacklist(mut self, token_blacklist: TokenBlacklist) -> Self {
+        self.token_blacklist = token_blacklist;
+        self
     }
 }
-impl Default for TaskServiceImpl {
-    fn default() -> Self {
-        Self::new()
+
+#[tonic::async_trait]
+impl TaskService for TaskServiceImpl {
+    type StreamTaskStatusStream = ReceiverStream<Result<proto::TaskStatusUpdate, Status>>;
+
+    async fn run_task(
+        &self,
+        request: Request<proto::RunTaskRequest>,
+    ) -> Result<Response<proto::RunTaskResponse>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let req = request.into_inner();
+        let project_id = to_id(req.project_id, "project_id")?;
+        let template_id = to_id(req.template_id, "template_id")?;
+        require_permission(
+            &self.store,
+            &user,
+            project_id,
+            template_id,
+            Permission::RunTasks,
+        )
+        .await?;
+
+        let template = self
+            .store
+            .get_template(project_id, template_id)
+            .await
+            .map_err(status_from_error)?;
+
+        // Аргументы передаются задаче как extra vars
+        let environment = (!req.arguments.is_empty()).then(|| {
+            let vars: HashMap<&str, &str> = req
+                .arguments
+                .iter()
+                .map(|a| (a.name.as_str(), a.value.as_str()))
+                .collect();
+            serde_json::to_string(&vars).unwrap_or_default()
+        });
+
+        let task = Task {
+            environment,
+            user_id: Some(user.user_id),
+            commit_hash: req.commit_hash.filter(|h| !h.is_empty()),
+            ..Task::new_waiting(template.id, project_id)
+        };
+        let created = self
+            .store
+            .create_task(task)
+            .await
+            .map_err(status_from_error)?;
+        info!(
+            "gRPC: task {} queued for template {}",
+            created.id, template.id
+        );
+
+        crate::services::task_execution::wake_dispatcher();
+
+        Ok(Response::new(proto::RunTaskResponse {
+            task_id: created.id.into(),
+            status: proto::TaskStatus::Pending as i32,
+            message: "Task queued".to_string(),
+        }))
     }
+
+    async fn stop_task(
+        &self,
+        request: Request<proto::StopTaskRequest>,
+    ) -> Result<Response<proto::StopTaskResponse>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let req = request.into_inner();
+        let task = find_task(&self.store, req.task_id).await?;
+        require_permission(
+            &self.store,
+            &user,
+            task.project_id,
+            task.template_id,
+            Permission::RunTasks,
+        )
+        .await?;
+
+        // Запущенная задача переводится в Stopping — исполнитель сам остановит
+        // процесс и выставит Stopped; ожидающая останавливается сразу
+        let status = match task.status {
+            TaskStatus::Running | TaskStatus::Starting => TaskStatus::Stopping,
+            status if status.is_finished() => {
+                return Ok(Response::new(proto::StopTaskResponse {
+                    success: false,
+                    message: format!("Task already finished with status {status}"),
+                }));
+            }
+            _ => TaskStatus::Stopped,
+        };
+        self.store
+            .update_task_status(task.project_id, task.id, status)
+            .await
+            .map_err(status_from_error)?;
+        if !req.reason.is_empty() {
+            info!("gRPC: task {} stop requested: {}", task.id, req.reason);
+        }
+
+        Ok(Response::new(proto::StopTaskResponse {
+            success: true,
+            message: format!("Task status set to {status}"),
+        }))
+    }
+
+    async fn get_task_status(
+        &self,
+        request: Request<proto::GetTaskStatusRequest>,
+    ) -> Result<Response<proto::TaskInfo>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let task = find_task(&self.store, request.get_ref().task_id).await?;
+        authorize_project(&self.store, &user, task.project_id).await?;
+
+        let output = self
+            .store
+            .get_task_outputs(task.id)
+            .await
+            .map_err(status_from_error)?;
+        Ok(Response::new(task_info(&task, join_output(&output))))
+    }
+
+    async fn list_project_tasks(
+        &self,
+        request: Request<proto::ListProjectTasksRequest>,
+    ) -> Result<Response<proto::ListProjectTasksResponse>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let req = request.into_inner();
+        let project_id = to_id(req.project_id, "project_id")?;
+        authorize_project(&self.store, &user, project_id).await?;
+
+        let filter = req.status_filter;
+        let tasks: Vec<Task> = self
+            .store
+            .get_tasks(project_id, None)
+            .await
+            .map_err(status_from_error)?
+            .into_iter()
+            .map(|t| t.task)
+            .filter(|t| filter.is_none_or(|s| proto_status(t.status) as i32 == s))
+            .collect();
+        let total = tasks.len() as i32;
+
+        Ok(Response::new(proto::ListProjectTasksResponse {
+            tasks: paginate(tasks, req.offset, req.limit)
+                .iter()
+                .map(|t| task_info(t, String::new()))
+                .collect(),
+            total,
+        }))
+    }
+
+    async fn stream_task_status(
+        &self,
+        request: Request<proto::StreamTaskStatusRequest>,
+    ) -> Result<Response<Self::StreamTaskStatusStream>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let task = find_task(&self.store, request.get_ref().task_id).await?;
+        authorize_project(&self.store, &user, task.project_id).await?;
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(stream_task_updates(self.store.clone(), task, tx));
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// Шлёт новые строки лога и смены статуса задачи, пока она не завершится
+async fn stream_task_updates(
+    store: SharedStore,
+    mut task: Task,
+    tx: mpsc::Sender<Result<proto::TaskStatusUpdate, Status>>,
+) {
+    let mut sent_lines = 0;
+    let mut sent_status = None;
+    loop {
+        let output = match store.get_task_outputs(task.id).await {
+            Ok(output) => output,
+            Err(e) => {
+                let _ = tx.send(Err(status_from_error(e))).await;
+                return;
+            }
+        };
+
+        let status = proto_status(task.status) as i32;
+        let mut updates: Vec<proto::TaskStatusUpdate> = output
+            .iter()
+            .skip(sent_lines)
+            .map(|line| proto::TaskStatusUpdate {
+                task_id: task.id.into(),
+                status,
+                output: line.output.clone(),
+                timestamp: line.time.timestamp(),
+            })
+            .collect();
+        sent_lines = output.len();
+        if updates.is_empty() && sent_status != Some(status) {
+            updates.push(proto::TaskStatusUpdate {
+                task_id: task.id.into(),
+                status,
+                output: String::new(),
+                timestamp: Utc::now().timestamp(),
+            });
+        }
+        sent_status = Some(status);
+
+        for update in updates {
+            if tx.send(Ok(update)).await.is_err() {
+                return;
+            }
+        }
+        if task.status.is_finished() {
+            return;
+        }
+
+        tokio::time::sleep(STREAM_POLL_INTERVAL).await;
+        task = match store.get_task(task.project_id, task.id).await {
+            Ok(task) => task,
+            Err(e) => {
+                let _ = tx.send(Err(status_from_error(e))).await;
+                return;
+            }
+        };
+    }
+}
+
+// ============================================================================
+// ProjectService
+// ============================================================================
+
+/// Реализация `semaphore.ProjectService`
+pub struct ProjectServiceImpl {
+    store: SharedStore,
+    cache: Option<Arc<CacheService>>,
+    token_blacklist: TokenBlacklist,
 }
 
-pub struct ProjectServiceImpl;
 impl ProjectServiceImpl {
-    pub fn new() -> Self {
-        Self
+    pub fn new(store: SharedStore, cache: Option<Arc<CacheService>>) -> Self {
+        Self {
+            store,
+            cache,
+            token_blacklist: TokenBlacklist::new(),
+        }
+    }
+
+    /// Общий с HTTP API список отозванных JWT
+    pub fn with_token_blacklist(mut self, token_blacklist: TokenBlacklist) -> Self {
+        self.token_blacklist = token_blacklist;
+        self
+    }
+
+    async fn project_info(&self, project: &crate::models::Project) -> proto::ProjectInfo {
+        let tasks_count = self
+            .store
+            .get_tasks(project.id, None)
+            .await
+            .map(|t| t.len() as i32)
+            .unwrap_or_default();
+        proto::ProjectInfo {
+            id: project.id.into(),
+            name: project.name.clone(),
+            description: String::new(),
+            repository_url: String::new(),
+            branch: String::new(),
+            tasks_count,
+            created_at: project.created.timestamp(),
+        }
     }
 }
-impl Default for ProjectServiceImpl {
-    fn default() -> Self {
-        Self::new()
+
+#[tonic::async_trait]
+impl ProjectService for ProjectServiceImpl {
+    async fn get_project(
+        &self,
+        request: Request<proto::GetProjectRequest>,
+    ) -> Result<Response<proto::ProjectInfo>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let project_id = to_id(request.get_ref().project_id, "project_id")?;
+        authorize_project(&self.store, &user, project_id).await?;
+
+        let project = self
+            .store
+            .get_project(project_id)
+            .await
+            .map_err(status_from_error)?;
+        Ok(Response::new(self.project_info(&project).await))
+    }
+
+    async fn list_projects(
+        &self,
+        request: Request<proto::ListProjectsRequest>,
+    ) -> Result<Response<proto::ListProjectsResponse>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let req = request.into_inner();
+
+        let mut projects = self
+            .store
+            .get_projects((!user.admin).then_some(user.user_id))
+            .await
+            .map_err(status_from_error)?;
+        // API-токен может быть ограничен проектами
+        let caller = user.caller();
+        projects.retain(|p| caller.allows_project(p.id));
+        projects.sort_by_key(|p| p.id);
+        let total = projects.len() as i32;
+
+        let mut infos = Vec::new();
+        for project in paginate(projects, req.offset, req.limit) {
+            infos.push(self.project_info(&project).await);
+        }
+        Ok(Response::new(proto::ListProjectsResponse {
+            projects: infos,
+            total,
+        }))
+    }
+
+    async fn invalidate_project_cache(
+        &self,
+        request: Request<proto::InvalidateProjectCacheRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let req = request.into_inner();
+        let project_id = to_id(req.project_id, "project_id")?;
+        check_project_write(&user, project_id)?;
+        authorize_project(&self.store, &user, project_id).await?;
+
+        if let Some(cache) = &self.cache {
+            cache
+                .invalidate_project(req.project_id)
+                .await
+                .map_err(status_from_error)?;
+            if req.invalidate_tasks {
+                cache
+                    .invalidate_project_tasks(req.project_id)
+                    .await
+                    .map_err(status_from_error)?;
+            }
+        }
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn get_project_statistics(
+        &self,
+        request: Request<proto::GetProjectStatisticsRequest>,
+    ) -> Result<Response<proto::ProjectStatistics>, Status> {
+        let user = authenticate(&self.store, &self.token_blacklist, &request).await?;
+        let project_id = to_id(request.get_ref().project_id, "project_id")?;
+        authorize_project(&self.store, &user, project_id).await?;
+
+        let tasks: Vec<Task> = self
+            .store
+            .get_tasks(project_id, None)
+            .await
+            .map_err(status_from_error)?
+            .into_iter()
+            .map(|t| t.task)
+            .collect();
+        Ok(Response::new(project_statistics(project_id, &tasks)))
+    }
+}
+
+/// Статистика задач проекта
+pub fn project_statistics(project_id: i32, tasks: &[Task]) -> proto::ProjectStatistics {
+    let count = |f: fn(&TaskStatus) -> bool| tasks.iter().filter(|t| f(&t.status)).count() as i32;
+    let durations: Vec<i64> = tasks
+        .iter()
+        .filter_map(|t| Some((t.end? - t.start?).num_seconds()))
+        .collect();
+
+    proto::ProjectStatistics {
+        project_id: project_id.into(),
+        total_tasks: tasks.len() as i32,
+        success_tasks: count(|s| *s == TaskStatus::Success),
+        failed_tasks: count(|s| *s == TaskStatus::Error),
+        running_tasks: count(|s| matches!(s, TaskStatus::Running | TaskStatus::Stopping)),
+        avg_duration_seconds: if durations.is_empty() {
+            0.0
+        } else {
+            durations.iter().sum::<i64>() as f64 / durations.len() as f64
+        },
+        last_task_at: tasks
+            .iter()
+            .map(|t| t.created.timestamp())
+            .max()
+            .unwrap_or_default(),
     }
 }
 
-pub struct RunnerServiceImpl;
+// ============================================================================
+// RunnerService
+// ============================================================================
+
+/// Реализация `semaphore.RunnerService` — альтернатива HTTP-поллингу `/api/internal/runners`
+pub struct RunnerServiceImpl {
+    store: SharedStore,
+}
+
 impl RunnerServiceImpl {
-    pub fn new() -> Self {
-        Self
+    pub fn new(store: SharedStore) -> Self {
+        Self { store }
     }
 }
-impl Default for RunnerServiceImpl {
-    fn default() -> Self {
-        Self::new()
+
+#[tonic::async_trait]
+impl RunnerService for RunnerServiceImpl {
+    async fn register_runner(
+        &self,
+        request: Request<proto::RegisterRunnerRequest>,
+    ) -> Result<Response<proto::RegisterRunnerResponse>, Status> {
+        let req = request.into_inner();
+        let Ok(mut runner) = self.store.find_runner_by_token(&req.runner_token).await else {
+            return Err(Status::unauthenticated("Invalid runner token"));
+        };
+        if !runner.active {
+            return Ok(Response::new(proto::RegisterRunnerResponse {
+                runner_id: runner.id.to_string(),
+                success: false,
+                message: "Runner is disabled".to_string(),
+            }));
+        }
+
+        if !req.labels.is_empty() {
+            runner.tag = Some(req.labels.join(","));
+        }
+        runner.touched = Some(Utc::now());
+        self.store
+            .update_runner(runner.clone())
+            .await
+            .map_err(status_from_error)?;
+        self.store
+            .touch_runner(runner.id)
+            .await
+            .map_err(status_from_error)?;
+        info!(
+            "gRPC: runner {} registered from {}",
+            runner.id, req.hostname
+        );
+
+        Ok(Response::new(proto::RegisterRunnerResponse {
+            runner_id: runner.id.to_string(),
+            success: true,
+            message: "Runner registered".to_string(),
+        }))
+    }
+
+    async fn send_heartbeat(
+        &self,
+        request: Request<proto::SendHeartbeatRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let runner =
+            authenticate_runner(&self.store, &request, &request.get_ref().runner_id).await?;
+        self.store
+            .touch_runner(runner.id)
+            .await
+            .map_err(status_from_error)?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn get_pending_tasks(
+        &self,
+        request: Request<proto::GetPendingTasksRequest>,
+    ) -> Result<Response<proto::GetPendingTasksResponse>, Status> {
+        let runner =
+            authenticate_runner(&self.store, &request, &request.get_ref().runner_id).await?;
+        let _ = self.store.touch_runner(runner.id).await;
+
+        let max_tasks = request.get_ref().max_tasks.max(1);
+        let mut tasks = Vec::new();
+        for _ in 0..max_tasks {
+            match remote_runners::claim_next_task(self.store.as_ref(), &runner)
+                .await
+                .map_err(status_from_error)?
+            {
+                Some(task) => tasks.push(task_info(&task, String::new())),
+                None => break,
+            }
+        }
+        Ok(Response::new(proto::GetPendingTasksResponse { tasks }))
+    }
+
+    async fn report_task_result(
+        &self,
+        request: Request<proto::ReportTaskResultRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let token = bearer_token(&request)?;
+        let runner = self
+            .store
+            .find_runner_by_token(&token)
+            .await
+            .map_err(|_| Status::unauthenticated("Invalid runner token"))?;
+        let req = request.into_inner();
+
+        let mut task = find_task(&self.store, req.task_id).await?;
+        if runner.project_id.is_some_and(|p| p != task.project_id) {
+            return Err(Status::permission_denied("Task belongs to another project"));
+        }
+        let owned = self
+            .store
+            .get_runner_task_ids(runner.id)
+            .await
+            .map_err(status_from_error)?;
+        if !owned.contains(&task.id) {
+            return Err(Status::permission_denied(
+                "Task is not assigned to this runner",
+            ));
+        }
+        task.status = match proto::TaskStatus::try_from(req.status) {
+            Ok(proto::TaskStatus::Success) => TaskStatus::Success,
+            Ok(proto::TaskStatus::Failed) => TaskStatus::Error,
+            Ok(proto::TaskStatus::Stopped) => TaskStatus::Stopped,
+            _ => {
+                return Err(Status::invalid_argument(
+                    "status must be SUCCESS, FAILED or STOPPED",
+                ));
+            }
+        };
+
+        for line in req.output.lines() {
+            self.store
+                .create_task_output(TaskOutput {
+                    id: 0,
+                    task_id: task.id,
+                    project_id: task.project_id,
+                    time: Utc::now(),
+                    output: line.to_string(),
+                    stage_id: None,
+                })
+                .await
+                .map_err(status_from_error)?;
+        }
+
+        task.end = Some(Utc::now());
+        if task.start.is_none() && req.duration_seconds > 0 {
+            task.start = task
+                .end
+                .map(|end| end - chrono::Duration::seconds(req.duration_seconds));
+        }
+        if task.status == TaskStatus::Error && req.exit_code != 0 {
+            task.message = Some(format!("Exit code {}", req.exit_code));
+        }
+        self.store
+            .update_task(task)
+            .await
+            .map_err(status_from_error)?;
+        let _ = self.store.touch_runner(runner.id).await;
+        Ok(Response::new(proto::Empty {}))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::auth_local::LocalAuthService;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{
+        ProjectAccessManager, ProjectStore, RunnerManager, TaskManager, TokenManager, UserManager,
+    };
+    use crate::models::{APIToken, Project, TokenScope, User};
+    use tokio_stream::StreamExt;
+
+    fn admin_token() -> String {
+        user_token(1, true)
+    }
+
+    fn user_token(id: i32, admin: bool) -> String {
+        let store: SharedStore = Arc::new(MockStore::new());
+        LocalAuthService::new(StoreWrapper::new(store))
+            .generate_token(&test_user(id, admin))
+            .unwrap()
+            .token
+    }
+
+    fn test_user(id: i32, admin: bool) -> User {
+        User {
+            id,
+            created: Utc::now(),
+            username: format!("user{id}"),
+            name: format!("User {id}"),
+            email: format!("user{id}@example.com"),
+            password: String::new(),
+            admin,
+            external: false,
+            alert: false,
+            pro: false,
+            totp: None,
+            email_otp: None,
+        }
+    }
+
+    fn authed<T>(message: T, token: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .metadata_mut()
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+        request
+    }
+
+    fn task(id: i32, status: TaskStatus) -> Task {
+        Task {
+            id,
+            project_id: 1,
+            template_id: 1,
+            status,
+            ..Default::default()
+        }
+    }
+
+    fn runner(id: i32, token: &str) -> Runner {
+        Runner {
+            id,
+            project_id: None,
+            token: token.to_string(),
+            name: format!("runner-{id}"),
+            active: true,
+            last_active: None,
+            webhook: None,
+            max_parallel_tasks: None,
+            tag: None,
+            cleaning_requested: None,
+            touched: None,
+            created: None,
+            capabilities: None,
+        }
+    }
 
     #[test]
     fn test_grpc_server_config() {
@@ -112,9 +890,370 @@ mod tests {
     }
 
     #[test]
-    fn test_service_creation() {
-        let _task_service = TaskServiceImpl::new();
-        let _project_service = ProjectServiceImpl::new();
-        let _runner_service = RunnerServiceImpl::new();
+    fn test_proto_status_mapping() {
+        assert_eq!(
+            proto_status(TaskStatus::Waiting),
+            proto::TaskStatus::Pending
+        );
+        assert_eq!(
+            proto_status(TaskStatus::Stopping),
+            proto::TaskStatus::Running
+        );
+        assert_eq!(proto_status(TaskStatus::Error), proto::TaskStatus::Failed);
+        assert_eq!(
+            proto_status(TaskStatus::Stopped),
+            proto::TaskStatus::Stopped
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_service_requires_token() {
+        let service = TaskServiceImpl::new(Arc::new(MockStore::new()));
+        let err = service
+            .get_task_status(Request::new(proto::GetTaskStatusRequest { task_id: 1 }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        let err = service
+            .get_task_status(authed(proto::GetTaskStatusRequest { task_id: 1 }, "bogus"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_guest_cannot_run_or_stop_tasks() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_task(task(1, TaskStatus::Running))
+            .await
+            .unwrap();
+        store.set_project_user_role(1, 5, "guest").await.unwrap();
+        let service = TaskServiceImpl::new(store.clone());
+        let token = user_token(5, false);
+
+        let err = service
+            .run_task(authed(
+                proto::RunTaskRequest {
+                    project_id: 1,
+                    template_id: 1,
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        let err = service
+            .stop_task(authed(
+                proto::StopTaskRequest {
+                    task_id: 1,
+                    reason: String::new(),
+                },
+                &token,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        assert_eq!(
+            store.get_task(1, 1).await.unwrap().status,
+            TaskStatus::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoked_jwt_is_rejected() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_task(task(1, TaskStatus::Success))
+            .await
+            .unwrap();
+        let blacklist = TokenBlacklist::new();
+        let service = TaskServiceImpl::new(store.clone()).with_token_blacklist(blacklist.clone());
+        let token = admin_token();
+        let request = || authed(proto::GetTaskStatusRequest { task_id: 1 }, &token);
+        service.get_task_status(request()).await.unwrap();
+
+        let claims = LocalAuthService::new(StoreWrapper::new(store.clone()))
+            .verify_token(&token)
+            .unwrap();
+        blacklist.revoke(&claims.jti, claims.exp);
+        let err = service.get_task_status(request()).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_api_token_authenticates_with_its_scopes() {
+        let store = Arc::new(MockStore::new());
+        store.create_user(test_user(5, false), "").await.unwrap();
+        store.set_project_user_role(1, 5, "owner").await.unwrap();
+        store.seed_template(crate::models::Template {
+            id: 1,
+            project_id: 1,
+            ..Default::default()
+        });
+        for (value, scopes, project_ids) in [
+            ("read-only", vec![TokenScope::ReadOnly], vec![]),
+            ("other-project", vec![], vec![2]),
+            ("runner", vec![TokenScope::RunTasks], vec![1]),
+        ] {
+            store
+                .create_api_token(APIToken {
+                    user_id: 5,
+                    token: value.to_string(),
+                    scopes,
+                    project_ids,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        let service = TaskServiceImpl::new(store.clone());
+        let run = |token: &str| {
+            authed(
+                proto::RunTaskRequest {
+                    project_id: 1,
+                    template_id: 1,
+                    ..Default::default()
+                },
+                token,
+            )
+        };
+        let list = |token: &str| {
+            authed(
+                proto::ListProjectTasksRequest {
+                    project_id: 1,
+                    ..Default::default()
+                },
+                token,
+            )
+        };
+
+        service.list_project_tasks(list("read-only")).await.unwrap();
+        let err = service.run_task(run("read-only")).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        let err = service
+            .list_project_tasks(list("other-project"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        // Задача ставится в очередь пула, а не запускается в обход него
+        let response = service.run_task(run("runner")).await.unwrap().into_inner();
+        let created = store.get_task(1, response.task_id as i32).await.unwrap();
+        assert_eq!(created.status, TaskStatus::Waiting);
+        assert_eq!(created.user_id, Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_get_task_status_returns_output() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_task(task(1, TaskStatus::Success))
+            .await
+            .unwrap();
+        for line in ["first", "second"] {
+            store
+                .create_task_output(TaskOutput {
+                    id: 0,
+                    task_id: 1,
+                    project_id: 1,
+                    time: Utc::now(),
+                    output: line.to_string(),
+                    stage_id: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let service = TaskServiceImpl::new(store);
+        let info = service
+            .get_task_status(authed(
+                proto::GetTaskStatusRequest { task_id: 1 },
+                &admin_token(),
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(info.status, proto::TaskStatus::Success as i32);
+        assert_eq!(info.output, "first\nsecond");
+    }
+
+    #[tokio::test]
+    async fn test_stream_task_status_ends_with_finished_task() {
+        let store = Arc::new(MockStore::new());
+        store.create_task(task(1, TaskStatus::Error)).await.unwrap();
+        store
+            .create_task_output(TaskOutput {
+                id: 0,
+                task_id: 1,
+                project_id: 1,
+                time: Utc::now(),
+                output: "fatal: boom".to_string(),
+                stage_id: None,
+            })
+            .await
+            .unwrap();
+
+        let service = TaskServiceImpl::new(store);
+        let stream = service
+            .stream_task_status(authed(
+                proto::StreamTaskStatusRequest { task_id: 1 },
+                &admin_token(),
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let updates: Vec<_> = stream.collect().await;
+        assert_eq!(updates.len(), 1);
+        let update = updates[0].as_ref().unwrap();
+        assert_eq!(update.output, "fatal: boom");
+        assert_eq!(update.status, proto::TaskStatus::Failed as i32);
+    }
+
+    #[tokio::test]
+    async fn test_list_project_tasks_filters_by_status() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_project(Project::new("p".to_string()))
+            .await
+            .unwrap();
+        store
+            .create_task(task(1, TaskStatus::Success))
+            .await
+            .unwrap();
+        store.create_task(task(2, TaskStatus::Error)).await.unwrap();
+
+        let service = TaskServiceImpl::new(store);
+        let resp = service
+            .list_project_tasks(authed(
+                proto::ListProjectTasksRequest {
+                    project_id: 1,
+                    status_filter: Some(proto::TaskStatus::Failed as i32),
+                    limit: 0,
+                    offset: 0,
+                },
+                &admin_token(),
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(resp.total, 1);
+        assert_eq!(resp.tasks[0].id, 2);
+    }
+
+    #[test]
+    fn test_project_statistics() {
+        let now = Utc::now();
+        let mut done = task(1, TaskStatus::Success);
+        done.start = Some(now - chrono::Duration::seconds(10));
+        done.end = Some(now);
+        let tasks = vec![
+            done,
+            task(2, TaskStatus::Error),
+            task(3, TaskStatus::Running),
+        ];
+        let stats = project_statistics(1, &tasks);
+        assert_eq!(stats.total_tasks, 3);
+        assert_eq!(stats.success_tasks, 1);
+        assert_eq!(stats.failed_tasks, 1);
+        assert_eq!(stats.running_tasks, 1);
+        assert_eq!(stats.avg_duration_seconds, 10.0);
+    }
+
+    #[tokio::test]
+    async fn test_runner_claims_and_reports_task() {
+        let store = Arc::new(MockStore::new());
+        store.seed_template(crate::models::Template {
+            id: 1,
+            project_id: 1,
+            ..Default::default()
+        });
+        store.create_runner(runner(1, "secret")).await.unwrap();
+        store
+            .create_task(task(1, TaskStatus::Waiting))
+            .await
+            .unwrap();
+        let service = RunnerServiceImpl::new(store.clone());
+
+        let err = service
+            .register_runner(Request::new(proto::RegisterRunnerRequest {
+                runner_token: "wrong".to_string(),
+                hostname: "host".to_string(),
+                labels: vec![],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        let registered = service
+            .register_runner(Request::new(proto::RegisterRunnerRequest {
+                runner_token: "secret".to_string(),
+                hostname: "host".to_string(),
+                labels: vec!["linux".to_string()],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(registered.success);
+        assert_eq!(registered.runner_id, "1");
+
+        let pending = service
+            .get_pending_tasks(authed(
+                proto::GetPendingTasksRequest {
+                    runner_id: "1".to_string(),
+                    labels: vec![],
+                    max_tasks: 5,
+                },
+                "secret",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(pending.tasks.len(), 1);
+        assert_eq!(
+            store.get_task(1, 1).await.unwrap().status,
+            TaskStatus::Running
+        );
+
+        service
+            .report_task_result(authed(
+                proto::ReportTaskResultRequest {
+                    task_id: 1,
+                    status: proto::TaskStatus::Success as i32,
+                    output: "line 1\nline 2".to_string(),
+                    exit_code: 0,
+                    duration_seconds: 3,
+                },
+                "secret",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_task(1, 1).await.unwrap().status,
+            TaskStatus::Success
+        );
+        assert_eq!(store.get_task_outputs(1).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_runner_heartbeat_rejects_foreign_runner_id() {
+        let store = Arc::new(MockStore::new());
+        store.create_runner(runner(1, "secret")).await.unwrap();
+        let service = RunnerServiceImpl::new(store);
+
+        let err = service
+            .send_heartbeat(authed(
+                proto::SendHeartbeatRequest {
+                    runner_id: "2".to_string(),
+                },
+                "secret",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
     }
 }
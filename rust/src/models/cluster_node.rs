@@ -0,0 +1,22 @@
+//! Cluster Node — узлы кластера серверов
+//!
+//! Узлы отмечаются в таблице `cluster_node`; один из них становится лидером
+//! и выполняет фоновые службы, которые должны работать в единственном экземпляре.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Текущий лидер кластера
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClusterLeader {
+    /// ID узла-лидера (см. `task_reaper::node_id`)
+    pub node_id: String,
+
+    /// Имя хоста узла, если известно
+    #[serde(default)]
+    pub hostname: Option<String>,
+
+    /// Последняя отметка узла в `cluster_node`
+    #[serde(default)]
+    pub last_seen: Option<DateTime<Utc>>,
+}
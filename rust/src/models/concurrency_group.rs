@@ -0,0 +1,100 @@
+//! Concurrency Group — именованные группы взаимного исключения задач
+//!
+//! Шаблоны и окружения деплоя могут входить в группу (например, "prod-db");
+//! одновременно в группе выполняется не больше `max_parallel` задач.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Лимит по умолчанию для группы без явной настройки (мьютекс)
+pub const DEFAULT_GROUP_LIMIT: i32 = 1;
+
+/// Группа параллелизма проекта
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConcurrencyGroup {
+    pub id: i32,
+    pub project_id: i32,
+
+    /// Имя группы (уникально в проекте)
+    pub name: String,
+
+    /// Максимум одновременно выполняемых задач группы
+    pub max_parallel: i32,
+
+    pub created: DateTime<Utc>,
+}
+
+/// Payload для создания/обновления группы
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcurrencyGroupUpsert {
+    pub name: String,
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: i32,
+}
+
+fn default_max_parallel() -> i32 {
+    DEFAULT_GROUP_LIMIT
+}
+
+/// Кто забирает задачу из очереди
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskClaimer {
+    /// Узел сервера (`task.node_id`)
+    Node(String),
+    /// Удалённый раннер (`task.runner_id`)
+    Runner(i32),
+}
+
+/// Слот, который задача занимает в группе при запуске
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotRequest {
+    /// Ключ группы (уникален в кластере)
+    pub group_key: String,
+    /// Максимум одновременно выполняемых задач группы
+    pub limit: i32,
+}
+
+/// Задача, занимающая слот группы
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConcurrencySlotHolder {
+    pub group_key: String,
+    pub task_id: i32,
+}
+
+/// Итог захвата задачи вместе со слотами её групп
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlotClaim {
+    /// Слоты заняты, задача переведена в `running`
+    Claimed,
+    /// Группа заполнена: её ключ и задача, занимающая слот
+    Blocked { group_key: String, task_id: i32 },
+    /// Задача уже не ждёт — её забрал другой узел или раннер
+    NotWaiting,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrency_group_upsert_default_limit() {
+        let payload: ConcurrencyGroupUpsert =
+            serde_json::from_str(r#"{"name":"prod-db"}"#).unwrap();
+        assert_eq!(payload.name, "prod-db");
+        assert_eq!(payload.max_parallel, DEFAULT_GROUP_LIMIT);
+    }
+
+    #[test]
+    fn test_concurrency_group_serialization() {
+        let group = ConcurrencyGroup {
+            id: 1,
+            project_id: 2,
+            name: "prod-db".to_string(),
+            max_parallel: 3,
+            created: Utc::now(),
+        };
+        let json = serde_json::to_string(&group).unwrap();
+        assert!(json.contains("\"name\":\"prod-db\""));
+        assert!(json.contains("\"max_parallel\":3"));
+    }
+}
@@ -78,6 +78,10 @@ pub struct DeploymentEnvironment {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_deployed_by: Option<i32>,
 
+    /// Группа параллелизма для деплоев в окружение
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency_group: Option<String>,
+
     pub created: DateTime<Utc>,
     pub updated: DateTime<Utc>,
 }
@@ -92,6 +96,8 @@ pub struct DeploymentEnvironmentCreate {
     pub tier: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub template_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency_group: Option<String>,
 }
 
 fn default_tier() -> String {
@@ -111,6 +117,8 @@ pub struct DeploymentEnvironmentUpdate {
     pub status: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub template_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency_group: Option<String>,
 }
 
 /// История деплоев для одного окружения
@@ -168,6 +176,7 @@ mod tests {
             last_deployed_by: Some(1),
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&env).unwrap();
         assert!(json.contains("\"name\":\"production\""));
@@ -182,6 +191,7 @@ mod tests {
             url: None,
             tier: String::new(),
             template_id: None,
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&create).unwrap();
         // tier должен быть по умолчанию "other" при сериализации
@@ -196,6 +206,7 @@ mod tests {
             tier: None,
             status: None,
             template_id: None,
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&update).unwrap();
         assert!(json.contains("\"name\":\"new-name\""));
@@ -237,6 +248,7 @@ mod tests {
             last_deployed_by: Some(1),
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         let cloned = env.clone();
         assert_eq!(cloned.name, env.name);
@@ -268,6 +280,7 @@ mod tests {
             url: Some("https://prod.example.com".to_string()),
             tier: "production".to_string(),
             template_id: Some(10),
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&create).unwrap();
         assert!(json.contains("\"tier\":\"production\""));
@@ -330,6 +343,7 @@ mod tests {
                 last_deployed_by: None,
                 created: Utc::now(),
                 updated: Utc::now(),
+                concurrency_group: None,
             };
             let json = serde_json::to_string(&env).unwrap();
             assert!(json.contains(&format!("\"status\":\"{}\"", status_str)));
@@ -351,6 +365,7 @@ mod tests {
             last_deployed_by: None,
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&env).unwrap();
         let restored: DeploymentEnvironment = serde_json::from_str(&json).unwrap();
@@ -372,6 +387,7 @@ mod tests {
             last_deployed_by: None,
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         let cloned = env.clone();
         env.name = "Modified".to_string();
@@ -412,6 +428,7 @@ mod tests {
             last_deployed_by: None,
             created: Utc::now(),
             updated: Utc::now(),
+            concurrency_group: None,
         };
         let json = serde_json::to_string(&env).unwrap();
         assert!(!json.contains("\"url\":"));
@@ -0,0 +1,142 @@
+//! Outbox событий и их доставки
+//!
+//! События жизненного цикла задач сначала записываются в таблицу
+//! `event_outbox`, затем диспетчер раскладывает каждое событие на доставки —
+//! по одной на подходящий webhook или политику уведомлений. Состояние
+//! доставок хранится в БД, поэтому повторы переживают перезапуск сервера.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Событие в outbox
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutboxEvent {
+    pub id: i64,
+    pub project_id: i32,
+    /// Тип события: `task_started`, `task_success`, `task_failed`, `task_stopped`
+    pub event_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_id: Option<i32>,
+    /// Снимок данных события на момент записи
+    pub payload: serde_json::Value,
+    pub created: DateTime<Utc>,
+}
+
+/// Получатель доставки
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryTarget {
+    Webhook,
+    NotificationPolicy,
+}
+
+impl DeliveryTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryTarget::Webhook => "webhook",
+            DeliveryTarget::NotificationPolicy => "notification_policy",
+        }
+    }
+}
+
+impl std::str::FromStr for DeliveryTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "webhook" => Ok(DeliveryTarget::Webhook),
+            "notification_policy" => Ok(DeliveryTarget::NotificationPolicy),
+            other => Err(format!("Unknown delivery target: {other}")),
+        }
+    }
+}
+
+/// Состояние доставки
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Ожидает отправки (в том числе повторной)
+    Pending,
+    Delivered,
+    /// Попытки исчерпаны; можно отправить заново через API
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "failed" => Ok(DeliveryStatus::Failed),
+            other => Err(format!("Unknown delivery status: {other}")),
+        }
+    }
+}
+
+/// Доставка события одному получателю
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventDelivery {
+    pub id: i64,
+    pub event_id: i64,
+    pub project_id: i32,
+    pub target: DeliveryTarget,
+    /// ID webhook или политики уведомлений
+    pub target_id: i64,
+    pub status: DeliveryStatus,
+    /// Сделанные попытки
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Время следующей попытки (для взятой в работу доставки — конец аренды)
+    pub next_attempt: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub created: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered: Option<DateTime<Utc>>,
+}
+
+/// Новая доставка при раскладке события
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewEventDelivery {
+    pub target: DeliveryTarget,
+    pub target_id: i64,
+    pub max_attempts: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delivery_enums_roundtrip() {
+        for target in [DeliveryTarget::Webhook, DeliveryTarget::NotificationPolicy] {
+            assert_eq!(target.as_str().parse::<DeliveryTarget>(), Ok(target));
+        }
+        for status in [
+            DeliveryStatus::Pending,
+            DeliveryStatus::Delivered,
+            DeliveryStatus::Failed,
+        ] {
+            assert_eq!(status.as_str().parse::<DeliveryStatus>(), Ok(status));
+            assert_eq!(
+                serde_json::to_string(&status).unwrap(),
+                format!("\"{}\"", status.as_str())
+            );
+        }
+        assert!("unknown".parse::<DeliveryStatus>().is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! Архив логов задач и политика хранения
+//!
+//! Логи завершённых задач переносятся из таблицы `task_output` в сжатые
+//! чанки (локальный каталог или S3-совместимое хранилище); запись архива
+//! указывает, где лежат чанки. Политика хранения проекта удаляет старые
+//! задачи вместе с их выводом.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Архивированный лог задачи
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskLogArchive {
+    pub task_id: i32,
+    pub project_id: i32,
+
+    /// Хранилище: `local` или `s3`
+    pub backend: String,
+
+    /// Префикс ключей чанков в хранилище
+    pub location: String,
+
+    /// Количество чанков
+    pub chunks: i32,
+
+    /// Количество строк вывода
+    pub lines: i32,
+
+    /// Суммарный размер сжатых чанков в байтах
+    pub bytes: i64,
+
+    pub archived: DateTime<Utc>,
+}
+
+/// Политика хранения задач проекта
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LogRetentionPolicy {
+    #[serde(default)]
+    pub project_id: i32,
+
+    /// Удалять задачи старше N дней
+    #[serde(default)]
+    pub max_age_days: Option<i32>,
+
+    /// Хранить только N последних запусков каждого шаблона
+    #[serde(default)]
+    pub keep_last_runs: Option<i32>,
+}
+
+impl LogRetentionPolicy {
+    /// Политика ничего не ограничивает
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age_days.is_none() && self.keep_last_runs.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_policy_deserialize_partial() {
+        let policy: LogRetentionPolicy = serde_json::from_str(r#"{"max_age_days":30}"#).unwrap();
+        assert_eq!(policy.max_age_days, Some(30));
+        assert!(policy.keep_last_runs.is_none());
+        assert!(!policy.is_unlimited());
+        assert!(LogRetentionPolicy::default().is_unlimited());
+    }
+}
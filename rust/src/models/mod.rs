@@ -36,6 +36,7 @@ pub mod role;
 pub mod runner;
 pub mod schedule;
 pub mod secret_storage;
+pub mod service_account;
 pub mod session;
 pub mod snapshot;
 pub mod task;
@@ -92,13 +93,14 @@ pub use project_invite::{ProjectInvite, ProjectInviteWithUser};
 pub use project_stats::ProjectStats;
 pub use project_user::ProjectUser;
 pub use repository::{Repository, RepositoryType};
-pub use role::Role;
-pub use runner::Runner;
+pub use role::{Permission, Role, RolePermissions};
+pub use runner::{Runner, RunnerCapabilities};
 pub use schedule::{Schedule, ScheduleWithTpl};
 pub use secret_storage::{SecretStorage, SecretStorageType};
+pub use service_account::{ServiceAccount, ServiceAccountCreate};
 pub use session::{Session, SessionVerificationMethod};
 pub use task::{
-    AnsibleTaskParams, DefaultTaskParams, Task, TaskOutput, TaskStage, TaskStageResult,
+    AnsibleTaskParams, DefaultTaskParams, Task, TaskHook, TaskOutput, TaskStage, TaskStageResult,
     TaskStageType, TaskStageWithResult, TaskWithTpl, TerraformTaskParams,
 };
 pub use task_params::{
@@ -111,15 +113,15 @@ pub use template::{
 };
 pub use template_vault::TemplateVault;
 pub use terraform_inventory::{Alias, TerraformInventoryAlias, TerraformInventoryState};
-pub use token::APIToken;
+pub use token::{APIToken, APITokenCreate, TokenScope};
 pub use totp_verification::TotpVerification;
 pub use user::{ProjectUserRole, User, UserEmailOtp, UserTotp, UserWithProjectRole};
 pub use view::View;
 pub use webhook::{CreateWebhook, TestWebhook, UpdateWebhook, Webhook, WebhookLog, WebhookType};
 pub use workflow::{
     EdgeCondition, Workflow, WorkflowCreate, WorkflowEdge, WorkflowEdgeCreate, WorkflowFull,
-    WorkflowNode, WorkflowNodeCreate, WorkflowNodeUpdate, WorkflowRun as WorkflowRunModel,
-    WorkflowUpdate,
+    WorkflowNode, WorkflowNodeCreate, WorkflowNodeInput, WorkflowNodeUpdate,
+    WorkflowRun as WorkflowRunModel, WorkflowRunCreate, WorkflowUpdate,
 };
 
 // Organization (Multi-Tenancy)
@@ -148,6 +150,23 @@ pub use deployment_environment::{
     DeploymentRecord,
 };
 
+pub mod concurrency_group;
+pub use concurrency_group::{
+    ConcurrencyGroup, ConcurrencyGroupUpsert, ConcurrencySlotHolder, SlotClaim, SlotRequest,
+    TaskClaimer,
+};
+
+pub mod cluster_node;
+pub use cluster_node::ClusterLeader;
+
+pub mod log_archive;
+pub use log_archive::{LogRetentionPolicy, TaskLogArchive};
+
+pub mod event_outbox;
+pub use event_outbox::{
+    DeliveryStatus, DeliveryTarget, EventDelivery, NewEventDelivery, OutboxEvent,
+};
+
 pub mod task_structured_output;
 pub use task_structured_output::{
     TaskOutputsMap, TaskStructuredOutput, TaskStructuredOutputBatch, TaskStructuredOutputCreate,
@@ -49,6 +49,12 @@ pub struct TerraformPlan {
     pub reviewed_at: Option<DateTime<Utc>>,
     pub reviewed_by: Option<i32>,
     pub review_comment: Option<String>,
+    /// Бинарный план (`plan -out`), применяемый после одобрения
+    #[serde(skip)]
+    pub plan_file: Option<Vec<u8>>,
+    /// Serial состояния, на котором построен план
+    #[serde(default)]
+    pub state_serial: Option<i64>,
 }
 
 /// Payload for approve/reject
@@ -100,6 +106,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let json = serde_json::to_string(&plan).unwrap();
         assert!(json.contains("\"task_id\":100"));
@@ -153,6 +161,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let cloned = plan.clone();
         assert_eq!(cloned.plan_output, plan.plan_output);
@@ -175,6 +185,8 @@ mod tests {
             reviewed_at: Some(Utc::now()),
             reviewed_by: Some(5),
             review_comment: Some("LGTM".to_string()),
+            plan_file: None,
+            state_serial: None,
         };
         let json = serde_json::to_string(&plan).unwrap();
         assert!(json.contains("\"status\":\"approved\""));
@@ -215,6 +227,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let debug_str = format!("{:?}", plan);
         assert!(debug_str.contains("TerraformPlan"));
@@ -242,6 +256,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let json = serde_json::to_string(&plan).unwrap();
         let restored: TerraformPlan = serde_json::from_str(&json).unwrap();
@@ -264,6 +280,8 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         };
         let cloned = plan.clone();
         plan.plan_output = "Modified".to_string();
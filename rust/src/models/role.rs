@@ -28,7 +28,57 @@ pub struct RolePermissions {
     pub manage_secret_storages: bool,
 }
 
+/// Отдельное разрешение проекта
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    RunTasks,
+    UpdateResources,
+    ManageProject,
+    ManageUsers,
+    ManageRoles,
+    ViewAuditLog,
+    ManageIntegrations,
+    ManageSecretStorages,
+}
+
+impl Permission {
+    /// Имя разрешения, совпадающее с полем `RolePermissions`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::RunTasks => "run_tasks",
+            Permission::UpdateResources => "update_resources",
+            Permission::ManageProject => "manage_project",
+            Permission::ManageUsers => "manage_users",
+            Permission::ManageRoles => "manage_roles",
+            Permission::ViewAuditLog => "view_audit_log",
+            Permission::ManageIntegrations => "manage_integrations",
+            Permission::ManageSecretStorages => "manage_secret_storages",
+        }
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl RolePermissions {
+    /// Есть ли у роли разрешение
+    pub fn has(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::RunTasks => self.run_tasks,
+            Permission::UpdateResources => self.update_resources,
+            Permission::ManageProject => self.manage_project,
+            Permission::ManageUsers => self.manage_users,
+            Permission::ManageRoles => self.manage_roles,
+            Permission::ViewAuditLog => self.view_audit_log,
+            Permission::ManageIntegrations => self.manage_integrations,
+            Permission::ManageSecretStorages => self.manage_secret_storages,
+        }
+    }
+
     /// Создаёт разрешения из bitmask
     pub fn from_bitmask(bitmask: i32) -> Self {
         Self {
@@ -103,7 +153,38 @@ impl Default for RolePermissions {
     }
 }
 
+/// Bitmask встроенной роли `manager`: всё, кроме управления
+/// пользователями и ролями
+const MANAGER_PERMISSIONS: i32 = 0b1110_0111;
+
 impl Role {
+    /// Встроенные роли проекта; у них отрицательные ID, чтобы не
+    /// пересекаться с кастомными
+    pub fn builtin(project_id: i32) -> Vec<Role> {
+        [
+            (-1, "owner", "Owner", "Full project control", 0x7FFF_FFFF),
+            (
+                -2,
+                "manager",
+                "Manager",
+                "Manage project resources",
+                MANAGER_PERMISSIONS,
+            ),
+            (-3, "task_runner", "Task Runner", "Run tasks", 0b0000_0001),
+            (-4, "guest", "Guest", "View only", 0),
+        ]
+        .into_iter()
+        .map(|(id, slug, name, description, permissions)| Role {
+            id,
+            project_id,
+            slug: slug.to_string(),
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            permissions: Some(permissions),
+        })
+        .collect()
+    }
+
     /// Создаёт новую роль
     pub fn new(project_id: i32, slug: String, name: String) -> Self {
         Self {
@@ -314,4 +395,32 @@ mod tests {
         let restored: Role = serde_json::from_str(&json).unwrap();
         assert_eq!(restored.name, "Special & <Role>");
     }
+
+    #[test]
+    fn test_builtin_manager_cannot_manage_users() {
+        let roles = Role::builtin(3);
+        let manager = roles.iter().find(|r| r.slug == "manager").unwrap();
+        let perms = manager.get_permissions();
+        assert!(perms.has(Permission::UpdateResources));
+        assert!(perms.has(Permission::ManageSecretStorages));
+        assert!(!perms.has(Permission::ManageUsers));
+        assert!(!perms.has(Permission::ManageRoles));
+        assert!(roles.iter().all(|r| r.id < 0 && r.project_id == 3));
+    }
+
+    #[test]
+    fn test_permission_names_match_fields() {
+        let json = serde_json::to_value(RolePermissions::admin()).unwrap();
+        for permission in [
+            Permission::RunTasks,
+            Permission::ManageRoles,
+            Permission::ManageSecretStorages,
+        ] {
+            assert_eq!(json[permission.as_str()], true);
+            assert_eq!(
+                serde_json::to_string(&permission).unwrap(),
+                format!("\"{permission}\"")
+            );
+        }
+    }
 }
@@ -4,6 +4,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use super::TemplateApp;
+
 fn default_active() -> bool {
     true
 }
@@ -46,6 +48,57 @@ pub struct Runner {
     /// Дата создания
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created: Option<DateTime<Utc>>,
+
+    /// Возможности, заявленные раннером при регистрации
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(skip)]
+    pub capabilities: Option<RunnerCapabilities>,
+}
+
+impl Runner {
+    /// Раннер может взять задачу с указанным тегом инвентаря
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tag.as_deref() == Some(tag)
+            || self
+                .capabilities
+                .as_ref()
+                .is_some_and(|c| c.tags.iter().any(|t| t == tag))
+    }
+
+    /// Раннер умеет запускать приложение шаблона.
+    ///
+    /// Раннер, не заявивший возможностей, считается универсальным.
+    pub fn supports_app(&self, app: &TemplateApp) -> bool {
+        self.capabilities.as_ref().is_none_or(|c| c.supports(app))
+    }
+}
+
+/// Возможности раннера
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunnerCapabilities {
+    pub ansible: bool,
+    pub terraform: bool,
+    pub bash: bool,
+    pub powershell: bool,
+    pub kubernetes: bool,
+    pub max_parallel_tasks: i32,
+    pub tags: Vec<String>,
+}
+
+impl RunnerCapabilities {
+    /// Проверяет, что раннер умеет запускать приложение шаблона
+    ///
+    /// Pulumi и приложения плагинов выполняются только на сервере.
+    pub fn supports(&self, app: &TemplateApp) -> bool {
+        match app {
+            TemplateApp::Ansible => self.ansible,
+            TemplateApp::Terraform | TemplateApp::Tofu | TemplateApp::Terragrunt => self.terraform,
+            TemplateApp::Bash | TemplateApp::Python | TemplateApp::Default => self.bash,
+            TemplateApp::PowerShell => self.powershell,
+            TemplateApp::Pulumi | TemplateApp::Plugin(_) => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -67,6 +120,7 @@ mod tests {
             cleaning_requested: None,
             touched: Some(Utc::now()),
             created: Some(Utc::now()),
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(json.contains("\"name\":\"Build Runner\""));
@@ -89,6 +143,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(!json.contains("\"webhook\":"));
@@ -112,6 +167,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert!(runner.active);
     }
@@ -131,6 +187,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let cloned = runner.clone();
         assert_eq!(cloned.name, runner.name);
@@ -138,6 +195,36 @@ mod tests {
         assert_eq!(cloned.max_parallel_tasks, runner.max_parallel_tasks);
     }
 
+    #[test]
+    fn test_runner_capabilities_match_apps() {
+        let caps = RunnerCapabilities {
+            ansible: true,
+            terraform: true,
+            ..Default::default()
+        };
+        assert!(caps.supports(&TemplateApp::Ansible));
+        assert!(caps.supports(&TemplateApp::Tofu));
+        assert!(!caps.supports(&TemplateApp::Bash));
+        assert!(!caps.supports(&TemplateApp::Plugin("x".to_string())));
+    }
+
+    #[test]
+    fn test_runner_tags_and_apps() {
+        let mut runner: Runner = serde_json::from_str(r#"{"name":"r","tag":"linux"}"#).unwrap();
+        assert!(runner.has_tag("linux"));
+        assert!(!runner.has_tag("gpu"));
+        assert!(runner.supports_app(&TemplateApp::PowerShell));
+
+        runner.capabilities = Some(RunnerCapabilities {
+            bash: true,
+            tags: vec!["gpu".to_string()],
+            ..Default::default()
+        });
+        assert!(runner.has_tag("gpu"));
+        assert!(runner.supports_app(&TemplateApp::Bash));
+        assert!(!runner.supports_app(&TemplateApp::PowerShell));
+    }
+
     #[test]
     fn test_runner_deserialization() {
         let json = r#"{"id":5,"project_id":20,"token":"deser-token","name":"Deser Runner","active":false,"last_active":null,"webhook":null,"max_parallel_tasks":10,"tag":"k8s","cleaning_requested":null,"touched":null,"created":null}"#;
@@ -164,6 +251,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(json.contains("\"active\":false"));
@@ -185,6 +273,7 @@ mod tests {
             cleaning_requested: Some(now),
             touched: Some(now),
             created: Some(now),
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         assert!(json.contains("\"webhook\":\"https://hooks.example.com\""));
@@ -207,6 +296,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let debug_str = format!("{:?}", runner);
         assert!(debug_str.contains("Runner"));
@@ -228,6 +318,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert!(runner.token.is_empty());
     }
@@ -247,6 +338,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert_eq!(runner.max_parallel_tasks, Some(0));
     }
@@ -275,6 +367,7 @@ mod tests {
             cleaning_requested: Some(now),
             touched: None,
             created: None,
+            capabilities: None,
         };
         assert!(runner.cleaning_requested.is_some());
     }
@@ -296,6 +389,7 @@ mod tests {
                 cleaning_requested: None,
                 touched: None,
                 created: None,
+                capabilities: None,
             };
             let json = serde_json::to_string(&runner).unwrap();
             assert!(json.contains(&format!("\"tag\":\"{}\"", tag)));
@@ -317,6 +411,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let json = serde_json::to_string(&runner).unwrap();
         let restored: Runner = serde_json::from_str(&json).unwrap();
@@ -338,6 +433,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         let cloned = runner.clone();
         runner.token = "modified".to_string();
@@ -1,13 +1,37 @@
 //! Модель расписания
 
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+/// Цель расписания: задача шаблона
+pub const SCHEDULE_TARGET_TEMPLATE: &str = "template";
+/// Цель расписания: запуск workflow
+pub const SCHEDULE_TARGET_WORKFLOW: &str = "workflow";
+/// Цель расписания: проверка дрейфа
+pub const SCHEDULE_TARGET_DRIFT: &str = "drift";
+
+/// Пропущенные за время простоя запуски отбрасываются
+pub const MISFIRE_SKIP: &str = "skip";
+/// Из пропущенных запусков выполняется только последний
+pub const MISFIRE_RUN_ONCE: &str = "run_once";
+/// Выполняются все пропущенные запуски
+pub const MISFIRE_RUN_ALL: &str = "run_all";
+
+fn default_target_type() -> String {
+    SCHEDULE_TARGET_TEMPLATE.to_string()
+}
+
+fn default_misfire_policy() -> String {
+    MISFIRE_SKIP.to_string()
+}
+
 /// Расписание - автоматический запуск задач
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Schedule {
     #[serde(default)]
     pub id: i32,
+    #[serde(default)]
     pub template_id: i32,
     #[serde(default)]
     pub project_id: i32,
@@ -30,6 +54,60 @@ pub struct Schedule {
     /// Удалить расписание после выполнения (только для run_at)
     #[serde(default)]
     pub delete_after_run: bool,
+    /// Что запускается: `template`, `workflow` или `drift`
+    #[serde(default = "default_target_type")]
+    pub target_type: String,
+    /// ID workflow или конфигурации дрейфа (для `template` используется template_id)
+    #[serde(default)]
+    pub target_id: Option<i32>,
+    /// Часовой пояс IANA, в котором вычисляется cron (по умолчанию UTC)
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Что делать с запусками, пропущенными во время простоя
+    #[serde(default = "default_misfire_policy")]
+    pub misfire_policy: String,
+    /// Случайная задержка запуска, секунды (0 — без задержки)
+    #[serde(default)]
+    pub jitter_secs: i32,
+    /// Периоды, в которые запуски не выполняются
+    #[serde(default)]
+    #[sqlx(json)]
+    pub blackout_windows: Vec<BlackoutWindow>,
+    /// Последнее обработанное время срабатывания
+    #[serde(default)]
+    pub last_fired: Option<DateTime<Utc>>,
+    /// Ветка для запуска по новому коммиту (по умолчанию — ветка репозитория)
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    /// Glob-шаблоны путей: запуск по коммиту только если изменились эти файлы
+    #[serde(default)]
+    #[sqlx(json)]
+    pub commit_paths: Vec<String>,
+}
+
+impl Schedule {
+    /// Запускается ли расписание по новому коммиту в репозитории
+    ///
+    /// cron в этом режиме задаёт периодичность опроса репозитория.
+    pub fn is_commit_triggered(&self) -> bool {
+        self.repository_id.is_some()
+    }
+}
+
+/// Период запрета запусков в часовом поясе расписания
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlackoutWindow {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl BlackoutWindow {
+    /// Попадает ли локальное время в период
+    pub fn contains(&self, local: NaiveDateTime) -> bool {
+        self.start <= local && local < self.end
+    }
 }
 
 /// Расписание с дополнительными полями
@@ -60,6 +138,15 @@ mod tests {
             created: Some("2024-01-01T00:00:00Z".to_string()),
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"name\":\"Hourly Deploy\""));
@@ -82,6 +169,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         // run_at uses skip_serializing_if so it's omitted when None
@@ -106,6 +202,15 @@ mod tests {
             created: None,
             run_at: Some("2024-06-15T10:00:00Z".to_string()),
             delete_after_run: true,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"run_at\":\"2024-06-15T10:00:00Z\""));
@@ -127,6 +232,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let with_tpl = ScheduleWithTpl {
             schedule,
@@ -152,6 +266,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let cloned = schedule.clone();
         assert_eq!(cloned.id, schedule.id);
@@ -174,6 +297,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert!(schedule.cron.is_empty());
         assert!(schedule.name.is_empty());
@@ -196,6 +328,15 @@ mod tests {
             created: Some("2024-01-01".to_string()),
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let cloned = schedule.clone();
         assert_eq!(cloned.id, schedule.id);
@@ -218,6 +359,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let debug_str = format!("{:?}", schedule);
         assert!(debug_str.contains("Schedule"));
@@ -248,6 +398,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let with_tpl = ScheduleWithTpl {
             schedule,
@@ -272,6 +431,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         assert!(!schedule.active);
         assert!(!schedule.delete_after_run);
@@ -292,6 +460,15 @@ mod tests {
             created: Some("2024-01-01T00:00:00Z".to_string()),
             run_at: Some("2024-06-01T12:00:00Z".to_string()),
             delete_after_run: true,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         assert!(json.contains("\"cron\":\"1 2 3 4 5\""));
@@ -314,6 +491,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&schedule).unwrap();
         let restored: Schedule = serde_json::from_str(&json).unwrap();
@@ -335,6 +521,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let cloned = schedule.clone();
         schedule.name = "Modified".to_string();
@@ -356,6 +551,15 @@ mod tests {
             created: Some("2024-01-01".to_string()),
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let json = serde_json::to_string(&original).unwrap();
         let restored: Schedule = serde_json::from_str(&json).unwrap();
@@ -379,6 +583,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
         let with_tpl = ScheduleWithTpl {
             schedule,
@@ -386,4 +599,23 @@ mod tests {
         };
         assert!(with_tpl.tpl_playbook.is_none());
     }
+
+    #[test]
+    fn test_schedule_target_defaults() {
+        let json = r#"{"template_id":5,"name":"Legacy","cron":"0 0 * * *"}"#;
+        let schedule: Schedule = serde_json::from_str(json).unwrap();
+        assert_eq!(schedule.target_type, SCHEDULE_TARGET_TEMPLATE);
+        assert_eq!(schedule.misfire_policy, MISFIRE_SKIP);
+        assert!(schedule.timezone.is_none());
+        assert!(schedule.blackout_windows.is_empty());
+    }
+
+    #[test]
+    fn test_blackout_window_contains() {
+        let window: BlackoutWindow =
+            serde_json::from_str(r#"{"start":"2026-12-24T00:00:00","end":"2026-12-27T00:00:00"}"#)
+                .unwrap();
+        assert!(window.contains(window.start));
+        assert!(!window.contains(window.end));
+    }
 }
@@ -0,0 +1,68 @@
+//! Модель сервисного аккаунта
+//!
+//! Сервисный аккаунт — пользователь без пароля, от имени которого работают
+//! CI-пайплайны и Terraform-провайдер. Он состоит в проектах как обычный
+//! участник и аутентифицируется только API-токенами.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Сервисный аккаунт
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ServiceAccount {
+    /// Уникальный идентификатор
+    pub id: i32,
+
+    /// Пользователь, от имени которого действует аккаунт
+    pub user_id: i32,
+
+    /// Описание назначения
+    pub description: Option<String>,
+
+    /// Администратор, создавший аккаунт
+    pub created_by: Option<i32>,
+
+    /// Дата создания
+    pub created: DateTime<Utc>,
+
+    /// Имя пользователя (логин)
+    #[sqlx(default)]
+    pub username: String,
+
+    /// Отображаемое имя
+    #[sqlx(default)]
+    pub name: String,
+}
+
+/// Параметры создания сервисного аккаунта
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceAccountCreate {
+    pub username: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_account_serialization() {
+        let account = ServiceAccount {
+            id: 1,
+            user_id: 7,
+            description: Some("CI pipeline".to_string()),
+            created_by: Some(1),
+            created: Utc::now(),
+            username: "svc-ci".to_string(),
+            name: "CI".to_string(),
+        };
+        let json = serde_json::to_value(&account).unwrap();
+        assert_eq!(json["user_id"], 7);
+        assert_eq!(json["username"], "svc-ci");
+        assert_eq!(json["description"], "CI pipeline");
+    }
+}
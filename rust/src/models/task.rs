@@ -99,22 +99,22 @@ pub struct Task {
     /// Параметры задачи
     #[serde(skip_serializing_if = "Option::is_none")]
     pub params: Option<serde_json::Value>,
-}
 
-impl Task {
-    /// Получает URL задачи
-    pub fn get_url(&self) -> String {
-        format!("/project/{}/tasks/{}", self.project_id, self.id)
-    }
+    /// Приоритет в очереди (переопределяет приоритет шаблона)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
 }
 
-#[cfg(test)]
-impl Default for Task {
-    fn default() -> Self {
+impl Task {
+    /// Создаёт новую задачу шаблона в статусе `Waiting`
+    ///
+    /// Остальные поля пусты; вызывающий код заполняет нужные через
+    /// `Task { message: ..., ..Task::new_waiting(template_id, project_id) }`.
+    pub fn new_waiting(template_id: i32, project_id: i32) -> Self {
         Self {
             id: 0,
-            template_id: 0,
-            project_id: 0,
+            template_id,
+            project_id,
             status: TaskStatus::Waiting,
             created: Utc::now(),
             playbook: None,
@@ -136,8 +136,21 @@ impl Default for Task {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         }
     }
+
+    /// Получает URL задачи
+    pub fn get_url(&self) -> String {
+        format!("/project/{}/tasks/{}", self.project_id, self.id)
+    }
+}
+
+#[cfg(test)]
+impl Default for Task {
+    fn default() -> Self {
+        Self::new_waiting(0, 0)
+    }
 }
 
 /// Задача с дополнительными полями шаблона
@@ -167,6 +180,17 @@ pub struct TaskWithTpl {
     pub build_task: Option<Box<Task>>,
 }
 
+/// Связь задачи-хука с родительской задачей (pre/post/fail хуки шаблона)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct TaskHook {
+    /// ID задачи-хука
+    pub task_id: i32,
+    /// ID родительской задачи
+    pub parent_task_id: i32,
+    /// Тип хука: `pre`, `post` или `fail`
+    pub hook_type: String,
+}
+
 /// Вывод задачи (лог)
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct TaskOutput {
@@ -397,6 +421,7 @@ mod tests {
             repository_id: Some(3),
             environment_id: Some(4),
             params: Some(serde_json::json!({"debug": true})),
+            priority: None,
         };
         let json = serde_json::to_string(&task).unwrap();
         assert!(json.contains("\"template_id\":10"));
@@ -432,6 +457,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
         let json = serde_json::to_string(&task).unwrap();
         assert!(!json.contains("\"playbook\":"));
@@ -485,6 +511,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
         assert_eq!(task.get_url(), "/project/7/tasks/42");
     }
@@ -36,6 +36,40 @@ fn default_value_type() -> String {
     "string".to_string()
 }
 
+/// Маркер строки stdout, за которым следует JSON-объект с outputs задачи
+pub const OUTPUT_MARKER: &str = "VELUM_OUTPUT:";
+
+impl TaskStructuredOutputCreate {
+    /// Разбирает строку вывода `VELUM_OUTPUT: {"key": value, ...}`
+    ///
+    /// Строки без маркера или с некорректным JSON дают пустой список.
+    pub fn parse_line(line: &str) -> Vec<Self> {
+        let Some((_, rest)) = line.split_once(OUTPUT_MARKER) else {
+            return Vec::new();
+        };
+        let Ok(outputs) = serde_json::from_str::<serde_json::Map<String, Value>>(rest.trim())
+        else {
+            return Vec::new();
+        };
+        outputs
+            .into_iter()
+            .map(|(key, value)| {
+                let value_type = match value {
+                    Value::String(_) => "string",
+                    Value::Number(_) => "number",
+                    Value::Bool(_) => "bool",
+                    _ => "json",
+                };
+                Self {
+                    key,
+                    value,
+                    value_type: value_type.to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
 /// Batch payload — несколько outputs за раз
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskStructuredOutputBatch {
@@ -263,4 +297,20 @@ mod tests {
         assert_eq!(original.key, restored.key);
         assert_eq!(original.value, restored.value);
     }
+
+    #[test]
+    fn test_parse_output_line() {
+        let outputs = TaskStructuredOutputCreate::parse_line(
+            r#"ok: VELUM_OUTPUT: {"vpc_id": "vpc-1", "count": 2, "tags": ["a"]}"#,
+        );
+        let by_key: HashMap<_, _> = outputs.iter().map(|o| (o.key.as_str(), o)).collect();
+        assert_eq!(by_key.len(), 3);
+        assert_eq!(by_key["vpc_id"].value, Value::String("vpc-1".to_string()));
+        assert_eq!(by_key["vpc_id"].value_type, "string");
+        assert_eq!(by_key["count"].value_type, "number");
+        assert_eq!(by_key["tags"].value_type, "json");
+
+        assert!(TaskStructuredOutputCreate::parse_line("PLAY RECAP").is_empty());
+        assert!(TaskStructuredOutputCreate::parse_line("VELUM_OUTPUT: not json").is_empty());
+    }
 }
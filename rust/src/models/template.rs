@@ -103,8 +103,10 @@ where
 }
 
 /// Приложение, используемое шаблоном
+///
+/// `Plugin(id)` хранится как `plugin:<id>` и выполняется `TaskExecutorPlugin`-ом.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[serde(try_from = "String", into = "String")]
 pub enum TemplateApp {
     Ansible,
     Terraform,
@@ -115,6 +117,25 @@ pub enum TemplateApp {
     Python,
     Pulumi,
     Default,
+    Plugin(String),
+}
+
+impl TemplateApp {
+    /// Разбирает имя приложения; `None` для неизвестных значений
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "ansible" => TemplateApp::Ansible,
+            "terraform" => TemplateApp::Terraform,
+            "tofu" => TemplateApp::Tofu,
+            "terragrunt" => TemplateApp::Terragrunt,
+            "bash" => TemplateApp::Bash,
+            "powershell" => TemplateApp::PowerShell,
+            "python" => TemplateApp::Python,
+            "pulumi" => TemplateApp::Pulumi,
+            "default" => TemplateApp::Default,
+            _ => TemplateApp::Plugin(crate::plugins::plugin_target(s)?.to_string()),
+        })
+    }
 }
 
 impl std::fmt::Display for TemplateApp {
@@ -129,6 +150,7 @@ impl std::fmt::Display for TemplateApp {
             TemplateApp::Python => write!(f, "python"),
             TemplateApp::Pulumi => write!(f, "pulumi"),
             TemplateApp::Default => write!(f, "default"),
+            TemplateApp::Plugin(id) => write!(f, "{}{id}", crate::plugins::PLUGIN_PREFIX),
         }
     }
 }
@@ -137,17 +159,21 @@ impl std::str::FromStr for TemplateApp {
     type Err = std::convert::Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "ansible" => TemplateApp::Ansible,
-            "terraform" => TemplateApp::Terraform,
-            "tofu" => TemplateApp::Tofu,
-            "terragrunt" => TemplateApp::Terragrunt,
-            "bash" => TemplateApp::Bash,
-            "powershell" => TemplateApp::PowerShell,
-            "python" => TemplateApp::Python,
-            "pulumi" => TemplateApp::Pulumi,
-            _ => TemplateApp::Default,
-        })
+        Ok(TemplateApp::parse(s).unwrap_or(TemplateApp::Default))
+    }
+}
+
+impl TryFrom<String> for TemplateApp {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        TemplateApp::parse(&s).ok_or_else(|| format!("unknown template app: {s}"))
+    }
+}
+
+impl From<TemplateApp> for String {
+    fn from(app: TemplateApp) -> Self {
+        app.to_string()
     }
 }
 
@@ -170,17 +196,7 @@ where
 {
     fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
         let s = <String as Decode<'r, DB>>::decode(value)?;
-        Ok(match s.as_str() {
-            "ansible" => TemplateApp::Ansible,
-            "terraform" => TemplateApp::Terraform,
-            "tofu" => TemplateApp::Tofu,
-            "terragrunt" => TemplateApp::Terragrunt,
-            "bash" => TemplateApp::Bash,
-            "powershell" => TemplateApp::PowerShell,
-            "python" => TemplateApp::Python,
-            "pulumi" => TemplateApp::Pulumi,
-            _ => TemplateApp::Default,
-        })
+        Ok(TemplateApp::parse(&s).unwrap_or(TemplateApp::Default))
     }
 }
 
@@ -193,19 +209,7 @@ where
         &self,
         buf: &mut <DB as Database>::ArgumentBuffer<'q>,
     ) -> Result<sqlx::encode::IsNull, Box<dyn std::error::Error + Send + Sync>> {
-        let s = match self {
-            TemplateApp::Ansible => "ansible",
-            TemplateApp::Terraform => "terraform",
-            TemplateApp::Tofu => "tofu",
-            TemplateApp::Terragrunt => "terragrunt",
-            TemplateApp::Bash => "bash",
-            TemplateApp::PowerShell => "powershell",
-            TemplateApp::Python => "python",
-            TemplateApp::Pulumi => "pulumi",
-            TemplateApp::Default => "default",
-        }
-        .to_string();
-        <String as Encode<'q, DB>>::encode(s, buf)
+        <String as Encode<'q, DB>>::encode(self.to_string(), buf)
     }
 }
 
@@ -334,6 +338,21 @@ pub struct Template {
     /// ID deployment environment (production/staging/dev) — обновляется при запуске
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deploy_environment_id: Option<i32>,
+
+    // ── Queue Priority ──────────────────────────────────────────────────────
+    /// Приоритет задач шаблона в очереди (больше — раньше)
+    #[serde(default)]
+    pub priority: i32,
+
+    // ── Concurrency ─────────────────────────────────────────────────────────
+    /// Группа параллелизма, в которую входит шаблон
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency_group: Option<String>,
+
+    // ── Execution Timeout ───────────────────────────────────────────────────
+    /// Максимальная длительность задачи в секундах (`None` — глобальное значение)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<i32>,
 }
 
 /// Шаблон с правами доступа
@@ -440,6 +459,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let json = serde_json::to_string(&template).unwrap();
         assert!(json.contains("\"name\":\"Deploy to Prod\""));
@@ -482,6 +504,9 @@ mod tests {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
         let json = serde_json::to_string(&template).unwrap();
         assert!(!json.contains("\"inventory_id\":"));
@@ -567,6 +592,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_template_app_plugin_roundtrip() {
+        let app: TemplateApp = serde_json::from_str("\"plugin:k8s_deploy\"").unwrap();
+        assert_eq!(app, TemplateApp::Plugin("k8s_deploy".to_string()));
+        assert_eq!(
+            serde_json::to_string(&app).unwrap(),
+            "\"plugin:k8s_deploy\""
+        );
+        assert_eq!(
+            serde_json::from_str::<TemplateApp>("\"powershell\"").unwrap(),
+            TemplateApp::PowerShell
+        );
+        assert!(serde_json::from_str::<TemplateApp>("\"unknown\"").is_err());
+        assert!(serde_json::from_str::<TemplateApp>("\"plugin:\"").is_err());
+    }
+
     #[test]
     fn test_template_default() {
         let template = Template::default();
@@ -720,6 +761,9 @@ impl Template {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         }
     }
 }
@@ -4,8 +4,44 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use super::Permission;
+
+/// Область действия API-токена
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Только чтение
+    ReadOnly,
+    /// Чтение и запуск задач
+    RunTasks,
+    /// Изменение ресурсов проекта (шаблоны, ключи, интеграции и т.д.)
+    ManageResources,
+    /// Все права пользователя, включая права администратора
+    Admin,
+}
+
+impl TokenScope {
+    /// Даёт ли область разрешение проекта; чтение доступно любой области
+    pub fn grants(&self, permission: Permission) -> bool {
+        match self {
+            TokenScope::ReadOnly => permission == Permission::ViewAuditLog,
+            TokenScope::RunTasks => {
+                matches!(permission, Permission::ViewAuditLog | Permission::RunTasks)
+            }
+            TokenScope::ManageResources => !matches!(
+                permission,
+                Permission::ManageProject | Permission::ManageUsers | Permission::ManageRoles
+            ),
+            TokenScope::Admin => true,
+        }
+    }
+}
+
 /// API-токен для аутентификации
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+///
+/// Токен без областей (`scopes`) действует со всеми правами владельца —
+/// так работают токены, выпущенные до появления областей.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
 pub struct APIToken {
     pub id: i32,
     pub user_id: i32,
@@ -13,6 +49,95 @@ pub struct APIToken {
     pub token: String,
     pub created: DateTime<Utc>,
     pub expired: bool,
+
+    /// Срок действия; `None` — бессрочный
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Области действия
+    #[serde(default)]
+    #[sqlx(json)]
+    pub scopes: Vec<TokenScope>,
+
+    /// Проекты, в которых действует токен; пустой список — все проекты
+    #[serde(default)]
+    #[sqlx(json)]
+    pub project_ids: Vec<i32>,
+
+    /// Шаблоны, на которых токен может запускать задачи и менять ресурсы;
+    /// пустой список — без ограничения
+    #[serde(default)]
+    #[sqlx(json)]
+    pub template_ids: Vec<i32>,
+
+    /// Время последнего использования
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+
+    /// IP-адрес последнего использования
+    #[serde(default)]
+    pub last_used_ip: Option<String>,
+}
+
+impl APIToken {
+    /// Токен не отозван и не истёк
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        !self.expired && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    /// Есть ли у токена область
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.is_empty() || self.scopes.contains(&scope)
+    }
+
+    /// Даёт ли токен разрешение проекта; `None` — чтение
+    pub fn grants(&self, permission: Option<Permission>) -> bool {
+        permission.is_none_or(|permission| {
+            self.scopes.is_empty() || self.scopes.iter().any(|s| s.grants(permission))
+        })
+    }
+
+    /// Разрешены ли токену изменения вне проектов (например, создание проектов)
+    pub fn allows_global_writes(&self) -> bool {
+        self.has_scope(TokenScope::ManageResources) || self.has_scope(TokenScope::Admin)
+    }
+
+    /// Действует ли токен в проекте
+    pub fn allows_project(&self, project_id: i32) -> bool {
+        self.project_ids.is_empty() || self.project_ids.contains(&project_id)
+    }
+
+    /// Допускает ли ограничение по шаблонам операцию с разрешением
+    /// `permission` над шаблоном `template_id`; чтение не ограничивается
+    pub fn allows_template(
+        &self,
+        template_id: Option<i32>,
+        permission: Option<Permission>,
+    ) -> bool {
+        self.template_ids.is_empty()
+            || permission.is_none()
+            || template_id.is_some_and(|id| self.template_ids.contains(&id))
+    }
+}
+
+/// Параметры выпуска API-токена
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct APITokenCreate {
+    /// Название; по умолчанию — дата выпуска
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Точный срок действия
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Срок действия в днях от момента выпуска
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+    #[serde(default)]
+    pub scopes: Vec<TokenScope>,
+    #[serde(default)]
+    pub project_ids: Vec<i32>,
+    #[serde(default)]
+    pub template_ids: Vec<i32>,
 }
 
 #[cfg(test)]
@@ -28,6 +153,7 @@ mod tests {
             token: "secret_token_value".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("\"name\":\"My Token\""));
@@ -53,6 +179,7 @@ mod tests {
             token: "token_value".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let cloned = token.clone();
         assert_eq!(cloned.name, token.name);
@@ -68,6 +195,7 @@ mod tests {
             token: "expired_token".to_string(),
             created: Utc::now() - chrono::Duration::days(30),
             expired: true,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("\"expired\":true"));
@@ -93,6 +221,7 @@ mod tests {
             token: "debug_secret".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let debug_str = format!("{:?}", token);
         assert!(debug_str.contains("APIToken"));
@@ -108,6 +237,7 @@ mod tests {
             token: "token_val".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("\"name\":\"\""));
@@ -123,6 +253,7 @@ mod tests {
             token: "future".to_string(),
             created: future,
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         assert!(json.contains("\"name\":\"Future Token\""));
@@ -137,6 +268,7 @@ mod tests {
             token: "roundtrip_secret".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&original).unwrap();
         let restored: APIToken = serde_json::from_str(&json).unwrap();
@@ -154,6 +286,7 @@ mod tests {
             token: "max_token".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         let restored: APIToken = serde_json::from_str(&json).unwrap();
@@ -170,6 +303,7 @@ mod tests {
             token: "special_token".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         let restored: APIToken = serde_json::from_str(&json).unwrap();
@@ -185,6 +319,7 @@ mod tests {
             token: "unicode_token".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let json = serde_json::to_string(&token).unwrap();
         let restored: APIToken = serde_json::from_str(&json).unwrap();
@@ -200,6 +335,7 @@ mod tests {
             token: "secret".to_string(),
             created: Utc::now(),
             expired: false,
+            ..Default::default()
         };
         let cloned = token.clone();
         token.name = "Modified".to_string();
@@ -215,6 +351,7 @@ mod tests {
             token: "debug_secret".to_string(),
             created: Utc::now(),
             expired: true,
+            ..Default::default()
         };
         let debug_str = format!("{:?}", token);
         assert!(debug_str.contains("99"));
@@ -232,9 +369,70 @@ mod tests {
                 token: "token".to_string(),
                 created: Utc::now(),
                 expired,
+                ..Default::default()
             };
             let json = serde_json::to_string(&token).unwrap();
             assert!(json.contains(&format!("\"expired\":{}", expired)));
         }
     }
+
+    #[test]
+    fn test_api_token_legacy_json_has_no_restrictions() {
+        let json = r#"{"id":1,"user_id":2,"name":"Old","token":"t","created":"2024-01-01T00:00:00Z","expired":false}"#;
+        let token: APIToken = serde_json::from_str(json).unwrap();
+        assert!(token.is_active(Utc::now()));
+        assert!(token.has_scope(TokenScope::Admin));
+        assert!(token.grants(Some(Permission::ManageUsers)));
+        assert!(token.allows_project(42));
+    }
+
+    #[test]
+    fn test_api_token_expiry() {
+        let now = Utc::now();
+        let mut token = APIToken {
+            expires_at: Some(now + chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        assert!(token.is_active(now));
+        token.expires_at = Some(now - chrono::Duration::seconds(1));
+        assert!(!token.is_active(now));
+        token.expires_at = None;
+        token.expired = true;
+        assert!(!token.is_active(now));
+    }
+
+    #[test]
+    fn test_api_token_scopes() {
+        let token = APIToken {
+            scopes: vec![TokenScope::RunTasks],
+            ..Default::default()
+        };
+        assert!(token.grants(None));
+        assert!(token.grants(Some(Permission::RunTasks)));
+        assert!(!token.grants(Some(Permission::UpdateResources)));
+        assert!(!token.has_scope(TokenScope::Admin));
+
+        assert!(!TokenScope::ReadOnly.grants(Permission::RunTasks));
+        assert!(TokenScope::ManageResources.grants(Permission::ManageSecretStorages));
+        assert!(!TokenScope::ManageResources.grants(Permission::ManageUsers));
+        assert_eq!(
+            serde_json::to_string(&TokenScope::ManageResources).unwrap(),
+            "\"manage_resources\""
+        );
+    }
+
+    #[test]
+    fn test_api_token_project_and_template_restrictions() {
+        let token = APIToken {
+            project_ids: vec![3],
+            template_ids: vec![7],
+            ..Default::default()
+        };
+        assert!(token.allows_project(3));
+        assert!(!token.allows_project(4));
+        assert!(token.allows_template(None, None));
+        assert!(token.allows_template(Some(7), Some(Permission::RunTasks)));
+        assert!(!token.allows_template(Some(8), Some(Permission::RunTasks)));
+        assert!(!token.allows_template(None, Some(Permission::UpdateResources)));
+    }
 }
@@ -73,10 +73,19 @@ pub struct TestWebhook {
 }
 
 /// История webhook
+///
+/// Также хранит попытки доставки событий политикам уведомлений: у таких
+/// записей `webhook_id` равен `0`, а заполнен `notification_policy_id`.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct WebhookLog {
     pub id: i64,
     pub webhook_id: i64,
+    /// Политика уведомлений, которой доставлялось событие
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_policy_id: Option<i32>,
+    /// Доставка outbox-события, к которой относится попытка
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_id: Option<i64>,
     pub event_type: String,
     pub status_code: Option<i32>,
     pub success: bool,
@@ -183,6 +192,8 @@ mod tests {
         let log = WebhookLog {
             id: 1,
             webhook_id: 10,
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "task_completed".to_string(),
             status_code: Some(200),
             success: true,
@@ -269,6 +280,8 @@ mod tests {
         let log = WebhookLog {
             id: 1,
             webhook_id: 10,
+            notification_policy_id: None,
+            delivery_id: None,
             event_type: "task_failed".to_string(),
             status_code: Some(500),
             success: false,
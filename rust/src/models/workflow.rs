@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use sqlx::FromRow;
 
 /// Workflow - DAG пайплайн из шаблонов
@@ -43,6 +44,45 @@ pub struct WorkflowNode {
     /// Волны выполняются по возрастанию: 0, 1, 2, ...
     #[serde(default)]
     pub wave: i32,
+    /// Входные переменные, передаваемые задаче узла как extra vars
+    #[serde(default)]
+    #[sqlx(json)]
+    pub inputs: Vec<WorkflowNodeInput>,
+    /// Тип узла: "template" — запуск шаблона, "approval" — ручное подтверждение
+    #[serde(default = "default_node_type")]
+    pub node_type: String,
+}
+
+/// Узел запускает задачу шаблона
+pub const NODE_TYPE_TEMPLATE: &str = "template";
+/// Узел приостанавливает запуск до одобрения или отклонения пользователем
+pub const NODE_TYPE_APPROVAL: &str = "approval";
+
+fn default_node_type() -> String {
+    NODE_TYPE_TEMPLATE.to_string()
+}
+
+impl WorkflowNode {
+    /// Узел ручного подтверждения (шаблон не запускается)
+    pub fn is_approval(&self) -> bool {
+        self.node_type == NODE_TYPE_APPROVAL
+    }
+}
+
+/// Входная переменная узла: `name <- node "<node>".outputs.<key>`
+/// или `name <- params.<key>`, если узел-источник не указан
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowNodeInput {
+    /// Имя переменной в задаче узла
+    pub name: String,
+    /// Имя узла-источника; `None` — параметр запуска workflow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    /// Ключ в outputs узла-источника или имя параметра запуска
+    pub key: String,
+    /// Значение, если источник его не вернул; без него узел завершается ошибкой
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
 }
 
 /// Данные для создания узла
@@ -54,6 +94,10 @@ pub struct WorkflowNodeCreate {
     pub pos_y: f64,
     #[serde(default)]
     pub wave: i32,
+    #[serde(default)]
+    pub inputs: Vec<WorkflowNodeInput>,
+    #[serde(default = "default_node_type")]
+    pub node_type: String,
 }
 
 /// Данные для обновления узла
@@ -64,6 +108,10 @@ pub struct WorkflowNodeUpdate {
     pub pos_y: f64,
     #[serde(default)]
     pub wave: i32,
+    #[serde(default)]
+    pub inputs: Vec<WorkflowNodeInput>,
+    #[serde(default = "default_node_type")]
+    pub node_type: String,
 }
 
 /// Условие перехода по ребру DAG
@@ -110,7 +158,7 @@ pub struct WorkflowRun {
     pub id: i32,
     pub workflow_id: i32,
     pub project_id: i32,
-    pub status: String, // "pending" | "running" | "success" | "failed" | "cancelled"
+    pub status: String, // "pending" | "running" | "waiting_approval" | "success" | "failed" | "cancelled"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
     pub created: DateTime<Utc>,
@@ -118,6 +166,56 @@ pub struct WorkflowRun {
     pub started: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub finished: Option<DateTime<Utc>>,
+    /// Входные параметры запуска
+    #[serde(default)]
+    #[sqlx(json)]
+    pub params: Map<String, Value>,
+}
+
+/// Данные для запуска workflow
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowRunCreate {
+    /// Входные параметры, доступные узлам через `inputs` без `node`
+    #[serde(default)]
+    pub params: Map<String, Value>,
+}
+
+/// Состояние узла в запуске workflow
+///
+/// Сохраняется при каждом переходе, чтобы после рестарта сервера запуск
+/// продолжился с того же места.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WorkflowRunNode {
+    pub run_id: i32,
+    pub node_id: i32,
+    /// "running" | "waiting_approval" | "success" | "failed" | "approved" | "rejected"
+    /// | "cancelled"
+    pub status: String,
+    /// Задача, запущенная для узла
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<i32>,
+    /// Пользователь, одобривший или отклонивший узел подтверждения
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub updated: DateTime<Utc>,
+}
+
+/// Запуск workflow вместе с состояниями узлов
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRunDetails {
+    #[serde(flatten)]
+    pub run: WorkflowRun,
+    pub nodes: Vec<WorkflowRunNode>,
+}
+
+/// Решение по узлу подтверждения
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowApprovalDecision {
+    /// Комментарий, сохраняемый в состоянии узла
+    #[serde(default)]
+    pub comment: Option<String>,
 }
 
 /// Полный workflow с узлами и рёбрами для рендера canvas
@@ -169,6 +267,8 @@ mod tests {
             pos_x: 100.0,
             pos_y: 200.0,
             wave: 0,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         assert_eq!(node.wave, 0);
     }
@@ -196,6 +296,7 @@ mod tests {
             created: Utc::now(),
             started: Some(Utc::now()),
             finished: None,
+            params: Default::default(),
         };
         let json = serde_json::to_string(&run).unwrap();
         assert!(json.contains("\"status\":\"running\""));
@@ -220,6 +321,8 @@ mod tests {
             pos_x: 0.0,
             pos_y: 0.0,
             wave: 0,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         let edge = WorkflowEdge {
             id: 1,
@@ -272,6 +375,8 @@ mod tests {
             pos_x: 100.5,
             pos_y: 200.5,
             wave: 2,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         let json = serde_json::to_string(&node).unwrap();
         assert!(json.contains("\"name\":\"Test Node\""));
@@ -287,6 +392,8 @@ mod tests {
             pos_x: 50.0,
             pos_y: 75.0,
             wave: 1,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         let json = serde_json::to_string(&create).unwrap();
         assert!(json.contains("\"name\":\"Create Node\""));
@@ -300,6 +407,8 @@ mod tests {
             pos_x: 150.0,
             pos_y: 250.0,
             wave: 3,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         };
         let json = serde_json::to_string(&update).unwrap();
         assert!(json.contains("\"name\":\"Updated Node\""));
@@ -340,6 +449,7 @@ mod tests {
             created: Utc::now(),
             started: Some(Utc::now()),
             finished: None,
+            params: Default::default(),
         };
         let cloned = run.clone();
         assert_eq!(cloned.status, run.status);
@@ -368,4 +478,18 @@ mod tests {
         let cloned = create.clone();
         assert_eq!(cloned.name, create.name);
     }
+
+    #[test]
+    fn test_workflow_node_type_defaults_to_template() {
+        let create: WorkflowNodeCreate =
+            serde_json::from_str(r#"{"template_id": 1, "name": "Deploy", "pos_x": 0, "pos_y": 0}"#)
+                .unwrap();
+        assert_eq!(create.node_type, NODE_TYPE_TEMPLATE);
+
+        let approval: WorkflowNodeCreate = serde_json::from_str(
+            r#"{"template_id": 0, "name": "Gate", "pos_x": 0, "pos_y": 0, "node_type": "approval"}"#,
+        )
+        .unwrap();
+        assert_eq!(approval.node_type, NODE_TYPE_APPROVAL);
+    }
 }
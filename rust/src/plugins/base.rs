@@ -311,13 +311,19 @@ use tokio::sync::RwLock;
 pub struct PluginManager {
     plugins: HashMap<String, Arc<RwLock<dyn Plugin>>>,
     hooks: HashMap<String, Vec<String>>, // hook_name -> plugin_ids
+    hook_plugins: HashMap<String, Arc<RwLock<dyn HookPlugin>>>,
+    notification_plugins: HashMap<String, Arc<RwLock<dyn NotificationPlugin>>>,
+    task_executors: HashMap<String, Arc<RwLock<dyn TaskExecutorPlugin>>>,
     config: PluginManagerConfig,
     /// WASM загрузчик плагинов
     wasm_loader: Option<crate::plugins::wasm_loader::WasmPluginLoader>,
     /// WASM runtime для выполнения плагинов
-    wasm_runtime: Option<crate::plugins::wasm_runtime::WasmRuntime>,
+    wasm_runtime: Option<Arc<crate::plugins::wasm_runtime::WasmRuntime>>,
 }
 
+/// Хук, на который подписан плагин, получающий все события
+pub const ALL_HOOKS: &str = "*";
+
 /// Конфигурация менеджера плагинов
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PluginManagerConfig {
@@ -355,7 +361,9 @@ impl PluginManager {
                     "semaphore:get_config".to_string(),
                     "semaphore:set_config".to_string(),
                     "semaphore:call_hook".to_string(),
+                    "semaphore:output".to_string(),
                 ],
+                ..Default::default()
             };
 
             match crate::plugins::wasm_loader::WasmPluginLoader::new(wasm_config) {
@@ -375,6 +383,9 @@ impl PluginManager {
         Self {
             plugins: HashMap::new(),
             hooks: HashMap::new(),
+            hook_plugins: HashMap::new(),
+            notification_plugins: HashMap::new(),
+            task_executors: HashMap::new(),
             config,
             wasm_loader,
             wasm_runtime,
@@ -386,7 +397,7 @@ impl PluginManager {
         if let Some(loader) = &self.wasm_loader {
             match crate::plugins::wasm_runtime::WasmRuntime::new(loader) {
                 Ok(runtime) => {
-                    self.wasm_runtime = Some(runtime);
+                    self.wasm_runtime = Some(Arc::new(runtime));
                     tracing::info!("WASM runtime initialized");
                     Ok(())
                 }
@@ -420,6 +431,84 @@ impl PluginManager {
         }
     }
 
+    /// Находит WASM плагины в директории и регистрирует их по возможностям
+    ///
+    /// Возвращает ID зарегистрированных плагинов. Включённые плагины сразу
+    /// загружаются (`plugin_init`); ошибка загрузки оставляет плагин в статусе `Error`.
+    pub async fn discover_wasm_plugins(&mut self) -> Result<Vec<String>> {
+        if self.wasm_loader.is_none() {
+            return Ok(Vec::new());
+        }
+        if self.wasm_runtime.is_none() {
+            self.initialize_wasm_runtime().await?;
+        }
+        let Some(runtime) = self.wasm_runtime.clone() else {
+            return Ok(Vec::new());
+        };
+
+        let mut registered = Vec::new();
+        for metadata in self.load_wasm_plugins().await? {
+            let id = metadata.info.id.clone();
+            if self.plugins.contains_key(&id) {
+                tracing::warn!("WASM plugin {} skipped: ID already registered", id);
+                continue;
+            }
+            let Some(module) = self
+                .wasm_loader
+                .as_ref()
+                .and_then(|loader| loader.get_module(&id))
+            else {
+                continue;
+            };
+            let instance = runtime.create_instance(module).await?;
+            let mut plugin =
+                crate::plugins::wasm_plugin::WasmPlugin::new(runtime.clone(), instance);
+            if self.is_enabled(&id) {
+                if let Err(e) = plugin.load().await {
+                    tracing::error!("Failed to load WASM plugin {}: {}", id, e);
+                }
+            }
+
+            let (hooks, notifies, executes) = (
+                plugin.is_hook_plugin(),
+                plugin.is_notification_plugin(),
+                plugin.is_task_executor(),
+            );
+            let plugin = Arc::new(RwLock::new(plugin));
+            self.plugins.insert(id.clone(), plugin.clone());
+            if hooks {
+                self.add_hook_plugin(id.clone(), plugin.clone()).await;
+            }
+            if notifies {
+                self.notification_plugins.insert(id.clone(), plugin.clone());
+            }
+            if executes {
+                self.task_executors.insert(id.clone(), plugin);
+            }
+            registered.push(id);
+        }
+        Ok(registered)
+    }
+
+    /// Перечитывает директорию WASM плагинов
+    ///
+    /// Нативные плагины не затрагиваются; включение/отключение сохраняется.
+    pub async fn reload(&mut self) -> Result<Vec<String>> {
+        let wasm_ids: Vec<String> = self
+            .list_wasm_plugins()
+            .iter()
+            .map(|m| m.info.id.clone())
+            .collect();
+        for id in wasm_ids {
+            if let Some(plugin) = self.plugins.get(&id) {
+                let _ = plugin.write().await.unload().await;
+            }
+            self.remove_plugin(&id);
+            let _ = self.unload_wasm_plugin(&id);
+        }
+        self.discover_wasm_plugins().await
+    }
+
     /// Выгружает WASM плагин
     pub fn unload_wasm_plugin(&mut self, plugin_id: &str) -> Result<()> {
         if let Some(loader) = &mut self.wasm_loader {
@@ -438,17 +527,62 @@ impl PluginManager {
         }
     }
 
-    /// Вызывает хук в WASM плагинах
-    pub async fn trigger_wasm_hooks(
+    /// Вызывает хук во включённых плагинах, подписанных на него
+    ///
+    /// Ошибка плагина не прерывает остальные и возвращается как неуспешный `HookResult`.
+    pub async fn trigger_hooks(
         &self,
-        hook_type: crate::plugins::hooks::HookType,
-        data: serde_json::Value,
-        context: crate::plugins::base::PluginContext,
-    ) -> Result<Vec<crate::plugins::base::HookResult>> {
-        // В полной реализации здесь будет вызов WASM плагинов
-        // Для пока возвращаем пустой результат
-        tracing::debug!("WASM hook triggered: {:?}", hook_type);
-        Ok(Vec::new())
+        hook_type: &crate::plugins::hooks::HookType,
+        data: JsonValue,
+        context: PluginContext,
+    ) -> Vec<(String, HookResult)> {
+        let name = hook_type.to_string();
+        let mut plugin_ids: Vec<&String> = self
+            .hooks
+            .get(&name)
+            .into_iter()
+            .chain(self.hooks.get(ALL_HOOKS))
+            .flatten()
+            .filter(|id| self.is_enabled(id))
+            .collect();
+        plugin_ids.sort();
+        plugin_ids.dedup();
+
+        let mut results = Vec::new();
+        for plugin_id in plugin_ids {
+            let Some(plugin) = self.hook_plugins.get(plugin_id) else {
+                continue;
+            };
+            let event = HookEvent {
+                name: name.clone(),
+                timestamp: Utc::now(),
+                data: data.clone(),
+                context: PluginContext {
+                    plugin_id: plugin_id.clone(),
+                    ..context.clone()
+                },
+            };
+            let result = plugin
+                .read()
+                .await
+                .execute_hook(event)
+                .await
+                .unwrap_or_else(|e| HookResult {
+                    success: false,
+                    data: None,
+                    error: Some(e.to_string()),
+                });
+            if !result.success {
+                tracing::warn!(
+                    "Plugin {} failed on hook {}: {:?}",
+                    plugin_id,
+                    name,
+                    result.error
+                );
+            }
+            results.push((plugin_id.clone(), result));
+        }
+        results
     }
 
     /// Регистрирует плагин
@@ -480,14 +614,66 @@ impl PluginManager {
         Ok(())
     }
 
-    /// Загружает все плагины
+    /// Регистрирует плагин хуков
+    pub async fn register_hook_plugin<P: HookPlugin + 'static>(&mut self, plugin: P) -> Result<()> {
+        let id = plugin.info().id;
+        let plugin = Arc::new(RwLock::new(plugin));
+        self.register(plugin.clone()).await?;
+        self.add_hook_plugin(id, plugin).await;
+        Ok(())
+    }
+
+    /// Регистрирует плагин уведомлений
+    pub async fn register_notification_plugin<P: NotificationPlugin + 'static>(
+        &mut self,
+        plugin: P,
+    ) -> Result<()> {
+        let id = plugin.info().id;
+        let plugin = Arc::new(RwLock::new(plugin));
+        self.register(plugin.clone()).await?;
+        self.notification_plugins.insert(id, plugin);
+        Ok(())
+    }
+
+    /// Регистрирует плагин-исполнитель задач
+    pub async fn register_task_executor<P: TaskExecutorPlugin + 'static>(
+        &mut self,
+        plugin: P,
+    ) -> Result<()> {
+        let id = plugin.info().id;
+        let plugin = Arc::new(RwLock::new(plugin));
+        self.register(plugin.clone()).await?;
+        self.task_executors.insert(id, plugin);
+        Ok(())
+    }
+
+    async fn add_hook_plugin(&mut self, id: String, plugin: Arc<RwLock<dyn HookPlugin>>) {
+        for hook in plugin.read().await.get_hooks() {
+            self.hooks.entry(hook).or_default().push(id.clone());
+        }
+        self.hook_plugins.insert(id, plugin);
+    }
+
+    fn remove_plugin(&mut self, plugin_id: &str) {
+        self.plugins.remove(plugin_id);
+        self.hook_plugins.remove(plugin_id);
+        self.notification_plugins.remove(plugin_id);
+        self.task_executors.remove(plugin_id);
+        for ids in self.hooks.values_mut() {
+            ids.retain(|id| id != plugin_id);
+        }
+        self.hooks.retain(|_, ids| !ids.is_empty());
+    }
+
+    /// Загружает все включённые плагины
     pub async fn load_all(&mut self) -> Result<()> {
-        for plugin_id in self.config.enabled_plugins.clone() {
-            if let Some(plugin) = self.plugins.get(&plugin_id) {
-                let mut plugin_guard = plugin.write().await;
-                if let Err(e) = plugin_guard.load().await {
-                    tracing::error!("Failed to load plugin {}: {}", plugin_id, e);
-                }
+        for (plugin_id, plugin) in &self.plugins {
+            if !self.is_enabled(plugin_id) {
+                continue;
+            }
+            let mut plugin_guard = plugin.write().await;
+            if let Err(e) = plugin_guard.load().await {
+                tracing::error!("Failed to load plugin {}: {}", plugin_id, e);
             }
         }
         Ok(())
@@ -505,6 +691,10 @@ impl PluginManager {
             }
         }
         self.plugins.clear();
+        self.hooks.clear();
+        self.hook_plugins.clear();
+        self.notification_plugins.clear();
+        self.task_executors.clear();
         Ok(())
     }
 
@@ -513,33 +703,59 @@ impl PluginManager {
         self.plugins.get(plugin_id).cloned()
     }
 
-    /// Получает список всех плагинов (включая WASM)
+    /// Получает список всех плагинов (включая WASM), отсортированный по ID
+    ///
+    /// `enabled` отражает состояние в менеджере.
     pub async fn list_plugins(&self) -> Vec<PluginInfo> {
         let mut infos = Vec::new();
 
-        // Добавляем нативные плагины
-        for plugin in self.plugins.values() {
-            let plugin_guard = plugin.read().await;
-            infos.push(plugin_guard.info());
+        // Добавляем зарегистрированные плагины
+        for (plugin_id, plugin) in &self.plugins {
+            let mut info = plugin.read().await.info();
+            info.enabled = self.is_enabled(plugin_id);
+            infos.push(info);
         }
 
-        // Добавляем WASM плагины
+        // Добавляем WASM плагины, загруженные без регистрации
         if let Some(loader) = &self.wasm_loader {
             for wasm_plugin in loader.list_loaded_plugins() {
-                infos.push(wasm_plugin.info.clone());
+                if !self.plugins.contains_key(&wasm_plugin.info.id) {
+                    infos.push(wasm_plugin.info.clone());
+                }
             }
         }
 
+        infos.sort_by(|a, b| a.id.cmp(&b.id));
         infos
     }
 
+    /// Проверяет, включён ли плагин
+    ///
+    /// При `auto_load` включены все плагины, кроме отключённых явно.
+    pub fn is_enabled(&self, plugin_id: &str) -> bool {
+        !self
+            .config
+            .disabled_plugins
+            .iter()
+            .any(|id| id == plugin_id)
+            && (self.config.auto_load
+                || self.config.enabled_plugins.iter().any(|id| id == plugin_id))
+    }
+
+    /// ID явно отключённых плагинов
+    pub fn disabled_plugins(&self) -> &[String] {
+        &self.config.disabled_plugins
+    }
+
     /// Включает плагин
     pub fn enable_plugin(&mut self, plugin_id: &str) -> Result<()> {
         if !self.plugins.contains_key(plugin_id) {
             return Err(Error::NotFound(format!("Plugin {} not found", plugin_id)));
         }
 
-        self.config.enabled_plugins.push(plugin_id.to_string());
+        if !self.config.enabled_plugins.iter().any(|id| id == plugin_id) {
+            self.config.enabled_plugins.push(plugin_id.to_string());
+        }
         self.config.disabled_plugins.retain(|id| id != plugin_id);
 
         Ok(())
@@ -548,11 +764,90 @@ impl PluginManager {
     /// Отключает плагин
     pub fn disable_plugin(&mut self, plugin_id: &str) -> Result<()> {
         self.config.enabled_plugins.retain(|id| id != plugin_id);
-        self.config.disabled_plugins.push(plugin_id.to_string());
+        if !self
+            .config
+            .disabled_plugins
+            .iter()
+            .any(|id| id == plugin_id)
+        {
+            self.config.disabled_plugins.push(plugin_id.to_string());
+        }
 
         Ok(())
     }
 
+    /// Каналы включённых плагинов уведомлений: (ID плагина, канал)
+    pub async fn notification_channels(&self) -> Vec<(String, NotificationChannel)> {
+        let mut channels = Vec::new();
+        for (plugin_id, plugin) in &self.notification_plugins {
+            if !self.is_enabled(plugin_id) {
+                continue;
+            }
+            for channel in plugin.read().await.get_channels() {
+                channels.push((plugin_id.clone(), channel));
+            }
+        }
+        channels.sort_by(|a, b| (&a.0, &a.1.id).cmp(&(&b.0, &b.1.id)));
+        channels
+    }
+
+    /// Отправляет уведомление через плагин
+    pub async fn send_notification(
+        &self,
+        plugin_id: &str,
+        context: PluginContext,
+        notification: Notification,
+    ) -> Result<()> {
+        let plugin = self.notification_plugins.get(plugin_id).ok_or_else(|| {
+            Error::NotFound(format!("Notification plugin {} not found", plugin_id))
+        })?;
+        if !self.is_enabled(plugin_id) {
+            return Err(Error::Validation(format!(
+                "Plugin {} is disabled",
+                plugin_id
+            )));
+        }
+        plugin.read().await.send(context, notification).await
+    }
+
+    /// ID включённых плагинов-исполнителей задач
+    pub fn task_executor_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .task_executors
+            .keys()
+            .filter(|id| self.is_enabled(id))
+            .cloned()
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Выполняет задачу плагином-исполнителем
+    pub async fn execute_task(
+        &self,
+        plugin_id: &str,
+        context: PluginContext,
+        task: &Task,
+    ) -> Result<TaskResult> {
+        let plugin = self.task_executors.get(plugin_id).ok_or_else(|| {
+            Error::NotFound(format!("Task executor plugin {} not found", plugin_id))
+        })?;
+        if !self.is_enabled(plugin_id) {
+            return Err(Error::Validation(format!(
+                "Plugin {} is disabled",
+                plugin_id
+            )));
+        }
+        let plugin = plugin.read().await;
+        if !plugin.can_execute(task).await {
+            return Err(Error::Validation(format!(
+                "Plugin {} cannot execute task {}",
+                plugin_id, task.id
+            )));
+        }
+        plugin.execute(context, task).await
+    }
+
     /// Проверяет, является ли плагин опциональным
     fn is_plugin_optional(&self, plugin_id: &str) -> bool {
         !self.config.enabled_plugins.contains(&plugin_id.to_string())
@@ -634,6 +929,7 @@ macro_rules! declare_plugin {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::plugins::hooks::HookType;
     use serde_json::json;
 
     // ========================================================================
@@ -1130,4 +1426,162 @@ mod tests {
         let result = manager.enable_plugin("nonexistent_plugin");
         assert!(result.is_err());
     }
+
+    /// Нативный плагин хуков, считающий вызовы
+    struct CountingHook {
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Plugin for CountingHook {
+        fn info(&self) -> PluginInfo {
+            PluginInfo {
+                id: "counter".to_string(),
+                name: "Counter".to_string(),
+                version: "1.0.0".to_string(),
+                description: String::new(),
+                author: String::new(),
+                r#type: PluginType::Hook,
+                enabled: true,
+                dependencies: vec![],
+                config_schema: None,
+            }
+        }
+
+        async fn initialize(&mut self, _config: PluginConfig) -> Result<()> {
+            Ok(())
+        }
+
+        async fn load(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn unload(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn status(&self) -> PluginStatus {
+            PluginStatus::Loaded
+        }
+
+        fn get_config(&self) -> PluginConfig {
+            PluginConfig::default()
+        }
+
+        async fn update_config(&mut self, _config: PluginConfig) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl HookPlugin for CountingHook {
+        fn get_hooks(&self) -> Vec<String> {
+            vec![HookType::TaskAfterComplete.to_string()]
+        }
+
+        async fn execute_hook(&self, _event: HookEvent) -> Result<HookResult> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(HookResult {
+                success: true,
+                data: None,
+                error: None,
+            })
+        }
+    }
+
+    fn test_context() -> PluginContext {
+        PluginContext {
+            plugin_id: "core".to_string(),
+            project_id: Some(1),
+            user_id: None,
+            task_id: Some(1),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trigger_hooks_respects_subscription_and_disable() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut manager = PluginManager::new(PluginManagerConfig {
+            auto_load: true,
+            ..Default::default()
+        });
+        manager
+            .register_hook_plugin(CountingHook {
+                calls: calls.clone(),
+            })
+            .await
+            .unwrap();
+
+        let results = manager
+            .trigger_hooks(&HookType::TaskAfterComplete, json!({}), test_context())
+            .await;
+        assert_eq!(results.len(), 1);
+        let results = manager
+            .trigger_hooks(&HookType::TaskAfterFail, json!({}), test_context())
+            .await;
+        assert!(results.is_empty());
+
+        manager.disable_plugin("counter").unwrap();
+        let results = manager
+            .trigger_hooks(&HookType::TaskAfterComplete, json!({}), test_context())
+            .await;
+        assert!(results.is_empty());
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(!manager.list_plugins().await[0].enabled);
+    }
+
+    #[tokio::test]
+    async fn test_discover_wasm_plugins_registers_by_exports() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let wasm = ::wat::parse_str(
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                 (func (export "handle_hook") (param i32 i32) (result i32) (i32.const 0))
+                 (func (export "execute_task") (param i32 i32) (result i32) (i32.const 0)))"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("deployer.wasm"), wasm).unwrap();
+
+        let mut manager = PluginManager::new(PluginManagerConfig {
+            wasm_plugins_dir: Some(dir.path().display().to_string()),
+            auto_load: true,
+            wasm_enabled: true,
+            wasm_max_memory_mb: 16,
+            wasm_max_execution_secs: 5,
+            ..Default::default()
+        });
+        let ids = manager.discover_wasm_plugins().await.unwrap();
+        assert_eq!(ids, vec!["deployer".to_string()]);
+        assert_eq!(manager.task_executor_ids(), vec!["deployer".to_string()]);
+        assert!(manager.notification_channels().await.is_empty());
+
+        let task = Task::default();
+        let result = manager
+            .execute_task("deployer", test_context(), &task)
+            .await
+            .unwrap();
+        assert!(result.success);
+        let hooks = manager
+            .trigger_hooks(&HookType::ProjectAfterCreate, json!({}), test_context())
+            .await;
+        assert_eq!(hooks.len(), 1);
+
+        manager.disable_plugin("deployer").unwrap();
+        assert!(manager.task_executor_ids().is_empty());
+        assert!(
+            manager
+                .execute_task("deployer", test_context(), &task)
+                .await
+                .is_err()
+        );
+
+        // Перечитывание директории сохраняет отключение
+        assert_eq!(
+            manager.reload().await.unwrap(),
+            vec!["deployer".to_string()]
+        );
+        assert!(!manager.is_enabled("deployer"));
+    }
 }
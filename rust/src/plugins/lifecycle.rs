@@ -0,0 +1,298 @@
+//! Plugin Lifecycle - подключение плагинов к серверу
+//!
+//! Менеджер плагинов создаётся при старте сервера (`init_plugins`) и доступен
+//! глобально через `plugin_manager()`: события задач, шаблонов и проектов
+//! передаются `HookPlugin`-ам через `emit_*`.
+//!
+//! ## Конфигурация
+//! ```bash
+//! SEMAPHORE_PLUGINS_DIR=/var/lib/velum/plugins   # директория с *.wasm
+//! SEMAPHORE_PLUGINS_DISABLED=noisy_hook,legacy    # отключённые плагины
+//! SEMAPHORE_PLUGINS_MAX_MEMORY_MB=64
+//! SEMAPHORE_PLUGINS_ALLOW_NETWORK=false
+//! ```
+//! Включение/отключение через API сохраняется в опции `plugins_disabled`.
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::notification::NotificationPolicy;
+use crate::models::{Project, Task, Template};
+use crate::plugins::base::{
+    Notification, NotificationLevel, PluginContext, PluginManager, PluginManagerConfig,
+};
+use crate::plugins::hooks::HookType;
+use serde_json::{Value as JsonValue, json};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+static PLUGIN_MANAGER: OnceLock<Arc<RwLock<PluginManager>>> = OnceLock::new();
+
+/// Опция со списком отключённых плагинов (JSON-массив ID)
+pub const DISABLED_PLUGINS_OPTION: &str = "plugins_disabled";
+
+/// Префикс app шаблона / типа канала уведомлений, обслуживаемых плагином
+pub const PLUGIN_PREFIX: &str = "plugin:";
+
+/// ID плагина из `plugin:<id>`
+pub fn plugin_target(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(PLUGIN_PREFIX)
+        .filter(|id| !id.is_empty())
+}
+
+/// Глобальный менеджер плагинов (если подсистема инициализирована)
+pub fn plugin_manager() -> Option<Arc<RwLock<PluginManager>>> {
+    PLUGIN_MANAGER.get().cloned()
+}
+
+/// Конфигурация плагинов из окружения; `None`, если `SEMAPHORE_PLUGINS_DIR` не задан
+pub fn config_from_env() -> Option<PluginManagerConfig> {
+    let dir = std::env::var("SEMAPHORE_PLUGINS_DIR")
+        .ok()
+        .filter(|d| !d.is_empty())?;
+    let env_flag = |name: &str| {
+        std::env::var(name)
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false)
+    };
+
+    Some(PluginManagerConfig {
+        plugins_dir: dir.clone(),
+        enabled_plugins: Vec::new(),
+        disabled_plugins: std::env::var("SEMAPHORE_PLUGINS_DISABLED")
+            .map(|v| parse_id_list(&v))
+            .unwrap_or_default(),
+        auto_load: true,
+        wasm_enabled: true,
+        wasm_plugins_dir: Some(dir),
+        wasm_max_memory_mb: std::env::var("SEMAPHORE_PLUGINS_MAX_MEMORY_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(64),
+        wasm_max_execution_secs: 30,
+        wasm_allow_network: env_flag("SEMAPHORE_PLUGINS_ALLOW_NETWORK"),
+        wasm_allow_filesystem: true,
+    })
+}
+
+fn parse_id_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Создаёт менеджер, находит плагины в директории и делает его глобальным
+///
+/// Отключённые через API плагины (опция `plugins_disabled`) добавляются к конфигурации.
+pub async fn init_plugins(
+    mut config: PluginManagerConfig,
+    store: &dyn Store,
+) -> Arc<RwLock<PluginManager>> {
+    if let Ok(Some(saved)) = store.get_option(DISABLED_PLUGINS_OPTION).await {
+        let saved: Vec<String> = serde_json::from_str(&saved).unwrap_or_default();
+        for id in saved {
+            if !config.disabled_plugins.contains(&id) {
+                config.disabled_plugins.push(id);
+            }
+        }
+    }
+
+    let plugins_dir = config.plugins_dir.clone();
+    let mut manager = PluginManager::new(config);
+    match manager.discover_wasm_plugins().await {
+        Ok(ids) => info!(
+            "Plugins: {} plugin(s) discovered in {}",
+            ids.len(),
+            plugins_dir
+        ),
+        Err(e) => warn!("Plugins: discovery in {} failed: {e}", plugins_dir),
+    }
+
+    let manager = Arc::new(RwLock::new(manager));
+    if PLUGIN_MANAGER.set(manager.clone()).is_err() {
+        warn!("Plugins: manager already initialized");
+    }
+    manager
+}
+
+/// Сохраняет список отключённых плагинов
+pub async fn persist_disabled(manager: &PluginManager, store: &dyn Store) -> Result<()> {
+    let value = serde_json::to_string(manager.disabled_plugins())?;
+    store.set_option(DISABLED_PLUGINS_OPTION, &value).await
+}
+
+/// Асинхронно передаёт событие плагинам хуков (не блокирует вызывающего)
+pub fn emit(hook_type: HookType, data: JsonValue, context: PluginContext) {
+    let Some(manager) = plugin_manager() else {
+        return;
+    };
+    tokio::spawn(async move {
+        manager
+            .read()
+            .await
+            .trigger_hooks(&hook_type, data, context)
+            .await;
+    });
+}
+
+fn context(project_id: i32, user_id: Option<i32>, task_id: Option<i32>) -> PluginContext {
+    PluginContext {
+        plugin_id: "core".to_string(),
+        project_id: Some(project_id.into()),
+        user_id: user_id.map(Into::into),
+        task_id: task_id.map(Into::into),
+        metadata: HashMap::new(),
+    }
+}
+
+/// Данные события задачи
+pub fn task_event_data(task: &Task) -> JsonValue {
+    json!({
+        "task_id": task.id,
+        "template_id": task.template_id,
+        "project_id": task.project_id,
+        "status": task.status.to_string(),
+        "message": task.message,
+        "commit_hash": task.commit_hash,
+        "start": task.start,
+        "end": task.end,
+    })
+}
+
+/// Событие задачи
+pub fn emit_task(hook_type: HookType, task: &Task) {
+    emit(
+        hook_type,
+        task_event_data(task),
+        context(task.project_id, task.user_id, Some(task.id)),
+    );
+}
+
+/// Событие шаблона; `task` — запуск, к которому относится событие
+pub fn emit_template(hook_type: HookType, template: &Template, task: Option<&Task>) {
+    emit(
+        hook_type,
+        json!({
+            "template_id": template.id,
+            "template_name": template.name,
+            "project_id": template.project_id,
+            "app": template.app.to_string(),
+            "task_id": task.map(|t| t.id),
+            "status": task.map(|t| t.status.to_string()),
+        }),
+        context(
+            template.project_id,
+            task.and_then(|t| t.user_id),
+            task.map(|t| t.id),
+        ),
+    );
+}
+
+/// Событие проекта
+pub fn emit_project(hook_type: HookType, project: &Project, user_id: Option<i32>) {
+    emit(
+        hook_type,
+        json!({
+            "project_id": project.id,
+            "project_name": project.name,
+        }),
+        context(project.id, user_id, None),
+    );
+}
+
+/// Проверяет канал `plugin:<id>` политики уведомлений
+///
+/// Возвращает `true`, если канал обслуживается плагином (webhook URL тогда не нужен).
+pub async fn validate_plugin_channel(channel_type: &str) -> Result<bool> {
+    let Some(plugin_id) = plugin_target(channel_type) else {
+        return Ok(false);
+    };
+    let manager = plugin_manager().ok_or_else(|| {
+        Error::Validation("Plugin subsystem is not enabled (SEMAPHORE_PLUGINS_DIR)".to_string())
+    })?;
+    let manager = manager.read().await;
+    if !manager
+        .notification_channels()
+        .await
+        .iter()
+        .any(|(id, _)| id == plugin_id)
+    {
+        return Err(Error::Validation(format!(
+            "Notification plugin '{}' is not available",
+            plugin_id
+        )));
+    }
+    Ok(true)
+}
+
+/// Отправляет уведомление политики через плагин-канал
+pub async fn notify_plugin_channel(
+    policy: &NotificationPolicy,
+    title: &str,
+    level: NotificationLevel,
+    data: JsonValue,
+) -> Result<()> {
+    let plugin_id = plugin_target(&policy.channel_type).ok_or_else(|| {
+        Error::Validation(format!(
+            "Policy {} is not a plugin channel ({})",
+            policy.id, policy.channel_type
+        ))
+    })?;
+    let manager = plugin_manager().ok_or_else(|| {
+        Error::Config("Plugin subsystem is not enabled (SEMAPHORE_PLUGINS_DIR)".to_string())
+    })?;
+    let notification = Notification {
+        title: title.to_string(),
+        message: data
+            .get("text")
+            .and_then(JsonValue::as_str)
+            .unwrap_or(title)
+            .to_string(),
+        level,
+        channels: vec![plugin_id.to_string()],
+        data,
+    };
+    let mut context = context(policy.project_id, None, None);
+    context.plugin_id = plugin_id.to_string();
+    manager
+        .read()
+        .await
+        .send_notification(plugin_id, context, notification)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_target() {
+        assert_eq!(plugin_target("plugin:jira"), Some("jira"));
+        assert_eq!(plugin_target("plugin:"), None);
+        assert_eq!(plugin_target("slack"), None);
+    }
+
+    #[test]
+    fn test_parse_id_list() {
+        assert_eq!(parse_id_list(" a, ,b "), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_task_event_data() {
+        let task = Task {
+            id: 5,
+            project_id: 2,
+            template_id: 3,
+            ..Default::default()
+        };
+        let data = task_event_data(&task);
+        assert_eq!(data["task_id"], 5);
+        assert_eq!(data["template_id"], 3);
+        assert_eq!(data["status"], "waiting");
+    }
+}
@@ -14,10 +14,14 @@
 
 pub mod base;
 pub mod hooks;
+pub mod lifecycle;
 pub mod wasm_loader;
+pub mod wasm_plugin;
 pub mod wasm_runtime;
 
 pub use base::*;
 pub use hooks::*;
+pub use lifecycle::*;
 pub use wasm_loader::*;
+pub use wasm_plugin::*;
 pub use wasm_runtime::*;
@@ -12,7 +12,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 use wasmtime::{Config, Engine, Module};
-use wasmtime_wasi::WasiCtx;
+use wasmtime_wasi::p1::WasiP1Ctx;
 
 /// Конфигурация WASM загрузчика
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +31,13 @@ pub struct WasmLoaderConfig {
     pub allow_filesystem: bool,
     /// Разрешить доступ к переменным окружения
     pub allow_env: bool,
+    /// Лимит fuel (примерно число инструкций) на один вызов плагина
+    #[serde(default = "default_max_fuel")]
+    pub max_fuel: u64,
+}
+
+fn default_max_fuel() -> u64 {
+    10_000_000_000
 }
 
 impl Default for WasmLoaderConfig {
@@ -42,12 +49,14 @@ impl Default for WasmLoaderConfig {
                 "semaphore:get_config".to_string(),
                 "semaphore:set_config".to_string(),
                 "semaphore:call_hook".to_string(),
+                "semaphore:output".to_string(),
             ],
             max_memory_pages: 1024, // 64 MB
             max_execution_time_secs: 30,
             allow_network: false,
             allow_filesystem: true,
             allow_env: false,
+            max_fuel: default_max_fuel(),
         }
     }
 }
@@ -83,6 +92,8 @@ impl WasmPluginLoader {
         let mut engine_config = Config::new();
         engine_config.wasm_reference_types(true);
         engine_config.wasm_multi_value(true);
+        // Fuel ограничивает время выполнения каждого вызова
+        engine_config.consume_fuel(true);
 
         let engine = Engine::new(&engine_config)
             .map_err(|e| Error::Other(format!("Failed to create WASM engine: {}", e)))?;
@@ -225,37 +236,19 @@ impl WasmPluginLoader {
         self.loaded_modules.values().map(|m| &m.metadata).collect()
     }
 
-    /// Создаёт WASI контекст для плагина
-    pub fn create_wasi_context(&self, plugin_id: &str) -> Result<WasiCtx> {
-        use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
-
-        let mut builder = WasiCtxBuilder::new();
-
-        // Настраиваем stdio
-        builder.inherit_stdio();
-
-        // Разрешаем доступ к директории плагинов если включено
-        if self.config.allow_filesystem {
-            // Предоставляем доступ только на чтение к директории плагинов
-            builder
-                .preopened_dir(
-                    &self.config.plugins_dir,
-                    ".",
-                    DirPerms::READ,
-                    FilePerms::READ,
-                )
-                .map_err(|e| Error::Other(format!("Failed to preopen directory: {}", e)))?;
-        }
+    /// Получает загруженный модуль
+    pub fn get_module(&self, plugin_id: &str) -> Option<&LoadedWasmModule> {
+        self.loaded_modules.get(plugin_id)
+    }
 
-        // Добавляем переменные окружения если разрешено
-        if self.config.allow_env {
-            let env: Vec<(String, String)> = std::env::vars()
-                .filter(|(k, _)| k.starts_with("SEMAPHORE_"))
-                .collect();
-            builder.envs(&env);
-        }
+    /// Создаёт WASI контекст для плагина
+    pub fn create_wasi_context(&self, _plugin_id: &str) -> Result<WasiP1Ctx> {
+        build_wasi_context(&self.config)
+    }
 
-        Ok(builder.build())
+    /// Конфигурация загрузчика
+    pub fn config(&self) -> &WasmLoaderConfig {
+        &self.config
     }
 
     /// Получает engine для создания store
@@ -264,6 +257,34 @@ impl WasmPluginLoader {
     }
 }
 
+/// Создаёт WASI preview1 контекст с учётом ограничений конфигурации
+pub fn build_wasi_context(config: &WasmLoaderConfig) -> Result<WasiP1Ctx> {
+    use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+    let mut builder = WasiCtxBuilder::new();
+
+    // Настраиваем stdio
+    builder.inherit_stdio();
+
+    // Разрешаем доступ к директории плагинов если включено
+    if config.allow_filesystem && config.plugins_dir.is_dir() {
+        // Предоставляем доступ только на чтение к директории плагинов
+        builder
+            .preopened_dir(&config.plugins_dir, ".", DirPerms::READ, FilePerms::READ)
+            .map_err(|e| Error::Other(format!("Failed to preopen directory: {}", e)))?;
+    }
+
+    // Добавляем переменные окружения если разрешено
+    if config.allow_env {
+        let env: Vec<(String, String)> = std::env::vars()
+            .filter(|(k, _)| k.starts_with("SEMAPHORE_"))
+            .collect();
+        builder.envs(&env);
+    }
+
+    Ok(builder.build_p1())
+}
+
 /// Helper для вычисления хэша файла
 async fn compute_file_hash(path: &Path) -> Result<String> {
     use sha2::{Digest, Sha256};
@@ -0,0 +1,206 @@
+//! WASM Plugin - адаптер WASM модуля к трейтам плагинов
+//!
+//! Возможности плагина определяются по экспортам модуля (см. ABI в `wasm_runtime`):
+//! - `handle_hook` — `HookPlugin`, получает все события и сам фильтрует их по имени
+//! - `send_notification` — `NotificationPlugin` с одним каналом (ID плагина)
+//! - `execute_task` — `TaskExecutorPlugin`, выбирается шаблоном как app `plugin:<id>`
+
+use crate::error::{Error, Result};
+use crate::models::Task;
+use crate::plugins::base::{
+    HookEvent, HookPlugin, HookResult, Notification, NotificationChannel, NotificationPlugin,
+    Plugin, PluginConfig, PluginContext, PluginInfo, PluginStatus, PluginType, TaskExecutorPlugin,
+    TaskResult,
+};
+use crate::plugins::wasm_runtime::{
+    EXPORT_EXECUTE_TASK, EXPORT_HANDLE_HOOK, EXPORT_PLUGIN_INIT, EXPORT_SEND_NOTIFICATION,
+    WasmPluginInstance, WasmRuntime,
+};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+
+/// Плагин, загруженный из `.wasm` файла
+pub struct WasmPlugin {
+    runtime: Arc<WasmRuntime>,
+    instance: WasmPluginInstance,
+    config: PluginConfig,
+    status: PluginStatus,
+}
+
+impl WasmPlugin {
+    pub fn new(runtime: Arc<WasmRuntime>, instance: WasmPluginInstance) -> Self {
+        Self {
+            runtime,
+            instance,
+            config: PluginConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            status: PluginStatus::Unloaded,
+        }
+    }
+
+    /// Обрабатывает хуки
+    pub fn is_hook_plugin(&self) -> bool {
+        self.instance.exports(EXPORT_HANDLE_HOOK)
+    }
+
+    /// Отправляет уведомления
+    pub fn is_notification_plugin(&self) -> bool {
+        self.instance.exports(EXPORT_SEND_NOTIFICATION)
+    }
+
+    /// Выполняет задачи
+    pub fn is_task_executor(&self) -> bool {
+        self.instance.exports(EXPORT_EXECUTE_TASK)
+    }
+
+    /// Вызов с JSON-аргументом; ненулевой код — ошибка
+    async fn call_checked(&self, export: &str, payload: serde_json::Value) -> Result<()> {
+        let result = self
+            .runtime
+            .call_function(&self.instance, export, Some(serde_json::to_vec(&payload)?))
+            .await?;
+        if result.code != 0 {
+            return Err(Error::Other(format!(
+                "Plugin {}: {export} returned {}",
+                self.instance.store_data.plugin_id, result.code
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Plugin for WasmPlugin {
+    fn info(&self) -> PluginInfo {
+        let mut info = self.instance.metadata.info.clone();
+        info.r#type = if self.is_task_executor() {
+            PluginType::TaskExecutor
+        } else if self.is_notification_plugin() {
+            PluginType::NotificationProvider
+        } else if self.is_hook_plugin() {
+            PluginType::Hook
+        } else {
+            PluginType::Custom
+        };
+        info.enabled = self.config.enabled;
+        info
+    }
+
+    async fn initialize(&mut self, config: PluginConfig) -> Result<()> {
+        self.config = config;
+        Ok(())
+    }
+
+    async fn load(&mut self) -> Result<()> {
+        if self.instance.exports(EXPORT_PLUGIN_INIT) {
+            let code = match self
+                .runtime
+                .call_function(&self.instance, EXPORT_PLUGIN_INIT, None)
+                .await
+            {
+                Ok(result) => result.code,
+                Err(e) => {
+                    self.status = PluginStatus::Error(e.to_string());
+                    return Err(e);
+                }
+            };
+            if code != 0 {
+                let message = format!("plugin_init returned {code}");
+                self.status = PluginStatus::Error(message.clone());
+                return Err(Error::Other(message));
+            }
+        }
+        self.status = PluginStatus::Loaded;
+        Ok(())
+    }
+
+    async fn unload(&mut self) -> Result<()> {
+        self.status = PluginStatus::Unloaded;
+        Ok(())
+    }
+
+    fn status(&self) -> PluginStatus {
+        self.status.clone()
+    }
+
+    fn get_config(&self) -> PluginConfig {
+        self.config.clone()
+    }
+
+    async fn update_config(&mut self, config: PluginConfig) -> Result<()> {
+        self.config = config;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HookPlugin for WasmPlugin {
+    fn get_hooks(&self) -> Vec<String> {
+        if self.is_hook_plugin() {
+            vec!["*".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    async fn execute_hook(&self, event: HookEvent) -> Result<HookResult> {
+        self.runtime.call_hook(&self.instance, event).await
+    }
+}
+
+#[async_trait]
+impl NotificationPlugin for WasmPlugin {
+    async fn send(&self, context: PluginContext, notification: Notification) -> Result<()> {
+        self.call_checked(
+            EXPORT_SEND_NOTIFICATION,
+            json!({
+                "context": context,
+                "notification": notification,
+                "settings": self.config.settings,
+            }),
+        )
+        .await
+    }
+
+    fn get_channels(&self) -> Vec<NotificationChannel> {
+        let info = &self.instance.metadata.info;
+        vec![NotificationChannel {
+            id: info.id.clone(),
+            name: info.name.clone(),
+            description: info.description.clone(),
+            config_schema: info.config_schema.clone(),
+        }]
+    }
+}
+
+#[async_trait]
+impl TaskExecutorPlugin for WasmPlugin {
+    async fn can_execute(&self, task: &Task) -> bool {
+        let task_json = serde_json::to_string(task).unwrap_or_default();
+        self.runtime
+            .can_execute_task(&self.instance, &task_json)
+            .await
+            .unwrap_or(false)
+    }
+
+    async fn execute(&self, context: PluginContext, task: &Task) -> Result<TaskResult> {
+        let payload = json!({
+            "context": context,
+            "task": task,
+            "settings": self.config.settings,
+        });
+        self.runtime
+            .execute_task(&self.instance, &payload.to_string())
+            .await
+    }
+
+    async fn stop(&self, _context: PluginContext, _task_id: i64) -> Result<()> {
+        // Вызов ограничен fuel и завершится сам; прервать его извне нельзя
+        Err(Error::NotImplemented(
+            "WASM task executors cannot be stopped".to_string(),
+        ))
+    }
+}
@@ -2,21 +2,59 @@
 //!
 //! Этот модуль предоставляет среду выполнения для WASM плагинов,
 //! включая хост-функции, sandboxing и управление ресурсами.
+//!
+//! ## ABI плагина
+//!
+//! Плагин экспортирует `memory` и `alloc(len: i32) -> i32`. Хост записывает
+//! JSON-аргумент в выделенный буфер и вызывает экспорт
+//! `fn(ptr: i32, len: i32) -> i32`; `0` означает успех:
+//! - `plugin_init() -> i32` — проверка при загрузке плагина
+//! - `handle_hook(ptr, len)` — событие `HookEvent`
+//! - `can_execute(ptr, len)` — `1`, если плагин выполнит задачу
+//! - `execute_task(ptr, len)` — выполнение задачи, результат — код выхода
+//! - `send_notification(ptr, len)` — отправка уведомления
+//!
+//! Хост-функции модуля `semaphore`: `log(level, ptr, len)` (0=error … 4=trace)
+//! и `output(ptr, len)` — строка вывода вызова (лог задачи или данные результата хука).
+//! Модули, собранные под `wasm32-wasi`, получают WASI preview1.
+//! Каждый вызов выполняется в новом инстансе с лимитами fuel и памяти.
 
 use crate::error::{Error, Result};
-use crate::plugins::base::{HookEvent, HookResult, PluginContext};
-use crate::plugins::wasm_loader::{LoadedWasmModule, WasmPluginLoader, WasmPluginMetadata};
-use serde_json::{Value as JsonValue, json};
+use crate::plugins::base::{HookEvent, HookResult, TaskResult};
+use crate::plugins::wasm_loader::{
+    LoadedWasmModule, WasmLoaderConfig, WasmPluginLoader, WasmPluginMetadata, build_wasi_context,
+};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Instant;
 use tracing::{debug, error, info, trace, warn};
-use wasmtime::{Engine, Store, Val};
-use wasmtime_wasi::WasiCtx;
+use wasmtime::{Caller, Engine, Extern, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::p1::WasiP1Ctx;
+
+/// Экспорт инициализации плагина
+pub const EXPORT_PLUGIN_INIT: &str = "plugin_init";
+/// Экспорт обработчика хуков
+pub const EXPORT_HANDLE_HOOK: &str = "handle_hook";
+/// Экспорт проверки возможности выполнить задачу
+pub const EXPORT_CAN_EXECUTE: &str = "can_execute";
+/// Экспорт выполнения задачи
+pub const EXPORT_EXECUTE_TASK: &str = "execute_task";
+/// Экспорт отправки уведомления
+pub const EXPORT_SEND_NOTIFICATION: &str = "send_notification";
 
 /// Контекст выполнения WASM плагина
 pub struct WasmPluginInstance {
     pub metadata: WasmPluginMetadata,
     pub store_data: PluginStoreData,
+    module: Module,
+}
+
+impl WasmPluginInstance {
+    /// Проверяет, экспортирует ли модуль функцию
+    pub fn exports(&self, name: &str) -> bool {
+        self.metadata.exports.iter().any(|e| e == name)
+    }
 }
 
 /// Данные хранилища плагина
@@ -31,13 +69,19 @@ pub struct PluginStoreData {
 pub struct WasmRuntime {
     engine: Engine,
     host_functions: HostFunctions,
+    linker: Arc<Linker<PluginStore>>,
+    config: WasmLoaderConfig,
+    sandbox: WasmSandbox,
 }
 
 /// Хранилище для WASM store
 pub struct PluginStore {
-    pub wasi: WasiCtx,
+    pub wasi: WasiP1Ctx,
     pub plugin_id: String,
     pub runtime_data: RuntimeData,
+    /// Строки, переданные плагином через `semaphore.output`
+    pub output: Vec<String>,
+    limits: StoreLimits,
 }
 
 /// Данные времени выполнения
@@ -48,6 +92,17 @@ pub struct RuntimeData {
     pub call_count: u64,
 }
 
+/// Результат вызова экспорта плагина
+#[derive(Debug, Clone)]
+pub struct WasmCallOutput {
+    /// Значение, возвращённое экспортом
+    pub code: i32,
+    /// Вывод плагина (`semaphore.output`)
+    pub output: Vec<String>,
+    /// Израсходованный fuel
+    pub fuel_consumed: u64,
+}
+
 /// Хост-функции доступные плагинам
 #[allow(clippy::type_complexity)]
 pub struct HostFunctions {
@@ -70,7 +125,7 @@ impl HostFunctions {
             }),
             config_getter: Arc::new(|_| None),
             config_setter: Arc::new(|_, _| Ok(())),
-            hook_caller: Arc::new(|_, _| Ok(json!(null))),
+            hook_caller: Arc::new(|_, _| Ok(JsonValue::Null)),
         }
     }
 
@@ -117,14 +172,176 @@ impl Default for HostFunctions {
     }
 }
 
+/// Читает строку из памяти плагина
+fn read_guest_string(caller: &mut Caller<'_, PluginStore>, ptr: i32, len: i32) -> Option<String> {
+    let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+        return None;
+    };
+    let mut buf = vec![0u8; usize::try_from(len).ok()?];
+    memory
+        .read(&*caller, usize::try_from(ptr).ok()?, &mut buf)
+        .ok()?;
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Линкер с WASI и разрешёнными хост-функциями `semaphore.*`
+fn build_linker(
+    engine: &Engine,
+    config: &WasmLoaderConfig,
+    host_functions: &HostFunctions,
+) -> Result<Linker<PluginStore>> {
+    let link_err = |e: wasmtime::Error| Error::Other(format!("Failed to link host functions: {e}"));
+    let allowed = |name: &str| config.allowed_host_calls.iter().any(|c| c == name);
+
+    let mut linker = Linker::new(engine);
+    wasmtime_wasi::p1::add_to_linker_sync(&mut linker, |s: &mut PluginStore| &mut s.wasi)
+        .map_err(link_err)?;
+
+    if allowed("semaphore:log") {
+        let log = host_functions.log_function.clone();
+        linker
+            .func_wrap(
+                "semaphore",
+                "log",
+                move |mut caller: Caller<'_, PluginStore>, level: i32, ptr: i32, len: i32| {
+                    if let Some(message) = read_guest_string(&mut caller, ptr, len) {
+                        let level = match level {
+                            0 => "error",
+                            1 => "warn",
+                            2 => "info",
+                            3 => "debug",
+                            _ => "trace",
+                        };
+                        log(level, &format!("{}: {}", caller.data().plugin_id, message));
+                    }
+                },
+            )
+            .map_err(link_err)?;
+    }
+
+    if allowed("semaphore:output") {
+        linker
+            .func_wrap(
+                "semaphore",
+                "output",
+                |mut caller: Caller<'_, PluginStore>, ptr: i32, len: i32| {
+                    if let Some(line) = read_guest_string(&mut caller, ptr, len) {
+                        caller.data_mut().output.push(line);
+                    }
+                },
+            )
+            .map_err(link_err)?;
+    }
+
+    Ok(linker)
+}
+
+/// Всё необходимое для вызова экспорта вне async-контекста
+struct Invocation {
+    engine: Engine,
+    linker: Arc<Linker<PluginStore>>,
+    module: Module,
+    config: WasmLoaderConfig,
+    sandbox: WasmSandbox,
+    plugin_id: String,
+}
+
+/// Синхронный вызов экспорта в новом инстансе модуля
+fn invoke(call: &Invocation, export: &str, payload: Option<&[u8]>) -> Result<WasmCallOutput> {
+    let Invocation {
+        engine,
+        linker,
+        module,
+        config,
+        sandbox,
+        plugin_id,
+    } = call;
+    let call_err =
+        |e: wasmtime::Error| Error::Other(format!("WASM plugin {plugin_id}: {export} failed: {e}"));
+
+    let mut store = Store::new(
+        engine,
+        PluginStore {
+            wasi: build_wasi_context(config)?,
+            plugin_id: plugin_id.to_string(),
+            runtime_data: RuntimeData {
+                fuel_limit: sandbox.max_fuel,
+                fuel_consumed: 0,
+                call_count: 0,
+            },
+            output: Vec::new(),
+            limits: StoreLimits::default(),
+        },
+    );
+    sandbox.apply_to_store(&mut store)?;
+
+    let instance = linker.instantiate(&mut store, module).map_err(call_err)?;
+
+    // WASI reactor-модули требуют инициализации перед вызовами
+    if let Ok(init) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+        init.call(&mut store, ()).map_err(call_err)?;
+    }
+
+    let code = match payload {
+        None => instance
+            .get_typed_func::<(), i32>(&mut store, export)
+            .map_err(call_err)?
+            .call(&mut store, ())
+            .map_err(call_err)?,
+        Some(bytes) => {
+            let memory = instance.get_memory(&mut store, "memory").ok_or_else(|| {
+                Error::Other(format!("WASM plugin {plugin_id}: no memory export"))
+            })?;
+            let len = i32::try_from(bytes.len())
+                .map_err(|_| Error::Validation("Plugin payload is too large".to_string()))?;
+            let ptr = instance
+                .get_typed_func::<i32, i32>(&mut store, "alloc")
+                .map_err(call_err)?
+                .call(&mut store, len)
+                .map_err(call_err)?;
+            memory
+                .write(&mut store, ptr as usize, bytes)
+                .map_err(|e| Error::Other(format!("WASM plugin {plugin_id}: {e}")))?;
+            instance
+                .get_typed_func::<(i32, i32), i32>(&mut store, export)
+                .map_err(call_err)?
+                .call(&mut store, (ptr, len))
+                .map_err(call_err)?
+        }
+    };
+
+    let fuel_left = store.get_fuel().unwrap_or_default();
+    Ok(WasmCallOutput {
+        code,
+        output: std::mem::take(&mut store.data_mut().output),
+        fuel_consumed: sandbox.max_fuel.saturating_sub(fuel_left),
+    })
+}
+
 impl WasmRuntime {
     /// Создаёт новый WASM runtime
     pub fn new(loader: &WasmPluginLoader) -> Result<Self> {
+        Self::with_host_functions(loader, HostFunctions::new())
+    }
+
+    /// Создаёт runtime с собственными хост-функциями
+    pub fn with_host_functions(
+        loader: &WasmPluginLoader,
+        host_functions: HostFunctions,
+    ) -> Result<Self> {
         let engine = loader.engine().clone();
+        let config = loader.config().clone();
+        let linker = build_linker(&engine, &config, &host_functions)?;
+        let sandbox = WasmSandbox::new()
+            .with_max_memory(u64::from(config.max_memory_pages) * 64 * 1024)
+            .with_max_fuel(config.max_fuel);
 
         Ok(Self {
             engine,
-            host_functions: HostFunctions::new(),
+            host_functions,
+            linker: Arc::new(linker),
+            config,
+            sandbox,
         })
     }
 
@@ -142,57 +359,112 @@ impl WasmRuntime {
                 fuel_consumed: 0,
                 calls_made: 0,
             },
+            module: module.module.clone(),
         })
     }
 
     /// Вызывает функцию экспортированную плагином
+    ///
+    /// Без `payload` вызывается `fn() -> i32`, иначе `payload` передаётся через
+    /// `alloc` в `fn(ptr, len) -> i32`. Вызов выполняется в blocking-потоке.
     pub async fn call_function(
         &self,
-        instance: &mut WasmPluginInstance,
+        instance: &WasmPluginInstance,
         function_name: &str,
-        args: &[Val],
-    ) -> Result<Vec<Val>> {
+        payload: Option<Vec<u8>>,
+    ) -> Result<WasmCallOutput> {
         debug!(
-            "Calling WASM function: {} with {} args",
-            function_name,
-            args.len()
+            "Calling WASM function: {} of plugin {}",
+            function_name, instance.store_data.plugin_id
         );
 
-        // В полной реализации здесь будет настоящий вызов WASM функции
-        // Для пока возвращаем заглушку
-        Ok(vec![])
+        let call = Invocation {
+            engine: self.engine.clone(),
+            linker: self.linker.clone(),
+            module: instance.module.clone(),
+            config: self.config.clone(),
+            sandbox: self.sandbox.clone(),
+            plugin_id: instance.store_data.plugin_id.clone(),
+        };
+        let export = function_name.to_string();
+
+        tokio::task::spawn_blocking(move || invoke(&call, &export, payload.as_deref()))
+            .await
+            .map_err(|e| Error::Other(format!("WASM call join error: {e}")))?
     }
 
     /// Вызывает хук в плагине
     pub async fn call_hook(
         &self,
-        instance: &mut WasmPluginInstance,
+        instance: &WasmPluginInstance,
         event: HookEvent,
     ) -> Result<HookResult> {
-        // В полной реализации будет вызов handle_hook
+        let payload = serde_json::to_vec(&event)?;
+        let result = self
+            .call_function(instance, EXPORT_HANDLE_HOOK, Some(payload))
+            .await?;
+
+        let output = result.output.join("\n");
+        let data = (!output.is_empty())
+            .then(|| serde_json::from_str(&output).unwrap_or(JsonValue::String(output.clone())));
         Ok(HookResult {
-            success: false,
-            data: None,
-            error: Some("Hook handler not implemented yet".to_string()),
+            success: result.code == 0,
+            data,
+            error: (result.code != 0).then(|| format!("handle_hook returned {}", result.code)),
         })
     }
 
     /// Проверяет может ли плагин выполнить задачу
+    ///
+    /// Плагин без `can_execute` выполняет любую задачу, если экспортирует `execute_task`.
     pub async fn can_execute_task(
         &self,
-        instance: &mut WasmPluginInstance,
+        instance: &WasmPluginInstance,
         task_json: &str,
     ) -> Result<bool> {
-        Ok(false)
+        if !instance.exports(EXPORT_EXECUTE_TASK) {
+            return Ok(false);
+        }
+        if !instance.exports(EXPORT_CAN_EXECUTE) {
+            return Ok(true);
+        }
+        let result = self
+            .call_function(
+                instance,
+                EXPORT_CAN_EXECUTE,
+                Some(task_json.as_bytes().to_vec()),
+            )
+            .await?;
+        Ok(result.code == 1)
     }
 
     /// Выполняет задачу в плагине
     pub async fn execute_task(
         &self,
-        instance: &mut WasmPluginInstance,
+        instance: &WasmPluginInstance,
         task_context: &str,
-    ) -> Result<JsonValue> {
-        Ok(json!({"success": true, "output": "Task executed"}))
+    ) -> Result<TaskResult> {
+        let started = Instant::now();
+        let result = self
+            .call_function(
+                instance,
+                EXPORT_EXECUTE_TASK,
+                Some(task_context.as_bytes().to_vec()),
+            )
+            .await?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "fuel_consumed".to_string(),
+            JsonValue::from(result.fuel_consumed),
+        );
+        Ok(TaskResult {
+            success: result.code == 0,
+            output: result.output.join("\n"),
+            exit_code: result.code,
+            duration_secs: started.elapsed().as_secs_f64(),
+            metadata,
+        })
     }
 
     /// Получает информацию о плагине
@@ -207,9 +479,15 @@ impl WasmRuntime {
     pub fn engine(&self) -> &Engine {
         &self.engine
     }
+
+    /// Хост-функции runtime
+    pub fn host_functions(&self) -> &HostFunctions {
+        &self.host_functions
+    }
 }
 
 /// Сэндбокс для безопасного выполнения WASM кода
+#[derive(Debug, Clone)]
 pub struct WasmSandbox {
     max_memory: u64,
     max_fuel: u64,
@@ -249,6 +527,10 @@ impl WasmSandbox {
         store
             .set_fuel(self.max_fuel)
             .map_err(|e| Error::Other(format!("Failed to set fuel limit: {}", e)))?;
+        store.data_mut().limits = StoreLimitsBuilder::new()
+            .memory_size(usize::try_from(self.max_memory).unwrap_or(usize::MAX))
+            .build();
+        store.limiter(|s| &mut s.limits);
         Ok(())
     }
 }
@@ -262,6 +544,56 @@ impl Default for WasmSandbox {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::plugins::base::PluginContext;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    /// Плагин: копирует вход в `output` и возвращает 0 (или 3 для `execute_task`)
+    const ECHO_PLUGIN: &str = r#"
+        (module
+          (import "semaphore" "output" (func $output (param i32 i32)))
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "plugin_init") (result i32) (i32.const 0))
+          (func (export "handle_hook") (param i32 i32) (result i32)
+            (call $output (local.get 0) (local.get 1))
+            (i32.const 0))
+          (func (export "execute_task") (param i32 i32) (result i32)
+            (call $output (local.get 0) (local.get 1))
+            (i32.const 3)))
+    "#;
+
+    /// Плагин с бесконечным циклом
+    const LOOP_PLUGIN: &str = r#"
+        (module
+          (func (export "plugin_init") (result i32)
+            (loop $l (br $l))
+            (i32.const 0)))
+    "#;
+
+    async fn load(wat: &str, sandbox_fuel: u64) -> (WasmRuntime, WasmPluginInstance, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("echo.wasm");
+        std::fs::write(&path, ::wat::parse_str(wat).unwrap()).unwrap();
+        let mut loader = WasmPluginLoader::new(WasmLoaderConfig {
+            plugins_dir: dir.path().to_path_buf(),
+            max_fuel: sandbox_fuel,
+            ..Default::default()
+        })
+        .unwrap();
+        loader.load_plugin(&path).await.unwrap();
+        let runtime = WasmRuntime::new(&loader).unwrap();
+        let instance = runtime
+            .create_instance(loader.get_module("echo").unwrap())
+            .await
+            .unwrap();
+        (runtime, instance, dir)
+    }
 
     #[test]
     fn test_sandbox_creation() {
@@ -272,4 +604,51 @@ mod tests {
         assert_eq!(sandbox.max_memory, 128 * 1024 * 1024);
         assert_eq!(sandbox.max_fuel, 2_000_000);
     }
+
+    #[tokio::test]
+    async fn test_call_hook_passes_event_and_collects_output() {
+        let (runtime, instance, _dir) = load(ECHO_PLUGIN, 1_000_000).await;
+        let event = HookEvent {
+            name: "task.after_complete".to_string(),
+            timestamp: Utc::now(),
+            data: serde_json::json!({"task_id": 7}),
+            context: PluginContext {
+                plugin_id: "echo".to_string(),
+                project_id: Some(1),
+                user_id: None,
+                task_id: Some(7),
+                metadata: HashMap::new(),
+            },
+        };
+
+        let result = runtime.call_hook(&instance, event).await.unwrap();
+        assert!(result.success);
+        let data = result.data.unwrap();
+        assert_eq!(data["name"], "task.after_complete");
+        assert_eq!(data["data"]["task_id"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_execute_task_returns_exit_code() {
+        let (runtime, instance, _dir) = load(ECHO_PLUGIN, 1_000_000).await;
+        assert!(runtime.can_execute_task(&instance, "{}").await.unwrap());
+
+        let result = runtime
+            .execute_task(&instance, r#"{"task":{"id":1}}"#)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(result.output, r#"{"task":{"id":1}}"#);
+    }
+
+    #[tokio::test]
+    async fn test_fuel_limit_stops_runaway_plugin() {
+        let (runtime, instance, _dir) = load(LOOP_PLUGIN, 10_000).await;
+        let err = runtime
+            .call_function(&instance, EXPORT_PLUGIN_INIT, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("plugin_init failed"));
+    }
 }
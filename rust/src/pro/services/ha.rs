@@ -2,8 +2,12 @@
 //!
 //! PRO модуль для High Availability режима
 
+use std::sync::{Arc, Mutex};
+
 use crate::db::store::Store;
 use crate::error::Result;
+use crate::services::leader_election;
+use crate::services::task_reaper::{self, TaskReaperConfig};
 
 // ============================================================================
 // Node Registry
@@ -24,17 +28,13 @@ pub trait NodeRegistry: Send + Sync {
     fn node_id(&self) -> String;
 }
 
-/// Базовая реализация Node Registry (заглушка)
-pub struct BasicNodeRegistry {
-    node_id: String,
-}
+/// Базовая реализация Node Registry (один узел)
+pub struct BasicNodeRegistry;
 
 impl BasicNodeRegistry {
     /// Создаёт новый реестр узлов
     pub fn new() -> Self {
-        Self {
-            node_id: uuid::Uuid::new_v4().to_string(),
-        }
+        Self
     }
 }
 
@@ -60,7 +60,7 @@ impl NodeRegistry for BasicNodeRegistry {
     }
 
     fn node_id(&self) -> String {
-        self.node_id.clone()
+        task_reaper::node_id().to_string()
     }
 }
 
@@ -77,25 +77,42 @@ pub trait OrphanCleaner: Send + Sync {
     fn stop(&self);
 }
 
-/// Базовая реализация Orphan Cleaner (заглушка)
+/// Базовая реализация Orphan Cleaner: фоновая проверка `services::task_reaper`
 pub struct BasicOrphanCleaner {
-    _store: Box<dyn Store + Send + Sync>,
+    store: Arc<dyn Store + Send + Sync>,
+    config: TaskReaperConfig,
+    handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
 }
 
 impl BasicOrphanCleaner {
     /// Создаёт новую очистку осиротевших задач
-    pub fn new(store: Box<dyn Store + Send + Sync>) -> Self {
-        Self { _store: store }
+    pub fn new(store: Arc<dyn Store + Send + Sync>) -> Self {
+        Self {
+            store,
+            config: TaskReaperConfig::default(),
+            handle: Mutex::new(None),
+        }
     }
 }
 
 impl OrphanCleaner for BasicOrphanCleaner {
     fn start(&self) {
-        // В базовой версии ничего не делаем
+        let Ok(mut handle) = self.handle.lock() else {
+            return;
+        };
+        if handle.is_none() {
+            *handle = Some(task_reaper::spawn_task_reaper(
+                self.store.clone(),
+                self.config.clone(),
+                leader_election::leadership(),
+            ));
+        }
     }
 
     fn stop(&self) {
-        // В базовой версии ничего не делаем
+        if let Some(handle) = self.handle.lock().ok().and_then(|mut h| h.take()) {
+            handle.abort();
+        }
     }
 }
 
@@ -109,7 +126,7 @@ pub fn new_node_registry() -> Box<dyn NodeRegistry> {
 }
 
 /// Создаёт новый Orphan Cleaner
-pub fn new_orphan_cleaner(store: Box<dyn Store + Send + Sync>) -> Box<dyn OrphanCleaner> {
+pub fn new_orphan_cleaner(store: Arc<dyn Store + Send + Sync>) -> Box<dyn OrphanCleaner> {
     Box::new(BasicOrphanCleaner::new(store))
 }
 
@@ -135,10 +152,12 @@ mod tests {
         registry.stop();
     }
 
-    #[test]
-    fn test_basic_orphan_cleaner_creation() {
-        // Создаём mock store (заглушку)
-        // В реальном тесте нужен настоящий mock
-        assert!(true);
+    #[tokio::test]
+    async fn test_basic_orphan_cleaner_start_stop() {
+        let cleaner = new_orphan_cleaner(Arc::new(crate::db::mock::MockStore::new()));
+        cleaner.start();
+        cleaner.start();
+        cleaner.stop();
+        cleaner.stop();
     }
 }
@@ -472,6 +472,62 @@ impl AlertService {
         Ok(())
     }
 
+    /// Отправляет событие в PagerDuty (Events API v2)
+    ///
+    /// Ключ интеграции передаётся параметром `routing_key` в URL, например
+    /// `https://events.pagerduty.com/v2/enqueue?routing_key=<key>`. Инциденты
+    /// группируются по шаблону: успешный запуск закрывает инцидент,
+    /// открытый предыдущим падением.
+    pub async fn send_pagerduty_alert(&self, events_url: &str) -> Result<()> {
+        let mut url = reqwest::Url::parse(events_url)
+            .map_err(|e| Error::Config(format!("Invalid PagerDuty URL: {e}")))?;
+        let routing_key = url
+            .query_pairs()
+            .find(|(k, _)| k == "routing_key")
+            .map(|(_, v)| v.into_owned())
+            .ok_or_else(|| {
+                Error::Config("PagerDuty URL must contain routing_key parameter".to_string())
+            })?;
+        url.set_query(None);
+
+        let alert = self.create_alert();
+        let (event_action, severity) = match self.task.status {
+            TaskStatus::Success => ("resolve", "info"),
+            TaskStatus::Error => ("trigger", "error"),
+            TaskStatus::Stopped => ("trigger", "warning"),
+            _ => ("trigger", "info"),
+        };
+        let payload = serde_json::json!({
+            "routing_key": routing_key,
+            "event_action": event_action,
+            "dedup_key": format!("velum-{}-{}", self.task.project_id, self.task.template_id),
+            "payload": {
+                "summary": format!("{}: task #{} {}", alert.name, alert.task.id, alert.task.result),
+                "source": crate::config::get_public_host(),
+                "severity": severity,
+                "custom_details": {
+                    "author": alert.author,
+                    "version": alert.task.version,
+                    "description": alert.task.desc,
+                },
+            },
+            "links": [{"href": alert.task.url, "text": "View Task"}],
+        });
+
+        let response = self.client.post(url).json(&payload).send().await?;
+
+        if !response.status().is_success() {
+            return Err(Error::Other(format!(
+                "PagerDuty API error {}: {}",
+                response.status(),
+                response.text().await?
+            )));
+        }
+
+        info!("PagerDuty alert sent");
+        Ok(())
+    }
+
     /// Отправляет Gotify уведомление
     pub async fn send_gotify_alert(&self, server_url: &str, app_token: &str) -> Result<()> {
         let alert = self.create_alert();
@@ -1,6 +1,6 @@
 //! Auto Backup Service - автоматическое резервное копирование
 //!
-//! Планировщик регулярных бэкапов проектов
+//! Планировщик регулярных бэкапов проектов. В кластере бэкап делает только узел-лидер.
 
 use chrono::{DateTime, Utc};
 use std::sync::Arc;
@@ -11,6 +11,7 @@ use tracing::{error, info, instrument, warn};
 use crate::db::store::Store;
 use crate::error::Result;
 use crate::services::backup::BackupFormat;
+use crate::services::leader_election::Leadership;
 
 /// Конфигурация автобэкапа
 #[derive(Debug, Clone)]
@@ -56,6 +57,8 @@ pub struct AutoBackupService {
     store: Arc<dyn Store + Send + Sync>,
     stats: Arc<RwLock<BackupStats>>,
     running: Arc<RwLock<bool>>,
+    /// Лидерство узла: бэкапы делает только лидер
+    leadership: Leadership,
 }
 
 impl AutoBackupService {
@@ -66,9 +69,16 @@ impl AutoBackupService {
             store,
             stats: Arc::new(RwLock::new(BackupStats::default())),
             running: Arc::new(RwLock::new(false)),
+            leadership: Leadership::standalone(),
         }
     }
 
+    /// Делает бэкапы только при лидерстве узла в кластере
+    pub fn with_leadership(mut self, leadership: Leadership) -> Self {
+        self.leadership = leadership;
+        self
+    }
+
     /// Запускает сервис автобэкапа
     pub async fn start(&self) {
         if !self.config.enabled {
@@ -89,8 +99,10 @@ impl AutoBackupService {
         let store = Arc::clone(&self.store);
         let stats = Arc::clone(&self.stats);
         let running = Arc::clone(&self.running);
+        let leadership = self.leadership.clone();
 
         tokio::spawn(async move {
+            let mut leader = leadership.subscribe();
             loop {
                 // Проверка флага остановки
                 {
@@ -100,6 +112,14 @@ impl AutoBackupService {
                     }
                 }
 
+                // Бэкап делает лидер кластера; остальные узлы ждут лидерства
+                if !*leader.borrow_and_update() {
+                    if leader.changed().await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+
                 // Выполнение бэкапа
                 match Self::run_backup(&config, &store, &stats).await {
                     Ok(_) => {
@@ -0,0 +1,401 @@
+//! Группы параллелизма (Concurrency Groups)
+//!
+//! Задача занимает слот в каждой группе, в которую входит:
+//! - именованная группа шаблона (`Template::concurrency_group`);
+//! - именованная группа окружения деплоя шаблона;
+//! - собственная группа шаблона размером 1, если `allow_parallel_tasks = false`.
+//!
+//! Лимиты именованных групп задаются в таблице `concurrency_group`;
+//! группа без настройки работает как мьютекс. Занятые слоты хранятся в
+//! таблице `concurrency_slot` и общие для всех узлов и раннеров.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::db::store::Store;
+use crate::error::Result;
+use crate::models::concurrency_group::DEFAULT_GROUP_LIMIT;
+use crate::models::{DeploymentEnvironment, SlotClaim, SlotRequest, Task, TaskClaimer, Template};
+
+/// Слот в группе параллелизма
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConcurrencySlot {
+    /// Ключ группы (уникален в кластере)
+    pub key: String,
+    /// Имя группы для пользователя
+    pub name: String,
+    /// Максимум одновременно выполняемых задач
+    pub limit: usize,
+}
+
+impl ConcurrencySlot {
+    /// Именованная группа проекта
+    pub fn named(project_id: i32, name: &str, limit: i32) -> Self {
+        Self {
+            key: format!("project:{}:group:{}", project_id, name),
+            name: name.to_string(),
+            limit: limit.max(1) as usize,
+        }
+    }
+
+    /// Группа шаблона без параллельного запуска
+    pub fn template(project_id: i32, template_id: i32) -> Self {
+        Self {
+            key: format!("project:{}:template:{}", project_id, template_id),
+            name: format!("template #{}", template_id),
+            limit: 1,
+        }
+    }
+}
+
+/// Причина, по которой задача ждёт в очереди
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockedBy {
+    pub group: String,
+    pub task_id: i32,
+}
+
+impl fmt::Display for BlockedBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "blocked by group {} / task #{}",
+            self.group, self.task_id
+        )
+    }
+}
+
+/// Слоты задачи по шаблону, окружению деплоя и лимитам групп проекта
+pub fn template_slots(
+    template: &Template,
+    environment: Option<&DeploymentEnvironment>,
+    limits: &HashMap<String, i32>,
+) -> Vec<ConcurrencySlot> {
+    let mut slots = Vec::new();
+    if !template.allow_parallel_tasks {
+        slots.push(ConcurrencySlot::template(template.project_id, template.id));
+    }
+
+    let groups = [
+        template.concurrency_group.as_deref(),
+        environment.and_then(|e| e.concurrency_group.as_deref()),
+    ];
+    for name in groups.into_iter().flatten().filter(|n| !n.is_empty()) {
+        let limit = limits.get(name).copied().unwrap_or(DEFAULT_GROUP_LIMIT);
+        let slot = ConcurrencySlot::named(template.project_id, name, limit);
+        if !slots.iter().any(|s| s.key == slot.key) {
+            slots.push(slot);
+        }
+    }
+    slots
+}
+
+/// Находит первую заполненную группу среди слотов задачи
+pub fn find_blocker(
+    task_id: i32,
+    slots: &[ConcurrencySlot],
+    holders: &HashMap<String, Vec<i32>>,
+) -> Option<BlockedBy> {
+    slots.iter().find_map(|slot| {
+        let held: Vec<i32> = holders
+            .get(&slot.key)
+            .map(|ids| ids.iter().copied().filter(|id| *id != task_id).collect())
+            .unwrap_or_default();
+        (held.len() >= slot.limit).then(|| BlockedBy {
+            group: slot.name.clone(),
+            task_id: held[0],
+        })
+    })
+}
+
+/// Определяет группы задач с кэшированием шаблонов и лимитов
+pub struct GroupResolver<'a> {
+    store: &'a dyn Store,
+    templates: HashMap<(i32, i32), Option<Template>>,
+    environments: HashMap<(i32, i32), Option<DeploymentEnvironment>>,
+    limits: HashMap<i32, HashMap<String, i32>>,
+}
+
+impl<'a> GroupResolver<'a> {
+    pub fn new(store: &'a dyn Store) -> Self {
+        Self {
+            store,
+            templates: HashMap::new(),
+            environments: HashMap::new(),
+            limits: HashMap::new(),
+        }
+    }
+
+    /// Слоты, которые займёт задача при запуске
+    pub async fn slots(&mut self, task: &Task) -> Vec<ConcurrencySlot> {
+        let project_id = task.project_id;
+        let key = (project_id, task.template_id);
+        if !self.templates.contains_key(&key) {
+            let template = self
+                .store
+                .get_template(project_id, task.template_id)
+                .await
+                .ok();
+            self.templates.insert(key, template);
+        }
+        let Some(template) = self.templates[&key].clone() else {
+            return Vec::new();
+        };
+
+        let environment = match template.deploy_environment_id {
+            Some(env_id) => {
+                let env_key = (project_id, env_id);
+                if !self.environments.contains_key(&env_key) {
+                    let env = self
+                        .store
+                        .get_deployment_environment(env_id, project_id)
+                        .await
+                        .ok();
+                    self.environments.insert(env_key, env);
+                }
+                self.environments[&env_key].clone()
+            }
+            None => None,
+        };
+
+        if !self.limits.contains_key(&project_id) {
+            let limits = self
+                .store
+                .get_concurrency_groups(project_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|g| (g.name, g.max_parallel))
+                .collect();
+            self.limits.insert(project_id, limits);
+        }
+
+        template_slots(&template, environment.as_ref(), &self.limits[&project_id])
+    }
+}
+
+/// Итог попытки забрать задачу из очереди
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Claim {
+    /// Слоты заняты, задача переведена в `running`
+    Claimed,
+    /// Группа параллелизма заполнена — задача остаётся в очереди
+    Blocked(BlockedBy),
+    /// Задачу уже забрал другой узел или раннер
+    Taken,
+}
+
+/// Занимает слоты групп задачи и забирает её одной операцией в БД
+///
+/// Слоты хранятся в БД, поэтому лимиты групп соблюдаются всеми узлами
+/// кластера и удалёнными раннерами. Слот освобождается вместе с выходом
+/// задачи из статусов выполнения.
+pub async fn claim_with_slots(
+    store: &dyn Store,
+    task: &Task,
+    claimer: TaskClaimer,
+) -> Result<Claim> {
+    let slots = GroupResolver::new(store).slots(task).await;
+    let requests: Vec<SlotRequest> = slots
+        .iter()
+        .map(|s| SlotRequest {
+            group_key: s.key.clone(),
+            limit: s.limit as i32,
+        })
+        .collect();
+
+    let claim = match store
+        .claim_task_with_slots(task.id, claimer, &requests)
+        .await?
+    {
+        SlotClaim::Claimed => Claim::Claimed,
+        SlotClaim::NotWaiting => Claim::Taken,
+        SlotClaim::Blocked { group_key, task_id } => {
+            let group = slots
+                .iter()
+                .find(|s| s.key == group_key)
+                .map(|s| s.name.clone())
+                .unwrap_or(group_key);
+            let blocked = BlockedBy { group, task_id };
+            debug!("[concurrency] task {}: {}", task.id, blocked);
+            Claim::Blocked(blocked)
+        }
+    };
+    Ok(claim)
+}
+
+/// Занятые слоты: ключ группы → задачи
+async fn slot_holders(store: &dyn Store) -> HashMap<String, Vec<i32>> {
+    let mut holders: HashMap<String, Vec<i32>> = HashMap::new();
+    for holder in store
+        .get_concurrency_slot_holders()
+        .await
+        .unwrap_or_default()
+    {
+        holders
+            .entry(holder.group_key)
+            .or_default()
+            .push(holder.task_id);
+    }
+    holders
+}
+
+/// Причина ожидания для каждой задачи очереди (по текущим занятым слотам)
+pub async fn blocked_reasons(store: &dyn Store, tasks: &[Task]) -> HashMap<i32, BlockedBy> {
+    let holders = slot_holders(store).await;
+    let mut resolver = GroupResolver::new(store);
+    let mut reasons = HashMap::new();
+    for task in tasks {
+        let slots = resolver.slots(task).await;
+        if let Some(blocked) = find_blocker(task.id, &slots, &holders) {
+            reasons.insert(task.id, blocked);
+        }
+    }
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{ConcurrencyGroupManager, TaskManager, TemplateManager};
+    use crate::models::ConcurrencyGroupUpsert;
+    use crate::services::task_logger::TaskStatus;
+
+    fn template(id: i32, parallel: bool, group: Option<&str>) -> Template {
+        Template {
+            id,
+            project_id: 1,
+            allow_parallel_tasks: parallel,
+            concurrency_group: group.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn environment(group: &str) -> DeploymentEnvironment {
+        DeploymentEnvironment {
+            id: 1,
+            project_id: 1,
+            name: "production".to_string(),
+            url: None,
+            tier: "production".to_string(),
+            status: "active".to_string(),
+            template_id: None,
+            last_task_id: None,
+            last_deploy_version: None,
+            last_deployed_by: None,
+            concurrency_group: Some(group.to_string()),
+            created: chrono::Utc::now(),
+            updated: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_template_slots_serial_template_is_group_of_one() {
+        let slots = template_slots(&template(5, false, None), None, &HashMap::new());
+        assert_eq!(slots, vec![ConcurrencySlot::template(1, 5)]);
+        assert!(template_slots(&template(5, true, None), None, &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_template_slots_merge_template_and_environment_groups() {
+        let limits = HashMap::from([("prod-db".to_string(), 3)]);
+        let env = environment("prod-db");
+        let slots = template_slots(&template(5, true, Some("prod-db")), Some(&env), &limits);
+        assert_eq!(slots.len(), 1);
+        assert_eq!(slots[0].limit, 3);
+
+        let env = environment("prod-net");
+        let slots = template_slots(&template(5, true, Some("prod-db")), Some(&env), &limits);
+        let names: Vec<&str> = slots.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["prod-db", "prod-net"]);
+        // Группа без настройки — мьютекс
+        assert_eq!(slots[1].limit, 1);
+    }
+
+    #[test]
+    fn test_find_blocker_respects_limit() {
+        let slot = ConcurrencySlot::named(1, "prod-db", 2);
+        let mut holders = HashMap::from([(slot.key.clone(), vec![10])]);
+        assert!(find_blocker(11, std::slice::from_ref(&slot), &holders).is_none());
+
+        holders.get_mut(&slot.key).unwrap().push(12);
+        let blocked = find_blocker(11, std::slice::from_ref(&slot), &holders).unwrap();
+        assert_eq!(blocked.to_string(), "blocked by group prod-db / task #10");
+    }
+
+    #[tokio::test]
+    async fn test_group_resolver_uses_project_limits() {
+        let store = MockStore::new();
+        store
+            .create_template(template(7, true, Some("resolver-db")))
+            .await
+            .unwrap();
+        store
+            .upsert_concurrency_group(
+                1,
+                ConcurrencyGroupUpsert {
+                    name: "resolver-db".to_string(),
+                    max_parallel: 4,
+                },
+            )
+            .await
+            .unwrap();
+
+        let task = Task {
+            id: 1,
+            project_id: 1,
+            template_id: 7,
+            ..Default::default()
+        };
+        let slots = GroupResolver::new(&store).slots(&task).await;
+        assert_eq!(slots, vec![ConcurrencySlot::named(1, "resolver-db", 4)]);
+    }
+
+    #[tokio::test]
+    async fn test_claim_with_slots_blocks_until_holder_finishes() {
+        let store = MockStore::new();
+        store
+            .create_template(template(8, true, Some("wait-db")))
+            .await
+            .unwrap();
+        let mut tasks = Vec::new();
+        for id in [9101, 9102] {
+            let task = store
+                .create_task(Task {
+                    id,
+                    ..Task::new_waiting(8, 1)
+                })
+                .await
+                .unwrap();
+            tasks.push(task);
+        }
+        let node = || TaskClaimer::Node("node-a".to_string());
+
+        let claim = claim_with_slots(&store, &tasks[0], node()).await.unwrap();
+        assert_eq!(claim, Claim::Claimed);
+        let reasons = blocked_reasons(&store, &tasks[1..]).await;
+        assert_eq!(
+            reasons[&9102].to_string(),
+            "blocked by group wait-db / task #9101"
+        );
+        // Слот общий для узлов сервера и раннеров
+        let claim = claim_with_slots(&store, &tasks[1], TaskClaimer::Runner(1))
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::Blocked(b) if b.task_id == 9101));
+
+        store
+            .update_task_status(1, 9101, TaskStatus::Success)
+            .await
+            .unwrap();
+        assert!(blocked_reasons(&store, &tasks[1..]).await.is_empty());
+        let claim = claim_with_slots(&store, &tasks[1], node()).await.unwrap();
+        assert_eq!(claim, Claim::Claimed);
+        let claim = claim_with_slots(&store, &tasks[1], node()).await.unwrap();
+        assert_eq!(claim, Claim::Taken);
+    }
+}
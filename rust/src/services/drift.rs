@@ -0,0 +1,627 @@
+//! Drift Detection — проверка расхождения инфраструктуры с кодом
+//!
+//! Проверка дрейфа ставит в очередь задачу шаблона в check-режиме:
+//! - Ansible — `--check --diff` (`task.params`: `dry_run`, `diff`);
+//! - Terraform / OpenTofu / Terragrunt — только `plan -detailed-exitcode`
+//!   (`task.params.plan`).
+//!
+//! Задача отмечена флагом `task.params.drift_check` и ничего не меняет:
+//! хуки шаблона, подтверждение плана и продвижение сборки для неё пропускаются.
+//!
+//! Задачу выполняет пул задач любого узла. Фоновая проверка потерянных задач
+//! (см. `task_reaper`) подводит итог завершившихся проверок: вывод задачи
+//! классифицируется как `clean`, `drifted` или `error`, результат
+//! `DriftResult` обновляется, а при переходе в состояние `drifted`
+//! срабатывают политики уведомлений с триггером `on_drift`. Итог хранится
+//! только в БД, поэтому проверки не зависают после рестарта узла.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use regex::Regex;
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::drift::{DriftConfig, DriftResult};
+use crate::models::template::TemplateApp;
+use crate::models::{Task, Template};
+use crate::plugins::{self, NotificationLevel};
+use crate::services::task_logger::TaskStatus;
+
+/// Статус результата, пока задача проверки не завершилась
+pub const DRIFT_PENDING: &str = "pending";
+/// Триггер политики уведомлений для обнаруженного дрейфа
+pub const DRIFT_NOTIFY_TRIGGER: &str = "on_drift";
+/// Ключ `task.params`, отмечающий задачу проверки дрейфа
+pub const DRIFT_CHECK_PARAM: &str = "drift_check";
+
+/// Итог проверки дрейфа
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftStatus {
+    Clean,
+    Drifted,
+    Error,
+}
+
+impl DriftStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftStatus::Clean => "clean",
+            DriftStatus::Drifted => "drifted",
+            DriftStatus::Error => "error",
+        }
+    }
+}
+
+/// Классифицированный результат проверки
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriftOutcome {
+    pub status: DriftStatus,
+    pub summary: String,
+}
+
+impl DriftOutcome {
+    fn new(status: DriftStatus, summary: impl Into<String>) -> Self {
+        Self {
+            status,
+            summary: summary.into(),
+        }
+    }
+}
+
+/// Параметры задачи check-режима для приложения шаблона
+///
+/// Флаг [`DRIFT_CHECK_PARAM`] отмечает задачу как проверку дрейфа.
+pub fn drift_check_params(app: &TemplateApp) -> Result<Value> {
+    match app {
+        TemplateApp::Ansible => Ok(json!({"dry_run": true, "diff": true, DRIFT_CHECK_PARAM: true})),
+        TemplateApp::Terraform | TemplateApp::Tofu | TemplateApp::Terragrunt => {
+            Ok(json!({"plan": true, DRIFT_CHECK_PARAM: true}))
+        }
+        other => Err(Error::Validation(format!(
+            "Drift checks are not supported for {other} templates"
+        ))),
+    }
+}
+
+/// Классифицирует вывод `ansible-playbook --check --diff` по PLAY RECAP
+pub fn classify_ansible_output(lines: &[String]) -> DriftOutcome {
+    let recap =
+        Regex::new(r"(\S+)\s+:\s+ok=(\d+)\s+changed=(\d+)\s+unreachable=(\d+)\s+failed=(\d+)")
+            .expect("valid recap regex");
+
+    let mut changed = Vec::new();
+    let mut broken = Vec::new();
+    let mut hosts = 0;
+    for caps in lines.iter().filter_map(|l| recap.captures(l)) {
+        hosts += 1;
+        let count = |i: usize| caps[i].parse::<u32>().unwrap_or(0);
+        if count(4) > 0 || count(5) > 0 {
+            broken.push(caps[1].to_string());
+        } else if count(3) > 0 {
+            changed.push(format!("{} (changed={})", &caps[1], count(3)));
+        }
+    }
+
+    if hosts == 0 {
+        DriftOutcome::new(DriftStatus::Error, "No PLAY RECAP found in check output")
+    } else if !broken.is_empty() {
+        DriftOutcome::new(
+            DriftStatus::Error,
+            format!("Unreachable or failed hosts: {}", broken.join(", ")),
+        )
+    } else if !changed.is_empty() {
+        DriftOutcome::new(
+            DriftStatus::Drifted,
+            format!(
+                "{} of {hosts} host(s) drifted: {}",
+                changed.len(),
+                changed.join(", ")
+            ),
+        )
+    } else {
+        DriftOutcome::new(
+            DriftStatus::Clean,
+            format!("{hosts} host(s) match the playbook"),
+        )
+    }
+}
+
+/// Классифицирует вывод `terraform plan`
+pub fn classify_terraform_output(lines: &[String]) -> DriftOutcome {
+    let plan = Regex::new(r"Plan: (\d+) to add, (\d+) to change, (\d+) to destroy")
+        .expect("valid plan regex");
+
+    if let Some(caps) = lines.iter().rev().find_map(|l| plan.captures(l)) {
+        return DriftOutcome::new(
+            DriftStatus::Drifted,
+            format!(
+                "{} to add, {} to change, {} to destroy",
+                &caps[1], &caps[2], &caps[3]
+            ),
+        );
+    }
+    if lines
+        .iter()
+        .any(|l| l.contains("Objects have changed outside of"))
+    {
+        return DriftOutcome::new(
+            DriftStatus::Drifted,
+            "Objects have changed outside of the configuration",
+        );
+    }
+    if lines.iter().any(|l| l.contains("No changes.")) {
+        return DriftOutcome::new(
+            DriftStatus::Clean,
+            "No changes, infrastructure matches the configuration",
+        );
+    }
+    DriftOutcome::new(DriftStatus::Error, "Could not find plan summary in output")
+}
+
+/// Классифицирует завершённую задачу проверки
+pub fn classify_drift(app: &TemplateApp, status: TaskStatus, lines: &[String]) -> DriftOutcome {
+    if status != TaskStatus::Success {
+        let last = lines
+            .iter()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| format!(": {}", l.trim()))
+            .unwrap_or_default();
+        return DriftOutcome::new(
+            DriftStatus::Error,
+            format!("Check task finished with status {status}{last}"),
+        );
+    }
+    match app {
+        TemplateApp::Ansible => classify_ansible_output(lines),
+        _ => classify_terraform_output(lines),
+    }
+}
+
+/// Задача является проверкой дрейфа
+///
+/// Такая задача только читает состояние: хуки шаблона, подтверждение плана и
+/// продвижение сборки для неё не выполняются.
+pub fn is_drift_check(task: &Task) -> bool {
+    task.params
+        .as_ref()
+        .and_then(|p| p.get(DRIFT_CHECK_PARAM))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Создаёт задачу проверки дрейфа для шаблона
+pub fn build_drift_task(template: &Template, params: Value) -> Task {
+    Task {
+        message: Some("Drift check".to_string()),
+        params: Some(params),
+        ..Task::new_waiting(template.id, template.project_id)
+    }
+}
+
+/// Запускает проверку дрейфа: ставит задачу в очередь и записывает `pending`-результат
+///
+/// Итог подводит [`settle_finished_checks`] после завершения задачи.
+pub async fn start_drift_check(
+    store: Arc<dyn Store + Send + Sync>,
+    config: &DriftConfig,
+) -> Result<DriftResult> {
+    let template = store
+        .get_template(config.project_id, config.template_id)
+        .await?;
+    let params = drift_check_params(&template.app)?;
+
+    let task = store
+        .create_task(build_drift_task(&template, params))
+        .await?;
+    let result = store
+        .create_drift_result(
+            config.project_id,
+            config.id,
+            config.template_id,
+            DRIFT_PENDING,
+            None,
+            Some(task.id),
+        )
+        .await?;
+    info!(
+        "Drift config {}: check task {} queued for template {}",
+        config.id, task.id, template.id
+    );
+
+    crate::services::task_execution::wake_dispatcher();
+
+    Ok(result)
+}
+
+/// Подводит итог `pending`-проверок, чьи задачи завершились
+///
+/// Проверка без задачи (задача удалена) завершается ошибкой. Возвращает
+/// число закрытых проверок.
+pub async fn settle_finished_checks(store: Arc<dyn Store + Send + Sync>) -> usize {
+    let pending = match store.get_pending_drift_results().await {
+        Ok(pending) => pending,
+        Err(e) => {
+            warn!("Failed to load pending drift checks: {e}");
+            return 0;
+        }
+    };
+    let mut settled = 0;
+    for result in pending {
+        let task = match result.task_id {
+            Some(task_id) => store.get_task(result.project_id, task_id).await.ok(),
+            None => None,
+        };
+        let outcome = match task {
+            Some(task) if !task.status.is_finished() => continue,
+            Some(task) => match store
+                .get_drift_config(result.drift_config_id, result.project_id)
+                .await
+            {
+                Ok(config) => finish_drift_check(store.clone(), &config, result.id, task.id)
+                    .await
+                    .map(|_| ()),
+                Err(e) => Err(e),
+            },
+            None => {
+                store
+                    .update_drift_result(
+                        result.id,
+                        DriftStatus::Error.as_str(),
+                        Some("Check task not found".to_string()),
+                    )
+                    .await
+            }
+        };
+        match outcome {
+            Ok(()) => settled += 1,
+            Err(e) => error!(
+                "Drift config {}: failed to record result: {e}",
+                result.drift_config_id
+            ),
+        }
+    }
+    settled
+}
+
+/// Есть ли у конфигурации незавершённая проверка
+pub async fn drift_check_in_progress(
+    store: &(dyn Store + Send + Sync),
+    config: &DriftConfig,
+) -> bool {
+    let Ok(latest) = store.get_drift_results(config.id, 1).await else {
+        return false;
+    };
+    let Some(result) = latest.into_iter().next() else {
+        return false;
+    };
+    if result.status != DRIFT_PENDING {
+        return false;
+    }
+    let Some(task_id) = result.task_id else {
+        return false;
+    };
+    matches!(
+        store.get_task(config.project_id, task_id).await,
+        Ok(task) if !task.status.is_finished()
+    )
+}
+
+/// Классифицирует завершённую задачу проверки и обновляет результат
+pub async fn finish_drift_check(
+    store: Arc<dyn Store + Send + Sync>,
+    config: &DriftConfig,
+    result_id: i32,
+    task_id: i32,
+) -> Result<DriftOutcome> {
+    let task = store.get_task(config.project_id, task_id).await?;
+    let template = store
+        .get_template(config.project_id, config.template_id)
+        .await?;
+    let lines: Vec<String> = store
+        .get_task_outputs(task_id)
+        .await?
+        .into_iter()
+        .map(|o| o.output)
+        .collect();
+    let outcome = classify_drift(&template.app, task.status, &lines);
+
+    let previous = store
+        .get_drift_results(config.id, 10)
+        .await?
+        .into_iter()
+        .find(|r| r.id != result_id && r.status != DRIFT_PENDING);
+
+    store
+        .update_drift_result(
+            result_id,
+            outcome.status.as_str(),
+            Some(outcome.summary.clone()),
+        )
+        .await?;
+    info!(
+        "Drift config {}: {} ({})",
+        config.id,
+        outcome.status.as_str(),
+        outcome.summary
+    );
+
+    let newly_drifted = outcome.status == DriftStatus::Drifted
+        && previous.is_none_or(|p| p.status != DriftStatus::Drifted.as_str());
+    if newly_drifted {
+        notify_drift(store.as_ref(), config, &template, task_id, &outcome).await;
+    }
+
+    Ok(outcome)
+}
+
+/// Отправляет уведомления политикам `on_drift` проекта
+async fn notify_drift(
+    store: &(dyn Store + Send + Sync),
+    config: &DriftConfig,
+    template: &Template,
+    task_id: i32,
+    outcome: &DriftOutcome,
+) {
+    let policies = match store
+        .get_matching_policies(config.project_id, DRIFT_NOTIFY_TRIGGER, Some(template.id))
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            warn!(
+                "Drift config {}: failed to load notification policies: {e}",
+                config.id
+            );
+            return;
+        }
+    };
+    if policies.is_empty() {
+        return;
+    }
+
+    let payload = json!({
+        "text": format!("[Velum] Drift detected in template '{}': {}", template.name, outcome.summary),
+        "event": "drift",
+        "project_id": config.project_id,
+        "template_id": template.id,
+        "drift_config_id": config.id,
+        "task_id": task_id,
+        "summary": outcome.summary,
+    });
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+    for policy in policies {
+        let sent = if plugins::plugin_target(&policy.channel_type).is_some() {
+            plugins::notify_plugin_channel(
+                &policy,
+                "Drift detected",
+                NotificationLevel::Warning,
+                payload.clone(),
+            )
+            .await
+        } else {
+            client
+                .post(&policy.webhook_url)
+                .json(&payload)
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(Error::from)
+        };
+        if let Err(e) = sent {
+            warn!("Drift notification policy {} failed: {e}", policy.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{DriftManager, TaskManager, TemplateManager};
+    use crate::models::TaskOutput;
+    use crate::models::drift::DriftConfigCreate;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn drift_check_params_per_app() {
+        assert_eq!(
+            drift_check_params(&TemplateApp::Ansible).unwrap(),
+            json!({"dry_run": true, "diff": true, "drift_check": true})
+        );
+        assert_eq!(
+            drift_check_params(&TemplateApp::Tofu).unwrap(),
+            json!({"plan": true, "drift_check": true})
+        );
+        assert!(drift_check_params(&TemplateApp::Bash).is_err());
+    }
+
+    #[test]
+    fn drift_task_is_marked_as_check() {
+        let template = Template {
+            id: 5,
+            project_id: 1,
+            ..Default::default()
+        };
+        let params = drift_check_params(&TemplateApp::Terraform).unwrap();
+        assert!(is_drift_check(&build_drift_task(&template, params)));
+        assert!(!is_drift_check(&Task::new_waiting(5, 1)));
+    }
+
+    #[test]
+    fn ansible_recap_classification() {
+        let clean = lines(
+            "PLAY RECAP *****\nweb1 : ok=3    changed=0    unreachable=0    failed=0    skipped=1",
+        );
+        assert_eq!(classify_ansible_output(&clean).status, DriftStatus::Clean);
+
+        let drifted = lines(
+            "web1                       : ok=3    changed=2    unreachable=0    failed=0\n\
+             web2                       : ok=3    changed=0    unreachable=0    failed=0",
+        );
+        let outcome = classify_ansible_output(&drifted);
+        assert_eq!(outcome.status, DriftStatus::Drifted);
+        assert_eq!(outcome.summary, "1 of 2 host(s) drifted: web1 (changed=2)");
+
+        let failed = lines("db1 : ok=0    changed=0    unreachable=1    failed=0");
+        assert_eq!(classify_ansible_output(&failed).status, DriftStatus::Error);
+        assert_eq!(classify_ansible_output(&[]).status, DriftStatus::Error);
+    }
+
+    #[test]
+    fn terraform_plan_classification() {
+        let drifted =
+            lines("Terraform will perform...\nPlan: 1 to add, 2 to change, 0 to destroy.");
+        let outcome = classify_terraform_output(&drifted);
+        assert_eq!(outcome.status, DriftStatus::Drifted);
+        assert_eq!(outcome.summary, "1 to add, 2 to change, 0 to destroy");
+
+        let clean = lines("No changes. Your infrastructure matches the configuration.");
+        assert_eq!(classify_terraform_output(&clean).status, DriftStatus::Clean);
+
+        let outside = lines(
+            "Note: Objects have changed outside of Terraform\nNo changes. Your infrastructure matches the configuration.",
+        );
+        assert_eq!(
+            classify_terraform_output(&outside).status,
+            DriftStatus::Drifted
+        );
+
+        assert_eq!(classify_terraform_output(&[]).status, DriftStatus::Error);
+    }
+
+    #[test]
+    fn failed_task_is_error() {
+        let outcome = classify_drift(
+            &TemplateApp::Terraform,
+            TaskStatus::Error,
+            &lines("Error: provider failed\n"),
+        );
+        assert_eq!(outcome.status, DriftStatus::Error);
+        assert!(outcome.summary.ends_with("Error: provider failed"));
+    }
+
+    #[tokio::test]
+    async fn finish_drift_check_updates_pending_result() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                id: 1,
+                project_id: 1,
+                app: TemplateApp::Terraform,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let config = store
+            .create_drift_config(
+                1,
+                DriftConfigCreate {
+                    template_id: 1,
+                    enabled: Some(true),
+                    schedule: None,
+                },
+            )
+            .await
+            .unwrap();
+        let task = store
+            .create_task(Task {
+                template_id: 1,
+                project_id: 1,
+                status: TaskStatus::Success,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store
+            .create_task_output(TaskOutput {
+                id: 0,
+                task_id: task.id,
+                project_id: 1,
+                time: Utc::now(),
+                output: "Plan: 0 to add, 1 to change, 0 to destroy.".to_string(),
+                stage_id: None,
+            })
+            .await
+            .unwrap();
+        let pending = store
+            .create_drift_result(1, config.id, 1, DRIFT_PENDING, None, Some(task.id))
+            .await
+            .unwrap();
+        assert!(!drift_check_in_progress(store.as_ref(), &config).await);
+
+        let outcome = finish_drift_check(store.clone(), &config, pending.id, task.id)
+            .await
+            .unwrap();
+        assert_eq!(outcome.status, DriftStatus::Drifted);
+
+        let latest = store.get_drift_results(config.id, 1).await.unwrap();
+        assert_eq!(latest[0].status, "drifted");
+        assert_eq!(
+            latest[0].summary.as_deref(),
+            Some("0 to add, 1 to change, 0 to destroy")
+        );
+    }
+
+    #[tokio::test]
+    async fn settle_finished_checks_records_result_of_queued_check() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                id: 1,
+                project_id: 1,
+                app: TemplateApp::Terraform,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let config = store
+            .create_drift_config(
+                1,
+                DriftConfigCreate {
+                    template_id: 1,
+                    enabled: Some(true),
+                    schedule: None,
+                },
+            )
+            .await
+            .unwrap();
+        let pending = start_drift_check(store.clone(), &config).await.unwrap();
+        let task_id = pending.task_id.unwrap();
+        assert_eq!(settle_finished_checks(store.clone()).await, 0);
+        assert!(drift_check_in_progress(store.as_ref(), &config).await);
+
+        // Задачу выполнил другой узел (или этот — до рестарта)
+        store
+            .create_task_output(TaskOutput {
+                id: 0,
+                task_id,
+                project_id: 1,
+                time: Utc::now(),
+                output: "No changes. Your infrastructure matches the configuration.".to_string(),
+                stage_id: None,
+            })
+            .await
+            .unwrap();
+        store
+            .update_task_status(1, task_id, TaskStatus::Success)
+            .await
+            .unwrap();
+
+        assert_eq!(settle_finished_checks(store.clone()).await, 1);
+        let latest = store.get_drift_results(config.id, 1).await.unwrap();
+        assert_eq!(latest[0].id, pending.id);
+        assert_eq!(latest[0].status, "clean");
+        assert_eq!(settle_finished_checks(store.clone()).await, 0);
+    }
+}
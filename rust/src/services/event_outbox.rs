@@ -0,0 +1,769 @@
+//! Доставка событий жизненного цикла задач через outbox
+//!
+//! Смена статуса задачи записывается событием в `event_outbox`. Диспетчер
+//! раскладывает каждое событие на доставки подходящим политикам уведомлений
+//! (`on_start`/`on_success`/`on_failure`/`always` с фильтром по шаблону) и
+//! активным webhook проекта, затем отправляет их. Неудачная попытка
+//! откладывается с экспоненциальной задержкой; состояние доставок хранится в
+//! БД, поэтому повторы переживают перезапуск. Каждая попытка пишется в
+//! `WebhookLog`.
+//!
+//! Настройка:
+//! - `SEMAPHORE_EVENT_DISPATCH_INTERVAL_SECS` — интервал диспетчера (5 с)
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use tracing::{info, warn};
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::webhook::WebhookType as WebhookModelType;
+use crate::models::{
+    DeliveryStatus, DeliveryTarget, EventDelivery, NewEventDelivery, NotificationPolicy,
+    OutboxEvent, Task, Webhook, WebhookLog,
+};
+use crate::plugins::{self, NotificationLevel};
+use crate::services::alert::AlertService;
+use crate::services::task_logger::TaskStatus;
+use crate::services::webhook::{
+    WebhookConfig, WebhookEvent, WebhookMetadata, WebhookService, WebhookType,
+};
+
+/// Задача запущена
+pub const TASK_STARTED: &str = "task_started";
+/// Задача завершилась успешно
+pub const TASK_SUCCESS: &str = "task_success";
+/// Задача завершилась ошибкой
+pub const TASK_FAILED: &str = "task_failed";
+/// Задача остановлена
+pub const TASK_STOPPED: &str = "task_stopped";
+/// Подписка webhook на любое завершение задачи
+const TASK_COMPLETED: &str = "task_completed";
+
+/// Событий, раскладываемых за один проход
+const DISPATCH_BATCH: i64 = 100;
+/// Доставок, отправляемых за один проход
+const DELIVERY_BATCH: i64 = 50;
+/// Аренда взятой доставки: если узел упадёт посреди отправки, доставку
+/// повторит другой узел после её окончания
+const DELIVERY_LEASE_SECS: i64 = 120;
+/// Попыток доставки политике уведомлений
+const POLICY_MAX_ATTEMPTS: i32 = 5;
+/// Первая задержка повтора
+const RETRY_BASE_SECS: i64 = 30;
+/// Наибольшая задержка повтора
+const RETRY_MAX_SECS: i64 = 3600;
+
+/// Тип события для статуса задачи; промежуточные статусы событий не дают
+pub fn task_event_type(status: TaskStatus) -> Option<&'static str> {
+    match status {
+        TaskStatus::Running => Some(TASK_STARTED),
+        TaskStatus::Success => Some(TASK_SUCCESS),
+        TaskStatus::Error => Some(TASK_FAILED),
+        TaskStatus::Stopped => Some(TASK_STOPPED),
+        _ => None,
+    }
+}
+
+/// Триггер политики уведомлений, соответствующий событию
+fn policy_trigger(event_type: &str) -> &'static str {
+    match event_type {
+        TASK_STARTED => "on_start",
+        TASK_SUCCESS => "on_success",
+        TASK_FAILED => "on_failure",
+        // Остановку получают только политики `always`
+        _ => "on_stop",
+    }
+}
+
+/// Подписан ли webhook на событие; пустой список означает все события
+fn webhook_subscribed(webhook: &Webhook, event_type: &str) -> bool {
+    let events: Vec<String> = serde_json::from_value(webhook.events.clone()).unwrap_or_default();
+    events.is_empty()
+        || events.iter().any(|e| {
+            e == "*" || e == event_type || (e == TASK_COMPLETED && event_type != TASK_STARTED)
+        })
+}
+
+/// Задержка перед повтором после `attempts` неудачных попыток
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exp = attempts.clamp(1, 16) as u32 - 1;
+    chrono::Duration::seconds((RETRY_BASE_SECS << exp).min(RETRY_MAX_SECS))
+}
+
+/// Интервал диспетчера событий
+pub fn dispatch_interval() -> Duration {
+    let secs = std::env::var("SEMAPHORE_EVENT_DISPATCH_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(5);
+    Duration::from_secs(secs)
+}
+
+/// Записывает событие смены статуса задачи в outbox
+///
+/// Ошибки записи только логируются: уведомления не должны влиять на
+/// выполнение задачи.
+pub async fn record_task_event(store: &dyn Store, task: &Task) {
+    let Some(event_type) = task_event_type(task.status) else {
+        return;
+    };
+    if let Err(e) = store.create_outbox_event(task_event(store, task, event_type).await).await {
+        warn!(
+            "Failed to record {} event of task {}: {}",
+            event_type, task.id, e
+        );
+    }
+}
+
+/// Собирает событие задачи; секреты и окружение задачи в него не попадают
+async fn task_event(store: &dyn Store, task: &Task, event_type: &str) -> OutboxEvent {
+    let template = store.get_template(task.project_id, task.template_id).await.ok();
+    let author = match task.user_id {
+        Some(user_id) => store.get_user(user_id).await.ok().map(|u| u.username),
+        None => None,
+    };
+    let payload = json!({
+        "event": event_type,
+        "project_id": task.project_id,
+        "task": {
+            "id": task.id,
+            "template_id": task.template_id,
+            "status": task.status,
+            "message": task.message,
+            "version": task.version,
+            "commit_hash": task.commit_hash,
+            "user_id": task.user_id,
+            "start": task.start,
+            "end": task.end,
+            "url": format!(
+                "{}/project/{}/tasks/{}",
+                crate::config::get_public_host(),
+                task.project_id,
+                task.id
+            ),
+        },
+        "template": {
+            "id": task.template_id,
+            "name": template.as_ref().map(|t| t.name.clone()),
+            "suppress_success_alerts": template.as_ref().is_some_and(|t| t.suppress_success_alerts),
+        },
+        "author": author,
+    });
+    OutboxEvent {
+        id: 0,
+        project_id: task.project_id,
+        event_type: event_type.to_string(),
+        task_id: Some(task.id),
+        template_id: Some(task.template_id),
+        payload,
+        created: Utc::now(),
+    }
+}
+
+/// Доставки события: подходящие политики и webhook проекта
+async fn event_deliveries(store: &dyn Store, event: &OutboxEvent) -> Result<Vec<NewEventDelivery>> {
+    let suppressed = event.event_type == TASK_SUCCESS
+        && event.payload["template"]["suppress_success_alerts"]
+            .as_bool()
+            .unwrap_or(false);
+    if suppressed {
+        return Ok(Vec::new());
+    }
+
+    let policies = store
+        .get_matching_policies(
+            event.project_id,
+            policy_trigger(&event.event_type),
+            event.template_id,
+        )
+        .await?;
+    let webhooks = store
+        .get_webhooks_by_project(event.project_id as i64)
+        .await?;
+
+    let policies = policies.into_iter().map(|p| NewEventDelivery {
+        target: DeliveryTarget::NotificationPolicy,
+        target_id: p.id as i64,
+        max_attempts: POLICY_MAX_ATTEMPTS,
+    });
+    let webhooks = webhooks
+        .into_iter()
+        .filter(|w| w.active && webhook_subscribed(w, &event.event_type))
+        .map(|w| NewEventDelivery {
+            target: DeliveryTarget::Webhook,
+            target_id: w.id,
+            max_attempts: w.retry_count.max(0) + 1,
+        });
+    Ok(policies.chain(webhooks).collect())
+}
+
+/// Раскладывает новые события outbox на доставки
+pub async fn dispatch_events(store: &dyn Store) -> usize {
+    let events = match store.get_undispatched_events(DISPATCH_BATCH).await {
+        Ok(events) => events,
+        Err(e) => {
+            warn!("Failed to load outbox events: {}", e);
+            return 0;
+        }
+    };
+    let mut dispatched = 0;
+    for event in events {
+        let result = match event_deliveries(store, &event).await {
+            Ok(deliveries) => store.dispatch_outbox_event(event.id, deliveries).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => dispatched += 1,
+            Err(e) => warn!("Failed to dispatch event {}: {}", event.id, e),
+        }
+    }
+    dispatched
+}
+
+/// Результат одной попытки доставки
+struct Attempt {
+    success: bool,
+    status_code: Option<i32>,
+    response: Option<Value>,
+    error: Option<String>,
+    /// Получатель удалён или выключен — повторять бессмысленно
+    permanent: bool,
+}
+
+impl Attempt {
+    fn failed(error: impl ToString, permanent: bool) -> Self {
+        Self {
+            success: false,
+            status_code: None,
+            response: None,
+            error: Some(error.to_string()),
+            permanent,
+        }
+    }
+
+    fn from_result(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => Self {
+                success: true,
+                status_code: None,
+                response: None,
+                error: None,
+                permanent: false,
+            },
+            Err(e) => Self::failed(e, false),
+        }
+    }
+}
+
+fn webhook_type(value: &WebhookModelType) -> WebhookType {
+    match value {
+        WebhookModelType::Generic => WebhookType::Generic,
+        WebhookModelType::Slack => WebhookType::Slack,
+        WebhookModelType::Teams => WebhookType::Teams,
+        WebhookModelType::Discord => WebhookType::Discord,
+        WebhookModelType::Telegram => WebhookType::Telegram,
+        WebhookModelType::Custom => WebhookType::Custom,
+    }
+}
+
+async fn send_to_webhook(webhook: &Webhook, event: &OutboxEvent) -> Attempt {
+    if !webhook.active {
+        return Attempt::failed("Webhook is inactive", true);
+    }
+    let config = WebhookConfig {
+        id: webhook.id,
+        name: webhook.name.clone(),
+        r#type: webhook_type(&webhook.r#type),
+        url: webhook.url.clone(),
+        secret: webhook.secret.clone(),
+        headers: webhook.headers.clone(),
+        active: true,
+        events: Vec::new(),
+        // Повторы выполняет outbox
+        retry_count: 0,
+        timeout_secs: webhook.timeout_secs,
+    };
+    let template = event.payload["template"]["name"].as_str().unwrap_or("task");
+    let status = event.payload["task"]["status"].as_str().unwrap_or("unknown");
+    let mut data = event.payload.clone();
+    data["title"] = json!(format!("Задача: {template}"));
+    data["text"] = json!(format!(
+        "Задача '{template}' #{} изменила статус на: {status}",
+        event.task_id.unwrap_or_default()
+    ));
+    let webhook_event = WebhookEvent {
+        event_type: event.event_type.clone(),
+        timestamp: event.created,
+        data,
+        metadata: WebhookMetadata {
+            source: "velum".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            project_id: Some(event.project_id as i64),
+            user_id: event.payload["task"]["user_id"].as_i64(),
+        },
+    };
+
+    let timeout = if webhook.timeout_secs > 0 {
+        webhook.timeout_secs as u64
+    } else {
+        30
+    };
+    match WebhookService::with_timeout(timeout)
+        .send_webhook(&config, &webhook_event)
+        .await
+    {
+        Ok(result) => Attempt {
+            success: result.success,
+            status_code: result.status_code.map(i32::from),
+            response: result.response_body.map(Value::String),
+            error: result.error,
+            permanent: false,
+        },
+        Err(e) => Attempt::failed(e, false),
+    }
+}
+
+/// Задача, восстановленная из события, для форматирования уведомления
+fn event_task(event: &OutboxEvent) -> Task {
+    let task = &event.payload["task"];
+    let text = |name: &str| task[name].as_str().map(str::to_string);
+    let time = |name: &str| serde_json::from_value::<Option<DateTime<Utc>>>(task[name].clone());
+    Task {
+        id: event.task_id.unwrap_or_default(),
+        status: serde_json::from_value(task["status"].clone()).unwrap_or_default(),
+        message: text("message"),
+        version: text("version"),
+        commit_hash: text("commit_hash"),
+        start: time("start").ok().flatten(),
+        end: time("end").ok().flatten(),
+        created: event.created,
+        user_id: task["user_id"].as_i64().map(|id| id as i32),
+        ..Task::new_waiting(event.template_id.unwrap_or_default(), event.project_id)
+    }
+}
+
+async fn send_to_policy(policy: &NotificationPolicy, event: &OutboxEvent) -> Attempt {
+    if !policy.enabled {
+        return Attempt::failed("Notification policy is disabled", true);
+    }
+    if plugins::plugin_target(&policy.channel_type).is_some() {
+        let level = match event.event_type.as_str() {
+            TASK_FAILED => NotificationLevel::Error,
+            TASK_STOPPED => NotificationLevel::Warning,
+            _ => NotificationLevel::Info,
+        };
+        let title = format!("Task {}", event.event_type.trim_start_matches("task_"));
+        return Attempt::from_result(
+            plugins::notify_plugin_channel(policy, &title, level, event.payload.clone()).await,
+        );
+    }
+
+    let alert = AlertService::new(
+        event_task(event),
+        event.payload["template"]["name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        event.payload["author"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+    );
+    let url = policy.webhook_url.as_str();
+    let result = match policy.channel_type.as_str() {
+        "slack" => alert.send_slack_alert(url).await,
+        "teams" => alert.send_teams_alert(url).await,
+        "pagerduty" => alert.send_pagerduty_alert(url).await,
+        "generic" => alert.send_generic_webhook(url, None).await,
+        other => {
+            return Attempt::failed(format!("Unsupported channel type: {other}"), true);
+        }
+    };
+    Attempt::from_result(result)
+}
+
+/// Выполняет одну попытку доставки и сохраняет её результат
+async fn deliver(store: &dyn Store, delivery: &EventDelivery) -> Result<bool> {
+    let event = store.get_outbox_event(delivery.event_id).await?;
+    let (attempt, webhook_id, notification_policy_id) = match delivery.target {
+        DeliveryTarget::Webhook => {
+            let attempt = match store.get_webhook(delivery.target_id).await {
+                Ok(webhook) => send_to_webhook(&webhook, &event).await,
+                Err(Error::NotFound(_)) => Attempt::failed("Webhook was deleted", true),
+                Err(e) => Attempt::failed(e, false),
+            };
+            (attempt, delivery.target_id, None)
+        }
+        DeliveryTarget::NotificationPolicy => {
+            let policy_id = delivery.target_id as i32;
+            let attempt = match store
+                .get_notification_policy(policy_id, delivery.project_id)
+                .await
+            {
+                Ok(policy) => send_to_policy(&policy, &event).await,
+                Err(Error::NotFound(_)) => {
+                    Attempt::failed("Notification policy was deleted", true)
+                }
+                Err(e) => Attempt::failed(e, false),
+            };
+            (attempt, 0, Some(policy_id))
+        }
+    };
+
+    let log = WebhookLog {
+        id: 0,
+        webhook_id,
+        notification_policy_id,
+        delivery_id: Some(delivery.id),
+        event_type: event.event_type.clone(),
+        status_code: attempt.status_code,
+        success: attempt.success,
+        error: attempt.error.clone(),
+        attempts: delivery.attempts,
+        payload: Some(event.payload.clone()),
+        response: attempt.response,
+        created: Utc::now(),
+    };
+    if let Err(e) = store.create_webhook_log(log).await {
+        warn!("Failed to log attempt of delivery {}: {}", delivery.id, e);
+    }
+
+    if attempt.success {
+        store
+            .finish_event_delivery(delivery.id, DeliveryStatus::Delivered, None, None)
+            .await?;
+    } else if attempt.permanent || delivery.attempts >= delivery.max_attempts {
+        store
+            .finish_event_delivery(delivery.id, DeliveryStatus::Failed, None, attempt.error)
+            .await?;
+    } else {
+        let next_attempt = Utc::now() + retry_delay(delivery.attempts);
+        store
+            .finish_event_delivery(
+                delivery.id,
+                DeliveryStatus::Pending,
+                Some(next_attempt),
+                attempt.error,
+            )
+            .await?;
+    }
+    Ok(attempt.success)
+}
+
+/// Отправляет доставки, срок которых наступил; возвращает число успешных
+pub async fn deliver_due(store: &dyn Store) -> usize {
+    let deliveries = match store
+        .claim_due_deliveries(DELIVERY_BATCH, DELIVERY_LEASE_SECS)
+        .await
+    {
+        Ok(deliveries) => deliveries,
+        Err(e) => {
+            warn!("Failed to claim event deliveries: {}", e);
+            return 0;
+        }
+    };
+    let mut delivered = 0;
+    for delivery in deliveries {
+        match deliver(store, &delivery).await {
+            Ok(true) => delivered += 1,
+            Ok(false) => {}
+            Err(e) => warn!("Event delivery {} failed: {}", delivery.id, e),
+        }
+    }
+    delivered
+}
+
+/// Запускает фоновый диспетчер событий
+pub fn spawn_event_dispatcher(
+    store: Arc<dyn Store + Send + Sync>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            dispatch_events(store.as_ref()).await;
+            let delivered = deliver_due(store.as_ref()).await;
+            if delivered > 0 {
+                info!("Delivered {} task events", delivered);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{
+        EventOutboxManager, NotificationPolicyManager, TemplateManager, WebhookManager,
+    };
+    use crate::models::{NotificationPolicyCreate, Template};
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use std::sync::Mutex;
+
+    /// Полученные запросы и коды ответов, которые вернёт приёмник
+    #[derive(Clone, Default)]
+    struct Receiver {
+        bodies: Arc<Mutex<Vec<Value>>>,
+        statuses: Arc<Mutex<Vec<StatusCode>>>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        axum::Json(body): axum::Json<Value>,
+    ) -> StatusCode {
+        receiver.bodies.lock().unwrap().push(body);
+        let mut statuses = receiver.statuses.lock().unwrap();
+        if statuses.is_empty() {
+            StatusCode::OK
+        } else {
+            statuses.remove(0)
+        }
+    }
+
+    async fn start_receiver() -> (String, Receiver) {
+        let receiver = Receiver::default();
+        let app = axum::Router::new()
+            .route("/hook", axum::routing::post(receive))
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}/hook"), receiver)
+    }
+
+    async fn setup(suppress_success_alerts: bool) -> MockStore {
+        let store = MockStore::new();
+        store
+            .create_template(Template {
+                id: 1,
+                project_id: 1,
+                name: "Deploy".to_string(),
+                suppress_success_alerts,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store
+    }
+
+    fn webhook(url: &str, events: Value, retry_count: i32) -> Webhook {
+        Webhook {
+            id: 0,
+            project_id: Some(1),
+            name: "ci".to_string(),
+            r#type: WebhookModelType::Generic,
+            url: url.to_string(),
+            secret: None,
+            headers: None,
+            active: true,
+            events,
+            retry_count,
+            timeout_secs: 5,
+            created: Utc::now(),
+            updated: Utc::now(),
+        }
+    }
+
+    fn policy(url: &str, trigger: &str, template_id: Option<i32>) -> NotificationPolicyCreate {
+        NotificationPolicyCreate {
+            name: trigger.to_string(),
+            channel_type: "generic".to_string(),
+            webhook_url: url.to_string(),
+            trigger: trigger.to_string(),
+            template_id,
+            enabled: Some(true),
+        }
+    }
+
+    fn task(status: TaskStatus) -> Task {
+        Task {
+            id: 7,
+            project_id: 1,
+            template_id: 1,
+            status,
+            secret: Some("top-secret".to_string()),
+            created: Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_task_event_type() {
+        assert_eq!(task_event_type(TaskStatus::Running), Some(TASK_STARTED));
+        assert_eq!(task_event_type(TaskStatus::Error), Some(TASK_FAILED));
+        assert_eq!(task_event_type(TaskStatus::Waiting), None);
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        assert_eq!(retry_delay(1).num_seconds(), 30);
+        assert_eq!(retry_delay(3).num_seconds(), 120);
+        assert_eq!(retry_delay(20).num_seconds(), RETRY_MAX_SECS);
+    }
+
+    #[test]
+    fn test_webhook_subscription() {
+        let hook = |events: Value| webhook("http://localhost", events, 0);
+        assert!(webhook_subscribed(&hook(json!([])), TASK_STARTED));
+        assert!(webhook_subscribed(&hook(json!(["task_completed"])), TASK_FAILED));
+        assert!(!webhook_subscribed(&hook(json!(["task_completed"])), TASK_STARTED));
+        assert!(!webhook_subscribed(&hook(json!(["task_success"])), TASK_FAILED));
+    }
+
+    #[tokio::test]
+    async fn test_event_matches_policies_and_webhooks() {
+        let store = setup(false).await;
+        let url = "http://127.0.0.1:9/hook";
+        for p in [
+            policy(url, "on_failure", None),
+            policy(url, "always", Some(1)),
+            policy(url, "on_success", None),
+            policy(url, "on_failure", Some(2)),
+        ] {
+            store.create_notification_policy(1, p).await.unwrap();
+        }
+        store
+            .create_webhook(webhook(url, json!(["task_failed"]), 2))
+            .await
+            .unwrap();
+        store
+            .create_webhook(webhook(url, json!(["task_started"]), 0))
+            .await
+            .unwrap();
+
+        record_task_event(&store, &task(TaskStatus::Error)).await;
+        assert_eq!(dispatch_events(&store).await, 1);
+
+        let mut deliveries = store.get_event_deliveries(1, None, 10).await.unwrap();
+        deliveries.sort_by_key(|d| d.id);
+        let targets: Vec<_> = deliveries
+            .iter()
+            .map(|d| (d.target, d.target_id, d.max_attempts))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                (DeliveryTarget::NotificationPolicy, 1, POLICY_MAX_ATTEMPTS),
+                (DeliveryTarget::NotificationPolicy, 2, POLICY_MAX_ATTEMPTS),
+                (DeliveryTarget::Webhook, 1, 3),
+            ]
+        );
+        // Повторная раскладка не создаёт дублей
+        assert_eq!(dispatch_events(&store).await, 0);
+
+        let event = store.get_outbox_event(1).await.unwrap();
+        assert!(!event.payload.to_string().contains("top-secret"));
+        assert_eq!(event.payload["template"]["name"], "Deploy");
+    }
+
+    #[tokio::test]
+    async fn test_suppressed_success_creates_no_deliveries() {
+        let store = setup(true).await;
+        store
+            .create_notification_policy(1, policy("http://127.0.0.1:9/hook", "always", None))
+            .await
+            .unwrap();
+
+        record_task_event(&store, &task(TaskStatus::Success)).await;
+        record_task_event(&store, &task(TaskStatus::Running)).await;
+        assert_eq!(dispatch_events(&store).await, 2);
+
+        let deliveries = store.get_event_deliveries(1, None, 10).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].event_id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempt_is_retried_and_logged() {
+        let store = setup(false).await;
+        let (url, receiver) = start_receiver().await;
+        receiver
+            .statuses
+            .lock()
+            .unwrap()
+            .push(StatusCode::INTERNAL_SERVER_ERROR);
+        store
+            .create_webhook(webhook(&url, json!([]), 2))
+            .await
+            .unwrap();
+
+        record_task_event(&store, &task(TaskStatus::Error)).await;
+        dispatch_events(&store).await;
+        assert_eq!(deliver_due(&store).await, 0);
+
+        let delivery = store.get_event_delivery(1, 1).await.unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Pending);
+        assert_eq!(delivery.attempts, 1);
+        assert!(delivery.next_attempt > Utc::now() + chrono::Duration::seconds(20));
+        // Повтор ещё не наступил
+        assert_eq!(deliver_due(&store).await, 0);
+
+        store.redeliver_event_delivery(1, 1).await.unwrap();
+        assert_eq!(deliver_due(&store).await, 1);
+        let delivery = store.get_event_delivery(1, 1).await.unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert!(delivery.delivered.is_some());
+
+        let logs = store.get_event_delivery_logs(1).await.unwrap();
+        assert_eq!(logs.len(), 2);
+        assert!(!logs[0].success);
+        assert!(logs[1].success);
+        assert_eq!(logs[1].webhook_id, 1);
+
+        let bodies = receiver.bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[1]["event"], TASK_FAILED);
+        assert_eq!(bodies[1]["data"]["task"]["id"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_policy_attempt_is_logged_and_rescheduled() {
+        let store = setup(false).await;
+        let (url, receiver) = start_receiver().await;
+        receiver
+            .statuses
+            .lock()
+            .unwrap()
+            .push(StatusCode::BAD_GATEWAY);
+        store
+            .create_notification_policy(1, policy(&url, "on_start", None))
+            .await
+            .unwrap();
+
+        record_task_event(&store, &task(TaskStatus::Running)).await;
+        dispatch_events(&store).await;
+        deliver_due(&store).await;
+        let delivery = store.get_event_delivery(1, 1).await.unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Pending);
+        assert!(delivery.last_error.unwrap().contains("502"));
+
+        let logs = store.get_event_delivery_logs(1).await.unwrap();
+        assert_eq!(logs[0].webhook_id, 0);
+        assert_eq!(logs[0].notification_policy_id, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_target_fails_without_retry() {
+        let store = setup(false).await;
+        store
+            .create_webhook(webhook("http://127.0.0.1:9/hook", json!([]), 5))
+            .await
+            .unwrap();
+        record_task_event(&store, &task(TaskStatus::Stopped)).await;
+        dispatch_events(&store).await;
+        store.delete_webhook(1).await.unwrap();
+
+        deliver_due(&store).await;
+        let delivery = store.get_event_delivery(1, 1).await.unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Failed);
+        assert_eq!(delivery.attempts, 1);
+    }
+}
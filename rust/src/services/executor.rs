@@ -260,6 +260,8 @@ impl TaskLogger for NullLogger {
     fn set_commit(&self, _hash: &str, _message: &str) {}
 
     fn wait_log(&self) {}
+
+    fn add_secret(&self, _secret: &str) {}
 }
 
 /// Ansible приложение
@@ -0,0 +1,655 @@
+//! Forge Webhooks — приёмники push/PR вебхуков GitHub, GitLab и Gitea
+//!
+//! Надстройка над моделью интеграций: вызов `POST /api/integrations/{alias}/{forge}`
+//! проверяет подпись форжа секретом интеграции (`auth_secret_id`), разбирает
+//! payload push/tag/merge request и запускает шаблоны проекта, чей репозиторий
+//! совпадает с репозиторием события, а ветка — с веткой события.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::{Integration, Repository, Task, Template};
+use crate::services::alert::AlertService;
+use crate::services::integration::{
+    IntegrationRequest, extract_values, load_integration_secret, lookup_json_path, matcher_matches,
+};
+use crate::services::task_logger::TaskStatus;
+use crate::utils::crypto::constant_time_eq;
+
+/// Поддерживаемые Git-форжи
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+    Gitea,
+}
+
+impl Forge {
+    /// Разбирает имя форжа из пути запроса
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "github" => Some(Forge::GitHub),
+            "gitlab" => Some(Forge::GitLab),
+            "gitea" | "forgejo" => Some(Forge::Gitea),
+            _ => None,
+        }
+    }
+
+    /// Отображаемое имя
+    pub fn title(&self) -> &'static str {
+        match self {
+            Forge::GitHub => "GitHub",
+            Forge::GitLab => "GitLab",
+            Forge::Gitea => "Gitea",
+        }
+    }
+}
+
+/// Тип события форжа
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeEventKind {
+    Push,
+    Tag,
+    PullRequest,
+}
+
+/// Разобранное событие форжа
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForgeEvent {
+    pub kind: ForgeEventKind,
+    /// Все URL репозитория из payload (https, ssh, web)
+    pub repo_urls: Vec<String>,
+    /// Ветка (для тегов — имя тега)
+    pub branch: String,
+    pub commit_hash: Option<String>,
+    pub commit_message: Option<String>,
+}
+
+/// Проверяет подпись вебхука форжа
+///
+/// - GitHub: `X-Hub-Signature-256: sha256=<hmac>`;
+/// - GitLab: `X-Gitlab-Token: <secret>`;
+/// - Gitea: `X-Gitea-Signature: <hmac>` (или `X-Hub-Signature-256`).
+pub fn verify_forge_signature(forge: Forge, secret: &str, request: &IntegrationRequest) -> bool {
+    let expected = AlertService::compute_hmac_signature(secret, &request.body);
+    let hmac_matches = |provided: &str| {
+        let provided = provided.trim();
+        let provided = provided.strip_prefix("sha256=").unwrap_or(provided);
+        constant_time_eq(
+            &format!("sha256={}", provided.to_ascii_lowercase()),
+            &expected,
+        )
+    };
+
+    match forge {
+        Forge::GitHub => request
+            .header("X-Hub-Signature-256")
+            .is_some_and(hmac_matches),
+        Forge::GitLab => request
+            .header("X-Gitlab-Token")
+            .is_some_and(|token| constant_time_eq(token.trim(), secret)),
+        Forge::Gitea => request
+            .header("X-Gitea-Signature")
+            .or_else(|| request.header("X-Forgejo-Signature"))
+            .or_else(|| request.header("X-Hub-Signature-256"))
+            .is_some_and(hmac_matches),
+    }
+}
+
+fn str_at(json: &Value, path: &str) -> Option<String> {
+    lookup_json_path(json, path)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn urls_at(json: &Value, paths: &[&str]) -> Vec<String> {
+    paths.iter().filter_map(|p| str_at(json, p)).collect()
+}
+
+/// Разбирает `refs/heads/x` / `refs/tags/x`
+fn split_ref(git_ref: &str) -> (ForgeEventKind, String) {
+    if let Some(tag) = git_ref.strip_prefix("refs/tags/") {
+        (ForgeEventKind::Tag, tag.to_string())
+    } else {
+        let branch = git_ref.strip_prefix("refs/heads/").unwrap_or(git_ref);
+        (ForgeEventKind::Push, branch.to_string())
+    }
+}
+
+/// Нулевой SHA — ветка или тег удалены
+fn is_zero_sha(sha: Option<&str>) -> bool {
+    sha.is_some_and(|s| !s.is_empty() && s.chars().all(|c| c == '0'))
+}
+
+/// Разбирает push-событие GitHub/Gitea
+fn parse_github_like_push(json: &Value) -> Option<ForgeEvent> {
+    if json.get("deleted").and_then(Value::as_bool) == Some(true) {
+        return None;
+    }
+    let after = str_at(json, "after");
+    if is_zero_sha(after.as_deref()) {
+        return None;
+    }
+    let (kind, branch) = split_ref(&str_at(json, "ref")?);
+    Some(ForgeEvent {
+        kind,
+        repo_urls: urls_at(
+            json,
+            &[
+                "repository.clone_url",
+                "repository.ssh_url",
+                "repository.html_url",
+            ],
+        ),
+        branch,
+        commit_hash: str_at(json, "head_commit.id").or(after),
+        commit_message: str_at(json, "head_commit.message"),
+    })
+}
+
+/// Разбирает pull request GitHub/Gitea (открыт, обновлён, переоткрыт)
+fn parse_github_like_pull_request(json: &Value) -> Option<ForgeEvent> {
+    let action = str_at(json, "action").unwrap_or_default();
+    if !matches!(
+        action.as_str(),
+        "opened" | "synchronize" | "synchronized" | "reopened"
+    ) {
+        return None;
+    }
+    Some(ForgeEvent {
+        kind: ForgeEventKind::PullRequest,
+        repo_urls: urls_at(
+            json,
+            &[
+                "repository.clone_url",
+                "repository.ssh_url",
+                "repository.html_url",
+            ],
+        ),
+        branch: str_at(json, "pull_request.head.ref")?,
+        commit_hash: str_at(json, "pull_request.head.sha"),
+        commit_message: str_at(json, "pull_request.title"),
+    })
+}
+
+/// Разбирает событие GitLab (Push Hook, Tag Push Hook, Merge Request Hook)
+fn parse_gitlab(event: &str, json: &Value) -> Option<ForgeEvent> {
+    let repo_urls = urls_at(
+        json,
+        &[
+            "project.git_http_url",
+            "project.git_ssh_url",
+            "project.web_url",
+            "repository.git_http_url",
+            "repository.git_ssh_url",
+        ],
+    );
+    match event {
+        "Push Hook" | "Tag Push Hook" => {
+            let sha = str_at(json, "checkout_sha").or_else(|| str_at(json, "after"));
+            if sha.is_none() || is_zero_sha(sha.as_deref()) {
+                return None;
+            }
+            let (kind, branch) = split_ref(&str_at(json, "ref")?);
+            let commit_message = json
+                .get("commits")
+                .and_then(Value::as_array)
+                .and_then(|commits| {
+                    commits
+                        .iter()
+                        .find(|c| c.get("id").and_then(Value::as_str) == sha.as_deref())
+                        .or(commits.last())
+                })
+                .and_then(|c| str_at(c, "message"));
+            Some(ForgeEvent {
+                kind,
+                repo_urls,
+                branch,
+                commit_hash: sha,
+                commit_message,
+            })
+        }
+        "Merge Request Hook" => {
+            let action = str_at(json, "object_attributes.action").unwrap_or_default();
+            if !matches!(action.as_str(), "open" | "update" | "reopen") {
+                return None;
+            }
+            Some(ForgeEvent {
+                kind: ForgeEventKind::PullRequest,
+                repo_urls,
+                branch: str_at(json, "object_attributes.source_branch")?,
+                commit_hash: str_at(json, "object_attributes.last_commit.id"),
+                commit_message: str_at(json, "object_attributes.title"),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Разбирает событие форжа; `None` — событие не запускает задачи (ping, удаление ветки и т.д.)
+pub fn parse_forge_event(forge: Forge, request: &IntegrationRequest) -> Option<ForgeEvent> {
+    let json = request.json()?;
+    match forge {
+        Forge::GitHub | Forge::Gitea => {
+            let header = if forge == Forge::GitHub {
+                "X-GitHub-Event"
+            } else {
+                "X-Gitea-Event"
+            };
+            match request.header(header).unwrap_or_default() {
+                "push" => parse_github_like_push(json),
+                "pull_request" => parse_github_like_pull_request(json),
+                _ => None,
+            }
+        }
+        Forge::GitLab => parse_gitlab(request.header("X-Gitlab-Event")?, json),
+    }
+}
+
+/// Нормализует URL репозитория: `host/owner/repo` в нижнем регистре
+///
+/// `https://user@GitHub.com/Owner/Repo.git`, `git@github.com:owner/repo` и
+/// `ssh://git@github.com:22/owner/repo` приводятся к `github.com/owner/repo`.
+pub fn normalize_repo_url(url: &str) -> String {
+    let url = url.trim();
+    let without_scheme = match url.find("://") {
+        Some(pos) => &url[pos + 3..],
+        None => url,
+    };
+    let without_user = match without_scheme.find('@') {
+        Some(pos) if !without_scheme[..pos].contains('/') => &without_scheme[pos + 1..],
+        _ => without_scheme,
+    };
+
+    let (host, path) = match without_user.find(['/', ':']) {
+        Some(pos) => (&without_user[..pos], &without_user[pos + 1..]),
+        None => (without_user, ""),
+    };
+    // Порт ssh://host:22/path
+    let path = match path.split_once('/') {
+        Some((port, rest)) if port.chars().all(|c| c.is_ascii_digit()) && !port.is_empty() => rest,
+        _ => path,
+    };
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    format!("{}/{}", host, path).to_lowercase()
+}
+
+/// Подбирает шаблоны, чьи репозитории и ветки совпадают с событием
+///
+/// Ветка шаблона — `Template::git_branch`, иначе `Repository::git_branch`.
+/// Пустая ветка совпадает с любым событием; для тегов сравнивается имя тега.
+pub fn match_templates<'a>(
+    event: &ForgeEvent,
+    templates: &'a [Template],
+    repositories: &[Repository],
+) -> Vec<&'a Template> {
+    let event_urls: Vec<String> = event
+        .repo_urls
+        .iter()
+        .map(|u| normalize_repo_url(u))
+        .collect();
+
+    templates
+        .iter()
+        .filter(|template| {
+            let Some(repo) = template
+                .repository_id
+                .and_then(|id| repositories.iter().find(|r| r.id == id))
+            else {
+                return false;
+            };
+            if !event_urls.contains(&normalize_repo_url(&repo.git_url)) {
+                return false;
+            }
+            let configured = template
+                .git_branch
+                .as_deref()
+                .filter(|b| !b.is_empty())
+                .or(repo.git_branch.as_deref())
+                .unwrap_or("");
+            configured.is_empty() || configured == event.branch
+        })
+        .collect()
+}
+
+/// Формирует задачу шаблона для события форжа
+pub fn build_forge_task(
+    forge: Forge,
+    integration: &Integration,
+    template: &Template,
+    event: &ForgeEvent,
+    environment: &Map<String, Value>,
+) -> Task {
+    let kind = match event.kind {
+        ForgeEventKind::Push => "push to",
+        ForgeEventKind::Tag => "tag",
+        ForgeEventKind::PullRequest => "pull request from",
+    };
+
+    Task {
+        environment: (!environment.is_empty())
+            .then(|| Value::Object(environment.clone()).to_string()),
+        git_branch: Some(event.branch.clone()),
+        integration_id: Some(integration.id),
+        message: Some(format!("{} {} {}", forge.title(), kind, event.branch)),
+        commit_hash: event.commit_hash.clone(),
+        commit_message: event.commit_message.clone(),
+        repository_id: template.repository_id,
+        ..Task::new_waiting(template.id, template.project_id)
+    }
+}
+
+/// Обрабатывает вебхук форжа по псевдониму интеграции
+///
+/// Возвращает созданные задачи (пустой список — событие не совпало ни с одним шаблоном).
+/// Неверная подпись или отсутствие секрета у интеграции — `Error::Unauthorized`.
+pub async fn trigger_forge_webhook(
+    store: Arc<dyn Store + Send + Sync>,
+    alias: &str,
+    forge: Forge,
+    request: &IntegrationRequest,
+) -> Result<Vec<Task>> {
+    let alias = store.get_integration_alias_by_alias(alias).await?;
+    let integration = store
+        .get_integration(alias.project_id, alias.integration_id)
+        .await?;
+
+    let secret = load_integration_secret(store.as_ref(), &integration).await;
+    if !secret.is_some_and(|s| verify_forge_signature(forge, &s, request)) {
+        return Err(Error::Unauthorized(format!(
+            "Invalid {} webhook signature",
+            forge.title()
+        )));
+    }
+
+    let Some(event) = parse_forge_event(forge, request) else {
+        return Ok(Vec::new());
+    };
+
+    let matchers = store
+        .get_integration_matchers(integration.project_id, integration.id)
+        .await?;
+    if !matchers.iter().all(|m| matcher_matches(m, request)) {
+        return Ok(Vec::new());
+    }
+    let values = store
+        .get_integration_extract_values(integration.project_id, integration.id)
+        .await?;
+    let extracted = extract_values(&values, request);
+
+    let templates = store.get_templates(integration.project_id).await?;
+    let repositories = store.get_repositories(integration.project_id).await?;
+
+    let mut created = Vec::new();
+    for template in match_templates(&event, &templates, &repositories) {
+        let task = build_forge_task(
+            forge,
+            &integration,
+            template,
+            &event,
+            &extracted.environment,
+        );
+        let task = store.create_task(task).await?;
+        info!(
+            "{} webhook: task {} queued for template {} ({})",
+            forge.title(),
+            task.id,
+            template.id,
+            event.branch
+        );
+
+        created.push(task);
+    }
+
+    if !created.is_empty() {
+        crate::services::task_execution::wake_dispatcher();
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn request(body: Value, headers: &[(&str, &str)]) -> IntegrationRequest {
+        IntegrationRequest::new(
+            "POST",
+            headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            body.to_string().into_bytes(),
+        )
+    }
+
+    fn template(id: i32, repository_id: i32, branch: Option<&str>) -> Template {
+        let mut t = Template::default_template(1, format!("t{id}"), "site.yml".to_string());
+        t.id = id;
+        t.repository_id = Some(repository_id);
+        t.git_branch = branch.map(str::to_string);
+        t
+    }
+
+    fn repository(id: i32, url: &str, branch: Option<&str>) -> Repository {
+        Repository {
+            id,
+            project_id: 1,
+            git_url: url.to_string(),
+            git_branch: branch.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_forge_parse() {
+        assert_eq!(Forge::parse("GitHub"), Some(Forge::GitHub));
+        assert_eq!(Forge::parse("gitlab"), Some(Forge::GitLab));
+        assert_eq!(Forge::parse("forgejo"), Some(Forge::Gitea));
+        assert_eq!(Forge::parse("svn"), None);
+    }
+
+    #[test]
+    fn test_verify_signatures() {
+        let body = json!({"ref": "refs/heads/main"});
+        let sig = AlertService::compute_hmac_signature("s", body.to_string().as_bytes());
+        let hex = sig.trim_start_matches("sha256=");
+
+        let gh = request(body.clone(), &[("X-Hub-Signature-256", &sig)]);
+        assert!(verify_forge_signature(Forge::GitHub, "s", &gh));
+        assert!(!verify_forge_signature(Forge::GitHub, "other", &gh));
+
+        let gitea = request(body.clone(), &[("X-Gitea-Signature", hex)]);
+        assert!(verify_forge_signature(Forge::Gitea, "s", &gitea));
+
+        let gl = request(body, &[("X-Gitlab-Token", "s")]);
+        assert!(verify_forge_signature(Forge::GitLab, "s", &gl));
+        assert!(!verify_forge_signature(Forge::GitHub, "s", &gl));
+    }
+
+    #[test]
+    fn test_parse_github_push_and_tag() {
+        let body = json!({
+            "ref": "refs/heads/main",
+            "after": "abc",
+            "head_commit": {"id": "abc", "message": "fix"},
+            "repository": {"clone_url": "https://github.com/o/r.git"}
+        });
+        let event = parse_forge_event(Forge::GitHub, &request(body, &[("X-GitHub-Event", "push")]))
+            .unwrap();
+        assert_eq!(event.kind, ForgeEventKind::Push);
+        assert_eq!(event.branch, "main");
+        assert_eq!(event.commit_hash.as_deref(), Some("abc"));
+        assert_eq!(event.commit_message.as_deref(), Some("fix"));
+
+        let tag = json!({"ref": "refs/tags/v1.0", "after": "def", "repository": {}});
+        let event =
+            parse_forge_event(Forge::GitHub, &request(tag, &[("X-GitHub-Event", "push")])).unwrap();
+        assert_eq!(event.kind, ForgeEventKind::Tag);
+        assert_eq!(event.branch, "v1.0");
+
+        let deleted = json!({"ref": "refs/heads/x", "after": "0000000", "deleted": true});
+        assert!(
+            parse_forge_event(
+                Forge::GitHub,
+                &request(deleted, &[("X-GitHub-Event", "push")])
+            )
+            .is_none()
+        );
+        assert!(
+            parse_forge_event(
+                Forge::GitHub,
+                &request(json!({"zen": "hi"}), &[("X-GitHub-Event", "ping")])
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_parse_pull_requests() {
+        let pr = json!({
+            "action": "synchronize",
+            "pull_request": {"head": {"ref": "feature", "sha": "123"}, "title": "Add"},
+            "repository": {"ssh_url": "git@gitea.local:o/r.git"}
+        });
+        let event = parse_forge_event(
+            Forge::Gitea,
+            &request(pr, &[("X-Gitea-Event", "pull_request")]),
+        )
+        .unwrap();
+        assert_eq!(event.kind, ForgeEventKind::PullRequest);
+        assert_eq!(event.branch, "feature");
+        assert_eq!(event.commit_hash.as_deref(), Some("123"));
+
+        let mr = json!({
+            "object_attributes": {
+                "action": "open",
+                "source_branch": "feat",
+                "title": "MR",
+                "last_commit": {"id": "456"}
+            },
+            "project": {"git_http_url": "https://gitlab.com/o/r.git"}
+        });
+        let event = parse_forge_event(
+            Forge::GitLab,
+            &request(mr, &[("X-Gitlab-Event", "Merge Request Hook")]),
+        )
+        .unwrap();
+        assert_eq!(event.branch, "feat");
+        assert_eq!(event.commit_hash.as_deref(), Some("456"));
+
+        let closed = json!({"action": "closed", "pull_request": {"head": {"ref": "x"}}});
+        assert!(
+            parse_forge_event(
+                Forge::GitHub,
+                &request(closed, &[("X-GitHub-Event", "pull_request")])
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_parse_gitlab_push() {
+        let body = json!({
+            "ref": "refs/heads/main",
+            "checkout_sha": "bbb",
+            "commits": [{"id": "aaa", "message": "one"}, {"id": "bbb", "message": "two"}],
+            "project": {"git_ssh_url": "git@gitlab.com:o/r.git"}
+        });
+        let event = parse_forge_event(
+            Forge::GitLab,
+            &request(body, &[("X-Gitlab-Event", "Push Hook")]),
+        )
+        .unwrap();
+        assert_eq!(event.branch, "main");
+        assert_eq!(event.commit_hash.as_deref(), Some("bbb"));
+        assert_eq!(event.commit_message.as_deref(), Some("two"));
+    }
+
+    #[test]
+    fn test_normalize_repo_url() {
+        let expected = "github.com/owner/repo";
+        assert_eq!(
+            normalize_repo_url("https://github.com/Owner/Repo.git"),
+            expected
+        );
+        assert_eq!(
+            normalize_repo_url("git@github.com:owner/repo.git"),
+            expected
+        );
+        assert_eq!(
+            normalize_repo_url("ssh://git@github.com:22/owner/repo"),
+            expected
+        );
+        assert_eq!(
+            normalize_repo_url("https://token@github.com/owner/repo/"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_match_templates() {
+        let event = ForgeEvent {
+            kind: ForgeEventKind::Push,
+            repo_urls: vec!["https://github.com/o/r.git".to_string()],
+            branch: "main".to_string(),
+            commit_hash: Some("abc".to_string()),
+            commit_message: None,
+        };
+        let repos = vec![
+            repository(1, "git@github.com:o/r.git", Some("main")),
+            repository(2, "https://github.com/o/other.git", None),
+        ];
+        let templates = vec![
+            template(1, 1, None),
+            template(2, 1, Some("develop")),
+            template(3, 2, None),
+        ];
+        let matched: Vec<i32> = match_templates(&event, &templates, &repos)
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(matched, vec![1]);
+    }
+
+    #[test]
+    fn test_build_forge_task() {
+        let integration = Integration {
+            id: 9,
+            project_id: 1,
+            name: "gh".to_string(),
+            template_id: 1,
+            auth_method: "hmac".to_string(),
+            auth_header: None,
+            auth_secret_id: Some(1),
+        };
+        let event = ForgeEvent {
+            kind: ForgeEventKind::PullRequest,
+            repo_urls: vec![],
+            branch: "feature".to_string(),
+            commit_hash: Some("123".to_string()),
+            commit_message: Some("Add".to_string()),
+        };
+        let task = build_forge_task(
+            Forge::GitHub,
+            &integration,
+            &template(4, 1, None),
+            &event,
+            &Map::new(),
+        );
+        assert_eq!(task.template_id, 4);
+        assert_eq!(task.git_branch.as_deref(), Some("feature"));
+        assert_eq!(task.commit_hash.as_deref(), Some("123"));
+        assert_eq!(task.integration_id, Some(9));
+        assert!(task.environment.is_none());
+    }
+}
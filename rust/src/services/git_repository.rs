@@ -48,6 +48,14 @@ pub trait GitClient: Send + Sync {
 
     /// Получает список удалённых веток
     async fn get_remote_branches(&self, repo: &GitRepository) -> Result<Vec<String>>;
+
+    /// Получает пути файлов, изменённых между двумя коммитами
+    async fn get_changed_files(
+        &self,
+        repo: &GitRepository,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<String>>;
 }
 
 /// Git репозиторий
@@ -319,6 +327,37 @@ impl GitRepository {
 
         Ok(branches)
     }
+
+    /// Получает пути файлов, изменённых между двумя коммитами
+    pub async fn get_changed_files(&self, from: &str, to: &str) -> Result<Vec<String>> {
+        let repo_path = self.get_full_path();
+
+        let mut cmd = TokioCommand::new("git");
+        cmd.arg("diff");
+        cmd.arg("--name-only");
+        cmd.arg(from);
+        cmd.arg(to);
+        cmd.current_dir(&repo_path);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let output = cmd
+            .output()
+            .await
+            .map_err(|e| Error::Other(format!("Ошибка получения изменённых файлов: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Other(format!("Git diff failed: {}", stderr)));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
 }
 
 /// Фабрика Git клиентов
@@ -367,6 +406,15 @@ impl GitClient for CmdGitClient {
     async fn get_remote_branches(&self, repo: &GitRepository) -> Result<Vec<String>> {
         repo.get_remote_branches().await
     }
+
+    async fn get_changed_files(
+        &self,
+        repo: &GitRepository,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<String>> {
+        repo.get_changed_files(from, to).await
+    }
 }
 
 #[cfg(test)]
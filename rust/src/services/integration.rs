@@ -0,0 +1,554 @@
+//! Integration Receiver — обработка входящих вызовов интеграций
+//!
+//! Входящий HTTP-вызов на `POST /api/integrations/{alias}` проходит три шага:
+//! 1. аутентификация по `auth_method` / `auth_header` / `auth_secret_id` интеграции;
+//! 2. проверка всех матчеров (тело JSON/строка или заголовок; equals/unequals/contains/regex);
+//! 3. извлечение значений в extra-vars (`Task::environment`) или параметры задачи
+//!    (`Task::params`) и постановка задачи шаблона `template_id` в очередь.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::http::HeaderMap;
+use chrono::Utc;
+use regex::Regex;
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::{Integration, IntegrationExtractValue, IntegrationMatcher, Task};
+use crate::services::alert::AlertService;
+use crate::services::task_logger::TaskStatus;
+use crate::utils::crypto::constant_time_eq;
+
+/// Заголовок по умолчанию для `auth_method = "token"`
+pub const DEFAULT_TOKEN_HEADER: &str = "X-Auth-Token";
+
+/// Заголовок по умолчанию для `auth_method = "hmac"`
+pub const DEFAULT_HMAC_HEADER: &str = "X-Hub-Signature-256";
+
+/// Входящий запрос интеграции (метод, заголовки, тело)
+#[derive(Debug, Clone, Default)]
+pub struct IntegrationRequest {
+    pub method: String,
+    /// Заголовки с именами в нижнем регистре
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+    json: Option<Value>,
+}
+
+impl IntegrationRequest {
+    /// Создаёт запрос; тело разбирается как JSON, если это возможно
+    pub fn new(method: &str, headers: HashMap<String, String>, body: Vec<u8>) -> Self {
+        let json = serde_json::from_slice(&body).ok();
+        let headers = headers
+            .into_iter()
+            .map(|(k, v)| (k.to_ascii_lowercase(), v))
+            .collect();
+        Self {
+            method: method.to_ascii_uppercase(),
+            headers,
+            body,
+            json,
+        }
+    }
+
+    /// Создаёт запрос из заголовков axum
+    pub fn from_http(method: &str, headers: &HeaderMap, body: Vec<u8>) -> Self {
+        let headers = headers
+            .iter()
+            .filter_map(|(k, v)| v.to_str().ok().map(|v| (k.to_string(), v.to_string())))
+            .collect();
+        Self::new(method, headers, body)
+    }
+
+    /// Значение заголовка (регистронезависимо)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Тело запроса как JSON (если разобралось)
+    pub fn json(&self) -> Option<&Value> {
+        self.json.as_ref()
+    }
+
+    /// Тело запроса как строка
+    pub fn body_string(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Ищет значение по пути вида `$.a.b[0].c` или `a.b.0.c`
+pub fn lookup_json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let path = path.strip_prefix('.').unwrap_or(path);
+    if path.is_empty() {
+        return Some(value);
+    }
+
+    let mut current = value;
+    for segment in path.split('.') {
+        let (name, indexes) = match segment.find('[') {
+            Some(pos) => (&segment[..pos], &segment[pos..]),
+            None => (segment, ""),
+        };
+        if !name.is_empty() {
+            current = match current {
+                Value::Object(map) => map.get(name)?,
+                Value::Array(items) => items.get(name.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            let index: usize = index.strip_suffix(']')?.parse().ok()?;
+            current = current.as_array()?.get(index)?;
+        }
+    }
+    Some(current)
+}
+
+/// Строковое представление JSON-значения (строки — без кавычек)
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Проверяет аутентификацию входящего запроса
+///
+/// - `none` (или пусто) — без проверки;
+/// - `token` — заголовок (по умолчанию `X-Auth-Token`) равен секрету,
+///   допускается форма `Bearer <secret>`;
+/// - `hmac` — заголовок (по умолчанию `X-Hub-Signature-256`) содержит
+///   HMAC-SHA256 тела, с префиксом `sha256=` или без него.
+pub fn verify_auth(
+    integration: &Integration,
+    secret: Option<&str>,
+    request: &IntegrationRequest,
+) -> bool {
+    let header_name = |default: &str| {
+        integration
+            .auth_header
+            .clone()
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| default.to_string())
+    };
+
+    match integration.auth_method.as_str() {
+        "" | "none" => true,
+        "token" => {
+            let (Some(secret), Some(provided)) =
+                (secret, request.header(&header_name(DEFAULT_TOKEN_HEADER)))
+            else {
+                return false;
+            };
+            let provided = provided.strip_prefix("Bearer ").unwrap_or(provided);
+            constant_time_eq(provided.trim(), secret)
+        }
+        "hmac" => {
+            let (Some(secret), Some(provided)) =
+                (secret, request.header(&header_name(DEFAULT_HMAC_HEADER)))
+            else {
+                return false;
+            };
+            let expected = AlertService::compute_hmac_signature(secret, &request.body);
+            let provided = provided.trim();
+            let provided = provided.strip_prefix("sha256=").unwrap_or(provided);
+            constant_time_eq(
+                &format!("sha256={}", provided.to_ascii_lowercase()),
+                &expected,
+            )
+        }
+        other => {
+            warn!(
+                "Integration {}: unknown auth method '{}'",
+                integration.id, other
+            );
+            false
+        }
+    }
+}
+
+/// Значение, с которым сравнивается матчер
+fn matcher_subject(matcher: &IntegrationMatcher, request: &IntegrationRequest) -> Option<String> {
+    let key = matcher.key.as_deref().unwrap_or("");
+    match matcher.body_data_type.as_str() {
+        "header" => request.header(key).map(str::to_string),
+        "string" => Some(request.body_string()),
+        _ => {
+            if key.is_empty() {
+                return Some(request.body_string());
+            }
+            request
+                .json()
+                .and_then(|json| lookup_json_path(json, key))
+                .map(value_to_string)
+        }
+    }
+}
+
+/// Проверяет один матчер
+///
+/// `method` (если задан и не `*`) ограничивает HTTP-метод запроса.
+pub fn matcher_matches(matcher: &IntegrationMatcher, request: &IntegrationRequest) -> bool {
+    if !matcher.method.is_empty()
+        && matcher.method != "*"
+        && !matcher.method.eq_ignore_ascii_case(&request.method)
+    {
+        return false;
+    }
+
+    let subject = matcher_subject(matcher, request);
+    let expected = matcher.matcher_value.as_str();
+
+    match matcher.matcher_type.as_str() {
+        "equals" => subject.as_deref() == Some(expected),
+        "unequals" | "not_equals" => subject.as_deref() != Some(expected),
+        "contains" => subject.is_some_and(|s| s.contains(expected)),
+        "regex" => match Regex::new(expected) {
+            Ok(re) => subject.is_some_and(|s| re.is_match(&s)),
+            Err(e) => {
+                warn!("Integration matcher {}: invalid regex: {}", matcher.id, e);
+                false
+            }
+        },
+        other => {
+            warn!(
+                "Integration matcher {}: unknown matcher type '{}'",
+                matcher.id, other
+            );
+            false
+        }
+    }
+}
+
+/// Извлекает одно значение из запроса
+pub fn extract_value(
+    value: &IntegrationExtractValue,
+    request: &IntegrationRequest,
+) -> Option<Value> {
+    let key = value.key.as_deref().unwrap_or("");
+    match value.value_source.as_str() {
+        "header" => request.header(key).map(|v| Value::String(v.to_string())),
+        _ => match value.body_data_type.as_str() {
+            "string" => Some(Value::String(request.body_string())),
+            _ => {
+                let json = request.json()?;
+                lookup_json_path(json, key).cloned()
+            }
+        },
+    }
+}
+
+/// Извлечённые значения, разложенные по назначению
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractedValues {
+    /// Extra-vars задачи (`Task::environment`)
+    pub environment: Map<String, Value>,
+    /// Параметры задачи (`Task::params`)
+    pub params: Map<String, Value>,
+}
+
+/// Извлекает все значения интеграции
+///
+/// Имя переменной — `variable`, затем `value_name`, затем `name`.
+/// `value_type = "task"` кладёт значение в параметры задачи, остальные — в extra-vars.
+pub fn extract_values(
+    values: &[IntegrationExtractValue],
+    request: &IntegrationRequest,
+) -> ExtractedValues {
+    let mut extracted = ExtractedValues::default();
+    for value in values {
+        let Some(found) = extract_value(value, request) else {
+            continue;
+        };
+        let name = [value.variable.as_deref(), Some(value.value_name.as_str())]
+            .into_iter()
+            .flatten()
+            .find(|n| !n.is_empty())
+            .unwrap_or(value.name.as_str())
+            .to_string();
+        if name.is_empty() {
+            continue;
+        }
+        match value.value_type.as_str() {
+            "task" | "task_param" => extracted.params.insert(name, found),
+            _ => extracted.environment.insert(name, found),
+        };
+    }
+    extracted
+}
+
+/// Формирует задачу для шаблона интеграции
+pub fn build_task(integration: &Integration, extracted: ExtractedValues) -> Task {
+    let environment = (!extracted.environment.is_empty())
+        .then(|| Value::Object(extracted.environment).to_string());
+    let params = (!extracted.params.is_empty()).then_some(Value::Object(extracted.params));
+
+    Task {
+        environment,
+        integration_id: Some(integration.id),
+        message: Some(format!("Integration: {}", integration.name)),
+        params,
+        ..Task::new_waiting(integration.template_id, integration.project_id)
+    }
+}
+
+/// Загружает секрет интеграции из ключа доступа `auth_secret_id`
+pub(crate) async fn load_integration_secret(
+    store: &(dyn Store + Send + Sync),
+    integration: &Integration,
+) -> Option<String> {
+    let key_id = integration.auth_secret_id?;
+    let mut key = match store.get_access_key(integration.project_id, key_id).await {
+        Ok(key) => key,
+        Err(e) => {
+            warn!(
+                "Integration {}: failed to load auth secret {}: {}",
+                integration.id, key_id, e
+            );
+            return None;
+        }
+    };
+    crate::services::key_encryption::decrypt_key_secrets(&mut key);
+    key.login_password_password
+        .filter(|s| !s.is_empty())
+        .or(key.access_key_secret_key)
+        .filter(|s| !s.is_empty())
+}
+
+/// Обрабатывает входящий вызов интеграции по псевдониму
+///
+/// Возвращает `Ok(None)`, если матчеры не совпали, и созданную задачу — если она
+/// поставлена в очередь. Ошибка аутентификации — `Error::Unauthorized`.
+pub async fn trigger_integration(
+    store: Arc<dyn Store + Send + Sync>,
+    alias: &str,
+    request: &IntegrationRequest,
+) -> Result<Option<Task>> {
+    let alias = store.get_integration_alias_by_alias(alias).await?;
+    let integration = store
+        .get_integration(alias.project_id, alias.integration_id)
+        .await?;
+
+    let secret = load_integration_secret(store.as_ref(), &integration).await;
+    if !verify_auth(&integration, secret.as_deref(), request) {
+        return Err(Error::Unauthorized(
+            "Invalid integration credentials".to_string(),
+        ));
+    }
+
+    let matchers = store
+        .get_integration_matchers(integration.project_id, integration.id)
+        .await?;
+    if !matchers.iter().all(|m| matcher_matches(m, request)) {
+        info!(
+            "Integration {}: request did not match, skipping",
+            integration.id
+        );
+        return Ok(None);
+    }
+
+    let values = store
+        .get_integration_extract_values(integration.project_id, integration.id)
+        .await?;
+    let task = build_task(&integration, extract_values(&values, request));
+
+    let created = store.create_task(task).await?;
+    info!(
+        "Integration {}: task {} queued for template {}",
+        integration.id, created.id, created.template_id
+    );
+
+    crate::services::task_execution::wake_dispatcher();
+
+    Ok(Some(created))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(body: Value, headers: &[(&str, &str)]) -> IntegrationRequest {
+        IntegrationRequest::new(
+            "post",
+            headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body.to_string().into_bytes(),
+        )
+    }
+
+    fn integration(auth_method: &str) -> Integration {
+        Integration {
+            id: 7,
+            project_id: 1,
+            name: "CI".to_string(),
+            template_id: 3,
+            auth_method: auth_method.to_string(),
+            auth_header: None,
+            auth_secret_id: None,
+        }
+    }
+
+    fn matcher(data_type: &str, key: &str, kind: &str, value: &str) -> IntegrationMatcher {
+        IntegrationMatcher {
+            id: 1,
+            integration_id: 7,
+            project_id: 1,
+            name: "m".to_string(),
+            body_data_type: data_type.to_string(),
+            key: Some(key.to_string()),
+            matcher_type: kind.to_string(),
+            matcher_value: value.to_string(),
+            method: String::new(),
+        }
+    }
+
+    fn extract(
+        source: &str,
+        key: &str,
+        variable: &str,
+        value_type: &str,
+    ) -> IntegrationExtractValue {
+        IntegrationExtractValue {
+            id: 1,
+            integration_id: 7,
+            project_id: 1,
+            name: "v".to_string(),
+            value_source: source.to_string(),
+            body_data_type: "json".to_string(),
+            key: Some(key.to_string()),
+            variable: Some(variable.to_string()),
+            value_name: String::new(),
+            value_type: value_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lookup_json_path() {
+        let v = json!({"a": {"b": [{"c": 1}, {"c": 2}]}});
+        assert_eq!(lookup_json_path(&v, "$.a.b[1].c"), Some(&json!(2)));
+        assert_eq!(lookup_json_path(&v, "a.b.0.c"), Some(&json!(1)));
+        assert_eq!(lookup_json_path(&v, "$"), Some(&v));
+        assert!(lookup_json_path(&v, "$.a.x").is_none());
+    }
+
+    #[test]
+    fn test_verify_auth_token() {
+        let mut i = integration("token");
+        let req = request(json!({}), &[("X-Auth-Token", "s3cret")]);
+        assert!(verify_auth(&i, Some("s3cret"), &req));
+        assert!(!verify_auth(&i, Some("other"), &req));
+        assert!(!verify_auth(&i, None, &req));
+
+        i.auth_header = Some("Authorization".to_string());
+        let req = request(json!({}), &[("authorization", "Bearer s3cret")]);
+        assert!(verify_auth(&i, Some("s3cret"), &req));
+    }
+
+    #[test]
+    fn test_verify_auth_hmac() {
+        let i = integration("hmac");
+        let body = json!({"ref": "main"});
+        let sig = AlertService::compute_hmac_signature("key", body.to_string().as_bytes());
+        assert!(verify_auth(
+            &i,
+            Some("key"),
+            &request(body.clone(), &[("X-Hub-Signature-256", &sig)])
+        ));
+        assert!(!verify_auth(
+            &i,
+            Some("wrong"),
+            &request(body, &[("X-Hub-Signature-256", &sig)])
+        ));
+    }
+
+    #[test]
+    fn test_verify_auth_none_and_unknown() {
+        let req = request(json!({}), &[]);
+        assert!(verify_auth(&integration("none"), None, &req));
+        assert!(!verify_auth(&integration("magic"), Some("x"), &req));
+    }
+
+    #[test]
+    fn test_matcher_types() {
+        let req = request(
+            json!({"event": "push", "ref": "refs/heads/main"}),
+            &[("X-Event", "push")],
+        );
+        assert!(matcher_matches(
+            &matcher("json", "$.event", "equals", "push"),
+            &req
+        ));
+        assert!(matcher_matches(
+            &matcher("json", "$.event", "unequals", "tag"),
+            &req
+        ));
+        assert!(matcher_matches(
+            &matcher("json", "ref", "contains", "main"),
+            &req
+        ));
+        assert!(matcher_matches(
+            &matcher("json", "ref", "regex", "^refs/heads/"),
+            &req
+        ));
+        assert!(matcher_matches(
+            &matcher("header", "x-event", "equals", "push"),
+            &req
+        ));
+        assert!(!matcher_matches(
+            &matcher("json", "$.missing", "equals", "push"),
+            &req
+        ));
+    }
+
+    #[test]
+    fn test_matcher_method_filter() {
+        let req = request(json!({"event": "push"}), &[]);
+        let mut m = matcher("json", "event", "equals", "push");
+        m.method = "GET".to_string();
+        assert!(!matcher_matches(&m, &req));
+        m.method = "post".to_string();
+        assert!(matcher_matches(&m, &req));
+    }
+
+    #[test]
+    fn test_extract_values_and_build_task() {
+        let req = request(
+            json!({"commit": {"id": "abc"}, "env": "prod"}),
+            &[("X-Request-Id", "42")],
+        );
+        let values = vec![
+            extract("body", "$.commit.id", "commit_id", "environment"),
+            extract("header", "X-Request-Id", "request_id", "task"),
+            extract("body", "$.missing", "nothing", "environment"),
+        ];
+        let extracted = extract_values(&values, &req);
+        assert_eq!(extracted.environment.get("commit_id"), Some(&json!("abc")));
+        assert_eq!(extracted.params.get("request_id"), Some(&json!("42")));
+        assert!(!extracted.environment.contains_key("nothing"));
+
+        let task = build_task(&integration("none"), extracted);
+        assert_eq!(task.integration_id, Some(7));
+        assert_eq!(task.template_id, 3);
+        assert_eq!(task.status, TaskStatus::Waiting);
+        let env: Value = serde_json::from_str(task.environment.as_deref().unwrap()).unwrap();
+        assert_eq!(env["commit_id"], "abc");
+        assert_eq!(task.params.unwrap()["request_id"], "42");
+    }
+
+    #[tokio::test]
+    async fn test_trigger_integration_unknown_alias() {
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(crate::db::MockStore::new());
+        let result = trigger_integration(store, "missing", &request(json!({}), &[])).await;
+        assert!(matches!(result, Err(Error::NotFound(_))));
+    }
+}
@@ -0,0 +1,598 @@
+//! Выбор лидера кластера
+//!
+//! Реплики сервера за балансировщиком работают с одной БД, но часть фоновых
+//! служб — планировщик расписаний и проверок дрейфа, автобэкап, очистка
+//! потерянных задач — должна работать в единственном экземпляре. Узлы
+//! соревнуются за аренду лидерства: advisory lock PostgreSQL на выделенном
+//! соединении либо ключ Redis с TTL. Лидер продлевает аренду каждые несколько
+//! секунд; после падения лидера аренду захватывает другой узел.
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::config::HAConfig;
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::ClusterLeader;
+use crate::services::task_reaper::{hostname, node_id};
+
+/// Аренда лидерства фоновых служб-синглтонов
+pub const LEADER_LEASE: &str = "background-services";
+
+/// Срок аренды по умолчанию
+const DEFAULT_LEASE_TTL: Duration = Duration::from_secs(10);
+
+/// Минимальный срок аренды, секунд
+const MIN_LEASE_TTL_SECS: u64 = 3;
+
+/// Хранилище аренды лидерства
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeaderBackend {
+    /// Единственный узел (HA выключен): всегда лидер
+    Standalone,
+    /// Advisory lock PostgreSQL
+    Postgres,
+    /// Ключ Redis с TTL (URL подключения)
+    Redis(String),
+}
+
+impl LeaderBackend {
+    /// Имя хранилища для логов и `/api/health/full`
+    pub fn name(&self) -> &'static str {
+        match self {
+            LeaderBackend::Standalone => "standalone",
+            LeaderBackend::Postgres => "postgres",
+            LeaderBackend::Redis(_) => "redis",
+        }
+    }
+}
+
+/// Конфигурация выбора лидера
+#[derive(Debug, Clone)]
+pub struct LeaderElectionConfig {
+    pub backend: LeaderBackend,
+    /// Срок аренды Redis; аренда продлевается втрое чаще
+    pub lease_ttl: Duration,
+}
+
+impl Default for LeaderElectionConfig {
+    fn default() -> Self {
+        Self {
+            backend: LeaderBackend::Standalone,
+            lease_ttl: DEFAULT_LEASE_TTL,
+        }
+    }
+}
+
+impl LeaderElectionConfig {
+    /// Интервал продления аренды и попыток её захвата
+    pub fn renew_interval(&self) -> Duration {
+        (self.lease_ttl / 3).max(Duration::from_secs(1))
+    }
+}
+
+/// Конфигурация выбора лидера из HA-настроек и окружения
+///
+/// Без HA-режима узел единственный. Хранилище аренды задаёт
+/// `SEMAPHORE_HA_LEADER_BACKEND` (`postgres` по умолчанию или `redis`),
+/// срок аренды — `SEMAPHORE_HA_LEADER_TTL_SECS`.
+pub fn config_from_env(ha: &HAConfig) -> Result<LeaderElectionConfig> {
+    if !ha.enable {
+        return Ok(LeaderElectionConfig::default());
+    }
+    let env = |name: &str| {
+        std::env::var(format!("SEMAPHORE_HA_LEADER{name}"))
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let backend = match env("_BACKEND").as_deref() {
+        None | Some("postgres") => LeaderBackend::Postgres,
+        Some("redis") if ha.redis.host.is_empty() => {
+            return Err(Error::Config(
+                "SEMAPHORE_HA_REDIS_HOST is required for the redis leader backend".to_string(),
+            ));
+        }
+        Some("redis") => LeaderBackend::Redis(ha.redis_url()),
+        Some(other) => {
+            return Err(Error::Config(format!(
+                "Unknown leader election backend '{other}' (expected postgres or redis)"
+            )));
+        }
+    };
+
+    Ok(LeaderElectionConfig {
+        backend,
+        lease_ttl: env("_TTL_SECS")
+            .and_then(|v| v.parse().ok())
+            .filter(|secs| *secs >= MIN_LEASE_TTL_SECS)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_LEASE_TTL),
+    })
+}
+
+/// Признак лидерства узла для фоновых служб
+///
+/// Службы проверяют его перед каждым запуском и подписываются на его смену,
+/// чтобы начать работу сразу после получения лидерства.
+#[derive(Debug, Clone)]
+pub struct Leadership(Arc<watch::Sender<bool>>);
+
+impl Leadership {
+    /// Узел всегда лидер (одиночный сервер)
+    pub fn standalone() -> Self {
+        Self(Arc::new(watch::Sender::new(true)))
+    }
+
+    /// Узел ожидает лидерства
+    pub fn follower() -> Self {
+        Self(Arc::new(watch::Sender::new(false)))
+    }
+
+    /// Является ли узел лидером
+    pub fn is_leader(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Подписка на смену лидерства
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.0.subscribe()
+    }
+
+    /// Устанавливает признак; возвращает `true`, если он изменился
+    fn set(&self, leader: bool) -> bool {
+        self.0
+            .send_if_modified(|current| std::mem::replace(current, leader) != leader)
+    }
+}
+
+impl Default for Leadership {
+    fn default() -> Self {
+        Self::standalone()
+    }
+}
+
+/// Аренда лидерства текущего узла
+#[async_trait]
+trait LeaderLock: Send + Sync {
+    /// Захватывает или продлевает аренду; `true`, пока она принадлежит узлу
+    async fn try_acquire(&self) -> Result<bool>;
+    /// Отпускает аренду
+    async fn release(&self) -> Result<()>;
+    /// Текущий держатель аренды
+    async fn leader(&self) -> Result<Option<ClusterLeader>>;
+}
+
+/// Аренда в БД (advisory lock PostgreSQL)
+struct StoreLeaderLock {
+    store: Arc<dyn Store + Send + Sync>,
+    node_id: String,
+}
+
+#[async_trait]
+impl LeaderLock for StoreLeaderLock {
+    async fn try_acquire(&self) -> Result<bool> {
+        self.store
+            .try_acquire_leader_lock(LEADER_LEASE, &self.node_id)
+            .await
+    }
+
+    async fn release(&self) -> Result<()> {
+        self.store
+            .release_leader_lock(LEADER_LEASE, &self.node_id)
+            .await
+    }
+
+    async fn leader(&self) -> Result<Option<ClusterLeader>> {
+        self.store.get_cluster_leader(LEADER_LEASE).await
+    }
+}
+
+/// Захват свободной аренды или продление своей
+const REDIS_ACQUIRE: &str = r"
+local holder = redis.call('GET', KEYS[1])
+if holder == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+end
+if not holder then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+    return 1
+end
+return 0
+";
+
+/// Освобождение аренды, только если она своя
+const REDIS_RELEASE: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
+/// Аренда в Redis: ключ с TTL, значение — описание узла-держателя
+struct RedisLeaderLock {
+    conn: ConnectionManager,
+    key: String,
+    value: String,
+    ttl: Duration,
+}
+
+impl RedisLeaderLock {
+    async fn connect(url: &str, ttl: Duration) -> Result<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| Error::Other(format!("Redis client error: {e}")))?;
+        let conn = tokio::time::timeout(Duration::from_secs(2), client.get_connection_manager())
+            .await
+            .map_err(|_| Error::Other("Redis connection timeout (2s)".to_string()))?
+            .map_err(redis_error)?;
+        let value = serde_json::to_string(&ClusterLeader {
+            node_id: node_id().to_string(),
+            hostname: Some(hostname()),
+            last_seen: None,
+        })?;
+        Ok(Self {
+            conn,
+            key: format!("velum:leader:{LEADER_LEASE}"),
+            value,
+            ttl,
+        })
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> Error {
+    Error::Other(format!("Redis error: {e}"))
+}
+
+#[async_trait]
+impl LeaderLock for RedisLeaderLock {
+    async fn try_acquire(&self) -> Result<bool> {
+        let acquired: i32 = redis::Script::new(REDIS_ACQUIRE)
+            .key(&self.key)
+            .arg(&self.value)
+            .arg(self.ttl.as_millis() as u64)
+            .invoke_async(&mut self.conn.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(acquired == 1)
+    }
+
+    async fn release(&self) -> Result<()> {
+        let _: i32 = redis::Script::new(REDIS_RELEASE)
+            .key(&self.key)
+            .arg(&self.value)
+            .invoke_async(&mut self.conn.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn leader(&self) -> Result<Option<ClusterLeader>> {
+        let value: Option<String> = self
+            .conn
+            .clone()
+            .get(&self.key)
+            .await
+            .map_err(redis_error)?;
+        Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+    }
+}
+
+/// Состояние лидерства для `/api/health/full`
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderStatus {
+    /// Хранилище аренды
+    pub backend: &'static str,
+    /// ID текущего узла
+    pub node_id: String,
+    /// Текущий узел — лидер
+    pub is_leader: bool,
+    /// Лидер кластера, если аренда занята
+    pub leader: Option<ClusterLeader>,
+}
+
+/// Выбор лидера для текущего узла
+pub struct LeaderElection {
+    config: LeaderElectionConfig,
+    node_id: String,
+    lock: Option<Box<dyn LeaderLock>>,
+    leadership: Leadership,
+}
+
+impl LeaderElection {
+    /// Подключается к хранилищу аренды; без HA узел сразу становится лидером
+    pub async fn connect(
+        store: Arc<dyn Store + Send + Sync>,
+        config: LeaderElectionConfig,
+    ) -> Result<Self> {
+        let lock: Option<Box<dyn LeaderLock>> = match &config.backend {
+            LeaderBackend::Standalone => None,
+            LeaderBackend::Postgres => Some(Box::new(StoreLeaderLock {
+                store,
+                node_id: node_id().to_string(),
+            })),
+            LeaderBackend::Redis(url) => Some(Box::new(
+                RedisLeaderLock::connect(url, config.lease_ttl).await?,
+            )),
+        };
+        Ok(Self::with_lock(config, node_id().to_string(), lock))
+    }
+
+    fn with_lock(
+        config: LeaderElectionConfig,
+        node_id: String,
+        lock: Option<Box<dyn LeaderLock>>,
+    ) -> Self {
+        let leadership = if lock.is_some() {
+            Leadership::follower()
+        } else {
+            Leadership::standalone()
+        };
+        Self {
+            config,
+            node_id,
+            lock,
+            leadership,
+        }
+    }
+
+    /// Признак лидерства для фоновых служб
+    pub fn leadership(&self) -> Leadership {
+        self.leadership.clone()
+    }
+
+    /// Имя хранилища аренды
+    pub fn backend(&self) -> &'static str {
+        self.config.backend.name()
+    }
+
+    /// Захватывает или продлевает аренду; возвращает признак лидерства
+    ///
+    /// Ошибка хранилища снимает лидерство: узел не может подтвердить аренду,
+    /// и её вправе захватить другой узел. Продление, не уложившееся в интервал
+    /// продления, считается ошибкой: зависшее соединение не держит лидерство.
+    pub async fn renew(&self) -> bool {
+        let Some(lock) = &self.lock else {
+            return true;
+        };
+        let timeout = self.config.renew_interval();
+        let leader = match tokio::time::timeout(timeout, lock.try_acquire()).await {
+            Ok(Ok(leader)) => leader,
+            Ok(Err(e)) => {
+                warn!("Failed to renew leader lease: {}", e);
+                false
+            }
+            Err(_) => {
+                warn!("Leader lease renewal timed out after {:?}", timeout);
+                false
+            }
+        };
+        if self.leadership.set(leader) {
+            if leader {
+                info!("Node {} became the cluster leader", self.node_id);
+            } else {
+                warn!("Node {} lost cluster leadership", self.node_id);
+            }
+        }
+        leader
+    }
+
+    /// Отпускает аренду при остановке узла, чтобы лидером сразу стал другой узел
+    pub async fn release(&self) {
+        let Some(lock) = &self.lock else {
+            return;
+        };
+        self.leadership.set(false);
+        if let Err(e) = lock.release().await {
+            warn!("Failed to release leader lease: {}", e);
+        }
+    }
+
+    /// Текущий лидер кластера и роль текущего узла
+    pub async fn status(&self) -> LeaderStatus {
+        let leader = match &self.lock {
+            None => Some(ClusterLeader {
+                node_id: self.node_id.clone(),
+                hostname: Some(hostname()),
+                last_seen: None,
+            }),
+            Some(lock) => lock.leader().await.unwrap_or_else(|e| {
+                warn!("Failed to load cluster leader: {}", e);
+                None
+            }),
+        };
+        LeaderStatus {
+            backend: self.backend(),
+            node_id: self.node_id.clone(),
+            is_leader: self.leadership.is_leader(),
+            leader,
+        }
+    }
+}
+
+/// Запускает фоновое продление аренды лидерства
+pub fn spawn_renewal(election: Arc<LeaderElection>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(election.config.renew_interval());
+        loop {
+            interval.tick().await;
+            election.renew().await;
+        }
+    })
+}
+
+static LEADER_ELECTION: OnceLock<Arc<LeaderElection>> = OnceLock::new();
+
+/// Включает глобальный выбор лидера
+pub fn init(election: LeaderElection) -> Arc<LeaderElection> {
+    LEADER_ELECTION.get_or_init(|| Arc::new(election)).clone()
+}
+
+/// Признак лидерства узла; без выбора лидера узел единственный и всегда лидер
+pub fn leadership() -> Leadership {
+    LEADER_ELECTION
+        .get()
+        .map(|election| election.leadership())
+        .unwrap_or_default()
+}
+
+/// Состояние лидерства узла (используется `/api/health/full`)
+pub async fn status() -> LeaderStatus {
+    match LEADER_ELECTION.get() {
+        Some(election) => election.status().await,
+        None => {
+            LeaderElection::with_lock(LeaderElectionConfig::default(), node_id().to_string(), None)
+                .status()
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+
+    fn store_election(store: &Arc<dyn Store + Send + Sync>, node: &str) -> LeaderElection {
+        let config = LeaderElectionConfig {
+            backend: LeaderBackend::Postgres,
+            ..Default::default()
+        };
+        let lock = StoreLeaderLock {
+            store: store.clone(),
+            node_id: node.to_string(),
+        };
+        LeaderElection::with_lock(config, node.to_string(), Some(Box::new(lock)))
+    }
+
+    #[tokio::test]
+    async fn test_standalone_is_always_leader() {
+        let election =
+            LeaderElection::connect(Arc::new(MockStore::new()), LeaderElectionConfig::default())
+                .await
+                .unwrap();
+        assert!(election.leadership().is_leader());
+        assert!(election.renew().await);
+
+        let status = election.status().await;
+        assert_eq!(status.backend, "standalone");
+        assert!(status.is_leader);
+        assert_eq!(status.leader.unwrap().node_id, node_id());
+    }
+
+    #[tokio::test]
+    async fn test_single_leader_and_failover() {
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(MockStore::new());
+        let first = store_election(&store, "node-a");
+        let second = store_election(&store, "node-b");
+        assert!(!first.leadership().is_leader());
+
+        assert!(first.renew().await);
+        assert!(!second.renew().await);
+        assert!(first.renew().await);
+        assert!(first.leadership().is_leader());
+        assert!(!second.leadership().is_leader());
+
+        let status = second.status().await;
+        assert!(!status.is_leader);
+        assert_eq!(status.leader.unwrap().node_id, "node-a");
+
+        first.release().await;
+        assert!(!first.leadership().is_leader());
+        assert!(second.renew().await);
+        assert!(!first.renew().await);
+        assert_eq!(
+            second.status().await.leader.unwrap().node_id,
+            "node-b".to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_leadership_change_is_notified() {
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(MockStore::new());
+        let election = store_election(&store, "node-a");
+        let mut changes = election.leadership().subscribe();
+
+        election.renew().await;
+        changes.changed().await.unwrap();
+        assert!(*changes.borrow_and_update());
+
+        // Продление без смены роли не будит подписчиков
+        election.renew().await;
+        assert!(!changes.has_changed().unwrap());
+    }
+
+    /// Аренда, продление которой зависает (полуоткрытое соединение)
+    struct HangingLock;
+
+    #[async_trait]
+    impl LeaderLock for HangingLock {
+        async fn try_acquire(&self) -> Result<bool> {
+            std::future::pending().await
+        }
+
+        async fn release(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn leader(&self) -> Result<Option<ClusterLeader>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hanging_renewal_drops_leadership() {
+        let config = LeaderElectionConfig {
+            backend: LeaderBackend::Postgres,
+            lease_ttl: Duration::from_secs(MIN_LEASE_TTL_SECS),
+        };
+        let election =
+            LeaderElection::with_lock(config, "node-a".to_string(), Some(Box::new(HangingLock)));
+        election.leadership.set(true);
+
+        assert!(!election.renew().await);
+        assert!(!election.leadership().is_leader());
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let ha = HAConfig {
+            enable: true,
+            ..Default::default()
+        };
+        temp_env::with_vars(
+            [
+                ("SEMAPHORE_HA_LEADER_BACKEND", None::<&str>),
+                ("SEMAPHORE_HA_LEADER_TTL_SECS", Some("6")),
+            ],
+            || {
+                let config = config_from_env(&ha).unwrap();
+                assert_eq!(config.backend, LeaderBackend::Postgres);
+                assert_eq!(config.renew_interval(), Duration::from_secs(2));
+                assert_eq!(
+                    config_from_env(&HAConfig::default()).unwrap().backend,
+                    LeaderBackend::Standalone
+                );
+            },
+        );
+        temp_env::with_var("SEMAPHORE_HA_LEADER_BACKEND", Some("redis"), || {
+            assert!(config_from_env(&ha).is_err());
+            let mut ha = ha.clone();
+            ha.redis.host = "redis.local".to_string();
+            assert_eq!(
+                config_from_env(&ha).unwrap().backend,
+                LeaderBackend::Redis("redis://redis.local:6379/0".to_string())
+            );
+        });
+        temp_env::with_var("SEMAPHORE_HA_LEADER_BACKEND", Some("etcd"), || {
+            assert!(config_from_env(&ha).is_err());
+        });
+    }
+}
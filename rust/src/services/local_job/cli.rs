@@ -84,6 +84,35 @@ impl LocalJob {
         let params: T = serde_json::from_str(&params_str)?;
         Ok(params)
     }
+
+    /// Булев флаг из `task.params` (отсутствующий или нелогический — `false`)
+    pub fn task_param_flag(&self, name: &str) -> bool {
+        self.task
+            .params
+            .as_ref()
+            .and_then(|p| p.get(name))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Параметры Terraform/OpenTofu задачи (`plan`, `destroy`, ...)
+    pub fn terraform_task_params(&self) -> crate::models::TerraformTaskParams {
+        crate::models::TerraformTaskParams {
+            plan: self.task_param_flag("plan"),
+            destroy: self.task_param_flag("destroy"),
+            auto_approve: self.task_param_flag("auto_approve"),
+            upgrade: self.task_param_flag("upgrade"),
+            reconfigure: self.task_param_flag("reconfigure"),
+        }
+    }
+
+    /// Аргументы Terraform/OpenTofu: `default` шаблона, затем `default` задачи
+    pub fn terraform_cli_args(&self) -> Result<Vec<String>> {
+        let (mut template_args, mut task_args) = self.get_cli_args_map()?;
+        let mut args = template_args.remove("default").unwrap_or_default();
+        args.extend(task_args.remove("default").unwrap_or_default());
+        Ok(args)
+    }
 }
 
 #[cfg(test)]
@@ -150,6 +179,15 @@ mod tests {
         assert!(task_map.contains_key("default"));
     }
 
+    #[test]
+    fn test_terraform_cli_args_joins_template_and_task_args() {
+        let job = create_test_job_with_args();
+        assert_eq!(
+            job.terraform_cli_args().unwrap(),
+            vec!["--template-arg", "--arg1", "--arg2"]
+        );
+    }
+
     #[test]
     fn test_get_template_params() {
         let job = create_test_job_with_args();
@@ -0,0 +1,583 @@
+//! LocalJob Container - запуск задачи в контейнере `execution_image`
+//!
+//! Если у шаблона задан `execution_image`, приложение (ansible-playbook,
+//! terraform/tofu/terragrunt, shell) запускается через `docker run --rm`
+//! (или `podman run`, см. `SEMAPHORE_CONTAINER_RUNTIME`). Репозиторий и
+//! временная директория задачи (инвентарь, SSH ключ, файлы паролей Vault)
+//! монтируются только для чтения, переменные окружения передаются через `-e`.
+//! SSH ключ пишется во временный файл задачи с правами 0600 и удаляется
+//! после остановки контейнера.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::error::{Error, Result};
+use crate::models::template::TemplateApp;
+use crate::services::local_job::LocalJob;
+use crate::services::task_logger::TaskStatus;
+
+/// Корень рабочей директории внутри контейнера
+pub const CONTAINER_ROOT: &str = "/semaphore";
+
+/// Интервал проверки запроса на остановку задачи
+const STOP_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Контейнерный рантайм: `SEMAPHORE_CONTAINER_RUNTIME` или `docker`
+pub fn container_runtime() -> String {
+    std::env::var("SEMAPHORE_CONTAINER_RUNTIME")
+        .ok()
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| "docker".to_string())
+}
+
+/// Имя контейнера задачи
+pub fn container_name(task_id: i32) -> String {
+    format!("semaphore-task-{}", task_id)
+}
+
+/// Bind mount в контейнер
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerMount {
+    pub host: PathBuf,
+    pub target: String,
+    pub read_only: bool,
+}
+
+/// Запущенный контейнер задачи (для остановки)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerHandle {
+    pub runtime: String,
+    pub name: String,
+}
+
+impl ContainerHandle {
+    /// Принудительно удаляет контейнер (`rm -f`)
+    pub fn remove(&self) {
+        let _ = std::process::Command::new(&self.runtime)
+            .args(["rm", "-f", &self.name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Описание запуска `<runtime> run`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerCommand {
+    pub runtime: String,
+    pub name: String,
+    pub image: String,
+    pub workdir: String,
+    pub mounts: Vec<ContainerMount>,
+    /// Переменные окружения `KEY=VALUE`
+    pub env: Vec<String>,
+    pub command: Vec<String>,
+}
+
+impl ContainerCommand {
+    /// Аргументы `run` для рантайма
+    ///
+    /// Значения переменных окружения не попадают в аргументы (`-e KEY`),
+    /// они передаются через окружение процесса рантайма.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--name".to_string(),
+            self.name.clone(),
+            "-w".to_string(),
+            self.workdir.clone(),
+        ];
+        for mount in &self.mounts {
+            let mut spec = format!("{}:{}", mount.host.display(), mount.target);
+            if mount.read_only {
+                spec.push_str(":ro");
+            }
+            args.push("-v".to_string());
+            args.push(spec);
+        }
+        for var in &self.env {
+            if let Some((key, _)) = var.split_once('=') {
+                args.push("-e".to_string());
+                args.push(key.to_string());
+            }
+        }
+        args.push(self.image.clone());
+        args.extend(self.command.iter().cloned());
+        args
+    }
+
+    /// Команда процесса рантайма
+    pub fn to_command(&self) -> Command {
+        let mut cmd = Command::new(&self.runtime);
+        cmd.args(self.to_args());
+        for var in &self.env {
+            if let Some((key, value)) = var.split_once('=') {
+                cmd.env(key, value);
+            }
+        }
+        cmd.stdin(Stdio::null());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+        cmd
+    }
+}
+
+/// Переводит путь на хосте в путь внутри контейнера
+fn to_container_path(arg: &str, host_root: &Path, container_root: &str) -> String {
+    let host_root = host_root.to_string_lossy();
+    match arg.strip_prefix(host_root.as_ref()) {
+        Some(rest) => format!("{}{}", container_root, rest),
+        None => arg.to_string(),
+    }
+}
+
+/// Записывает приватный ключ в уникальный файл `ssh_key_*` в `dir` с правами 0600
+fn write_private_key(dir: &Path, private_key: &str) -> std::io::Result<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("ssh_key_")
+        .tempfile_in(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.as_file()
+            .set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::io::Write::write_all(&mut file, private_key.as_bytes())?;
+    Ok(file)
+}
+
+impl LocalJob {
+    /// Путь к репозиторию внутри контейнера
+    fn container_repository_dir() -> String {
+        format!("{}/repository", CONTAINER_ROOT)
+    }
+
+    /// Путь к временной директории внутри контейнера
+    fn container_tmp_dir() -> String {
+        format!("{}/tmp", CONTAINER_ROOT)
+    }
+
+    /// Команда приложения шаблона внутри контейнера
+    fn container_app_command(
+        &self,
+        cli_args: &[String],
+        extra_vars: &str,
+        ssh_key: Option<&Path>,
+    ) -> Vec<String> {
+        let cli_args = cli_args
+            .iter()
+            .map(|a| to_container_path(a, &self.tmp_dir, &Self::container_tmp_dir()));
+        let playbook = self.template.playbook.clone();
+
+        match self.template.app {
+            TemplateApp::Ansible => {
+                let mut cmd = vec![
+                    "ansible-playbook".to_string(),
+                    "-e".to_string(),
+                    extra_vars.to_string(),
+                ];
+                if let Some(key) = ssh_key {
+                    cmd.push("--private-key".to_string());
+                    cmd.push(to_container_path(
+                        &key.to_string_lossy(),
+                        &self.tmp_dir,
+                        &Self::container_tmp_dir(),
+                    ));
+                }
+                cmd.extend(cli_args);
+                cmd.push(playbook);
+                cmd
+            }
+            TemplateApp::Terraform | TemplateApp::Tofu | TemplateApp::Terragrunt => {
+                let name = match self.template.app {
+                    TemplateApp::Tofu => "tofu",
+                    TemplateApp::Terragrunt => "terragrunt",
+                    _ => "terraform",
+                };
+                // Тот же порядок, что у TerraformApp::run: init, plan, затем destroy
+                // или apply сохранённого плана. Аргументы передаются как "$@",
+                // код 2 у -detailed-exitcode означает «есть изменения», не ошибку.
+                let params = self.terraform_task_params();
+                let next = if params.plan {
+                    "exit 0".to_string()
+                } else if params.destroy {
+                    format!("{name} destroy -input=false -auto-approve \"$@\"")
+                } else {
+                    format!(
+                        "if [ $code -eq 2 ]; then {name} apply -input=false -auto-approve tfplan; \
+                         else echo 'No changes to apply'; fi"
+                    )
+                };
+                let script = format!(
+                    "set -e; {name} init -input=false; \
+                     code=0; {name} plan -input=false -no-color -detailed-exitcode -out=tfplan \"$@\" || code=$?; \
+                     [ $code -eq 0 ] || [ $code -eq 2 ] || exit $code; {next}"
+                );
+                ["sh".to_string(), "-c".to_string(), script, name.to_string()]
+                    .into_iter()
+                    .chain(cli_args)
+                    .collect()
+            }
+            TemplateApp::Python => std::iter::once("python3".to_string())
+                .chain(std::iter::once(playbook))
+                .chain(cli_args)
+                .collect(),
+            TemplateApp::PowerShell => ["pwsh".to_string(), "-File".to_string(), playbook]
+                .into_iter()
+                .chain(cli_args)
+                .collect(),
+            _ => std::iter::once("bash".to_string())
+                .chain(std::iter::once(playbook))
+                .chain(cli_args)
+                .collect(),
+        }
+    }
+
+    /// Формирует запуск контейнера для задачи
+    ///
+    /// Terraform-приложениям репозиторий монтируется на запись: `init`
+    /// создаёт `.terraform`, а локальный state пишется рядом с кодом.
+    pub fn container_command(
+        &self,
+        image: &str,
+        cli_args: &[String],
+        extra_vars: &str,
+        ssh_key: Option<&Path>,
+    ) -> Result<ContainerCommand> {
+        let repository_writable = matches!(
+            self.template.app,
+            TemplateApp::Terraform | TemplateApp::Tofu | TemplateApp::Terragrunt
+        );
+
+        let mut env = self.get_environment_env().unwrap_or_else(|e| {
+            self.log(&format!(
+                "Warning: could not build container environment: {e}"
+            ));
+            Vec::new()
+        });
+        match self.template.app {
+            TemplateApp::Ansible => {
+                env.push("PYTHONUNBUFFERED=1".to_string());
+                env.push("ANSIBLE_FORCE_COLOR=False".to_string());
+                env.push("ANSIBLE_HOST_KEY_CHECKING=False".to_string());
+            }
+            TemplateApp::Terraform | TemplateApp::Tofu | TemplateApp::Terragrunt => {
+                env.push("TF_INPUT=0".to_string());
+                env.push("TF_IN_AUTOMATION=1".to_string());
+            }
+            _ => {}
+        }
+
+        Ok(ContainerCommand {
+            runtime: container_runtime(),
+            name: container_name(self.task.id),
+            image: image.to_string(),
+            workdir: Self::container_repository_dir(),
+            mounts: vec![
+                ContainerMount {
+                    host: self.work_dir.join("repository"),
+                    target: Self::container_repository_dir(),
+                    read_only: !repository_writable,
+                },
+                ContainerMount {
+                    host: self.tmp_dir.clone(),
+                    target: Self::container_tmp_dir(),
+                    read_only: true,
+                },
+            ],
+            env,
+            command: self.container_app_command(cli_args, extra_vars, ssh_key),
+        })
+    }
+
+    /// Записывает SSH ключ инвентаря во временный файл задачи для монтирования
+    /// в контейнер; файл удаляется при освобождении `NamedTempFile`
+    async fn write_container_ssh_key(&self) -> Option<NamedTempFile> {
+        let key_id = self.inventory.ssh_key_id?;
+        let key = match self.load_access_key(key_id).await? {
+            Ok(key) => key,
+            Err(e) => {
+                self.log(&format!("Failed to load SSH key {}: {}", key_id, e));
+                return None;
+            }
+        };
+        let private_key = key.ssh_key.filter(|k| !k.is_empty())?;
+
+        match write_private_key(&self.tmp_dir, &private_key) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                self.log(&format!("Warning: could not write SSH key file: {e}"));
+                None
+            }
+        }
+    }
+
+    /// Проверяет, запрошена ли остановка задачи
+    async fn stop_requested(&self) -> bool {
+        if self.killed {
+            return true;
+        }
+        let Some(store) = self.store.as_ref() else {
+            return false;
+        };
+        matches!(
+            store.get_task(self.task.project_id, self.task.id).await,
+            Ok(task) if matches!(task.status, TaskStatus::Stopping | TaskStatus::Stopped)
+        )
+    }
+
+    /// Запускает приложение шаблона в контейнере `image`
+    ///
+    /// Контейнер удаляется (`rm -f`), если задача остановлена.
+    pub async fn run_in_container(
+        &mut self,
+        image: &str,
+        cli_args: &[String],
+        username: &str,
+        incoming_version: Option<&str>,
+    ) -> Result<()> {
+        let ssh_key = self.write_container_ssh_key().await;
+        let extra_vars = self.get_environment_extra_vars_json(username, incoming_version)?;
+        let spec = self.container_command(
+            image,
+            cli_args,
+            &extra_vars,
+            ssh_key.as_ref().map(|f| f.path()),
+        )?;
+
+        self.log(&format!(
+            "Running in container {} ({})",
+            spec.image, spec.runtime
+        ));
+
+        let mut child = spec.to_command().spawn().map_err(|e| {
+            Error::Other(format!(
+                "Failed to start container runtime '{}': {}",
+                spec.runtime, e
+            ))
+        })?;
+        let handle = ContainerHandle {
+            runtime: spec.runtime.clone(),
+            name: spec.name.clone(),
+        };
+        self.container = Some(handle.clone());
+
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            let logger = self.logger.clone();
+            readers.push(tokio::spawn(async move {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    logger.log(&line);
+                }
+            }));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            let logger = self.logger.clone();
+            readers.push(tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    logger.log(&line);
+                }
+            }));
+        }
+
+        let status = loop {
+            tokio::select! {
+                status = child.wait() => break status?,
+                _ = tokio::time::sleep(STOP_POLL_INTERVAL) => {
+                    if self.stop_requested().await {
+                        self.log("Stop requested, removing container...");
+                        self.killed = true;
+                        let handle = handle.clone();
+                        let _ = tokio::task::spawn_blocking(move || handle.remove()).await;
+                        let _ = child.kill().await;
+                        break child.wait().await?;
+                    }
+                }
+            }
+        };
+        for reader in readers {
+            let _ = reader.await;
+        }
+        self.container = None;
+        drop(ssh_key);
+
+        if self.killed {
+            return Err(Error::Other("Task stopped".to_string()));
+        }
+        if !status.success() {
+            return Err(Error::Other(format!(
+                "Container exited with status: {}",
+                status
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_lib::AccessKeyInstallerImpl;
+    use crate::services::task_logger::BasicLogger;
+    use std::sync::Arc;
+
+    fn create_test_job(app: TemplateApp) -> LocalJob {
+        let task = crate::models::Task {
+            id: 42,
+            ..Default::default()
+        };
+        let template = crate::models::Template {
+            app,
+            playbook: "site.yml".to_string(),
+            execution_image: Some("quay.io/ansible/awx-ee:latest".to_string()),
+            ..Default::default()
+        };
+        let environment = crate::models::Environment {
+            json: r#"{"REGION": "eu"}"#.to_string(),
+            ..Default::default()
+        };
+
+        LocalJob::new(
+            task,
+            template,
+            crate::models::Inventory::default(),
+            crate::models::Repository::default(),
+            environment,
+            Arc::new(BasicLogger::new()),
+            AccessKeyInstallerImpl::new(),
+            PathBuf::from("/tmp/job"),
+            PathBuf::from("/tmp/job/tmp"),
+        )
+    }
+
+    #[test]
+    fn test_container_name() {
+        assert_eq!(container_name(7), "semaphore-task-7");
+    }
+
+    #[test]
+    fn test_to_container_path() {
+        let root = Path::new("/tmp/job/tmp");
+        assert_eq!(
+            to_container_path("/tmp/job/tmp/inventory", root, "/semaphore/tmp"),
+            "/semaphore/tmp/inventory"
+        );
+        assert_eq!(
+            to_container_path("--limit", root, "/semaphore/tmp"),
+            "--limit"
+        );
+    }
+
+    #[test]
+    fn test_ansible_container_command() {
+        let job = create_test_job(TemplateApp::Ansible);
+        let cli_args = vec!["-i".to_string(), "/tmp/job/tmp/inventory".to_string()];
+        let spec = job
+            .container_command("ee:1", &cli_args, r#"{"a":1}"#, None)
+            .unwrap();
+
+        assert_eq!(spec.name, "semaphore-task-42");
+        assert_eq!(spec.workdir, "/semaphore/repository");
+        assert!(spec.mounts.iter().all(|m| m.read_only));
+        assert_eq!(
+            spec.command,
+            vec![
+                "ansible-playbook",
+                "-e",
+                r#"{"a":1}"#,
+                "-i",
+                "/semaphore/tmp/inventory",
+                "site.yml"
+            ]
+        );
+        assert!(spec.env.contains(&"REGION=eu".to_string()));
+
+        let args = spec.to_args();
+        assert_eq!(&args[..4], ["run", "--rm", "--name", "semaphore-task-42"]);
+        assert!(args.contains(&"/tmp/job/repository:/semaphore/repository:ro".to_string()));
+        assert!(args.contains(&"REGION".to_string()));
+        assert!(!args.iter().any(|a| a == "REGION=eu"));
+        let image_pos = args.iter().position(|a| a == "ee:1").unwrap();
+        assert_eq!(args[image_pos + 1], "ansible-playbook");
+    }
+
+    #[test]
+    fn test_terraform_container_command_mounts_repository_writable() {
+        let job = create_test_job(TemplateApp::Tofu);
+        let spec = job.container_command("tofu:1", &[], "{}", None).unwrap();
+        assert!(!spec.mounts[0].read_only);
+        assert!(spec.mounts[1].read_only);
+        assert!(spec.command[2].contains("tofu apply -input=false -auto-approve tfplan"));
+        assert!(spec.env.contains(&"TF_INPUT=0".to_string()));
+    }
+
+    #[test]
+    fn test_terraform_container_command_passes_args_and_params() {
+        let mut job = create_test_job(TemplateApp::Terraform);
+        job.task.params = Some(serde_json::json!({"destroy": true}));
+        let spec = job
+            .container_command("tf:1", &["-var=region=eu".to_string()], "{}", None)
+            .unwrap();
+
+        assert_eq!(spec.command[..2], ["sh", "-c"]);
+        assert!(spec.command[2].contains("terraform plan"));
+        assert!(spec.command[2].contains("terraform destroy -input=false -auto-approve \"$@\""));
+        assert!(!spec.command[2].contains("terraform apply"));
+        assert_eq!(spec.command[3..], ["terraform", "-var=region=eu"]);
+
+        job.task.params = Some(serde_json::json!({"plan": true}));
+        let spec = job.container_command("tf:1", &[], "{}", None).unwrap();
+        assert!(spec.command[2].ends_with("exit 0"));
+    }
+
+    #[test]
+    fn test_ansible_container_command_uses_task_ssh_key() {
+        let job = create_test_job(TemplateApp::Ansible);
+        let key = Path::new("/tmp/job/tmp/ssh_key_abc");
+        let spec = job.container_command("ee:1", &[], "{}", Some(key)).unwrap();
+        assert_eq!(
+            spec.command[3..5],
+            ["--private-key", "/semaphore/tmp/ssh_key_abc"]
+        );
+    }
+
+    #[test]
+    fn test_write_private_key_is_unique_private_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = write_private_key(dir.path(), "KEY-1").unwrap();
+        let second = write_private_key(dir.path(), "KEY-2").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(std::fs::read_to_string(first.path()).unwrap(), "KEY-1");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(first.path())
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_shell_container_command() {
+        let job = create_test_job(TemplateApp::Bash);
+        let spec = job
+            .container_command("alpine", &["--flag".to_string()], "{}", None)
+            .unwrap();
+        assert_eq!(spec.command, vec!["bash", "site.yml", "--flag"]);
+    }
+}
@@ -77,14 +77,27 @@ impl LocalJob {
         details
     }
 
+    /// Переменные окружения, дополненные переопределениями задачи (`Task::environment`)
+    fn base_extra_vars(&self) -> HashMap<String, Value> {
+        let mut extra_vars: HashMap<String, Value> =
+            serde_json::from_str(&self.environment.json).unwrap_or_default();
+
+        if let Some(task_env) = self.task.environment.as_deref().filter(|s| !s.is_empty()) {
+            let overrides: HashMap<String, Value> =
+                serde_json::from_str(task_env).unwrap_or_default();
+            extra_vars.extend(overrides);
+        }
+
+        extra_vars
+    }
+
     /// Получает дополнительные переменные из окружения
     pub fn get_environment_extra_vars(
         &self,
         username: &str,
         incoming_version: Option<&str>,
     ) -> Result<HashMap<String, Value>> {
-        let mut extra_vars: HashMap<String, Value> =
-            serde_json::from_str(&self.environment.json).unwrap_or_default();
+        let mut extra_vars = self.base_extra_vars();
 
         let task_details = self.get_task_details(username, incoming_version);
         let mut semaphore_vars = Map::new();
@@ -104,8 +117,7 @@ impl LocalJob {
         username: &str,
         incoming_version: Option<&str>,
     ) -> Result<String> {
-        let mut extra_vars: HashMap<String, Value> =
-            serde_json::from_str(&self.environment.json).unwrap_or_default();
+        let mut extra_vars = self.base_extra_vars();
 
         if !self.secret.is_empty() {
             let secret_vars: HashMap<String, Value> =
@@ -715,6 +727,21 @@ mod tests {
         assert!(!details.contains_key("incoming_version"));
     }
 
+    #[test]
+    fn test_get_environment_extra_vars_merges_task_environment() {
+        let mut job = create_test_job();
+        job.task.environment = Some(r#"{"key": "override", "commit_id": "abc"}"#.to_string());
+        let vars = job.get_environment_extra_vars("user", None).unwrap();
+        assert_eq!(
+            vars.get("key"),
+            Some(&Value::String("override".to_string()))
+        );
+        assert_eq!(
+            vars.get("commit_id"),
+            Some(&Value::String("abc".to_string()))
+        );
+    }
+
     #[test]
     fn test_get_environment_extra_vars_json_is_valid() {
         let mut job = create_test_job();
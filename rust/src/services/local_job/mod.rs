@@ -5,10 +5,12 @@
 
 pub mod args;
 pub mod cli;
+pub mod container;
 pub mod environment;
 pub mod repository;
 pub mod run;
 pub mod ssh;
+pub mod timeout;
 pub mod types;
 pub mod vault;
 
@@ -65,6 +65,11 @@ impl LocalJob {
     pub async fn checkout_repository(&mut self) -> Result<()> {
         use crate::services::git_repository::GitRepository;
 
+        if self.repository.git_url.is_empty() {
+            self.log("No repository configured, skipping checkout");
+            return Ok(());
+        }
+
         let git_repo = GitRepository::new(
             self.repository.clone(),
             self.task.project_id,
@@ -77,7 +82,13 @@ impl LocalJob {
             git_repo.checkout(&commit_hash).await?;
             let msg = self.task.commit_message.clone().unwrap_or_default();
             self.set_commit(&commit_hash, &msg);
-        } else if let Some(branch) = self.repository.git_branch.clone() {
+        } else if let Some(branch) = self
+            .task
+            .git_branch
+            .clone()
+            .filter(|b| !b.is_empty())
+            .or_else(|| self.repository.git_branch.clone())
+        {
             if !branch.is_empty() {
                 self.log(&format!("Checking out branch: {}", branch));
                 git_repo.checkout(&branch).await?;
@@ -269,6 +280,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         let mut repo = crate::models::Repository::default();
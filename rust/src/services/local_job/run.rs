@@ -3,20 +3,66 @@
 //! Аналог services/tasks/local_job_run.go из Go версии
 
 use crate::db_lib::local_app::{LocalApp, LocalAppInstallingArgs, LocalAppRunningArgs};
-use crate::db_lib::{AnsibleApp, TerraformApp, create_app};
-use crate::error::Result;
+use crate::db_lib::{AnsibleApp, PlanStage, TerraformApp, create_app};
+use crate::error::{Error, Result};
 use crate::models::template::TemplateApp;
+use crate::plugins::PluginContext;
 use crate::services::local_job::LocalJob;
+use crate::services::local_job::timeout::{TERMINATION_GRACE, TERMINATION_WAIT, timeout_message};
 use crate::services::task_logger::TaskStatus;
+use std::collections::HashMap;
 
 impl LocalJob {
-    /// Запускает задачу
+    /// Запускает задачу с учётом таймаута (`timeout`)
+    ///
+    /// По истечении таймаута группы процессов приложения получают SIGTERM,
+    /// затем SIGKILL, а задача завершается ошибкой с сообщением о таймауте.
     pub async fn run(
         &mut self,
         username: &str,
         incoming_version: Option<&str>,
         alias: &str,
     ) -> Result<()> {
+        let Some(limit) = self.timeout else {
+            return self.run_stages(username, incoming_version, alias).await;
+        };
+        let groups = self.process_groups.clone();
+        let logger = self.logger.clone();
+        {
+            let stages = self.run_stages(username, incoming_version, alias);
+            tokio::pin!(stages);
+            if let Ok(result) = tokio::time::timeout(limit, &mut stages).await {
+                return result;
+            }
+            logger.log(&format!(
+                "Task exceeded its timeout of {}s, terminating",
+                limit.as_secs()
+            ));
+            // Этапы опрашиваются и дальше: процессы дожидаются, вывод дочитывается
+            let _ = tokio::time::timeout(TERMINATION_GRACE + TERMINATION_WAIT, async {
+                tokio::join!(&mut stages, groups.terminate_all(TERMINATION_GRACE))
+            })
+            .await;
+        }
+
+        self.timed_out = true;
+        if let Some(container) = self.container.take() {
+            container.remove();
+        }
+        let message = timeout_message(limit);
+        self.log(&message);
+        self.set_status(TaskStatus::Error);
+        Err(Error::Other(message))
+    }
+
+    /// Выполняет этапы задачи: ключи, репозиторий, запуск приложения
+    async fn run_stages(
+        &mut self,
+        username: &str,
+        incoming_version: Option<&str>,
+        alias: &str,
+    ) -> Result<()> {
+        self.register_secrets();
         self.set_status(TaskStatus::Starting);
         self.log("Starting job...");
 
@@ -64,8 +110,8 @@ impl LocalJob {
     /// Подготавливает запуск задачи — создаёт и выполняет приложение
     async fn prepare_run(
         &mut self,
-        _username: &str,
-        _incoming_version: Option<&str>,
+        username: &str,
+        incoming_version: Option<&str>,
         _alias: &str,
     ) -> Result<()> {
         self.log("Preparing to run task...");
@@ -75,7 +121,10 @@ impl LocalJob {
         repository.git_path = Some(repo_path.to_string_lossy().to_string());
 
         let install_args = LocalAppInstallingArgs::default();
-        let mut run_args = LocalAppRunningArgs::default();
+        let mut run_args = LocalAppRunningArgs {
+            callback: self.process_groups.callback(),
+            ..Default::default()
+        };
 
         // Write inventory data to temp file and pass -i to ansible-playbook
         if !self.inventory.inventory_data.is_empty() {
@@ -83,25 +132,27 @@ impl LocalJob {
             if let Err(e) = std::fs::write(&inv_path, &self.inventory.inventory_data) {
                 self.log(&format!("Warning: could not write inventory file: {e}"));
             } else {
-                let cli_args = run_args
-                    .cli_args
-                    .entry("default".to_string())
-                    .or_insert_with(Vec::new);
+                let cli_args = run_args.cli_args.entry("default".to_string()).or_default();
                 cli_args.push("-i".to_string());
                 cli_args.push(inv_path.to_string_lossy().to_string());
             }
         }
 
+        // Шаблон с execution_image выполняется в контейнере
+        let container_image = self
+            .template
+            .execution_image
+            .clone()
+            .map(|i| i.trim().to_string())
+            .filter(|i| !i.is_empty());
+
         match self.template.app {
             TemplateApp::Ansible => {
                 self.log("Running Ansible playbook...");
 
                 // Конвертируем task_params шаблона в флаги ansible-playbook
                 {
-                    let cli_args = run_args
-                        .cli_args
-                        .entry("default".to_string())
-                        .or_insert_with(Vec::new);
+                    let cli_args = run_args.cli_args.entry("default".to_string()).or_default();
 
                     if let Some(ref params) = self.template.task_params {
                         // --forks N
@@ -240,6 +291,14 @@ impl LocalJob {
                         }
                     }
 
+                    // Check-режим задачи: dry_run → --check, diff → --diff
+                    if self.task_param_flag("dry_run") {
+                        cli_args.push("--check".to_string());
+                    }
+                    if self.task_param_flag("diff") {
+                        cli_args.push("--diff".to_string());
+                    }
+
                     // --vault-password-file для каждого установленного vault ключа
                     let vault_names: Vec<String> =
                         self.vault_file_installations.keys().cloned().collect();
@@ -253,6 +312,13 @@ impl LocalJob {
                     }
                 }
 
+                if let Some(image) = container_image {
+                    let cli_args = run_args.cli_args.remove("default").unwrap_or_default();
+                    return self
+                        .run_in_container(&image, &cli_args, username, incoming_version)
+                        .await;
+                }
+
                 let app = AnsibleApp::new(
                     self.logger.clone(),
                     self.template.clone(),
@@ -270,7 +336,20 @@ impl LocalJob {
                     _ => "terraform",
                 };
                 self.log(&format!("Running {}...", name));
-                let app = TerraformApp::new(
+                // Аргументы шаблона и задачи для plan/destroy (инвентарь -i не нужен)
+                let cli_args = self.terraform_cli_args()?;
+                if let Some(image) = container_image {
+                    if self.plan_stage.is_some() {
+                        return Err(crate::error::Error::Validation(
+                            "Plan approval is not supported for tasks with an execution image"
+                                .to_string(),
+                        ));
+                    }
+                    return self
+                        .run_in_container(&image, &cli_args, username, incoming_version)
+                        .await;
+                }
+                let mut app = TerraformApp::new(
                     self.logger.clone(),
                     self.template.clone(),
                     repository,
@@ -278,10 +357,29 @@ impl LocalJob {
                     name.to_string(),
                     self.work_dir.clone(),
                 );
-                app.run(run_args).await?;
+                app.process_groups = self.process_groups.clone();
+                run_args.task_params = Box::new(self.terraform_task_params());
+                run_args.cli_args = HashMap::from([("default".to_string(), cli_args)]);
+                match self.plan_stage.clone() {
+                    Some(PlanStage::Review) => {
+                        self.plan_review = Some(app.plan_for_review(run_args).await?);
+                    }
+                    Some(PlanStage::Apply(saved)) => app.apply_saved_plan(run_args, &saved).await?,
+                    None => app.run(run_args).await?,
+                }
+            }
+            TemplateApp::Plugin(ref plugin_id) => {
+                let plugin_id = plugin_id.clone();
+                self.run_plugin_executor(&plugin_id).await?;
             }
             _ => {
                 self.log("Running Shell script...");
+                if let Some(image) = container_image {
+                    let cli_args = run_args.cli_args.remove("default").unwrap_or_default();
+                    return self
+                        .run_in_container(&image, &cli_args, username, incoming_version)
+                        .await;
+                }
                 let mut app = create_app(
                     self.template.clone(),
                     repository,
@@ -298,6 +396,37 @@ impl LocalJob {
         Ok(())
     }
 
+    /// Выполняет задачу плагином-исполнителем (app шаблона `plugin:<id>`)
+    async fn run_plugin_executor(&self, plugin_id: &str) -> Result<()> {
+        self.log(&format!("Running plugin {}...", plugin_id));
+        let manager = crate::plugins::plugin_manager().ok_or_else(|| {
+            Error::Config("Plugin subsystem is not enabled (SEMAPHORE_PLUGINS_DIR)".to_string())
+        })?;
+        let context = PluginContext {
+            plugin_id: plugin_id.to_string(),
+            project_id: Some(self.task.project_id.into()),
+            user_id: self.task.user_id.map(Into::into),
+            task_id: Some(self.task.id.into()),
+            metadata: HashMap::new(),
+        };
+        let result = manager
+            .read()
+            .await
+            .execute_task(plugin_id, context, &self.task)
+            .await?;
+
+        for line in result.output.lines() {
+            self.log(line);
+        }
+        if !result.success {
+            return Err(Error::Other(format!(
+                "Plugin {} failed with exit code {}",
+                plugin_id, result.exit_code
+            )));
+        }
+        Ok(())
+    }
+
     /// Очищает ресурсы после выполнения
     pub fn cleanup(&self) {
         // Очищаем рабочую директорию
@@ -346,6 +475,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         LocalJob::new(
@@ -11,8 +11,8 @@ impl LocalJob {
     pub async fn install_ssh_keys(&mut self) -> Result<()> {
         // SSH ключ для инвентаря
         if let Some(key_id) = self.inventory.ssh_key_id {
-            if let Some(store) = &self.store {
-                match store.get_access_key(self.task.project_id, key_id).await {
+            if let Some(loaded) = self.load_access_key(key_id).await {
+                match loaded {
                     Ok(ak) => {
                         let db_key = model_access_key_to_db(&ak);
                         match self.key_installer.install(
@@ -43,8 +43,8 @@ impl LocalJob {
 
         // Become ключ
         if let Some(key_id) = self.inventory.become_key_id {
-            if let Some(store) = &self.store {
-                match store.get_access_key(self.task.project_id, key_id).await {
+            if let Some(loaded) = self.load_access_key(key_id).await {
+                match loaded {
                     Ok(ak) => {
                         let db_key = model_access_key_to_db(&ak);
                         match self.key_installer.install(
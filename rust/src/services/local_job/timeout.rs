@@ -0,0 +1,72 @@
+//! LocalJob Timeout - ограничение длительности задачи
+//!
+//! Таймаут берётся из шаблона (`timeout_secs`), а если он не задан — из
+//! переменной окружения `SEMAPHORE_TASK_TIMEOUT_SECS`. По истечении таймаута
+//! процессы приложения получают SIGTERM, затем SIGKILL.
+
+use std::time::Duration;
+
+use crate::models::Template;
+
+/// Глобальный таймаут задач по умолчанию (секунды, `0` — без ограничения)
+pub const TASK_TIMEOUT_ENV: &str = "SEMAPHORE_TASK_TIMEOUT_SECS";
+
+/// Время между SIGTERM и SIGKILL
+pub const TERMINATION_GRACE: Duration = Duration::from_secs(10);
+
+/// Сколько ещё ждать завершения этапов после SIGKILL
+pub const TERMINATION_WAIT: Duration = Duration::from_secs(5);
+
+/// Глобальный таймаут из окружения
+pub fn default_task_timeout() -> Option<Duration> {
+    std::env::var(TASK_TIMEOUT_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Таймаут задачи шаблона с учётом глобального значения
+pub fn task_timeout(template: &Template) -> Option<Duration> {
+    template
+        .timeout_secs
+        .filter(|secs| *secs > 0)
+        .map(|secs| Duration::from_secs(secs as u64))
+        .or_else(default_task_timeout)
+}
+
+/// Сообщение задачи, прерванной по таймауту
+pub fn timeout_message(limit: Duration) -> String {
+    format!("Task timed out after {}s", limit.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_timeout_prefers_template() {
+        let template = Template {
+            timeout_secs: Some(90),
+            ..Default::default()
+        };
+        assert_eq!(task_timeout(&template), Some(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn test_task_timeout_ignores_non_positive() {
+        let template = Template {
+            timeout_secs: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(task_timeout(&template), default_task_timeout());
+    }
+
+    #[test]
+    fn test_timeout_message() {
+        assert_eq!(
+            timeout_message(Duration::from_secs(3600)),
+            "Task timed out after 3600s"
+        );
+    }
+}
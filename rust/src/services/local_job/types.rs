@@ -9,7 +9,11 @@ use tracing::{error, info, warn};
 
 use crate::db_lib::AccessKeyInstallerImpl;
 use crate::error::Result;
-use crate::models::{Environment, Inventory, Repository, Task, Template};
+use crate::models::{
+    AccessKey, Environment, EnvironmentSecretValue, Inventory, Repository, Task, Template,
+};
+use crate::services::key_encryption::decrypt_key_secrets;
+use crate::services::local_job::container::ContainerHandle;
 use crate::services::ssh_agent::AccessKeyInstallation;
 use crate::services::task_logger::{TaskLogger, TaskStatus};
 use crate::services::task_runner::Job;
@@ -40,20 +44,34 @@ pub struct LocalJob {
     pub key_installer: AccessKeyInstallerImpl,
     /// Процесс
     pub process: Option<Child>,
+    /// Контейнер `execution_image`, в котором выполняется задача
+    pub container: Option<ContainerHandle>,
     /// Флаг остановки
     pub killed: bool,
+    /// Максимальная длительность выполнения (`None` — без ограничения)
+    pub timeout: Option<std::time::Duration>,
+    /// Задача прервана по таймауту
+    pub timed_out: bool,
+    /// Группы процессов приложения (для завершения по таймауту)
+    pub process_groups: crate::utils::process::ProcessGroups,
     /// Рабочая директория
     pub work_dir: PathBuf,
     /// Временная директория
     pub tmp_dir: PathBuf,
     /// Store для загрузки SSH ключей из БД (опционально)
     pub store: Option<Arc<dyn crate::db::store::Store + Send + Sync>>,
+    /// Ключи доступа, переданные сервером удалённому раннеру (вместо store)
+    pub access_keys: std::collections::HashMap<i32, AccessKey>,
     /// Имя пользователя (для Job trait)
     pub username: String,
     /// Входящая версия (для Job trait)
     pub incoming_version: Option<String>,
     /// Alias для запуска (для Job trait)
     pub alias: String,
+    /// Этап подтверждения плана Terraform (`require_approval`)
+    pub plan_stage: Option<crate::db_lib::PlanStage>,
+    /// План, построенный на этапе `PlanStage::Review`
+    pub plan_review: Option<crate::db_lib::PlanReview>,
 }
 
 impl LocalJob {
@@ -83,13 +101,20 @@ impl LocalJob {
             vault_file_installations: std::collections::HashMap::new(),
             key_installer,
             process: None,
+            container: None,
             killed: false,
+            timeout: None,
+            timed_out: false,
+            process_groups: Default::default(),
             work_dir,
             tmp_dir,
             username: String::new(),
             incoming_version: None,
             alias: String::new(),
             store: None,
+            access_keys: std::collections::HashMap::new(),
+            plan_stage: None,
+            plan_review: None,
         }
     }
 
@@ -105,11 +130,95 @@ impl LocalJob {
         self.alias = alias;
     }
 
+    /// Загружает ключ доступа из переданных ключей или из БД.
+    ///
+    /// Секретные поля ключа расшифровываются и регистрируются в логгере для
+    /// маскирования. `None` — источника ключей нет (ни store, ни переданных ключей).
+    pub async fn load_access_key(&self, key_id: i32) -> Option<Result<AccessKey>> {
+        let key = if let Some(key) = self.access_keys.get(&key_id) {
+            key.clone()
+        } else {
+            match &self.store {
+                Some(store) => match store.get_access_key(self.task.project_id, key_id).await {
+                    Ok(mut key) => {
+                        decrypt_key_secrets(&mut key);
+                        key
+                    }
+                    Err(e) => return Some(Err(e)),
+                },
+                None if self.access_keys.is_empty() => return None,
+                None => {
+                    return Some(Err(crate::error::Error::NotFound(format!(
+                        "Access key {} was not provided to the runner",
+                        key_id
+                    ))));
+                }
+            }
+        };
+        for secret in [
+            &key.ssh_key,
+            &key.ssh_passphrase,
+            &key.login_password_password,
+            &key.access_key_secret_key,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.logger.add_secret(secret);
+        }
+        Some(Ok(key))
+    }
+
+    /// Регистрирует в логгере секреты, известные до запуска: секреты окружения,
+    /// секретные переменные Survey и значения, извлечённые интеграцией.
+    ///
+    /// Ключи доступа регистрируются при загрузке (`load_access_key`).
+    pub fn register_secrets(&self) {
+        if let Some(secrets) = self
+            .environment
+            .secrets
+            .as_deref()
+            .and_then(|json| serde_json::from_str::<Vec<EnvironmentSecretValue>>(json).ok())
+        {
+            for secret in secrets {
+                self.logger.add_secret(&secret.secret);
+            }
+        }
+
+        let mut sources = vec![self.task.secret.as_deref(), Some(self.secret.as_str())];
+        if self.task.integration_id.is_some() {
+            sources.push(self.task.environment.as_deref());
+        }
+        for json in sources.into_iter().flatten() {
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(json) {
+                self.add_value_secrets(&value);
+            }
+        }
+        if let (Some(_), Some(params)) = (self.task.integration_id, &self.task.params) {
+            self.add_value_secrets(params);
+        }
+    }
+
+    /// Регистрирует все строковые значения JSON
+    fn add_value_secrets(&self, value: &serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) => self.logger.add_secret(s),
+            serde_json::Value::Array(items) => items.iter().for_each(|v| self.add_value_secrets(v)),
+            serde_json::Value::Object(map) => map.values().for_each(|v| self.add_value_secrets(v)),
+            _ => {}
+        }
+    }
+
     /// Проверяет, убита ли задача
     pub fn is_killed(&self) -> bool {
         self.killed
     }
 
+    /// Проверяет, прервана ли задача по таймауту
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+
     /// Останавливает задачу
     pub fn kill(&mut self) {
         self.killed = true;
@@ -117,6 +226,10 @@ impl LocalJob {
             let _ = process.start_kill();
             self.logger.log("Process killed");
         }
+        if let Some(container) = self.container.take() {
+            container.remove();
+            self.logger.log("Container removed");
+        }
     }
 
     /// Логирует сообщение
@@ -199,6 +312,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             build_task_id: None,
+            priority: None,
         };
 
         LocalJob::new(
@@ -338,4 +452,37 @@ mod tests {
         let job = create_test_local_job();
         assert!(job.store.is_none());
     }
+    #[tokio::test]
+    async fn test_local_job_masks_registered_secrets() {
+        let mut job = create_test_local_job();
+        let lines = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        job.logger.add_log_listener(Box::new(move |_time, msg| {
+            sink.lock().unwrap().push(msg);
+        }));
+
+        job.environment.secrets = Some(
+            serde_json::json!([{"name": "TOKEN", "secret": "env-token-1", "secret_type": "env"}])
+                .to_string(),
+        );
+        job.task.secret = Some(r#"{"db_password": "survey-pass"}"#.to_string());
+        job.task.integration_id = Some(3);
+        job.task.environment = Some(r#"{"hook_secret": "from-webhook"}"#.to_string());
+        let mut key = AccessKey::new(
+            "vault".to_string(),
+            crate::models::AccessKeyType::LoginPassword,
+        );
+        key.id = 5;
+        key.login_password_password = Some("vault-pass".to_string());
+        job.access_keys.insert(5, key);
+
+        job.register_secrets();
+        assert!(job.load_access_key(5).await.unwrap().is_ok());
+        job.log("env-token-1 survey-pass from-webhook vault-pass");
+
+        assert_eq!(
+            lines.lock().unwrap().last().unwrap(),
+            "******** ******** ******** ********"
+        );
+    }
 }
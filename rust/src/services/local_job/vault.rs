@@ -49,22 +49,16 @@ impl LocalJob {
             return Ok(());
         }
 
-        let store = match self.store.as_ref() {
-            Some(s) => s.clone(),
-            None => {
-                self.log("Warning: no store available for vault key loading");
-                return Ok(());
-            }
-        };
+        if self.store.is_none() && self.access_keys.is_empty() {
+            self.log("Warning: no store available for vault key loading");
+            return Ok(());
+        }
 
         for (i, vref) in vault_refs.iter().enumerate() {
-            use crate::db::store::AccessKeyManager;
-            let key = match store
-                .get_access_key(self.task.project_id, vref.vault_key_id)
-                .await
-            {
-                Ok(k) => k,
-                Err(e) => {
+            let key = match self.load_access_key(vref.vault_key_id).await {
+                Some(Ok(k)) => k,
+                None => continue,
+                Some(Err(e)) => {
                     self.log(&format!(
                         "Warning: vault key {} not found: {}",
                         vref.vault_key_id, e
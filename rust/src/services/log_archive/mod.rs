@@ -0,0 +1,544 @@
+//! Архивирование логов задач
+//!
+//! Вывод завершённых задач переносится из таблицы `task_output` в сжатые
+//! чанки (gzip, JSON Lines по строке `TaskOutput`) в локальном каталоге или
+//! S3-совместимом хранилище. Запись `task_log_archive` указывает, где лежат
+//! чанки; строки из `task_output` удаляются в той же транзакции. Каждая попытка
+//! архивации пишет в свой каталог, поэтому неудачная попытка удаляет только
+//! свои чанки. Архивация и политики хранения выполняются на узле-лидере.
+//!
+//! Чтение прозрачно: [`load_task_outputs`] склеивает архив и строки, оставшиеся
+//! в БД. Политика хранения проекта (см. [`apply_retention`]) работает и без
+//! настроенного архива.
+//!
+//! Настройка через окружение:
+//! - `SEMAPHORE_LOG_ARCHIVE` — `local` или `s3`
+//! - `SEMAPHORE_LOG_ARCHIVE_PATH` — каталог для `local`
+//! - `SEMAPHORE_LOG_ARCHIVE_S3_ENDPOINT`, `_S3_BUCKET`, `_S3_REGION`,
+//!   `_S3_ACCESS_KEY`, `_S3_SECRET_KEY` — подключение для `s3`
+//! - `SEMAPHORE_LOG_ARCHIVE_PREFIX` — префикс ключей
+//! - `SEMAPHORE_LOG_ARCHIVE_CHUNK_LINES` — строк в чанке
+//! - `SEMAPHORE_LOG_ARCHIVE_AFTER_SECS` — через сколько после завершения
+//!   задачи архивировать её лог
+//! - `SEMAPHORE_LOG_MAINTENANCE_INTERVAL_SECS` — интервал архивации и
+//!   применения политик хранения
+
+mod retention;
+mod s3;
+mod storage;
+
+pub use retention::apply_retention;
+pub use s3::{S3Config, S3Storage};
+pub use storage::{ArchiveStorage, LocalStorage};
+
+use chrono::Utc;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::{TaskLogArchive, TaskOutput};
+use crate::services::leader_election::Leadership;
+
+/// Строк вывода в одном чанке по умолчанию
+pub const DEFAULT_CHUNK_LINES: usize = 5000;
+
+/// Сколько задач архивируется за один проход
+const ARCHIVE_BATCH: i64 = 100;
+
+static LOG_ARCHIVE: OnceLock<Arc<LogArchive>> = OnceLock::new();
+
+/// Хранилище архива
+#[derive(Debug, Clone)]
+pub enum ArchiveBackend {
+    Local(PathBuf),
+    S3(S3Config),
+}
+
+/// Конфигурация архива логов
+#[derive(Debug, Clone)]
+pub struct LogArchiveConfig {
+    pub backend: ArchiveBackend,
+    /// Префикс ключей чанков
+    pub prefix: String,
+    pub chunk_lines: usize,
+    /// Задержка архивации после завершения задачи
+    pub archive_after: Duration,
+}
+
+/// Конфигурация архива из окружения; `None`, если архив не включён
+pub fn config_from_env() -> Result<Option<LogArchiveConfig>> {
+    let env = |name: &str| {
+        std::env::var(format!("SEMAPHORE_LOG_ARCHIVE{name}"))
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let required = |name: &str| {
+        env(name).ok_or_else(|| {
+            Error::Config(format!(
+                "SEMAPHORE_LOG_ARCHIVE{name} is required for the log archive"
+            ))
+        })
+    };
+
+    let backend = match env("").as_deref() {
+        None => return Ok(None),
+        Some("local") => ArchiveBackend::Local(required("_PATH")?.into()),
+        Some("s3") => ArchiveBackend::S3(S3Config {
+            endpoint: required("_S3_ENDPOINT")?,
+            bucket: required("_S3_BUCKET")?,
+            region: env("_S3_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+            access_key: required("_S3_ACCESS_KEY")?,
+            secret_key: required("_S3_SECRET_KEY")?,
+        }),
+        Some(other) => {
+            return Err(Error::Config(format!(
+                "Unknown log archive backend '{other}' (expected local or s3)"
+            )));
+        }
+    };
+
+    Ok(Some(LogArchiveConfig {
+        backend,
+        prefix: env("_PREFIX").unwrap_or_default(),
+        chunk_lines: env("_CHUNK_LINES")
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_CHUNK_LINES),
+        archive_after: Duration::from_secs(
+            env("_AFTER_SECS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+        ),
+    }))
+}
+
+/// Интервал фонового обслуживания логов
+pub fn maintenance_interval() -> Duration {
+    let secs = std::env::var("SEMAPHORE_LOG_MAINTENANCE_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(600);
+    Duration::from_secs(secs)
+}
+
+/// Архив логов задач
+pub struct LogArchive {
+    storage: Box<dyn ArchiveStorage>,
+    prefix: String,
+    chunk_lines: usize,
+    archive_after: Duration,
+}
+
+impl LogArchive {
+    pub fn new(
+        storage: Box<dyn ArchiveStorage>,
+        prefix: impl Into<String>,
+        chunk_lines: usize,
+    ) -> Self {
+        Self {
+            storage,
+            prefix: prefix.into(),
+            chunk_lines: chunk_lines.max(1),
+            archive_after: Duration::ZERO,
+        }
+    }
+
+    pub fn from_config(config: LogArchiveConfig) -> Self {
+        let storage: Box<dyn ArchiveStorage> = match config.backend {
+            ArchiveBackend::Local(path) => Box::new(LocalStorage::new(path)),
+            ArchiveBackend::S3(s3) => Box::new(S3Storage::new(s3)),
+        };
+        Self {
+            archive_after: config.archive_after,
+            ..Self::new(storage, config.prefix, config.chunk_lines)
+        }
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.storage.backend()
+    }
+
+    /// Каталог попытки архивации: `<prefix>project_X/task_Y/<uuid>/`
+    fn location(&self, project_id: i32, task_id: i32) -> String {
+        format!(
+            "{}project_{project_id}/task_{task_id}/{}/",
+            self.prefix,
+            uuid::Uuid::new_v4().simple()
+        )
+    }
+
+    fn chunk_key(location: &str, index: i32) -> String {
+        format!("{location}{index:05}.jsonl.gz")
+    }
+
+    /// Переносит вывод задачи в архив; `None`, если выводить нечего
+    pub async fn archive_task(
+        &self,
+        store: &dyn Store,
+        project_id: i32,
+        task_id: i32,
+    ) -> Result<Option<TaskLogArchive>> {
+        let mut outputs = store.get_task_outputs(task_id).await?;
+        if outputs.is_empty() {
+            return Ok(None);
+        }
+        outputs.sort_by_key(|o| (o.time, o.id));
+        let last_output_id = outputs.iter().map(|o| o.id).max().unwrap_or(0);
+
+        let location = self.location(project_id, task_id);
+        let mut keys = Vec::new();
+        let mut bytes = 0i64;
+        for chunk in outputs.chunks(self.chunk_lines) {
+            let data = encode_chunk(chunk)?;
+            bytes += data.len() as i64;
+            let key = Self::chunk_key(&location, keys.len() as i32);
+            if let Err(e) = self.storage.put(&key, data).await {
+                self.delete_keys(&keys).await;
+                return Err(e);
+            }
+            keys.push(key);
+        }
+
+        let archive = TaskLogArchive {
+            task_id,
+            project_id,
+            backend: self.backend().to_string(),
+            location,
+            chunks: keys.len() as i32,
+            lines: outputs.len() as i32,
+            bytes,
+            archived: Utc::now(),
+        };
+        // Без записи в БД чанки недостижимы — удаляем их. Каталог принадлежит
+        // только этой попытке, чужой архив задачи не затрагивается.
+        if let Err(e) = store
+            .complete_task_log_archive(archive.clone(), last_output_id)
+            .await
+        {
+            self.delete_keys(&keys).await;
+            return Err(e);
+        }
+        Ok(Some(archive))
+    }
+
+    /// Читает архивированный вывод задачи
+    pub async fn read(&self, archive: &TaskLogArchive) -> Result<Vec<TaskOutput>> {
+        if archive.backend != self.backend() {
+            return Err(Error::Config(format!(
+                "Task {} log is archived in '{}' storage, but '{}' is configured",
+                archive.task_id,
+                archive.backend,
+                self.backend()
+            )));
+        }
+        let mut outputs = Vec::with_capacity(archive.lines.max(0) as usize);
+        for index in 0..archive.chunks {
+            let data = self
+                .storage
+                .get(&Self::chunk_key(&archive.location, index))
+                .await?;
+            outputs.extend(decode_chunk(&data)?);
+        }
+        Ok(outputs)
+    }
+
+    /// Удаляет чанки архива из хранилища
+    pub async fn delete(&self, archive: &TaskLogArchive) -> Result<()> {
+        for index in 0..archive.chunks {
+            self.storage
+                .delete(&Self::chunk_key(&archive.location, index))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_keys(&self, keys: &[String]) {
+        for key in keys {
+            if let Err(e) = self.storage.delete(key).await {
+                warn!("Failed to delete archive chunk {}: {}", key, e);
+            }
+        }
+    }
+}
+
+fn encode_chunk(outputs: &[TaskOutput]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for output in outputs {
+        serde_json::to_writer(&mut encoder, output)?;
+        encoder.write_all(b"\n")?;
+    }
+    Ok(encoder.finish()?)
+}
+
+fn decode_chunk(data: &[u8]) -> Result<Vec<TaskOutput>> {
+    let mut outputs = Vec::new();
+    for line in BufReader::new(GzDecoder::new(data)).lines() {
+        let line = line?;
+        if !line.is_empty() {
+            outputs.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(outputs)
+}
+
+/// Включает глобальный архив логов
+pub fn init(archive: LogArchive) -> Arc<LogArchive> {
+    LOG_ARCHIVE.get_or_init(|| Arc::new(archive)).clone()
+}
+
+/// Глобальный архив логов, если он включён
+pub fn archive() -> Option<Arc<LogArchive>> {
+    LOG_ARCHIVE.get().cloned()
+}
+
+/// Вывод задачи с учётом архива (используется API `/output` и `/raw_output`)
+pub async fn load_task_outputs(store: &dyn Store, task_id: i32) -> Result<Vec<TaskOutput>> {
+    read_task_outputs(store, archive().as_deref(), task_id).await
+}
+
+/// Архивированный вывод и строки, добавленные в БД после архивации
+pub async fn read_task_outputs(
+    store: &dyn Store,
+    archive: Option<&LogArchive>,
+    task_id: i32,
+) -> Result<Vec<TaskOutput>> {
+    let remaining = store.get_task_outputs(task_id).await?;
+    let Some(record) = store.get_task_log_archive(task_id).await? else {
+        return Ok(remaining);
+    };
+    let archive = archive.ok_or_else(|| {
+        Error::Config(format!(
+            "Task {task_id} log is archived, but the log archive is not configured"
+        ))
+    })?;
+    let mut outputs = archive.read(&record).await?;
+    outputs.extend(remaining);
+    Ok(outputs)
+}
+
+/// Архивирует вывод задач, завершённых дольше `archive_after` назад
+pub async fn archive_finished_tasks(store: &dyn Store, archive: &LogArchive) -> usize {
+    let cutoff = Utc::now()
+        - chrono::Duration::from_std(archive.archive_after).unwrap_or(chrono::Duration::zero());
+    let tasks = match store.get_tasks_to_archive(cutoff, ARCHIVE_BATCH).await {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            warn!("Failed to list tasks for log archival: {}", e);
+            return 0;
+        }
+    };
+
+    let mut archived = 0;
+    for (project_id, task_id) in tasks {
+        match archive.archive_task(store, project_id, task_id).await {
+            Ok(Some(_)) => archived += 1,
+            Ok(None) => {}
+            Err(e) => warn!("Failed to archive log of task {}: {}", task_id, e),
+        }
+    }
+    archived
+}
+
+/// Запускает фоновую архивацию логов и применение политик хранения
+/// (выполняются только на узле-лидере)
+pub fn spawn_log_maintenance(
+    store: Arc<dyn Store + Send + Sync>,
+    archive: Option<Arc<LogArchive>>,
+    interval: Duration,
+    leadership: Leadership,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if !leadership.is_leader() {
+                continue;
+            }
+            if let Some(archive) = &archive {
+                let archived = archive_finished_tasks(store.as_ref(), archive).await;
+                if archived > 0 {
+                    info!("Archived logs of {} tasks", archived);
+                }
+            }
+            let purged = apply_retention(store.as_ref(), archive.as_deref()).await;
+            if purged > 0 {
+                info!("Retention policies removed {} tasks", purged);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{TaskLogArchiveManager, TaskManager};
+    use crate::models::Task;
+    use crate::services::task_logger::TaskStatus;
+
+    pub(super) async fn finished_task(store: &MockStore, id: i32, lines: usize) {
+        store
+            .create_task(Task {
+                id,
+                project_id: 1,
+                template_id: 1,
+                status: TaskStatus::Success,
+                end: Some(Utc::now() - chrono::Duration::hours(1)),
+                ..Task::default()
+            })
+            .await
+            .unwrap();
+        for line in 0..lines {
+            store
+                .create_task_output(TaskOutput {
+                    id: 0,
+                    task_id: id,
+                    project_id: 1,
+                    time: Utc::now() + chrono::Duration::milliseconds(line as i64),
+                    output: format!("task {id} line {line}"),
+                    stage_id: None,
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    fn local_archive(dir: &std::path::Path) -> LogArchive {
+        LogArchive::new(Box::new(LocalStorage::new(dir)), "logs/", 2)
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = local_archive(dir.path());
+        let store = MockStore::new();
+        finished_task(&store, 1, 5).await;
+        finished_task(&store, 2, 0).await;
+
+        assert_eq!(archive_finished_tasks(&store, &archive).await, 1);
+        let record = store.get_task_log_archive(1).await.unwrap().unwrap();
+        assert_eq!((record.chunks, record.lines), (3, 5));
+        assert!(record.location.starts_with("logs/project_1/task_1/"));
+        assert!(
+            dir.path()
+                .join(format!("{}00002.jsonl.gz", record.location))
+                .exists()
+        );
+        assert!(store.get_task_outputs(1).await.unwrap().is_empty());
+
+        // Строка, дописанная после архивации, остаётся в БД и читается следом
+        store
+            .create_task_output(TaskOutput {
+                id: 0,
+                task_id: 1,
+                project_id: 1,
+                time: Utc::now() + chrono::Duration::seconds(10),
+                output: "late line".to_string(),
+                stage_id: None,
+            })
+            .await
+            .unwrap();
+        let outputs = read_task_outputs(&store, Some(&archive), 1).await.unwrap();
+        let lines: Vec<_> = outputs.iter().map(|o| o.output.as_str()).collect();
+        assert_eq!(
+            lines,
+            [
+                "task 1 line 0",
+                "task 1 line 1",
+                "task 1 line 2",
+                "task 1 line 3",
+                "task 1 line 4",
+                "late line"
+            ]
+        );
+
+        // Уже архивированная задача повторно не архивируется
+        assert_eq!(archive_finished_tasks(&store, &archive).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_without_archive_configured() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MockStore::new();
+        finished_task(&store, 1, 1).await;
+        assert_eq!(read_task_outputs(&store, None, 1).await.unwrap().len(), 1);
+
+        local_archive(dir.path())
+            .archive_task(&store, 1, 1)
+            .await
+            .unwrap();
+        assert!(matches!(
+            read_task_outputs(&store, None, 1).await,
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_archive_to_s3() {
+        let (storage, objects) = s3::tests::start_minio().await;
+        let archive = LogArchive::new(Box::new(storage), "", 100);
+        let store = MockStore::new();
+        finished_task(&store, 7, 3).await;
+
+        let record = archive.archive_task(&store, 1, 7).await.unwrap().unwrap();
+        assert_eq!(record.backend, "s3");
+        assert!(
+            objects
+                .lock()
+                .unwrap()
+                .contains_key(&format!("logs/{}00000.jsonl.gz", record.location))
+        );
+        assert_eq!(archive.read(&record).await.unwrap().len(), 3);
+
+        archive.delete(&record).await.unwrap();
+        assert!(objects.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempt_keeps_committed_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = local_archive(dir.path());
+        let store = MockStore::new();
+        finished_task(&store, 1, 3).await;
+        let lines = store.get_task_outputs(1).await.unwrap();
+
+        archive.archive_task(&store, 1, 1).await.unwrap().unwrap();
+        let committed = store.get_task_log_archive(1).await.unwrap().unwrap();
+
+        // Второй узел прочитал те же строки до фиксации первого
+        for line in lines {
+            store.create_task_output(line).await.unwrap();
+        }
+        assert!(archive.archive_task(&store, 1, 1).await.is_err());
+
+        let outputs = archive.read(&committed).await.unwrap();
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs[0].output, "task 1 line 0");
+    }
+
+    #[tokio::test]
+    async fn test_backend_mismatch_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = local_archive(dir.path());
+        let record = TaskLogArchive {
+            task_id: 1,
+            project_id: 1,
+            backend: "s3".to_string(),
+            location: "project_1/task_1/".to_string(),
+            chunks: 1,
+            lines: 1,
+            bytes: 10,
+            archived: Utc::now(),
+        };
+        assert!(matches!(archive.read(&record).await, Err(Error::Config(_))));
+    }
+}
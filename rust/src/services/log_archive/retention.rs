@@ -0,0 +1,184 @@
+//! Политики хранения задач
+//!
+//! Задачи проекта старше `max_age_days` или вне последних `keep_last_runs`
+//! запусков своего шаблона удаляются вместе с выводом, стадиями и чанками
+//! архива. Незавершённые задачи не удаляются.
+
+use tracing::warn;
+
+use super::LogArchive;
+use crate::db::store::Store;
+
+/// Применяет политики хранения всех проектов; возвращает число удалённых задач
+pub async fn apply_retention(store: &dyn Store, archive: Option<&LogArchive>) -> usize {
+    let policies = match store.get_log_retention_policies().await {
+        Ok(policies) => policies,
+        Err(e) => {
+            warn!("Failed to load log retention policies: {}", e);
+            return 0;
+        }
+    };
+
+    let mut purged = 0;
+    for policy in policies.iter().filter(|p| !p.is_unlimited()) {
+        let expired = match store.get_expired_task_ids(policy).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                warn!(
+                    "Failed to find expired tasks of project {}: {}",
+                    policy.project_id, e
+                );
+                continue;
+            }
+        };
+
+        let mut removable = Vec::with_capacity(expired.len());
+        for task_id in expired {
+            if delete_archived_log(store, archive, task_id).await {
+                removable.push(task_id);
+            }
+        }
+        match store.purge_tasks(policy.project_id, &removable).await {
+            Ok(()) => purged += removable.len(),
+            Err(e) => warn!(
+                "Failed to purge expired tasks of project {}: {}",
+                policy.project_id, e
+            ),
+        }
+    }
+    purged
+}
+
+/// Удаляет архив задачи из хранилища. `false` — задачу пока нельзя удалять:
+/// без записи в БД чанки архива остались бы в хранилище навсегда
+async fn delete_archived_log(
+    store: &dyn Store,
+    archive: Option<&LogArchive>,
+    task_id: i32,
+) -> bool {
+    let record = match store.get_task_log_archive(task_id).await {
+        Ok(Some(record)) => record,
+        Ok(None) => return true,
+        Err(e) => {
+            warn!("Failed to load log archive of task {}: {}", task_id, e);
+            return false;
+        }
+    };
+    let Some(archive) = archive.filter(|a| a.backend() == record.backend) else {
+        warn!(
+            "Task {} log is archived in '{}' storage, which is not configured; keeping the task",
+            task_id, record.backend
+        );
+        return false;
+    };
+    match archive.delete(&record).await {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to delete log archive of task {}: {}", task_id, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{TaskLogArchiveManager, TaskManager};
+    use crate::models::{LogRetentionPolicy, Task};
+    use crate::services::log_archive::LocalStorage;
+    use crate::services::log_archive::tests::finished_task;
+    use crate::services::task_logger::TaskStatus;
+    use chrono::Utc;
+
+    #[tokio::test]
+    async fn test_keep_last_runs_per_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = LogArchive::new(Box::new(LocalStorage::new(dir.path())), "", 10);
+        let store = MockStore::new();
+        for id in 1..=4 {
+            finished_task(&store, id, 1).await;
+        }
+        let record = archive.archive_task(&store, 1, 1).await.unwrap().unwrap();
+        let chunk = dir
+            .path()
+            .join(format!("{}00000.jsonl.gz", record.location));
+        assert!(chunk.exists());
+        store
+            .set_log_retention_policy(LogRetentionPolicy {
+                project_id: 1,
+                max_age_days: None,
+                keep_last_runs: Some(2),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(apply_retention(&store, Some(&archive)).await, 2);
+        let mut left: Vec<_> = store
+            .get_tasks(1, None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|t| t.task.id)
+            .collect();
+        left.sort();
+        assert_eq!(left, vec![3, 4]);
+        assert!(!chunk.exists());
+        assert!(store.get_task_outputs(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_max_age_keeps_running_tasks() {
+        let store = MockStore::new();
+        let old = Utc::now() - chrono::Duration::days(40);
+        for (id, status) in [(1, TaskStatus::Success), (2, TaskStatus::Running)] {
+            store
+                .create_task(Task {
+                    id,
+                    project_id: 1,
+                    template_id: 1,
+                    status,
+                    created: old,
+                    end: Some(old),
+                    ..Task::default()
+                })
+                .await
+                .unwrap();
+        }
+        finished_task(&store, 3, 1).await;
+        store
+            .set_log_retention_policy(LogRetentionPolicy {
+                project_id: 1,
+                max_age_days: Some(30),
+                keep_last_runs: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(apply_retention(&store, None).await, 1);
+        assert!(store.get_task(1, 1).await.is_err());
+        assert!(store.get_task(1, 2).await.is_ok());
+        assert!(store.get_task(1, 3).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_archived_task_kept_without_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = LogArchive::new(Box::new(LocalStorage::new(dir.path())), "", 10);
+        let store = MockStore::new();
+        finished_task(&store, 1, 1).await;
+        finished_task(&store, 2, 1).await;
+        archive.archive_task(&store, 1, 1).await.unwrap();
+        store
+            .set_log_retention_policy(LogRetentionPolicy {
+                project_id: 1,
+                max_age_days: None,
+                keep_last_runs: Some(1),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(apply_retention(&store, None).await, 0);
+        assert!(store.get_task(1, 1).await.is_ok());
+    }
+}
@@ -0,0 +1,118 @@
+//! Хранилища чанков архива логов
+
+use async_trait::async_trait;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Хранилище объектов архива (ключ → байты)
+#[async_trait]
+pub trait ArchiveStorage: Send + Sync {
+    /// Имя хранилища, записываемое в `TaskLogArchive::backend`
+    fn backend(&self) -> &'static str;
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// Удаляет объект; отсутствие объекта ошибкой не считается
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// Архив в локальном каталоге
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Путь объекта; ключи с `..` и абсолютные пути отклоняются
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(key);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(Error::Validation(format!("Invalid archive key: {key}")));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl ArchiveStorage for LocalStorage {
+    fn backend(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Запись через временный файл: чанк либо записан целиком, либо отсутствует
+        let tmp = path.with_extension("partial");
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(tokio::fs::read(self.path(key)?).await?)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_storage_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+
+        storage
+            .put("project_1/task_2/00000.jsonl.gz", b"chunk".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .get("project_1/task_2/00000.jsonl.gz")
+                .await
+                .unwrap(),
+            b"chunk"
+        );
+        storage
+            .delete("project_1/task_2/00000.jsonl.gz")
+            .await
+            .unwrap();
+        storage
+            .delete("project_1/task_2/00000.jsonl.gz")
+            .await
+            .unwrap();
+        assert!(
+            storage
+                .get("project_1/task_2/00000.jsonl.gz")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_rejects_escaping_keys() {
+        let storage = LocalStorage::new("/tmp/archive");
+        assert!(storage.put("../etc/passwd", Vec::new()).await.is_err());
+        assert!(storage.get("/etc/passwd").await.is_err());
+    }
+}
@@ -6,20 +6,33 @@ pub mod alert;
 pub mod auto_backup;
 pub mod backup;
 pub mod cache_service;
+pub mod concurrency;
+pub mod drift;
+pub mod event_outbox;
 pub mod executor;
 pub mod exporter;
 pub mod exporter_main;
+pub mod forge_webhook;
 pub mod git_repository;
+pub mod integration;
 pub mod key_encryption;
+pub mod leader_election;
 pub mod local_job;
+pub mod log_archive;
 pub mod metrics;
 pub mod playbook_run_service;
 pub mod playbook_run_status_service;
 pub mod playbook_sync_service;
+pub mod project_access;
+pub mod promotion;
 pub mod remote_runners;
 pub mod restore;
+pub mod runner_job;
 pub mod runners;
+pub mod schedule_calendar;
+pub mod schedule_commit;
 pub mod scheduler;
+pub mod secret_masker;
 pub mod ssh_agent;
 pub mod ssh_auth_service;
 pub mod task_execution;
@@ -29,8 +42,10 @@ pub mod task_pool_queue;
 pub mod task_pool_runner;
 pub mod task_pool_status;
 pub mod task_pool_types;
+pub mod task_reaper;
 pub mod task_runner;
 pub mod telegram_bot;
+pub mod template_inheritance;
 pub mod totp;
 pub mod webhook;
 pub mod workflow_executor;
@@ -97,6 +97,7 @@ impl PlaybookRunService {
             repository_id: playbook.repository_id,
             environment_id: request.environment_id,
             params: None,
+            priority: None,
         };
 
         // 8. Сохраняем задачу
@@ -195,6 +196,9 @@ impl PlaybookRunService {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
 
         let created_template = store.create_template(template).await?;
@@ -0,0 +1,339 @@
+//! Права пользователя в проекте
+//!
+//! Роль участника хранится slug'ом в `project_user.role`: это либо встроенная
+//! роль (`owner`, `manager`, `task_runner`, `guest`), либо кастомная роль
+//! проекта из `project_role`. Разрешения роли берутся из её bitmask.
+//!
+//! Для операций над шаблоном действуют переопределения из `template_role`:
+//! участники с ролью `role_slug` получают на этом шаблоне разрешения роли
+//! `role_id` (встроенной — по отрицательному ID, или кастомной).
+//!
+//! Глобальный администратор имеет все разрешения в любом проекте.
+//!
+//! Запрос с API-токеном дополнительно ограничен областями токена и списками
+//! разрешённых проектов и шаблонов.
+
+use std::sync::Arc;
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::{APIToken, Permission, Role, RolePermissions};
+
+/// Роль участника, не дающая доступа к проекту
+const NO_ROLE: &str = "none";
+
+/// Субъект проверки прав
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    pub user_id: i32,
+    pub admin: bool,
+    /// API-токен, которым аутентифицирован запрос
+    pub token: Option<Arc<APIToken>>,
+}
+
+impl Caller {
+    /// Может ли вызывающий выполнять изменения вне проектов (например,
+    /// создавать проекты)
+    pub fn allows_global_writes(&self) -> bool {
+        self.token.as_ref().is_none_or(|t| t.allows_global_writes())
+    }
+
+    /// Виден ли вызывающему проект (API-токен может быть ограничен проектами)
+    pub fn allows_project(&self, project_id: i32) -> bool {
+        self.token
+            .as_ref()
+            .is_none_or(|t| t.allows_project(project_id))
+    }
+}
+
+/// Отказ в доступе к проекту
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDenied {
+    /// Пользователь не участник проекта
+    NotMember,
+    /// У роли нет требуемого разрешения
+    Missing(Permission),
+    /// API-токен не действует в этом проекте или на этом шаблоне
+    TokenRestricted,
+    /// Области API-токена не дают требуемого разрешения
+    TokenScope(Permission),
+}
+
+impl AccessDenied {
+    /// Имя недостающего разрешения
+    pub fn permission(&self) -> Option<Permission> {
+        match self {
+            AccessDenied::NotMember | AccessDenied::TokenRestricted => None,
+            AccessDenied::Missing(permission) | AccessDenied::TokenScope(permission) => {
+                Some(*permission)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessDenied::NotMember => f.write_str("User is not a member of this project"),
+            AccessDenied::Missing(permission) => write!(f, "Missing permission: {permission}"),
+            AccessDenied::TokenRestricted => {
+                f.write_str("API token is not valid for this project or template")
+            }
+            AccessDenied::TokenScope(permission) => {
+                write!(f, "API token scope does not grant permission: {permission}")
+            }
+        }
+    }
+}
+
+impl From<AccessDenied> for Error {
+    fn from(denied: AccessDenied) -> Self {
+        Error::Forbidden(denied.to_string())
+    }
+}
+
+/// Разрешения пользователя в проекте; `None`, если он не участник
+///
+/// С `template_id` учитываются переопределения ролей на этом шаблоне.
+pub async fn resolve_permissions(
+    store: &dyn Store,
+    caller: &Caller,
+    project_id: i32,
+    template_id: Option<i32>,
+) -> Result<Option<RolePermissions>> {
+    if caller.admin {
+        return Ok(Some(RolePermissions::admin()));
+    }
+    let Some(slug) = store
+        .get_project_user_role(project_id, caller.user_id)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if slug == NO_ROLE {
+        return Ok(None);
+    }
+
+    let mut roles = Role::builtin(project_id);
+    roles.extend(store.get_project_roles(project_id).await?);
+
+    let mut role = roles.iter().find(|r| r.slug == slug);
+    if let Some(template_id) = template_id {
+        let overrides = store
+            .get_template_role_perms(project_id, template_id)
+            .await?;
+        if let Some(perm) = overrides.iter().find(|p| p.role_slug == slug) {
+            role = roles.iter().find(|r| r.id == perm.role_id);
+        }
+    }
+    // Роль удалена — участник остаётся в проекте без разрешений
+    Ok(Some(
+        role.map(Role::get_permissions)
+            .unwrap_or_else(|| RolePermissions::from_bitmask(0)),
+    ))
+}
+
+/// Ограничения API-токена на операцию в проекте
+pub fn check_token(
+    token: &APIToken,
+    project_id: i32,
+    template_id: Option<i32>,
+    required: Option<Permission>,
+) -> std::result::Result<(), AccessDenied> {
+    if !token.allows_project(project_id) || !token.allows_template(template_id, required) {
+        return Err(AccessDenied::TokenRestricted);
+    }
+    match required {
+        Some(permission) if !token.grants(required) => Err(AccessDenied::TokenScope(permission)),
+        _ => Ok(()),
+    }
+}
+
+/// Проверяет доступ к проекту; без `required` достаточно быть участником
+pub async fn check_permission(
+    store: &dyn Store,
+    caller: &Caller,
+    project_id: i32,
+    template_id: Option<i32>,
+    required: Option<Permission>,
+) -> Result<std::result::Result<(), AccessDenied>> {
+    if let Some(token) = &caller.token {
+        if let Err(denied) = check_token(token, project_id, template_id, required) {
+            return Ok(Err(denied));
+        }
+    }
+    let permissions = resolve_permissions(store, caller, project_id, template_id).await?;
+    Ok(match (permissions, required) {
+        (None, _) => Err(AccessDenied::NotMember),
+        (Some(permissions), Some(required)) if !permissions.has(required) => {
+            Err(AccessDenied::Missing(required))
+        }
+        _ => Ok(()),
+    })
+}
+
+/// Проверяет доступ, превращая отказ в `Error::Forbidden`
+pub async fn require_permission(
+    store: &dyn Store,
+    caller: &Caller,
+    project_id: i32,
+    template_id: Option<i32>,
+    required: Option<Permission>,
+) -> Result<()> {
+    check_permission(store, caller, project_id, template_id, required)
+        .await?
+        .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{ProjectAccessManager, ProjectRoleManager};
+    use crate::models::{TemplateRolePerm, TokenScope};
+
+    const USER: Caller = Caller {
+        user_id: 5,
+        admin: false,
+        token: None,
+    };
+
+    async fn store_with_role(slug: &str) -> MockStore {
+        let store = MockStore::new();
+        store
+            .set_project_user_role(1, USER.user_id, slug)
+            .await
+            .unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_builtin_roles() {
+        let store = store_with_role("task_runner").await;
+        let check = |required| check_permission(&store, &USER, 1, None, required);
+        assert_eq!(check(None).await.unwrap(), Ok(()));
+        assert_eq!(check(Some(Permission::RunTasks)).await.unwrap(), Ok(()));
+        assert_eq!(
+            check(Some(Permission::UpdateResources)).await.unwrap(),
+            Err(AccessDenied::Missing(Permission::UpdateResources))
+        );
+        assert_eq!(
+            check_permission(&store, &USER, 2, None, None)
+                .await
+                .unwrap(),
+            Err(AccessDenied::NotMember)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_and_none_role() {
+        let store = store_with_role("none").await;
+        assert_eq!(
+            check_permission(&store, &USER, 1, None, None)
+                .await
+                .unwrap(),
+            Err(AccessDenied::NotMember)
+        );
+        let admin = Caller {
+            user_id: 99,
+            admin: true,
+            token: None,
+        };
+        assert!(
+            require_permission(&store, &admin, 1, None, Some(Permission::ManageUsers))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_role_and_template_override() {
+        let store = store_with_role("auditor").await;
+        let auditor = store
+            .create_project_role(Role::new_with_permissions(
+                1,
+                "auditor".to_string(),
+                "Auditor".to_string(),
+                0b0010_0000,
+            ))
+            .await
+            .unwrap();
+        let deployer = store
+            .create_project_role(Role::new_with_permissions(
+                1,
+                "deployer".to_string(),
+                "Deployer".to_string(),
+                0b0000_0001,
+            ))
+            .await
+            .unwrap();
+        assert_ne!(auditor.id, deployer.id);
+
+        let perms = resolve_permissions(&store, &USER, 1, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(perms.has(Permission::ViewAuditLog));
+        assert!(!perms.has(Permission::RunTasks));
+
+        store
+            .create_template_role_perm(TemplateRolePerm {
+                id: 0,
+                project_id: 1,
+                template_id: 7,
+                role_id: deployer.id,
+                role_slug: "auditor".to_string(),
+            })
+            .await
+            .unwrap();
+        let run = |template_id| {
+            require_permission(&store, &USER, 1, template_id, Some(Permission::RunTasks))
+        };
+        assert!(run(Some(7)).await.is_ok());
+        let err = run(Some(8)).await.unwrap_err();
+        assert!(matches!(err, Error::Forbidden(ref m) if m == "Missing permission: run_tasks"));
+    }
+
+    #[tokio::test]
+    async fn test_api_token_restrictions() {
+        let store = store_with_role("owner").await;
+        let token = |scopes, project_ids, template_ids| Caller {
+            token: Some(Arc::new(APIToken {
+                scopes,
+                project_ids,
+                template_ids,
+                ..Default::default()
+            })),
+            ..USER
+        };
+        let store = &store;
+        let check = |caller: Caller, template_id, required| async move {
+            check_permission(store, &caller, 1, template_id, required)
+                .await
+                .unwrap()
+        };
+
+        let read_only = token(vec![TokenScope::ReadOnly], vec![], vec![]);
+        assert_eq!(check(read_only.clone(), None, None).await, Ok(()));
+        assert_eq!(
+            check(read_only, None, Some(Permission::RunTasks)).await,
+            Err(AccessDenied::TokenScope(Permission::RunTasks))
+        );
+
+        let other_project = token(vec![], vec![2], vec![]);
+        assert_eq!(
+            check(other_project, None, None).await,
+            Err(AccessDenied::TokenRestricted)
+        );
+
+        let runner = token(vec![TokenScope::RunTasks], vec![1], vec![7]);
+        assert_eq!(
+            check(runner.clone(), Some(7), Some(Permission::RunTasks)).await,
+            Ok(())
+        );
+        assert_eq!(
+            check(runner, Some(8), Some(Permission::RunTasks)).await,
+            Err(AccessDenied::TokenRestricted)
+        );
+    }
+}
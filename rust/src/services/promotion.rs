@@ -0,0 +1,412 @@
+//! Build → Deploy Promotion — продвижение версий сборок в deploy-шаблоны
+//!
+//! Успешная задача build-шаблона получает версию:
+//! 1. `Task::version`, если она задана при запуске;
+//! 2. иначе первая группа регулярного выражения `task_params.version_pattern`
+//!    шаблона, применённого к выводу задачи (побеждает последнее совпадение);
+//! 3. иначе номер задачи сборки.
+//!
+//! Затем для каждого deploy-шаблона проекта с `autorun = true`, у которого
+//! `build_template_id` указывает на шаблон сборки, ставится в очередь задача
+//! с `build_task_id` и `version` сборки.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use regex::Regex;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::template::TemplateType;
+use crate::models::{Task, TaskOutput, Template};
+use crate::services::drift::is_drift_check;
+use crate::services::task_execution;
+use crate::services::task_logger::TaskStatus;
+
+/// Ключ `task_params` шаблона сборки с регулярным выражением версии
+pub const VERSION_PATTERN_PARAM: &str = "version_pattern";
+
+/// Успешная сборка, доступная для развёртывания
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BuildVersion {
+    pub build_task_id: i32,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<chrono::DateTime<Utc>>,
+}
+
+/// Извлекает версию из вывода задачи по регулярному выражению
+///
+/// Используется первая группа захвата (или всё совпадение, если групп нет).
+pub fn version_from_output(pattern: &str, output: &[TaskOutput]) -> Result<Option<String>> {
+    let re = Regex::new(pattern)
+        .map_err(|e| Error::Validation(format!("Invalid version pattern: {e}")))?;
+    Ok(output
+        .iter()
+        .filter_map(|line| {
+            re.captures(&line.output).map(|caps| {
+                caps.get(1)
+                    .or_else(|| caps.get(0))
+                    .map(|m| m.as_str().trim().to_string())
+                    .unwrap_or_default()
+            })
+        })
+        .rfind(|v| !v.is_empty()))
+}
+
+/// Определяет версию завершённой задачи сборки
+pub async fn resolve_build_version(
+    store: &(dyn Store + Send + Sync),
+    template: &Template,
+    task: &Task,
+) -> Result<String> {
+    if let Some(version) = task.version.as_deref().filter(|v| !v.is_empty()) {
+        return Ok(version.to_string());
+    }
+
+    let pattern = template
+        .task_params
+        .as_ref()
+        .and_then(|p| p.get(VERSION_PATTERN_PARAM))
+        .and_then(|v| v.as_str())
+        .filter(|p| !p.is_empty());
+    if let Some(pattern) = pattern {
+        let output = store.get_task_outputs(task.id).await?;
+        if let Some(version) = version_from_output(pattern, &output)? {
+            return Ok(version);
+        }
+        warn!(
+            "Build task {}: version pattern did not match output, using task number",
+            task.id
+        );
+    }
+
+    Ok(task.id.to_string())
+}
+
+/// Создаёт задачу deploy-шаблона для указанной сборки
+pub fn build_deploy_task(deploy: &Template, build: &Task, version: &str) -> Task {
+    Task {
+        user_id: build.user_id,
+        message: Some(format!("Deploy of build #{} ({version})", build.id)),
+        commit_hash: build.commit_hash.clone(),
+        commit_message: build.commit_message.clone(),
+        build_task_id: Some(build.id),
+        version: Some(version.to_string()),
+        ..Task::new_waiting(deploy.id, deploy.project_id)
+    }
+}
+
+/// Deploy-шаблоны проекта, автоматически запускаемые после сборки `build_template_id`
+pub async fn autorun_deploy_templates(
+    store: &(dyn Store + Send + Sync),
+    project_id: i32,
+    build_template_id: i32,
+) -> Result<Vec<Template>> {
+    Ok(store
+        .get_templates(project_id)
+        .await?
+        .into_iter()
+        .filter(|t| {
+            t.r#type == TemplateType::Deploy
+                && t.autorun
+                && t.build_template_id == Some(build_template_id)
+        })
+        .collect())
+}
+
+/// Продвигает завершённую задачу сборки
+///
+/// Ничего не делает, если задача не успешна, является проверкой дрейфа или
+/// шаблон не является build-шаблоном. Возвращает поставленные в очередь
+/// deploy-задачи.
+pub async fn promote_build(store: Arc<dyn Store + Send + Sync>, task: &Task) -> Result<Vec<Task>> {
+    let mut build = store.get_task(task.project_id, task.id).await?;
+    if build.status != TaskStatus::Success || is_drift_check(&build) {
+        return Ok(Vec::new());
+    }
+    let template = store
+        .get_template(build.project_id, build.template_id)
+        .await?;
+    if template.r#type != TemplateType::Build {
+        return Ok(Vec::new());
+    }
+
+    let version = resolve_build_version(store.as_ref(), &template, &build).await?;
+    if build.version.as_deref() != Some(version.as_str()) {
+        build.version = Some(version.clone());
+        store.update_task(build.clone()).await?;
+    }
+
+    let mut queued = Vec::new();
+    for deploy in autorun_deploy_templates(store.as_ref(), build.project_id, template.id).await? {
+        let created = store
+            .create_task(build_deploy_task(&deploy, &build, &version))
+            .await?;
+        info!(
+            "Build task {}: version {version} promoted to deploy template {} (task {})",
+            build.id, deploy.id, created.id
+        );
+        queued.push(created);
+    }
+    if !queued.is_empty() {
+        task_execution::wake_dispatcher();
+    }
+    Ok(queued)
+}
+
+/// Успешные сборки build-шаблона deploy-шаблона, от новых к старым
+pub async fn list_build_versions(
+    store: &(dyn Store + Send + Sync),
+    deploy: &Template,
+) -> Result<Vec<BuildVersion>> {
+    let Some(build_template_id) = deploy.build_template_id else {
+        return Err(Error::Validation(format!(
+            "Template {} has no build template",
+            deploy.id
+        )));
+    };
+
+    let mut builds: Vec<Task> = store
+        .get_tasks(deploy.project_id, Some(build_template_id))
+        .await?
+        .into_iter()
+        .map(|t| t.task)
+        .filter(|t| t.status == TaskStatus::Success)
+        .collect();
+    builds.sort_by_key(|t| std::cmp::Reverse(t.id));
+
+    Ok(builds
+        .into_iter()
+        .map(|t| BuildVersion {
+            build_task_id: t.id,
+            version: t.version.unwrap_or_else(|| t.id.to_string()),
+            commit_hash: t.commit_hash,
+            end: t.end,
+        })
+        .collect())
+}
+
+/// Ставит в очередь развёртывание указанной сборки deploy-шаблоном
+pub async fn deploy_build(
+    store: Arc<dyn Store + Send + Sync>,
+    deploy: &Template,
+    build_task_id: i32,
+    user_id: Option<i32>,
+) -> Result<Task> {
+    if deploy.r#type != TemplateType::Deploy {
+        return Err(Error::Validation(format!(
+            "Template {} is not a deploy template",
+            deploy.id
+        )));
+    }
+    let build = store.get_task(deploy.project_id, build_task_id).await?;
+    if Some(build.template_id) != deploy.build_template_id {
+        return Err(Error::Validation(format!(
+            "Task {build_task_id} is not a build of template {}",
+            deploy.id
+        )));
+    }
+    if build.status != TaskStatus::Success {
+        return Err(Error::Validation(format!(
+            "Build task {build_task_id} did not succeed"
+        )));
+    }
+
+    let version = build
+        .version
+        .clone()
+        .unwrap_or_else(|| build.id.to_string());
+    let mut task = build_deploy_task(deploy, &build, &version);
+    task.user_id = user_id;
+    let created = store.create_task(task).await?;
+    task_execution::wake_dispatcher();
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{TaskManager, TemplateManager};
+    use serde_json::json;
+
+    fn output(line: &str) -> TaskOutput {
+        TaskOutput {
+            id: 0,
+            task_id: 1,
+            project_id: 1,
+            time: Utc::now(),
+            output: line.to_string(),
+            stage_id: None,
+        }
+    }
+
+    fn template(id: i32, r#type: TemplateType) -> Template {
+        Template {
+            id,
+            project_id: 1,
+            r#type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn version_from_output_takes_last_match() {
+        let lines = [
+            output("building..."),
+            output("VERSION=1.0.0"),
+            output("VERSION=1.0.1"),
+        ];
+        assert_eq!(
+            version_from_output(r"VERSION=(\S+)", &lines).unwrap(),
+            Some("1.0.1".to_string())
+        );
+        assert_eq!(version_from_output(r"nope=(\S+)", &lines).unwrap(), None);
+        assert!(version_from_output(r"(", &lines).is_err());
+    }
+
+    #[tokio::test]
+    async fn promote_build_enqueues_autorun_deploys() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                task_params: Some(json!({"version_pattern": r"artifact v(\S+)"})),
+                ..template(1, TemplateType::Build)
+            })
+            .await
+            .unwrap();
+        store
+            .create_template(Template {
+                build_template_id: Some(1),
+                autorun: true,
+                ..template(2, TemplateType::Deploy)
+            })
+            .await
+            .unwrap();
+        store
+            .create_template(Template {
+                build_template_id: Some(1),
+                ..template(3, TemplateType::Deploy)
+            })
+            .await
+            .unwrap();
+
+        let build = store
+            .create_task(Task {
+                template_id: 1,
+                project_id: 1,
+                status: TaskStatus::Success,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store
+            .create_task_output(TaskOutput {
+                task_id: build.id,
+                ..output("artifact v2.3.4 uploaded")
+            })
+            .await
+            .unwrap();
+
+        let queued = promote_build(store.clone(), &build).await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].template_id, 2);
+        assert_eq!(queued[0].build_task_id, Some(build.id));
+        assert_eq!(queued[0].version.as_deref(), Some("2.3.4"));
+
+        let saved = store.get_task(1, build.id).await.unwrap();
+        assert_eq!(saved.version.as_deref(), Some("2.3.4"));
+    }
+
+    #[tokio::test]
+    async fn promote_build_ignores_failed_builds() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_template(template(1, TemplateType::Build))
+            .await
+            .unwrap();
+        let build = store
+            .create_task(Task {
+                template_id: 1,
+                project_id: 1,
+                status: TaskStatus::Error,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(promote_build(store, &build).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn promote_build_skips_drift_checks() {
+        let store = Arc::new(MockStore::new());
+        store
+            .create_template(template(1, TemplateType::Build))
+            .await
+            .unwrap();
+        store
+            .create_template(Template {
+                build_template_id: Some(1),
+                autorun: true,
+                ..template(2, TemplateType::Deploy)
+            })
+            .await
+            .unwrap();
+        let check = store
+            .create_task(Task {
+                template_id: 1,
+                project_id: 1,
+                status: TaskStatus::Success,
+                params: Some(json!({"dry_run": true, "drift_check": true})),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(
+            promote_build(store.clone(), &check)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let saved = store.get_task(1, check.id).await.unwrap();
+        assert_eq!(saved.version, None);
+    }
+
+    #[tokio::test]
+    async fn list_build_versions_returns_successful_builds() {
+        let store = MockStore::new();
+        for (status, version) in [
+            (TaskStatus::Success, Some("1.0")),
+            (TaskStatus::Error, None),
+            (TaskStatus::Success, None),
+        ] {
+            store
+                .create_task(Task {
+                    template_id: 1,
+                    project_id: 1,
+                    status,
+                    version: version.map(str::to_string),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        let deploy = Template {
+            build_template_id: Some(1),
+            ..template(2, TemplateType::Deploy)
+        };
+
+        let versions = list_build_versions(&store, &deploy).await.unwrap();
+        let pairs: Vec<(i32, &str)> = versions
+            .iter()
+            .map(|v| (v.build_task_id, v.version.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![(3, "3"), (1, "1.0")]);
+    }
+}
@@ -14,7 +14,13 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
-use crate::models::Runner;
+use crate::db::store::{InventoryManager, RunnerManager, Store, TaskManager, TemplateManager};
+pub use crate::models::RunnerCapabilities;
+use crate::models::{Runner, Task, TaskClaimer, TaskOutput, TemplateApp};
+use crate::services::concurrency::{self, Claim};
+use crate::services::leader_election::Leadership;
+use crate::services::task_logger::TaskStatus;
+use crate::services::task_pool::{QueuePriorityPolicy, load_global_queue};
 
 /// Конфигурация Remote Runners
 #[derive(Debug, Clone)]
@@ -49,18 +55,6 @@ pub struct RegisteredRunner {
     pub capabilities: RunnerCapabilities,
 }
 
-/// Возможности раннера
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct RunnerCapabilities {
-    pub ansible: bool,
-    pub terraform: bool,
-    pub bash: bool,
-    pub powershell: bool,
-    pub kubernetes: bool,
-    pub max_parallel_tasks: i32,
-    pub tags: Vec<String>,
-}
-
 /// Менеджер Remote Runners
 pub struct RemoteRunnersManager {
     config: RemoteRunnersConfig,
@@ -249,6 +243,165 @@ pub struct RemoteRunnersStats {
     pub tasks_queued: usize,
 }
 
+/// Максимум задач очереди, просматриваемых за один опрос раннера
+const CLAIM_SCAN_LIMIT: usize = 50;
+
+/// Требования задачи к раннеру
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskRequirements {
+    pub project_id: i32,
+    /// Тег раннера из инвентаря задачи (или шаблона)
+    pub tag: Option<String>,
+    /// Приложение шаблона
+    pub app: TemplateApp,
+    /// Задача выполняется только сервером: подтверждение плана и хуки шаблона
+    pub server_only: bool,
+}
+
+impl TaskRequirements {
+    /// Определяет требования по шаблону и инвентарю задачи
+    pub async fn resolve(store: &dyn Store, task: &Task) -> crate::error::Result<Self> {
+        let template = store
+            .get_template(task.project_id, task.template_id)
+            .await?;
+        let tag = match task.inventory_id.or(template.inventory_id) {
+            Some(inventory_id) => store
+                .get_inventory(task.project_id, inventory_id)
+                .await
+                .ok()
+                .and_then(|inv| inv.runner_tag)
+                .filter(|tag| !tag.is_empty()),
+            None => None,
+        };
+        Ok(Self {
+            project_id: task.project_id,
+            tag,
+            server_only: template.require_approval
+                || crate::services::task_execution::has_hooks(&template),
+            app: template.app,
+        })
+    }
+
+    /// Раннер подходит задаче по проекту, тегу и приложению
+    pub fn accepts(&self, runner: &Runner) -> bool {
+        !self.server_only
+            && runner.active
+            && runner.project_id.is_none_or(|id| id == self.project_id)
+            && self.tag.as_deref().is_none_or(|tag| runner.has_tag(tag))
+            && runner.supports_app(&self.app)
+    }
+}
+
+/// Тег раннера, без которого задачу нельзя запустить (`None` — подойдёт и сервер)
+pub async fn required_runner_tag(store: &dyn Store, task: &Task) -> Option<String> {
+    let requirements = TaskRequirements::resolve(store, task).await.ok()?;
+    match requirements.server_only {
+        true => None,
+        false => requirements.tag,
+    }
+}
+
+/// Подбирает следующую подходящую раннеру задачу и атомарно закрепляет её.
+///
+/// Задачи просматриваются в порядке общей очереди; задача забирается вместе со
+/// слотами своих групп параллелизма (как и на узлах сервера). Если задачу
+/// одновременно забрал другой раннер или её группа заполнилась, берётся следующая.
+pub async fn claim_next_task(
+    store: &dyn Store,
+    runner: &Runner,
+) -> crate::error::Result<Option<Task>> {
+    if let Some(limit) = runner.max_parallel_tasks.filter(|limit| *limit > 0) {
+        if store.get_runner_task_ids(runner.id).await?.len() >= limit as usize {
+            return Ok(None);
+        }
+    }
+
+    let queue = load_global_queue(store, &QueuePriorityPolicy::default()).await?;
+    for entry in queue
+        .into_iter()
+        .filter(|e| e.blocked_by.is_none())
+        .take(CLAIM_SCAN_LIMIT)
+    {
+        let requirements = match TaskRequirements::resolve(store, &entry.task).await {
+            Ok(r) => r,
+            Err(e) => {
+                debug!("Task {} skipped for runners: {}", entry.task.id, e);
+                continue;
+            }
+        };
+        if !requirements.accepts(runner) {
+            continue;
+        }
+        let claim =
+            concurrency::claim_with_slots(store, &entry.task, TaskClaimer::Runner(runner.id))
+                .await?;
+        if claim == Claim::Claimed {
+            let mut task = entry.task;
+            task.status = TaskStatus::Running;
+            task.start = Some(Utc::now());
+            info!("Task {} claimed by runner {}", task.id, runner.id);
+            return Ok(Some(task));
+        }
+    }
+    Ok(None)
+}
+
+/// Возвращает в очередь задачи раннеров, переставших слать heartbeat
+///
+/// Останавливаемые задачи таких раннеров завершаются со статусом `stopped`.
+/// Возвращает ID затронутых задач.
+pub async fn requeue_orphaned_tasks(store: &dyn Store, config: &RemoteRunnersConfig) -> Vec<i32> {
+    let tasks = match store
+        .requeue_stale_runner_tasks(config.runner_timeout_secs as i64)
+        .await
+    {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            warn!("Failed to requeue tasks of lost runners: {}", e);
+            return Vec::new();
+        }
+    };
+    for task in &tasks {
+        let output = if task.status == TaskStatus::Stopped {
+            warn!("Task {} stopped: runner stopped heartbeating", task.id);
+            "Runner stopped heartbeating, task stopped"
+        } else {
+            warn!("Task {} requeued: runner stopped heartbeating", task.id);
+            "Runner stopped heartbeating, task returned to the queue"
+        };
+        let _ = store
+            .create_task_output(TaskOutput {
+                id: 0,
+                task_id: task.id,
+                project_id: task.project_id,
+                time: Utc::now(),
+                output: output.to_string(),
+                stage_id: None,
+            })
+            .await;
+    }
+    tasks.iter().map(|t| t.id).collect()
+}
+
+/// Запускает фоновую проверку задач потерянных раннеров (в кластере — только на узле-лидере)
+pub fn spawn_orphan_reaper(
+    store: Arc<dyn Store + Send + Sync>,
+    config: RemoteRunnersConfig,
+    leadership: Leadership,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            config.heartbeat_interval_secs,
+        ));
+        loop {
+            interval.tick().await;
+            if leadership.is_leader() {
+                requeue_orphaned_tasks(store.as_ref(), &config).await;
+            }
+        }
+    })
+}
+
 /// Request для регистрации раннера
 #[derive(Debug, Deserialize)]
 pub struct RunnerRegisterRequest {
@@ -323,6 +476,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         let capabilities = RunnerCapabilities::default();
@@ -349,6 +503,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         let capabilities = RunnerCapabilities::default();
@@ -408,6 +563,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         let result = manager
@@ -445,6 +601,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         manager
@@ -487,6 +644,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         manager
@@ -546,6 +704,7 @@ mod tests {
                 cleaning_requested: None,
                 touched: None,
                 created: None,
+                capabilities: None,
             };
             manager
                 .register_runner(runner, RunnerCapabilities::default())
@@ -676,6 +835,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         let result = manager
@@ -722,6 +882,7 @@ mod tests {
                 cleaning_requested: None,
                 touched: None,
                 created: None,
+                capabilities: None,
             };
             let result = manager
                 .register_runner(runner, RunnerCapabilities::default())
@@ -751,6 +912,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         manager
             .register_runner(runner1, RunnerCapabilities::default())
@@ -770,6 +932,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         manager
             .register_runner(runner2, RunnerCapabilities::default())
@@ -799,6 +962,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         manager
             .register_runner(runner, RunnerCapabilities::default())
@@ -831,6 +995,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         manager
             .register_runner(runner, RunnerCapabilities::default())
@@ -958,6 +1123,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         manager
             .register_runner(runner, RunnerCapabilities::default())
@@ -987,6 +1153,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         manager
             .register_runner(runner, RunnerCapabilities::default())
@@ -1040,6 +1207,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
         manager
             .register_runner(runner, RunnerCapabilities::default())
@@ -1075,6 +1243,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         let registered = RegisteredRunner {
@@ -1141,6 +1310,7 @@ mod tests {
             cleaning_requested: None,
             touched: None,
             created: None,
+            capabilities: None,
         };
 
         Arc::clone(&manager)
@@ -1165,4 +1335,182 @@ mod tests {
         let stats = manager.get_stats().await;
         assert_eq!(stats.total_tasks_running, 20);
     }
+
+    fn store_runner(
+        id: i32,
+        tag: Option<&str>,
+        capabilities: Option<RunnerCapabilities>,
+    ) -> Runner {
+        Runner {
+            id,
+            project_id: None,
+            token: format!("token-{id}"),
+            name: format!("runner-{id}"),
+            active: true,
+            last_active: Some(Utc::now()),
+            webhook: None,
+            max_parallel_tasks: None,
+            tag: tag.map(str::to_string),
+            cleaning_requested: None,
+            touched: None,
+            created: None,
+            capabilities,
+        }
+    }
+
+    async fn seed_queue() -> crate::db::mock::MockStore {
+        use crate::models::{Inventory, Template};
+
+        let store = crate::db::mock::MockStore::new();
+        store.seed_template(Template {
+            id: 1,
+            project_id: 1,
+            app: TemplateApp::Ansible,
+            ..Default::default()
+        });
+        store.seed_template(Template {
+            id: 2,
+            project_id: 1,
+            app: TemplateApp::Terraform,
+            inventory_id: Some(5),
+            ..Default::default()
+        });
+        store.seed_inventory(Inventory {
+            id: 5,
+            project_id: 1,
+            runner_tag: Some("gpu".to_string()),
+            ..Default::default()
+        });
+        for (id, template_id) in [(1, 2), (2, 1)] {
+            store
+                .create_task(Task {
+                    id,
+                    project_id: 1,
+                    template_id,
+                    created: Utc::now() - chrono::Duration::seconds(10 - id as i64),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn test_claim_next_task_routes_by_tag_and_app() {
+        let store = seed_queue().await;
+        let ansible_only = store_runner(
+            1,
+            None,
+            Some(RunnerCapabilities {
+                ansible: true,
+                ..Default::default()
+            }),
+        );
+        // Задача 1 требует тег gpu — раннер без тега пропускает её
+        let task = claim_next_task(&store, &ansible_only)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.id, 2);
+        assert!(
+            claim_next_task(&store, &ansible_only)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let gpu = store_runner(2, Some("gpu"), None);
+        let task = claim_next_task(&store, &gpu).await.unwrap().unwrap();
+        assert_eq!(task.id, 1);
+        assert_eq!(store.get_runner_task_ids(2).await.unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_claim_task_is_compare_and_set() {
+        let store = seed_queue().await;
+        assert!(store.claim_task(2, 1).await.unwrap());
+        assert!(!store.claim_task(2, 2).await.unwrap());
+        assert_eq!(store.get_runner_task_ids(1).await.unwrap(), vec![2]);
+        assert!(store.get_runner_task_ids(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_claim_respects_runner_project_and_limit() {
+        let store = seed_queue().await;
+        let mut runner = store_runner(1, Some("gpu"), None);
+        runner.project_id = Some(2);
+        assert!(claim_next_task(&store, &runner).await.unwrap().is_none());
+
+        runner.project_id = Some(1);
+        runner.max_parallel_tasks = Some(1);
+        store.create_runner(runner.clone()).await.unwrap();
+        assert!(claim_next_task(&store, &runner).await.unwrap().is_some());
+        assert!(claim_next_task(&store, &runner).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_requeue_orphaned_tasks() {
+        let store = seed_queue().await;
+        let mut lost = store_runner(1, None, None);
+        lost.last_active = Some(Utc::now() - chrono::Duration::seconds(600));
+        store.create_runner(lost).await.unwrap();
+        store
+            .create_runner(store_runner(2, None, None))
+            .await
+            .unwrap();
+        assert!(store.claim_task(1, 1).await.unwrap());
+        assert!(store.claim_task(2, 2).await.unwrap());
+
+        let requeued = requeue_orphaned_tasks(&store, &RemoteRunnersConfig::default()).await;
+        assert_eq!(requeued, vec![1]);
+        let task = store.get_task(1, 1).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Waiting);
+        assert!(task.start.is_none());
+        assert_eq!(store.get_runner_task_ids(2).await.unwrap(), vec![2]);
+        let output = store.get_task_outputs(1).await.unwrap();
+        assert_eq!(output[0].project_id, task.project_id);
+    }
+
+    #[tokio::test]
+    async fn test_stopping_task_of_lost_runner_is_stopped() {
+        let store = seed_queue().await;
+        let mut lost = store_runner(1, None, None);
+        lost.last_active = Some(Utc::now() - chrono::Duration::seconds(600));
+        store.create_runner(lost).await.unwrap();
+        assert!(store.claim_task(1, 1).await.unwrap());
+        store
+            .update_task_status(1, 1, TaskStatus::Stopping)
+            .await
+            .unwrap();
+
+        let affected = requeue_orphaned_tasks(&store, &RemoteRunnersConfig::default()).await;
+        assert_eq!(affected, vec![1]);
+        let task = store.get_task(1, 1).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Stopped);
+        assert!(task.end.is_some());
+        assert!(store.get_runner_task_ids(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_approval_templates_stay_on_server() {
+        use crate::models::Template;
+
+        let store = seed_queue().await;
+        store.seed_template(Template {
+            id: 2,
+            project_id: 1,
+            app: TemplateApp::Terraform,
+            inventory_id: Some(5),
+            require_approval: true,
+            ..Default::default()
+        });
+        let task = store.get_task(1, 1).await.unwrap();
+        assert_eq!(required_runner_tag(&store, &task).await, None);
+
+        let gpu = store_runner(2, Some("gpu"), None);
+        let claimed = claim_next_task(&store, &gpu).await.unwrap().unwrap();
+        assert_eq!(claimed.id, 2);
+        assert!(claim_next_task(&store, &gpu).await.unwrap().is_none());
+    }
 }
@@ -6,6 +6,7 @@ use rand::RngCore;
 use tracing::{error, info, warn};
 
 use crate::error::{Error, Result};
+use crate::models::schedule::{MISFIRE_SKIP, SCHEDULE_TARGET_TEMPLATE};
 use crate::models::*;
 use crate::services::backup::*;
 
@@ -225,6 +226,15 @@ impl RestoreEntryAsync for BackupSchedule {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: SCHEDULE_TARGET_TEMPLATE.to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: MISFIRE_SKIP.to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         let new_schedule = store.create_schedule(schedule).await?;
@@ -439,6 +449,9 @@ impl RestoreEntryAsync for BackupTemplate {
             post_template_id: None,
             fail_template_id: None,
             deploy_environment_id: None,
+            priority: 0,
+            concurrency_group: None,
+            timeout_secs: None,
         };
 
         let new_tpl = store.create_template(tpl).await?;
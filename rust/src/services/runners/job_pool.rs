@@ -1,19 +1,20 @@
 //! Job Pool
 //!
-//! Пул задач для раннеров
+//! Локальный исполнитель очереди задач: забирает ожидающие задачи из БД
+//! вместе со слотами групп параллелизма и выполняет их на этом узле
 
 use std::collections::HashSet;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use tokio::time::{Duration, interval, sleep, timeout};
 
 use crate::db::store::Store;
 use crate::error::Result;
-use crate::models::Task;
-use crate::services::runners::task_queue::TaskQueue;
-use crate::services::task_execution;
-use crate::services::task_logger::TaskStatus;
+use crate::services::remote_runners;
+use crate::services::task_execution::{self, Dispatch};
+use crate::services::task_pool::{QueuePriorityPolicy, load_global_queue};
 
 /// Логгер задач
 pub struct JobLogger {
@@ -46,16 +47,12 @@ impl JobLogger {
     }
 }
 
-/// Пул задач
+/// Пул задач: забирает ожидающие задачи из БД и выполняет их на этом узле
 pub struct JobPool {
-    /// Очередь задач, ожидающих запуска
-    queue: Arc<Mutex<Vec<QueuedTask>>>,
     /// ID задач, которые сейчас выполняются (трекер параллелизма)
     running_ids: Arc<Mutex<HashSet<i32>>>,
     /// Хранилище данных
     store: Arc<dyn Store + Send + Sync>,
-    /// Персистентная очередь задач (Redis или in-memory)
-    task_queue: Option<Arc<dyn TaskQueue>>,
     /// Максимальное число параллельных задач
     max_parallel: usize,
     /// Флаг завершения — при true новые задачи не берутся
@@ -65,34 +62,19 @@ pub struct JobPool {
 impl JobPool {
     /// Создаёт новый пул задач
     pub fn new(store: Arc<dyn Store + Send + Sync>) -> Self {
-        Self::with_task_queue(store, None)
-    }
-
-    /// Создаёт пул задач с ограничением параллелизма
-    pub fn with_max_parallel(store: Arc<dyn Store + Send + Sync>, max_parallel: usize) -> Self {
-        Self::with_task_queue(store, None)
-    }
-
-    /// Создаёт пул задач с персистентной очередью (Redis)
-    pub fn with_task_queue(
-        store: Arc<dyn Store + Send + Sync>,
-        task_queue: Option<Arc<dyn TaskQueue>>,
-    ) -> Self {
-        let backend = task_queue
-            .as_ref()
-            .map(|q| q.backend_name())
-            .unwrap_or("none");
-        tracing::info!("[job_pool] Task queue backend: {}", backend);
         Self {
-            queue: Arc::new(Mutex::new(Vec::new())),
             running_ids: Arc::new(Mutex::new(HashSet::new())),
             store,
-            task_queue,
             max_parallel: 10,
             shutting_down: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Создаёт пул задач с ограничением параллелизма
+    pub fn with_max_parallel(store: Arc<dyn Store + Send + Sync>, max_parallel: usize) -> Self {
+        Self::new(store)
+    }
+
     /// Graceful shutdown: прекращает брать новые задачи и ждёт завершения текущих (макс. 30 сек)
     pub async fn shutdown(&self) {
         self.shutting_down.store(true, Ordering::SeqCst);
@@ -117,37 +99,17 @@ impl JobPool {
         }
     }
 
-    /// Проверяет, есть ли задача в очереди
-    pub async fn exists_in_queue(&self, task_id: i32) -> bool {
-        // Проверяем персистентную очередь если доступна
-        if let Some(ref tq) = self.task_queue {
-            if let Ok(found) = tq.contains(task_id).await {
-                return found;
-            }
-        }
-        // Fallback на in-memory очередь
-        let queue = self.queue.lock().await;
-        queue.iter().any(|j| j.task.id == task_id)
-    }
-
-    /// Возвращает длину очереди задач
-    pub async fn queue_len(&self) -> usize {
-        if let Some(ref tq) = self.task_queue {
-            tq.len().await.unwrap_or(0)
-        } else {
-            self.queue.lock().await.len()
-        }
-    }
-
     /// Проверяет, есть ли запущенные задачи
     pub async fn has_running_jobs(&self) -> bool {
         !self.running_ids.lock().await.is_empty()
     }
 
     /// Запускает пул задач (бесконечный цикл, завершается при shutdown)
+    ///
+    /// Очередь перепроверяется раз в секунду и сразу, когда появляется новая
+    /// задача или освобождается слот (`task_execution::wake_dispatcher`).
     pub async fn run(&self) -> Result<()> {
         let logger = JobLogger::new("running");
-        let mut queue_interval = interval(Duration::from_secs(5));
         let mut request_interval = interval(Duration::from_secs(1));
 
         loop {
@@ -156,178 +118,76 @@ impl JobPool {
                 return Ok(());
             }
             tokio::select! {
-                _ = queue_interval.tick() => {
-                    self.check_queue(&logger).await;
-                }
-                _ = request_interval.tick() => {
-                    self.check_new_jobs(&logger).await;
-                }
+                _ = request_interval.tick() => {}
+                _ = task_execution::dispatcher_woken() => {}
             }
+            self.check_new_jobs(&logger).await;
         }
     }
 
-    /// Запускает ожидающие задачи из очереди
-    async fn check_queue(&self, logger: &JobLogger) {
+    /// Забирает ожидающие задачи из очереди в БД и запускает их
+    ///
+    /// Очередь просматривается в порядке приоритета (`task_pool::load_global_queue`),
+    /// поэтому освободившийся слот группы достаётся самой приоритетной задаче.
+    /// Задачи с занятой группой параллелизма остаются в очереди.
+    async fn check_new_jobs(&self, logger: &JobLogger) {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return;
+        }
         let running_count = self.running_ids.lock().await.len();
-        if running_count >= self.max_parallel {
+        let mut available_slots = self.max_parallel.saturating_sub(running_count);
+        if available_slots == 0 {
             return;
         }
 
-        // Пробуем взять задачу из персистентной очереди (Redis)
-        if let Some(ref tq) = self.task_queue {
-            match tq.pop().await {
-                Ok(Some(task_id)) => {
-                    tracing::info!(
-                        "[job_pool] Launching task from persistent queue: {}",
-                        task_id
-                    );
-                    self.launch_task_by_id(task_id).await;
-                    return;
-                }
-                Ok(None) => {} // Очередь пуста
+        let queue =
+            match load_global_queue(self.store.as_ref(), &QueuePriorityPolicy::default()).await {
+                Ok(queue) => queue,
                 Err(e) => {
-                    tracing::warn!("[job_pool] Task queue error: {}", e);
+                    tracing::warn!("[job_pool] Failed to load task queue: {e}");
+                    return;
                 }
-            }
-        }
-
-        // Fallback на in-memory очередь
-        let mut queue = self.queue.lock().await;
-        if queue.is_empty() {
-            return;
-        }
-
-        let queued = queue.remove(0);
-        if queued.status == TaskStatus::Error {
-            logger.task_info("Task dequeued (error)", queued.task.id, "failed");
-            return;
-        }
-
-        self.launch_queued_task(queued).await;
-    }
-
-    /// Запускает задачу по ID (извлекает из БД)
-    async fn launch_task_by_id(&self, task_id: i32) {
-        self.running_ids.lock().await.insert(task_id);
+            };
 
-        let store = self.store.clone();
-        let running_ids = self.running_ids.clone();
+        for entry in queue.into_iter().filter(|e| e.blocked_by.is_none()) {
+            if available_slots == 0 {
+                break;
+            }
+            let task = entry.task;
+            // Задачи, требующие тег раннера, выполняют только удалённые раннеры
+            if remote_runners::required_runner_tag(self.store.as_ref(), &task)
+                .await
+                .is_some()
+            {
+                continue;
+            }
 
-        tokio::spawn(async move {
-            // Получаем задачу из БД
-            match store.get_tasks(0, None).await {
-                Ok(tasks) => {
-                    if let Some(task_with_tpl) = tasks.into_iter().find(|t| t.task.id == task_id) {
-                        task_execution::execute_task(store, task_with_tpl.task).await;
-                    }
+            let task_id = task.id;
+            match task_execution::dispatch_task(self.store.clone(), task).await {
+                Dispatch::Started(handle) => {
+                    logger.task_info("Task dispatched", task_id, "running");
+                    available_slots -= 1;
+                    self.track(task_id, handle).await;
+                }
+                Dispatch::Blocked(blocked) => {
+                    logger.debug(&format!("Task {task_id} is waiting: {blocked}"));
                 }
-                Err(e) => tracing::warn!("[job_pool] Failed to load task {}: {}", task_id, e),
+                Dispatch::Taken => {}
             }
-            running_ids.lock().await.remove(&task_id);
-        });
+        }
     }
 
-    /// Запускает задачу из in-memory очереди
-    async fn launch_queued_task(&self, queued: QueuedTask) {
-        let task_id = queued.task.id;
-
+    /// Учитывает запущенную задачу до её завершения
+    async fn track(&self, task_id: i32, handle: JoinHandle<()>) {
         self.running_ids.lock().await.insert(task_id);
-
-        let store = self.store.clone();
-        let task = queued.task;
         let running_ids = self.running_ids.clone();
-
         tokio::spawn(async move {
-            task_execution::execute_task(store, task).await;
+            let _ = handle.await;
             running_ids.lock().await.remove(&task_id);
         });
     }
-
-    /// Ищет новые задачи в БД и добавляет их в очередь
-    async fn check_new_jobs(&self, logger: &JobLogger) {
-        if self.shutting_down.load(Ordering::SeqCst) {
-            return;
-        }
-        let running_count = self.running_ids.lock().await.len();
-        if running_count >= self.max_parallel {
-            return;
-        }
-
-        let queue_len = self.queue.lock().await.len();
-        let available_slots = (self.max_parallel - running_count).saturating_sub(queue_len);
-        if available_slots == 0 {
-            return;
-        }
-
-        let limit = available_slots.min(50) as i32;
-        let tasks = match self
-            .store
-            .get_global_tasks(Some(vec!["waiting".to_string()]), Some(limit))
-            .await
-        {
-            Ok(t) => t,
-            Err(e) => {
-                tracing::warn!("[job_pool] Failed to fetch waiting tasks: {e}");
-                return;
-            }
-        };
-
-        if tasks.is_empty() {
-            return;
-        }
-
-        // Если есть персистентная очередь — пушим туда
-        if let Some(ref tq) = self.task_queue {
-            for task_with_tpl in tasks {
-                let task_id = task_with_tpl.task.id;
-                // Проверяем что задача ещё не в очереди
-                if let Ok(exists) = tq.contains(task_id).await {
-                    if exists {
-                        continue;
-                    }
-                }
-                if let Err(e) = tq.push(task_id).await {
-                    logger.task_info(
-                        "Failed to push to task queue",
-                        task_id,
-                        &format!("error: {}", e),
-                    );
-                }
-            }
-            return;
-        }
-
-        // Fallback на in-memory очередь
-        let mut queue = self.queue.lock().await;
-        let running = self.running_ids.lock().await;
-
-        for task_with_tpl in tasks {
-            let task_id = task_with_tpl.task.id;
-            if queue.iter().any(|j| j.task.id == task_id) || running.contains(&task_id) {
-                continue;
-            }
-
-            logger.task_info("Queueing task", task_id, "waiting");
-            queue.push(QueuedTask {
-                task: task_with_tpl.task,
-                status: TaskStatus::Waiting,
-            });
-        }
-    }
 }
 
-/// Задача в очереди
-#[derive(Clone)]
-pub struct QueuedTask {
-    /// Задача для запуска
-    pub task: Task,
-    /// Статус в очереди
-    pub status: TaskStatus,
-}
-
-// Обратная совместимость
-pub type Job = QueuedTask;
-
 // ============================================================================
 // Tests
 // ============================================================================
@@ -353,12 +213,6 @@ mod tests {
         assert_eq!(logger.context, "test");
     }
 
-    #[tokio::test]
-    async fn test_exists_in_queue_empty() {
-        let pool = JobPool::new(make_store());
-        assert!(!pool.exists_in_queue(1).await);
-    }
-
     #[tokio::test]
     async fn test_has_running_jobs_empty() {
         let pool = JobPool::new(make_store());
@@ -383,30 +237,6 @@ mod tests {
         assert!(!pool.has_running_jobs().await);
     }
 
-    #[tokio::test]
-    async fn test_queue_len_nonempty() {
-        let pool = JobPool::new(make_store());
-        pool.queue.lock().await.push(QueuedTask {
-            task: crate::models::Task::default(),
-            status: TaskStatus::Waiting,
-        });
-        assert_eq!(pool.queue_len().await, 1);
-    }
-
-    #[tokio::test]
-    async fn test_exists_in_queue_positive() {
-        let pool = JobPool::new(make_store());
-        pool.queue.lock().await.push(QueuedTask {
-            task: crate::models::Task {
-                id: 99,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Waiting,
-        });
-        assert!(pool.exists_in_queue(99).await);
-        assert!(!pool.exists_in_queue(1).await);
-    }
-
     #[tokio::test]
     async fn test_has_running_jobs_positive() {
         let pool = JobPool::new(make_store());
@@ -423,12 +253,6 @@ mod tests {
         assert_eq!(pool.max_parallel, 10); // documents bug
     }
 
-    #[tokio::test]
-    async fn test_with_task_queue_none() {
-        let pool = JobPool::with_task_queue(make_store(), None);
-        assert_eq!(pool.queue_len().await, 0);
-    }
-
     #[tokio::test]
     async fn test_run_stops_on_shutdown() {
         let pool = JobPool::new(make_store());
@@ -437,16 +261,6 @@ mod tests {
         assert!(result.is_ok());
     }
 
-    #[test]
-    fn test_queued_task_default() {
-        let qt = QueuedTask {
-            task: crate::models::Task::default(),
-            status: TaskStatus::Waiting,
-        };
-        assert_eq!(qt.task.id, 0);
-        assert_eq!(qt.status, TaskStatus::Waiting);
-    }
-
     #[test]
     fn test_job_logger_info_method() {
         let logger = JobLogger::new("test_logger");
@@ -482,23 +296,6 @@ mod tests {
         assert_eq!(pool.max_parallel, 10);
     }
 
-    #[tokio::test]
-    async fn test_queue_len_multiple_tasks() {
-        let pool = JobPool::new(make_store());
-        let mut queue = pool.queue.lock().await;
-        for i in 1..=5 {
-            queue.push(QueuedTask {
-                task: crate::models::Task {
-                    id: i,
-                    ..crate::models::Task::default()
-                },
-                status: TaskStatus::Waiting,
-            });
-        }
-        drop(queue);
-        assert_eq!(pool.queue_len().await, 5);
-    }
-
     #[tokio::test]
     async fn test_running_ids_multiple_tasks() {
         let pool = JobPool::new(make_store());
@@ -518,32 +315,6 @@ mod tests {
         assert!(pool.shutting_down.load(Ordering::SeqCst));
     }
 
-    #[test]
-    fn test_queued_task_clone() {
-        let qt1 = QueuedTask {
-            task: crate::models::Task {
-                id: 1,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Running,
-        };
-        let qt2 = qt1.clone();
-        assert_eq!(qt2.task.id, qt1.task.id);
-        assert_eq!(qt2.status, qt1.status);
-    }
-
-    #[test]
-    fn test_queued_task_task_id_accessible() {
-        let qt = QueuedTask {
-            task: crate::models::Task {
-                id: 42,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Waiting,
-        };
-        assert_eq!(qt.task.id, 42);
-    }
-
     #[test]
     fn test_job_logger_context_stored() {
         let logger = JobLogger::new("my_context");
@@ -579,38 +350,6 @@ mod tests {
         logger.task_info("Stopped", 5, "stopped");
     }
 
-    #[tokio::test]
-    async fn test_queued_task_with_error_status() {
-        let qt = QueuedTask {
-            task: crate::models::Task {
-                id: 99,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Error,
-        };
-        assert_eq!(qt.status, TaskStatus::Error);
-        assert_eq!(qt.task.id, 99);
-    }
-
-    #[tokio::test]
-    async fn test_queued_task_with_running_status() {
-        let qt = QueuedTask {
-            task: crate::models::Task {
-                id: 10,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Running,
-        };
-        assert_eq!(qt.status, TaskStatus::Running);
-    }
-
-    #[tokio::test]
-    async fn test_job_pool_queue_initial_state() {
-        let pool = JobPool::new(make_store());
-        assert_eq!(pool.queue_len().await, 0);
-        assert!(!pool.has_running_jobs().await);
-    }
-
     #[tokio::test]
     async fn test_job_pool_multiple_running_ids() {
         let pool = JobPool::new(make_store());
@@ -622,29 +361,6 @@ mod tests {
         assert_eq!(pool.running_ids.lock().await.len(), 10);
     }
 
-    #[tokio::test]
-    async fn test_job_pool_queue_multiple_tasks() {
-        let pool = JobPool::new(make_store());
-        let mut queue = pool.queue.lock().await;
-        for i in 1..=3 {
-            queue.push(QueuedTask {
-                task: crate::models::Task {
-                    id: i,
-                    ..crate::models::Task::default()
-                },
-                status: TaskStatus::Waiting,
-            });
-        }
-        drop(queue);
-
-        // Verify FIFO order
-        let mut queue = pool.queue.lock().await;
-        let first = queue.remove(0);
-        assert_eq!(first.task.id, 1);
-        let second = queue.remove(0);
-        assert_eq!(second.task.id, 2);
-    }
-
     #[tokio::test]
     async fn test_job_pool_shutdown_prevents_new_jobs() {
         let pool = JobPool::new(make_store());
@@ -670,52 +386,73 @@ mod tests {
         assert!(pool.shutting_down.load(Ordering::SeqCst));
     }
 
-    #[tokio::test]
-    async fn test_exists_in_queue_with_different_ids() {
-        let pool = JobPool::new(make_store());
-        assert!(!pool.exists_in_queue(1).await);
-        assert!(!pool.exists_in_queue(2).await);
-        assert!(!pool.exists_in_queue(999).await);
+    #[test]
+    fn test_job_logger_new_does_not_panic() {
+        let _logger = JobLogger::new("initialization_test");
     }
 
     #[tokio::test]
-    async fn test_job_type_alias() {
-        let job: Job = QueuedTask {
-            task: crate::models::Task {
-                id: 7,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Waiting,
-        };
-        assert_eq!(job.task.id, 7);
-    }
+    async fn test_high_priority_task_overtakes_waiting_low_priority() {
+        use crate::db::store::{TaskManager, TemplateManager};
+        use crate::models::{Task, TaskClaimer, Template};
+        use crate::services::concurrency;
+        use crate::services::task_logger::TaskStatus;
 
-    #[tokio::test]
-    async fn test_queued_task_with_success_status() {
-        let qt = QueuedTask {
-            task: crate::models::Task {
-                id: 50,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Success,
+        let store = make_store();
+        // Шаблон без параллельного запуска; гейт плана завершает запуск без процессов
+        store
+            .create_template(Template {
+                id: 1,
+                project_id: 1,
+                allow_parallel_tasks: false,
+                require_approval: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let holder = store
+            .create_task(Task {
+                id: 1,
+                ..Task::new_waiting(1, 1)
+            })
+            .await
+            .unwrap();
+        concurrency::claim_with_slots(
+            store.as_ref(),
+            &holder,
+            TaskClaimer::Node("other-node".to_string()),
+        )
+        .await
+        .unwrap();
+        for (id, priority, waited) in [(2, 0, 60), (3, 10, 0)] {
+            store
+                .create_task(Task {
+                    id,
+                    priority: Some(priority),
+                    created: chrono::Utc::now() - chrono::Duration::seconds(waited),
+                    ..Task::new_waiting(1, 1)
+                })
+                .await
+                .unwrap();
+        }
+        let status = |id| {
+            let store = store.clone();
+            async move { store.get_task(1, id).await.unwrap().status }
         };
-        assert_eq!(qt.status, TaskStatus::Success);
-    }
 
-    #[tokio::test]
-    async fn test_queued_task_with_stopped_status() {
-        let qt = QueuedTask {
-            task: crate::models::Task {
-                id: 51,
-                ..crate::models::Task::default()
-            },
-            status: TaskStatus::Stopped,
-        };
-        assert_eq!(qt.status, TaskStatus::Stopped);
-    }
+        let pool = JobPool::new(store.clone());
+        let logger = JobLogger::new("test");
+        pool.check_new_jobs(&logger).await;
+        assert_eq!(status(2).await, TaskStatus::Waiting);
+        assert_eq!(status(3).await, TaskStatus::Waiting);
 
-    #[test]
-    fn test_job_logger_new_does_not_panic() {
-        let _logger = JobLogger::new("initialization_test");
+        // Слот освобождается — его забирает более приоритетная задача, хотя она моложе
+        store
+            .update_task_status(1, 1, TaskStatus::Success)
+            .await
+            .unwrap();
+        pool.check_new_jobs(&logger).await;
+        assert_ne!(status(3).await, TaskStatus::Waiting);
+        assert_eq!(status(2).await, TaskStatus::Waiting);
     }
 }
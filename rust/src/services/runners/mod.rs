@@ -7,7 +7,7 @@ pub mod running_job;
 pub mod task_queue;
 pub mod types;
 
-pub use job_pool::{Job, JobLogger, JobPool};
+pub use job_pool::{JobLogger, JobPool};
 pub use running_job::RunningJob;
 pub use task_queue::{InMemoryTaskQueue, RedisTaskQueue, TaskQueue, build_task_queue};
 pub use types::{
@@ -0,0 +1,245 @@
+//! Календарь расписания
+//!
+//! Вычисляет время срабатываний cron в часовом поясе расписания с учётом
+//! периодов запрета и политики пропущенных запусков.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use cron::Schedule as CronSchedule;
+
+use crate::error::{Error, Result};
+use crate::models::Schedule;
+use crate::models::schedule::{BlackoutWindow, MISFIRE_RUN_ALL, MISFIRE_RUN_ONCE};
+use crate::services::scheduler::SchedulePool;
+
+/// Сколько секунд после срабатывания запуск ещё считается своевременным
+pub const MISFIRE_GRACE_SECS: i64 = 60;
+
+/// Максимум пропущенных запусков, догоняемых по политике `run_all`
+pub const MAX_CATCH_UP: usize = 100;
+
+/// Предел перебора cron-итератора (защита от выражений вида «каждую секунду»)
+const MAX_SCAN: usize = 10_000;
+
+/// Cron-расписание, привязанное к часовому поясу и периодам запрета
+#[derive(Debug, Clone)]
+pub struct ScheduleCalendar {
+    cron: CronSchedule,
+    tz: Tz,
+    blackouts: Vec<BlackoutWindow>,
+}
+
+impl ScheduleCalendar {
+    /// Создаёт календарь; `timezone` — имя IANA, по умолчанию UTC
+    pub fn new(cron: &str, timezone: Option<&str>, blackouts: Vec<BlackoutWindow>) -> Result<Self> {
+        let expr = SchedulePool::normalize_cron_expression(cron);
+        if expr.is_empty() {
+            return Err(Error::Other("Пустое cron выражение".to_string()));
+        }
+        let cron = expr
+            .parse::<CronSchedule>()
+            .map_err(|e| Error::Other(format!("Неверное cron выражение '{}': {}", expr, e)))?;
+        Ok(Self {
+            cron,
+            tz: parse_timezone(timezone)?,
+            blackouts,
+        })
+    }
+
+    /// Календарь расписания из его настроек
+    pub fn for_schedule(schedule: &Schedule) -> Result<Self> {
+        Self::new(
+            &schedule.cron,
+            schedule.timezone.as_deref(),
+            schedule.blackout_windows.clone(),
+        )
+    }
+
+    /// Попадает ли момент в период запрета (в часовом поясе расписания)
+    pub fn in_blackout(&self, at: DateTime<Utc>) -> bool {
+        let local = at.with_timezone(&self.tz).naive_local();
+        self.blackouts.iter().any(|w| w.contains(local))
+    }
+
+    /// Ближайшее срабатывание cron строго после `after`, без учёта периодов запрета
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.cron
+            .after(&after.with_timezone(&self.tz))
+            .next()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Ближайшие `count` срабатываний после `after`, кроме попавших в периоды запрета
+    pub fn upcoming(&self, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        self.cron
+            .after(&after.with_timezone(&self.tz))
+            .take(MAX_SCAN)
+            .map(|t| t.with_timezone(&Utc))
+            .filter(|t| !self.in_blackout(*t))
+            .take(count)
+            .collect()
+    }
+
+    /// Срабатывания в интервале `(after, until]` по возрастанию, кроме периодов запрета
+    ///
+    /// Возвращаются не более [`MAX_CATCH_UP`] последних срабатываний интервала.
+    pub fn fire_times(&self, after: DateTime<Utc>, until: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        if until <= after {
+            return Vec::new();
+        }
+        // Итератор назад отдаёт времена строго раньше точки отсчёта
+        let start = (until + TimeDelta::seconds(1)).with_timezone(&self.tz);
+        let mut fires: Vec<DateTime<Utc>> = self
+            .cron
+            .after(&start)
+            .rev()
+            .take(MAX_SCAN)
+            .map(|t| t.with_timezone(&Utc))
+            .take_while(|t| *t > after)
+            .filter(|t| *t <= until && !self.in_blackout(*t))
+            .take(MAX_CATCH_UP)
+            .collect();
+        fires.reverse();
+        fires
+    }
+}
+
+/// Разбирает имя часового пояса IANA; пустое значение — UTC
+pub fn parse_timezone(timezone: Option<&str>) -> Result<Tz> {
+    match timezone.map(str::trim).filter(|tz| !tz.is_empty()) {
+        None => Ok(Tz::UTC),
+        Some(name) => name
+            .parse::<Tz>()
+            .map_err(|_| Error::Validation(format!("Неизвестный часовой пояс '{}'", name))),
+    }
+}
+
+/// Отбирает срабатывания для запуска согласно политике пропусков
+///
+/// `fires` — срабатывания по возрастанию. Своевременными считаются те, что наступили
+/// не раньше чем [`MISFIRE_GRACE_SECS`] назад; остальные пропущены (например, во время простоя).
+pub fn apply_misfire_policy(
+    policy: &str,
+    fires: Vec<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    let grace_start = now - TimeDelta::seconds(MISFIRE_GRACE_SECS);
+    let (on_time, missed): (Vec<_>, Vec<_>) = fires.into_iter().partition(|t| *t >= grace_start);
+    match policy {
+        MISFIRE_RUN_ALL => missed.into_iter().chain(on_time).collect(),
+        MISFIRE_RUN_ONCE if on_time.is_empty() => missed.into_iter().last().into_iter().collect(),
+        _ => on_time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_next_after_uses_schedule_timezone() {
+        // 09:00 по Москве (UTC+3) — это 06:00 UTC
+        let calendar = ScheduleCalendar::new("0 9 * * *", Some("Europe/Moscow"), vec![]).unwrap();
+        let next = calendar.next_after(utc(2026, 3, 10, 0, 0)).unwrap();
+        assert_eq!(next, utc(2026, 3, 10, 6, 0));
+    }
+
+    #[test]
+    fn test_next_after_follows_dst() {
+        // Нью-Йорк переходит на летнее время 8 марта 2026
+        let calendar =
+            ScheduleCalendar::new("0 9 * * *", Some("America/New_York"), vec![]).unwrap();
+        assert_eq!(
+            calendar.next_after(utc(2026, 3, 6, 0, 0)).unwrap(),
+            utc(2026, 3, 6, 14, 0)
+        );
+        assert_eq!(
+            calendar.next_after(utc(2026, 3, 9, 0, 0)).unwrap(),
+            utc(2026, 3, 9, 13, 0)
+        );
+    }
+
+    #[test]
+    fn test_unknown_timezone_rejected() {
+        assert!(ScheduleCalendar::new("0 9 * * *", Some("Mars/Olympus"), vec![]).is_err());
+        assert_eq!(parse_timezone(Some("")).unwrap(), Tz::UTC);
+    }
+
+    #[test]
+    fn test_fire_times_interval_bounds() {
+        let calendar = ScheduleCalendar::new("0 * * * *", None, vec![]).unwrap();
+        let fires = calendar.fire_times(utc(2026, 1, 1, 10, 0), utc(2026, 1, 1, 13, 0));
+        assert_eq!(
+            fires,
+            vec![
+                utc(2026, 1, 1, 11, 0),
+                utc(2026, 1, 1, 12, 0),
+                utc(2026, 1, 1, 13, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fire_times_capped_to_latest() {
+        let calendar = ScheduleCalendar::new("* * * * *", None, vec![]).unwrap();
+        let until = utc(2026, 1, 2, 0, 0);
+        let fires = calendar.fire_times(utc(2026, 1, 1, 0, 0), until);
+        assert_eq!(fires.len(), MAX_CATCH_UP);
+        assert_eq!(*fires.last().unwrap(), until);
+    }
+
+    #[test]
+    fn test_blackout_windows_excluded() {
+        let blackout = BlackoutWindow {
+            start: local(2026, 12, 31, 0),
+            end: local(2027, 1, 2, 0),
+            reason: Some("Новогодний freeze".to_string()),
+        };
+        let calendar =
+            ScheduleCalendar::new("0 12 * * *", Some("Europe/Berlin"), vec![blackout]).unwrap();
+        let upcoming = calendar.upcoming(utc(2026, 12, 30, 0, 0), 2);
+        assert_eq!(
+            upcoming,
+            vec![utc(2026, 12, 30, 11, 0), utc(2027, 1, 2, 11, 0)]
+        );
+        assert!(calendar.in_blackout(utc(2026, 12, 31, 11, 0)));
+    }
+
+    #[test]
+    fn test_misfire_policies() {
+        let now = utc(2026, 1, 1, 12, 0);
+        let fires = vec![
+            utc(2026, 1, 1, 10, 0),
+            utc(2026, 1, 1, 11, 0),
+            utc(2026, 1, 1, 12, 0),
+        ];
+        assert_eq!(
+            apply_misfire_policy("skip", fires.clone(), now),
+            vec![utc(2026, 1, 1, 12, 0)]
+        );
+        assert_eq!(
+            apply_misfire_policy("run_once", fires.clone(), now),
+            vec![utc(2026, 1, 1, 12, 0)]
+        );
+        assert_eq!(apply_misfire_policy("run_all", fires.clone(), now), fires);
+
+        let missed = fires[..2].to_vec();
+        assert!(apply_misfire_policy("skip", missed.clone(), now).is_empty());
+        assert_eq!(
+            apply_misfire_policy("run_once", missed, now),
+            vec![utc(2026, 1, 1, 11, 0)]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Запуск расписаний по новому коммиту
+//!
+//! Расписание с `repository_id` на каждом срабатывании cron опрашивает репозиторий
+//! и запускает шаблон, только если HEAD ветки сменился, а при заданных
+//! `commit_paths` — только если коммиты затронули подходящие файлы.
+
+use glob::{MatchOptions, Pattern};
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock, Mutex};
+use tracing::{info, warn};
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::Schedule;
+use crate::services::git_repository::{GitClient, GitClientFactory, GitRepository};
+
+/// Новый коммит, на который нужно запустить задачу
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewCommit {
+    pub hash: String,
+    pub message: String,
+}
+
+/// Расписания, чей репозиторий опрашивается прямо сейчас
+static POLLING: LazyLock<Mutex<HashSet<i32>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Отметка об опросе репозитория расписанием; снимается при завершении опроса
+struct PollGuard(i32);
+
+impl PollGuard {
+    fn acquire(schedule_id: i32) -> Option<Self> {
+        let mut polling = POLLING.lock().unwrap_or_else(|e| e.into_inner());
+        polling.insert(schedule_id).then(|| Self(schedule_id))
+    }
+}
+
+impl Drop for PollGuard {
+    fn drop(&mut self) {
+        POLLING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.0);
+    }
+}
+
+/// Проверяет glob-шаблоны путей перед сохранением расписания
+pub fn validate_commit_paths(patterns: &[String]) -> Result<()> {
+    for pattern in patterns {
+        if pattern.trim().is_empty() {
+            return Err(Error::Validation("Пустой шаблон пути".to_string()));
+        }
+        Pattern::new(pattern.trim())
+            .map_err(|e| Error::Validation(format!("Неверный шаблон пути '{}': {}", pattern, e)))?;
+    }
+    Ok(())
+}
+
+/// Затрагивает ли хотя бы один из файлов шаблоны путей
+///
+/// `*` не пересекает границу каталога, `**` соответствует любой вложенности.
+pub fn paths_match(patterns: &[String], files: &[String]) -> bool {
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let patterns: Vec<Pattern> = patterns
+        .iter()
+        .filter_map(|p| Pattern::new(p.trim()).ok())
+        .collect();
+    files
+        .iter()
+        .any(|file| patterns.iter().any(|p| p.matches_with(file, options)))
+}
+
+/// Опрашивает репозиторий расписания и возвращает новый коммит, если его нужно запустить
+///
+/// Новый хэш сохраняется в расписании, даже если изменения не затронули `commit_paths`,
+/// чтобы следующий опрос сравнивал изменения уже с ним.
+pub async fn poll_new_commit(
+    store: &Arc<dyn Store + Send + Sync>,
+    schedule: &Schedule,
+) -> Result<Option<NewCommit>> {
+    let Some(repository_id) = schedule.repository_id else {
+        return Ok(None);
+    };
+    let Some(_guard) = PollGuard::acquire(schedule.id) else {
+        info!(
+            "Репозиторий расписания {} ещё опрашивается, пропускаем",
+            schedule.id
+        );
+        return Ok(None);
+    };
+
+    let mut repository = store
+        .get_repository(schedule.project_id, repository_id)
+        .await?;
+    if let Some(branch) = schedule
+        .git_branch
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+    {
+        repository.git_branch = Some(branch.to_string());
+    }
+    let branch = repository.git_branch.clone().filter(|b| !b.is_empty());
+
+    let client = GitClientFactory::create();
+    let repo = GitRepository::new(repository, schedule.project_id, schedule.template_id)
+        .with_tmp_dir(format!("schedule_{}", schedule.id));
+    if !client.can_be_pulled(&repo) {
+        client.clone(&repo).await?;
+    }
+    if let Some(branch) = branch.as_deref() {
+        client.checkout(&repo, branch).await?;
+    }
+    client.pull(&repo).await?;
+
+    let hash = client.get_last_commit_hash(&repo).await?;
+    if schedule.last_commit_hash.as_deref() == Some(hash.as_str()) {
+        return Ok(None);
+    }
+
+    let relevant = match schedule.last_commit_hash.as_deref() {
+        Some(previous) if !schedule.commit_paths.is_empty() => {
+            match client.get_changed_files(&repo, previous, &hash).await {
+                Ok(files) => paths_match(&schedule.commit_paths, &files),
+                Err(e) => {
+                    // Предыдущий коммит мог исчезнуть (force push) — запускаем
+                    warn!(
+                        "Не удалось сравнить коммиты расписания {}: {}",
+                        schedule.id, e
+                    );
+                    true
+                }
+            }
+        }
+        _ => true,
+    };
+
+    store
+        .set_schedule_commit_hash(schedule.project_id, schedule.id, &hash)
+        .await?;
+
+    if !relevant {
+        info!(
+            "Коммит {} не затрагивает пути расписания {}, пропускаем",
+            hash, schedule.id
+        );
+        return Ok(None);
+    }
+
+    let message = client
+        .get_last_commit_message(&repo)
+        .await
+        .unwrap_or_default();
+    Ok(Some(NewCommit { hash, message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_paths_match_globs() {
+        let files = strings(&["infra/modules/vpc/main.tf", "README.md"]);
+        assert!(paths_match(&strings(&["infra/**"]), &files));
+        assert!(paths_match(&strings(&["**/*.tf"]), &files));
+        assert!(paths_match(&strings(&["*.md"]), &files));
+        assert!(!paths_match(&strings(&["*.tf"]), &files));
+        assert!(!paths_match(&strings(&["playbooks/**"]), &files));
+        assert!(!paths_match(&strings(&["**/*.tf"]), &[]));
+    }
+
+    #[test]
+    fn test_validate_commit_paths() {
+        assert!(validate_commit_paths(&strings(&["roles/**", "site.yml"])).is_ok());
+        assert!(validate_commit_paths(&strings(&["roles/[a"])).is_err());
+        assert!(validate_commit_paths(&strings(&[" "])).is_err());
+    }
+
+    #[test]
+    fn test_poll_guard_is_exclusive() {
+        let guard = PollGuard::acquire(-42).unwrap();
+        assert!(PollGuard::acquire(-42).is_none());
+        drop(guard);
+        assert!(PollGuard::acquire(-42).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_poll_without_repository_is_noop() {
+        use crate::db::mock::MockStore;
+
+        let store: Arc<dyn Store + Send + Sync> = Arc::new(MockStore::new());
+        let schedule: Schedule =
+            serde_json::from_str(r#"{"template_id":1,"name":"cron","cron":"0 * * * *"}"#).unwrap();
+        assert_eq!(poll_new_commit(&store, &schedule).await.unwrap(), None);
+    }
+}
@@ -1,19 +1,33 @@
 //! Планировщик задач
 //!
 //! Предоставляет инфраструктуру для автоматического запуска задач по расписанию (cron).
+//! Расписание может запускать шаблон, workflow или проверку дрейфа; cron вычисляется
+//! в часовом поясе расписания, а пропущенные за время простоя запуски обрабатываются
+//! согласно политике расписания. В кластере расписания запускает только узел-лидер.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use cron::Schedule as CronSchedule;
+use serde_json::Map;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{Duration, sleep};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
+use crate::api::state::AppState;
+use crate::config::Config;
 use crate::db::store::Store;
 use crate::error::{Error, Result};
 use crate::models::Schedule;
-use crate::services::task_execution;
+use crate::models::drift::DriftConfig;
+use crate::models::schedule::{SCHEDULE_TARGET_DRIFT, SCHEDULE_TARGET_WORKFLOW};
+use crate::services::leader_election::Leadership;
+use crate::services::schedule_calendar::{ScheduleCalendar, apply_misfire_policy};
+use crate::services::schedule_commit::{self, NewCommit};
+use crate::services::{drift, task_execution, workflow_executor};
+
+/// Максимальный интервал между проверками расписаний
+const TICK: Duration = Duration::from_secs(10);
 
 /// Задача планировщика
 #[derive(Debug, Clone)]
@@ -22,28 +36,74 @@ pub struct ScheduledJob {
     pub template_id: i32,
     pub project_id: i32,
     pub cron: String,
+    pub timezone: Option<String>,
     pub name: String,
     pub active: bool,
     pub next_run: Option<DateTime<Utc>>,
 }
 
+impl ScheduledJob {
+    fn from_schedule(schedule: &Schedule, next_run: Option<DateTime<Utc>>) -> Self {
+        Self {
+            schedule_id: schedule.id,
+            template_id: schedule.template_id,
+            project_id: schedule.project_id,
+            cron: schedule.cron.clone(),
+            timezone: schedule.timezone.clone(),
+            name: schedule.name.clone(),
+            active: schedule.active,
+            next_run,
+        }
+    }
+
+    /// Совпадает ли вычисление времени запуска с настройками расписания
+    fn same_timing(&self, schedule: &Schedule) -> bool {
+        self.cron == schedule.cron && self.timezone == schedule.timezone
+    }
+}
+
+/// Расписание, которое пора запускать
+#[derive(Debug, Clone)]
+struct DueSchedule {
+    schedule: Schedule,
+    /// Срабатывания, которые нужно выполнить
+    fires: Vec<DateTime<Utc>>,
+    /// Момент, до которого срабатывания обработаны (новое значение `last_fired`)
+    fired_until: DateTime<Utc>,
+}
+
 /// Менеджер пула планировщика
 pub struct SchedulePool {
     store: Arc<dyn Store + Send + Sync>,
     jobs: Arc<RwLock<HashMap<i32, ScheduledJob>>>,
+    /// Расписания проверок дрейфа: drift_config_id → (cron, следующий запуск)
+    drift_jobs: Arc<RwLock<HashMap<i32, DriftJob>>>,
     running: Arc<RwLock<bool>>,
+    /// Лидерство узла: расписания и проверки дрейфа запускает только лидер
+    leadership: Leadership,
 }
 
+/// Расписание проверки дрейфа
+type DriftJob = (String, DateTime<Utc>);
+
 impl SchedulePool {
     /// Создаёт новый пул планировщика
     pub fn new(store: Arc<dyn Store + Send + Sync>) -> Self {
         Self {
             store,
             jobs: Arc::new(RwLock::new(HashMap::new())),
+            drift_jobs: Arc::new(RwLock::new(HashMap::new())),
             running: Arc::new(RwLock::new(false)),
+            leadership: Leadership::standalone(),
         }
     }
 
+    /// Запускает расписания только при лидерстве узла в кластере
+    pub fn with_leadership(mut self, leadership: Leadership) -> Self {
+        self.leadership = leadership;
+        self
+    }
+
     /// Запускает планировщик
     pub async fn start(&self) -> Result<()> {
         let mut running = self.running.write().await;
@@ -53,18 +113,38 @@ impl SchedulePool {
         *running = true;
         drop(running);
 
-        // Загружаем все активные расписания
-        self.load_schedules().await?;
+        // Загружаем расписания и догоняем запуски, пропущенные во время простоя
+        let workflows = Arc::new(AppState::new(self.store.clone(), Config::default(), None));
+        if self.leadership.is_leader() {
+            Self::check_schedules(&self.jobs, &self.store, &workflows).await;
+        }
 
         // Запускаем фоновую задачу для проверки расписаний
         let jobs = self.jobs.clone();
+        let drift_jobs = self.drift_jobs.clone();
         let running = self.running.clone();
         let store = self.store.clone();
+        let leadership = self.leadership.clone();
 
         tokio::spawn(async move {
+            let mut leader = leadership.subscribe();
             while *running.read().await {
-                Self::check_schedules(&jobs, &store).await;
-                sleep(Duration::from_secs(10)).await;
+                let wait = Self::next_wakeup(&jobs, Utc::now()).await;
+                tokio::select! {
+                    _ = sleep(wait) => {}
+                    _ = leader.changed() => {}
+                }
+                if !*running.read().await {
+                    break;
+                }
+                if !*leader.borrow_and_update() {
+                    // Запуски ведёт другой узел; получив лидерство, догоняем их с `last_fired`
+                    jobs.write().await.clear();
+                    drift_jobs.write().await.clear();
+                    continue;
+                }
+                Self::check_schedules(&jobs, &store, &workflows).await;
+                Self::check_drift_schedules(&drift_jobs, &store).await;
             }
         });
 
@@ -84,83 +164,318 @@ impl SchedulePool {
         Ok(())
     }
 
-    /// Загружает все активные расписания из БД
-    async fn load_schedules(&self) -> Result<()> {
-        let schedules = self.store.get_all_schedules().await?;
+    /// Сколько ждать до следующей проверки: до ближайшего запуска, но не дольше [`TICK`]
+    async fn next_wakeup(
+        jobs: &Arc<RwLock<HashMap<i32, ScheduledJob>>>,
+        now: DateTime<Utc>,
+    ) -> Duration {
+        let nearest = jobs.read().await.values().filter_map(|j| j.next_run).min();
+        nearest
+            .map(|next| {
+                (next - now)
+                    .to_std()
+                    .unwrap_or_default()
+                    .clamp(Duration::from_millis(200), TICK)
+            })
+            .unwrap_or(TICK)
+    }
+
+    /// Проверяет расписания и запускает их цели
+    ///
+    /// Расписания перечитываются на каждом такте, поэтому созданные и изменённые
+    /// через API расписания применяются без перезапуска планировщика.
+    async fn check_schedules(
+        jobs: &Arc<RwLock<HashMap<i32, ScheduledJob>>>,
+        store: &Arc<dyn Store + Send + Sync>,
+        workflows: &Arc<AppState>,
+    ) {
+        let schedules = match store.get_all_schedules().await {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                error!("Ошибка загрузки расписаний: {}", e);
+                return;
+            }
+        };
 
-        let mut jobs = self.jobs.write().await;
-        jobs.clear();
+        let due = {
+            let mut jobs = jobs.write().await;
+            Self::due_schedules(&mut jobs, &schedules, Utc::now())
+        };
+
+        for entry in due {
+            if let Err(e) = store
+                .set_schedule_last_fired(entry.schedule.id, entry.fired_until)
+                .await
+            {
+                error!(
+                    "Ошибка сохранения времени запуска расписания {}: {}",
+                    entry.schedule.id, e
+                );
+            }
+            // Опрос репозитория несколько раз подряд ничего не даст — достаточно последнего
+            let fires = if entry.schedule.is_commit_triggered() {
+                entry.fires.last().copied().into_iter().collect()
+            } else {
+                entry.fires
+            };
+            for fire in fires {
+                Self::spawn_fire(
+                    store.clone(),
+                    workflows.clone(),
+                    entry.schedule.clone(),
+                    fire,
+                );
+            }
+        }
+    }
 
-        for schedule in schedules {
-            if schedule.active {
-                let run_at_only = schedule.cron_format.as_deref() == Some("run_at")
-                    && schedule.cron.trim().is_empty();
-                if run_at_only {
+    /// Участвует ли расписание в cron-планировании
+    fn is_cron_schedule(schedule: &Schedule) -> bool {
+        let run_at_only =
+            schedule.cron_format.as_deref() == Some("run_at") && schedule.cron.trim().is_empty();
+        schedule.active && !run_at_only
+    }
+
+    /// Отбирает расписания, которые пора запускать, и сдвигает их время запуска
+    ///
+    /// Новое или изменённое (cron, часовой пояс) расписание только планирует первый
+    /// запуск. При первой встрече расписания после старта догоняются срабатывания
+    /// с `last_fired` согласно политике пропусков.
+    fn due_schedules(
+        jobs: &mut HashMap<i32, ScheduledJob>,
+        schedules: &[Schedule],
+        now: DateTime<Utc>,
+    ) -> Vec<DueSchedule> {
+        jobs.retain(|id, _| {
+            schedules
+                .iter()
+                .any(|s| s.id == *id && Self::is_cron_schedule(s))
+        });
+
+        let mut due = Vec::new();
+        for schedule in schedules.iter().filter(|s| Self::is_cron_schedule(s)) {
+            let calendar = match ScheduleCalendar::for_schedule(schedule) {
+                Ok(calendar) => calendar,
+                Err(e) => {
+                    warn!("Расписание {} пропущено: {}", schedule.id, e);
+                    jobs.remove(&schedule.id);
                     continue;
                 }
-                if let Ok(next_run) = Self::calculate_next_run(&schedule.cron) {
-                    jobs.insert(
-                        schedule.id,
-                        ScheduledJob {
-                            schedule_id: schedule.id,
-                            template_id: schedule.template_id,
-                            project_id: schedule.project_id,
-                            cron: schedule.cron.clone(),
-                            name: schedule.name.clone(),
-                            active: schedule.active,
-                            next_run: Some(next_run),
-                        },
-                    );
-                }
+            };
+
+            // Интервал (after, now], срабатывания которого нужно обработать на этом такте
+            let window = match jobs.get(&schedule.id) {
+                Some(job) if job.same_timing(schedule) => job
+                    .next_run
+                    .filter(|next| now >= *next)
+                    .map(|next| next - TimeDelta::milliseconds(1)),
+                Some(_) => None,
+                None => Some(schedule.last_fired.unwrap_or(now)),
+            };
+
+            if let Some(after) = window {
+                let fires = apply_misfire_policy(
+                    &schedule.misfire_policy,
+                    calendar.fire_times(after, now),
+                    now,
+                );
+                due.push(DueSchedule {
+                    schedule: schedule.clone(),
+                    fires,
+                    fired_until: now,
+                });
             }
+
+            let next_run = match jobs.get(&schedule.id) {
+                Some(job) if window.is_none() && job.same_timing(schedule) => job.next_run,
+                _ => calendar.next_after(now),
+            };
+            jobs.insert(schedule.id, ScheduledJob::from_schedule(schedule, next_run));
         }
+        due
+    }
 
-        Ok(())
+    /// Запускает цель расписания в фоне с учётом случайной задержки
+    fn spawn_fire(
+        store: Arc<dyn Store + Send + Sync>,
+        workflows: Arc<AppState>,
+        schedule: Schedule,
+        fire: DateTime<Utc>,
+    ) {
+        tokio::spawn(async move {
+            if schedule.jitter_secs > 0 {
+                let jitter = rand::random_range(0..=schedule.jitter_secs as u64);
+                sleep(Duration::from_secs(jitter)).await;
+            }
+            info!(
+                "Срабатывание расписания {} ({}) на {}",
+                schedule.id, schedule.target_type, fire
+            );
+            if let Err(e) = Self::fire_target(&store, &workflows, &schedule).await {
+                error!("Ошибка запуска по расписанию {}: {}", schedule.id, e);
+            }
+        });
     }
 
-    /// Проверяет расписания и запускает задачи
-    async fn check_schedules(
-        jobs: &Arc<RwLock<HashMap<i32, ScheduledJob>>>,
+    /// Запускает цель расписания: шаблон, workflow или проверку дрейфа
+    async fn fire_target(
+        store: &Arc<dyn Store + Send + Sync>,
+        workflows: &Arc<AppState>,
+        schedule: &Schedule,
+    ) -> Result<()> {
+        let target_id = || {
+            schedule.target_id.ok_or_else(|| {
+                Error::Validation(format!(
+                    "Для цели '{}' не задан target_id",
+                    schedule.target_type
+                ))
+            })
+        };
+        match schedule.target_type.as_str() {
+            SCHEDULE_TARGET_WORKFLOW => {
+                let run = workflow_executor::run_workflow(
+                    workflows.clone(),
+                    target_id()?,
+                    schedule.project_id,
+                    Map::new(),
+                )
+                .await?;
+                info!(
+                    "Запущен workflow run {} по расписанию {}",
+                    run.id, schedule.id
+                );
+                Ok(())
+            }
+            SCHEDULE_TARGET_DRIFT => {
+                let config = store
+                    .get_drift_config(target_id()?, schedule.project_id)
+                    .await?;
+                if drift::drift_check_in_progress(store.as_ref(), &config).await {
+                    info!("Проверка дрейфа {} ещё выполняется, пропускаем", config.id);
+                    return Ok(());
+                }
+                drift::start_drift_check(store.clone(), &config).await?;
+                Ok(())
+            }
+            _ => {
+                let commit = if schedule.is_commit_triggered() {
+                    match schedule_commit::poll_new_commit(store, schedule).await? {
+                        Some(commit) => Some(commit),
+                        None => return Ok(()),
+                    }
+                } else {
+                    None
+                };
+                Self::trigger_task(
+                    store,
+                    schedule.id,
+                    schedule.template_id,
+                    schedule.project_id,
+                    commit,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Запускает проверки дрейфа, чьё cron-расписание наступило
+    ///
+    /// Конфигурации перечитываются на каждом такте, поэтому изменения через API
+    /// применяются без перезапуска планировщика.
+    async fn check_drift_schedules(
+        drift_jobs: &Arc<RwLock<HashMap<i32, DriftJob>>>,
         store: &Arc<dyn Store + Send + Sync>,
     ) {
-        let now = Utc::now();
-        let mut jobs_to_run = Vec::new();
+        let configs = match store.get_enabled_drift_configs().await {
+            Ok(configs) => configs,
+            Err(e) => {
+                error!("Ошибка загрузки конфигураций дрейфа: {}", e);
+                return;
+            }
+        };
 
-        {
-            let mut jobs_write = jobs.write().await;
-            for (id, job) in jobs_write.iter_mut() {
-                if !job.active {
-                    continue;
-                }
+        let due = {
+            let mut jobs = drift_jobs.write().await;
+            Self::due_drift_configs(&mut jobs, &configs, Utc::now())
+        };
+
+        for config in due {
+            if drift::drift_check_in_progress(store.as_ref(), &config).await {
+                info!("Проверка дрейфа {} ещё выполняется, пропускаем", config.id);
+                continue;
+            }
+            if let Err(e) = drift::start_drift_check(store.clone(), &config).await {
+                error!("Ошибка запуска проверки дрейфа {}: {}", config.id, e);
+            }
+        }
+    }
 
-                if let Some(next_run) = job.next_run {
-                    if now >= next_run {
-                        jobs_to_run.push((*id, job.template_id, job.project_id));
+    /// Отбирает конфигурации дрейфа, которые пора запускать, и сдвигает их расписание
+    ///
+    /// Новая или изменённая cron-строка только планирует первый запуск.
+    fn due_drift_configs(
+        jobs: &mut HashMap<i32, DriftJob>,
+        configs: &[DriftConfig],
+        now: DateTime<Utc>,
+    ) -> Vec<DriftConfig> {
+        let scheduled = |c: &DriftConfig| {
+            c.schedule
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        jobs.retain(|id, _| {
+            configs
+                .iter()
+                .any(|c| c.id == *id && scheduled(c).is_some())
+        });
 
-                        // Обновляем следующее время запуска
-                        if let Ok(new_next) = Self::calculate_next_run(&job.cron) {
-                            job.next_run = Some(new_next);
-                        }
+        let mut due = Vec::new();
+        for config in configs {
+            let Some(cron) = scheduled(config) else {
+                continue;
+            };
+            match jobs.get(&config.id) {
+                Some((known, next)) if *known == cron => {
+                    if now < *next {
+                        continue;
                     }
+                    due.push(config.clone());
                 }
+                _ => {}
             }
-        }
-
-        // Запускаем задачи
-        for (schedule_id, template_id, project_id) in jobs_to_run {
-            if let Err(e) = Self::trigger_task(store, schedule_id, template_id, project_id).await {
-                error!("Ошибка запуска задачи по расписанию {}: {}", schedule_id, e);
+            match Self::calculate_next_run(&cron) {
+                Ok(next) => {
+                    jobs.insert(config.id, (cron, next));
+                }
+                Err(_) => {
+                    jobs.remove(&config.id);
+                }
             }
         }
+        due
     }
 
-    /// Запускает задачу
+    /// Запускает задачу; для расписаний по коммиту задача получает хэш и сообщение коммита
     async fn trigger_task(
         store: &Arc<dyn Store + Send + Sync>,
         schedule_id: i32,
         template_id: i32,
         project_id: i32,
+        commit: Option<NewCommit>,
     ) -> Result<()> {
+        let message = match &commit {
+            Some(commit) => format!(
+                "Запущено по новому коммиту {}",
+                &commit.hash[..commit.hash.len().min(8)]
+            ),
+            None => "Запущено по расписанию".to_string(),
+        };
+        let (commit_hash, commit_message) = commit
+            .map(|c| (Some(c.hash), Some(c.message)))
+            .unwrap_or_default();
+
         // Создаём новую задачу
         let task = crate::models::Task {
             id: 0,
@@ -178,15 +493,16 @@ impl SchedulePool {
             created: Utc::now(),
             start: None,
             end: None,
-            message: Some("Запущено по расписанию".to_string()),
-            commit_hash: None,
-            commit_message: None,
+            message: Some(message),
+            commit_hash,
+            commit_message,
             build_task_id: None,
             version: None,
             inventory_id: None,
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         let created_task = store.create_task(task).await?;
@@ -196,17 +512,14 @@ impl SchedulePool {
             created_task.id, schedule_id
         );
 
-        // Запускаем задачу в фоновом потоке
-        let store_clone = store.clone();
-        tokio::spawn(async move {
-            task_execution::execute_task(store_clone, created_task).await;
-        });
+        // Задачу забирает пул задач
+        task_execution::wake_dispatcher();
 
         Ok(())
     }
 
     /// Нормализует cron: UI передаёт 5 полей (`мин час DOM M DOW`), библиотека ожидает секунды первым полем.
-    fn normalize_cron_expression(cron: &str) -> String {
+    pub(crate) fn normalize_cron_expression(cron: &str) -> String {
         let cron = cron.trim();
         if cron.is_empty() {
             return String::new();
@@ -248,30 +561,16 @@ impl SchedulePool {
 
     /// Добавляет расписание в пул
     pub async fn add_schedule(&self, schedule: Schedule) -> Result<()> {
-        if !schedule.active {
-            return Ok(());
-        }
-
-        let run_at_only =
-            schedule.cron_format.as_deref() == Some("run_at") && schedule.cron.trim().is_empty();
-        if run_at_only {
+        if !Self::is_cron_schedule(&schedule) {
             return Ok(());
         }
 
-        let next_run = Self::calculate_next_run(&schedule.cron)?;
+        let next_run = ScheduleCalendar::for_schedule(&schedule)?.next_after(Utc::now());
 
         let mut jobs = self.jobs.write().await;
         jobs.insert(
             schedule.id,
-            ScheduledJob {
-                schedule_id: schedule.id,
-                template_id: schedule.template_id,
-                project_id: schedule.project_id,
-                cron: schedule.cron.clone(),
-                name: schedule.name.clone(),
-                active: schedule.active,
-                next_run: Some(next_run),
-            },
+            ScheduledJob::from_schedule(&schedule, next_run),
         );
 
         Ok(())
@@ -302,6 +601,132 @@ impl SchedulePool {
 mod tests {
     use super::*;
 
+    fn drift_config(id: i32, schedule: Option<&str>) -> DriftConfig {
+        DriftConfig {
+            id,
+            project_id: 1,
+            template_id: 1,
+            enabled: true,
+            schedule: schedule.map(str::to_string),
+            created: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_due_drift_configs_schedules_then_fires() {
+        let mut jobs = HashMap::new();
+        let configs = vec![
+            drift_config(1, Some("*/5 * * * *")),
+            drift_config(2, None),
+            drift_config(3, Some("not a cron")),
+        ];
+
+        // Первый проход только планирует
+        let due = SchedulePool::due_drift_configs(&mut jobs, &configs, Utc::now());
+        assert!(due.is_empty());
+        assert_eq!(jobs.len(), 1);
+        let next = jobs[&1].1;
+
+        // Наступило время — конфигурация запускается и перепланируется
+        let due = SchedulePool::due_drift_configs(&mut jobs, &configs, next);
+        assert_eq!(due.iter().map(|c| c.id).collect::<Vec<_>>(), vec![1]);
+
+        // Удалённое расписание убирается из пула
+        let due = SchedulePool::due_drift_configs(&mut jobs, &configs[1..], next);
+        assert!(due.is_empty());
+        assert!(jobs.is_empty());
+    }
+
+    fn hourly_schedule(id: i32, policy: &str, last_fired: Option<DateTime<Utc>>) -> Schedule {
+        Schedule {
+            id,
+            template_id: 1,
+            project_id: 1,
+            cron: "0 0 * * * *".to_string(),
+            cron_format: None,
+            name: format!("schedule-{id}"),
+            active: true,
+            last_commit_hash: None,
+            repository_id: None,
+            created: None,
+            run_at: None,
+            delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: policy.to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired,
+            git_branch: None,
+            commit_paths: Vec::new(),
+        }
+    }
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+        Utc.with_ymd_and_hms(2026, 1, 1, h, m, 30).unwrap()
+    }
+
+    #[test]
+    fn test_due_schedules_schedules_then_fires() {
+        let mut jobs = HashMap::new();
+        let schedules = vec![hourly_schedule(1, "skip", None)];
+
+        // Новое расписание без last_fired только планируется
+        let due = SchedulePool::due_schedules(&mut jobs, &schedules, at(9, 30));
+        assert_eq!(due.len(), 1);
+        assert!(due[0].fires.is_empty());
+        assert_eq!(due[0].fired_until, at(9, 30));
+        let next = jobs[&1].next_run.unwrap();
+        assert_eq!(next, at(10, 0) - TimeDelta::seconds(30));
+
+        // До наступления времени ничего не запускается
+        let due = SchedulePool::due_schedules(&mut jobs, &schedules, at(9, 45));
+        assert!(due.is_empty());
+
+        // Наступило время — срабатывание запускается и перепланируется
+        let due = SchedulePool::due_schedules(&mut jobs, &schedules, next);
+        assert_eq!(due[0].fires, vec![next]);
+        assert!(jobs[&1].next_run.unwrap() > next);
+
+        // Удалённое расписание убирается из пула
+        let due = SchedulePool::due_schedules(&mut jobs, &[], next);
+        assert!(due.is_empty());
+        assert!(jobs.is_empty());
+    }
+
+    #[test]
+    fn test_due_schedules_misfire_after_downtime() {
+        let fires = |policy: &str| {
+            let mut jobs = HashMap::new();
+            let schedules = vec![hourly_schedule(1, policy, Some(at(9, 30)))];
+            let due = SchedulePool::due_schedules(&mut jobs, &schedules, at(12, 30));
+            due[0]
+                .fires
+                .iter()
+                .map(|t| t.format("%H:%M").to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(fires("skip").is_empty());
+        assert_eq!(fires("run_once"), vec!["12:00"]);
+        assert_eq!(fires("run_all"), vec!["10:00", "11:00", "12:00"]);
+    }
+
+    #[test]
+    fn test_due_schedules_changed_cron_only_reschedules() {
+        let mut jobs = HashMap::new();
+        let mut schedules = vec![hourly_schedule(1, "run_all", None)];
+        SchedulePool::due_schedules(&mut jobs, &schedules, at(9, 30));
+
+        schedules[0].cron = "0 */15 * * * *".to_string();
+        schedules[0].last_fired = Some(at(9, 30));
+        let due = SchedulePool::due_schedules(&mut jobs, &schedules, at(11, 0));
+        assert!(due.is_empty());
+        assert_eq!(jobs[&1].cron, "0 */15 * * * *");
+    }
+
     #[test]
     fn test_cron_parse_valid() {
         let result = SchedulePool::calculate_next_run("0 0 * * * *");
@@ -333,6 +758,7 @@ mod tests {
             template_id: 2,
             project_id: 3,
             cron: "0 0 * * * *".to_string(),
+            timezone: None,
             name: "Test Job".to_string(),
             active: true,
             next_run: Some(Utc::now()),
@@ -349,6 +775,7 @@ mod tests {
             template_id: 5,
             project_id: 1,
             cron: "*/5 * * * *".to_string(),
+            timezone: None,
             name: "Inactive Job".to_string(),
             active: false,
             next_run: None,
@@ -434,6 +861,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         // Проверяем что валидация cron работает
@@ -489,6 +925,7 @@ mod tests {
             template_id: 20,
             project_id: 30,
             cron: "0 */2 * * *".to_string(),
+            timezone: None,
             name: "Every 2 hours".to_string(),
             active: true,
             next_run: Some(Utc::now()),
@@ -518,6 +955,15 @@ mod tests {
             created: Some("2026-01-01T00:00:00Z".to_string()),
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         assert_eq!(schedule.cron, "0 9 * * 1-5");
@@ -577,6 +1023,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         // Add
@@ -620,6 +1075,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         // Inactive schedule не должна быть добавлена
@@ -654,6 +1118,15 @@ mod tests {
             created: None,
             run_at: Some("2026-05-01T00:00:00Z".to_string()),
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         // run_at с пустым cron должна быть пропущена
@@ -688,6 +1161,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         pool.add_schedule(schedule).await.unwrap();
@@ -706,6 +1188,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         let result = pool.update_schedule(updated).await;
@@ -741,6 +1232,15 @@ mod tests {
             created: None,
             run_at: None,
             delete_after_run: false,
+            target_type: "template".to_string(),
+            target_id: None,
+            timezone: None,
+            misfire_policy: "skip".to_string(),
+            jitter_secs: 0,
+            blackout_windows: Vec::new(),
+            last_fired: None,
+            git_branch: None,
+            commit_paths: Vec::new(),
         };
 
         pool.add_schedule(schedule).await.unwrap();
@@ -760,6 +1260,7 @@ mod tests {
             template_id: 2,
             project_id: 3,
             cron: "0 * * * *".to_string(),
+            timezone: None,
             name: "Clone Test".to_string(),
             active: true,
             next_run: Some(Utc::now()),
@@ -830,6 +1331,7 @@ mod tests {
             template_id: 1,
             project_id: 1,
             cron: "0 * * * *".to_string(),
+            timezone: None,
             name: "Inactive".to_string(),
             active: false,
             next_run: Some(Utc::now()),
@@ -845,6 +1347,7 @@ mod tests {
             template_id: 10,
             project_id: 5,
             cron: "0 0 * * * *".to_string(),
+            timezone: None,
             name: "Debug Test".to_string(),
             active: true,
             next_run: Some(Utc::now()),
@@ -916,6 +1419,7 @@ mod tests {
             template_id: 200,
             project_id: 300,
             cron: "0 0 1 1 *".to_string(),
+            timezone: None,
             name: "New Year Job".to_string(),
             active: true,
             next_run: Some(now),
@@ -1030,6 +1534,7 @@ mod tests {
             template_id: 10,
             project_id: 15,
             cron: "invalid".to_string(),
+            timezone: None,
             name: "Broken Job".to_string(),
             active: true,
             next_run: None,
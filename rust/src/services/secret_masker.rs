@@ -0,0 +1,189 @@
+//! Маскирование секретов в выводе задач
+//!
+//! Логгер задачи регистрирует каждое секретное значение, попадающее в задание
+//! (ключи доступа, секреты окружения, пароли vault, секретные переменные,
+//! извлечённые интеграцией значения), и заменяет его на [`MASK`] во всём
+//! выводе — в БД, в stdout сервера и в live-потоках.
+//!
+//! Помимо самого значения маскируются его производные формы: отдельные строки
+//! многострочного секрета (вывод приходит построчно), JSON-экранированная
+//! форма и base64-кодирование, в том числе перенесённое по 76 символов.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use std::sync::RwLock;
+
+/// Замена секрета в выводе
+pub const MASK: &str = "********";
+
+/// Минимальная длина маскируемого значения: короткие строки вроде `yes`
+/// или `1` встречаются в выводе повсюду и не являются секретами
+const MIN_SECRET_LEN: usize = 4;
+
+/// Длина строки base64 при переносе (`base64`, MIME)
+const BASE64_LINE_LEN: usize = 76;
+
+/// Реестр секретов задачи
+#[derive(Debug, Default)]
+pub struct SecretMasker {
+    /// Маскируемые строки, от длинных к коротким
+    secrets: RwLock<Vec<String>>,
+}
+
+impl SecretMasker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Регистрирует секрет вместе с его производными формами
+    pub fn add(&self, secret: &str) {
+        let forms = secret_forms(secret);
+        if forms.is_empty() {
+            return;
+        }
+        let Ok(mut secrets) = self.secrets.write() else {
+            return;
+        };
+        for form in forms {
+            if !secrets.contains(&form) {
+                secrets.push(form);
+            }
+        }
+        // Длинные формы заменяются первыми, чтобы их части не оставались открытыми
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    }
+
+    /// Нет ни одного зарегистрированного секрета
+    pub fn is_empty(&self) -> bool {
+        self.secrets.read().map(|s| s.is_empty()).unwrap_or(true)
+    }
+
+    /// Заменяет зарегистрированные секреты в тексте на [`MASK`]
+    pub fn mask(&self, text: &str) -> String {
+        let Ok(secrets) = self.secrets.read() else {
+            return text.to_string();
+        };
+        let mut masked = text.to_string();
+        for secret in secrets.iter() {
+            if masked.contains(secret.as_str()) {
+                masked = masked.replace(secret.as_str(), MASK);
+            }
+        }
+        masked
+    }
+}
+
+/// Формы, в которых секрет может появиться в выводе
+fn secret_forms(secret: &str) -> Vec<String> {
+    let value = secret.trim();
+    if value.len() < MIN_SECRET_LEN {
+        return Vec::new();
+    }
+
+    let mut forms = vec![value.to_string()];
+    if value.contains('\n') {
+        forms.push(value.replace("\r\n", "\\n").replace('\n', "\\n"));
+        forms.extend(
+            value
+                .lines()
+                .map(str::trim)
+                .filter(|line| line.len() >= MIN_SECRET_LEN)
+                .map(str::to_string),
+        );
+    }
+
+    // Исходное значение и вывод `echo secret | base64` (с переводом строки).
+    // Без паддинга: закодированное значение остаётся префиксом и внутри `==`
+    for raw in [value.to_string(), secret.to_string(), format!("{value}\n")] {
+        let encoded = STANDARD_NO_PAD.encode(raw);
+        if encoded.len() > BASE64_LINE_LEN {
+            forms.extend(
+                encoded
+                    .as_bytes()
+                    .chunks(BASE64_LINE_LEN)
+                    .filter(|chunk| chunk.len() >= MIN_SECRET_LEN)
+                    .map(|chunk| String::from_utf8_lossy(chunk).into_owned()),
+            );
+        }
+        forms.push(encoded);
+    }
+
+    let mut unique = Vec::with_capacity(forms.len());
+    for form in forms {
+        if !unique.contains(&form) {
+            unique.push(form);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+
+    #[test]
+    fn test_mask_plain_secret() {
+        let masker = SecretMasker::new();
+        masker.add("s3cr3t-token");
+        assert_eq!(
+            masker.mask("curl -H 'Authorization: s3cr3t-token'"),
+            "curl -H 'Authorization: ********'"
+        );
+        assert_eq!(masker.mask("nothing here"), "nothing here");
+    }
+
+    #[test]
+    fn test_short_values_are_ignored() {
+        let masker = SecretMasker::new();
+        masker.add("yes");
+        masker.add("  \n");
+        assert!(masker.is_empty());
+        assert_eq!(masker.mask("changed: yes"), "changed: yes");
+    }
+
+    #[test]
+    fn test_mask_multiline_secret_line_by_line() {
+        let key = "-----BEGIN KEY-----\nMIIEpAIBAAKCAQEA\nq83Jd0sl\n-----END KEY-----\n";
+        let masker = SecretMasker::new();
+        masker.add(key);
+
+        assert_eq!(masker.mask("MIIEpAIBAAKCAQEA"), MASK);
+        assert_eq!(masker.mask("  q83Jd0sl"), format!("  {MASK}"));
+        let escaped =
+            r#"{"key": "-----BEGIN KEY-----\nMIIEpAIBAAKCAQEA\nq83Jd0sl\n-----END KEY-----"}"#;
+        assert_eq!(masker.mask(escaped), format!(r#"{{"key": "{MASK}"}}"#));
+    }
+
+    #[test]
+    fn test_mask_base64_forms() {
+        let masker = SecretMasker::new();
+        masker.add("hunter2-password");
+
+        let padded = STANDARD.encode("hunter2-password");
+        assert!(!masker.mask(&padded).contains("aHVudGVy"));
+        let with_newline = STANDARD.encode("hunter2-password\n");
+        assert!(!masker.mask(&with_newline).contains("aHVudGVy"));
+    }
+
+    #[test]
+    fn test_mask_wrapped_base64() {
+        let secret = "x".repeat(120);
+        let masker = SecretMasker::new();
+        masker.add(&secret);
+
+        let encoded = STANDARD.encode(&secret);
+        for line in encoded.as_bytes().chunks(BASE64_LINE_LEN) {
+            let line = std::str::from_utf8(line).unwrap();
+            assert!(!masker.mask(line).contains("eHh4"), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_longer_secret_masked_first() {
+        let masker = SecretMasker::new();
+        masker.add("pass");
+        masker.add("password123");
+        assert_eq!(masker.mask("password123"), MASK);
+    }
+}
@@ -1,38 +1,371 @@
 //! Сервис выполнения задач
 //!
-//! Предоставляет единую точку запуска задач, используемую как HTTP-хендлером,
-//! так и планировщиком (scheduler).
+//! Предоставляет единую точку запуска задач: HTTP-хендлеры, workflow,
+//! планировщик и интеграции ставят задачу в очередь (`waiting`), пул задач
+//! забирает её вместе со слотами групп параллелизма и выполняет.
 
 use chrono::Utc;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Notify;
 use tracing::{error, info};
 
 use crate::db::store::{PlanApprovalManager, Store};
-use crate::db_lib::AccessKeyInstallerImpl;
-use crate::models::{Environment, Inventory, Repository, Task, TaskOutput, TerraformPlan};
+use crate::db_lib::{AccessKeyInstallerImpl, PlanReview, PlanStage, SavedPlan};
+use crate::models::template::{TemplateApp, TemplateType};
+use crate::models::{
+    Environment, Inventory, Repository, Task, TaskClaimer, TaskHook, TaskOutput, TerraformPlan,
+};
+use crate::plugins::{self, HookType};
+use crate::services::concurrency::{self, BlockedBy, Claim};
+use crate::services::drift::is_drift_check;
 use crate::services::local_job::LocalJob;
+use crate::services::local_job::timeout::{TERMINATION_GRACE, TERMINATION_WAIT, task_timeout};
+use crate::services::promotion::promote_build;
 use crate::services::task_logger::{BasicLogger, LogListener, TaskLogger, TaskStatus};
+use crate::services::task_reaper;
+use crate::services::template_inheritance::{EffectiveTemplate, resolve_template};
+
+/// Итог попытки запустить задачу из очереди на этом узле
+pub enum Dispatch {
+    /// Задача забрана и выполняется в фоне
+    Started(tokio::task::JoinHandle<()>),
+    /// Группа параллелизма заполнена — задача остаётся в очереди
+    Blocked(BlockedBy),
+    /// Задачу уже забрал другой узел или раннер
+    Taken,
+}
+
+/// Запускает ожидающую задачу на этом узле.
+///
+/// Слоты групп параллелизма занимаются вместе с захватом задачи одной
+/// операцией в БД (см. `services::concurrency`), затем задача выполняется в
+/// фоне: сначала pre-хук (его ошибка прерывает задачу), затем сама задача,
+/// затем post-хук при успехе или fail-хук при ошибке. Задачи-хуки связываются
+/// с родительской задачей. Завершение задачи обрабатывает [`on_task_finished`].
+///
+/// Вызывается только пулом задач (`JobPool`); остальные источники ставят
+/// задачу в очередь и будят пул (`wake_dispatcher`).
+pub async fn dispatch_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) -> Dispatch {
+    let claimer = TaskClaimer::Node(task_reaper::node_id().to_string());
+    match concurrency::claim_with_slots(store.as_ref(), &task, claimer).await {
+        Ok(Claim::Claimed) => {}
+        Ok(Claim::Blocked(blocked)) => return Dispatch::Blocked(blocked),
+        Ok(Claim::Taken) => return Dispatch::Taken,
+        Err(e) => {
+            error!("[task_runner] task {} failed to claim: {e}", task.id);
+            return Dispatch::Taken;
+        }
+    }
+    info!("[task_runner] task {} status → Running", task.id);
+    task.status = TaskStatus::Running;
+    task.start = Some(Utc::now());
+
+    Dispatch::Started(tokio::spawn(async move {
+        run_with_hooks(store, task).await;
+        // Слоты задачи освободились вместе со сменой её статуса
+        wake_dispatcher();
+    }))
+}
+
+/// Сохраняет итог задачи, выполненной этим узлом или удалённым раннером
+///
+/// Именованные outputs из лога задачи сохраняются до итогового статуса, чтобы
+/// они были доступны, как только задача завершена. Затем освободившиеся слоты
+/// отдаются очереди, а успешная задача build-шаблона продвигается в autorun
+/// deploy-шаблоны.
+pub async fn on_task_finished(
+    store: Arc<dyn Store + Send + Sync>,
+    task: &Task,
+) -> crate::error::Result<()> {
+    save_structured_outputs(store.as_ref(), task).await;
+    store.update_task(task.clone()).await?;
+    wake_dispatcher();
+    if let Err(e) = promote_build(store, task).await {
+        error!(
+            "[task_runner] task {}: build promotion failed: {e}",
+            task.id
+        );
+    }
+    Ok(())
+}
+
+/// Сигнал пулу задач: в очереди новая задача или освободился слот
+fn dispatch_signal() -> &'static Notify {
+    static SIGNAL: OnceLock<Notify> = OnceLock::new();
+    SIGNAL.get_or_init(Notify::new)
+}
+
+/// Будит пул задач, не дожидаясь очередного опроса очереди
+pub fn wake_dispatcher() {
+    dispatch_signal().notify_one();
+}
+
+/// Ждёт, пока пул задач разбудят
+pub(crate) async fn dispatcher_woken() {
+    dispatch_signal().notified().await;
+}
+
+/// Ставит созданную задачу в очередь и ждёт её итога
+///
+/// Задачу забирает пул задач этого или другого узла либо удалённый раннер.
+/// Возвращает итоговый статус или `waiting_confirmation`, если план ждёт ревью.
+pub async fn execute_task(store: Arc<dyn Store + Send + Sync>, task: Task) -> TaskStatus {
+    wake_dispatcher();
+    wait_status(store.as_ref(), &task, |status| {
+        status.is_finished() || *status == TaskStatus::WaitingConfirmation
+    })
+    .await
+}
+
+/// Выполняет забранную задачу вместе с хуками шаблона
+///
+/// Проверка дрейфа выполняется без хуков.
+async fn run_with_hooks(store: Arc<dyn Store + Send + Sync>, task: Task) {
+    let template = match store.get_template(task.project_id, task.template_id).await {
+        Ok(t) if has_hooks(&t) && !is_drift_check(&task) => t,
+        _ => {
+            run_claimed_task(store, task).await;
+            return;
+        }
+    };
+
+    if let Some(pre_template_id) = template.pre_template_id {
+        if !pre_hook_succeeded(store.as_ref(), &task).await {
+            let status = run_hook(&store, &task, pre_template_id, HOOK_PRE).await;
+            if status != TaskStatus::Success {
+                error!(
+                    "[task_runner] task {}: pre-hook failed ({status}), aborting",
+                    task.id
+                );
+                let mut task = task;
+                task.status = TaskStatus::Error;
+                task.end = Some(Utc::now());
+                task.message = Some(format!("Pre-hook template {} failed", pre_template_id));
+                let _ = store.update_task(task.clone()).await;
+                if let Some(fail_template_id) = template.fail_template_id {
+                    run_hook(&store, &task, fail_template_id, HOOK_FAIL).await;
+                }
+                return;
+            }
+        }
+    }
+
+    run_claimed_task(store.clone(), task.clone()).await;
+
+    let finished = store
+        .get_task(task.project_id, task.id)
+        .await
+        .unwrap_or(task);
+    let next_hook = match finished.status {
+        TaskStatus::Success => template.post_template_id.map(|id| (id, HOOK_POST)),
+        TaskStatus::Error => template.fail_template_id.map(|id| (id, HOOK_FAIL)),
+        _ => None,
+    };
+    if let Some((hook_template_id, hook_type)) = next_hook {
+        run_hook(&store, &finished, hook_template_id, hook_type).await;
+    }
+}
+
+/// Тип хука: перед задачей
+pub const HOOK_PRE: &str = "pre";
+/// Тип хука: после успешной задачи
+pub const HOOK_POST: &str = "post";
+/// Тип хука: после ошибки задачи
+pub const HOOK_FAIL: &str = "fail";
+
+pub(crate) fn has_hooks(template: &crate::models::Template) -> bool {
+    template.pre_template_id.is_some()
+        || template.post_template_id.is_some()
+        || template.fail_template_id.is_some()
+}
+
+/// Шаблоны-хуки шаблона: pre, post и fail
+fn hook_template_ids(template: &crate::models::Template) -> impl Iterator<Item = i32> {
+    [
+        template.pre_template_id,
+        template.post_template_id,
+        template.fail_template_id,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Проверяет хуки сохраняемого шаблона
+///
+/// Шаблон-хук должен существовать в проекте, а цепочка хуков не должна
+/// возвращаться к самому шаблону. Возвращает `Error::Validation`.
+pub async fn validate_template_hooks(
+    store: &dyn Store,
+    template: &crate::models::Template,
+) -> crate::error::Result<()> {
+    use crate::error::Error;
+
+    for id in hook_template_ids(template) {
+        if id == template.id {
+            return Err(Error::Validation(format!(
+                "Template {id} cannot be its own hook"
+            )));
+        }
+        match store.get_template(template.project_id, id).await {
+            Ok(_) => {}
+            Err(Error::NotFound(_)) => {
+                return Err(Error::Validation(format!("Hook template {id} not found")));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut next: Vec<i32> = hook_template_ids(template).collect();
+    while let Some(id) = next.pop() {
+        if id == template.id {
+            return Err(Error::Validation(format!(
+                "Hook templates of template {} form a cycle",
+                template.id
+            )));
+        }
+        if !seen.insert(id) {
+            continue;
+        }
+        if let Ok(hook) = store.get_template(template.project_id, id).await {
+            next.extend(hook_template_ids(&hook));
+        }
+    }
+    Ok(())
+}
+
+/// Pre-хук уже успешно выполнялся (задача возобновлена после подтверждения плана)
+async fn pre_hook_succeeded(store: &(dyn Store + Send + Sync), task: &Task) -> bool {
+    let links = store
+        .get_task_hook_links(task.project_id, task.id)
+        .await
+        .unwrap_or_default();
+    for link in links
+        .iter()
+        .filter(|l| l.parent_task_id == task.id && l.hook_type == HOOK_PRE)
+    {
+        if let Ok(hook) = store.get_task(task.project_id, link.task_id).await {
+            if hook.status == TaskStatus::Success {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Создаёт задачу-хук для родительской задачи, выполняет её и возвращает итоговый статус
+///
+/// Хуки наследуют ветку, коммит и extra-vars родителя; собственные хуки
+/// шаблона-хука не запускаются. Задача-хук не попадает в очередь и не
+/// выдаётся раннерам: её забирает этот узел вместе со слотами групп
+/// параллелизма шаблона-хука, при заполненной группе хук ждёт слот.
+async fn run_hook(
+    store: &Arc<dyn Store + Send + Sync>,
+    parent: &Task,
+    hook_template_id: i32,
+    hook_type: &str,
+) -> TaskStatus {
+    let hook_task = Task {
+        environment: parent.environment.clone(),
+        git_branch: parent.git_branch.clone(),
+        user_id: parent.user_id,
+        message: Some(format!("{} hook of task #{}", hook_type, parent.id)),
+        commit_hash: parent.commit_hash.clone(),
+        commit_message: parent.commit_message.clone(),
+        ..Task::new_waiting(hook_template_id, parent.project_id)
+    };
+
+    let mut hook_task = match store
+        .create_hook_task(hook_task, parent.id, hook_type)
+        .await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            error!(
+                "[task_runner] task {}: failed to create {hook_type} hook: {e}",
+                parent.id
+            );
+            return TaskStatus::Error;
+        }
+    };
+
+    info!(
+        "[task_runner] task {}: running {hook_type} hook task {} (template {})",
+        parent.id, hook_task.id, hook_template_id
+    );
+    loop {
+        let claimer = TaskClaimer::Node(task_reaper::node_id().to_string());
+        match concurrency::claim_with_slots(store.as_ref(), &hook_task, claimer).await {
+            Ok(Claim::Claimed) => break,
+            Ok(Claim::Blocked(blocked)) => {
+                info!(
+                    "[task_runner] task {}: {hook_type} hook task {} {blocked}",
+                    parent.id, hook_task.id
+                );
+                tokio::time::sleep(FINISH_POLL_INTERVAL).await;
+            }
+            // Задачу-хук остановили, пока она ждала слот
+            Ok(Claim::Taken) => return wait_finished(store.as_ref(), &hook_task).await,
+            Err(e) => {
+                error!(
+                    "[task_runner] task {}: failed to claim {hook_type} hook {}: {e}",
+                    parent.id, hook_task.id
+                );
+                let _ = store
+                    .update_task_status(hook_task.project_id, hook_task.id, TaskStatus::Error)
+                    .await;
+                return TaskStatus::Error;
+            }
+        }
+    }
+    hook_task.status = TaskStatus::Running;
+    hook_task.start = Some(Utc::now());
+    run_claimed_task(store.clone(), hook_task.clone()).await;
+
+    store
+        .get_task(hook_task.project_id, hook_task.id)
+        .await
+        .map(|t| t.status)
+        .unwrap_or(TaskStatus::Error)
+}
+
+/// Интервал опроса задачи, которую выполняет другой обработчик
+const FINISH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Ждёт, пока задача, выполняемая другим обработчиком, завершится
+pub(crate) async fn wait_finished(store: &(dyn Store + Send + Sync), task: &Task) -> TaskStatus {
+    wait_status(store, task, TaskStatus::is_finished).await
+}
 
-/// Запускает задачу в фоновом потоке.
+/// Опрашивает задачу, пока её статус не удовлетворит `done`
+async fn wait_status(
+    store: &(dyn Store + Send + Sync),
+    task: &Task,
+    done: impl Fn(&TaskStatus) -> bool,
+) -> TaskStatus {
+    loop {
+        match store.get_task(task.project_id, task.id).await {
+            Ok(current) if done(&current.status) => return current.status,
+            Ok(_) => tokio::time::sleep(FINISH_POLL_INTERVAL).await,
+            Err(_) => return TaskStatus::Error,
+        }
+    }
+}
+
+/// Выполняет забранную этим узлом задачу без хуков.
 ///
 /// Загружает шаблон, инвентарь, репозиторий и окружение, запускает LocalJob,
 /// сохраняет вывод в БД и обновляет статус задачи.
-pub async fn execute_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) {
+async fn run_claimed_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) {
     info!(
         "[task_runner] Starting task {} (template {})",
         task.id, task.template_id
     );
+    crate::services::event_outbox::record_task_event(store.as_ref(), &task).await;
 
-    // Обновляем статус → Running и фиксируем время начала
-    task.status = TaskStatus::Running;
-    task.start = Some(Utc::now());
-    match store.update_task(task.clone()).await {
-        Ok(()) => info!("[task_runner] task {} status → Running", task.id),
-        Err(e) => error!("[task_runner] task {} failed to set Running: {e}", task.id),
-    }
-
-    // Загружаем шаблон
-    let template = match store.get_template(task.project_id, task.template_id).await {
+    // Загружаем шаблон с учётом наследования (parent_template_id)
+    let effective = match resolve_template(store.as_ref(), task.project_id, task.template_id).await
+    {
         Ok(t) => t,
         Err(e) => {
             error!(
@@ -41,27 +374,52 @@ pub async fn execute_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) {
             );
             task.status = TaskStatus::Error;
             task.end = Some(Utc::now());
+            task.message = Some(e.to_string());
             let _ = store.update_task(task).await;
             return;
         }
     };
-
-    // Phase 2: Plan Approval gate — if template requires approval, pause before executing
-    if template.require_approval {
+    let EffectiveTemplate {
+        template,
+        environment_vars,
+        ..
+    } = effective;
+
+    // Phase 2: Plan Approval gate — Terraform-задача сначала строит план
+    // (`plan -out`) и ждёт ревью, после одобрения применяется ровно этот план.
+    // Проверка дрейфа только строит план и подтверждения не ждёт.
+    let mut plan_stage = None;
+    if template.require_approval && !is_drift_check(&task) {
         // Check if there's already an approved plan for this task
         let existing_plan = store
             .get_plan_by_task(task.project_id, task.id)
             .await
             .unwrap_or(None);
         match existing_plan {
-            Some(ref plan) if plan.status == "approved" => {
-                // Plan was approved — proceed with execution
+            Some(plan) if plan.status == "approved" => {
                 info!(
                     "[task_runner] task {}: plan approved, proceeding with execution",
                     task.id
                 );
+                if is_terraform_app(&template) {
+                    let Some(saved) = plan.plan_file else {
+                        error!(
+                            "[task_runner] task {}: approved plan has no saved plan file",
+                            task.id
+                        );
+                        task.status = TaskStatus::Error;
+                        task.end = Some(Utc::now());
+                        task.message = Some("Approved plan has no saved plan file".to_string());
+                        let _ = store.update_task(task).await;
+                        return;
+                    };
+                    plan_stage = Some(PlanStage::Apply(SavedPlan {
+                        plan: saved,
+                        state_serial: plan.state_serial,
+                    }));
+                }
             }
-            Some(ref plan) if plan.status == "rejected" => {
+            Some(plan) if plan.status == "rejected" => {
                 // Plan was rejected — stop task
                 info!(
                     "[task_runner] task {}: plan rejected, stopping task",
@@ -72,28 +430,22 @@ pub async fn execute_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) {
                     .await;
                 return;
             }
+            None if is_terraform_app(&template) => {
+                info!(
+                    "[task_runner] task {}: require_approval=true, building plan for review",
+                    task.id
+                );
+                plan_stage = Some(PlanStage::Review);
+            }
             None => {
-                // No plan yet — create pending record and set WaitingConfirmation
+                // Не Terraform — плана нет, создаём pending-запись и ждём подтверждения
                 info!(
                     "[task_runner] task {}: require_approval=true, creating pending plan",
                     task.id
                 );
-                let pending_plan = TerraformPlan {
-                    id: 0,
-                    task_id: task.id,
-                    project_id: task.project_id,
-                    plan_output: String::new(),
-                    plan_json: None,
-                    resources_added: 0,
-                    resources_changed: 0,
-                    resources_removed: 0,
-                    status: "pending".to_string(),
-                    created_at: chrono::Utc::now(),
-                    reviewed_at: None,
-                    reviewed_by: None,
-                    review_comment: None,
-                };
-                let _ = store.create_plan(pending_plan).await;
+                let _ = store
+                    .create_plan(pending_plan(&task, &PlanReview::default()))
+                    .await;
                 let _ = store
                     .update_task_status(task.project_id, task.id, TaskStatus::WaitingConfirmation)
                     .await;
@@ -110,33 +462,12 @@ pub async fn execute_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) {
         }
     }
 
-    // Загружаем инвентарь, репозиторий, окружение
-    let inventory_id = task.inventory_id.or(template.inventory_id);
-    let inventory = match inventory_id {
-        Some(id) => store
-            .get_inventory(task.project_id, id)
-            .await
-            .unwrap_or_default(),
-        None => Inventory::default(),
-    };
-
-    let repository_id = task.repository_id.or(template.repository_id);
-    let repository = match repository_id {
-        Some(id) => store
-            .get_repository(task.project_id, id)
-            .await
-            .unwrap_or_default(),
-        None => Repository::default(),
-    };
-
-    let environment_id = task.environment_id.or(template.environment_id);
-    let environment = match environment_id {
-        Some(id) => store
-            .get_environment(task.project_id, id)
-            .await
-            .unwrap_or_default(),
-        None => Environment::default(),
-    };
+    let JobResources {
+        inventory,
+        repository,
+        environment,
+        incoming_version,
+    } = load_job_resources(store.as_ref(), &task, &template, environment_vars).await;
 
     // Логгер с буфером для сохранения в БД
     let log_buffer: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
@@ -175,38 +506,88 @@ pub async fn execute_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) {
     );
 
     job.store = Some(store.clone());
-    let result = job.run("runner", None, "default").await;
+    job.plan_stage = plan_stage.clone();
+    job.timeout = task_timeout(&template);
+    plugins::emit_task(HookType::TaskBeforeStart, &task);
+    plugins::emit_template(HookType::TemplateBeforeRun, &template, Some(&task));
+    let (result, stop_requested) =
+        run_until_stopped(store.as_ref(), &task, &mut job, incoming_version.as_deref()).await;
+    let stopped = stop_requested || job.is_killed();
+    let timed_out = job.is_timed_out();
+    let plan_review = job.plan_review.take();
     job.cleanup();
 
     // Сохраняем логи в БД
     let log_lines: Vec<String> = log_buffer.lock().map(|v| v.clone()).unwrap_or_default();
-    for line in log_lines {
+    for line in &log_lines {
         let output = TaskOutput {
             id: 0,
             task_id: task.id,
             project_id: task.project_id,
             time: chrono::Utc::now(),
-            output: line,
+            output: line.clone(),
             stage_id: None,
         };
         let _ = store.create_task_output(output).await;
     }
 
+    // План с изменениями сохраняется на ревью, задача ждёт подтверждения
+    if let (Ok(()), Some(review)) = (&result, plan_review.filter(|r| r.has_changes)) {
+        let mut plan = pending_plan(&task, &review);
+        plan.plan_output = log_lines.join("\n");
+        match store.create_plan(plan).await {
+            Ok(_) => {
+                info!(
+                    "[task_runner] task {}: plan saved, waiting for approval",
+                    task.id
+                );
+                task.status = TaskStatus::WaitingConfirmation;
+            }
+            Err(e) => {
+                error!("[task_runner] task {}: failed to save plan: {e}", task.id);
+                task.status = TaskStatus::Error;
+                task.end = Some(Utc::now());
+            }
+        }
+        let _ = store.update_task(task).await;
+        return;
+    }
+
     task.end = Some(Utc::now());
     match result {
         Ok(()) => {
             info!("[task_runner] task {} completed successfully", task.id);
             task.status = TaskStatus::Success;
         }
+        Err(e) if timed_out => {
+            error!("[task_runner] task {}: {e}", task.id);
+            task.status = TaskStatus::Error;
+            task.message = Some(e.to_string());
+        }
+        Err(_) if stopped => {
+            info!("[task_runner] task {} stopped", task.id);
+            task.status = TaskStatus::Stopped;
+        }
         Err(e) => {
             error!("[task_runner] task {} failed: {e}", task.id);
             task.status = TaskStatus::Error;
+            if matches!(plan_stage, Some(PlanStage::Apply(_))) {
+                task.message = Some(e.to_string());
+            }
         }
     }
-    match store.update_task(task.clone()).await {
+    match on_task_finished(store.clone(), &task).await {
         Ok(()) => {
+            let hook = match task.status {
+                TaskStatus::Success => HookType::TaskAfterComplete,
+                TaskStatus::Stopped => HookType::TaskAfterStop,
+                _ => HookType::TaskAfterFail,
+            };
+            plugins::emit_task(hook, &task);
+            plugins::emit_template(HookType::TemplateAfterRun, &template, Some(&task));
             crate::services::telegram_bot::notify_on_task_finished(store.clone(), &task, &template)
                 .await;
+            crate::services::event_outbox::record_task_event(store.as_ref(), &task).await;
         }
         Err(e) => error!(
             "[task_runner] task {} failed to persist final status: {e}",
@@ -215,6 +596,193 @@ pub async fn execute_task(store: Arc<dyn Store + Send + Sync>, mut task: Task) {
     }
 }
 
+/// Интервал опроса запроса на остановку задачи
+const STOP_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Запрошена ли остановка задачи (статус Stopping или Stopped)
+async fn stop_requested(store: &(dyn Store + Send + Sync), task: &Task) -> bool {
+    matches!(
+        store.get_task(task.project_id, task.id).await,
+        Ok(current) if matches!(current.status, TaskStatus::Stopping | TaskStatus::Stopped)
+    )
+}
+
+/// Выполняет задачу, пока не запрошена её остановка (`POST .../tasks/{id}/stop`
+/// или отмена запуска workflow)
+///
+/// После запроса процессы задачи получают SIGTERM, затем SIGKILL. Второй
+/// элемент результата — была ли задача остановлена.
+async fn run_until_stopped(
+    store: &(dyn Store + Send + Sync),
+    task: &Task,
+    job: &mut LocalJob,
+    incoming_version: Option<&str>,
+) -> (crate::error::Result<()>, bool) {
+    let groups = job.process_groups.clone();
+    let run = job.run("runner", incoming_version, "default");
+    tokio::pin!(run);
+    let stop = async {
+        loop {
+            tokio::time::sleep(STOP_POLL_INTERVAL).await;
+            if stop_requested(store, task).await {
+                return;
+            }
+        }
+    };
+    tokio::select! {
+        result = &mut run => {
+            // Контейнерный запуск останавливается сам и завершается ошибкой
+            let stopped = result.is_err() && stop_requested(store, task).await;
+            (result, stopped)
+        }
+        () = stop => {
+            // Этапы опрашиваются и дальше: процессы дожидаются, вывод дочитывается
+            let _ = tokio::time::timeout(TERMINATION_GRACE + TERMINATION_WAIT, async {
+                tokio::join!(&mut run, groups.terminate_all(TERMINATION_GRACE))
+            })
+            .await;
+            (Err(crate::error::Error::Other("Task stopped".to_string())), true)
+        }
+    }
+}
+
+/// Сохраняет именованные outputs задачи из строк `VELUM_OUTPUT: {...}` её лога
+async fn save_structured_outputs(store: &(dyn Store + Send + Sync), task: &Task) {
+    use crate::db::store::StructuredOutputManager;
+    use crate::models::TaskStructuredOutputCreate;
+
+    let log = match store.get_task_outputs(task.id).await {
+        Ok(log) => log,
+        Err(e) => {
+            error!("[task_runner] task {}: failed to read log: {e}", task.id);
+            return;
+        }
+    };
+    let outputs: Vec<_> = log
+        .iter()
+        .flat_map(|line| TaskStructuredOutputCreate::parse_line(&line.output))
+        .collect();
+    if outputs.is_empty() {
+        return;
+    }
+    if let Err(e) = store
+        .create_task_structured_outputs_batch(task.id, task.project_id, outputs)
+        .await
+    {
+        error!(
+            "[task_runner] task {}: failed to save outputs: {e}",
+            task.id
+        );
+    }
+}
+
+/// Ресурсы задачи, необходимые для запуска `LocalJob`
+pub(crate) struct JobResources {
+    pub inventory: Inventory,
+    pub repository: Repository,
+    pub environment: Environment,
+    pub incoming_version: Option<String>,
+}
+
+/// Загружает инвентарь, репозиторий, окружение и входящую версию задачи.
+///
+/// Используется как локальным исполнением, так и сборкой задания для удалённого раннера.
+pub(crate) async fn load_job_resources(
+    store: &(dyn Store + Send + Sync),
+    task: &Task,
+    template: &crate::models::Template,
+    environment_vars: Option<serde_json::Value>,
+) -> JobResources {
+    // Загружаем инвентарь, репозиторий, окружение
+    let inventory_id = task.inventory_id.or(template.inventory_id);
+    let inventory = match inventory_id {
+        Some(id) => store
+            .get_inventory(task.project_id, id)
+            .await
+            .unwrap_or_default(),
+        None => Inventory::default(),
+    };
+
+    let repository_id = task.repository_id.or(template.repository_id);
+    let repository = match repository_id {
+        Some(id) => store
+            .get_repository(task.project_id, id)
+            .await
+            .unwrap_or_default(),
+        None => Repository::default(),
+    };
+
+    // Окружение задачи перекрывает унаследованное; иначе берём ближайшее окружение
+    // цепочки шаблонов со слитыми extra vars всех предков
+    let environment = match (task.environment_id, template.environment_id) {
+        (Some(id), _) => store
+            .get_environment(task.project_id, id)
+            .await
+            .unwrap_or_default(),
+        (None, Some(id)) => {
+            let mut environment = store
+                .get_environment(task.project_id, id)
+                .await
+                .unwrap_or_default();
+            if let Some(vars) = environment_vars {
+                environment.json = vars.to_string();
+            }
+            environment
+        }
+        (None, None) => Environment::default(),
+    };
+
+    // Deploy-задача получает версию развёртываемой сборки как incoming_version
+    let incoming_version = match template.r#type {
+        TemplateType::Deploy => match (task.version.clone(), task.build_task_id) {
+            (Some(version), _) => Some(version),
+            (None, Some(build_task_id)) => store
+                .get_task(task.project_id, build_task_id)
+                .await
+                .ok()
+                .and_then(|build| build.version),
+            (None, None) => None,
+        },
+        _ => None,
+    };
+
+    JobResources {
+        inventory,
+        repository,
+        environment,
+        incoming_version,
+    }
+}
+
+/// Шаблон выполняется Terraform / OpenTofu / Terragrunt
+fn is_terraform_app(template: &crate::models::Template) -> bool {
+    matches!(
+        template.app,
+        TemplateApp::Terraform | TemplateApp::Tofu | TemplateApp::Terragrunt
+    )
+}
+
+/// Pending-план задачи, ожидающий ревью
+fn pending_plan(task: &Task, review: &PlanReview) -> TerraformPlan {
+    TerraformPlan {
+        id: 0,
+        task_id: task.id,
+        project_id: task.project_id,
+        plan_output: String::new(),
+        plan_json: review.plan_json.clone(),
+        resources_added: review.resources_added,
+        resources_changed: review.resources_changed,
+        resources_removed: review.resources_removed,
+        status: "pending".to_string(),
+        created_at: Utc::now(),
+        reviewed_at: None,
+        reviewed_by: None,
+        review_comment: None,
+        plan_file: review.has_changes.then(|| review.saved.plan.clone()),
+        state_serial: review.saved.state_serial,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -232,14 +800,22 @@ mod tests {
         }
     }
 
+    /// Запускает задачу на этом узле и ждёт её выполнения
+    async fn run_dispatched(store: Arc<dyn crate::db::store::Store + Send + Sync>, task: Task) {
+        match dispatch_task(store, task).await {
+            Dispatch::Started(handle) => handle.await.unwrap(),
+            _ => panic!("task was not dispatched"),
+        }
+    }
+
     #[tokio::test]
-    async fn execute_task_sets_error_when_template_missing() {
+    async fn dispatch_task_sets_error_when_template_missing() {
         let ms = Arc::new(MockStore::new());
         let store: Arc<dyn crate::db::store::Store + Send + Sync> = ms.clone();
         let task = sample_task();
         store.create_task(task.clone()).await.unwrap();
 
-        execute_task(store.clone(), task).await;
+        run_dispatched(store.clone(), task).await;
 
         let saved = store.get_task(10, 1).await.unwrap();
         assert_eq!(saved.status, TaskStatus::Error);
@@ -247,7 +823,226 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn execute_task_waits_confirmation_when_plan_approval_required() {
+    async fn dispatch_task_aborts_and_runs_fail_hook_when_pre_hook_fails() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        let tpl = Template {
+            id: 100,
+            project_id: 10,
+            pre_template_id: Some(200),
+            fail_template_id: Some(300),
+            ..Default::default()
+        };
+        store.create_template(tpl).await.unwrap();
+        let task = sample_task();
+        store.create_task(task.clone()).await.unwrap();
+
+        run_dispatched(store.clone(), task).await;
+
+        let saved = store.get_task(10, 1).await.unwrap();
+        assert_eq!(saved.status, TaskStatus::Error);
+        assert!(saved.message.unwrap().contains("Pre-hook"));
+
+        let links = store.get_task_hook_links(10, 1).await.unwrap();
+        let types: Vec<&str> = links.iter().map(|l| l.hook_type.as_str()).collect();
+        assert_eq!(types, vec![HOOK_PRE, HOOK_FAIL]);
+        assert!(links.iter().all(|l| l.parent_task_id == 1));
+
+        let pre = store.get_task(10, links[0].task_id).await.unwrap();
+        assert_eq!(pre.template_id, 200);
+        assert_eq!(pre.status, TaskStatus::Error);
+    }
+
+    fn drift_task() -> Task {
+        Task {
+            params: Some(serde_json::json!({"plan": true, "drift_check": true})),
+            ..sample_task()
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatch_task_skips_hooks_for_drift_check() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        let tpl = Template {
+            id: 100,
+            project_id: 10,
+            pre_template_id: Some(200),
+            fail_template_id: Some(300),
+            ..Default::default()
+        };
+        store.create_template(tpl).await.unwrap();
+        let task = drift_task();
+        store.create_task(task.clone()).await.unwrap();
+
+        run_dispatched(store.clone(), task).await;
+
+        assert!(store.get_task_hook_links(10, 1).await.unwrap().is_empty());
+        assert!(store.get_task(10, 1).await.unwrap().status.is_finished());
+    }
+
+    #[tokio::test]
+    async fn dispatch_task_skips_plan_approval_for_drift_check() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                id: 100,
+                project_id: 10,
+                app: TemplateApp::Terraform,
+                require_approval: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let task = drift_task();
+        store.create_task(task.clone()).await.unwrap();
+
+        run_dispatched(store.clone(), task).await;
+
+        let saved = store.get_task(10, 1).await.unwrap();
+        assert_ne!(saved.status, TaskStatus::WaitingConfirmation);
+        assert!(saved.status.is_finished());
+        assert!(store.get_plan_by_task(10, 1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn pre_hook_succeeded_detects_successful_hook() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        let task = sample_task();
+        store.create_task(task.clone()).await.unwrap();
+        assert!(!pre_hook_succeeded(store.as_ref(), &task).await);
+
+        let hook = Task {
+            id: 2,
+            project_id: 10,
+            template_id: 200,
+            status: TaskStatus::Success,
+            ..Default::default()
+        };
+        store.create_task(hook).await.unwrap();
+        store
+            .link_task_hook(
+                10,
+                TaskHook {
+                    task_id: 2,
+                    parent_task_id: 1,
+                    hook_type: HOOK_PRE.to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert!(pre_hook_succeeded(store.as_ref(), &task).await);
+    }
+
+    #[tokio::test]
+    async fn run_hook_keeps_hook_task_out_of_queue() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        let parent = Task {
+            status: TaskStatus::Running,
+            ..sample_task()
+        };
+        store.create_task(parent.clone()).await.unwrap();
+
+        let status = run_hook(&store, &parent, 200, HOOK_POST).await;
+        assert_eq!(status, TaskStatus::Error);
+
+        let links = store.get_task_hook_links(10, 1).await.unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].hook_type, HOOK_POST);
+        let hook = store.get_task(10, links[0].task_id).await.unwrap();
+        assert_eq!(hook.template_id, 200);
+
+        // Ожидающий хук не виден очереди и раннерам
+        store
+            .update_task_status(10, hook.id, TaskStatus::Waiting)
+            .await
+            .unwrap();
+        assert!(store.get_queued_tasks(None, 10).await.unwrap().is_empty());
+        let claim = concurrency::claim_with_slots(store.as_ref(), &hook, TaskClaimer::Runner(1))
+            .await
+            .unwrap();
+        assert_eq!(claim, Claim::Taken);
+    }
+
+    #[tokio::test]
+    async fn run_hook_waits_for_concurrency_slot() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                id: 200,
+                project_id: 10,
+                allow_parallel_tasks: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        // Другая задача шаблона-хука держит его единственный слот
+        let holder = Task {
+            id: 5,
+            template_id: 200,
+            ..sample_task()
+        };
+        store.create_task(holder.clone()).await.unwrap();
+        let claimer = TaskClaimer::Node("other".to_string());
+        assert_eq!(
+            concurrency::claim_with_slots(store.as_ref(), &holder, claimer)
+                .await
+                .unwrap(),
+            Claim::Claimed
+        );
+        let parent = sample_task();
+        store.create_task(parent.clone()).await.unwrap();
+
+        let hook = tokio::spawn({
+            let store = store.clone();
+            async move { run_hook(&store, &parent, 200, HOOK_POST).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let links = store.get_task_hook_links(10, 1).await.unwrap();
+        let hook_id = links[0].task_id;
+        assert_eq!(
+            store.get_task(10, hook_id).await.unwrap().status,
+            TaskStatus::Waiting
+        );
+
+        store
+            .update_task_status(10, holder.id, TaskStatus::Success)
+            .await
+            .unwrap();
+        assert!(hook.await.unwrap().is_finished());
+    }
+
+    #[tokio::test]
+    async fn dispatch_task_skips_task_claimed_elsewhere() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        let task = Task {
+            status: TaskStatus::Running,
+            ..sample_task()
+        };
+        store.create_task(task.clone()).await.unwrap();
+
+        assert!(matches!(
+            dispatch_task(store.clone(), task).await,
+            Dispatch::Taken
+        ));
+
+        let saved = store.get_task(10, 1).await.unwrap();
+        assert_eq!(saved.status, TaskStatus::Running);
+        assert!(saved.end.is_none());
+    }
+
+    #[tokio::test]
+    async fn execute_task_returns_status_of_task_finished_elsewhere() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        let task = Task {
+            status: TaskStatus::Success,
+            ..sample_task()
+        };
+        store.create_task(task.clone()).await.unwrap();
+
+        assert_eq!(execute_task(store, task).await, TaskStatus::Success);
+    }
+
+    #[tokio::test]
+    async fn dispatch_task_waits_confirmation_when_plan_approval_required() {
         let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
         let mut tpl = Template::default();
         tpl.id = 100;
@@ -258,14 +1053,14 @@ mod tests {
         let task = sample_task();
         store.create_task(task.clone()).await.unwrap();
 
-        execute_task(store.clone(), task).await;
+        run_dispatched(store.clone(), task).await;
 
         let saved = store.get_task(10, 1).await.unwrap();
         assert_eq!(saved.status, TaskStatus::WaitingConfirmation);
     }
 
     #[tokio::test]
-    async fn execute_task_stops_when_plan_rejected() {
+    async fn dispatch_task_stops_when_plan_rejected() {
         let ms = Arc::new(MockStore::new());
         let store: Arc<dyn crate::db::store::Store + Send + Sync> = ms.clone();
         let mut tpl = Template::default();
@@ -288,13 +1083,177 @@ mod tests {
             reviewed_at: None,
             reviewed_by: None,
             review_comment: None,
+            plan_file: None,
+            state_serial: None,
         });
 
         let task = sample_task();
         store.create_task(task.clone()).await.unwrap();
-        execute_task(store, task).await;
+        run_dispatched(store, task).await;
+        let saved = ms.get_task(10, 1).await.unwrap();
+        assert_eq!(saved.status, TaskStatus::Error);
+    }
+
+    #[tokio::test]
+    async fn dispatch_task_fails_when_approved_terraform_plan_has_no_file() {
+        let ms = Arc::new(MockStore::new());
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = ms.clone();
+        store
+            .create_template(Template {
+                id: 100,
+                project_id: 10,
+                app: TemplateApp::Terraform,
+                require_approval: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let task = sample_task();
+        ms.seed_terraform_plan(TerraformPlan {
+            status: "approved".to_string(),
+            ..pending_plan(&task, &PlanReview::default())
+        });
+        store.create_task(task.clone()).await.unwrap();
+        run_dispatched(store, task).await;
+
         let saved = ms.get_task(10, 1).await.unwrap();
         assert_eq!(saved.status, TaskStatus::Error);
+        assert_eq!(
+            saved.message.as_deref(),
+            Some("Approved plan has no saved plan file")
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_template_hooks_rejects_cycles() {
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                id: 200,
+                project_id: 10,
+                post_template_id: Some(100),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store
+            .create_template(Template {
+                id: 300,
+                project_id: 10,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let template = |pre: Option<i32>, fail: Option<i32>| Template {
+            id: 100,
+            project_id: 10,
+            pre_template_id: pre,
+            fail_template_id: fail,
+            ..Default::default()
+        };
+
+        assert!(
+            validate_template_hooks(store.as_ref(), &template(Some(300), None))
+                .await
+                .is_ok()
+        );
+        for (pre, fail) in [(Some(100), None), (Some(300), Some(200)), (Some(400), None)] {
+            assert!(matches!(
+                validate_template_hooks(store.as_ref(), &template(pre, fail)).await,
+                Err(crate::error::Error::Validation(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn on_task_finished_saves_outputs_and_promotes_build() {
+        use crate::db::store::StructuredOutputManager;
+        use crate::models::template::TemplateType;
+
+        let store: Arc<dyn crate::db::store::Store + Send + Sync> = Arc::new(MockStore::new());
+        store
+            .create_template(Template {
+                id: 100,
+                project_id: 10,
+                r#type: TemplateType::Build,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store
+            .create_template(Template {
+                id: 200,
+                project_id: 10,
+                r#type: TemplateType::Deploy,
+                build_template_id: Some(100),
+                autorun: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let task = sample_task();
+        store.create_task(task.clone()).await.unwrap();
+        // Лог пришёл от удалённого раннера
+        store
+            .create_task_output(TaskOutput {
+                id: 0,
+                task_id: 1,
+                project_id: 10,
+                time: Utc::now(),
+                output: r#"VELUM_OUTPUT: {"image": "app:1.2"}"#.to_string(),
+                stage_id: None,
+            })
+            .await
+            .unwrap();
+
+        let finished = Task {
+            status: TaskStatus::Success,
+            end: Some(Utc::now()),
+            ..task
+        };
+        on_task_finished(store.clone(), &finished).await.unwrap();
+
+        assert_eq!(
+            store.get_task(10, 1).await.unwrap().status,
+            TaskStatus::Success
+        );
+        let outputs = store.get_task_outputs_map(1, 10).await.unwrap().outputs;
+        assert_eq!(outputs["image"], serde_json::json!("app:1.2"));
+        let deploys = store.get_tasks(10, Some(200)).await.unwrap();
+        assert_eq!(deploys.len(), 1);
+        assert_eq!(deploys[0].task.build_task_id, Some(1));
+    }
+
+    #[test]
+    fn pending_plan_keeps_saved_plan_only_when_changed() {
+        let task = sample_task();
+        let review = PlanReview {
+            has_changes: true,
+            saved: SavedPlan {
+                plan: vec![1, 2, 3],
+                state_serial: Some(4),
+            },
+            plan_json: Some("{}".to_string()),
+            resources_added: 1,
+            resources_changed: 2,
+            resources_removed: 3,
+        };
+        let plan = pending_plan(&task, &review);
+        assert_eq!(plan.status, "pending");
+        assert_eq!(plan.plan_file, Some(vec![1, 2, 3]));
+        assert_eq!(plan.state_serial, Some(4));
+        assert_eq!(
+            (
+                plan.resources_added,
+                plan.resources_changed,
+                plan.resources_removed
+            ),
+            (1, 2, 3)
+        );
+
+        let unchanged = pending_plan(&task, &PlanReview::default());
+        assert_eq!(unchanged.plan_file, None);
     }
 }
 
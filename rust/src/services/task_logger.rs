@@ -9,6 +9,8 @@ use std::process::Command;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 
+use crate::services::secret_masker::SecretMasker;
+
 // ============================================================================
 // TaskStatus - статусы задач
 // ============================================================================
@@ -220,6 +222,9 @@ pub trait TaskLogger: Send + Sync {
 
     /// Ждёт завершения обработки всех логов
     fn wait_log(&self);
+
+    /// Регистрирует секрет, который будет заменён на `********` во всём выводе
+    fn add_secret(&self, secret: &str);
 }
 
 // ============================================================================
@@ -233,6 +238,7 @@ pub struct BasicLogger {
     log_listeners: RwLock<Vec<LogListener>>,
     commit_hash: RwLock<Option<String>>,
     commit_message: RwLock<Option<String>>,
+    secrets: SecretMasker,
 }
 
 impl BasicLogger {
@@ -243,9 +249,17 @@ impl BasicLogger {
             log_listeners: RwLock::new(Vec::new()),
             commit_hash: RwLock::new(None),
             commit_message: RwLock::new(None),
+            secrets: SecretMasker::new(),
         }
     }
 
+    /// Коммит, на котором выполнялась задача (`set_commit`)
+    pub fn commit(&self) -> Option<(String, String)> {
+        let hash = self.commit_hash.read().unwrap().clone()?;
+        let message = self.commit_message.read().unwrap().clone();
+        Some((hash, message.unwrap_or_default()))
+    }
+
     fn notify_status_listeners(&self, status: TaskStatus) {
         let listeners = self.status_listeners.read().unwrap();
         for listener in listeners.iter() {
@@ -279,8 +293,9 @@ impl TaskLogger for BasicLogger {
     }
 
     fn log_with_time(&self, time: DateTime<Utc>, msg: &str) {
+        let msg = self.secrets.mask(msg);
         println!("[{}] {}", time.format("%H:%M:%S"), msg);
-        self.notify_log_listeners(time, msg.to_string());
+        self.notify_log_listeners(time, msg);
     }
 
     fn logf_with_time(&self, time: DateTime<Utc>, format: &str, args: fmt::Arguments<'_>) {
@@ -330,6 +345,10 @@ impl TaskLogger for BasicLogger {
         // В базовой реализации ничего не делаем
         // В production можно реализовать очередь логов
     }
+
+    fn add_secret(&self, secret: &str) {
+        self.secrets.add(secret);
+    }
 }
 
 // ============================================================================
@@ -831,6 +850,26 @@ mod tests {
         assert_eq!(msgs.len(), 3);
     }
 
+    #[test]
+    fn test_basic_logger_masks_secrets() {
+        let logger = BasicLogger::new();
+        let received = Arc::new(RwLock::new(Vec::new()));
+        let r = received.clone();
+        logger.add_log_listener(Box::new(move |_time, msg| {
+            r.write().unwrap().push(msg);
+        }));
+
+        logger.add_secret("db-password-42");
+        logger.log("connecting with db-password-42");
+        let mut cmd = Command::new("mysql");
+        cmd.arg("--password=db-password-42");
+        logger.log_cmd(&cmd);
+
+        let msgs = received.read().unwrap();
+        assert_eq!(msgs[0], "connecting with ********");
+        assert_eq!(msgs[1], "$ mysql --password=********");
+    }
+
     #[test]
     fn test_basic_logger_set_commit() {
         let logger = BasicLogger::new();
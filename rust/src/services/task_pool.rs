@@ -4,7 +4,8 @@
 //! Реализует очередь задач, логирование, управление состоянием.
 
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::{RwLock, Semaphore, mpsc};
 use tracing::{debug, error, info, warn};
@@ -13,8 +14,15 @@ use crate::api::websocket::WebSocketManager;
 use crate::db::store::{Store, TaskManager};
 use crate::error::{Error, Result};
 use crate::models::{Project, Task, TaskOutput};
+use crate::services::concurrency::{self, BlockedBy, ConcurrencySlot, GroupResolver, find_blocker};
 use crate::services::task_logger::TaskStatus;
 
+/// Период синхронизации очереди с БД (в тиках обработки по 1 с)
+const QUEUE_SYNC_TICKS: u64 = 10;
+
+/// Максимум ожидающих задач, загружаемых из БД при восстановлении очереди
+const QUEUE_RESTORE_LIMIT: i32 = 10_000;
+
 /// Событие пула задач
 #[derive(Debug, Clone)]
 pub enum TaskPoolEvent {
@@ -57,6 +65,12 @@ pub struct TaskPoolState {
     pub active_projects: HashMap<i32, Project>,
     /// Блокировки для параллельных задач
     pub blocks: HashMap<i32, Arc<Semaphore>>,
+    /// Базовые приоритеты задач в очереди (task_id → приоритет)
+    pub priorities: HashMap<i32, i32>,
+    /// Группы параллелизма задач в очереди и выполняемых
+    pub groups: HashMap<i32, Vec<ConcurrencySlot>>,
+    /// Задачи очереди, ожидающие освобождения группы
+    pub blocked: HashMap<i32, BlockedBy>,
 }
 
 impl Default for TaskPoolState {
@@ -72,8 +86,360 @@ impl TaskPoolState {
             running: HashMap::new(),
             active_projects: HashMap::new(),
             blocks: HashMap::new(),
+            priorities: HashMap::new(),
+            groups: HashMap::new(),
+            blocked: HashMap::new(),
+        }
+    }
+
+    /// Базовый приоритет задачи в очереди
+    fn base_priority(&self, task: &Task) -> i32 {
+        self.priorities
+            .get(&task.id)
+            .copied()
+            .unwrap_or_else(|| task.priority.unwrap_or(0))
+    }
+
+    /// Проверяет, находится ли задача в очереди или выполняется
+    fn is_tracked(&self, task_id: i32) -> bool {
+        self.queue.values().flatten().any(|t| t.id == task_id)
+            || self
+                .running
+                .values()
+                .flatten()
+                .any(|rt| rt.task.id == task_id)
+    }
+
+    /// Добавляет задачу в очередь проекта, если её там ещё нет
+    fn enqueue(&mut self, task: Task, base_priority: i32, slots: Vec<ConcurrencySlot>) -> bool {
+        self.groups.insert(task.id, slots);
+        self.priorities.insert(task.id, base_priority);
+        if self.is_tracked(task.id) {
+            return false;
         }
+        self.queue.entry(task.project_id).or_default().push(task);
+        true
     }
+
+    /// Убирает задачу из очереди вместе с её метаданными
+    fn forget(&mut self, task_id: i32) {
+        for queue in self.queue.values_mut() {
+            queue.retain(|t| t.id != task_id);
+        }
+        self.priorities.remove(&task_id);
+        self.blocked.remove(&task_id);
+    }
+
+    /// Занятые слоты групп выполняемыми задачами
+    fn group_holders(&self) -> HashMap<String, Vec<i32>> {
+        let mut holders: HashMap<String, Vec<i32>> = HashMap::new();
+        for rt in self.running.values().flatten() {
+            for slot in self.groups.get(&rt.task.id).into_iter().flatten() {
+                holders
+                    .entry(slot.key.clone())
+                    .or_default()
+                    .push(rt.task.id);
+            }
+        }
+        holders
+    }
+}
+
+/// Политика приоритетов очереди
+///
+/// Эффективный приоритет = базовый + бонус за ожидание: каждые
+/// `aging_interval_secs` секунд в очереди задача получает `aging_step`,
+/// но не больше `max_aging_boost`. Так задачи с низким приоритетом
+/// не голодают бесконечно.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuePriorityPolicy {
+    pub aging_interval_secs: i64,
+    pub aging_step: i32,
+    pub max_aging_boost: i32,
+}
+
+impl Default for QueuePriorityPolicy {
+    fn default() -> Self {
+        Self {
+            aging_interval_secs: 300,
+            aging_step: 1,
+            max_aging_boost: 50,
+        }
+    }
+}
+
+impl QueuePriorityPolicy {
+    /// Бонус за время ожидания в очереди
+    pub fn aging_boost(&self, created: DateTime<Utc>, now: DateTime<Utc>) -> i32 {
+        if self.aging_interval_secs <= 0 || self.aging_step <= 0 {
+            return 0;
+        }
+        let waited = (now - created).num_seconds().max(0);
+        let steps = (waited / self.aging_interval_secs).min(i32::MAX as i64) as i32;
+        steps
+            .saturating_mul(self.aging_step)
+            .min(self.max_aging_boost.max(0))
+    }
+
+    /// Эффективный приоритет с учётом ожидания
+    pub fn effective_priority(&self, base: i32, created: DateTime<Utc>, now: DateTime<Utc>) -> i32 {
+        base.saturating_add(self.aging_boost(created, now))
+    }
+}
+
+/// Задача в очереди с рассчитанным приоритетом
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub task: Task,
+    /// Позиция в очереди проекта (с нуля)
+    pub position: usize,
+    pub base_priority: i32,
+    pub effective_priority: i32,
+    /// Почему задача ждёт (например, "blocked by group prod-db / task #12")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<String>,
+}
+
+/// Сортирует задачи по эффективному приоритету (затем по времени создания)
+fn rank_queue(
+    tasks: Vec<(Task, i32)>,
+    policy: &QueuePriorityPolicy,
+    now: DateTime<Utc>,
+) -> Vec<QueueEntry> {
+    let mut entries: Vec<QueueEntry> = tasks
+        .into_iter()
+        .map(|(task, base)| QueueEntry {
+            effective_priority: policy.effective_priority(base, task.created, now),
+            base_priority: base,
+            position: 0,
+            blocked_by: None,
+            task,
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.effective_priority
+            .cmp(&a.effective_priority)
+            .then(a.task.created.cmp(&b.task.created))
+            .then(a.task.id.cmp(&b.task.id))
+    });
+    for (position, entry) in entries.iter_mut().enumerate() {
+        entry.position = position;
+    }
+    entries
+}
+
+/// Базовый приоритет задачи: собственный или приоритет шаблона
+pub async fn resolve_base_priority(store: &dyn Store, task: &Task) -> i32 {
+    if let Some(priority) = task.priority {
+        return priority;
+    }
+    store
+        .get_template(task.project_id, task.template_id)
+        .await
+        .map(|t| t.priority)
+        .unwrap_or(0)
+}
+
+/// Загружает ожидающие задачи из БД (в порядке очереди) с базовыми приоритетами
+async fn load_waiting_tasks(
+    store: &dyn Store,
+    project_id: Option<i32>,
+) -> Result<Vec<(Task, i32)>> {
+    let tasks = store
+        .get_queued_tasks(project_id, QUEUE_RESTORE_LIMIT)
+        .await?;
+
+    let mut template_priorities: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut result = Vec::new();
+    for task in tasks {
+        let base = match task.priority {
+            Some(priority) => priority,
+            None => {
+                let key = (task.project_id, task.template_id);
+                match template_priorities.get(&key) {
+                    Some(priority) => *priority,
+                    None => {
+                        let priority = resolve_base_priority(store, &task).await;
+                        template_priorities.insert(key, priority);
+                        priority
+                    }
+                }
+            }
+        };
+        result.push((task, base));
+    }
+    Ok(result)
+}
+
+/// Возвращает очередь ожидающих задач проекта в порядке запуска
+pub async fn load_queue(
+    store: &dyn Store,
+    project_id: i32,
+    policy: &QueuePriorityPolicy,
+) -> Result<Vec<QueueEntry>> {
+    load_ranked_queue(store, Some(project_id), policy).await
+}
+
+/// Общая очередь всех проектов в порядке выдачи (для удалённых раннеров)
+pub async fn load_global_queue(
+    store: &dyn Store,
+    policy: &QueuePriorityPolicy,
+) -> Result<Vec<QueueEntry>> {
+    load_ranked_queue(store, None, policy).await
+}
+
+async fn load_ranked_queue(
+    store: &dyn Store,
+    project_id: Option<i32>,
+    policy: &QueuePriorityPolicy,
+) -> Result<Vec<QueueEntry>> {
+    let tasks = load_waiting_tasks(store, project_id).await?;
+    let waiting: Vec<Task> = tasks.iter().map(|(t, _)| t.clone()).collect();
+    let mut reasons = concurrency::blocked_reasons(store, &waiting).await;
+
+    let mut queue = rank_queue(tasks, policy, Utc::now());
+    for entry in &mut queue {
+        entry.blocked_by = reasons.remove(&entry.task.id).map(|b| b.to_string());
+    }
+    Ok(queue)
+}
+
+/// Загружает ожидающую задачу или возвращает ошибку валидации
+async fn get_waiting_task(store: &dyn Store, project_id: i32, task_id: i32) -> Result<Task> {
+    let task = store.get_task(project_id, task_id).await?;
+    if task.project_id != project_id {
+        return Err(Error::NotFound(format!("Task {} not found", task_id)));
+    }
+    if task.status != TaskStatus::Waiting {
+        return Err(Error::Validation(format!(
+            "Task {} is not waiting in the queue (status: {})",
+            task_id, task.status
+        )));
+    }
+    Ok(task)
+}
+
+/// Записывает приоритет, только пока задача ждёт в очереди
+///
+/// Строка целиком не перезаписывается: задачу могут забрать пул или раннер
+/// между чтением и записью, и её статус нельзя вернуть в `waiting`.
+async fn write_waiting_priority(
+    store: &dyn Store,
+    task: &mut Task,
+    priority: Option<i32>,
+) -> Result<()> {
+    if !store
+        .set_waiting_task_priority(task.project_id, task.id, priority)
+        .await?
+    {
+        // Задача исчезла или уже запущена — сообщаем точную причину
+        get_waiting_task(store, task.project_id, task.id).await?;
+        return Err(Error::Validation(format!(
+            "Task {} is not waiting in the queue",
+            task.id
+        )));
+    }
+    task.priority = priority;
+    Ok(())
+}
+
+/// Устанавливает приоритет ожидающей задачи (`None` — вернуть приоритет шаблона)
+pub async fn set_task_priority(
+    store: &dyn Store,
+    project_id: i32,
+    task_id: i32,
+    priority: Option<i32>,
+) -> Result<Task> {
+    let mut task = get_waiting_task(store, project_id, task_id).await?;
+    write_waiting_priority(store, &mut task, priority).await?;
+    Ok(task)
+}
+
+/// Поднимает задачу в начало очереди проекта
+pub async fn bump_task(
+    store: &dyn Store,
+    project_id: i32,
+    task_id: i32,
+    policy: &QueuePriorityPolicy,
+) -> Result<Task> {
+    let mut task = get_waiting_task(store, project_id, task_id).await?;
+    let now = Utc::now();
+    let queue = rank_queue(
+        load_waiting_tasks(store, Some(project_id)).await?,
+        policy,
+        now,
+    );
+
+    let top = queue
+        .iter()
+        .filter(|e| e.task.id != task_id)
+        .map(|e| e.effective_priority)
+        .max();
+    let Some(top) = top else {
+        return Ok(task);
+    };
+    if queue.first().is_some_and(|e| e.task.id == task_id) {
+        return Ok(task);
+    }
+
+    // Базовый приоритет такой, чтобы с учётом своего бонуса обойти лидера
+    let own_boost = policy.aging_boost(task.created, now);
+    let priority = Some(top.saturating_sub(own_boost).saturating_add(1));
+    write_waiting_priority(store, &mut task, priority).await?;
+    Ok(task)
+}
+
+/// Переставляет задачи в начало очереди в указанном порядке
+///
+/// Остальные задачи сохраняют свой порядок после перечисленных. Приоритеты
+/// разносятся на `max_aging_boost + 1`, чтобы бонус за ожидание не нарушил
+/// заданный порядок.
+pub async fn reorder_queue(
+    store: &dyn Store,
+    project_id: i32,
+    task_ids: &[i32],
+    policy: &QueuePriorityPolicy,
+) -> Result<Vec<QueueEntry>> {
+    let unique: HashSet<i32> = task_ids.iter().copied().collect();
+    if unique.len() != task_ids.len() {
+        return Err(Error::Validation(
+            "Duplicate task ids in queue order".to_string(),
+        ));
+    }
+
+    let waiting = load_waiting_tasks(store, Some(project_id)).await?;
+    if let Some(missing) = task_ids
+        .iter()
+        .find(|id| !waiting.iter().any(|(t, _)| t.id == **id))
+    {
+        return Err(Error::Validation(format!(
+            "Task {} is not waiting in the queue of project {}",
+            missing, project_id
+        )));
+    }
+
+    let max_boost = policy.max_aging_boost.max(0);
+    let anchor = waiting
+        .iter()
+        .filter(|(t, _)| !unique.contains(&t.id))
+        .map(|(_, base)| base.saturating_add(max_boost))
+        .max()
+        .unwrap_or(0);
+    let spacing = max_boost.saturating_add(1);
+    let count = task_ids.len() as i32;
+
+    for (index, task_id) in task_ids.iter().enumerate() {
+        let (mut task, _) = waiting
+            .iter()
+            .find(|(t, _)| t.id == *task_id)
+            .cloned()
+            .expect("checked above");
+        let rank = count - index as i32;
+        let priority = Some(anchor.saturating_add(rank.saturating_mul(spacing)));
+        write_waiting_priority(store, &mut task, priority).await?;
+    }
+
+    load_queue(store, project_id, policy).await
 }
 
 /// Пул задач
@@ -94,6 +460,8 @@ pub struct TaskPool {
     max_tasks_per_project: usize,
     /// WebSocket менеджер для real-time уведомлений
     pub ws_manager: Arc<WebSocketManager>,
+    /// Политика приоритетов очереди
+    policy: QueuePriorityPolicy,
 }
 
 impl TaskPool {
@@ -117,14 +485,12 @@ impl TaskPool {
             while let Some(task) = register_rx.recv().await {
                 debug!("Получена новая задача: {}", task.id);
 
-                let mut state = state_clone.write().await;
-                let project_id = task.project_id;
-
-                // Инициализируем очередь для проекта если нужно
-                state.queue.entry(project_id).or_insert_with(Vec::new);
-
-                // Добавляем задачу в очередь
-                state.queue.get_mut(&project_id).unwrap().push(task.clone());
+                let base = resolve_base_priority(store_clone.as_ref(), &task).await;
+                let slots = GroupResolver::new(store_clone.as_ref()).slots(&task).await;
+                if !state_clone.write().await.enqueue(task.clone(), base, slots) {
+                    debug!("Задача {} уже в очереди", task.id);
+                    continue;
+                }
 
                 // Отправляем событие
                 let _ = events_tx_clone.send(TaskPoolEvent::TaskCreated(task)).await;
@@ -171,6 +537,7 @@ impl TaskPool {
                         for (_, running_list) in state.running.iter_mut() {
                             running_list.retain(|rt| rt.task.id != task_id);
                         }
+                        state.groups.remove(&task_id);
                     }
                     TaskPoolEvent::TaskFailed { task_id, error } => {
                         error!("Задача {} не удалась: {}", task_id, error);
@@ -192,9 +559,21 @@ impl TaskPool {
             running,
             max_tasks_per_project,
             ws_manager,
+            policy: QueuePriorityPolicy::default(),
         }
     }
 
+    /// Задаёт политику приоритетов очереди
+    pub fn with_priority_policy(mut self, policy: QueuePriorityPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Политика приоритетов очереди
+    pub fn priority_policy(&self) -> QueuePriorityPolicy {
+        self.policy
+    }
+
     /// Получает доступ к хранилищу
     pub fn store(&self) -> Arc<dyn Store> {
         self.store.clone()
@@ -211,16 +590,33 @@ impl TaskPool {
 
         info!("Пул задач запущен");
 
+        // Восстанавливаем очередь из задач в статусе waiting
+        match Self::sync_queue(&self.state, self.store.as_ref()).await {
+            Ok(restored) if restored > 0 => info!("Восстановлено задач в очереди: {}", restored),
+            Ok(_) => {}
+            Err(e) => warn!("Не удалось восстановить очередь задач: {}", e),
+        }
+
         // Запускаем цикл обработки очереди
         let state = self.state.clone();
         let events = self.events.clone();
         let running = self.running.clone();
+        let store = self.store.clone();
         let max_tasks = self.max_tasks_per_project;
+        let policy = self.policy;
 
         tokio::spawn(async move {
+            let mut tick: u64 = 0;
             while *running.read().await {
-                TaskPool::process_queue(&state, &events, max_tasks).await;
+                // Периодически подхватываем изменения приоритетов из БД
+                if tick > 0 && tick.is_multiple_of(QUEUE_SYNC_TICKS) {
+                    if let Err(e) = TaskPool::sync_queue(&state, store.as_ref()).await {
+                        warn!("Ошибка синхронизации очереди задач: {}", e);
+                    }
+                }
+                TaskPool::process_queue(&state, &events, max_tasks, &policy).await;
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                tick += 1;
             }
         });
 
@@ -253,17 +649,79 @@ impl TaskPool {
             .map_err(|e| Error::Other(format!("Ошибка логирования: {}", e)))
     }
 
+    /// Восстанавливает очередь из БД и обновляет приоритеты
+    pub async fn restore_queue(&self) -> Result<usize> {
+        Self::sync_queue(&self.state, self.store.as_ref()).await
+    }
+
+    /// Синхронизирует очередь с задачами в статусе waiting
+    ///
+    /// Добавляет недостающие задачи, обновляет базовые приоритеты и убирает
+    /// из очереди задачи, которые в БД уже не ожидают запуска.
+    /// Возвращает число добавленных задач.
+    async fn sync_queue(state: &Arc<RwLock<TaskPoolState>>, store: &dyn Store) -> Result<usize> {
+        let waiting = load_waiting_tasks(store, None).await?;
+        let waiting_ids: HashSet<i32> = waiting.iter().map(|(t, _)| t.id).collect();
+
+        let queued: Vec<(i32, i32)> = {
+            let state = state.read().await;
+            state
+                .queue
+                .values()
+                .flatten()
+                .map(|t| (t.project_id, t.id))
+                .collect()
+        };
+        let mut stale = Vec::new();
+        for (project_id, task_id) in queued
+            .into_iter()
+            .filter(|(_, id)| !waiting_ids.contains(id))
+        {
+            if let Ok(row) = store.get_task(project_id, task_id).await {
+                if row.status != TaskStatus::Waiting {
+                    stale.push(task_id);
+                }
+            }
+        }
+
+        let mut resolver = GroupResolver::new(store);
+        let mut resolved = Vec::with_capacity(waiting.len());
+        for (task, base) in waiting {
+            let slots = resolver.slots(&task).await;
+            resolved.push((task, base, slots));
+        }
+
+        let mut state = state.write().await;
+        for task_id in stale {
+            state.forget(task_id);
+            state.groups.remove(&task_id);
+        }
+
+        let mut restored = 0;
+        for (task, base, slots) in resolved {
+            if state.enqueue(task, base, slots) {
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    }
+
     /// Обрабатывает очередь задач
     async fn process_queue(
         state: &Arc<RwLock<TaskPoolState>>,
         events: &mpsc::Sender<TaskPoolEvent>,
         max_tasks: usize,
+        policy: &QueuePriorityPolicy,
     ) {
+        let now = Utc::now();
+
         // Собираем информацию о задачах для запуска
-        let mut tasks_to_start: Vec<(Task, i32)> = {
+        let (mut tasks_to_start, blocked) = {
             let state = state.read().await;
 
-            let mut result = Vec::new();
+            let mut result: Vec<(Task, i32)> = Vec::new();
+            let mut holders = state.group_holders();
+            let mut blocked = HashMap::new();
 
             // Копируем данные для итерации
             let project_ids: Vec<i32> = state.queue.keys().copied().collect();
@@ -282,38 +740,63 @@ impl TaskPool {
                     continue;
                 }
 
-                // Берём первую задачу из очереди
-                if let Some(task) = queue.first() {
+                // Берём задачу с наибольшим эффективным приоритетом
+                let candidates = queue
+                    .iter()
+                    .map(|t| (t.clone(), state.base_priority(t)))
+                    .collect();
+                let mut next = None;
+                for entry in rank_queue(candidates, policy, now) {
                     // Проверяем блокировки
                     let has_blocks = state
                         .blocks
-                        .get(&task.template_id)
+                        .get(&entry.task.template_id)
                         .map(|s| s.available_permits() == 0)
                         .unwrap_or(false);
-
                     if has_blocks {
-                        debug!("Задача {} заблокирована", task.id);
+                        debug!("Задача {} заблокирована", entry.task.id);
                         continue;
                     }
 
-                    result.push((task.clone(), project_id));
+                    // Проверяем группы параллелизма
+                    let slots = state
+                        .groups
+                        .get(&entry.task.id)
+                        .map(Vec::as_slice)
+                        .unwrap_or(&[]);
+                    if let Some(reason) = find_blocker(entry.task.id, slots, &holders) {
+                        debug!("Задача {}: {}", entry.task.id, reason);
+                        blocked.insert(entry.task.id, reason);
+                        continue;
+                    }
+
+                    if next.is_none() {
+                        for slot in slots {
+                            holders
+                                .entry(slot.key.clone())
+                                .or_default()
+                                .push(entry.task.id);
+                        }
+                        next = Some(entry.task);
+                    }
+                }
+
+                if let Some(task) = next {
+                    result.push((task, project_id));
                 }
             }
 
-            result
+            (result, blocked)
         };
 
         // Обрабатываем задачи вне блокировки
         {
             let mut state = state.write().await;
+            state.blocked = blocked;
 
             for (task, project_id) in tasks_to_start.drain(..) {
                 // Удаляем из очереди
-                if let Some(queue) = state.queue.get_mut(&project_id) {
-                    if !queue.is_empty() {
-                        queue.remove(0);
-                    }
-                }
+                state.forget(task.id);
 
                 // Добавляем в running
                 let running_task = RunningTask {
@@ -348,6 +831,25 @@ impl TaskPool {
         state.queue.values().map(|q| q.len()).sum()
     }
 
+    /// Получает очередь проекта в порядке запуска
+    pub async fn get_project_queue(&self, project_id: i32) -> Vec<QueueEntry> {
+        let state = self.state.read().await;
+        let tasks = state
+            .queue
+            .get(&project_id)
+            .map(|q| {
+                q.iter()
+                    .map(|t| (t.clone(), state.base_priority(t)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut queue = rank_queue(tasks, &self.policy, Utc::now());
+        for entry in &mut queue {
+            entry.blocked_by = state.blocked.get(&entry.task.id).map(|b| b.to_string());
+        }
+        queue
+    }
+
     /// Получает количество выполняемых задач
     pub async fn get_running_count(&self) -> usize {
         let state = self.state.read().await;
@@ -416,6 +918,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         });
 
         match event {
@@ -454,6 +957,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         let running = RunningTask {
@@ -524,6 +1028,7 @@ mod tests {
                 repository_id: None,
                 environment_id: None,
                 params: None,
+                priority: None,
             },
             project_id: 1,
             started_at: Utc::now(),
@@ -562,6 +1067,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         let events = vec![
@@ -611,6 +1117,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         state
@@ -1224,4 +1731,271 @@ mod tests {
         };
         assert!(task.created <= Utc::now());
     }
+
+    fn queued_task(id: i32, priority: Option<i32>, age_secs: i64) -> Task {
+        Task {
+            id,
+            project_id: 1,
+            template_id: 1,
+            status: TaskStatus::Waiting,
+            created: Utc::now() - chrono::Duration::seconds(age_secs),
+            priority,
+            ..Default::default()
+        }
+    }
+
+    async fn queue_store(
+        tasks: Vec<Task>,
+        template_priority: i32,
+    ) -> Arc<crate::db::mock::MockStore> {
+        use crate::db::store::TemplateManager;
+        let store = Arc::new(crate::db::mock::MockStore::new());
+        store
+            .create_template(crate::models::Template {
+                id: 1,
+                project_id: 1,
+                priority: template_priority,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        for task in tasks {
+            store.create_task(task).await.unwrap();
+        }
+        store
+    }
+
+    async fn queue_order(store: &crate::db::mock::MockStore) -> Vec<i32> {
+        load_queue(store, 1, &QueuePriorityPolicy::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.task.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_queue_policy_aging_is_capped() {
+        let policy = QueuePriorityPolicy::default();
+        let now = Utc::now();
+        assert_eq!(policy.aging_boost(now, now), 0);
+        assert_eq!(
+            policy.aging_boost(now - chrono::Duration::seconds(600), now),
+            2
+        );
+        assert_eq!(
+            policy.aging_boost(now - chrono::Duration::days(30), now),
+            policy.max_aging_boost
+        );
+        assert_eq!(
+            policy.effective_priority(10, now - chrono::Duration::seconds(300), now),
+            11
+        );
+    }
+
+    #[test]
+    fn test_rank_queue_orders_by_priority_then_age() {
+        let tasks = vec![
+            (queued_task(1, None, 10), 0),
+            (queued_task(2, None, 20), 5),
+            (queued_task(3, None, 30), 0),
+        ];
+        let ranked = rank_queue(tasks, &QueuePriorityPolicy::default(), Utc::now());
+        let ids: Vec<i32> = ranked.iter().map(|e| e.task.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+        assert_eq!(ranked[0].position, 0);
+        assert_eq!(ranked[2].position, 2);
+    }
+
+    #[tokio::test]
+    async fn test_process_queue_picks_highest_priority() {
+        let state = Arc::new(RwLock::new(TaskPoolState::new()));
+        {
+            let mut state = state.write().await;
+            state.enqueue(queued_task(1, None, 60), 0, Vec::new());
+            state.enqueue(queued_task(2, Some(10), 0), 10, Vec::new());
+        }
+        let (tx, _rx) = mpsc::channel(10);
+        TaskPool::process_queue(&state, &tx, 1, &QueuePriorityPolicy::default()).await;
+
+        let state = state.read().await;
+        assert_eq!(state.running[&1][0].task.id, 2);
+        assert_eq!(state.queue[&1].len(), 1);
+        assert_eq!(state.queue[&1][0].id, 1);
+        assert!(!state.priorities.contains_key(&2));
+    }
+
+    #[tokio::test]
+    async fn test_process_queue_aging_prevents_starvation() {
+        let state = Arc::new(RwLock::new(TaskPoolState::new()));
+        {
+            let mut state = state.write().await;
+            // Ждёт 3 часа: бонус 36 перекрывает разницу в приоритетах
+            state.enqueue(queued_task(1, None, 3 * 3600), 0, Vec::new());
+            state.enqueue(queued_task(2, Some(30), 0), 30, Vec::new());
+        }
+        let (tx, _rx) = mpsc::channel(10);
+        TaskPool::process_queue(&state, &tx, 1, &QueuePriorityPolicy::default()).await;
+
+        let state = state.read().await;
+        assert_eq!(state.running[&1][0].task.id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_queue_respects_concurrency_groups() {
+        let group = vec![ConcurrencySlot::named(1, "prod-db", 1)];
+        let state = Arc::new(RwLock::new(TaskPoolState::new()));
+        {
+            let mut state = state.write().await;
+            state.enqueue(queued_task(1, Some(10), 0), 10, group.clone());
+            state.enqueue(queued_task(2, Some(5), 0), 5, group.clone());
+            state.enqueue(queued_task(3, None, 0), 0, Vec::new());
+        }
+        let (tx, _rx) = mpsc::channel(10);
+        let policy = QueuePriorityPolicy::default();
+
+        TaskPool::process_queue(&state, &tx, 5, &policy).await;
+        TaskPool::process_queue(&state, &tx, 5, &policy).await;
+
+        // Задача 2 ждёт задачу 1 в группе, задача 3 обходит её
+        let state = state.read().await;
+        let running: Vec<i32> = state.running[&1].iter().map(|rt| rt.task.id).collect();
+        assert_eq!(running, vec![1, 3]);
+        assert_eq!(
+            state.blocked[&2].to_string(),
+            "blocked by group prod-db / task #1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_queue_loads_waiting_tasks() {
+        let mut running = queued_task(3, None, 0);
+        running.status = TaskStatus::Running;
+        let store = queue_store(
+            vec![queued_task(1, None, 0), queued_task(2, Some(7), 0), running],
+            4,
+        )
+        .await;
+        let pool = TaskPool::new(store, 5);
+
+        assert_eq!(pool.restore_queue().await.unwrap(), 2);
+        // Повторная синхронизация не дублирует задачи
+        assert_eq!(pool.restore_queue().await.unwrap(), 0);
+        assert_eq!(pool.get_queue_size().await, 2);
+
+        let queue = pool.get_project_queue(1).await;
+        assert_eq!(queue[0].task.id, 2);
+        assert_eq!(queue[0].base_priority, 7);
+        assert_eq!(queue[1].base_priority, 4);
+    }
+
+    #[tokio::test]
+    async fn test_restore_queue_drops_tasks_no_longer_waiting() {
+        let store = queue_store(vec![queued_task(1, None, 0)], 0).await;
+        let pool = TaskPool::new(store.clone(), 5);
+        pool.restore_queue().await.unwrap();
+
+        store
+            .update_task_status(1, 1, TaskStatus::Stopped)
+            .await
+            .unwrap();
+        pool.restore_queue().await.unwrap();
+        assert_eq!(pool.get_queue_size().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_bump_task_moves_to_front() {
+        let store = queue_store(
+            vec![
+                queued_task(1, Some(5), 3600),
+                queued_task(2, None, 600),
+                queued_task(3, None, 0),
+            ],
+            0,
+        )
+        .await;
+        assert_eq!(queue_order(&store).await, vec![1, 2, 3]);
+
+        let policy = QueuePriorityPolicy::default();
+        bump_task(store.as_ref(), 1, 3, &policy).await.unwrap();
+        assert_eq!(queue_order(&store).await, vec![3, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_reorder_queue_places_tasks_in_order() {
+        let store = queue_store(
+            vec![
+                queued_task(1, Some(20), 0),
+                queued_task(2, None, 0),
+                queued_task(3, None, 7200),
+                queued_task(4, None, 0),
+            ],
+            0,
+        )
+        .await;
+
+        let policy = QueuePriorityPolicy::default();
+        let queue = reorder_queue(store.as_ref(), 1, &[4, 2], &policy)
+            .await
+            .unwrap();
+        let ids: Vec<i32> = queue.iter().map(|e| e.task.id).collect();
+        // Остальные задачи идут следом в порядке эффективного приоритета
+        assert_eq!(ids, vec![4, 2, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_reorder_queue_rejects_unknown_tasks() {
+        let store = queue_store(vec![queued_task(1, None, 0)], 0).await;
+        let policy = QueuePriorityPolicy::default();
+
+        let err = reorder_queue(store.as_ref(), 1, &[1, 1], &policy).await;
+        assert!(matches!(err, Err(Error::Validation(_))));
+        let err = reorder_queue(store.as_ref(), 1, &[42], &policy).await;
+        assert!(matches!(err, Err(Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_set_task_priority_requires_waiting_task() {
+        let mut running = queued_task(2, None, 0);
+        running.status = TaskStatus::Running;
+        let store = queue_store(vec![queued_task(1, None, 0), running], 0).await;
+
+        let task = set_task_priority(store.as_ref(), 1, 1, Some(9))
+            .await
+            .unwrap();
+        assert_eq!(task.priority, Some(9));
+        assert_eq!(store.get_task(1, 1).await.unwrap().priority, Some(9));
+
+        let err = set_task_priority(store.as_ref(), 1, 2, Some(9)).await;
+        assert!(matches!(err, Err(Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_priority_write_keeps_task_claimed_meanwhile() {
+        let store = queue_store(vec![queued_task(1, None, 0)], 0).await;
+        let mut task = store.get_task(1, 1).await.unwrap();
+
+        // Пул забрал задачу между чтением и записью приоритета
+        let mut claimed = task.clone();
+        claimed.status = TaskStatus::Running;
+        store.update_task(claimed).await.unwrap();
+
+        let err = write_waiting_priority(store.as_ref(), &mut task, Some(9)).await;
+        assert!(matches!(err, Err(Error::Validation(_))));
+        let stored = store.get_task(1, 1).await.unwrap();
+        assert_eq!(stored.status, TaskStatus::Running);
+        assert_eq!(stored.priority, None);
+
+        let err = write_waiting_priority(
+            store.as_ref(),
+            &mut Task {
+                id: 42,
+                project_id: 1,
+                ..Default::default()
+            },
+            Some(9),
+        )
+        .await;
+        assert!(matches!(err, Err(Error::NotFound(_))));
+    }
 }
@@ -200,6 +200,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -235,6 +236,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -272,6 +274,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -314,6 +317,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -357,6 +361,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         // Проверяем разные статусы
@@ -410,6 +415,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let remaining_statuses = [
@@ -462,6 +468,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: Some(998),
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -501,6 +508,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -537,6 +545,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -572,6 +581,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -612,6 +622,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -649,6 +660,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -684,6 +696,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -719,6 +732,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -757,6 +771,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -793,6 +808,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
 
         let message = TaskStatusMessage::new(&task);
@@ -828,6 +844,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         assert_ne!(message.status, TaskStatus::Running);
@@ -860,6 +877,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -895,6 +913,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -930,6 +949,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -964,6 +984,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -999,6 +1020,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         assert!(message.start.is_some());
@@ -1032,6 +1054,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let msg = TaskStatusMessage::new(&task);
         let status_str = msg.status.to_string();
@@ -1065,6 +1088,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -1099,6 +1123,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let debug = format!("{:?}", message);
@@ -1132,6 +1157,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let msg = TaskStatusMessage::new(&task);
         let cloned = msg.clone();
@@ -1169,6 +1195,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -1202,6 +1229,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -1235,6 +1263,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -1269,6 +1298,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -1303,6 +1333,7 @@ mod tests {
             secret: None,
             environment: None,
             build_task_id: None,
+            priority: None,
         };
         let message = TaskStatusMessage::new(&task);
         let json = message.to_json();
@@ -0,0 +1,220 @@
+//! Очистка потерянных задач
+//!
+//! Каждый узел сервера регистрируется в таблице `cluster_node` и периодически
+//! отмечается в ней; задачи, выполняемые локально, закрепляются за узлом.
+//! Фоновая проверка завершает с ошибкой незавершённые задачи, чей узел
+//! перестал отмечаться (падение или перезапуск процесса) либо чей раннер был
+//! удалён или отключён. Задачи раннеров, просто переставших слать heartbeat,
+//! возвращаются в очередь (см. `remote_runners::requeue_orphaned_tasks`).
+
+use chrono::Utc;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::db::store::Store;
+use crate::models::TaskOutput;
+use crate::services::leader_election::Leadership;
+use crate::services::{drift, event_outbox};
+
+/// Сообщение задачи, завершённой проверкой потерянных задач
+pub const ORPHANED_TASK_MESSAGE: &str = "Task failed: its server node or runner is gone";
+
+/// Конфигурация проверки потерянных задач
+#[derive(Debug, Clone)]
+pub struct TaskReaperConfig {
+    /// Интервал отметки узла и проверки задач
+    pub interval: Duration,
+    /// Через сколько узел без отметки считается исчезнувшим
+    pub node_timeout: Duration,
+}
+
+impl Default for TaskReaperConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(15),
+            node_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// ID текущего узла сервера (новый при каждом запуске процесса)
+pub fn node_id() -> &'static str {
+    static NODE_ID: OnceLock<String> = OnceLock::new();
+    NODE_ID.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Имя хоста текущего узла
+pub fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Отмечает текущий узел живым
+pub async fn touch_node(store: &dyn Store) {
+    if let Err(e) = store.touch_cluster_node(node_id(), &hostname()).await {
+        warn!("Failed to update cluster node heartbeat: {}", e);
+    }
+}
+
+/// Завершает с ошибкой задачи исчезнувших узлов и раннеров
+pub async fn reap_orphaned_tasks(store: &dyn Store, config: &TaskReaperConfig) -> Vec<i32> {
+    let failed = match store
+        .fail_orphaned_tasks(config.node_timeout.as_secs() as i64, ORPHANED_TASK_MESSAGE)
+        .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            warn!("Failed to reap orphaned tasks: {}", e);
+            return Vec::new();
+        }
+    };
+    for task_id in &failed {
+        warn!("Task {} marked as failed: owner is gone", task_id);
+        let _ = store
+            .create_task_output(TaskOutput {
+                id: 0,
+                task_id: *task_id,
+                project_id: 0,
+                time: Utc::now(),
+                output: ORPHANED_TASK_MESSAGE.to_string(),
+                stage_id: None,
+            })
+            .await;
+        if let Ok(task) = store.get_task(0, *task_id).await {
+            event_outbox::record_task_event(store, &task).await;
+        }
+    }
+    failed
+}
+
+/// Запускает фоновую отметку текущего узла
+pub fn spawn_node_heartbeat(
+    store: Arc<dyn Store + Send + Sync>,
+    config: TaskReaperConfig,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            touch_node(store.as_ref()).await;
+        }
+    })
+}
+
+/// Запускает фоновую проверку потерянных задач (в кластере — только на узле-лидере)
+///
+/// Вместе с ней подводится итог проверок дрейфа, чьи задачи завершились.
+pub fn spawn_task_reaper(
+    store: Arc<dyn Store + Send + Sync>,
+    config: TaskReaperConfig,
+    leadership: Leadership,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            if leadership.is_leader() {
+                reap_orphaned_tasks(store.as_ref(), &config).await;
+                drift::settle_finished_checks(store.clone()).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{ClusterNodeManager, RunnerManager, TaskManager};
+    use crate::models::{Runner, Task};
+    use crate::services::task_logger::TaskStatus;
+
+    fn running_task(id: i32, started_secs_ago: i64) -> Task {
+        Task {
+            id,
+            project_id: 1,
+            template_id: 1,
+            status: TaskStatus::Running,
+            start: Some(Utc::now() - chrono::Duration::seconds(started_secs_ago)),
+            ..Task::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reaper_fails_tasks_of_lost_nodes() {
+        let store = MockStore::new();
+        for task in [running_task(1, 5), running_task(2, 5), running_task(3, 600)] {
+            store.create_task(task).await.unwrap();
+        }
+        store.touch_cluster_node("alive", "a").await.unwrap();
+        store.set_task_node(1, "alive").await.unwrap();
+        store.set_task_node(2, "crashed").await.unwrap();
+
+        let failed = reap_orphaned_tasks(&store, &TaskReaperConfig::default()).await;
+        assert_eq!(failed, vec![2, 3]);
+        let task = store.get_task(1, 2).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Error);
+        assert_eq!(task.message.as_deref(), Some(ORPHANED_TASK_MESSAGE));
+        assert_eq!(
+            store.get_task(1, 1).await.unwrap().status,
+            TaskStatus::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reaper_fails_tasks_of_removed_runners() {
+        let store = MockStore::new();
+        for id in [1, 2] {
+            store
+                .create_task(Task {
+                    status: TaskStatus::Waiting,
+                    ..running_task(id, 0)
+                })
+                .await
+                .unwrap();
+        }
+        for (id, active) in [(1, true), (2, false)] {
+            store
+                .create_runner(Runner {
+                    id,
+                    project_id: None,
+                    token: format!("token-{id}"),
+                    name: format!("runner-{id}"),
+                    active,
+                    last_active: Some(Utc::now()),
+                    webhook: None,
+                    max_parallel_tasks: None,
+                    tag: None,
+                    cleaning_requested: None,
+                    touched: None,
+                    created: None,
+                    capabilities: None,
+                })
+                .await
+                .unwrap();
+        }
+        assert!(store.claim_task(1, 1).await.unwrap());
+        assert!(store.claim_task(2, 2).await.unwrap());
+
+        let failed = reap_orphaned_tasks(&store, &TaskReaperConfig::default()).await;
+        assert_eq!(failed, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_touch_node_keeps_own_tasks() {
+        let store = MockStore::new();
+        store.create_task(running_task(1, 600)).await.unwrap();
+        store.set_task_node(1, node_id()).await.unwrap();
+        touch_node(&store).await;
+
+        assert!(
+            reap_orphaned_tasks(&store, &TaskReaperConfig::default())
+                .await
+                .is_empty()
+        );
+    }
+}
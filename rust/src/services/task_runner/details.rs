@@ -42,6 +42,15 @@ impl TaskRunner {
                 .await?;
         }
 
+        // Секреты окружения маскируются в логах задачи
+        if let Some(secrets) = self.environment.secrets.as_deref().and_then(|json| {
+            serde_json::from_str::<Vec<crate::models::EnvironmentSecretValue>>(json).ok()
+        }) {
+            for secret in secrets {
+                self.secrets.add(&secret.secret);
+            }
+        }
+
         Ok(())
     }
 
@@ -154,6 +154,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         };
 
         let pool = Arc::new(TaskPool::new(Arc::new(MockStore::new()), 5));
@@ -36,10 +36,13 @@ impl TaskRunner {
         }
     }
 
-    /// log записывает лог задачи
+    /// log записывает лог задачи (зарегистрированные секреты маскируются)
     pub fn log(&self, msg: &str) {
         use tracing::info;
 
+        let msg = self.secrets.mask(msg);
+        let msg = msg.as_str();
+
         info!("[Task {}] {}", self.task.id, msg);
 
         // Запись в БД
@@ -4,6 +4,7 @@
 
 use crate::db_lib::AccessKeyInstallerImpl;
 use crate::models::{Environment, Inventory, Repository, Task, Template};
+use crate::services::secret_masker::SecretMasker;
 use crate::services::task_logger::{LogListener, StatusListener, TaskLogger, TaskStatus};
 use crate::services::task_pool::TaskPool;
 use std::sync::Arc;
@@ -71,6 +72,9 @@ pub struct TaskRunner {
 
     /// Флаг остановки
     pub killed: Arc<Mutex<bool>>,
+
+    /// Секреты задачи, маскируемые в логах
+    pub secrets: SecretMasker,
 }
 
 impl TaskRunner {
@@ -103,6 +107,7 @@ impl TaskRunner {
             log_listeners: Vec::new(),
             alias: None,
             killed: Arc::new(Mutex::new(false)),
+            secrets: SecretMasker::new(),
         }
     }
 
@@ -156,6 +161,7 @@ mod tests {
             repository_id: None,
             environment_id: None,
             params: None,
+            priority: None,
         }
     }
 
@@ -0,0 +1,366 @@
+//! Template Inheritance — наследование конфигурации шаблонов
+//!
+//! Шаблон может ссылаться на родителя через `parent_template_id`. Перед запуском
+//! задачи цепочка родителей разворачивается в «эффективный» шаблон: значения
+//! потомка перекрывают значения предка, JSON-объекты сливаются рекурсивно.
+//!
+//! Сливаются:
+//! - extra vars окружения (`environment.json`) по цепочке `environment_id`;
+//! - `task_params` и `arguments` (объекты — рекурсивно, прочие значения заменяются);
+//! - `survey_vars` — по имени переменной;
+//! - `vaults` — по метке vault (`type`), плюс `vault_key_id`.
+//!
+//! Незаданные ссылки потомка (`inventory_id`, `repository_id`, `environment_id`,
+//! `git_branch`, `execution_image`, `timeout_secs`, пустой `playbook`) наследуются от предка.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::db::store::Store;
+use crate::error::{Error, Result};
+use crate::models::Template;
+
+/// Максимальная глубина цепочки наследования
+pub const MAX_INHERITANCE_DEPTH: usize = 32;
+
+/// Шаблон после применения наследования
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveTemplate {
+    /// Шаблон с применёнными значениями предков
+    pub template: Template,
+    /// ID шаблонов цепочки: от запрошенного к корневому
+    pub chain: Vec<i32>,
+    /// Слитые extra vars окружений цепочки (`None`, если окружений нет)
+    pub environment_vars: Option<Value>,
+}
+
+/// Загружает цепочку шаблонов `template_id` → родитель → … (от потомка к корню)
+///
+/// Возвращает `Error::Validation` при циклической ссылке или слишком глубокой цепочке.
+pub async fn load_template_chain(
+    store: &(dyn Store + Send + Sync),
+    project_id: i32,
+    template_id: i32,
+) -> Result<Vec<Template>> {
+    let mut chain = Vec::new();
+    let mut seen = HashSet::new();
+    let mut next = Some(template_id);
+
+    while let Some(id) = next {
+        if !seen.insert(id) {
+            let path: Vec<String> = chain
+                .iter()
+                .map(|t: &Template| t.id.to_string())
+                .chain(std::iter::once(id.to_string()))
+                .collect();
+            return Err(Error::Validation(format!(
+                "Template inheritance cycle: {}",
+                path.join(" -> ")
+            )));
+        }
+        if chain.len() >= MAX_INHERITANCE_DEPTH {
+            return Err(Error::Validation(format!(
+                "Template inheritance chain of template {template_id} is deeper than {MAX_INHERITANCE_DEPTH}"
+            )));
+        }
+        let template = store.get_template(project_id, id).await?;
+        next = template.parent_template_id;
+        chain.push(template);
+    }
+
+    Ok(chain)
+}
+
+/// Разворачивает наследование шаблона и его окружений
+pub async fn resolve_template(
+    store: &(dyn Store + Send + Sync),
+    project_id: i32,
+    template_id: i32,
+) -> Result<EffectiveTemplate> {
+    let chain = load_template_chain(store, project_id, template_id).await?;
+
+    // Окружения сливаются от корня к потомку; одинаковые ID загружаются один раз
+    let mut environment_vars: Option<Value> = None;
+    let mut loaded = HashSet::new();
+    for template in chain.iter().rev() {
+        let Some(env_id) = template.environment_id else {
+            continue;
+        };
+        if !loaded.insert(env_id) {
+            continue;
+        }
+        let environment = store.get_environment(project_id, env_id).await?;
+        let vars: Value = serde_json::from_str(&environment.json).unwrap_or(Value::Null);
+        environment_vars = merge_optional(environment_vars, Some(vars));
+    }
+
+    Ok(EffectiveTemplate {
+        chain: chain.iter().map(|t| t.id).collect(),
+        template: merge_chain(chain),
+        environment_vars,
+    })
+}
+
+/// Сливает цепочку шаблонов (от потомка к корню) в один шаблон
+pub fn merge_chain(chain: Vec<Template>) -> Template {
+    let mut iter = chain.into_iter().rev();
+    let Some(mut merged) = iter.next() else {
+        return Template::default();
+    };
+    for child in iter {
+        merged = merge_template(&merged, child);
+    }
+    merged
+}
+
+/// Накладывает шаблон-потомок на родителя
+///
+/// Собственные поля потомка (ID, имя, тип, флаги, хуки) сохраняются как есть.
+pub fn merge_template(parent: &Template, child: Template) -> Template {
+    let arguments = merge_arguments(parent.arguments.as_deref(), child.arguments.as_deref());
+    Template {
+        playbook: if child.playbook.is_empty() {
+            parent.playbook.clone()
+        } else {
+            child.playbook
+        },
+        inventory_id: child.inventory_id.or(parent.inventory_id),
+        repository_id: child.repository_id.or(parent.repository_id),
+        environment_id: child.environment_id.or(parent.environment_id),
+        git_branch: child
+            .git_branch
+            .filter(|b| !b.is_empty())
+            .or_else(|| parent.git_branch.clone()),
+        vault_key_id: child.vault_key_id.or(parent.vault_key_id),
+        timeout_secs: child.timeout_secs.or(parent.timeout_secs),
+        execution_image: child
+            .execution_image
+            .filter(|i| !i.is_empty())
+            .or_else(|| parent.execution_image.clone()),
+        arguments,
+        task_params: merge_optional(parent.task_params.clone(), child.task_params),
+        survey_vars: merge_keyed(parent.survey_vars.clone(), child.survey_vars, "name"),
+        vaults: merge_keyed(parent.vaults.clone(), child.vaults, "type"),
+        ..child
+    }
+}
+
+/// Рекурсивно сливает JSON: объекты объединяются по ключам, остальное заменяется
+pub fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn merge_optional(parent: Option<Value>, child: Option<Value>) -> Option<Value> {
+    match (parent, child) {
+        (Some(mut parent), Some(child)) => {
+            deep_merge(&mut parent, child);
+            Some(parent)
+        }
+        (parent, None) => parent,
+        (None, child) => child,
+    }
+}
+
+/// Сливает массивы объектов по ключу `key`; элементы потомка заменяют одноимённые
+fn merge_keyed(parent: Option<Value>, child: Option<Value>, key: &str) -> Option<Value> {
+    let (Some(Value::Array(parent)), Some(Value::Array(child))) = (&parent, &child) else {
+        return child.or(parent);
+    };
+
+    let key_of = |item: &Value| item.get(key).cloned().unwrap_or(Value::Null);
+    let mut merged: Vec<Value> = parent
+        .iter()
+        .filter(|p| !child.iter().any(|c| key_of(c) == key_of(p)))
+        .cloned()
+        .collect();
+    merged.extend(child.iter().cloned());
+    Some(Value::Array(merged))
+}
+
+/// Сливает `arguments`: объекты (аргументы по командам) — рекурсивно,
+/// список аргументов потомка заменяет родительский целиком
+fn merge_arguments(parent: Option<&str>, child: Option<&str>) -> Option<String> {
+    let child = child.filter(|s| !s.trim().is_empty());
+    let (Some(parent_raw), Some(child_raw)) = (parent, child) else {
+        return child.or(parent).map(str::to_string);
+    };
+    match (
+        serde_json::from_str::<Value>(parent_raw),
+        serde_json::from_str::<Value>(child_raw),
+    ) {
+        (Ok(mut parent @ Value::Object(_)), Ok(child @ Value::Object(_))) => {
+            deep_merge(&mut parent, child);
+            Some(parent.to_string())
+        }
+        _ => Some(child_raw.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::mock::MockStore;
+    use crate::db::store::{EnvironmentManager, TemplateManager};
+    use crate::models::Environment;
+    use serde_json::json;
+
+    fn template(id: i32, parent: Option<i32>) -> Template {
+        Template {
+            id,
+            project_id: 1,
+            name: format!("tpl-{id}"),
+            parent_template_id: parent,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn deep_merge_child_overrides_parent() {
+        let mut base = json!({"a": 1, "nested": {"x": 1, "y": 2}, "list": [1, 2]});
+        deep_merge(&mut base, json!({"b": 2, "nested": {"y": 3}, "list": [3]}));
+        assert_eq!(
+            base,
+            json!({"a": 1, "b": 2, "nested": {"x": 1, "y": 3}, "list": [3]})
+        );
+    }
+
+    #[test]
+    fn merge_template_inherits_unset_fields() {
+        let parent = Template {
+            playbook: "deploy.yml".to_string(),
+            inventory_id: Some(3),
+            repository_id: Some(4),
+            git_branch: Some("main".to_string()),
+            execution_image: Some("alpine".to_string()),
+            task_params: Some(json!({"limit": "all", "tags": ["a"]})),
+            ..template(1, None)
+        };
+        let child = Template {
+            inventory_id: Some(5),
+            task_params: Some(json!({"limit": "web"})),
+            ..template(2, Some(1))
+        };
+
+        let merged = merge_template(&parent, child);
+        assert_eq!(merged.id, 2);
+        assert_eq!(merged.name, "tpl-2");
+        assert_eq!(merged.playbook, "deploy.yml");
+        assert_eq!(merged.inventory_id, Some(5));
+        assert_eq!(merged.repository_id, Some(4));
+        assert_eq!(merged.git_branch.as_deref(), Some("main"));
+        assert_eq!(merged.execution_image.as_deref(), Some("alpine"));
+        assert_eq!(
+            merged.task_params,
+            Some(json!({"limit": "web", "tags": ["a"]}))
+        );
+    }
+
+    #[test]
+    fn merge_keyed_replaces_items_by_key() {
+        let parent = Some(json!([
+            {"name": "region", "title": "Region"},
+            {"name": "size", "title": "Size"}
+        ]));
+        let child = Some(json!([{"name": "size", "title": "Instance size"}]));
+        assert_eq!(
+            merge_keyed(parent, child, "name"),
+            Some(json!([
+                {"name": "region", "title": "Region"},
+                {"name": "size", "title": "Instance size"}
+            ]))
+        );
+    }
+
+    #[test]
+    fn merge_arguments_objects_and_lists() {
+        assert_eq!(
+            merge_arguments(
+                Some(r#"{"plan": ["-lock=false"], "apply": ["-auto-approve"]}"#),
+                Some(r#"{"plan": ["-refresh=false"]}"#)
+            )
+            .map(|s| serde_json::from_str::<Value>(&s).unwrap()),
+            Some(json!({"plan": ["-refresh=false"], "apply": ["-auto-approve"]}))
+        );
+        assert_eq!(
+            merge_arguments(Some(r#"["-v"]"#), Some(r#"["-vvv"]"#)).as_deref(),
+            Some(r#"["-vvv"]"#)
+        );
+        assert_eq!(
+            merge_arguments(Some(r#"["-v"]"#), None).as_deref(),
+            Some(r#"["-v"]"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_template_merges_chain_and_environments() {
+        let store = MockStore::new();
+        let base_env = store
+            .create_environment(Environment::new(
+                1,
+                "base".to_string(),
+                r#"{"region": "eu", "replicas": 2}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        let child_env = store
+            .create_environment(Environment::new(
+                1,
+                "child".to_string(),
+                r#"{"replicas": 5}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+
+        store
+            .create_template(Template {
+                environment_id: Some(base_env.id),
+                vaults: Some(json!([{"vault_key_id": 1, "type": "prod"}])),
+                ..template(1, None)
+            })
+            .await
+            .unwrap();
+        store
+            .create_template(Template {
+                environment_id: Some(child_env.id),
+                vaults: Some(json!([{"vault_key_id": 2, "type": "prod"}])),
+                ..template(2, Some(1))
+            })
+            .await
+            .unwrap();
+
+        let effective = resolve_template(&store, 1, 2).await.unwrap();
+        assert_eq!(effective.chain, vec![2, 1]);
+        assert_eq!(
+            effective.environment_vars,
+            Some(json!({"region": "eu", "replicas": 5}))
+        );
+        assert_eq!(
+            effective.template.vaults,
+            Some(json!([{"vault_key_id": 2, "type": "prod"}]))
+        );
+    }
+
+    #[tokio::test]
+    async fn load_template_chain_detects_cycles() {
+        let store = MockStore::new();
+        store.create_template(template(1, Some(2))).await.unwrap();
+        store.create_template(template(2, Some(1))).await.unwrap();
+
+        let err = load_template_chain(&store, 1, 1).await.unwrap_err();
+        assert!(matches!(err, Error::Validation(ref m) if m.contains("1 -> 2 -> 1")));
+    }
+}
@@ -2,16 +2,28 @@
 //!
 //! Выполняет DAG workflow: запускает шаблоны из узлов в правильном порядке
 //! с учётом условий переходов (success/failure/always)
+//!
+//! Задача узла получает extra vars: все параметры запуска и входы узла
+//! (`WorkflowNode::inputs`), взятые из outputs уже выполненных узлов.
+//!
+//! Узлы выполняются волнами (`WorkflowNode::wave`): узлы следующей волны
+//! стартуют только после завершения всех достигнутых узлов предыдущей.
+//! Узел подтверждения (`approval`) приостанавливает запуск до решения
+//! пользователя. Состояние узлов сохраняется в `workflow_run_node`, поэтому
+//! отмена и решения видны executor'у на любом узле кластера, а незавершённые
+//! запуски продолжаются после рестарта сервера.
 
 use chrono::Utc;
+use serde_json::{Map, Value};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 use crate::api::state::AppState;
 use crate::db::store::{
-    EnvironmentManager, InventoryManager, RepositoryManager, Store, TaskManager, TemplateManager,
-    WorkflowManager,
+    ClusterNodeManager, EnvironmentManager, InventoryManager, RepositoryManager, Store,
+    StructuredOutputManager, TaskManager, TemplateManager, WorkflowManager,
 };
 use crate::error::{Error, Result};
 use crate::models::environment::Environment;
@@ -19,8 +31,14 @@ use crate::models::inventory::Inventory;
 use crate::models::repository::Repository;
 use crate::models::task::Task;
 use crate::models::template::Template;
-use crate::models::workflow::{Workflow, WorkflowEdge, WorkflowNode, WorkflowRun};
+use crate::models::workflow::{Workflow, WorkflowEdge, WorkflowNode, WorkflowRun, WorkflowRunNode};
+use crate::services::leader_election::Leadership;
+use crate::services::task_execution;
 use crate::services::task_logger::TaskStatus;
+use crate::services::task_reaper::{self, TaskReaperConfig};
+
+/// Интервал опроса отмены запуска и решений по узлам подтверждения
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 /// Состояние выполнения узла
 #[derive(Debug, Clone)]
@@ -30,6 +48,30 @@ pub enum NodeExecutionStatus {
     Success(Task),
     Failed(Task),
     Skipped,
+    /// Узел подтверждения ждёт решения пользователя
+    WaitingApproval,
+    Approved,
+    Rejected,
+    /// Запуск отменён до завершения узла
+    Cancelled,
+}
+
+impl NodeExecutionStatus {
+    /// Статус для условий исходящих рёбер; `None` — узел не завершён
+    /// или отменён
+    pub fn edge_status(&self) -> Option<TaskStatus> {
+        match self {
+            NodeExecutionStatus::Success(_) | NodeExecutionStatus::Approved => {
+                Some(TaskStatus::Success)
+            }
+            NodeExecutionStatus::Failed(task) => Some(match task.status {
+                TaskStatus::Stopped | TaskStatus::NotExecuted => task.status,
+                _ => TaskStatus::Error,
+            }),
+            NodeExecutionStatus::Rejected => Some(TaskStatus::Error),
+            _ => None,
+        }
+    }
 }
 
 /// Контекст выполнения workflow
@@ -40,6 +82,8 @@ pub struct WorkflowExecutionContext {
     pub run: WorkflowRun,
     pub node_statuses: HashMap<i32, NodeExecutionStatus>,
     pub project_id: i32,
+    /// Узлы, до которых дошло выполнение (начальные и по сработавшим рёбрам)
+    pub triggered: HashSet<i32>,
 }
 
 impl WorkflowExecutionContext {
@@ -56,14 +100,17 @@ impl WorkflowExecutionContext {
             node_statuses.insert(node.id, NodeExecutionStatus::Pending);
         }
 
-        Self {
+        let mut ctx = Self {
             workflow,
             nodes,
             edges,
             run,
             node_statuses,
             project_id,
-        }
+            triggered: HashSet::new(),
+        };
+        ctx.triggered = ctx.find_start_nodes().into_iter().collect();
+        ctx
     }
 
     /// Найти начальные узлы (в которые нет входящих рёбер)
@@ -106,6 +153,45 @@ impl WorkflowExecutionContext {
         next_nodes
     }
 
+    /// Достигнутые, но не запущенные узлы с наименьшей волной
+    ///
+    /// Узлы следующей волны не попадают в выборку, пока в текущей остаются
+    /// достигнутые узлы.
+    pub fn next_wave(&self) -> Vec<i32> {
+        let ready: Vec<&WorkflowNode> = self
+            .nodes
+            .iter()
+            .filter(|n| self.triggered.contains(&n.id))
+            .filter(|n| {
+                matches!(
+                    self.node_statuses.get(&n.id),
+                    Some(NodeExecutionStatus::Pending)
+                )
+            })
+            .collect();
+        let Some(wave) = ready.iter().map(|n| n.wave).min() else {
+            return Vec::new();
+        };
+        ready
+            .into_iter()
+            .filter(|n| n.wave == wave)
+            .map(|n| n.id)
+            .collect()
+    }
+
+    /// Пересчитывает достигнутые узлы по статусам завершённых узлов
+    ///
+    /// Используется при возобновлении запуска из сохранённого состояния.
+    pub fn restore_triggered(&mut self) {
+        let mut triggered: HashSet<i32> = self.find_start_nodes().into_iter().collect();
+        for (node_id, status) in &self.node_statuses {
+            if let Some(task_status) = status.edge_status() {
+                triggered.extend(self.find_next_nodes(*node_id, task_status));
+            }
+        }
+        self.triggered = triggered;
+    }
+
     /// Проверить, все ли узлы завершены
     pub fn is_complete(&self) -> bool {
         self.node_statuses.values().all(|s| {
@@ -114,6 +200,9 @@ impl WorkflowExecutionContext {
                 NodeExecutionStatus::Success(_)
                     | NodeExecutionStatus::Failed(_)
                     | NodeExecutionStatus::Skipped
+                    | NodeExecutionStatus::Approved
+                    | NodeExecutionStatus::Rejected
+                    | NodeExecutionStatus::Cancelled
             )
         })
     }
@@ -126,6 +215,32 @@ impl WorkflowExecutionContext {
     }
 }
 
+/// Статус узла из сохранённого состояния; `None` — узел нужно выполнить заново
+///
+/// `task` — текущая задача узла. Задача, прерванная рестартом (не дошедшая до
+/// финального статуса) или удалённая, запускается повторно.
+pub fn restored_status(state: &WorkflowRunNode, task: Option<Task>) -> Option<NodeExecutionStatus> {
+    match state.status.as_str() {
+        "success" => Some(NodeExecutionStatus::Success(task?)),
+        "failed" => Some(NodeExecutionStatus::Failed(task?)),
+        "running" => {
+            let task = task?;
+            match task.status {
+                TaskStatus::Success => Some(NodeExecutionStatus::Success(task)),
+                TaskStatus::Error | TaskStatus::Stopped | TaskStatus::NotExecuted => {
+                    Some(NodeExecutionStatus::Failed(task))
+                }
+                _ => None,
+            }
+        }
+        "approved" => Some(NodeExecutionStatus::Approved),
+        "rejected" => Some(NodeExecutionStatus::Rejected),
+        "skipped" => Some(NodeExecutionStatus::Skipped),
+        "cancelled" => Some(NodeExecutionStatus::Cancelled),
+        _ => None,
+    }
+}
+
 /// Workflow Executor - выполняет DAG workflow
 pub struct WorkflowExecutor {
     pub state: Arc<AppState>,
@@ -149,9 +264,6 @@ impl WorkflowExecutor {
 
     /// Запустить выполнение workflow
     pub async fn execute(&self) -> Result<()> {
-        let project_id = self.context.lock().await.project_id;
-        let workflow_id = self.context.lock().await.run.workflow_id;
-
         // Обновить статус запуска на "running"
         self.state
             .store
@@ -162,43 +274,31 @@ impl WorkflowExecutor {
             )
             .await?;
 
-        // Найти начальные узлы
-        let start_nodes = {
-            let ctx = self.context.lock().await;
-            ctx.find_start_nodes()
-        };
+        loop {
+            if self.is_cancelled().await {
+                break;
+            }
 
-        // Создать очередь узлов для выполнения
-        let mut queue: Vec<i32> = start_nodes;
+            // Узлы текущей волны запускаются параллельно
+            let wave = self.context.lock().await.next_wave();
+            if wave.is_empty() {
+                break;
+            }
 
-        while !queue.is_empty() {
-            // Запустить все узлы из очереди параллельно
             let mut running_tasks = Vec::new();
-
-            while let Some(node_id) = queue.pop() {
-                let executor = WorkflowExecutor::new(
-                    self.state.clone(),
-                    self.context.lock().await.workflow.clone(),
-                    self.context.lock().await.nodes.clone(),
-                    self.context.lock().await.edges.clone(),
-                    self.context.lock().await.run.clone(),
-                );
+            for node_id in wave {
+                // Общий контекст: узлу нужны статусы и задачи вышестоящих узлов
+                let executor = self.clone();
 
                 let handle = tokio::spawn(async move { executor.execute_node_sync(node_id).await });
                 running_tasks.push((node_id, handle));
             }
 
-            // Дождаться завершения всех задач и собрать следующие узлы
-            let mut new_queue = Vec::new();
-
-            let old_tasks = std::mem::take(&mut running_tasks);
-            for (node_id, handle) in old_tasks {
+            // Дождаться завершения всей волны и отметить достигнутые узлы
+            for (node_id, handle) in running_tasks {
                 match handle.await {
                     Ok(Ok(next_nodes)) => {
-                        // Узел выполнен успешно, добавить следующие узлы
-                        for next in next_nodes {
-                            new_queue.push(next);
-                        }
+                        self.context.lock().await.triggered.extend(next_nodes);
                     }
                     Ok(Err(e)) => {
                         eprintln!("[workflow_executor] Node {} error: {}", node_id, e);
@@ -208,8 +308,6 @@ impl WorkflowExecutor {
                     }
                 }
             }
-
-            queue = new_queue;
         }
 
         // Завершить workflow
@@ -225,13 +323,62 @@ impl WorkflowExecutor {
         }
     }
 
+    /// Отменён ли запуск (`POST .../runs/{run_id}/cancel`)
+    async fn is_cancelled(&self) -> bool {
+        let (run_id, project_id) = {
+            let ctx = self.context.lock().await;
+            (ctx.run.id, ctx.project_id)
+        };
+        matches!(
+            self.state.store.get_workflow_run(run_id, project_id).await,
+            Ok(run) if run.status == "cancelled"
+        )
+    }
+
+    /// Сохранить состояние узла в запуске
+    async fn save_node_state(
+        &self,
+        node_id: i32,
+        status: &str,
+        task_id: Option<i32>,
+        message: Option<String>,
+    ) {
+        let run_id = self.context.lock().await.run.id;
+        let state = WorkflowRunNode {
+            run_id,
+            node_id,
+            status: status.to_string(),
+            task_id,
+            decided_by: None,
+            message,
+            updated: Utc::now(),
+        };
+        if let Err(e) = self.state.store.save_workflow_run_node(state).await {
+            eprintln!(
+                "[workflow_executor] Failed to save state of node {}: {}",
+                node_id, e
+            );
+        }
+    }
+
     /// Выполнить один узел workflow (синхронная версия для tokio::spawn)
     /// Возвращает список следующих узлов для запуска
     async fn execute_node_sync(&self, node_id: i32) -> Result<Vec<i32>> {
         println!("[workflow_executor] Executing node {}", node_id);
 
+        let is_approval = {
+            let ctx = self.context.lock().await;
+            ctx.nodes
+                .iter()
+                .find(|n| n.id == node_id)
+                .is_some_and(|n| n.is_approval())
+        };
+        if is_approval {
+            return self.execute_approval_node(node_id).await;
+        }
+
         // Получить данные узла
-        let (template, task, project_id) = {
+        let task = {
             let mut ctx = self.context.lock().await;
 
             // Проверить статус узла
@@ -253,6 +400,7 @@ impl WorkflowExecutor {
                 .nodes
                 .iter()
                 .find(|n| n.id == node_id)
+                .cloned()
                 .ok_or_else(|| Error::NotFound(format!("Node {} not found", node_id)))?;
 
             let template = self
@@ -266,14 +414,27 @@ impl WorkflowExecutor {
 
             let project_id = ctx.project_id;
 
+            // Входные переменные из параметров запуска и outputs вышестоящих узлов
+            let upstream = self.upstream_outputs(&ctx, &node).await;
+            let vars =
+                upstream.and_then(|upstream| resolve_node_vars(&node, &ctx.run.params, &upstream));
+
             // Создать задачу
             let task = Task {
                 id: 0,
                 template_id: template.id,
                 project_id: ctx.project_id,
-                status: TaskStatus::Waiting,
+                // С неразрешёнными входами задача не попадает в очередь
+                status: if vars.is_ok() {
+                    TaskStatus::Waiting
+                } else {
+                    TaskStatus::Error
+                },
                 playbook: Some(template.playbook.clone()),
-                environment: None,
+                environment: match &vars {
+                    Ok(vars) if !vars.is_empty() => Some(Value::Object(vars.clone()).to_string()),
+                    _ => None,
+                },
                 secret: None,
                 arguments: None,
                 git_branch: None,
@@ -292,107 +453,265 @@ impl WorkflowExecutor {
                 repository_id: template.repository_id,
                 environment_id: template.environment_id,
                 params: None,
+                priority: None,
             };
 
-            let created_task = self.state.store.create_task(task.clone()).await?;
+            let mut created_task = self.state.store.create_task(task.clone()).await?;
+
+            // Входы не разрешились — задача не запускается, узел завершается ошибкой
+            if let Err(message) = vars {
+                eprintln!("[workflow_executor] Node {} inputs: {}", node_id, message);
+                let _ = self
+                    .state
+                    .store
+                    .create_task_output(crate::models::TaskOutput {
+                        id: 0,
+                        task_id: created_task.id,
+                        project_id,
+                        time: Utc::now(),
+                        output: message.clone(),
+                        stage_id: None,
+                    })
+                    .await;
+                let _ = self
+                    .state
+                    .store
+                    .update_task_status(project_id, created_task.id, TaskStatus::Error)
+                    .await;
+                created_task.status = TaskStatus::Error;
+                let task_id = created_task.id;
+                ctx.node_statuses
+                    .insert(node_id, NodeExecutionStatus::Failed(created_task));
+                let next = ctx.find_next_nodes(node_id, TaskStatus::Error);
+                drop(ctx);
+                self.save_node_state(node_id, "failed", Some(task_id), Some(message))
+                    .await;
+                return Ok(next);
+            }
 
             // Обновить статус узла на Running
             ctx.node_statuses
                 .insert(node_id, NodeExecutionStatus::Running(created_task.clone()));
 
-            (template, created_task, project_id)
+            created_task
         };
 
+        self.save_node_state(node_id, "running", Some(task.id), None)
+            .await;
+
         // Запустить задачу
-        let task_result = self.run_task(task.clone(), template, project_id).await;
+        let task_status = self.run_task(&task).await;
 
         // Обновить статус узла по результату и вернуть следующие узлы
+        let mut task = task;
+        task.status = task_status;
+        let succeeded = task_status == TaskStatus::Success;
         let next_nodes = {
             let mut ctx = self.context.lock().await;
 
-            let final_status = match &task_result {
-                Ok(status) => match status {
-                    TaskStatus::Success => NodeExecutionStatus::Success(task.clone()),
-                    TaskStatus::Error | TaskStatus::Stopped | TaskStatus::NotExecuted => {
-                        NodeExecutionStatus::Failed(task.clone())
-                    }
-                    _ => NodeExecutionStatus::Failed(task.clone()),
-                },
-                Err(_) => NodeExecutionStatus::Failed(task.clone()),
+            let final_status = if succeeded {
+                NodeExecutionStatus::Success(task.clone())
+            } else {
+                NodeExecutionStatus::Failed(task.clone())
             };
 
             ctx.node_statuses.insert(node_id, final_status);
 
             // Найти следующие узлы
-            let task_status = task_result.unwrap_or(TaskStatus::Error);
             ctx.find_next_nodes(node_id, task_status)
         };
 
+        let status = if succeeded { "success" } else { "failed" };
+        self.save_node_state(node_id, status, Some(task.id), None)
+            .await;
+
         Ok(next_nodes)
     }
 
-    /// Запустить задачу Ansible/Terraform
-    async fn run_task(
-        &self,
-        task: Task,
-        template: Template,
-        project_id: i32,
-    ) -> Result<TaskStatus> {
-        use crate::api::handlers::tasks::execute_task_background_with_template;
-
-        // Получить inventory, repository, environment
-        let inventory = if let Some(inv_id) = task.inventory_id.or(template.inventory_id) {
-            self.state
-                .store
-                .get_inventory(project_id, inv_id)
-                .await
-                .unwrap_or_default()
-        } else {
-            Inventory::default()
+    /// Узел подтверждения: ждёт, пока пользователь одобрит или отклонит его
+    ///
+    /// Одобрение продолжает запуск по рёбрам `success`, отклонение — по рёбрам
+    /// `failure`. Отмена запуска прерывает ожидание.
+    async fn execute_approval_node(&self, node_id: i32) -> Result<Vec<i32>> {
+        let (run_id, node_name) = {
+            let mut ctx = self.context.lock().await;
+            if !matches!(
+                ctx.node_statuses.get(&node_id),
+                Some(NodeExecutionStatus::Pending)
+            ) {
+                return Ok(Vec::new());
+            }
+            ctx.node_statuses
+                .insert(node_id, NodeExecutionStatus::WaitingApproval);
+            let name = ctx
+                .nodes
+                .iter()
+                .find(|n| n.id == node_id)
+                .map(|n| n.name.clone())
+                .unwrap_or_default();
+            (ctx.run.id, name)
         };
 
-        let repository = if let Some(repo_id) = task.repository_id.or(template.repository_id) {
-            self.state
+        // После рестарта узел уже может ждать решения — его не перезаписываем
+        let persisted = self
+            .state
+            .store
+            .get_workflow_run_nodes(run_id)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .find(|s| s.node_id == node_id);
+        if persisted.is_none_or(|s| s.status != "waiting_approval") {
+            self.save_node_state(node_id, "waiting_approval", None, None)
+                .await;
+        }
+        self.state
+            .store
+            .update_workflow_run_status(
+                run_id,
+                "waiting_approval",
+                Some(format!("Waiting for approval: {}", node_name)),
+            )
+            .await?;
+
+        let approved = loop {
+            if self.is_cancelled().await {
+                self.context
+                    .lock()
+                    .await
+                    .node_statuses
+                    .insert(node_id, NodeExecutionStatus::Cancelled);
+                self.save_node_state(node_id, "cancelled", None, None).await;
+                return Ok(Vec::new());
+            }
+            let decision = self
+                .state
                 .store
-                .get_repository(project_id, repo_id)
-                .await
-                .unwrap_or_default()
-        } else {
-            Repository::default()
+                .get_workflow_run_nodes(run_id)
+                .await?
+                .into_iter()
+                .find(|s| s.node_id == node_id)
+                .map(|s| s.status);
+            match decision.as_deref() {
+                Some("approved") => break true,
+                Some("rejected") => break false,
+                _ => tokio::time::sleep(POLL_INTERVAL).await,
+            }
+        };
+
+        let (next_nodes, still_waiting) = {
+            let mut ctx = self.context.lock().await;
+            let (status, task_status) = if approved {
+                (NodeExecutionStatus::Approved, TaskStatus::Success)
+            } else {
+                (NodeExecutionStatus::Rejected, TaskStatus::Error)
+            };
+            ctx.node_statuses.insert(node_id, status);
+            let still_waiting = ctx
+                .node_statuses
+                .values()
+                .any(|s| matches!(s, NodeExecutionStatus::WaitingApproval));
+            (ctx.find_next_nodes(node_id, task_status), still_waiting)
         };
 
-        let environment = if let Some(env_id) = task.environment_id.or(template.environment_id) {
+        if !still_waiting {
+            let decision = if approved { "approved" } else { "rejected" };
             self.state
                 .store
-                .get_environment(project_id, env_id)
-                .await
-                .unwrap_or_default()
-        } else {
-            Environment::default()
-        };
+                .update_workflow_run_status(
+                    run_id,
+                    "running",
+                    Some(format!("Node '{}' {}", node_name, decision)),
+                )
+                .await?;
+        }
 
-        // Запустить задачу в фоне и ждать результата
-        let result = execute_task_background_with_template(
-            self.state.clone(),
-            task.clone(),
-            template,
-            inventory,
-            repository,
-            environment,
-        )
-        .await;
+        Ok(next_nodes)
+    }
 
-        Ok(result)
+    /// Outputs узлов, на которые ссылаются входы `node`, по именам узлов
+    ///
+    /// Узлы, ещё не запускавшие задачу, в результат не попадают.
+    async fn upstream_outputs(
+        &self,
+        ctx: &WorkflowExecutionContext,
+        node: &WorkflowNode,
+    ) -> std::result::Result<HashMap<String, HashMap<String, Value>>, String> {
+        let mut upstream = HashMap::new();
+        for source in node.inputs.iter().filter_map(|i| i.node.as_deref()) {
+            if upstream.contains_key(source) {
+                continue;
+            }
+            let task_id = ctx.nodes.iter().filter(|n| n.name == source).find_map(|n| {
+                match ctx.node_statuses.get(&n.id) {
+                    Some(
+                        NodeExecutionStatus::Success(task) | NodeExecutionStatus::Failed(task),
+                    ) => Some(task.id),
+                    _ => None,
+                }
+            });
+            let Some(task_id) = task_id else {
+                continue;
+            };
+            let outputs = self
+                .state
+                .store
+                .get_task_outputs_map(task_id, ctx.project_id)
+                .await
+                .map_err(|e| format!("Failed to load outputs of node '{}': {}", source, e))?;
+            upstream.insert(source.to_string(), outputs.outputs);
+        }
+        Ok(upstream)
+    }
+
+    /// Поставить задачу узла в очередь и дождаться её итога
+    ///
+    /// Задачу забирает пул задач: слоты групп параллелизма, хуки, гейт плана
+    /// и таймаут. Отмена запуска переводит задачу в `stopping`.
+    async fn run_task(&self, task: &Task) -> TaskStatus {
+        task_execution::wake_dispatcher();
+        task_execution::wait_finished(&self.state.store, task).await
     }
 
     /// Завершить workflow
+    ///
+    /// Недостигнутые узлы помечаются пропущенными. Отменённый запуск сохраняет
+    /// статус `cancelled`, а его незапущенные узлы — отменёнными.
     async fn finalize_workflow(&self) -> Result<()> {
-        let ctx = self.context.lock().await;
+        let cancelled = self.is_cancelled().await;
+        let mut ctx = self.context.lock().await;
+
+        let mut unfinished = Vec::new();
+        for (node_id, status) in ctx.node_statuses.iter_mut() {
+            if matches!(
+                status,
+                NodeExecutionStatus::Pending | NodeExecutionStatus::WaitingApproval
+            ) {
+                *status = if cancelled {
+                    NodeExecutionStatus::Cancelled
+                } else {
+                    NodeExecutionStatus::Skipped
+                };
+                unfinished.push(*node_id);
+            }
+        }
 
-        let has_failures = ctx
-            .node_statuses
-            .values()
-            .any(|s| matches!(s, NodeExecutionStatus::Failed(_)));
+        let has_failures = ctx.node_statuses.values().any(|s| {
+            matches!(
+                s,
+                NodeExecutionStatus::Failed(_) | NodeExecutionStatus::Rejected
+            )
+        });
+        let run_id = ctx.run.id;
+        drop(ctx);
+
+        let node_status = if cancelled { "cancelled" } else { "skipped" };
+        for node_id in unfinished {
+            self.save_node_state(node_id, node_status, None, None).await;
+        }
+        if cancelled {
+            return Ok(());
+        }
 
         let (final_status, message) = if has_failures {
             (
@@ -406,22 +725,54 @@ impl WorkflowExecutor {
             )
         };
 
-        drop(ctx);
-
         self.state
             .store
-            .update_workflow_run_status(self.context.lock().await.run.id, &final_status, message)
+            .update_workflow_run_status(run_id, &final_status, message)
             .await?;
 
         Ok(())
     }
 }
 
+/// Extra vars задачи узла: параметры запуска, поверх них — входы узла
+///
+/// Вход с `node` берёт ключ из outputs этого узла, без `node` — из параметров
+/// запуска. Отсутствующее значение заменяется `default`, иначе — ошибка.
+pub fn resolve_node_vars(
+    node: &WorkflowNode,
+    params: &Map<String, Value>,
+    upstream: &HashMap<String, HashMap<String, Value>>,
+) -> std::result::Result<Map<String, Value>, String> {
+    let mut vars = params.clone();
+    for input in &node.inputs {
+        let value = match &input.node {
+            Some(source) => upstream.get(source).and_then(|o| o.get(&input.key)),
+            None => params.get(&input.key),
+        };
+        let value = value
+            .or(input.default.as_ref())
+            .cloned()
+            .ok_or_else(|| match &input.node {
+                Some(source) => format!(
+                    "Input '{}': node '{}' has no output '{}'",
+                    input.name, source, input.key
+                ),
+                None => format!(
+                    "Input '{}': workflow parameter '{}' is not set",
+                    input.name, input.key
+                ),
+            })?;
+        vars.insert(input.name.clone(), value);
+    }
+    Ok(vars)
+}
+
 /// Запустить workflow (публичный API)
 pub async fn run_workflow(
     state: Arc<AppState>,
     workflow_id: i32,
     project_id: i32,
+    params: Map<String, Value>,
 ) -> Result<WorkflowRun> {
     // Проверить workflow
     let workflow = state.store.get_workflow(workflow_id, project_id).await?;
@@ -434,15 +785,37 @@ pub async fn run_workflow(
         return Err(Error::Other("Workflow has no nodes".to_string()));
     }
 
+    // Входы должны ссылаться на существующие узлы
+    for node in &nodes {
+        for source in node.inputs.iter().filter_map(|i| i.node.as_deref()) {
+            if !nodes.iter().any(|n| n.name == source) {
+                return Err(Error::Validation(format!(
+                    "Node '{}' references unknown node '{}'",
+                    node.name, source
+                )));
+            }
+        }
+    }
+
     // Создать запись запуска
     let run = state
         .store
-        .create_workflow_run(workflow_id, project_id)
+        .create_workflow_run(workflow_id, project_id, params)
+        .await?;
+    state
+        .store
+        .set_workflow_run_node(run.id, task_reaper::node_id())
         .await?;
 
     // Создать executor и запустить в фоне
     let executor = WorkflowExecutor::new(state.clone(), workflow, nodes, edges, run.clone());
+    spawn_execution(state, executor, run.id);
 
+    Ok(run)
+}
+
+/// Выполнить workflow в фоне; ошибка executor'а завершает запуск статусом failed
+fn spawn_execution(state: Arc<AppState>, executor: WorkflowExecutor, run_id: i32) {
     tokio::spawn(async move {
         if let Err(e) = executor.execute().await {
             eprintln!("[workflow_executor] Workflow execution error: {}", e);
@@ -451,21 +824,152 @@ pub async fn run_workflow(
             let _ = state
                 .store
                 .update_workflow_run_status(
-                    run.id,
+                    run_id,
                     "failed",
                     Some(format!("Execution error: {}", e)),
                 )
                 .await;
         }
     });
+}
 
-    Ok(run)
+/// Отменить запуск workflow
+///
+/// Новые узлы не запускаются, задачи выполняющихся узлов получают запрос
+/// остановки, а ожидающие узлы подтверждения перестают ждать.
+pub async fn cancel_workflow_run(state: &AppState, run: &WorkflowRun) -> Result<()> {
+    state
+        .store
+        .update_workflow_run_status(run.id, "cancelled", Some("Cancelled by user".to_string()))
+        .await?;
+
+    for node in state.store.get_workflow_run_nodes(run.id).await? {
+        let Some(task_id) = node.task_id.filter(|_| node.status == "running") else {
+            continue;
+        };
+        let task = state.store.get_task(run.project_id, task_id).await?;
+        if matches!(
+            task.status,
+            TaskStatus::Waiting | TaskStatus::Starting | TaskStatus::Running
+        ) {
+            state
+                .store
+                .update_task_status(run.project_id, task_id, TaskStatus::Stopping)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Продолжить незавершённые запуски, чей узел перестал отмечаться
+///
+/// Запуски атомарно забираются на текущий узел, поэтому каждый из них
+/// продолжает только один узел, а запуски живых узлов не затрагиваются.
+/// Завершённые узлы восстанавливаются из `workflow_run_node`, узлы с
+/// прерванными задачами выполняются заново.
+pub async fn resume_workflow_runs(state: Arc<AppState>, node_timeout: Duration) -> Result<usize> {
+    let mut resumed = 0;
+    let runs = state
+        .store
+        .claim_orphaned_workflow_runs(node_timeout.as_secs() as i64, task_reaper::node_id())
+        .await?;
+    for run in runs {
+        let run_id = run.id;
+        match resume_workflow_run(state.clone(), run).await {
+            Ok(()) => resumed += 1,
+            Err(e) => {
+                eprintln!(
+                    "[workflow_executor] Failed to resume workflow run {}: {}",
+                    run_id, e
+                );
+                let _ = state
+                    .store
+                    .update_workflow_run_status(
+                        run_id,
+                        "failed",
+                        Some(format!("Failed to resume after restart: {}", e)),
+                    )
+                    .await;
+            }
+        }
+    }
+    Ok(resumed)
+}
+
+async fn resume_workflow_run(state: Arc<AppState>, run: WorkflowRun) -> Result<()> {
+    let workflow = state
+        .store
+        .get_workflow(run.workflow_id, run.project_id)
+        .await?;
+    let nodes = state.store.get_workflow_nodes(run.workflow_id).await?;
+    let edges = state.store.get_workflow_edges(run.workflow_id).await?;
+    let saved = state.store.get_workflow_run_nodes(run.id).await?;
+
+    let run_id = run.id;
+    let project_id = run.project_id;
+    let executor = WorkflowExecutor::new(state.clone(), workflow, nodes, edges, run);
+    {
+        let mut ctx = executor.context.lock().await;
+        for node_state in saved {
+            if !ctx.node_statuses.contains_key(&node_state.node_id) {
+                continue;
+            }
+            let task = match node_state.task_id {
+                Some(task_id) => state.store.get_task(project_id, task_id).await.ok(),
+                None => None,
+            };
+            match restored_status(&node_state, task) {
+                Some(status) => {
+                    ctx.node_statuses.insert(node_state.node_id, status);
+                }
+                None => {
+                    // Задача прервана рестартом — закрываем её, узел запустится заново
+                    if let (Some(task_id), "running") =
+                        (node_state.task_id, node_state.status.as_str())
+                    {
+                        let _ = state
+                            .store
+                            .update_task_status(project_id, task_id, TaskStatus::Error)
+                            .await;
+                    }
+                }
+            }
+        }
+        ctx.restore_triggered();
+    }
+
+    println!("[workflow_executor] Resuming workflow run {}", run_id);
+    spawn_execution(state, executor, run_id);
+    Ok(())
+}
+
+/// Запускает фоновое возобновление запусков workflow исчезнувших узлов
+///
+/// Проверка выполняется вместе с очисткой потерянных задач и только на
+/// узле-лидере.
+pub fn spawn_resume(state: Arc<AppState>, leadership: Leadership) -> tokio::task::JoinHandle<()> {
+    let config = TaskReaperConfig::default();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            if !leadership.is_leader() {
+                continue;
+            }
+            match resume_workflow_runs(state.clone(), config.node_timeout).await {
+                Ok(0) => {}
+                Ok(count) => println!("[workflow_executor] Resumed {} workflow run(s)", count),
+                Err(e) => eprintln!("[workflow_executor] Failed to load workflow runs: {}", e),
+            }
+        }
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Utc;
+    use serde_json::json;
 
     fn create_test_workflow() -> Workflow {
         Workflow {
@@ -487,6 +991,8 @@ mod tests {
             pos_x: 0.0,
             pos_y: 0.0,
             wave: 0,
+            inputs: Vec::new(),
+            node_type: "template".to_string(),
         }
     }
 
@@ -521,6 +1027,7 @@ mod tests {
             created: Utc::now(),
             started: None,
             finished: None,
+            params: Default::default(),
         }
     }
 
@@ -1349,6 +1856,10 @@ mod tests {
             NodeExecutionStatus::Success(task.clone()),
             NodeExecutionStatus::Failed(task.clone()),
             NodeExecutionStatus::Skipped,
+            NodeExecutionStatus::WaitingApproval,
+            NodeExecutionStatus::Approved,
+            NodeExecutionStatus::Rejected,
+            NodeExecutionStatus::Cancelled,
         ];
 
         for status in &statuses {
@@ -1357,7 +1868,11 @@ mod tests {
                 NodeExecutionStatus::Running(t) => assert_eq!(t.id, 100),
                 NodeExecutionStatus::Success(t) => assert_eq!(t.id, 100),
                 NodeExecutionStatus::Failed(t) => assert_eq!(t.id, 100),
-                NodeExecutionStatus::Skipped => {}
+                NodeExecutionStatus::Skipped
+                | NodeExecutionStatus::WaitingApproval
+                | NodeExecutionStatus::Approved
+                | NodeExecutionStatus::Rejected
+                | NodeExecutionStatus::Cancelled => {}
             }
         }
     }
@@ -1507,4 +2022,124 @@ mod tests {
             assert_eq!(next, vec![2], "always should match for {:?}", status);
         }
     }
+
+    fn input(name: &str, node: Option<&str>, key: &str) -> crate::models::WorkflowNodeInput {
+        crate::models::WorkflowNodeInput {
+            name: name.to_string(),
+            node: node.map(str::to_string),
+            key: key.to_string(),
+            default: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_node_vars_from_upstream_and_params() {
+        let mut node = create_test_node(2);
+        node.inputs = vec![
+            input("vpc_id", Some("network"), "vpc_id"),
+            input("region", None, "aws_region"),
+        ];
+        let mut params = Map::new();
+        params.insert("aws_region".to_string(), json!("eu-west-1"));
+        let upstream = HashMap::from([(
+            "network".to_string(),
+            HashMap::from([("vpc_id".to_string(), json!("vpc-123"))]),
+        )]);
+
+        let vars = resolve_node_vars(&node, &params, &upstream).unwrap();
+        assert_eq!(vars["vpc_id"], json!("vpc-123"));
+        assert_eq!(vars["region"], json!("eu-west-1"));
+        // Параметры запуска передаются всем узлам
+        assert_eq!(vars["aws_region"], json!("eu-west-1"));
+    }
+
+    #[test]
+    fn test_resolve_node_vars_missing_value() {
+        let mut node = create_test_node(2);
+        node.inputs = vec![input("vpc_id", Some("network"), "vpc_id")];
+        let err = resolve_node_vars(&node, &Map::new(), &HashMap::new()).unwrap_err();
+        assert!(err.contains("node 'network' has no output 'vpc_id'"));
+
+        node.inputs[0].default = Some(json!("vpc-default"));
+        let vars = resolve_node_vars(&node, &Map::new(), &HashMap::new()).unwrap();
+        assert_eq!(vars["vpc_id"], json!("vpc-default"));
+    }
+
+    #[test]
+    fn test_next_wave_waits_for_lower_wave() {
+        let mut nodes = vec![
+            create_test_node(1),
+            create_test_node(2),
+            create_test_node(3),
+        ];
+        nodes[1].wave = 1;
+        nodes[2].wave = 0;
+        // 1 -> 3: узел 3 той же волны, что и 1, но достигается только после него
+        let edges = vec![create_test_edge(1, 3, "success")];
+        let mut ctx =
+            WorkflowExecutionContext::new(create_test_workflow(), nodes, edges, create_test_run());
+
+        assert_eq!(ctx.next_wave(), vec![1]);
+        ctx.node_statuses
+            .insert(1, NodeExecutionStatus::Success(create_test_task(10)));
+        ctx.triggered
+            .extend(ctx.find_next_nodes(1, TaskStatus::Success));
+        // Волна 0 ещё не завершена — узел 2 (волна 1) ждёт
+        assert_eq!(ctx.next_wave(), vec![3]);
+        ctx.node_statuses
+            .insert(3, NodeExecutionStatus::Success(create_test_task(11)));
+        assert_eq!(ctx.next_wave(), vec![2]);
+    }
+
+    #[test]
+    fn test_restore_triggered_from_saved_state() {
+        let nodes = vec![
+            create_test_node(1),
+            create_test_node(2),
+            create_test_node(3),
+        ];
+        let edges = vec![
+            create_test_edge(1, 2, "success"),
+            create_test_edge(1, 3, "failure"),
+        ];
+        let mut ctx =
+            WorkflowExecutionContext::new(create_test_workflow(), nodes, edges, create_test_run());
+        ctx.node_statuses.insert(1, NodeExecutionStatus::Approved);
+        ctx.restore_triggered();
+
+        assert_eq!(ctx.next_wave(), vec![2]);
+        assert!(!ctx.triggered.contains(&3));
+    }
+
+    #[test]
+    fn test_restored_status() {
+        let saved = |status: &str| WorkflowRunNode {
+            run_id: 1,
+            node_id: 1,
+            status: status.to_string(),
+            task_id: Some(7),
+            decided_by: None,
+            message: None,
+            updated: Utc::now(),
+        };
+        let mut task = create_test_task(7);
+
+        assert!(matches!(
+            restored_status(&saved("success"), Some(task.clone())),
+            Some(NodeExecutionStatus::Success(_))
+        ));
+        assert!(matches!(
+            restored_status(&saved("rejected"), None),
+            Some(NodeExecutionStatus::Rejected)
+        ));
+        // Задача завершилась до рестарта — узел не перезапускается
+        assert!(matches!(
+            restored_status(&saved("running"), Some(task.clone())),
+            Some(NodeExecutionStatus::Success(_))
+        ));
+        // Прерванная задача и ожидание подтверждения выполняются заново
+        task.status = TaskStatus::Running;
+        assert!(restored_status(&saved("running"), Some(task)).is_none());
+        assert!(restored_status(&saved("waiting_approval"), None).is_none());
+    }
 }
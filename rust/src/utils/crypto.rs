@@ -0,0 +1,29 @@
+//! Криптографические утилиты
+//!
+//! Вспомогательные функции для проверки секретов и подписей
+
+/// Сравнивает строки за постоянное время
+///
+/// Время сравнения не зависит от позиции первого несовпадающего байта,
+/// поэтому функцию следует использовать для токенов, секретов и HMAC-подписей.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(constant_time_eq("", ""));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+        assert!(!constant_time_eq("secret", ""));
+    }
+}
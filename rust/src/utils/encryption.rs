@@ -2,14 +2,17 @@
 //!
 //! AES-256-GCM для симметричного шифрования секретов.
 //! ECDSA P-256 для генерации ключевых пар (runner keypairs).
+//! ECDH P-256 + HKDF-SHA256 для передачи секретов удалённым раннерам.
 //!
 //! Замена RSA → P-256: RUSTSEC-2023-0071 (Marvin Attack).
 
 use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng as AesOsRng};
 use aes_gcm::{Aes256Gcm, Key, Nonce};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use p256::ecdh::{EphemeralSecret, SharedSecret};
 use p256::ecdsa::SigningKey;
 use p256::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+use p256::{EncodedPoint, PublicKey};
 use std::io::Write;
 use thiserror::Error;
 
@@ -61,6 +64,70 @@ pub fn aes256_decrypt(encoded: &str, key: &[u8; 32]) -> Result<Vec<u8>, Encrypti
         .map_err(|e| EncryptionError::Encoding(e.to_string()))
 }
 
+/// Контекст HKDF для ключей запечатанных секретов
+const SEALING_INFO: &[u8] = b"velum-runner-secrets";
+
+fn sealing_key(shared: &SharedSecret) -> Result<[u8; 32], EncryptionError> {
+    let mut key = [0u8; 32];
+    shared
+        .extract::<sha2::Sha256>(None)
+        .expand(SEALING_INFO, &mut key)
+        .map_err(|e| EncryptionError::KeyGeneration(e.to_string()))?;
+    Ok(key)
+}
+
+fn decode_public_key(encoded: &str) -> Result<PublicKey, EncryptionError> {
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| EncryptionError::Encoding(e.to_string()))?;
+    PublicKey::from_sec1_bytes(&bytes).map_err(|e| EncryptionError::Encoding(e.to_string()))
+}
+
+fn encode_public_key(key: &PublicKey) -> String {
+    BASE64.encode(EncodedPoint::from(key).as_bytes())
+}
+
+/// Эфемерная пара ключей получателя запечатанных секретов (ECDH P-256)
+pub struct SealingKeyPair {
+    secret: EphemeralSecret,
+}
+
+impl SealingKeyPair {
+    /// Генерирует новую пару ключей
+    pub fn generate() -> Self {
+        Self {
+            secret: EphemeralSecret::random(&mut rand_core::OsRng),
+        }
+    }
+
+    /// Публичный ключ (SEC1, base64) для передачи отправителю
+    pub fn public_key(&self) -> String {
+        encode_public_key(&self.secret.public_key())
+    }
+
+    /// Расшифровывает данные, запечатанные функцией [`seal`]
+    pub fn open(&self, sender_public_key: &str, sealed: &str) -> Result<Vec<u8>, EncryptionError> {
+        let sender = decode_public_key(sender_public_key)?;
+        let key = sealing_key(&self.secret.diffie_hellman(&sender))?;
+        aes256_decrypt(sealed, &key)
+    }
+}
+
+/// Шифрует данные для владельца публичного ключа.
+///
+/// Возвращает эфемерный публичный ключ отправителя и шифротекст AES-256-GCM;
+/// расшифровать их может только владелец [`SealingKeyPair`].
+pub fn seal(
+    recipient_public_key: &str,
+    plaintext: &[u8],
+) -> Result<(String, String), EncryptionError> {
+    let recipient = decode_public_key(recipient_public_key)?;
+    let ephemeral = EphemeralSecret::random(&mut rand_core::OsRng);
+    let key = sealing_key(&ephemeral.diffie_hellman(&recipient))?;
+    let sealed = aes256_encrypt(plaintext, &key)?;
+    Ok((encode_public_key(&ephemeral.public_key()), sealed))
+}
+
 /// Генерирует ECDSA P-256 приватный ключ и записывает его в файл (PKCS#8 PEM).
 /// Заменяет RSA-2048 — устраняет RUSTSEC-2023-0071 (Marvin Attack).
 pub fn generate_private_key<W: Write>(
@@ -150,6 +217,24 @@ mod tests {
         assert!(msg.contains("test error"));
     }
 
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let recipient = SealingKeyPair::generate();
+        let (sender, sealed) = seal(&recipient.public_key(), b"ssh private key").unwrap();
+        assert_eq!(
+            recipient.open(&sender, &sealed).unwrap(),
+            b"ssh private key"
+        );
+
+        let stranger = SealingKeyPair::generate();
+        assert!(stranger.open(&sender, &sealed).is_err());
+    }
+
+    #[test]
+    fn test_seal_rejects_invalid_public_key() {
+        assert!(seal("not-a-key", b"data").is_err());
+    }
+
     #[test]
     fn test_aes256_empty_plaintext() {
         let key = [0u8; 32];
@@ -4,11 +4,13 @@ pub mod ansi;
 pub mod app;
 pub mod common_errors;
 pub mod conv;
+pub mod crypto;
 pub mod debug;
 pub mod encryption;
 pub mod error_logging;
 pub mod mailer;
 pub mod oidc_provider;
+pub mod process;
 pub mod shell;
 pub mod test_helpers;
 pub mod version;
@@ -19,6 +21,7 @@ pub use common_errors::{
     InvalidSubscriptionError, UserVisibleError, get_error_context, new_user_error,
 };
 pub use conv::{convert_float_to_int_if_possible, struct_to_flat_map};
+pub use crypto::constant_time_eq;
 pub use debug::{debug_thread_id, log_thread_id, thread_id};
 pub use encryption::{EncryptionError, KeyPair, generate_private_key};
 pub use error_logging::{
@@ -0,0 +1,137 @@
+//! Группы процессов задач
+//!
+//! Каждый процесс приложения (ansible-playbook, terraform, shell) запускается
+//! в собственной группе процессов, чтобы при остановке или таймауте задачи
+//! завершить его вместе со всеми дочерними процессами: сначала SIGTERM,
+//! затем, если группа не завершилась за отведённое время, SIGKILL.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Интервал проверки завершения группы после SIGTERM
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Переводит запускаемый процесс в новую группу (PGID = PID)
+pub fn isolate_process_group(cmd: &mut tokio::process::Command) {
+    cmd.process_group(0);
+}
+
+/// Переводит запускаемый процесс (std) в новую группу (PGID = PID)
+pub fn isolate_std_process_group(cmd: &mut std::process::Command) {
+    use std::os::unix::process::CommandExt;
+    cmd.process_group(0);
+}
+
+/// Отправляет сигнал всей группе процессов; `false` — группы уже нет
+fn signal_group(pgid: u32, signal: libc::c_int) -> bool {
+    let Ok(pgid) = libc::pid_t::try_from(pgid) else {
+        return false;
+    };
+    if pgid <= 0 {
+        return false;
+    }
+    // SAFETY: kill(2) с отрицательным PID лишь отправляет сигнал группе процессов
+    unsafe { libc::kill(-pgid, signal) == 0 }
+}
+
+/// Завершает группу процессов: SIGTERM, ожидание `grace`, затем SIGKILL.
+///
+/// Возвращает `true`, если пришлось применить SIGKILL.
+pub async fn terminate_process_group(pgid: u32, grace: Duration) -> bool {
+    if !signal_group(pgid, libc::SIGTERM) {
+        return false;
+    }
+    let deadline = tokio::time::Instant::now() + grace;
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        // Сигнал 0 только проверяет существование группы
+        if !signal_group(pgid, 0) {
+            return false;
+        }
+    }
+    signal_group(pgid, libc::SIGKILL)
+}
+
+/// Группы процессов, запущенных задачей
+#[derive(Debug, Clone, Default)]
+pub struct ProcessGroups {
+    pgids: Arc<Mutex<Vec<u32>>>,
+}
+
+impl ProcessGroups {
+    /// Запоминает группу только что запущенного процесса
+    pub fn register(&self, pgid: u32) {
+        if pgid == 0 {
+            return;
+        }
+        if let Ok(mut pgids) = self.pgids.lock() {
+            pgids.push(pgid);
+        }
+    }
+
+    /// Callback для `LocalAppRunningArgs::callback`
+    pub fn callback(&self) -> Box<dyn FnOnce(u32) + Send + 'static> {
+        let groups = self.clone();
+        Box::new(move |pid| groups.register(pid))
+    }
+
+    /// Завершает все запомненные группы процессов
+    pub async fn terminate_all(&self, grace: Duration) {
+        let pgids: Vec<u32> = self
+            .pgids
+            .lock()
+            .map(|mut pgids| std::mem::take(&mut *pgids))
+            .unwrap_or_default();
+        for pgid in pgids {
+            terminate_process_group(pgid, grace).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_terminate_process_group_stops_children() {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args(["-c", "sleep 30 & sleep 30"]);
+        isolate_process_group(&mut cmd);
+        let mut child = cmd.spawn().unwrap();
+        let pgid = child.id().unwrap();
+
+        let groups = ProcessGroups::default();
+        groups.register(pgid);
+        // Процесс должен ожидаться параллельно, иначе он останется зомби
+        let started = std::time::Instant::now();
+        let ((), status) = tokio::join!(groups.terminate_all(Duration::from_secs(5)), child.wait());
+        assert!(!status.unwrap().success());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_terminate_process_group_kills_after_grace() {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args(["-c", "trap '' TERM; sleep 30"]);
+        isolate_process_group(&mut cmd);
+        let mut child = cmd.spawn().unwrap();
+        let pgid = child.id().unwrap();
+        // Даём shell установить trap
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(terminate_process_group(pgid, Duration::from_millis(300)).await);
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn test_process_groups_ignore_zero_pid() {
+        let groups = ProcessGroups::default();
+        groups.register(0);
+        (groups.callback())(42);
+        assert_eq!(*groups.pgids.lock().unwrap(), vec![42]);
+    }
+}